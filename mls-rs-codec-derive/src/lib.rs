@@ -80,11 +80,20 @@ impl MlsFieldReceiver {
 }
 
 #[derive(Debug, FromVariant)]
-#[darling(attributes(mls_codec))]
+#[darling(attributes(mls_codec), forward_attrs(cfg))]
 struct MlsVariantReceiver {
     ident: Ident,
+    attrs: Vec<Attribute>,
     discriminant: Option<Expr>,
     fields: ast::Fields<MlsFieldReceiver>,
+    /// Value of the tag identifying this variant, used instead of the Rust
+    /// discriminant when the enum has a `tag_type`.
+    #[darling(default)]
+    tag: Option<Expr>,
+    /// Catch-all variant for tags not matched by any other variant. Its
+    /// single field is responsible for encoding and decoding the tag.
+    #[darling(default)]
+    other: bool,
 }
 
 #[derive(FromDeriveInput)]
@@ -94,13 +103,20 @@ struct MlsInputReceiver {
     ident: Ident,
     generics: Generics,
     data: ast::Data<MlsVariantReceiver, MlsFieldReceiver>,
+    /// Type of the tag preceding the variant data, for enums whose variants
+    /// are identified by values of a type other than an integer.
+    #[darling(default)]
+    tag_type: Option<Path>,
 }
 
 impl MlsInputReceiver {
     fn handle_input(&self, operation: Operation) -> TokenStream {
         match self.data {
             ast::Data::Struct(ref s) => struct_impl(s, operation),
-            ast::Data::Enum(ref e) => enum_impl(&self.ident, &self.attrs, e, operation),
+            ast::Data::Enum(ref e) => match self.tag_type {
+                Some(ref tag_type) => tagged_enum_impl(&self.ident, tag_type, e, operation),
+                None => enum_impl(&self.ident, &self.attrs, e, operation),
+            },
         }
     }
 }
@@ -178,15 +194,16 @@ fn enum_impl(
     if matches!(operation, Operation::Decode) {
         let cases = variants.iter().map(|variant| {
             let variant_name = &variant.ident;
+            let cfg = &variant.attrs;
 
             let discriminant = discriminant_for_variant(variant, &repr_ident);
 
             // TODO: Support more than 1 field
             match variant.fields.len() {
-                0 => quote! { #discriminant => Ok(#enum_name::#variant_name), },
+                0 => quote! { #(#cfg)* #discriminant => Ok(#enum_name::#variant_name), },
                 1 =>{
                     let path = variant.fields.fields[0].with.as_ref().unwrap_or(&path);
-                    quote! { #discriminant => Ok(#enum_name::#variant_name(#path::#call(#extras) #handle_error)), }
+                    quote! { #(#cfg)* #discriminant => Ok(#enum_name::#variant_name(#path::#call(#extras) #handle_error)), }
                 },
                 _ => panic!("Enum discriminants with more than 1 field are not currently supported")
             }
@@ -225,8 +242,144 @@ fn enum_impl(
         };
 
         let discrim = quote! { #path::#call (&#discriminant #extras) #handle_error };
+        let cfg = &variant.attrs;
+
+        quote! { #(#cfg)* #enum_name::#variant_name #parameter => { #discrim #field }}
+    });
+
+    let enum_impl = quote! {
+        match self {
+            #(#cases)*
+        }
+    };
+
+    if operation.is_result() {
+        quote! {
+            Ok(#enum_impl)
+        }
+    } else {
+        enum_impl
+    }
+}
+
+/// Implementation for enums with `#[mls_codec(tag_type = "...")]`, where each
+/// variant is identified by a `#[mls_codec(tag = "...")]` value and at most one
+/// `#[mls_codec(other)]` variant covers all remaining tags.
+fn tagged_enum_impl(
+    enum_name: &Ident,
+    tag_type: &Path,
+    variants: &[MlsVariantReceiver],
+    operation: Operation,
+) -> TokenStream {
+    let handle_error = operation.is_result().then_some(quote! { ? });
+    let path = operation.path();
+    let call = operation.call();
+    let extras = operation.extras();
+
+    let others = variants.iter().filter(|v| v.other).count();
+    assert!(others <= 1, "At most one variant can be marked as other");
+
+    let field_path = |variant: &MlsVariantReceiver| match variant.fields.len() {
+        0 => None,
+        1 => Some(
+            variant.fields.fields[0]
+                .with
+                .clone()
+                .unwrap_or(path.clone()),
+        ),
+        _ => panic!("Enum variants with more than 1 field are not currently supported"),
+    };
+
+    let tag_for_variant = |variant: &MlsVariantReceiver| {
+        variant
+            .tag
+            .clone()
+            .expect("Variants of enums with a tag_type must define a tag or be marked as other")
+    };
+
+    if matches!(operation, Operation::Decode) {
+        let mut other_case = quote! {};
+
+        let cases = variants
+            .iter()
+            .filter_map(|variant| {
+                let variant_name = &variant.ident;
+                let cfg = &variant.attrs;
+                let field_path = field_path(variant);
+
+                if variant.other {
+                    let field_path =
+                        field_path.expect("The other variant must have exactly 1 field");
+
+                    // The field of the other variant decodes the tag itself
+                    other_case = quote! {
+                        #(#cfg)*
+                        _ => {
+                            *reader = tagged;
+                            Ok(#enum_name::#variant_name(#field_path::#call(#extras)?))
+                        }
+                    };
+
+                    return None;
+                }
+
+                let tag = tag_for_variant(variant);
+
+                let value = match field_path {
+                    Some(field_path) => {
+                        quote! { #enum_name::#variant_name(#field_path::#call(#extras)?) }
+                    }
+                    None => quote! { #enum_name::#variant_name },
+                };
+
+                Some(quote! { #(#cfg)* tag if tag == #tag => Ok(#value), })
+            })
+            .collect::<Vec<_>>();
+
+        let save_reader = (others > 0).then(|| quote! { let tagged = *reader; });
+
+        return quote! {
+            #save_reader
+            let tag = <#tag_type as mls_rs_codec::MlsDecode>::mls_decode(reader)?;
+
+            #[allow(unreachable_patterns)]
+            match tag {
+                #(#cases)*
+                #other_case
+                _ => Err(mls_rs_codec::Error::UnsupportedEnumDiscriminant),
+            }
+        };
+    }
+
+    let cases = variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let cfg = &variant.attrs;
+
+        assert!(
+            !variant.other || variant.fields.len() == 1,
+            "The other variant must have exactly 1 field"
+        );
+
+        let (parameter, field) = match field_path(variant) {
+            Some(field_path) => (
+                Some(quote! {(ref val)}),
+                Some(quote! { #field_path::#call (val #extras) #handle_error }),
+            ),
+            None => (None, None),
+        };
+
+        let tag = (!variant.other).then(|| {
+            let tag = tag_for_variant(variant);
+            let tag = quote! { #path::#call (&(#tag) #extras) #handle_error };
+
+            match (&operation, field.is_some()) {
+                (Operation::Size, true) => quote! { #tag + },
+                (_, true) => quote! { #tag; },
+                (_, false) => tag,
+            }
+        });
 
-        quote! { #enum_name::#variant_name #parameter => { #discrim #field }}
+        quote! { #(#cfg)* #enum_name::#variant_name #parameter => { #tag #field }}
     });
 
     let enum_impl = quote! {
@@ -0,0 +1,71 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::borrow::Cow;
+
+use crate::{iter::mls_decode_split_on_collection, Error};
+
+/// Trait to support decoding a type in MLS encoding while borrowing large
+/// opaque fields from the input buffer instead of copying them.
+///
+/// Implementations typically decode small fields with
+/// [`MlsDecode`](crate::MlsDecode) and large variable length byte strings,
+/// such as ratchet trees or ciphertexts, as `&'a [u8]`.
+pub trait MlsDecodeBorrowed<'a>: Sized {
+    fn mls_decode_borrowed(reader: &mut &'a [u8]) -> Result<Self, Error>;
+}
+
+impl<'a> MlsDecodeBorrowed<'a> for &'a [u8] {
+    fn mls_decode_borrowed(reader: &mut &'a [u8]) -> Result<Self, Error> {
+        let (data, rest) = mls_decode_split_on_collection(reader)?;
+
+        *reader = rest;
+
+        Ok(data)
+    }
+}
+
+impl<'a> MlsDecodeBorrowed<'a> for Cow<'a, [u8]> {
+    fn mls_decode_borrowed(reader: &mut &'a [u8]) -> Result<Self, Error> {
+        <&'a [u8]>::mls_decode_borrowed(reader).map(Cow::Borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, MlsDecode, MlsDecodeBorrowed, MlsEncode};
+    use alloc::{borrow::Cow, vec::Vec};
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn borrowed_decoding_matches_owned_decoding() {
+        let data = vec![1u8, 2, 3];
+        let encoded = (&data, 42u32).mls_encode_to_vec().unwrap();
+
+        let reader = &mut &*encoded;
+        let borrowed = <&[u8]>::mls_decode_borrowed(reader).unwrap();
+
+        assert_eq!(borrowed, data.as_slice());
+        assert_eq!(u32::mls_decode(reader).unwrap(), 42);
+        assert!(reader.is_empty());
+
+        // The decoded slice points into the input buffer.
+        assert_eq!(borrowed.as_ptr(), encoded[1..].as_ptr());
+
+        let cow = Cow::<[u8]>::mls_decode_borrowed(&mut &*encoded).unwrap();
+        assert_matches!(cow, Cow::Borrowed(b) if b == data.as_slice());
+        assert_eq!(Vec::<u8>::mls_decode(&mut &*encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn too_short_input_gives_an_error() {
+        assert_matches!(
+            <&[u8]>::mls_decode_borrowed(&mut &[3, 1, 2][..]),
+            Err(Error::UnexpectedEOF)
+        );
+    }
+}
@@ -10,6 +10,7 @@ use alloc::boxed::Box;
 pub use alloc::vec::Vec;
 
 mod array;
+mod borrowed;
 
 /// Optimized encoding and decoding for types that can be represented by `Vec<u8>`.
 ///
@@ -18,6 +19,12 @@ pub mod byte_vec;
 
 pub mod iter;
 
+/// Encoding and decoding of a value as `opaque value<V>`, i.e. prefixed by the
+/// length of its encoding.
+///
+/// Compatible with derive macros by using `mls_codec(with = "mls_rs_codec::opaque")`
+pub mod opaque;
+
 mod cow;
 mod map;
 mod option;
@@ -27,6 +34,7 @@ mod tuple;
 mod varint;
 mod vec;
 
+pub use borrowed::MlsDecodeBorrowed;
 pub use varint::*;
 
 pub use mls_rs_codec_derive::*;
@@ -43,6 +51,8 @@ pub enum Error {
     VarIntMinimumLengthEncoding,
     #[cfg_attr(feature = "std", error("UnexpectedEOF"))]
     UnexpectedEOF,
+    #[cfg_attr(feature = "std", error("Unexpected trailing bytes"))]
+    UnexpectedTrailingBytes,
     #[cfg_attr(feature = "std", error("Option marker out of range: {0}"))]
     OptionOutOfRange(u8),
     #[cfg_attr(feature = "std", error("Unsupported enum discriminant"))]
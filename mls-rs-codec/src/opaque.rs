@@ -0,0 +1,66 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use crate::{iter::mls_decode_split_on_collection, Error, MlsDecode, MlsEncode, MlsSize, VarInt};
+
+use alloc::vec::Vec;
+
+/// Length of a value encoded as `opaque value<V>`.
+pub fn mls_encoded_len<T: MlsSize>(value: &T) -> usize {
+    let len = value.mls_encoded_len();
+    let header_length = VarInt::try_from(len).unwrap_or(VarInt(0)).mls_encoded_len();
+
+    header_length + len
+}
+
+/// Encode a value as `opaque value<V>`, that is its MLS encoding prefixed by
+/// its length.
+pub fn mls_encode<T: MlsEncode>(value: &T, writer: &mut Vec<u8>) -> Result<(), Error> {
+    VarInt::try_from(value.mls_encoded_len())?.mls_encode(writer)?;
+    value.mls_encode(writer)
+}
+
+/// Decode a value encoded as `opaque value<V>`. The value must use all bytes
+/// covered by the length prefix.
+pub fn mls_decode<T: MlsDecode>(reader: &mut &[u8]) -> Result<T, Error> {
+    let (mut data, rest) = mls_decode_split_on_collection(reader)?;
+    let value = T::mls_decode(&mut data)?;
+
+    if !data.is_empty() {
+        return Err(Error::UnexpectedTrailingBytes);
+    }
+
+    *reader = rest;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, MlsEncode};
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn value_is_length_prefixed() {
+        let value = (1u16, 2u8);
+        let mut encoded = Vec::new();
+        super::mls_encode(&value, &mut encoded).unwrap();
+
+        assert_eq!(encoded, [3, 0, 1, 2]);
+        assert_eq!(super::mls_encoded_len(&value), encoded.len());
+        assert_eq!(value.mls_encode_to_vec().unwrap(), encoded[1..]);
+
+        let decoded: (u16, u8) = super::mls_decode(&mut &*encoded).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn trailing_bytes_inside_the_prefix_are_rejected() {
+        let res = super::mls_decode::<u16>(&mut &[3u8, 0, 1, 2][..]);
+        assert_matches!(res, Err(Error::UnexpectedTrailingBytes));
+    }
+}
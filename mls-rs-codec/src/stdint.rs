@@ -33,14 +33,37 @@ impl_stdint!(u16);
 impl_stdint!(u32);
 impl_stdint!(u64);
 
+impl MlsSize for bool {
+    fn mls_encoded_len(&self) -> usize {
+        1
+    }
+}
+
+impl MlsEncode for bool {
+    fn mls_encode(&self, writer: &mut Vec<u8>) -> Result<(), crate::Error> {
+        (*self as u8).mls_encode(writer)
+    }
+}
+
+impl MlsDecode for bool {
+    fn mls_decode(reader: &mut &[u8]) -> Result<Self, crate::Error> {
+        match u8::mls_decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(crate::Error::UnsupportedEnumDiscriminant),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
-    use crate::{MlsDecode, MlsEncode};
+    use crate::{Error, MlsDecode, MlsEncode};
 
     use alloc::vec;
+    use assert_matches::assert_matches;
 
     #[test]
     fn u8_round_trip() {
@@ -81,4 +104,24 @@ mod tests {
 
         assert_eq!(recovered, 100000000000u64);
     }
+
+    #[test]
+    fn bool_round_trip() {
+        for value in [false, true] {
+            let serialized = value.mls_encode_to_vec().unwrap();
+            assert_eq!(serialized, vec![value as u8]);
+
+            let recovered = bool::mls_decode(&mut &*serialized).unwrap();
+
+            assert_eq!(recovered, value);
+        }
+    }
+
+    #[test]
+    fn bool_out_of_range() {
+        assert_matches!(
+            bool::mls_decode(&mut &*vec![2u8]),
+            Err(Error::UnsupportedEnumDiscriminant)
+        );
+    }
 }
@@ -154,6 +154,94 @@ fn round_trip_custom_module_enum() {
     assert_eq!(item, decoded)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+struct TestTag(u16);
+
+impl TestTag {
+    const FIRST: TestTag = TestTag(1);
+    const SECOND: TestTag = TestTag(1000);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+struct TestUnknown {
+    tag: TestTag,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[mls_codec(tag_type = "TestTag")]
+enum TestTaggedEnum {
+    #[mls_codec(tag = "TestTag::FIRST")]
+    First,
+    #[mls_codec(tag = "TestTag::SECOND")]
+    Second(#[mls_codec(with = "mls_rs_codec::opaque")] TestFieldStruct),
+    #[cfg(any())]
+    #[mls_codec(tag = "TestTag(3)")]
+    Disabled(u8),
+    #[mls_codec(other)]
+    Unknown(TestUnknown),
+}
+
+#[test]
+fn round_trip_tagged_enum() {
+    let items = [
+        TestTaggedEnum::First,
+        TestTaggedEnum::Second(TestFieldStruct {
+            item1: Some(1),
+            item2: 2,
+        }),
+        TestTaggedEnum::Unknown(TestUnknown {
+            tag: TestTag(3),
+            data: vec![4, 5],
+        }),
+    ];
+
+    for item in items {
+        let serialized = item.mls_encode_to_vec().unwrap();
+        assert_eq!(serialized.len(), item.mls_encoded_len());
+
+        let decoded = TestTaggedEnum::mls_decode(&mut &*serialized).unwrap();
+        assert_eq!(decoded, item);
+    }
+
+    let serialized = TestTaggedEnum::First.mls_encode_to_vec().unwrap();
+    assert_eq!(serialized, TestTag::FIRST.mls_encode_to_vec().unwrap());
+
+    // The field of the other variant encodes the tag
+    let unknown = TestUnknown {
+        tag: TestTag(3),
+        data: vec![4, 5],
+    };
+
+    assert_eq!(
+        TestTaggedEnum::Unknown(unknown.clone())
+            .mls_encode_to_vec()
+            .unwrap(),
+        unknown.mls_encode_to_vec().unwrap()
+    );
+}
+
+#[test]
+fn tagged_enum_without_other_variant_rejects_unknown_tags() {
+    #[derive(Debug, Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+    #[mls_codec(tag_type = "TestTag")]
+    enum TestClosedEnum {
+        #[mls_codec(tag = "TestTag::FIRST")]
+        First(u8),
+    }
+
+    let item = TestClosedEnum::First(7);
+    let serialized = item.mls_encode_to_vec().unwrap();
+    assert_eq!(TestClosedEnum::mls_decode(&mut &*serialized).unwrap(), item);
+
+    let serialized = TestTag(3).mls_encode_to_vec().unwrap();
+
+    assert!(matches!(
+        TestClosedEnum::mls_decode(&mut &*serialized),
+        Err(mls_rs_codec::Error::UnsupportedEnumDiscriminant)
+    ));
+}
+
 mod test_with {
     use mls_rs_codec::MlsDecode;
 
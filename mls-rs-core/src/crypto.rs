@@ -299,6 +299,20 @@ pub trait CryptoProvider: Send + Sync {
         -> Option<Self::CipherSuiteProvider>;
 }
 
+/// Compare `a` and `b` in time that depends only on their lengths.
+///
+/// This is the default implementation of
+/// [constant_time_eq](CipherSuiteProvider::constant_time_eq).
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+
+    core::hint::black_box(diff) == 0
+}
+
 /// Provides all cryptographic operations required by MLS for a given cipher suite.
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
@@ -318,11 +332,33 @@ pub trait CipherSuiteProvider: Send + Sync {
     /// Compute the hash of `data`.
     async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error>;
 
+    /// Compute the hash of `data` truncated to its first `len` bytes. If `len`
+    /// exceeds the hash size, the full hash is returned.
+    ///
+    /// The default implementation truncates the output of
+    /// [hash](CipherSuiteProvider::hash).
+    async fn hash_truncated(&self, data: &[u8], len: usize) -> Result<Vec<u8>, Self::Error> {
+        let mut hash = self.hash(data).await?;
+        hash.truncate(len);
+
+        Ok(hash)
+    }
+
     /// Compute the MAC tag of `data` using the `key` of length [kdf_extract_size](CipherSuiteProvider::kdf_extract_size).
     /// Verifying a MAC tag of `data` using `key` is done by calling this function
-    /// and checking that the result matches the tag.
+    /// and checking that the result matches the tag using
+    /// [constant_time_eq](CipherSuiteProvider::constant_time_eq).
     async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error>;
 
+    /// Compare `a` and `b` in time that depends only on their lengths. Used to
+    /// verify MAC tags, such as confirmation and membership tags.
+    ///
+    /// The default implementation is [constant_time_eq]. Platforms providing
+    /// hardware backed constant time comparison can override it.
+    fn constant_time_eq(&self, a: &[u8], b: &[u8]) -> bool {
+        constant_time_eq(a, b)
+    }
+
     /// Encrypt `data` with public additional authenticated data `aad`, using additional `nonce`
     /// (sometimes called the initialization vector, IV). The output should include
     /// the authentication tag, if used by the given AEAD implementation (for example,
@@ -348,10 +384,15 @@ pub trait CipherSuiteProvider: Send + Sync {
 
     /// Return the length of the secret key `key` passed to [aead_seal](CipherSuiteProvider::aead_seal)
     /// and [aead_open](CipherSuiteProvider::aead_open).
+    ///
+    /// The size must be between 1 and 65535 bytes.
     fn aead_key_size(&self) -> usize;
 
     /// Return the length of the `nonce` passed to [aead_seal](CipherSuiteProvider::aead_seal)
     /// and [aead_open](CipherSuiteProvider::aead_open).
+    ///
+    /// The size must be between 4 and 65535 bytes, as the nonce of each
+    /// private message is XORed with a 4-byte reuse guard.
     fn aead_nonce_size(&self) -> usize;
 
     /// Generate a pseudo-random key `prk` extracted from the initial key
@@ -375,6 +416,30 @@ pub trait CipherSuiteProvider: Send + Sync {
         len: usize,
     ) -> Result<Zeroizing<Vec<u8>>, Self::Error>;
 
+    /// Expand the pseudo-random key `prk` once per element of `infos`, each
+    /// element holding the `info` and `len` inputs of a call to
+    /// [kdf_expand](CipherSuiteProvider::kdf_expand). The outputs must be
+    /// returned in the order of `infos`.
+    ///
+    /// MLS derives sibling secrets from the same key, such as the left and
+    /// right children of a secret tree node or the key and nonce of a message.
+    /// The default implementation calls [kdf_expand](CipherSuiteProvider::kdf_expand)
+    /// for each element. Providers can override it to share work between the
+    /// derivations, for instance by keying HMAC with `prk` only once.
+    async fn kdf_expand_batch(
+        &self,
+        prk: &[u8],
+        infos: &[(&[u8], usize)],
+    ) -> Result<Vec<Zeroizing<Vec<u8>>>, Self::Error> {
+        let mut outputs = Vec::with_capacity(infos.len());
+
+        for (info, len) in infos {
+            outputs.push(self.kdf_expand(prk, info, *len).await?);
+        }
+
+        Ok(outputs)
+    }
+
     /// Return the size of pseudo-random key `prk` outputted by [kdf_extract](CipherSuiteProvider::kdf_extract)
     /// and inputted to [kdf_expand](CipherSuiteProvider::kdf_expand).
     fn kdf_extract_size(&self) -> usize;
@@ -394,6 +459,49 @@ pub trait CipherSuiteProvider: Send + Sync {
         pt: &[u8],
     ) -> Result<HpkeCiphertext, Self::Error>;
 
+    /// Same as [hpke_seal](CipherSuiteProvider::hpke_seal) with the ephemeral key pair derived
+    /// from `ikm_e` as by [kem_derive](CipherSuiteProvider::kem_derive), as in the test vectors
+    /// of RFC 9180. The ciphertext is then a function of the inputs only.
+    ///
+    /// This is only available with the `test_util` feature, as reusing `ikm_e` breaks the
+    /// security of HPKE. The default implementation returns `None`, meaning that the provider
+    /// cannot derandomize encryption.
+    #[cfg(feature = "test_util")]
+    async fn hpke_seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, Self::Error> {
+        let _ = (remote_key, info, aad, pt, ikm_e);
+        Ok(None)
+    }
+
+    /// Encrypt each plaintext of `recipients` to the public key it is paired with, using the
+    /// same `info` and `aad` for all recipients. The ciphertexts are returned in the order of
+    /// `recipients` and each of them can be decrypted with [hpke_open](CipherSuiteProvider::hpke_open).
+    ///
+    /// This is used to encrypt the secrets of a Welcome message to all new members. Providers
+    /// may override it to compute the values depending only on `info` once for all recipients,
+    /// or to use a dedicated multi-recipient implementation. The default implementation calls
+    /// [hpke_seal](CipherSuiteProvider::hpke_seal) for each recipient.
+    async fn hpke_seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, Self::Error> {
+        let mut ciphertexts = Vec::with_capacity(recipients.len());
+
+        for (remote_key, pt) in recipients {
+            ciphertexts.push(self.hpke_seal(remote_key, info, aad, pt).await?);
+        }
+
+        Ok(ciphertexts)
+    }
+
     /// Decrypt the `ciphertext` generated by [hpke_seal](CipherSuiteProvider::hpke_seal).
     /// This function combines the action of the [hpke_setup_r](CipherSuiteProvider::hpke_setup_r)
     /// and then calling [open](HpkeContextR::open) on the resulting [HpkeContextR](self::HpkeContextR).
@@ -501,3 +609,16 @@ pub trait CipherSuiteProvider: Send + Sync {
         data: &[u8],
     ) -> Result<(), Self::Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn constant_time_eq_compares_contents_and_lengths() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"tag", b"tag"));
+        assert!(!constant_time_eq(b"tag", b"tab"));
+        assert!(!constant_time_eq(b"tag", b"tags"));
+    }
+}
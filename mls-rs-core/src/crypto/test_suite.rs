@@ -271,6 +271,43 @@ async fn verify_hpke_tests<C: CipherSuiteProvider>(cs: &C, test_cases: HpkeTestC
     let generated = generate_hpke_tests(cs).await;
     verify_hpke_test(cs, generated).await;
     verify_hpke_test(cs, test_cases).await;
+    verify_hpke_seal_multi(cs).await;
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_hpke_seal_multi<C: CipherSuiteProvider>(cs: &C) {
+    let info = cs.random_bytes_vec(32).unwrap();
+    let aad = cs.random_bytes_vec(16).unwrap();
+
+    let mut keys = Vec::new();
+    let mut plaintexts = Vec::new();
+
+    for size in DATA_SIZES.iter().copied().skip(1) {
+        keys.push(cs.kem_generate().await.unwrap());
+        plaintexts.push(cs.random_bytes_vec(size).unwrap());
+    }
+
+    let recipients = keys
+        .iter()
+        .zip(&plaintexts)
+        .map(|((_, public), plaintext)| (public, plaintext.as_slice()))
+        .collect::<Vec<_>>();
+
+    let ciphertexts = cs
+        .hpke_seal_multi(&recipients, &info, Some(&aad))
+        .await
+        .unwrap();
+
+    assert_eq!(ciphertexts.len(), recipients.len());
+
+    for (((secret, public), plaintext), ct) in keys.iter().zip(&plaintexts).zip(&ciphertexts) {
+        let opened = cs
+            .hpke_open(ct, secret, public, &info, Some(&aad))
+            .await
+            .unwrap();
+
+        assert_eq!(&opened, plaintext);
+    }
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
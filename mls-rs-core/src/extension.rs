@@ -33,6 +33,45 @@ impl ExtensionType {
     pub const REQUIRED_CAPABILITIES: ExtensionType = ExtensionType(3);
     pub const EXTERNAL_PUB: ExtensionType = ExtensionType(4);
     pub const EXTERNAL_SENDERS: ExtensionType = ExtensionType(5);
+    /// Non-standard extension requiring knowledge of an external PSK to join
+    /// a group via Welcome.
+    ///
+    /// The non-standard extension types of this library are not registered
+    /// with IANA and use the private use range `0xF000` to `0xFFFF`. If they
+    /// collide with extension types of an application, they can be changed
+    /// when building this crate by setting the environment variable named in
+    /// their documentation to a decimal or `0x` prefixed hexadecimal value.
+    /// All members of a group must use the same values.
+    ///
+    /// Defaults to `0xF001`, changed by `MLS_RS_REQUIRED_JOIN_PSK_EXTENSION`.
+    pub const REQUIRED_JOIN_PSK: ExtensionType = ExtensionType(codepoint(
+        option_env!("MLS_RS_REQUIRED_JOIN_PSK_EXTENSION"),
+        0xF001,
+    ));
+    /// Non-standard extension listing keys that receive the exporter secret of
+    /// each epoch.
+    ///
+    /// Defaults to `0xF002`, changed by `MLS_RS_EXPORTER_ESCROW_EXTENSION`.
+    pub const EXPORTER_ESCROW: ExtensionType = ExtensionType(codepoint(
+        option_env!("MLS_RS_EXPORTER_ESCROW_EXTENSION"),
+        0xF002,
+    ));
+    /// Non-standard extension holding small application values owned by
+    /// individual members.
+    ///
+    /// Defaults to `0xF003`, changed by `MLS_RS_MEMBER_SLOTS_EXTENSION`.
+    pub const MEMBER_SLOTS: ExtensionType = ExtensionType(codepoint(
+        option_env!("MLS_RS_MEMBER_SLOTS_EXTENSION"),
+        0xF003,
+    ));
+    /// Non-standard extension holding the key that application message
+    /// receive keys can be escrowed to.
+    ///
+    /// Defaults to `0xF004`, changed by `MLS_RS_MESSAGE_ESCROW_EXTENSION`.
+    pub const MESSAGE_ESCROW: ExtensionType = ExtensionType(codepoint(
+        option_env!("MLS_RS_MESSAGE_ESCROW_EXTENSION"),
+        0xF004,
+    ));
 
     /// Default extension types defined
     /// in [RFC 9420](https://www.rfc-editor.org/rfc/rfc9420.html#name-leaf-node-contents)
@@ -61,6 +100,44 @@ impl ExtensionType {
     }
 }
 
+/// Codepoint of a non-standard type of this library, given by the build time
+/// environment variable `value` if it is set.
+pub(crate) const fn codepoint(value: Option<&str>, default: u16) -> u16 {
+    let Some(value) = value else {
+        return default;
+    };
+
+    let bytes = value.as_bytes();
+
+    let (radix, mut i) = if bytes.len() > 2 && bytes[0] == b'0' && bytes[1] == b'x' {
+        (16, 2)
+    } else {
+        (10, 0)
+    };
+
+    assert!(i < bytes.len(), "empty codepoint");
+
+    let mut codepoint = 0u32;
+
+    while i < bytes.len() {
+        let digit = match bytes[i] {
+            b'0'..=b'9' => bytes[i] - b'0',
+            b'a'..=b'f' => bytes[i] - b'a' + 10,
+            b'A'..=b'F' => bytes[i] - b'A' + 10,
+            _ => panic!("invalid digit in codepoint"),
+        } as u32;
+
+        assert!(digit < radix, "invalid digit in codepoint");
+
+        codepoint = codepoint * radix + digit;
+        assert!(codepoint <= u16::MAX as u32, "codepoint out of range");
+
+        i += 1;
+    }
+
+    codepoint as u16
+}
+
 impl From<u16> for ExtensionType {
     fn from(value: u16) -> Self {
         ExtensionType(value)
@@ -221,7 +298,9 @@ mod tests {
     use assert_matches::assert_matches;
     use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
-    use super::{Extension, ExtensionError, ExtensionType, MlsCodecExtension, MlsExtension};
+    use super::{
+        codepoint, Extension, ExtensionError, ExtensionType, MlsCodecExtension, MlsExtension,
+    };
 
     struct TestExtension;
 
@@ -265,4 +344,11 @@ mod tests {
 
         assert_matches!(AnotherTestExtension::from_extension(&ext), Err(ExtensionError::IncorrectType(found)) if found == 42.into());
     }
+
+    #[test]
+    fn codepoints_are_parsed() {
+        assert_eq!(codepoint(None, 0xF001), 0xF001);
+        assert_eq!(codepoint(Some("61441"), 0), 0xF001);
+        assert_eq!(codepoint(Some("0xf00A"), 0), 0xF00A);
+    }
 }
@@ -9,6 +9,9 @@ use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
 /// A collection of MLS [Extensions](super::Extension).
 ///
+/// Decoding keeps the extensions in their encoded order, including
+/// extensions of unknown types, so that a decoded list is re-encoded
+/// byte-exact.
 ///
 /// # Warning
 ///
@@ -164,6 +167,23 @@ impl ExtensionList {
     pub fn append(&mut self, others: Self) {
         self.0.extend(others.0);
     }
+
+    /// Iterate over the extensions whose type is neither one of the
+    /// [default](super::ExtensionType::DEFAULT) types nor in `known`, in the
+    /// order they were received.
+    ///
+    /// The [extension data](super::Extension::extension_data) of each entry is
+    /// the opaque value received from the sender. Lists decoded from a leaf
+    /// node or a key package are kept unchanged, so these extensions remain
+    /// covered by the signature of the leaf node or key package.
+    pub fn unknown_extensions<'a>(
+        &'a self,
+        known: &'a [ExtensionType],
+    ) -> impl Iterator<Item = &'a Extension> + 'a {
+        self.0.iter().filter(move |ext| {
+            !ext.extension_type.is_default() && !known.contains(&ext.extension_type)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -361,4 +381,25 @@ mod tests {
 
         assert_eq!(list, expected);
     }
+
+    #[test]
+    fn unknown_extensions_are_listed_with_their_data() {
+        let unknown = Extension::new(ExtensionType(254), vec![0, 1, 2]);
+
+        let list = ExtensionList::from(vec![
+            Extension::new(ExtensionType::APPLICATION_ID, vec![3]),
+            TestExtensionA(33).into_extension().unwrap(),
+            unknown.clone(),
+        ]);
+
+        let known = [<TestExtensionA as MlsCodecExtension>::extension_type()];
+        let unknown_extensions = list.unknown_extensions(&known).collect::<Vec<_>>();
+
+        assert_eq!(unknown_extensions, vec![&unknown]);
+
+        let decoded = ExtensionList::mls_decode(&mut &*list.mls_encode_to_vec().unwrap()).unwrap();
+        let decoded_unknown = decoded.unknown_extensions(&known).collect::<Vec<_>>();
+
+        assert_eq!(decoded_unknown, vec![&unknown]);
+    }
 }
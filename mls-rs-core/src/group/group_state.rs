@@ -4,7 +4,7 @@
 
 use core::fmt::{self, Debug};
 
-use crate::error::IntoAnyError;
+use crate::{error::IntoAnyError, time::MlsTime};
 #[cfg(mls_build_async)]
 use alloc::boxed::Box;
 use alloc::vec::Vec;
@@ -104,4 +104,56 @@ pub trait GroupStateStorage: Send + Sync {
     /// The [`EpochRecord::id`] value that is associated with a stored
     /// prior epoch for a particular group.
     async fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, Self::Error>;
+
+    /// Retrieve the ids of all groups with a stored state, or `None` if the
+    /// storage can not enumerate them.
+    ///
+    /// This function is used to enumerate the data stored for a client, for
+    /// instance to export it. The default implementation returns `None`.
+    async fn group_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Time at which the state of the group with `group_id` was last written,
+    /// or `None` if the storage does not record it.
+    ///
+    /// This function is used to describe the data stored for a client. The
+    /// default implementation returns `None`.
+    async fn state_updated_at(&self, group_id: &[u8]) -> Result<Option<MlsTime>, Self::Error> {
+        let _ = group_id;
+        Ok(None)
+    }
+
+    /// Time at which the prior epoch `epoch_id` of the group with `group_id`
+    /// was last written, or `None` if the storage does not record it.
+    ///
+    /// This function is used to describe the data stored for a client. The
+    /// default implementation returns `None`.
+    async fn epoch_updated_at(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<MlsTime>, Self::Error> {
+        let _ = (group_id, epoch_id);
+        Ok(None)
+    }
+
+    /// Reclaim storage space used by a particular group.
+    ///
+    /// Prior epochs with an [`EpochRecord::id`] lower than `delete_epoch_under`
+    /// should be deleted if it is set to `Some`. This function is called once per
+    /// group, so whole-database maintenance such as vacuuming should be left to a
+    /// separate call made by the application.
+    ///
+    /// This function is called by `Group::compact_storage` in `mls_rs` after
+    /// the current group state was rewritten. It returns the number of bytes of
+    /// group data that were deleted, or `0` if unknown. The default implementation
+    /// does nothing.
+    async fn compact(
+        &mut self,
+        _group_id: &[u8],
+        _delete_epoch_under: Option<u64>,
+    ) -> Result<u64, Self::Error> {
+        Ok(0)
+    }
 }
@@ -7,6 +7,8 @@ use core::ops::Deref;
 
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
+use crate::extension::codepoint;
+
 /// Wrapper type representing a proposal type identifier along with default
 /// values defined by the MLS RFC.
 #[derive(
@@ -56,6 +58,17 @@ impl ProposalType {
     pub const RE_INIT: ProposalType = ProposalType(5);
     pub const EXTERNAL_INIT: ProposalType = ProposalType(6);
     pub const GROUP_CONTEXT_EXTENSIONS: ProposalType = ProposalType(7);
+    /// Non-standard proposal setting a value owned by its sender in the
+    /// member slots extension.
+    ///
+    /// Like the [non-standard extension types](crate::extension::ExtensionType::REQUIRED_JOIN_PSK)
+    /// of this library, it is not registered with IANA and uses the private
+    /// use range. Defaults to `0xF003`, changed by
+    /// `MLS_RS_SET_MEMBER_SLOT_PROPOSAL`.
+    pub const SET_MEMBER_SLOT: ProposalType = ProposalType(codepoint(
+        option_env!("MLS_RS_SET_MEMBER_SLOT_PROPOSAL"),
+        0xF003,
+    ));
 
     /// Default proposal types defined
     /// in [RFC 9420](https://www.rfc-editor.org/rfc/rfc9420.html#name-leaf-node-contents)
@@ -119,13 +119,14 @@ impl CustomCredential {
 }
 
 /// A MLS credential used to authenticate a group member.
-#[derive(Clone, Debug, PartialEq, Ord, PartialOrd, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Ord, PartialOrd, Eq, Hash, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[cfg_attr(
     all(feature = "ffi", not(test)),
     safer_ffi_gen::ffi_type(clone, opaque)
 )]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[mls_codec(tag_type = "CredentialType")]
 #[non_exhaustive]
 pub enum Credential {
     /// Basic identifier-only credential.
@@ -135,11 +136,14 @@ pub enum Credential {
     /// Basic credentials are inherently insecure since they can not be
     /// properly validated. It is not recommended to use [`BasicCredential`]
     /// in production applications.
+    #[mls_codec(tag = "CredentialType::BASIC")]
     Basic(BasicCredential),
     #[cfg(feature = "x509")]
     /// X.509 Certificate chain.
+    #[mls_codec(tag = "CredentialType::X509")]
     X509(CertificateChain),
     /// User provided custom credential.
+    #[mls_codec(other)]
     Custom(CustomCredential),
 }
 
@@ -186,48 +190,6 @@ impl Credential {
     }
 }
 
-impl MlsSize for Credential {
-    fn mls_encoded_len(&self) -> usize {
-        let inner_len = match self {
-            Credential::Basic(c) => c.mls_encoded_len(),
-            #[cfg(feature = "x509")]
-            Credential::X509(c) => c.mls_encoded_len(),
-            Credential::Custom(c) => mls_rs_codec::byte_vec::mls_encoded_len(&c.data),
-        };
-
-        self.credential_type().mls_encoded_len() + inner_len
-    }
-}
-
-impl MlsEncode for Credential {
-    fn mls_encode(&self, writer: &mut Vec<u8>) -> Result<(), mls_rs_codec::Error> {
-        self.credential_type().mls_encode(writer)?;
-
-        match self {
-            Credential::Basic(c) => c.mls_encode(writer),
-            #[cfg(feature = "x509")]
-            Credential::X509(c) => c.mls_encode(writer),
-            Credential::Custom(c) => mls_rs_codec::byte_vec::mls_encode(&c.data, writer),
-        }
-    }
-}
-
-impl MlsDecode for Credential {
-    fn mls_decode(reader: &mut &[u8]) -> Result<Self, mls_rs_codec::Error> {
-        let credential_type = CredentialType::mls_decode(reader)?;
-
-        Ok(match credential_type {
-            CredentialType::BASIC => Credential::Basic(BasicCredential::mls_decode(reader)?),
-            #[cfg(feature = "x509")]
-            CredentialType::X509 => Credential::X509(CertificateChain::mls_decode(reader)?),
-            custom => Credential::Custom(CustomCredential {
-                credential_type: custom,
-                data: mls_rs_codec::byte_vec::mls_decode(reader)?,
-            }),
-        })
-    }
-}
-
 /// Trait that provides a conversion between an underlying credential type and
 /// the [`Credential`] enum.
 pub trait MlsCredential: Sized {
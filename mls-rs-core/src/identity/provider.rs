@@ -52,6 +52,27 @@ pub trait IdentityProvider: Send + Sync {
         extensions: &ExtensionList,
     ) -> Result<Vec<u8>, Self::Error>;
 
+    /// Unique identifiers for all of `signing_identities`, in the same order.
+    ///
+    /// This is used when many identities must be resolved at once, for
+    /// example when applying a commit adding many members. Providers relying
+    /// on an external service can override it to resolve all identities in a
+    /// single round-trip. The default implementation calls
+    /// [`identity`](IdentityProvider::identity) for each signing identity.
+    async fn identities(
+        &self,
+        signing_identities: &[&SigningIdentity],
+        extensions: &ExtensionList,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let mut identities = Vec::with_capacity(signing_identities.len());
+
+        for signing_identity in signing_identities {
+            identities.push(self.identity(signing_identity, extensions).await?);
+        }
+
+        Ok(identities)
+    }
+
     /// Determines if `successor` can remove `predecessor` as part of an external commit.
     ///
     /// The MLS protocol allows for removal of an existing member when adding a
@@ -65,6 +86,23 @@ pub trait IdentityProvider: Send + Sync {
         extensions: &ExtensionList,
     ) -> Result<bool, Self::Error>;
 
+    /// Determine if the extensions of the leaf node of a group member using
+    /// `signing_identity` are acceptable, for example by verifying a device
+    /// attestation carried in an application defined extension.
+    ///
+    /// This is called every time a leaf node is validated, after
+    /// [`validate_member`](IdentityProvider::validate_member) accepted
+    /// `signing_identity`. `group_extensions` are the extensions of the group
+    /// context, if known. The default implementation accepts any extensions.
+    async fn validate_leaf_node_extensions(
+        &self,
+        _signing_identity: &SigningIdentity,
+        _leaf_node_extensions: &ExtensionList,
+        _group_extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     /// Credential types that are supported by this provider.
     fn supported_types(&self) -> Vec<CredentialType>;
 }
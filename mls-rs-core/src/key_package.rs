@@ -76,9 +76,49 @@ pub trait KeyPackageStorage: Send + Sync {
     /// This function is automatically called whenever a new key package is created.
     async fn insert(&mut self, id: Vec<u8>, pkg: KeyPackageData) -> Result<(), Self::Error>;
 
+    /// Store several [`KeyPackageData`] at once, each accessible by its `id`.
+    ///
+    /// This function is called when generating a batch of key packages.
+    /// Storage backends should write the whole batch in a single operation.
+    /// The default implementation calls [`insert`](Self::insert) for each key
+    /// package.
+    async fn insert_batch(
+        &mut self,
+        key_packages: Vec<(Vec<u8>, KeyPackageData)>,
+    ) -> Result<(), Self::Error> {
+        for (id, pkg) in key_packages {
+            self.insert(id, pkg).await?;
+        }
+
+        Ok(())
+    }
+
     /// Retrieve [`KeyPackageData`] by its `id`.
     ///
     /// `None` should be returned in the event that no key packages are found
     /// that match `id`.
     async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error>;
+
+    /// Retrieve the ids of all key packages whose
+    /// [expiration](KeyPackageData::expiration) is before `time`, in seconds
+    /// since the Unix epoch.
+    ///
+    /// This function is used to sweep expired key packages. Storage backends
+    /// should answer it with a range query on an index of expiration times.
+    /// The default implementation returns no ids, in which case no key package
+    /// is swept.
+    async fn expired_before(&self, time: u64) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let _ = time;
+        Ok(Vec::new())
+    }
+
+    /// Retrieve the ids of all stored key packages, or `None` if the storage
+    /// can not enumerate them.
+    ///
+    /// This function is used to enumerate the data stored for a client, for
+    /// instance to export it or to check the stored key packages against the
+    /// client's configuration. The default implementation returns `None`.
+    async fn key_package_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        Ok(None)
+    }
 }
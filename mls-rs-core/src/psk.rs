@@ -134,4 +134,13 @@ pub trait PreSharedKeyStorage: Send + Sync {
     async fn contains(&self, id: &ExternalPskId) -> Result<bool, Self::Error> {
         self.get(id).await.map(|key| key.is_some())
     }
+
+    /// Retrieve the ids of all stored pre-shared keys, or `None` if the
+    /// storage can not enumerate them.
+    ///
+    /// This function is used to enumerate the data stored for a client, for
+    /// instance to export it. The default implementation returns `None`.
+    async fn psk_ids(&self) -> Result<Option<Vec<ExternalPskId>>, Self::Error> {
+        Ok(None)
+    }
 }
@@ -4,9 +4,6 @@
 
 use core::time::Duration;
 
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::*;
-
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::ffi_type)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -48,20 +45,14 @@ impl From<u64> for MlsTime {
     }
 }
 
-#[cfg(target_arch = "wasm32")]
-#[wasm_bindgen(inline_js = r#"
-export function date_now() {
-  return Date.now();
-}"#)]
-extern "C" {
-    fn date_now() -> f64;
-}
-
+// `SystemTime` panics on wasm32-unknown-unknown, the clock of the JS host is
+// used instead. This does not depend on the `std` feature.
 #[cfg(target_arch = "wasm32")]
 impl MlsTime {
+    /// Current time of the JS host.
     pub fn now() -> Self {
         Self {
-            seconds: (date_now() / 1000.0) as u64,
+            seconds: (js_sys::Date::now() / 1000.0) as u64,
         }
     }
 }
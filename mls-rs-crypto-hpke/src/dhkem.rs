@@ -102,24 +102,20 @@ impl<DH: DhType, KDF: KdfType> KemType for DhKem<DH, KDF> {
     }
 
     async fn encap(&self, remote_pk: &HpkePublicKey) -> Result<KemResult, Self::Error> {
-        let (ephemeral_sk, ephemeral_pk) = self.generate().await?;
-
-        let ecdh_ss = self
-            .dh
-            .dh(&ephemeral_sk, remote_pk)
-            .await
-            .map(Zeroizing::new)
-            .map_err(|e| DhKemError::DhError(e.into_any_error()))?;
-
-        let kem_context = [ephemeral_pk.as_ref(), remote_pk.as_ref()].concat();
+        let ephemeral = self.generate().await?;
+        self.encap_with_ephemeral(ephemeral, remote_pk).await
+    }
 
-        let shared_secret = self
-            .kdf
-            .labeled_extract_then_expand(&ecdh_ss, &kem_context, self.n_secret)
+    #[cfg(feature = "test_util")]
+    async fn encap_derand(
+        &self,
+        remote_pk: &HpkePublicKey,
+        ikm_e: &[u8],
+    ) -> Result<Option<KemResult>, Self::Error> {
+        let ephemeral = self.derive(ikm_e).await?;
+        self.encap_with_ephemeral(ephemeral, remote_pk)
             .await
-            .map_err(|e| DhKemError::KdfError(e.into_any_error()))?;
-
-        Ok(KemResult::new(shared_secret, ephemeral_pk.into()))
+            .map(Some)
     }
 
     async fn decap(
@@ -153,6 +149,30 @@ impl<DH: DhType, KDF: KdfType> KemType for DhKem<DH, KDF> {
 }
 
 impl<DH: DhType, KDF: KdfType> DhKem<DH, KDF> {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn encap_with_ephemeral(
+        &self,
+        (ephemeral_sk, ephemeral_pk): (HpkeSecretKey, HpkePublicKey),
+        remote_pk: &HpkePublicKey,
+    ) -> Result<KemResult, DhKemError> {
+        let ecdh_ss = self
+            .dh
+            .dh(&ephemeral_sk, remote_pk)
+            .await
+            .map(Zeroizing::new)
+            .map_err(|e| DhKemError::DhError(e.into_any_error()))?;
+
+        let kem_context = [ephemeral_pk.as_ref(), remote_pk.as_ref()].concat();
+
+        let shared_secret = self
+            .kdf
+            .labeled_extract_then_expand(&ecdh_ss, &kem_context, self.n_secret)
+            .await
+            .map_err(|e| DhKemError::KdfError(e.into_any_error()))?;
+
+        Ok(KemResult::new(shared_secret, ephemeral_pk.into()))
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn derive_with_rejection_sampling(
         &self,
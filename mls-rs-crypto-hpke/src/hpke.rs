@@ -128,6 +128,86 @@ where
         })
     }
 
+    /// Same as [seal](Hpke::seal) with the ephemeral key pair of the KEM derived
+    /// from `ikm_e`, as in the test vectors of RFC 9180. Returns `None` if the
+    /// KEM cannot derandomize encapsulation. See
+    /// [encap_derand](KemType::encap_derand).
+    #[cfg(feature = "test_util")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        psk: Option<Psk<'_>>,
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, HpkeError> {
+        let kem_res = self
+            .kem
+            .encap_derand(remote_key, ikm_e)
+            .await
+            .map_err(|e| HpkeError::KemError(e.into_any_error()))?;
+
+        let Some(kem_res) = kem_res else {
+            return Ok(None);
+        };
+
+        let mut ctx = self
+            .key_schedule(self.base_mode(&psk), kem_res.shared_secret(), info, psk)
+            .await
+            .map(ContextS)?;
+
+        Ok(Some(HpkeCiphertext {
+            kem_output: kem_res.enc().to_owned(),
+            ciphertext: ctx.seal(aad, pt).await?,
+        }))
+    }
+
+    /// Encrypt each plaintext of `recipients` to the public key it is paired
+    /// with, using the same `info`, `psk` and `aad` for all recipients.
+    ///
+    /// The result is the same as calling [seal](Hpke::seal) for each
+    /// recipient. Only the key schedule context, which depends on `info` and
+    /// `psk` alone, is computed once. Encapsulation and the rest of the key
+    /// schedule still run for every recipient, so the savings are small.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        psk: Option<Psk<'_>>,
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, HpkeError> {
+        let mode = self.base_mode(&psk);
+        self.check_psk(psk.as_ref())?;
+
+        let psk = psk.unwrap_or_default();
+        let key_schedule_context = self.key_schedule_context(mode, info, &psk).await?;
+
+        let mut ciphertexts = Vec::with_capacity(recipients.len());
+
+        for (remote_key, pt) in recipients {
+            let kem_res = self
+                .kem
+                .encap(remote_key)
+                .await
+                .map_err(|e| HpkeError::KemError(e.into_any_error()))?;
+
+            let mut ctx = self
+                .key_schedule_with_context(kem_res.shared_secret(), &key_schedule_context, &psk)
+                .await
+                .map(ContextS)?;
+
+            ciphertexts.push(HpkeCiphertext {
+                kem_output: kem_res.enc().to_owned(),
+                ciphertext: ctx.seal(aad, pt).await?,
+            });
+        }
+
+        Ok(ciphertexts)
+    }
+
     /// Based on RFC 9180 Single-Shot APIs. This function combines the action
     /// of the [setup_receiver](Hpke::setup_receiver) and then calling
     /// [open](ContextR::open) on the resulting [ContextR](self::ContextR).
@@ -241,7 +321,20 @@ where
         self.check_psk(psk.as_ref())?;
 
         let psk = psk.unwrap_or_default();
+        let key_schedule_context = self.key_schedule_context(mode, info, &psk).await?;
+
+        self.key_schedule_with_context(shared_secret, &key_schedule_context, &psk)
+            .await
+    }
 
+    /// Compute `key_schedule_context`, which does not depend on the shared secret.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn key_schedule_context(
+        &self,
+        mode: HpkeModeId,
+        info: &[u8],
+        psk: &Psk<'_>,
+    ) -> Result<Vec<u8>, HpkeError> {
         let psk_id_hash = self
             .kdf
             .labeled_extract(&[], b"psk_id_hash", psk.id)
@@ -254,6 +347,21 @@ where
             .await
             .map_err(|e| HpkeError::KdfError(e.into_any_error()))?;
 
+        Ok([
+            &(mode as u8).to_be_bytes() as &[u8],
+            &psk_id_hash,
+            &info_hash,
+        ]
+        .concat())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn key_schedule_with_context(
+        &self,
+        shared_secret: &[u8],
+        key_schedule_context: &[u8],
+        psk: &Psk<'_>,
+    ) -> Result<Context<KDF, AEAD>, HpkeError> {
         let secret = self
             .kdf
             .labeled_extract(shared_secret, b"secret", psk.value)
@@ -261,17 +369,10 @@ where
             .map(Zeroizing::new)
             .map_err(|e| HpkeError::KdfError(e.into_any_error()))?;
 
-        let key_schedule_context = [
-            &(mode as u8).to_be_bytes() as &[u8],
-            &psk_id_hash,
-            &info_hash,
-        ]
-        .concat();
-
         let encryption_context = if let Some(aead) = &self.aead {
             let key = self
                 .kdf
-                .labeled_expand(&secret, b"key", &key_schedule_context, aead.key_size())
+                .labeled_expand(&secret, b"key", key_schedule_context, aead.key_size())
                 .await
                 .map_err(|e| HpkeError::KdfError(e.into_any_error()))?;
 
@@ -280,7 +381,7 @@ where
                 .labeled_expand(
                     &secret,
                     b"base_nonce",
-                    &key_schedule_context,
+                    key_schedule_context,
                     aead.nonce_size(),
                 )
                 .await
@@ -295,7 +396,7 @@ where
 
         let exporter_secret = self
             .kdf
-            .labeled_expand(&secret, b"exp", &key_schedule_context, len)
+            .labeled_expand(&secret, b"exp", key_schedule_context, len)
             .await
             .map_err(|e| HpkeError::KdfError(e.into_any_error()))?;
 
@@ -274,6 +274,30 @@ where
         Ok(self.hpke.seal(remote_key, info, None, aad, pt).await?)
     }
 
+    #[cfg(feature = "test_util")]
+    async fn hpke_seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, Self::Error> {
+        Ok(self
+            .hpke
+            .seal_derand(remote_key, info, None, aad, pt, ikm_e)
+            .await?)
+    }
+
+    async fn hpke_seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, Self::Error> {
+        Ok(self.hpke.seal_multi(recipients, info, None, aad).await?)
+    }
+
     async fn hpke_open(
         &self,
         ciphertext: &HpkeCiphertext,
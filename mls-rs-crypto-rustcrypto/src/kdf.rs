@@ -82,6 +82,33 @@ impl KdfType for Kdf {
         Ok(buf)
     }
 
+    async fn expand_batch(
+        &self,
+        prk: &[u8],
+        infos: &[(&[u8], usize)],
+    ) -> Result<Vec<Vec<u8>>, KdfError> {
+        if prk.len() < self.extract_size() {
+            return Err(KdfError::TooShortKey(prk.len(), self.extract_size()));
+        }
+
+        // HMAC is keyed with the prk once and the keyed state is reused for every info
+        match self.0 {
+            KdfId::HkdfSha256 => {
+                let hkdf = SimpleHkdf::<Sha256>::from_prk(prk)?;
+                expand_all(infos, |info, buf| hkdf.expand(info, buf))
+            }
+            KdfId::HkdfSha384 => {
+                let hkdf = SimpleHkdf::<Sha384>::from_prk(prk)?;
+                expand_all(infos, |info, buf| hkdf.expand(info, buf))
+            }
+            KdfId::HkdfSha512 => {
+                let hkdf = SimpleHkdf::<Sha512>::from_prk(prk)?;
+                expand_all(infos, |info, buf| hkdf.expand(info, buf))
+            }
+            _ => Err(KdfError::UnsupportedCipherSuite),
+        }
+    }
+
     async fn extract(&self, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, KdfError> {
         if ikm.is_empty() {
             return Err(KdfError::TooShortKey(0, 1));
@@ -106,6 +133,20 @@ impl KdfType for Kdf {
     }
 }
 
+fn expand_all<F>(infos: &[(&[u8], usize)], expand: F) -> Result<Vec<Vec<u8>>, KdfError>
+where
+    F: Fn(&[u8], &mut [u8]) -> Result<(), hkdf::InvalidLength>,
+{
+    infos
+        .iter()
+        .map(|(info, len)| {
+            let mut buf = vec![0u8; *len];
+            expand(info, &mut buf)?;
+            Ok(buf)
+        })
+        .collect()
+}
+
 #[cfg(all(test, not(mls_build_async)))]
 mod test {
     use assert_matches::assert_matches;
@@ -114,7 +155,7 @@ mod test {
 
     use crate::kdf::{Kdf, KdfError};
 
-    use alloc::vec;
+    use alloc::{vec, vec::Vec};
 
     #[test]
     fn no_key() {
@@ -142,4 +183,24 @@ mod test {
 
         assert_matches!(kdf.expand(&key, &[], 42), Err(KdfError::TooShortKey(_, _)));
     }
+
+    #[test]
+    fn batch_expansion_matches_single_expansions() {
+        for cs in [
+            CipherSuite::CURVE25519_AES128,
+            CipherSuite::P384_AES256,
+            CipherSuite::P521_AES256,
+        ] {
+            let kdf = Kdf::new(cs).unwrap();
+            let key = vec![7u8; kdf.extract_size()];
+            let infos: [(&[u8], usize); 3] = [(b"left", 32), (b"right", 48), (b"", 12)];
+
+            let expected = infos
+                .iter()
+                .map(|(info, len)| kdf.expand(&key, info, *len).unwrap())
+                .collect::<Vec<_>>();
+
+            assert_eq!(kdf.expand_batch(&key, &infos).unwrap(), expected);
+        }
+    }
 }
@@ -262,6 +262,18 @@ where
             .map(Zeroizing::new)
     }
 
+    async fn kdf_expand_batch(
+        &self,
+        prk: &[u8],
+        infos: &[(&[u8], usize)],
+    ) -> Result<Vec<Zeroizing<Vec<u8>>>, Self::Error> {
+        self.kdf
+            .expand_batch(prk, infos)
+            .await
+            .map_err(|e| RustCryptoError::KdfError(e.into_any_error()))
+            .map(|outputs| outputs.into_iter().map(Zeroizing::new).collect())
+    }
+
     async fn kdf_extract(
         &self,
         salt: &[u8],
@@ -288,6 +300,30 @@ where
         Ok(self.hpke.seal(remote_key, info, None, aad, pt).await?)
     }
 
+    #[cfg(feature = "test_util")]
+    async fn hpke_seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, Self::Error> {
+        Ok(self
+            .hpke
+            .seal_derand(remote_key, info, None, aad, pt, ikm_e)
+            .await?)
+    }
+
+    async fn hpke_seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, Self::Error> {
+        Ok(self.hpke.seal_multi(recipients, info, None, aad).await?)
+    }
+
     async fn hpke_open(
         &self,
         ciphertext: &HpkeCiphertext,
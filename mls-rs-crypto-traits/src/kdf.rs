@@ -23,6 +23,29 @@ pub trait KdfType: Send + Sync {
     fn kdf_id(&self) -> u16;
 
     async fn expand(&self, prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, Self::Error>;
+
+    /// Expand `prk` once per element of `infos`, each holding the `info` and
+    /// `len` inputs of a call to [expand](KdfType::expand), and return the
+    /// outputs in the same order.
+    ///
+    /// The default implementation calls [expand](KdfType::expand) for each
+    /// element. Implementations can override it to key HMAC with `prk` once.
+    // automock cannot generate the mock with an elided lifetime in `infos`.
+    #[allow(clippy::needless_lifetimes)]
+    async fn expand_batch<'a>(
+        &self,
+        prk: &[u8],
+        infos: &[(&'a [u8], usize)],
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let mut outputs = Vec::with_capacity(infos.len());
+
+        for (info, len) in infos {
+            outputs.push(self.expand(prk, info, *len).await?);
+        }
+
+        Ok(outputs)
+    }
+
     async fn extract(&self, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, Self::Error>;
     fn extract_size(&self) -> usize;
 }
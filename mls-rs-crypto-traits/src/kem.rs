@@ -32,6 +32,23 @@ pub trait KemType: Send + Sync {
 
     async fn encap(&self, remote_key: &HpkePublicKey) -> Result<KemResult, Self::Error>;
 
+    /// Same as [encap](KemType::encap) with the ephemeral key pair derived from
+    /// `ikm_e` with [derive](KemType::derive), as in the test vectors of RFC 9180.
+    /// Only available with the `test_util` feature, as reusing `ikm_e` breaks the
+    /// security of the KEM.
+    ///
+    /// The default implementation returns `None`, meaning that the KEM cannot
+    /// derandomize encapsulation.
+    #[cfg(feature = "test_util")]
+    async fn encap_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        ikm_e: &[u8],
+    ) -> Result<Option<KemResult>, Self::Error> {
+        let _ = (remote_key, ikm_e);
+        Ok(None)
+    }
+
     async fn decap(
         &self,
         enc: &[u8],
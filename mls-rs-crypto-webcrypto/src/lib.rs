@@ -212,6 +212,33 @@ impl CipherSuiteProvider for WebCryptoCipherSuite {
             .map_err(|e| CryptoError::HpkeError(e.into_any_error()))
     }
 
+    #[cfg(feature = "test_util")]
+    async fn hpke_seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, Self::Error> {
+        self.hpke
+            .seal_derand(remote_key, info, None, aad, pt, ikm_e)
+            .await
+            .map_err(|e| CryptoError::HpkeError(e.into_any_error()))
+    }
+
+    async fn hpke_seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, Self::Error> {
+        self.hpke
+            .seal_multi(recipients, info, None, aad)
+            .await
+            .map_err(|e| CryptoError::HpkeError(e.into_any_error()))
+    }
+
     async fn hpke_open(
         &self,
         ciphertext: &HpkeCiphertext,
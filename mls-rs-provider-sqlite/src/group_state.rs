@@ -2,7 +2,10 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+use mls_rs_core::{
+    group::{EpochRecord, GroupState, GroupStateStorage},
+    time::MlsTime,
+};
 use rusqlite::{params, Connection, OptionalExtension};
 use std::{
     fmt::Debug,
@@ -13,6 +16,8 @@ use crate::SqLiteDataStorageError;
 
 pub(crate) const DEFAULT_EPOCH_RETENTION_LIMIT: u64 = 3;
 
+const NOW: &str = "CAST(strftime('%s', 'now') AS INTEGER)";
+
 #[derive(Debug, Clone)]
 /// SQLite Storage for MLS group states.
 pub struct SqLiteGroupStateStorage {
@@ -105,6 +110,41 @@ impl SqLiteGroupStateStorage {
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
 
+    fn get_snapshot_updated_at(
+        &self,
+        group_id: &[u8],
+    ) -> Result<Option<u64>, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .query_row(
+                "SELECT updated_at FROM mls_group where group_id = ?",
+                [group_id],
+                |row| row.get::<_, Option<u64>>(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+
+    fn get_epoch_updated_at(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<u64>, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .query_row(
+                "SELECT updated_at FROM epoch where group_id = ? AND epoch_id = ?",
+                params![group_id, epoch_id],
+                |row| row.get::<_, Option<u64>>(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+
     fn max_epoch_id(&self, group_id: &[u8]) -> Result<Option<u64>, SqLiteDataStorageError> {
         let connection = self.connection.lock().unwrap();
 
@@ -133,7 +173,7 @@ impl SqLiteGroupStateStorage {
 
         // Upsert into the group table to set the most recent snapshot
         transaction.execute(
-            "INSERT INTO mls_group (group_id, snapshot) VALUES (?, ?) ON CONFLICT(group_id) DO UPDATE SET snapshot=excluded.snapshot",
+            &format!("INSERT INTO mls_group (group_id, snapshot, updated_at) VALUES (?, ?, {NOW}) ON CONFLICT(group_id) DO UPDATE SET snapshot=excluded.snapshot, updated_at=excluded.updated_at"),
             params![group_id, group_snapshot],
         ).map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
 
@@ -143,7 +183,7 @@ impl SqLiteGroupStateStorage {
 
             transaction
                 .execute(
-                    "INSERT INTO epoch (group_id, epoch_id, epoch_data) VALUES (?, ?, ?)",
+                    &format!("INSERT INTO epoch (group_id, epoch_id, epoch_data, updated_at) VALUES (?, ?, ?, {NOW})"),
                     params![group_id, epoch.id, epoch.data],
                 )
                 .map(|_| ())
@@ -154,7 +194,7 @@ impl SqLiteGroupStateStorage {
         updates.into_iter().try_for_each(|epoch| {
             transaction
                 .execute(
-                    "UPDATE epoch SET epoch_data = ? WHERE group_id = ? AND epoch_id = ?",
+                    &format!("UPDATE epoch SET epoch_data = ?, updated_at = {NOW} WHERE group_id = ? AND epoch_id = ?"),
                     params![epoch.data, group_id, epoch.id],
                 )
                 .map(|_| ())
@@ -180,6 +220,43 @@ impl SqLiteGroupStateStorage {
             .commit()
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
+
+    fn compact_group(
+        &self,
+        group_id: &[u8],
+        delete_epoch_under: Option<u64>,
+    ) -> Result<u64, SqLiteDataStorageError> {
+        let Some(delete_under) = delete_epoch_under else {
+            return Ok(0);
+        };
+
+        let mut connection = self.connection.lock().unwrap();
+
+        let transaction = connection
+            .transaction()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let deleted_bytes = transaction
+            .query_row(
+                "SELECT COALESCE(SUM(LENGTH(epoch_data)), 0) FROM epoch WHERE group_id = ? AND epoch_id < ?",
+                params![group_id, delete_under],
+                |row| row.get::<_, u64>(0),
+            )
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        transaction
+            .execute(
+                "DELETE FROM epoch WHERE group_id = ? AND epoch_id < ?",
+                params![group_id, delete_under],
+            )
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        transaction
+            .commit()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        Ok(deleted_bytes)
+    }
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -210,6 +287,32 @@ impl GroupStateStorage for SqLiteGroupStateStorage {
     async fn epoch(&self, group_id: &[u8], epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
         self.get_epoch_data(group_id, epoch_id)
     }
+
+    async fn compact(
+        &mut self,
+        group_id: &[u8],
+        delete_epoch_under: Option<u64>,
+    ) -> Result<u64, Self::Error> {
+        self.compact_group(group_id, delete_epoch_under)
+    }
+
+    async fn group_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        self.group_ids().map(Some)
+    }
+
+    async fn state_updated_at(&self, group_id: &[u8]) -> Result<Option<MlsTime>, Self::Error> {
+        self.get_snapshot_updated_at(group_id)
+            .map(|time| time.map(MlsTime::from))
+    }
+
+    async fn epoch_updated_at(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<MlsTime>, Self::Error> {
+        self.get_epoch_updated_at(group_id, epoch_id)
+            .map(|time| time.map(MlsTime::from))
+    }
 }
 
 #[cfg(test)]
@@ -399,6 +502,44 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn compact_deletes_old_epochs() {
+        let test_data = setup_group_storage_test();
+
+        test_data
+            .storage
+            .update_group_state(
+                &test_data.group_id,
+                test_snapshot(),
+                vec![test_epoch(1), test_epoch(2)],
+                vec![],
+            )
+            .unwrap();
+
+        let deleted_bytes = test_data
+            .storage
+            .compact_group(&test_data.group_id, Some(2))
+            .unwrap();
+
+        assert_eq!(deleted_bytes, 2 * test_data.epoch_0.data.len() as u64);
+
+        for (epoch_id, retained) in [(0, false), (1, false), (2, true)] {
+            let stored = test_data
+                .storage
+                .get_epoch_data(&test_data.group_id, epoch_id)
+                .unwrap();
+
+            assert_eq!(stored.is_some(), retained);
+        }
+
+        let snapshot = test_data
+            .storage
+            .get_snapshot_data(&test_data.group_id)
+            .unwrap();
+
+        assert!(snapshot.is_some());
+    }
+
     #[test]
     fn max_epoch_is_none_for_non_persisted_group() {
         let storage = get_test_storage();
@@ -47,6 +47,36 @@ impl SqLiteKeyPackageStorage {
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
 
+    fn insert_batch(
+        &mut self,
+        key_packages: Vec<(Vec<u8>, KeyPackageData)>,
+    ) -> Result<(), SqLiteDataStorageError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let transaction = connection
+            .transaction()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        for (id, key_package) in key_packages {
+            transaction
+                .execute(
+                    "INSERT INTO key_package (id, expiration, data) VALUES (?,?,?)",
+                    params![
+                        id,
+                        key_package.expiration,
+                        key_package
+                            .mls_encode_to_vec()
+                            .map_err(|e| SqLiteDataStorageError::DataConversionError(e.into()))?
+                    ],
+                )
+                .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+        }
+
+        transaction
+            .commit()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
+
     fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, SqLiteDataStorageError> {
         let connection = self.connection.lock().unwrap();
 
@@ -75,6 +105,41 @@ impl SqLiteKeyPackageStorage {
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
 
+    /// Ids of all stored key packages.
+    pub fn key_package_ids(&self) -> Result<Vec<Vec<u8>>, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        let mut statement = connection
+            .prepare("SELECT id FROM key_package")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let ids = statement
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()));
+
+        ids
+    }
+
+    /// Ids of the key packages expiring before `time`, in seconds since the
+    /// Unix epoch.
+    pub fn expired_before(&self, time: u64) -> Result<Vec<Vec<u8>>, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        let mut statement = connection
+            .prepare("SELECT id FROM key_package WHERE expiration < ?")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let ids = statement
+            .query_map(params![time], |row| row.get::<_, Vec<u8>>(0))
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()));
+
+        ids
+    }
+
     pub fn delete_expired(&self) -> Result<(), SqLiteDataStorageError> {
         self.delete_expired_by_time(MlsTime::now().seconds_since_epoch())
     }
@@ -101,6 +166,13 @@ impl KeyPackageStorage for SqLiteKeyPackageStorage {
         self.insert(id.as_slice(), pkg)
     }
 
+    async fn insert_batch(
+        &mut self,
+        key_packages: Vec<(Vec<u8>, KeyPackageData)>,
+    ) -> Result<(), Self::Error> {
+        self.insert_batch(key_packages)
+    }
+
     async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error> {
         self.get(id)
     }
@@ -108,6 +180,14 @@ impl KeyPackageStorage for SqLiteKeyPackageStorage {
     async fn delete(&mut self, id: &[u8]) -> Result<(), Self::Error> {
         (*self).delete(id)
     }
+
+    async fn expired_before(&self, time: u64) -> Result<Vec<Vec<u8>>, Self::Error> {
+        self.expired_before(time)
+    }
+
+    async fn key_package_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        self.key_package_ids().map(Some)
+    }
 }
 
 #[cfg(test)]
@@ -166,6 +246,23 @@ mod tests {
         assert_matches!(dupe_res, Err(SqLiteDataStorageError::SqlEngineError(_)));
     }
 
+    #[test]
+    fn failed_batch_insert_stores_nothing() {
+        let mut storage = test_storage();
+        let (first_id, first) = test_key_package();
+        let (second_id, second) = test_key_package();
+
+        let res = storage.insert_batch(vec![
+            (first_id.clone(), first),
+            (second_id.clone(), second.clone()),
+            (second_id.clone(), second),
+        ]);
+
+        assert_matches!(res, Err(SqLiteDataStorageError::SqlEngineError(_)));
+        assert!(storage.get(&first_id).unwrap().is_none());
+        assert!(storage.get(&second_id).unwrap().is_none());
+    }
+
     #[test]
     fn key_package_not_found() {
         let mut storage = test_storage();
@@ -215,4 +312,45 @@ mod tests {
         assert!(storage.get(&data[2].0).unwrap().is_none());
         assert!(storage.get(&data[3].0).unwrap().is_none());
     }
+
+    #[test]
+    fn expired_key_package_ids() {
+        let mut storage = test_storage();
+
+        let data = [1, 15, 30].map(|exp| {
+            let mut kp = test_key_package();
+            kp.1.expiration = exp;
+            kp
+        });
+
+        for (id, data) in &data {
+            storage.insert(id, data.clone()).unwrap();
+        }
+
+        let mut expired = storage.expired_before(30).unwrap();
+        expired.sort();
+
+        let mut expected = vec![data[0].0.clone(), data[1].0.clone()];
+        expected.sort();
+
+        assert_eq!(expired, expected);
+    }
+
+    #[test]
+    fn all_key_package_ids() {
+        let mut storage = test_storage();
+        let mut expected = Vec::new();
+
+        for _ in 0..3 {
+            let (id, data) = test_key_package();
+            storage.insert(&id, data).unwrap();
+            expected.push(id);
+        }
+
+        let mut ids = storage.key_package_ids().unwrap();
+        ids.sort();
+        expected.sort();
+
+        assert_eq!(ids, expected);
+    }
 }
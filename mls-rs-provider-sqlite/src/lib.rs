@@ -83,10 +83,14 @@ where
             .pragma_query_value(None, "user_version", |rows| rows.get::<_, u32>(0))
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
 
-        if current_schema != 1 {
+        if current_schema == 0 {
             create_tables_v1(&connection)?;
         }
 
+        if current_schema < 2 {
+            migrate_tables_v2(&connection)?;
+        }
+
         Ok(connection)
     }
 
@@ -113,6 +117,17 @@ where
     ) -> Result<SqLiteApplicationStorage, SqLiteDataStorageError> {
         Ok(SqLiteApplicationStorage::new(self.create_connection()?))
     }
+
+    /// Rebuild the database file to release the space freed by deleted rows.
+    ///
+    /// Compacting a group only deletes rows, leaving the database file at the
+    /// same size. This maintenance operation rewrites the whole database and
+    /// should be run once after compacting groups rather than per group.
+    pub fn vacuum(&self) -> Result<(), SqLiteDataStorageError> {
+        self.create_connection()?
+            .execute_batch("VACUUM")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+    }
 }
 
 fn create_tables_v1(connection: &Connection) -> Result<(), SqLiteDataStorageError> {
@@ -150,6 +165,19 @@ fn create_tables_v1(connection: &Connection) -> Result<(), SqLiteDataStorageErro
         .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
 }
 
+// Version 2 records when group states and epochs are written, in seconds since the Unix epoch.
+fn migrate_tables_v2(connection: &Connection) -> Result<(), SqLiteDataStorageError> {
+    connection
+        .execute_batch(
+            "BEGIN;
+            ALTER TABLE mls_group ADD COLUMN updated_at INTEGER;
+            ALTER TABLE epoch ADD COLUMN updated_at INTEGER;
+            PRAGMA user_version = 2;
+            COMMIT;",
+        )
+        .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{connection_strategy::MemoryStrategy, SqLiteDataStorageEngine};
@@ -168,6 +196,13 @@ mod tests {
             .pragma_query_value(None, "user_version", |rows| rows.get::<_, u32>(0))
             .unwrap();
 
-        assert_eq!(current_schema, 1);
+        assert_eq!(current_schema, 2);
+    }
+
+    #[test]
+    pub fn vacuum_test() {
+        let database = SqLiteDataStorageEngine::new(MemoryStrategy).unwrap();
+
+        database.vacuum().unwrap();
     }
 }
@@ -60,6 +60,23 @@ impl SqLitePreSharedKeyStorage {
             .map(|_| ())
             .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))
     }
+
+    /// Get the ids of all stored pre-shared keys.
+    pub fn psk_ids(&self) -> Result<Vec<Vec<u8>>, SqLiteDataStorageError> {
+        let connection = self.connection.lock().unwrap();
+
+        let mut statement = connection
+            .prepare("SELECT psk_id FROM psk")
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?;
+
+        let ids = statement
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SqLiteDataStorageError::SqlEngineError(e.into()));
+
+        ids
+    }
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -71,6 +88,11 @@ impl PreSharedKeyStorage for SqLitePreSharedKeyStorage {
         self.get(id)
             .map_err(|e| SqLiteDataStorageError::DataConversionError(e.into()))
     }
+
+    async fn psk_ids(&self) -> Result<Option<Vec<ExternalPskId>>, Self::Error> {
+        self.psk_ids()
+            .map(|ids| Some(ids.into_iter().map(ExternalPskId::new).collect()))
+    }
 }
 
 #[cfg(test)]
@@ -133,4 +155,22 @@ mod tests {
 
         assert!(storage.get(&psk_id).unwrap().is_none());
     }
+
+    #[test]
+    fn test_psk_ids() {
+        let storage = test_storage();
+        let mut expected = Vec::new();
+
+        for _ in 0..3 {
+            let (psk_id, psk) = test_psk();
+            storage.insert(psk_id.clone(), psk).unwrap();
+            expected.push(psk_id);
+        }
+
+        let mut ids = storage.psk_ids().unwrap();
+        ids.sort();
+        expected.sort();
+
+        assert_eq!(ids, expected);
+    }
 }
@@ -0,0 +1,356 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs::{
+    client_builder::{BaseConfig, WithCryptoProvider, WithIdentityProvider},
+    error::{IntoAnyError, MlsError},
+    identity::basic::BasicIdentityProvider,
+    CipherSuite, Client, CryptoProvider, GroupStateStorage,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{basic_signing_identity, DeliveryPolicy, HarnessError, MockDeliveryService, Scenario};
+
+/// Configuration of the clients of a [`ChurnSimulation`].
+pub type ChurnConfig<P> =
+    WithIdentityProvider<BasicIdentityProvider, WithCryptoProvider<P, BaseConfig>>;
+
+/// Distribution of the number of operations of a kind in a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChurnDistribution {
+    /// Always `n` operations.
+    Constant(usize),
+    /// Between `min` and `max` operations, inclusive, with uniform probability.
+    Uniform { min: usize, max: usize },
+    /// `size` operations with probability `per_mille / 1000`, and none otherwise.
+    Burst { per_mille: u32, size: usize },
+}
+
+impl ChurnDistribution {
+    fn sample(&self, rng: &mut StdRng) -> usize {
+        match *self {
+            Self::Constant(n) => n,
+            Self::Uniform { min, max } => rng.gen_range(min..=max.max(min)),
+            Self::Burst { per_mille, size } if rng.gen_ratio(per_mille.min(1000), 1000) => size,
+            Self::Burst { .. } => 0,
+        }
+    }
+}
+
+/// Parameters of a [`ChurnSimulation`].
+///
+/// Joins and leaves are clamped to keep the number of members between
+/// `min_members` and `max_members`.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ChurnProfile {
+    pub joins: ChurnDistribution,
+    pub leaves: ChurnDistribution,
+    /// Commits updating the path of a random member.
+    pub updates: ChurnDistribution,
+    /// Application messages sent by random members.
+    pub messages: ChurnDistribution,
+    pub min_members: usize,
+    pub max_members: usize,
+    /// Seed of the random choices and of the delivery service, so that a
+    /// simulation can be replayed.
+    pub seed: u64,
+}
+
+impl Default for ChurnProfile {
+    fn default() -> Self {
+        Self {
+            joins: ChurnDistribution::Uniform { min: 0, max: 3 },
+            leaves: ChurnDistribution::Uniform { min: 0, max: 3 },
+            updates: ChurnDistribution::Uniform { min: 0, max: 1 },
+            messages: ChurnDistribution::Uniform { min: 0, max: 4 },
+            min_members: 2,
+            max_members: 32,
+            seed: 1,
+        }
+    }
+}
+
+impl ChurnProfile {
+    pub fn new(min_members: usize, max_members: usize) -> Self {
+        Self {
+            min_members,
+            max_members,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_joins(self, joins: ChurnDistribution) -> Self {
+        Self { joins, ..self }
+    }
+
+    pub fn with_leaves(self, leaves: ChurnDistribution) -> Self {
+        Self { leaves, ..self }
+    }
+
+    pub fn with_updates(self, updates: ChurnDistribution) -> Self {
+        Self { updates, ..self }
+    }
+
+    pub fn with_messages(self, messages: ChurnDistribution) -> Self {
+        Self { messages, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// State of the group after a step of a [`ChurnSimulation`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ChurnStats {
+    pub epoch: u64,
+    pub members: usize,
+    /// Number of leaves of the ratchet tree, including blank leaves.
+    pub tree_leaves: u32,
+    pub blank_leaves: u32,
+    /// Largest size of the group state and prior epochs stored by a member.
+    pub max_storage_bytes: usize,
+    /// Total number of joins, leaves and updates since the start.
+    pub joins: usize,
+    pub leaves: usize,
+    pub updates: usize,
+}
+
+/// Group whose members join, leave and update according to a
+/// [`ChurnProfile`], for soak tests.
+///
+/// Each step commits the removal of random members, then the addition of new
+/// members, followed by commits updating the paths of random members and by
+/// application messages, as drawn from the distributions of the profile. All
+/// messages go through the [`MockDeliveryService`] of a [`Scenario`]. After
+/// each step, all members write their state to storage and are checked to be
+/// synchronized. The returned [`ChurnStats`] can be used to detect unbounded
+/// growth of the tree or of the stored state.
+pub struct ChurnSimulation<P: CryptoProvider + Clone> {
+    profile: ChurnProfile,
+    crypto_provider: P,
+    cipher_suite: CipherSuite,
+    scenario: Scenario<ChurnConfig<P>>,
+    next_id: usize,
+    rng: StdRng,
+    stats: ChurnStats,
+}
+
+impl<P: CryptoProvider + Clone> ChurnSimulation<P> {
+    /// Create a group with `profile.min_members` members.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn new(
+        crypto_provider: P,
+        cipher_suite: CipherSuite,
+        profile: ChurnProfile,
+    ) -> Result<Self, HarnessError> {
+        let delivery_service =
+            MockDeliveryService::new(DeliveryPolicy::new().with_seed(profile.seed));
+
+        let mut simulation = Self {
+            rng: StdRng::seed_from_u64(profile.seed),
+            profile,
+            crypto_provider,
+            cipher_suite,
+            scenario: Scenario::new(delivery_service),
+            next_id: 0,
+            stats: Default::default(),
+        };
+
+        let creator = simulation.new_client().await?;
+        simulation.scenario.create_group(&creator).await?;
+
+        let joins = simulation.profile.min_members.saturating_sub(1);
+        simulation.add_members(&creator, joins).await?;
+        simulation.scenario.deliver_all().await?;
+        simulation.stats.joins = 0;
+
+        Ok(simulation)
+    }
+
+    /// Apply `steps` steps and return the stats after each of them.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn run(&mut self, steps: usize) -> Result<Vec<ChurnStats>, HarnessError> {
+        let mut stats = Vec::with_capacity(steps);
+
+        for _ in 0..steps {
+            stats.push(self.step().await?);
+        }
+
+        Ok(stats)
+    }
+
+    /// Apply one step and return the resulting stats.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn step(&mut self) -> Result<ChurnStats, HarnessError> {
+        let members = self.members();
+        let current = members.len();
+        let min_members = self.profile.min_members.max(1);
+
+        let leaves = self
+            .profile
+            .leaves
+            .sample(&mut self.rng)
+            .min(current.saturating_sub(min_members));
+
+        let joins = self
+            .profile
+            .joins
+            .sample(&mut self.rng)
+            .min(self.profile.max_members.saturating_sub(current - leaves));
+
+        let committer = members[self.rng.gen_range(0..current)].clone();
+
+        let others = members
+            .iter()
+            .filter(|member| **member != committer)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        let removed = others
+            .choose_multiple(&mut self.rng, leaves)
+            .copied()
+            .collect::<Vec<_>>();
+
+        if !removed.is_empty() {
+            self.scenario.remove_members(&committer, &removed).await?;
+            self.stats.leaves += removed.len();
+        }
+
+        self.add_members(&committer, joins).await?;
+        self.scenario.deliver_all().await?;
+
+        for _ in 0..self.profile.updates.sample(&mut self.rng) {
+            let member = self.random_member();
+            self.scenario.update(&member).await?;
+            self.scenario.deliver_all().await?;
+            self.stats.updates += 1;
+        }
+
+        for _ in 0..self.profile.messages.sample(&mut self.rng) {
+            let member = self.random_member();
+            self.scenario.send(&member, b"churn").await?;
+        }
+
+        self.scenario.deliver_all().await?;
+
+        for member in self.members() {
+            self.scenario.group_mut(&member)?.write_to_storage().await?;
+        }
+
+        self.scenario.check_synchronized()?;
+        self.update_stats().await?;
+
+        Ok(self.stats.clone())
+    }
+
+    /// Scenario running the simulated group.
+    pub fn scenario(&self) -> &Scenario<ChurnConfig<P>> {
+        &self.scenario
+    }
+
+    fn members(&self) -> Vec<String> {
+        self.scenario
+            .members()
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    fn random_member(&mut self) -> String {
+        let members = self.members();
+        members[self.rng.gen_range(0..members.len())].clone()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn new_client(&mut self) -> Result<String, HarnessError> {
+        let name = format!("churn {}", self.next_id);
+        self.next_id += 1;
+
+        let (signing_identity, secret) =
+            basic_signing_identity(&self.crypto_provider, self.cipher_suite, &name).await?;
+
+        let client = Client::builder()
+            .crypto_provider(self.crypto_provider.clone())
+            .identity_provider(BasicIdentityProvider)
+            .signing_identity(signing_identity, secret, self.cipher_suite)
+            .try_build()
+            .await?;
+
+        self.scenario.add_client(name.clone(), client);
+
+        Ok(name)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn add_members(&mut self, committer: &str, joins: usize) -> Result<(), HarnessError> {
+        if joins == 0 {
+            return Ok(());
+        }
+
+        let mut added = Vec::with_capacity(joins);
+
+        for _ in 0..joins {
+            added.push(self.new_client().await?);
+        }
+
+        let added = added.iter().map(String::as_str).collect::<Vec<_>>();
+        self.scenario.add_members(committer, &added).await?;
+        self.stats.joins += joins;
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn update_stats(&mut self) -> Result<(), HarnessError> {
+        let members = self.members();
+        let report = self.scenario.group(&members[0])?.state_report().await?;
+
+        self.stats.epoch = report.epoch;
+        self.stats.members = members.len();
+        self.stats.tree_leaves = report.tree.total_leaf_count;
+        self.stats.blank_leaves = report.tree.total_leaf_count - report.tree.leaves.len() as u32;
+
+        let mut max_storage_bytes = 0;
+
+        for member in members.iter() {
+            let storage = self.scenario.client(member)?.group_state_storage();
+            let group_id = self.scenario.group(member)?.group_id();
+            let bytes = stored_bytes(&storage, group_id).await?;
+            max_storage_bytes = max_storage_bytes.max(bytes);
+        }
+
+        self.stats.max_storage_bytes = max_storage_bytes;
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn stored_bytes<S: GroupStateStorage>(
+    storage: &S,
+    group_id: &[u8],
+) -> Result<usize, HarnessError> {
+    let storage_error = |e: S::Error| MlsError::GroupStorageError(e.into_any_error());
+
+    let state = storage.state(group_id).await.map_err(storage_error)?;
+    let mut bytes = state.unwrap_or_default().len();
+    let mut epoch_id = storage
+        .max_epoch_id(group_id)
+        .await
+        .map_err(storage_error)?;
+
+    while let Some(id) = epoch_id {
+        let Some(epoch) = storage.epoch(group_id, id).await.map_err(storage_error)? else {
+            break;
+        };
+
+        bytes += epoch.len();
+        epoch_id = id.checked_sub(1);
+    }
+
+    Ok(bytes)
+}
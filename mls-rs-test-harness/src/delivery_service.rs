@@ -0,0 +1,338 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Debug},
+};
+
+use mls_rs::{group::ExportedTree, MlsMessage};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Kind of a message routed by a [`MockDeliveryService`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    /// Commit or proposal.
+    Handshake,
+    /// Application message.
+    Application,
+    /// Welcome message sent to a new member together with the ratchet tree.
+    Welcome,
+}
+
+/// Message queued for a single recipient.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Envelope {
+    pub sender: String,
+    pub kind: MessageKind,
+    pub message: MlsMessage,
+    /// Ratchet tree of the group, sent along with welcome messages.
+    pub ratchet_tree: Option<ExportedTree<'static>>,
+}
+
+/// Fate of a message decided by a rule of a [`MockDeliveryService`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fate {
+    Deliver,
+    Drop,
+}
+
+/// Faults introduced by a [`MockDeliveryService`].
+///
+/// Faults apply to application messages only unless
+/// [`faulty_handshake`](Self::faulty_handshake) is set. Group members lose
+/// track of the group if a commit they should receive is dropped, so faulty
+/// handshake delivery is meant for testing how applications recover. Welcome
+/// messages are always delivered.
+///
+/// A reordered message overtakes some of the application messages queued
+/// before it. Handshake messages are never overtaken, which keeps the order
+/// of epochs intact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct DeliveryPolicy {
+    /// Probability in `[0, 1]` that a message is dropped.
+    pub drop_probability: f64,
+    /// Probability in `[0, 1]` that a message is reordered.
+    pub reorder_probability: f64,
+    /// Whether drops and reordering apply to handshake messages.
+    pub faulty_handshake: bool,
+    /// Seed of the random choices, making runs reproducible.
+    pub seed: u64,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            drop_probability: 0.0,
+            reorder_probability: 0.0,
+            faulty_handshake: false,
+            seed: 0,
+        }
+    }
+}
+
+impl DeliveryPolicy {
+    /// Create a policy delivering all messages in order.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_drop_probability(self, drop_probability: f64) -> Self {
+        Self {
+            drop_probability,
+            ..self
+        }
+    }
+
+    pub fn with_reorder_probability(self, reorder_probability: f64) -> Self {
+        Self {
+            reorder_probability,
+            ..self
+        }
+    }
+
+    pub fn with_faulty_handshake(self, faulty_handshake: bool) -> Self {
+        Self {
+            faulty_handshake,
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// Number of messages handled by a [`MockDeliveryService`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeliveryStats {
+    pub queued: usize,
+    pub dropped: usize,
+    pub reordered: usize,
+    pub rejected_commits: usize,
+}
+
+type Rule = Box<dyn FnMut(&str, &Envelope) -> Option<Fate> + Send>;
+
+/// In-memory delivery service of a single group.
+///
+/// Every group member has an inbox of messages in the order they are to be
+/// processed. The first commit submitted for an epoch is accepted and all
+/// other commits for that epoch are rejected, as a delivery service
+/// sequencing commits would do.
+pub struct MockDeliveryService {
+    policy: DeliveryPolicy,
+    rng: StdRng,
+    rule: Option<Rule>,
+    epoch: u64,
+    members: BTreeSet<String>,
+    inboxes: BTreeMap<String, VecDeque<Envelope>>,
+    stats: DeliveryStats,
+}
+
+impl Debug for MockDeliveryService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockDeliveryService")
+            .field("policy", &self.policy)
+            .field("epoch", &self.epoch)
+            .field("members", &self.members)
+            .field("inboxes", &self.inboxes)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl Default for MockDeliveryService {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl MockDeliveryService {
+    /// Create a delivery service applying `policy`. Probabilities outside
+    /// `[0, 1]` are clamped to that range, and NaN is treated as `0`.
+    pub fn new(policy: DeliveryPolicy) -> Self {
+        let policy = DeliveryPolicy {
+            drop_probability: clamp_probability(policy.drop_probability),
+            reorder_probability: clamp_probability(policy.reorder_probability),
+            ..policy
+        };
+
+        Self {
+            rng: StdRng::seed_from_u64(policy.seed),
+            policy,
+            rule: None,
+            epoch: 0,
+            members: Default::default(),
+            inboxes: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn policy(&self) -> &DeliveryPolicy {
+        &self.policy
+    }
+
+    pub fn stats(&self) -> DeliveryStats {
+        self.stats
+    }
+
+    /// Epoch of the last accepted commit.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Decide the fate of messages before the policy is applied. Messages
+    /// for which `rule` returns `None` are subject to the policy.
+    pub fn set_rule<F>(&mut self, rule: F)
+    where
+        F: FnMut(&str, &Envelope) -> Option<Fate> + Send + 'static,
+    {
+        self.rule = Some(Box::new(rule));
+    }
+
+    pub fn clear_rule(&mut self) {
+        self.rule = None;
+    }
+
+    /// Start routing group messages to `member`.
+    pub fn join(&mut self, member: &str, epoch: u64) {
+        self.members.insert(member.to_owned());
+        self.epoch = self.epoch.max(epoch);
+    }
+
+    /// Stop routing group messages to `member`. Messages already queued
+    /// remain in its inbox.
+    pub fn leave(&mut self, member: &str) {
+        self.members.remove(member);
+    }
+
+    /// Number of messages queued for `recipient`.
+    pub fn pending(&self, recipient: &str) -> usize {
+        self.inboxes.get(recipient).map_or(0, VecDeque::len)
+    }
+
+    /// Recipients with at least one queued message.
+    pub fn recipients_with_pending(&self) -> Vec<String> {
+        self.inboxes
+            .iter()
+            .filter(|(_, inbox)| !inbox.is_empty())
+            .map(|(recipient, _)| recipient.clone())
+            .collect()
+    }
+
+    /// Remove the next message queued for `recipient`.
+    pub fn take(&mut self, recipient: &str) -> Option<Envelope> {
+        self.inboxes.get_mut(recipient)?.pop_front()
+    }
+
+    /// Accept `commit` created by `sender` in `epoch` if no other commit was
+    /// accepted for that epoch, and route it to all other members.
+    pub fn sequence_commit(&mut self, sender: &str, epoch: u64, commit: MlsMessage) -> bool {
+        if epoch != self.epoch {
+            self.stats.rejected_commits += 1;
+            return false;
+        }
+
+        self.epoch += 1;
+        self.broadcast(sender, MessageKind::Handshake, commit);
+
+        true
+    }
+
+    /// Route `message` to all members other than `sender`.
+    pub fn broadcast(&mut self, sender: &str, kind: MessageKind, message: MlsMessage) {
+        let recipients = self
+            .members
+            .iter()
+            .filter(|member| *member != sender)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for recipient in recipients {
+            let envelope = Envelope {
+                sender: sender.to_owned(),
+                kind,
+                message: message.clone(),
+                ratchet_tree: None,
+            };
+
+            self.route(&recipient, envelope);
+        }
+    }
+
+    /// Route a welcome message to `recipient`, which becomes a member.
+    pub fn send_welcome(
+        &mut self,
+        sender: &str,
+        recipient: &str,
+        welcome: MlsMessage,
+        ratchet_tree: ExportedTree<'static>,
+    ) {
+        self.members.insert(recipient.to_owned());
+
+        let envelope = Envelope {
+            sender: sender.to_owned(),
+            kind: MessageKind::Welcome,
+            message: welcome,
+            ratchet_tree: Some(ratchet_tree),
+        };
+
+        self.route(recipient, envelope);
+    }
+
+    fn route(&mut self, recipient: &str, envelope: Envelope) {
+        let faulty = match envelope.kind {
+            MessageKind::Application => true,
+            MessageKind::Handshake => self.policy.faulty_handshake,
+            MessageKind::Welcome => false,
+        };
+
+        let fate = match self
+            .rule
+            .as_mut()
+            .and_then(|rule| rule(recipient, &envelope))
+        {
+            Some(fate) => fate,
+            None if faulty && self.rng.gen_bool(self.policy.drop_probability) => Fate::Drop,
+            None => Fate::Deliver,
+        };
+
+        if fate == Fate::Drop {
+            self.stats.dropped += 1;
+            return;
+        }
+
+        let inbox = self.inboxes.entry(recipient.to_owned()).or_default();
+
+        let overtaken = if faulty && self.rng.gen_bool(self.policy.reorder_probability) {
+            let overtakable = inbox
+                .iter()
+                .rev()
+                .take_while(|queued| queued.kind == MessageKind::Application)
+                .count();
+
+            (overtakable > 0).then(|| self.rng.gen_range(1..=overtakable))
+        } else {
+            None
+        };
+
+        let position = inbox.len() - overtaken.unwrap_or_default();
+        inbox.insert(position, envelope);
+
+        self.stats.queued += 1;
+        self.stats.reordered += usize::from(overtaken.is_some());
+    }
+}
+
+fn clamp_probability(probability: f64) -> f64 {
+    if probability.is_nan() {
+        0.0
+    } else {
+        probability.clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs::error::MlsError;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HarnessError {
+    #[error(transparent)]
+    MlsError(#[from] MlsError),
+    #[error("unknown client {0}")]
+    UnknownClient(String),
+    #[error("{0} is not a member of the group")]
+    NotAMember(String),
+    #[error("{0} is already a member of the group")]
+    AlreadyAMember(String),
+    #[error("group state of {1} differs from the group state of {0}")]
+    NotSynchronized(String, String),
+}
@@ -0,0 +1,346 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Simulated clients and delivery service for integration tests of
+//! applications built on [mls-rs](mls_rs).
+//!
+//! A [`Scenario`] drives a single group shared by any number of clients
+//! configured by the application. Handshake and application messages are
+//! routed through a [`MockDeliveryService`] that sequences commits like a
+//! real delivery service and can drop or reorder messages according to a
+//! [`DeliveryPolicy`].
+//!
+//! ```ignore
+//! let mut scenario = Scenario::new(MockDeliveryService::new(
+//!     DeliveryPolicy::new().with_reorder_probability(0.5),
+//! ));
+//!
+//! for name in ["alice", "bob", "carol"] {
+//!     scenario.add_client(name, basic_client(crypto.clone(), cipher_suite, name)?);
+//! }
+//!
+//! scenario.run([
+//!     Step::create_group("alice"),
+//!     Step::add("alice", ["bob", "carol"]),
+//!     Step::DeliverAll,
+//!     Step::send("bob", b"hello"),
+//!     Step::update("carol"),
+//!     Step::DeliverAll,
+//!     Step::CheckSynchronized,
+//! ])?;
+//! ```
+//!
+//! [`TreeWithSigners`] builds fully populated ratchet trees of any size for
+//! tests that need a tree rather than a running group.
+//!
+//! [`ChurnSimulation`] runs a scenario whose members keep joining, leaving
+//! and updating at random, for soak tests of long-lived groups.
+
+mod churn;
+mod delivery_service;
+mod error;
+mod scenario;
+mod tree;
+
+pub use churn::{ChurnConfig, ChurnDistribution, ChurnProfile, ChurnSimulation, ChurnStats};
+pub use delivery_service::{
+    DeliveryPolicy, DeliveryStats, Envelope, Fate, MessageKind, MockDeliveryService,
+};
+pub use error::HarnessError;
+pub use scenario::{CommitOutcome, Scenario, Step};
+pub use tree::TreeWithSigners;
+
+use mls_rs::{
+    client_builder::MlsConfig,
+    crypto::SignatureSecretKey,
+    error::{IntoAnyError, MlsError},
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider,
+};
+
+/// Create a client identified by a basic credential containing `name`.
+///
+/// Applications testing their own configuration build clients with
+/// [`Client::builder`] instead and pass them to [`Scenario::add_client`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn basic_client<P: CryptoProvider + Clone>(
+    crypto_provider: P,
+    cipher_suite: CipherSuite,
+    name: &str,
+) -> Result<Client<impl MlsConfig>, HarnessError> {
+    let (signing_identity, secret) =
+        basic_signing_identity(&crypto_provider, cipher_suite, name).await?;
+
+    Ok(Client::builder()
+        .identity_provider(BasicIdentityProvider)
+        .crypto_provider(crypto_provider)
+        .signing_identity(signing_identity, secret, cipher_suite)
+        .try_build()
+        .await?)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn basic_signing_identity<P: CryptoProvider>(
+    crypto_provider: &P,
+    cipher_suite: CipherSuite,
+    name: &str,
+) -> Result<(SigningIdentity, SignatureSecretKey), HarnessError> {
+    let cipher_suite_provider = crypto_provider
+        .cipher_suite_provider(cipher_suite)
+        .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+
+    let (secret, public) = cipher_suite_provider
+        .signature_key_generate()
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    let credential = BasicCredential::new(name.as_bytes().to_vec()).into_credential();
+
+    Ok((SigningIdentity::new(credential, public), secret))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs::{client_builder::MlsConfig, group::ExportedTree, CipherSuite, Client};
+    use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+    use crate::{
+        basic_client, ChurnDistribution, ChurnProfile, ChurnSimulation, ChurnStats, CommitOutcome,
+        DeliveryPolicy, Fate, HarnessError, MessageKind, MockDeliveryService, Scenario, Step,
+        TreeWithSigners,
+    };
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn client(name: &str) -> Client<impl MlsConfig> {
+        basic_client(OpensslCryptoProvider::default(), CIPHER_SUITE, name)
+            .await
+            .unwrap()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn scenario(policy: DeliveryPolicy, names: &[&str]) -> Scenario<impl MlsConfig> {
+        let mut scenario = Scenario::new(MockDeliveryService::new(policy));
+
+        for name in names {
+            scenario.add_client(*name, client(name).await);
+        }
+
+        scenario
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn members_stay_synchronized() {
+        let names = ["alice", "bob", "carol", "dave"];
+        let mut scenario = scenario(DeliveryPolicy::new(), &names).await;
+
+        scenario
+            .run([
+                Step::create_group("alice"),
+                Step::add("alice", ["bob", "carol"]),
+                Step::DeliverAll,
+                Step::send("bob", b"hello"),
+                Step::update("carol"),
+                Step::DeliverAll,
+                Step::add("carol", ["dave"]),
+                Step::DeliverAll,
+                Step::remove("dave", ["bob"]),
+                Step::DeliverAll,
+                Step::CheckSynchronized,
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(scenario.members(), ["alice", "carol", "dave"]);
+        assert_eq!(scenario.received("alice").unwrap()[0].data(), b"hello");
+        assert_eq!(scenario.group("dave").unwrap().current_epoch(), 4);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn concurrent_commits_are_sequenced() {
+        let mut scenario = scenario(DeliveryPolicy::new(), &["alice", "bob"]).await;
+
+        scenario
+            .run([
+                Step::create_group("alice"),
+                Step::add("alice", ["bob"]),
+                Step::DeliverAll,
+            ])
+            .await
+            .unwrap();
+
+        let first = scenario.update("bob").await.unwrap();
+        let second = scenario.update("alice").await.unwrap();
+
+        assert_eq!(first, CommitOutcome::Accepted);
+        assert_eq!(second, CommitOutcome::Rejected);
+
+        scenario.deliver_all().await.unwrap();
+        scenario.check_synchronized().unwrap();
+        assert!(!scenario.group("alice").unwrap().has_pending_commit());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn reordered_and_dropped_application_messages_are_tolerated() {
+        let policy = DeliveryPolicy::new()
+            .with_drop_probability(0.2)
+            .with_reorder_probability(0.5)
+            .with_seed(7);
+
+        let mut scenario = scenario(policy, &["alice", "bob", "carol"]).await;
+
+        scenario
+            .run([
+                Step::create_group("alice"),
+                Step::add("alice", ["bob", "carol"]),
+                Step::DeliverAll,
+            ])
+            .await
+            .unwrap();
+
+        for i in 0..20u8 {
+            scenario.send("alice", &[i]).await.unwrap();
+
+            if i % 5 == 4 {
+                scenario.update("bob").await.unwrap();
+            }
+        }
+
+        scenario.deliver_all().await.unwrap();
+        scenario.check_synchronized().unwrap();
+
+        let stats = scenario.delivery_service().stats();
+        let received = scenario.received("carol").unwrap();
+
+        assert!(stats.dropped > 0 && stats.reordered > 0);
+        assert!(received.len() < 20);
+        assert!(received.windows(2).any(|w| w[0].data() > w[1].data()));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn dropped_commit_desynchronizes_recipient() {
+        let mut scenario = scenario(DeliveryPolicy::new(), &["alice", "bob"]).await;
+
+        scenario
+            .run([
+                Step::create_group("alice"),
+                Step::add("alice", ["bob"]),
+                Step::DeliverAll,
+            ])
+            .await
+            .unwrap();
+
+        scenario
+            .delivery_service_mut()
+            .set_rule(|recipient, envelope| {
+                (recipient == "bob" && envelope.kind == MessageKind::Handshake)
+                    .then_some(Fate::Drop)
+            });
+
+        scenario.update("alice").await.unwrap();
+        scenario.deliver_all().await.unwrap();
+
+        assert_matches!(
+            scenario.check_synchronized(),
+            Err(HarnessError::NotSynchronized(..))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn full_tree_has_a_signer_per_leaf() {
+        let full_tree =
+            TreeWithSigners::make_full_tree(OpensslCryptoProvider::default(), CIPHER_SUITE, 5)
+                .await
+                .unwrap();
+
+        assert_eq!(full_tree.signers.len(), 5);
+
+        let bytes = full_tree.tree.to_bytes().unwrap();
+        assert_eq!(ExportedTree::from_bytes(&bytes).unwrap(), full_tree.tree);
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn churn(profile: ChurnProfile) -> ChurnSimulation<OpensslCryptoProvider> {
+        ChurnSimulation::new(OpensslCryptoProvider::default(), CIPHER_SUITE, profile)
+            .await
+            .unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn churn_keeps_members_within_bounds() {
+        let profile = ChurnProfile::new(3, 8).with_joins(ChurnDistribution::Constant(3));
+        let mut simulation = churn(profile).await;
+
+        let stats = simulation.run(12).await.unwrap();
+
+        assert!(stats.iter().all(|s| (3..=8).contains(&s.members)));
+
+        let last = stats.last().unwrap();
+
+        assert!(last.joins > 0 && last.leaves > 0);
+        assert_eq!(last.members, simulation.scenario().members().len());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn churn_is_reproducible() {
+        let profile = ChurnProfile::new(2, 6).with_seed(7);
+
+        let mut first = churn(profile.clone()).await;
+        let mut second = churn(profile).await;
+
+        let shape = |stats: Vec<ChurnStats>| {
+            stats
+                .into_iter()
+                .map(|s| (s.epoch, s.members, s.joins, s.leaves, s.updates))
+                .collect::<Vec<_>>()
+        };
+
+        let first = shape(first.run(5).await.unwrap());
+        let second = shape(second.run(5).await.unwrap());
+
+        assert_eq!(first, second);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test::test))]
+    async fn storage_and_tree_stay_bounded_under_churn() {
+        let profile = ChurnProfile::new(4, 12).with_updates(ChurnDistribution::Burst {
+            per_mille: 500,
+            size: 2,
+        });
+
+        let mut simulation = churn(profile).await;
+        let stats = simulation.run(40).await.unwrap();
+
+        // Trailing blank leaves are truncated
+        assert!(stats.iter().all(|s| s.tree_leaves as usize <= 2 * 12));
+
+        // Storage keeps a bounded number of prior epochs per member
+        let bytes_per_member = |stats: &[ChurnStats]| {
+            stats
+                .iter()
+                .map(|s| s.max_storage_bytes / s.members)
+                .max()
+                .unwrap()
+        };
+
+        assert!(bytes_per_member(&stats[30..]) <= 2 * bytes_per_member(&stats[..10]));
+    }
+
+    #[test]
+    fn invalid_probabilities_are_clamped() {
+        let policy = DeliveryPolicy::new()
+            .with_drop_probability(f64::NAN)
+            .with_reorder_probability(2.0);
+
+        let delivery_service = MockDeliveryService::new(policy);
+
+        assert_eq!(delivery_service.policy().drop_probability, 0.0);
+        assert_eq!(delivery_service.policy().reorder_probability, 1.0);
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use std::collections::BTreeMap;
+
+use mls_rs::{
+    client_builder::MlsConfig,
+    group::{ApplicationMessageDescription, ReceivedMessage},
+    Client, ExtensionList, Group, MlsMessage,
+};
+
+use crate::{HarnessError, MessageKind, MockDeliveryService};
+
+/// Result of submitting a commit to the [`MockDeliveryService`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitOutcome {
+    /// The commit was applied by the committer and routed to other members.
+    Accepted,
+    /// Another commit was accepted for the same epoch. The committer
+    /// discarded its pending commit.
+    Rejected,
+}
+
+/// Action of a [`Scenario`] run by [`Scenario::run`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Step {
+    CreateGroup {
+        creator: String,
+    },
+    Add {
+        committer: String,
+        members: Vec<String>,
+    },
+    Remove {
+        committer: String,
+        members: Vec<String>,
+    },
+    Update {
+        member: String,
+    },
+    Send {
+        sender: String,
+        data: Vec<u8>,
+    },
+    Deliver {
+        recipient: String,
+    },
+    DeliverAll,
+    CheckSynchronized,
+}
+
+impl Step {
+    pub fn create_group(creator: &str) -> Self {
+        Self::CreateGroup {
+            creator: creator.to_owned(),
+        }
+    }
+
+    pub fn add<'a>(committer: &str, members: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::Add {
+            committer: committer.to_owned(),
+            members: members.into_iter().map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn remove<'a>(committer: &str, members: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::Remove {
+            committer: committer.to_owned(),
+            members: members.into_iter().map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn update(member: &str) -> Self {
+        Self::Update {
+            member: member.to_owned(),
+        }
+    }
+
+    pub fn send(sender: &str, data: &[u8]) -> Self {
+        Self::Send {
+            sender: sender.to_owned(),
+            data: data.to_vec(),
+        }
+    }
+
+    pub fn deliver(recipient: &str) -> Self {
+        Self::Deliver {
+            recipient: recipient.to_owned(),
+        }
+    }
+}
+
+struct Participant<C: MlsConfig> {
+    client: Client<C>,
+    group: Option<Group<C>>,
+    received: Vec<ApplicationMessageDescription>,
+}
+
+/// Clients sharing a group through a [`MockDeliveryService`].
+///
+/// Clients are identified by the name they were added with. Messages sent
+/// by a client are queued by the delivery service and processed by their
+/// recipients on [`deliver`](Self::deliver) or
+/// [`deliver_all`](Self::deliver_all).
+pub struct Scenario<C: MlsConfig> {
+    participants: BTreeMap<String, Participant<C>>,
+    delivery_service: MockDeliveryService,
+}
+
+impl<C: MlsConfig> Scenario<C> {
+    pub fn new(delivery_service: MockDeliveryService) -> Self {
+        Self {
+            participants: Default::default(),
+            delivery_service,
+        }
+    }
+
+    /// Add a client that can later create or be added to the group.
+    pub fn add_client(&mut self, name: impl Into<String>, client: Client<C>) {
+        let participant = Participant {
+            client,
+            group: None,
+            received: Vec::new(),
+        };
+
+        self.participants.insert(name.into(), participant);
+    }
+
+    pub fn delivery_service(&self) -> &MockDeliveryService {
+        &self.delivery_service
+    }
+
+    pub fn delivery_service_mut(&mut self) -> &mut MockDeliveryService {
+        &mut self.delivery_service
+    }
+
+    /// Names of the clients that are currently members of the group.
+    pub fn members(&self) -> Vec<&str> {
+        self.participants
+            .iter()
+            .filter(|(_, participant)| participant.group.is_some())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn client(&self, name: &str) -> Result<&Client<C>, HarnessError> {
+        Ok(&self.participant(name)?.client)
+    }
+
+    pub fn group(&self, name: &str) -> Result<&Group<C>, HarnessError> {
+        self.participant(name)?
+            .group
+            .as_ref()
+            .ok_or_else(|| HarnessError::NotAMember(name.to_owned()))
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Result<&mut Group<C>, HarnessError> {
+        self.participant_mut(name)?
+            .group
+            .as_mut()
+            .ok_or_else(|| HarnessError::NotAMember(name.to_owned()))
+    }
+
+    /// Application messages received by `name` in the order they were
+    /// processed.
+    pub fn received(&self, name: &str) -> Result<&[ApplicationMessageDescription], HarnessError> {
+        Ok(&self.participant(name)?.received)
+    }
+
+    /// Run `steps` in order, stopping at the first error. Rejected commits
+    /// are not errors.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn run(&mut self, steps: impl IntoIterator<Item = Step>) -> Result<(), HarnessError> {
+        for step in steps {
+            match step {
+                Step::CreateGroup { creator } => self.create_group(&creator).await?,
+                Step::Add { committer, members } => {
+                    let members = members.iter().map(String::as_str).collect::<Vec<_>>();
+                    self.add_members(&committer, &members).await?;
+                }
+                Step::Remove { committer, members } => {
+                    let members = members.iter().map(String::as_str).collect::<Vec<_>>();
+                    self.remove_members(&committer, &members).await?;
+                }
+                Step::Update { member } => {
+                    self.update(&member).await?;
+                }
+                Step::Send { sender, data } => self.send(&sender, &data).await?,
+                Step::Deliver { recipient } => {
+                    self.deliver(&recipient).await?;
+                }
+                Step::DeliverAll => {
+                    self.deliver_all().await?;
+                }
+                Step::CheckSynchronized => self.check_synchronized()?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create the group with `creator` as its only member.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn create_group(&mut self, creator: &str) -> Result<(), HarnessError> {
+        let participant = self.participant_mut(creator)?;
+
+        if participant.group.is_some() {
+            return Err(HarnessError::AlreadyAMember(creator.to_owned()));
+        }
+
+        let group = participant
+            .client
+            .create_group(ExtensionList::default())
+            .await?;
+
+        let epoch = group.current_epoch();
+        participant.group = Some(group);
+        self.delivery_service.join(creator, epoch);
+
+        Ok(())
+    }
+
+    /// Commit adding `members` on behalf of `committer`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn add_members(
+        &mut self,
+        committer: &str,
+        members: &[&str],
+    ) -> Result<CommitOutcome, HarnessError> {
+        let mut key_packages = Vec::new();
+
+        for member in members {
+            let participant = self.participant(member)?;
+
+            if participant.group.is_some() {
+                return Err(HarnessError::AlreadyAMember(member.to_string()));
+            }
+
+            key_packages.push(participant.client.generate_key_package_message().await?);
+        }
+
+        self.commit(committer, key_packages, members, &[]).await
+    }
+
+    /// Commit removing `members` on behalf of `committer`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn remove_members(
+        &mut self,
+        committer: &str,
+        members: &[&str],
+    ) -> Result<CommitOutcome, HarnessError> {
+        self.commit(committer, Vec::new(), &[], members).await
+    }
+
+    /// Commit updating the leaf of `member`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn update(&mut self, member: &str) -> Result<CommitOutcome, HarnessError> {
+        self.commit(member, Vec::new(), &[], &[]).await
+    }
+
+    /// Send an application message containing `data` from `sender`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn send(&mut self, sender: &str, data: &[u8]) -> Result<(), HarnessError> {
+        let message = self
+            .group_mut(sender)?
+            .encrypt_application_message(data, Vec::new())
+            .await?;
+
+        self.delivery_service
+            .broadcast(sender, MessageKind::Application, message);
+
+        Ok(())
+    }
+
+    /// Process all messages queued for `recipient` and return their number.
+    ///
+    /// Messages other than welcome messages received by a client that is not
+    /// a member of the group are discarded.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn deliver(&mut self, recipient: &str) -> Result<usize, HarnessError> {
+        let mut delivered = 0;
+
+        while let Some(envelope) = self.delivery_service.take(recipient) {
+            delivered += 1;
+
+            let participant = self
+                .participants
+                .get_mut(recipient)
+                .ok_or_else(|| HarnessError::UnknownClient(recipient.to_owned()))?;
+
+            if envelope.kind == MessageKind::Welcome {
+                let (group, _) = participant
+                    .client
+                    .join_group(envelope.ratchet_tree, &envelope.message)
+                    .await?;
+
+                participant.group = Some(group);
+                continue;
+            }
+
+            let Some(group) = participant.group.as_mut() else {
+                continue;
+            };
+
+            match group.process_incoming_message(envelope.message).await? {
+                ReceivedMessage::ApplicationMessage(message) => participant.received.push(message),
+                ReceivedMessage::Commit(commit) if !commit.state_update.is_active() => {
+                    participant.group = None
+                }
+                _ => {}
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Process queued messages until all inboxes are empty and return the
+    /// number of processed messages.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn deliver_all(&mut self) -> Result<usize, HarnessError> {
+        let mut delivered = 0;
+
+        loop {
+            let recipients = self.delivery_service.recipients_with_pending();
+
+            if recipients.is_empty() {
+                return Ok(delivered);
+            }
+
+            for recipient in recipients {
+                delivered += self.deliver(&recipient).await?;
+            }
+        }
+    }
+
+    /// Check that all members are in the same epoch with the same epoch
+    /// authenticator.
+    pub fn check_synchronized(&self) -> Result<(), HarnessError> {
+        let mut groups = self
+            .participants
+            .iter()
+            .filter_map(|(name, participant)| Some((name, participant.group.as_ref()?)));
+
+        let Some((first_name, first)) = groups.next() else {
+            return Ok(());
+        };
+
+        let first_authenticator = first.epoch_authenticator()?;
+
+        for (name, group) in groups {
+            if group.current_epoch() != first.current_epoch()
+                || group.epoch_authenticator()? != first_authenticator
+            {
+                return Err(HarnessError::NotSynchronized(
+                    first_name.clone(),
+                    name.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn commit(
+        &mut self,
+        committer: &str,
+        key_packages: Vec<MlsMessage>,
+        added: &[&str],
+        removed: &[&str],
+    ) -> Result<CommitOutcome, HarnessError> {
+        let removed_indexes = removed
+            .iter()
+            .map(|member| Ok(self.group(member)?.current_member_index()))
+            .collect::<Result<Vec<_>, HarnessError>>()?;
+
+        let group = self
+            .participants
+            .get_mut(committer)
+            .ok_or_else(|| HarnessError::UnknownClient(committer.to_owned()))?
+            .group
+            .as_mut()
+            .ok_or_else(|| HarnessError::NotAMember(committer.to_owned()))?;
+
+        let epoch = group.current_epoch();
+        let mut builder = group.commit_builder();
+
+        for key_package in key_packages {
+            builder = builder.add_member(key_package)?;
+        }
+
+        for index in removed_indexes {
+            builder = builder.remove_member(index)?;
+        }
+
+        let output = builder.build().await?;
+
+        if !self
+            .delivery_service
+            .sequence_commit(committer, epoch, output.commit_message)
+        {
+            group.clear_pending_commit();
+            return Ok(CommitOutcome::Rejected);
+        }
+
+        group.apply_pending_commit().await?;
+
+        if let Some(welcome) = output.welcome_messages.first() {
+            let ratchet_tree = group.export_tree().into_owned();
+
+            for member in added {
+                self.delivery_service.send_welcome(
+                    committer,
+                    member,
+                    welcome.clone(),
+                    ratchet_tree.clone(),
+                );
+            }
+        }
+
+        for member in removed {
+            self.delivery_service.leave(member);
+        }
+
+        Ok(CommitOutcome::Accepted)
+    }
+
+    fn participant(&self, name: &str) -> Result<&Participant<C>, HarnessError> {
+        self.participants
+            .get(name)
+            .ok_or_else(|| HarnessError::UnknownClient(name.to_owned()))
+    }
+
+    fn participant_mut(&mut self, name: &str) -> Result<&mut Participant<C>, HarnessError> {
+        self.participants
+            .get_mut(name)
+            .ok_or_else(|| HarnessError::UnknownClient(name.to_owned()))
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs::{
+    crypto::SignatureSecretKey,
+    group::ExportedTree,
+    identity::basic::BasicIdentityProvider,
+    mls_rules::{CommitOptions, DefaultMlsRules},
+    CipherSuite, Client, CryptoProvider, ExtensionList,
+};
+
+use crate::{basic_signing_identity, HarnessError};
+
+/// Ratchet tree of a group without blank parent nodes, together with the
+/// secret signing keys of its members.
+///
+/// This is useful to test code handling trees of a given size, for instance
+/// tree validation or welcome processing, without going through a scenario.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct TreeWithSigners {
+    pub tree: ExportedTree<'static>,
+    /// Signing key of the member at each leaf index.
+    pub signers: Vec<SignatureSecretKey>,
+    pub group_id: Vec<u8>,
+}
+
+impl TreeWithSigners {
+    /// Build a group with `n_leaves` members identified by basic credentials.
+    ///
+    /// The first member adds the second one, which adds the third one and so
+    /// on, each commit containing an update path. As a result, every parent
+    /// node of the tree is populated.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn make_full_tree<P: CryptoProvider + Clone>(
+        crypto_provider: P,
+        cipher_suite: CipherSuite,
+        n_leaves: u32,
+    ) -> Result<Self, HarnessError> {
+        let mls_rules = DefaultMlsRules::new()
+            .with_commit_options(CommitOptions::new().with_path_required(true));
+
+        let mut clients = Vec::new();
+        let mut signers = Vec::new();
+
+        for i in 0..n_leaves.max(1) {
+            let (signing_identity, secret) =
+                basic_signing_identity(&crypto_provider, cipher_suite, &format!("member{i}"))
+                    .await?;
+
+            let client = Client::builder()
+                .identity_provider(BasicIdentityProvider)
+                .crypto_provider(crypto_provider.clone())
+                .mls_rules(mls_rules.clone())
+                .signing_identity(signing_identity, secret.clone(), cipher_suite)
+                .try_build()
+                .await?;
+
+            clients.push(client);
+            signers.push(secret);
+        }
+
+        let mut group = clients[0].create_group(ExtensionList::default()).await?;
+
+        for client in &clients[1..] {
+            let key_package = client.generate_key_package_message().await?;
+
+            let output = group
+                .commit_builder()
+                .add_member(key_package)?
+                .build()
+                .await?;
+
+            group.apply_pending_commit().await?;
+
+            let (joined, _) = client
+                .join_group(Some(group.export_tree()), &output.welcome_messages[0])
+                .await?;
+
+            group = joined;
+        }
+
+        Ok(Self {
+            tree: group.export_tree().into_owned(),
+            signers,
+            group_id: group.group_id().to_vec(),
+        })
+    }
+}
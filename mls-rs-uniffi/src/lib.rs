@@ -277,6 +277,8 @@ pub enum ReceivedMessage {
     Welcome,
     /// Validated key package.
     KeyPackage,
+    /// Message sent by this member and delivered back to it.
+    OwnMessageEcho { epoch: u64 },
 }
 
 /// Supported cipher suites.
@@ -378,7 +380,7 @@ impl Client {
             .signing_identity(signing_identity, secret_key.into(), cipher_suite.into())
             .group_state_storage(client_config.group_state_storage.into())
             .mls_rules(mls_rules)
-            .build();
+            .build_unchecked();
 
         Client { inner: client }
     }
@@ -777,6 +779,9 @@ impl Group {
             group::ReceivedMessage::GroupInfo(_) => Ok(ReceivedMessage::GroupInfo),
             group::ReceivedMessage::Welcome => Ok(ReceivedMessage::Welcome),
             group::ReceivedMessage::KeyPackage(_) => Ok(ReceivedMessage::KeyPackage),
+            group::ReceivedMessage::OwnMessageEcho(echo) => {
+                Ok(ReceivedMessage::OwnMessageEcho { epoch: echo.epoch })
+            }
         }
     }
 }
@@ -83,5 +83,6 @@ fn make_client(name: &str) -> Client<impl MlsConfig> {
             secret_key,
             cipher_suite,
         )
-        .build()
+        .try_build()
+        .unwrap()
 }
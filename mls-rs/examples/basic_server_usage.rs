@@ -118,7 +118,7 @@ fn make_client(name: &str) -> Result<Client<impl MlsConfig>, MlsError> {
         .identity_provider(BasicIdentityProvider)
         .crypto_provider(crypto_provider())
         .signing_identity(signing_identity, secret, CIPHERSUITE)
-        .build())
+        .try_build()?)
 }
 
 fn make_identity(name: &str) -> (SignatureSecretKey, SigningIdentity) {
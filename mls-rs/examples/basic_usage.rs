@@ -33,7 +33,7 @@ fn make_client<P: CryptoProvider + Clone>(
         .identity_provider(BasicIdentityProvider)
         .crypto_provider(crypto_provider)
         .signing_identity(signing_identity, secret, CIPHERSUITE)
-        .build())
+        .try_build()?)
 }
 
 fn main() -> Result<(), MlsError> {
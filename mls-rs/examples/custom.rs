@@ -353,7 +353,7 @@ fn make_client(member: Member) -> Result<Client<impl MlsConfig>, CustomError> {
         .extension_type(ROSTER_EXTENSION_V1)
         .crypto_provider(crypto())
         .signing_identity(signing_identity, member.signer, CIPHER_SUITE)
-        .build())
+        .try_build()?)
 }
 
 fn main() -> Result<(), CustomError> {
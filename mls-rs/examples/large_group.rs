@@ -152,7 +152,7 @@ fn make_client<P: CryptoProvider + Clone>(
                 .with_commit_options(CommitOptions::new().with_path_required(true)),
         )
         .signing_identity(signing_identity, secret, CIPHERSUITE)
-        .build())
+        .try_build()?)
 }
 
 fn make_name(i: usize) -> String {
@@ -29,7 +29,8 @@ fn main() {
             .unwrap(),
         )
         .signing_identity(signing_identity, secret_key, CIPHERSUITE)
-        .build();
+        .try_build()
+        .unwrap();
 
     let mut alice_group = alice_client.create_group(Default::default()).unwrap();
 
@@ -5,21 +5,28 @@
 use crate::cipher_suite::CipherSuite;
 use crate::client_builder::{recreate_config, BaseConfig, ClientBuilder, MakeConfig};
 use crate::client_config::ClientConfig;
-use crate::group::framing::MlsMessage;
+use crate::crypto::CipherSuiteCapabilities;
+use crate::group::framing::{MlsMessage, MlsMessagePayload};
 
+#[cfg(feature = "private_message")]
+use crate::group::DecryptionFailure;
+use crate::group::{
+    find_key_package_generation, snapshot::Snapshot, DecodeLimit, ExportedMembership, ExportedTree,
+    ExternalCommitRejection, Group, GroupConfigOverrides, MessageContext, NewMemberInfo,
+    ReplayEntry, ReplayReport, WelcomePreview,
+};
 #[cfg(feature = "by_ref_proposal")]
 use crate::group::{
-    framing::{Content, MlsMessagePayload, PublicMessage, Sender, WireFormat},
+    framing::{Content, PublicMessage, Sender, WireFormat},
     message_signature::AuthenticatedContent,
     proposal::{AddProposal, Proposal},
 };
-use crate::group::{snapshot::Snapshot, ExportedTree, Group, NewMemberInfo};
 use crate::identity::SigningIdentity;
-use crate::key_package::{KeyPackageGeneration, KeyPackageGenerator};
+use crate::key_package::{KeyPackageGeneration, KeyPackageGenerator, KeyPackageRef};
 use crate::protocol_version::ProtocolVersion;
-use crate::tree_kem::node::NodeIndex;
+#[cfg(feature = "psk")]
+use crate::psk::PskUsage;
 use alloc::vec::Vec;
-use mls_rs_codec::MlsDecode;
 use mls_rs_core::crypto::{CryptoProvider, SignatureSecretKey};
 use mls_rs_core::error::{AnyError, IntoAnyError};
 use mls_rs_core::extension::{ExtensionError, ExtensionList, ExtensionType};
@@ -29,7 +36,6 @@ use mls_rs_core::key_package::KeyPackageStorage;
 
 use crate::group::external_commit::ExternalCommitBuilder;
 
-#[cfg(feature = "by_ref_proposal")]
 use alloc::boxed::Box;
 
 #[derive(Debug)]
@@ -39,8 +45,18 @@ use alloc::boxed::Box;
 pub enum MlsError {
     #[cfg_attr(feature = "std", error(transparent))]
     IdentityProviderError(AnyError),
+    #[cfg_attr(
+        feature = "std",
+        error("leaf node extensions rejected by the identity provider: {0}")
+    )]
+    LeafNodeExtensionsRejected(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
     CryptoProviderError(AnyError),
+    #[cfg_attr(
+        feature = "std",
+        error("crypto provider returned {0} outputs for a batch of {1} kdf expansions")
+    )]
+    KdfBatchSizeMismatch(usize, usize),
     #[cfg_attr(feature = "std", error(transparent))]
     KeyPackageRepoError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
@@ -49,6 +65,18 @@ pub enum MlsError {
     PskStoreError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
     MlsRulesError(AnyError),
+    #[cfg(feature = "external_client")]
+    #[cfg_attr(feature = "std", error(transparent))]
+    CommitAuthorizerError(AnyError),
+    #[cfg(feature = "external_client")]
+    #[cfg_attr(feature = "std", error("Commit denied with code {}: {}", .0.code, .0.reason))]
+    CommitDenied(crate::external_client::CommitDenial),
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(
+        feature = "std",
+        error("Message from member {0} rejected with code {}: {}", .1.code, .1.reason)
+    )]
+    MessageRejected(u32, crate::mls_rules::MessageRejection),
     #[cfg_attr(feature = "std", error(transparent))]
     SerializationError(AnyError),
     #[cfg_attr(feature = "std", error(transparent))]
@@ -69,6 +97,11 @@ pub enum MlsError {
     InvalidTreeKemPrivateKey,
     #[cfg_attr(feature = "std", error("key package not found, unable to process"))]
     WelcomeKeyPackageNotFound,
+    #[cfg_attr(
+        feature = "std",
+        error("no stored key package matches the welcome message, candidates: {0:?}")
+    )]
+    NoMatchingWelcomeKeyPackage(Vec<KeyPackageRef>),
     #[cfg_attr(feature = "std", error("leaf not found in tree for index {0}"))]
     LeafNotFound(u32),
     #[cfg_attr(feature = "std", error("message from self can't be processed"))]
@@ -78,6 +111,60 @@ pub enum MlsError {
         error("pending proposals found, commit required before application messages can be sent")
     )]
     CommitRequired,
+    #[cfg_attr(
+        feature = "std",
+        error("AEAD usage limit reached, commit required before application messages can be sent")
+    )]
+    AeadUsageLimitReached,
+    #[cfg_attr(
+        feature = "std",
+        error("recommended AEAD usage limit exceeds the maximum limit")
+    )]
+    InvalidAeadUsageLimits,
+    #[cfg_attr(feature = "std", error("Decode limit exceeded: {0:?}"))]
+    DecodeLimitExceeded(DecodeLimit),
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(
+        feature = "std",
+        error("private message could not be decrypted: {0:?}")
+    )]
+    PrivateMessageDecryptionFailed(DecryptionFailure),
+    #[cfg_attr(feature = "std", error("Unsupported snapshot version {0}"))]
+    UnsupportedSnapshotVersion(u16),
+    #[cfg_attr(feature = "std", error("Unsupported roster document version {0}"))]
+    UnsupportedRosterDocumentVersion(u16),
+    #[cfg_attr(
+        feature = "std",
+        error("content binding does not match the current epoch")
+    )]
+    InvalidContentBinding,
+    #[cfg_attr(
+        feature = "std",
+        error("key package bundle already contains a key package for cipher suite {0:?}")
+    )]
+    DuplicateCipherSuiteInKeyPackageBundle(CipherSuite),
+    #[cfg_attr(
+        feature = "std",
+        error("key package bundle contains no key package for cipher suite {0:?}")
+    )]
+    NoKeyPackageForCipherSuite(CipherSuite),
+    #[cfg_attr(
+        feature = "std",
+        error("external commit rejected by the group policy: {0:?}")
+    )]
+    ExternalCommitRejected(ExternalCommitRejection),
+    #[cfg_attr(feature = "std", error("failed to process message {0:?}"))]
+    MessageProcessingFailed(
+        Box<MessageContext>,
+        #[cfg_attr(feature = "std", source)] Box<MlsError>,
+    ),
+    #[cfg_attr(feature = "std", error("message buffer is full"))]
+    MessageBufferFull,
+    #[cfg_attr(
+        feature = "std",
+        error("all message generations reserved by the application sender were used")
+    )]
+    ApplicationSenderExhausted,
     #[cfg_attr(
         feature = "std",
         error("ratchet tree not provided or discovered in GroupInfo")
@@ -91,8 +178,27 @@ pub enum MlsError {
     ProtocolVersionMismatch,
     #[cfg_attr(feature = "std", error("Unsupported cipher suite {0:?}"))]
     UnsupportedCipherSuite(CipherSuite),
+    #[cfg_attr(
+        feature = "std",
+        error("AEAD key or nonce size of cipher suite {0:?} is not supported")
+    )]
+    UnsupportedAeadSizes(CipherSuite),
+    #[cfg_attr(
+        feature = "std",
+        error("credential type {0:?} of the signing identity is not supported by the identity provider")
+    )]
+    UnsupportedSigningCredential(CredentialType),
+    #[cfg_attr(
+        feature = "std",
+        error("stored key package {0:?} uses {1:?} and {2:?}, which the client does not support; delete it or configure support for them")
+    )]
+    UnsupportedStoredKeyPackage(KeyPackageRef, ProtocolVersion, CipherSuite),
     #[cfg_attr(feature = "std", error("Signing key of external sender is unknown"))]
     UnknownSigningIdentityForExternalSender,
+    #[cfg_attr(feature = "std", error("External sender is already allowed"))]
+    ExternalSenderAlreadyAllowed,
+    #[cfg_attr(feature = "std", error("External sender not found"))]
+    ExternalSenderNotFound,
     #[cfg_attr(
         feature = "std",
         error("External proposals are disabled for this group")
@@ -107,6 +213,11 @@ pub enum MlsError {
     MissingExternalPubExtension,
     #[cfg_attr(feature = "std", error("Epoch not found"))]
     EpochNotFound,
+    #[cfg_attr(
+        feature = "std",
+        error("stored epoch {0} is encrypted with chained keys that are not available; chained epoch encryption was disabled or a following epoch was stored without it")
+    )]
+    UnreadableEpochRecord(u64),
     #[cfg_attr(feature = "std", error("Unencrypted application message"))]
     UnencryptedApplicationMessage,
     #[cfg_attr(
@@ -131,6 +242,86 @@ pub enum MlsError {
         )
     )]
     GroupUsedAfterReInit,
+    #[cfg_attr(
+        feature = "std",
+        error("This member was removed from the group, which is now read-only.")
+    )]
+    RemovedFromGroup,
+    #[cfg_attr(feature = "std", error("unsupported client state export version {0}"))]
+    UnsupportedClientStateVersion(u16),
+    #[cfg_attr(
+        feature = "std",
+        error("group state storage can not enumerate the stored groups")
+    )]
+    GroupStorageNotEnumerable,
+    #[cfg_attr(
+        feature = "std",
+        error("key package storage can not enumerate the stored key packages")
+    )]
+    KeyPackageStorageNotEnumerable,
+    #[cfg_attr(
+        feature = "std",
+        error("pre-shared key storage can not enumerate the stored keys")
+    )]
+    PskStorageNotEnumerable,
+    #[cfg_attr(
+        feature = "std",
+        error("random integers must have a non-zero upper bound")
+    )]
+    InvalidRandomBound,
+    #[cfg_attr(
+        feature = "std",
+        error("application messages are not sent to groups with unverified member {0}")
+    )]
+    UnverifiedRecipient(u32),
+    #[cfg_attr(
+        feature = "std",
+        error("key rotation was requested in epoch {0} which is not the current epoch")
+    )]
+    KeyRotationEpochMismatch(u64),
+    #[cfg_attr(feature = "std", error("compression algorithm {0} is not supported"))]
+    UnsupportedCompressionAlgorithm(u16),
+    #[cfg_attr(
+        feature = "std",
+        error("message envelope version {0} is not supported")
+    )]
+    UnsupportedEnvelopeVersion(u8),
+    #[cfg_attr(
+        feature = "std",
+        error("decompressed message of {0} bytes exceeds the size limit")
+    )]
+    DecompressedSizeLimitExceeded(u64),
+    #[cfg_attr(feature = "std", error("compressed message is invalid"))]
+    InvalidCompressedMessage,
+    #[cfg_attr(feature = "std", error("message compression failed"))]
+    CompressionFailed,
+    #[cfg_attr(feature = "std", error("invalid member slot proposal: {0}"))]
+    InvalidMemberSlotProposal(&'static str),
+    #[cfg_attr(
+        feature = "std",
+        error("credential type {0:?} of external sender is not supported")
+    )]
+    UnsupportedExternalSenderCredential(CredentialType),
+    #[cfg_attr(
+        feature = "std",
+        error("message escrow is not enabled by the group context")
+    )]
+    MessageEscrowNotEnabled,
+    #[cfg_attr(
+        feature = "std",
+        error("escrow threshold {0} is not between 1 and the {1} escrow keys")
+    )]
+    InvalidEscrowThreshold(u8, usize),
+    #[cfg_attr(
+        feature = "std",
+        error("{0} escrow shares do not reach the threshold of {1}")
+    )]
+    NotEnoughEscrowShares(usize, u8),
+    #[cfg_attr(
+        feature = "std",
+        error("escrow shares are of different epochs or repeat a share index")
+    )]
+    InconsistentEscrowShares,
     #[cfg_attr(feature = "std", error("Pending ReIinit not found."))]
     PendingReInitNotFound,
     #[cfg_attr(
@@ -165,8 +356,14 @@ pub enum MlsError {
     NonZeroRetentionRequired,
     #[cfg_attr(feature = "std", error("Too many PSK IDs to compute PSK secret"))]
     TooManyPskIds,
-    #[cfg_attr(feature = "std", error("Missing required Psk"))]
-    MissingRequiredPsk,
+    #[cfg(feature = "psk")]
+    #[cfg_attr(feature = "std", error("psk failure {1:?} for {0:?}"))]
+    PskFailure(Vec<PskUsage>, PskFailureKind),
+    #[cfg_attr(
+        feature = "std",
+        error("commit or welcome does not include the PSK required to join the group")
+    )]
+    MissingRequiredJoinPsk,
     #[cfg_attr(feature = "std", error("Old group state not found"))]
     OldGroupStateNotFound,
     #[cfg_attr(feature = "std", error("leaf secret already consumed"))]
@@ -186,8 +383,21 @@ pub enum MlsError {
     InvalidTreeIndex,
     #[cfg_attr(feature = "std", error("time overflow"))]
     TimeOverflow,
-    #[cfg_attr(feature = "std", error("invalid leaf_node_source"))]
-    InvalidLeafNodeSource,
+    #[cfg_attr(
+        feature = "std",
+        error("leaf node at index {0:?} has source {2:?}, expected {1:?}")
+    )]
+    UnexpectedLeafNodeSource(Option<u32>, LeafNodeSourceKind, LeafNodeSourceKind),
+    #[cfg_attr(
+        feature = "std",
+        error("signature of the {1:?} leaf node at index {0} does not bind it to this group and leaf index")
+    )]
+    LeafNodeBindingMismatch(u32, LeafNodeSourceKind),
+    #[cfg_attr(
+        feature = "std",
+        error("parent hash of the commit leaf node at index {0} is {2:?}, the update path gives {1:?}")
+    )]
+    CommitLeafParentHashMismatch(u32, Vec<u8>, Vec<u8>),
     #[cfg_attr(feature = "std", error("key package has expired or is not valid yet"))]
     InvalidLifetime,
     #[cfg_attr(feature = "std", error("required extension not found"))]
@@ -201,14 +411,26 @@ pub enum MlsError {
     #[cfg_attr(feature = "std", error("expected non-blank node"))]
     ExpectedNode,
     #[cfg_attr(feature = "std", error("node index is out of bounds {0}"))]
-    InvalidNodeIndex(NodeIndex),
+    InvalidNodeIndex(u32),
     #[cfg_attr(feature = "std", error("unexpected empty node found"))]
     UnexpectedEmptyNode,
     #[cfg_attr(
         feature = "std",
-        error("duplicate signature key, hpke key or identity found at index {0}")
+        error("{1:?} of the new leaf is already used by the leaf at index {0}")
+    )]
+    DuplicateLeafData(u32, LeafDataKind),
+    #[cfg_attr(feature = "std", error("{0:?} reuses the HPKE key of {1:?}"))]
+    HpkeKeyReuse(HpkeKeyHolder, HpkeKeyHolder),
+    #[cfg_attr(
+        feature = "std",
+        error("leaf {0} chosen for a new member is neither blank nor right after the last leaf")
+    )]
+    InvalidLeafPlacement(u32),
+    #[cfg_attr(
+        feature = "std",
+        error("identity provider returned {1} identities for {0} signing identities")
     )]
-    DuplicateLeafData(u32),
+    IdentityCountMismatch(usize, usize),
     #[cfg_attr(
         feature = "std",
         error("In-use credential type not supported by new leaf at index")
@@ -295,6 +517,11 @@ pub enum MlsError {
         error("External commit contains more than one Remove proposal")
     )]
     ExternalCommitWithMoreThanOneRemove,
+    #[cfg_attr(
+        feature = "std",
+        error("External commit resync found no leaf of the same identity")
+    )]
+    ExternalCommitResyncLeafNotFound,
     #[cfg_attr(feature = "std", error("Duplicate PSK IDs"))]
     DuplicatePskIds,
     #[cfg_attr(
@@ -317,6 +544,8 @@ pub enum MlsError {
     UnsupportedCustomProposal(ProposalType),
     #[cfg_attr(feature = "std", error("by-ref proposal not found"))]
     ProposalNotFound,
+    #[cfg_attr(feature = "std", error("proposal type {0:?} is only allowed by value"))]
+    ProposalTypeRequiresByValue(ProposalType),
     #[cfg_attr(
         feature = "std",
         error("Removing non-existing member (or removing a member twice)")
@@ -337,6 +566,63 @@ pub enum MlsError {
     InvalidWelcomeMessage,
 }
 
+/// Data of a leaf node that must be unique among the leaves of the ratchet
+/// tree, reported by [`MlsError::DuplicateLeafData`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeafDataKind {
+    /// Signature key of the signing identity.
+    SignatureKey,
+    /// HPKE encryption key of the leaf node.
+    EncryptionKey,
+    /// Identity according to the identity provider.
+    Identity,
+}
+
+/// Source of a leaf node, reported by leaf node validation errors such as
+/// [`MlsError::UnexpectedLeafNodeSource`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeafNodeSourceKind {
+    /// Leaf node of a key package, added by an add proposal.
+    KeyPackage,
+    /// Leaf node of an update proposal.
+    Update,
+    /// Leaf node of the update path of a commit.
+    Commit,
+}
+
+/// Holder of an HPKE public key, reported by [`MlsError::HpkeKeyReuse`].
+///
+/// Add proposals are identified by their position among the add proposals
+/// of the commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HpkeKeyHolder {
+    /// Encryption key of the leaf at the given index.
+    Leaf(u32),
+    /// Init key of the key package of the given add proposal.
+    AddInitKey(usize),
+    /// Leaf encryption key of the key package of the given add proposal.
+    AddLeafKey(usize),
+}
+
+/// Failure of the pre-shared keys of a commit or welcome, reported by
+/// [`MlsError::PskFailure`].
+#[cfg(feature = "psk")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PskFailureKind {
+    /// The external PSK is not in the PSK store, or the epoch of the
+    /// resumption PSK is not available.
+    Missing,
+    /// The PSKs were found but the key schedule does not reproduce the
+    /// confirmation tag of the commit or decrypt the group info of the
+    /// welcome, so at least one value differs from the sender's. All PSKs
+    /// are combined into one secret, so all of them are reported.
+    ValueMismatch,
+}
+
 impl IntoAnyError for MlsError {
     #[cfg(feature = "std")]
     fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
@@ -430,6 +716,60 @@ where
         Ok(self.generate_key_package().await?.key_package_message())
     }
 
+    /// Creates `count` new key package messages at once.
+    ///
+    /// This function behaves the same way as
+    /// [generate_key_package_message](Client::generate_key_package_message)
+    /// called `count` times, but stores all key packages with a single call to
+    /// [KeyPackageStorage::insert_batch](crate::KeyPackageStorage::insert_batch).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn generate_key_package_messages(
+        &self,
+        count: usize,
+    ) -> Result<Vec<MlsMessage>, MlsError> {
+        let (signing_identity, cipher_suite) = self.signing_identity()?;
+
+        let cipher_suite_provider = self
+            .config
+            .crypto_provider()
+            .cipher_suite_provider(cipher_suite)
+            .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+
+        let key_package_generator = KeyPackageGenerator {
+            protocol_version: self.version,
+            cipher_suite_provider: &cipher_suite_provider,
+            signing_key: self.signer()?,
+            signing_identity,
+            identity_provider: &self.config.member_identity_provider(),
+        };
+
+        let generations = key_package_generator
+            .generate_batch(
+                count,
+                self.config.lifetime(),
+                self.config.capabilities(),
+                self.config.key_package_extensions(),
+                self.config.leaf_node_extensions(),
+            )
+            .await?;
+
+        let key_packages = generations
+            .iter()
+            .map(KeyPackageGeneration::to_storage)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.config
+            .key_package_repo()
+            .insert_batch(key_packages)
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+        Ok(generations
+            .iter()
+            .map(KeyPackageGeneration::key_package_message)
+            .collect())
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn generate_key_package(&self) -> Result<KeyPackageGeneration, MlsError> {
         let (signing_identity, cipher_suite) = self.signing_identity()?;
@@ -445,7 +785,7 @@ where
             cipher_suite_provider: &cipher_suite_provider,
             signing_key: self.signer()?,
             signing_identity,
-            identity_provider: &self.config.identity_provider(),
+            identity_provider: &self.config.member_identity_provider(),
         };
 
         let key_pkg_gen = key_package_generator
@@ -495,6 +835,7 @@ where
             signing_identity.clone(),
             group_context_extensions,
             self.signer()?.clone(),
+            Default::default(),
         )
         .await
     }
@@ -508,6 +849,23 @@ where
     pub async fn create_group(
         &self,
         group_context_extensions: ExtensionList,
+    ) -> Result<Group<C>, MlsError> {
+        self.create_group_with_overrides(group_context_extensions, Default::default())
+            .await
+    }
+
+    /// Create a MLS group that uses per-group overrides of the client
+    /// configuration.
+    ///
+    /// This function behaves the same way as
+    /// [create_group](Client::create_group) except that the values set in
+    /// `config_overrides` take precedence over the client configuration. The
+    /// overrides are persisted together with the group state.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn create_group_with_overrides(
+        &self,
+        group_context_extensions: ExtensionList,
+        config_overrides: GroupConfigOverrides,
     ) -> Result<Group<C>, MlsError> {
         let (signing_identity, cipher_suite) = self.signing_identity()?;
 
@@ -519,6 +877,7 @@ where
             signing_identity.clone(),
             group_context_extensions,
             self.signer()?.clone(),
+            config_overrides,
         )
         .await
     }
@@ -537,12 +896,97 @@ where
         &self,
         tree_data: Option<ExportedTree<'_>>,
         welcome_message: &MlsMessage,
+    ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
+        self.join_group_with_overrides(tree_data, welcome_message, Default::default())
+            .await
+    }
+
+    /// Find the stored key package that `welcome_message` is addressed to.
+    ///
+    /// The [KeyPackageStorage] of this client is searched for each key package
+    /// reference listed in the welcome message. This is the key package
+    /// [join_group](Client::join_group) uses to decrypt the group secrets. If
+    /// none of the references is found, the error lists all of them.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn welcome_key_package(
+        &self,
+        welcome_message: &MlsMessage,
+    ) -> Result<KeyPackageRef, MlsError> {
+        let MlsMessagePayload::Welcome(welcome) = &welcome_message.payload else {
+            return Err(MlsError::UnexpectedMessageType);
+        };
+
+        find_key_package_generation(&self.config.key_package_repo(), &welcome.secrets)
+            .await
+            .map(|(_, key_package_generation)| key_package_generation.reference)
+    }
+
+    /// Read the group a welcome message invites to without joining it.
+    ///
+    /// The group secrets and the group info of `welcome_message` are decrypted
+    /// with the stored key package the message is addressed to, which stays in
+    /// the [KeyPackageStorage] so that the group can be joined afterwards with
+    /// [join_group](Client::join_group). The returned
+    /// [group info extensions](WelcomePreview::group_info_extensions) include
+    /// extensions unknown to this client.
+    ///
+    /// The signature of the group info and the ratchet tree are not validated,
+    /// so the preview must not be trusted before the group is joined.
+    /// Welcome messages for resumption of a group can not be previewed.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn preview_welcome(
+        &self,
+        welcome_message: &MlsMessage,
+    ) -> Result<WelcomePreview, MlsError> {
+        Group::preview_welcome(welcome_message, &self.config).await
+    }
+
+    /// Join a MLS group using per-group overrides of the client configuration.
+    ///
+    /// This function behaves the same way as [join_group](Client::join_group)
+    /// except that the values set in `config_overrides` take precedence over
+    /// the client configuration. The overrides are persisted together with the
+    /// group state.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn join_group_with_overrides(
+        &self,
+        tree_data: Option<ExportedTree<'_>>,
+        welcome_message: &MlsMessage,
+        config_overrides: GroupConfigOverrides,
     ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
         Group::join(
             welcome_message,
             tree_data,
             self.config.clone(),
             self.signer()?.clone(),
+            config_overrides,
+        )
+        .await
+    }
+
+    /// Join a MLS group via a serialized welcome message.
+    ///
+    /// This function behaves the same way as
+    /// [join_group_with_overrides](Client::join_group_with_overrides), but
+    /// decodes `welcome_message` in place instead of copying its encrypted
+    /// group info into an [MlsMessage] first.
+    ///
+    /// Only that copy is saved. The group info is still decrypted into a new
+    /// buffer, and the group info and the ratchet tree it may carry are
+    /// decoded into owned values, as when joining from an [MlsMessage].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn join_group_from_bytes(
+        &self,
+        tree_data: Option<ExportedTree<'_>>,
+        welcome_message: &[u8],
+        config_overrides: GroupConfigOverrides,
+    ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
+        Group::join_from_bytes(
+            welcome_message,
+            tree_data,
+            self.config.clone(),
+            self.signer()?.clone(),
+            config_overrides,
         )
         .await
     }
@@ -615,11 +1059,52 @@ where
             .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
             .ok_or(MlsError::GroupNotFound)?;
 
-        let snapshot = Snapshot::mls_decode(&mut &*snapshot)?;
+        let snapshot = Snapshot::from_bytes(&snapshot)?;
 
         Group::from_snapshot(self.config.clone(), snapshot).await
     }
 
+    /// Continue a membership exported by another process of this client with
+    /// [Group::export_membership](crate::Group::export_membership), without
+    /// joining the group with a Welcome message.
+    ///
+    /// `export_key` is the key the membership was exported with. The imported
+    /// group is written to the [GroupStateStorage](crate::GroupStateStorage)
+    /// of this client, so that it can later be loaded with
+    /// [load_group](Client::load_group). Prior epochs of the group are not
+    /// part of the export.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn import_membership(
+        &self,
+        exported: &ExportedMembership,
+        export_key: &[u8],
+    ) -> Result<Group<C>, MlsError> {
+        Group::import_membership(self.config.clone(), exported, export_key).await
+    }
+
+    /// Replay `log` on a previous state of a group and compare the result with
+    /// the state of the group in the
+    /// [GroupStateStorage](crate::GroupStateStorage) of this client.
+    ///
+    /// `initial_state` is a group state previously written to storage by this
+    /// client, for example taken from a backup, and `log` contains the
+    /// messages processed by the group since then, in processing order. The
+    /// messages are processed deterministically by a copy of the group that is
+    /// never written to storage. Every epoch created by a commit of the log is
+    /// compared with the stored group state and prior epochs, and the replay
+    /// stops at the first difference, which is returned in the report.
+    ///
+    /// Commits created by the group itself can only be replayed if they are
+    /// pending in `initial_state`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn replay_group(
+        &self,
+        initial_state: &[u8],
+        log: Vec<ReplayEntry>,
+    ) -> Result<ReplayReport, MlsError> {
+        Group::replay(self.config.clone(), initial_state, log).await
+    }
+
     /// Request to join an existing [group](crate::group::Group).
     ///
     /// An existing group member will need to perform a
@@ -655,8 +1140,12 @@ where
             protocol_version,
             group_info,
             tree_data,
-            &self.config.identity_provider(),
+            &self.config.member_identity_provider(),
             &cipher_suite_provider,
+            &self.config.decode_limits(),
+            &self.config.mls_rules(),
+            Default::default(),
+            self.config.tree_validation_parallelism(),
         )
         .await?;
 
@@ -703,6 +1192,20 @@ where
             .ok_or(MlsError::SignerNotFound)
     }
 
+    /// Capabilities of every cipher suite supported by the
+    /// [`CryptoProvider`] of this client.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn cipher_suite_capabilities(&self) -> Vec<CipherSuiteCapabilities> {
+        let crypto_provider = self.config.crypto_provider();
+
+        crypto_provider
+            .supported_cipher_suites()
+            .into_iter()
+            .filter_map(|cs| crypto_provider.cipher_suite_provider(cs))
+            .map(|cs_provider| CipherSuiteCapabilities::new(&cs_provider))
+            .collect()
+    }
+
     /// Returns key package extensions used by this client
     pub fn key_package_extensions(&self) -> ExtensionList {
         self.config.key_package_extensions()
@@ -764,7 +1267,7 @@ pub(crate) mod test_utils {
         let mut client = TestClientBuilder::new_for_test()
             .used_protocol_version(protocol_version)
             .signing_identity(identity.clone(), secret_key, cipher_suite)
-            .build();
+            .build_unchecked();
 
         config(&mut client.config);
 
@@ -780,17 +1283,18 @@ mod tests {
 
     use super::*;
     use crate::{
-        crypto::test_utils::TestCryptoProvider,
+        crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
         identity::test_utils::{get_test_basic_credential, get_test_signing_identity},
-        tree_kem::leaf_node::LeafNodeSource,
+        tree_kem::leaf_node::{test_utils::get_test_capabilities, LeafNodeSource},
     };
     use assert_matches::assert_matches;
+    use mls_rs_core::extension::Extension;
 
     use crate::{
         group::{
             message_processor::ProposalMessageDescription,
             proposal::Proposal,
-            test_utils::{test_group, test_group_custom_config},
+            test_utils::{test_group, test_group_custom_config, TestGroup},
             ReceivedMessage,
         },
         psk::{ExternalPskId, PreSharedKey},
@@ -798,6 +1302,117 @@ mod tests {
 
     use alloc::vec;
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn client_reports_cipher_suite_capabilities() {
+        let client = TestClientBuilder::new_for_test().build_unchecked();
+        let capabilities = client.cipher_suite_capabilities();
+
+        let supported = capabilities
+            .iter()
+            .map(|c| c.cipher_suite)
+            .collect::<Vec<_>>();
+        assert_eq!(supported, TestCryptoProvider::all_supported_cipher_suites());
+
+        let p256 = capabilities
+            .iter()
+            .find(|c| c.cipher_suite == CipherSuite::P256_AES128)
+            .unwrap();
+
+        assert_eq!(p256.kem_id, Some(0x0010));
+        assert_eq!(p256.hash_size, 32);
+        assert_eq!(p256.aead_key_size, 16);
+        assert_eq!(p256.aead_nonce_size, 12);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn try_build_rejects_unsupported_cipher_suite() {
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+        let unsupported = CipherSuite::new(0xffff);
+
+        let res = TestClientBuilder::new_for_test()
+            .signing_identity(identity.clone(), secret_key.clone(), unsupported)
+            .try_build()
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::UnsupportedCipherSuite(cs)) if cs == unsupported);
+
+        let res = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .try_build()
+            .await;
+
+        assert!(res.is_ok());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn build_unchecked_skips_compatibility_checks() {
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+
+        TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, CipherSuite::new(0xffff))
+            .build_unchecked();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn try_build_rejects_unsupported_signing_credential() {
+        let (mut identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+
+        let credential_type = CredentialType::new(0xff00);
+
+        identity.credential = mls_rs_core::identity::Credential::Custom(
+            mls_rs_core::identity::CustomCredential::new(credential_type, vec![]),
+        );
+
+        let res = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .try_build()
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::UnsupportedSigningCredential(t)) if t == credential_type);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn try_build_rejects_stored_key_package_of_unsupported_cipher_suite() {
+        let (client, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let key_package_ref = key_package
+            .into_key_package()
+            .unwrap()
+            .to_reference(&test_cipher_suite_provider(TEST_CIPHER_SUITE))
+            .await
+            .unwrap();
+
+        let crypto_provider = TestCryptoProvider::with_enabled_cipher_suites(
+            TestCryptoProvider::all_supported_cipher_suites()
+                .into_iter()
+                .filter(|cs| cs != &TEST_CIPHER_SUITE)
+                .collect(),
+        );
+
+        let res = TestClientBuilder::new_for_test()
+            .key_package_repo(client.config.key_package_repo())
+            .crypto_provider(crypto_provider)
+            .try_build()
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::UnsupportedStoredKeyPackage(r, v, cs))
+                if r == key_package_ref && v == TEST_PROTOCOL_VERSION && cs == TEST_CIPHER_SUITE
+        );
+
+        let res = TestClientBuilder::new_for_test()
+            .key_package_repo(client.config.key_package_repo())
+            .try_build()
+            .await;
+
+        assert!(res.is_ok());
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_keygen() {
         // This is meant to test the inputs to the internal key package generator
@@ -811,7 +1426,7 @@ mod tests {
 
             let client = TestClientBuilder::new_for_test()
                 .signing_identity(identity.clone(), secret_key, cipher_suite)
-                .build();
+                .build_unchecked();
 
             // TODO: Tests around extensions
             let key_package = client.generate_key_package_message().await.unwrap();
@@ -837,6 +1452,192 @@ mod tests {
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_packages_can_be_generated_in_batch() {
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+
+        let client = TestClientBuilder::new_for_test()
+            .signing_identity(identity.clone(), secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let key_packages = client
+            .generate_key_package_messages(5)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|msg| msg.into_key_package().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(key_packages.len(), 5);
+        assert_eq!(client.key_package_store().key_packages().len(), 5);
+
+        for (i, key_package) in key_packages.iter().enumerate() {
+            assert_eq!(key_package.leaf_node.signing_identity, identity);
+
+            assert_eq!(
+                key_package.leaf_node.ungreased_capabilities(),
+                client.config.capabilities()
+            );
+
+            assert!(key_packages[i + 1..]
+                .iter()
+                .all(|other| other.hpke_init_key != key_package.hpke_init_key
+                    && other.leaf_node.public_key != key_package.leaf_node.public_key));
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn capabilities_are_derived_from_configuration() {
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+        let registered_type = ExtensionType::new(0xFA00);
+        let leaf_extension = Extension::new(ExtensionType::new(0xFA01), vec![1]);
+
+        let client = TestClientBuilder::new_for_test()
+            .extension_type(registered_type)
+            .custom_proposal_type(TEST_CUSTOM_PROPOSAL_TYPE)
+            .leaf_node_extensions(vec![leaf_extension.clone()].into())
+            .signing_identity(identity.clone(), secret_key.clone(), TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let capabilities = client.config.capabilities();
+
+        assert_eq!(
+            capabilities.cipher_suites,
+            TestCryptoProvider::all_supported_cipher_suites()
+        );
+        assert_eq!(
+            capabilities.extensions,
+            vec![registered_type, leaf_extension.extension_type]
+        );
+        assert_eq!(capabilities.proposals, vec![TEST_CUSTOM_PROPOSAL_TYPE]);
+        assert_eq!(
+            capabilities.credentials,
+            client.config.supported_credential_types()
+        );
+
+        // The leaf node is valid without registering the type of its extension
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let key_package = client.generate_key_package_message().await.unwrap();
+
+        alice
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let capabilities = get_test_capabilities();
+
+        let client = TestClientBuilder::new_for_test()
+            .capabilities(capabilities.clone())
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let key_package = client
+            .generate_key_package_message()
+            .await
+            .unwrap()
+            .into_key_package()
+            .unwrap();
+
+        assert_eq!(key_package.leaf_node.ungreased_capabilities(), capabilities);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn welcome_key_package_is_found_among_stored_key_packages() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob_identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let bob = TestClientBuilder::new_for_test()
+            .signing_identity(bob_identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let mut key_packages = bob.generate_key_package_messages(3).await.unwrap();
+        let key_package = key_packages.remove(1);
+
+        let cs = key_package.cipher_suite().unwrap();
+        let cs = test_cipher_suite_provider(cs);
+        let expected = key_package
+            .key_package_reference(&cs)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let commit = alice_group
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let welcome = &commit.welcome_messages[0];
+
+        let found = bob.welcome_key_package(welcome).await.unwrap();
+        assert_eq!(found, expected);
+
+        let (carol_identity, secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, b"carol").await;
+
+        let carol = TestClientBuilder::new_for_test()
+            .signing_identity(carol_identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let res = carol.welcome_key_package(welcome).await;
+
+        assert_matches!(res, Err(MlsError::NoMatchingWelcomeKeyPackage(refs)) if refs == vec![expected.clone()]);
+
+        let res = carol.join_group(None, welcome).await.map(|_| ());
+
+        assert_matches!(res, Err(MlsError::NoMatchingWelcomeKeyPackage(refs)) if refs == vec![expected]);
+
+        let res = bob.welcome_key_package(&commit.commit_message).await;
+        assert_matches!(res, Err(MlsError::UnexpectedMessageType));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_can_be_joined_from_serialized_welcome() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let commit = alice_group
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.group.apply_pending_commit().await.unwrap();
+
+        let welcome = commit.welcome_messages[0].to_bytes().unwrap();
+
+        let (bob_group, _) = bob
+            .join_group_from_bytes(None, &welcome, Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(alice_group.group.state, bob_group.state);
+
+        let res = bob
+            .join_group_from_bytes(
+                None,
+                &commit.commit_message.to_bytes().unwrap(),
+                Default::default(),
+            )
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::UnexpectedMessageType));
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn new_member_add_proposal_adds_to_group() {
@@ -846,7 +1647,7 @@ mod tests {
 
         let bob = TestClientBuilder::new_for_test()
             .signing_identity(bob_identity.clone(), secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let proposal = bob
             .external_add_proposal(
@@ -918,7 +1719,7 @@ mod tests {
         let new_client = TestClientBuilder::new_for_test()
             .psk(psk_id.clone(), psk)
             .signing_identity(new_client_identity.clone(), secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let mut builder = new_client.external_commit_builder().unwrap();
 
@@ -994,7 +1795,7 @@ mod tests {
 
         let alice = TestClientBuilder::new_for_test()
             .signing_identity(alice_identity.clone(), secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let msg = alice.generate_key_package_message().await.unwrap();
         let res = alice.commit_external(msg).await.map(|_| ());
@@ -1021,7 +1822,7 @@ mod tests {
 
         let carol = TestClientBuilder::new_for_test()
             .signing_identity(carol_identity, secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let (_, external_commit) = carol
             .external_commit_builder()
@@ -1038,12 +1839,96 @@ mod tests {
         assert_matches!(res, Err(_));
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn external_commit_test_setup(
+        name: &str,
+    ) -> (TestGroup, MlsMessage, Client<TestClientConfig>) {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        alice_group.join("bob").await;
+
+        let group_info_msg = alice_group
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let (identity, secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, name.as_bytes()).await;
+
+        let client = TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        (alice_group, group_info_msg, client)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_commit_resync_replaces_own_leaf() {
+        let (mut alice_group, group_info_msg, bob) = external_commit_test_setup("bob").await;
+
+        let (bob_group, external_commit) = bob
+            .external_commit_builder()
+            .unwrap()
+            .with_resync()
+            .build(group_info_msg)
+            .await
+            .unwrap();
+
+        assert_eq!(bob_group.roster().members_iter().count(), 2);
+
+        alice_group
+            .group
+            .process_incoming_message(external_commit)
+            .await
+            .unwrap();
+
+        assert_eq!(alice_group.group.roster().members_iter().count(), 2);
+
+        assert_eq!(
+            alice_group.group.epoch_authenticator().unwrap(),
+            bob_group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_commit_resync_requires_leaf_of_same_identity() {
+        let (_, group_info_msg, carol) = external_commit_test_setup("carol").await;
+
+        let res = carol
+            .external_commit_builder()
+            .unwrap()
+            .with_resync()
+            .build(group_info_msg)
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::ExternalCommitResyncLeafNotFound));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_commit_cannot_remove_other_identity() {
+        let (_, group_info_msg, bob) = external_commit_test_setup("bob").await;
+
+        let res = bob
+            .external_commit_builder()
+            .unwrap()
+            .with_removal(0)
+            .build(group_info_msg)
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::ExternalCommitRemovesOtherIdentity));
+    }
+
     #[test]
     fn builder_can_be_obtained_from_client_to_edit_properties_for_new_client() {
         let alice = TestClientBuilder::new_for_test()
             .extension_type(33.into())
-            .build();
-        let bob = alice.to_builder().extension_type(34.into()).build();
+            .build_unchecked();
+        let bob = alice
+            .to_builder()
+            .extension_type(34.into())
+            .build_unchecked();
         assert_eq!(bob.config.supported_extensions(), [33, 34].map(Into::into));
     }
 }
@@ -8,15 +8,20 @@
 
 use crate::{
     cipher_suite::CipherSuite,
-    client::Client,
+    client::{Client, MlsError},
     client_config::ClientConfig,
+    crypto::validate_aead_sizes,
     extension::{ExtensionType, MlsExtension},
     group::{
         mls_rules::{DefaultMlsRules, MlsRules},
         proposal::ProposalType,
+        DecodeLimits, LifetimePolicy, MessageBufferLimits, TreeValidationLevel,
     },
+    identity::custom::{CustomCredentialHandler, CustomCredentialProvider},
     identity::CredentialType,
     identity::SigningIdentity,
+    identity::UnsupportedCredentialPolicy,
+    key_package::{KeyPackage, KeyPackageRef},
     protocol_version::ProtocolVersion,
     psk::{ExternalPskId, PreSharedKey},
     storage_provider::in_memory::{
@@ -26,6 +31,15 @@ use crate::{
     Sealed,
 };
 
+#[cfg(feature = "private_message")]
+use crate::group::AeadUsageLimits;
+
+#[cfg(feature = "custom_proposal")]
+use crate::group::proposal::CustomProposalMetadata;
+
+#[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+use crate::group::secret_tree::MAX_RATCHET_BACK_HISTORY;
+
 #[cfg(feature = "std")]
 use crate::time::MlsTime;
 
@@ -40,7 +54,6 @@ use mls_rs_provider_sqlite::{
     },
 };
 
-#[cfg(feature = "private_message")]
 pub use crate::group::padding::PaddingMode;
 
 /// Base client configuration type when instantiating `ClientBuilder`
@@ -81,7 +94,7 @@ pub type BaseSqlConfig = Config<
 /// This is returned by [`Client::builder`] and allows to tweak settings the `Client` will use. At a
 /// minimum, the builder must be told the [`CryptoProvider`] and [`IdentityProvider`] to use. Other
 /// settings have default values. This means that the following
-/// methods must be called before [`ClientBuilder::build`]:
+/// methods must be called before [`ClientBuilder::try_build`]:
 ///
 /// - To specify the [`CryptoProvider`]: [`ClientBuilder::crypto_provider`]
 /// - To specify the [`IdentityProvider`]: [`ClientBuilder::identity_provider`]
@@ -108,7 +121,8 @@ pub type BaseSqlConfig = Config<
 ///     .crypto_provider(OpensslCryptoProvider::default())
 ///     .identity_provider(BasicIdentityProvider::new())
 ///     .signing_identity(signing_identity, secret_key, CipherSuite::CURVE25519_AES128)
-///     .build();
+///     .try_build()
+///     .unwrap();
 /// ```
 ///
 /// # Spelling out a `Client` type
@@ -120,13 +134,14 @@ pub type BaseSqlConfig = Config<
 /// use mls_rs::{
 ///     Client,
 ///     client_builder::MlsConfig,
+///     error::MlsError,
 ///     identity::{SigningIdentity, basic::{BasicIdentityProvider, BasicCredential}},
 ///     CipherSuite,
 /// };
 ///
 /// use mls_rs_crypto_openssl::OpensslCryptoProvider;
 ///
-/// fn make_client() -> Client<impl MlsConfig> {
+/// fn make_client() -> Result<Client<impl MlsConfig>, MlsError> {
 ///     // Replace by code to load the certificate and secret key
 ///     let secret_key = b"never hard-code secrets".to_vec().into();
 ///     let public_key = b"test invalid public key".to_vec().into();
@@ -137,7 +152,7 @@ pub type BaseSqlConfig = Config<
 ///         .crypto_provider(OpensslCryptoProvider::default())
 ///         .identity_provider(BasicIdentityProvider::new())
 ///         .signing_identity(signing_identity, secret_key, CipherSuite::CURVE25519_AES128)
-///         .build()
+///         .try_build()
 /// }
 ///```
 ///
@@ -146,6 +161,7 @@ pub type BaseSqlConfig = Config<
 /// use mls_rs::{
 ///     Client,
 ///     client_builder::{BaseConfig, WithIdentityProvider, WithCryptoProvider},
+///     error::MlsError,
 ///     identity::{SigningIdentity, basic::{BasicIdentityProvider, BasicCredential}},
 ///     CipherSuite,
 /// };
@@ -159,7 +175,7 @@ pub type BaseSqlConfig = Config<
 ///     >,
 /// >;
 ///
-/// fn make_client_2() -> MlsClient {
+/// fn make_client_2() -> Result<MlsClient, MlsError> {
 ///     // Replace by code to load the certificate and secret key
 ///     let secret_key = b"never hard-code secrets".to_vec().into();
 ///     let public_key = b"test invalid public key".to_vec().into();
@@ -170,7 +186,7 @@ pub type BaseSqlConfig = Config<
 ///         .crypto_provider(OpensslCryptoProvider::default())
 ///         .identity_provider(BasicIdentityProvider::new())
 ///         .signing_identity(signing_identity, secret_key, CipherSuite::CURVE25519_AES128)
-///         .build()
+///         .try_build()
 /// }
 ///
 /// ```
@@ -276,6 +292,26 @@ impl<C: IntoConfig> ClientBuilder<C> {
         ClientBuilder(c)
     }
 
+    /// Add a custom proposal type to the list of proposal types supported by the client,
+    /// along with `metadata` describing how proposals of this type are validated.
+    ///
+    /// Custom proposal types added without metadata use [`CustomProposalMetadata::default`].
+    #[cfg(feature = "custom_proposal")]
+    pub fn custom_proposal_type_with_metadata(
+        self,
+        type_: ProposalType,
+        metadata: CustomProposalMetadata,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        let settings = &mut c.0.settings;
+        settings.custom_proposal_types.push(type_);
+        settings
+            .custom_proposal_metadata
+            .retain(|(t, _)| *t != type_);
+        settings.custom_proposal_metadata.push((type_, metadata));
+        ClientBuilder(c)
+    }
+
     /// Add a protocol version to the list of protocol versions supported by the client.
     ///
     /// If no protocol version is explicitly added, the client will support all protocol versions
@@ -354,6 +390,288 @@ impl<C: IntoConfig> ClientBuilder<C> {
         ClientBuilder(c)
     }
 
+    /// Enable the [proposal rejection cache](crate::group::ProposalRejectionCache)
+    /// of groups created by the client.
+    ///
+    /// By-reference proposals dropped while preparing a commit are remembered for
+    /// `ttl_in_epochs` epochs after the epoch in which they were rejected. By default,
+    /// the cache is disabled.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_ttl(self, ttl_in_epochs: u64) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.proposal_rejection_ttl = Some(ttl_in_epochs);
+        ClientBuilder(c)
+    }
+
+    /// Expire by-reference proposals cached by groups of the client
+    /// `ttl_in_s` seconds after they were sent or received.
+    ///
+    /// Expired proposals are left out of the commits created by the client.
+    /// Commits of other members referencing expired proposals are still
+    /// processed, since expiry is not part of the shared group state, and the
+    /// expired proposals they commit are listed in
+    /// [`StateUpdate::expired_proposals`](crate::group::StateUpdate::expired_proposals).
+    /// The expiry of a single proposal can be changed with
+    /// [`Group::set_proposal_expiry`](crate::Group::set_proposal_expiry). By
+    /// default, proposals do not expire before the next commit.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_ttl(self, ttl_in_s: u64) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.proposal_ttl = Some(ttl_in_s);
+        ClientBuilder(c)
+    }
+
+    /// Require proposals of type `type_` to be sent by value.
+    ///
+    /// The client will neither send standalone proposal messages of this type nor
+    /// commit standalone proposals of this type received from other members.
+    /// Proposals of this type can still be included by value in commits.
+    ///
+    /// The policy only applies to commits created by this client. Commits from
+    /// other members referencing proposals of this type are processed as usual,
+    /// so that all members agree on the group state.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn by_value_proposal_type(self, type_: ProposalType) -> ClientBuilder<IntoConfigOutput<C>> {
+        self.by_value_proposal_types(Some(type_))
+    }
+
+    /// Require proposals of multiple types to be sent by value.
+    ///
+    /// See [`by_value_proposal_type`](ClientBuilder::by_value_proposal_type).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn by_value_proposal_types<I>(self, types: I) -> ClientBuilder<IntoConfigOutput<C>>
+    where
+        I: IntoIterator<Item = ProposalType>,
+    {
+        let mut c = self.0.into_config();
+        c.0.settings.by_value_proposal_types.extend(types);
+        ClientBuilder(c)
+    }
+
+    /// Set the limits on AEAD usage for groups using `cipher_suite`.
+    ///
+    /// Cipher suites without explicitly configured limits use
+    /// [`AeadUsageLimits::default`]. See [`Group::rekey_status`](crate::Group::rekey_status).
+    #[cfg(feature = "private_message")]
+    pub fn aead_usage_limits(
+        self,
+        cipher_suite: CipherSuite,
+        limits: AeadUsageLimits,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        let settings = &mut c.0.settings.aead_usage_limits;
+        settings.retain(|(cs, _)| *cs != cipher_suite);
+        settings.push((cipher_suite, limits));
+        ClientBuilder(c)
+    }
+
+    /// Set the maximum number of generations a sender's ratchet is moved forward to
+    /// decrypt a single message.
+    ///
+    /// Decrypting a message with generation `n` requires deriving the keys of all
+    /// generations between the last one received from the sender and `n`. Messages
+    /// further ahead are rejected with [`MlsError::InvalidFutureGeneration`](crate::error::MlsError::InvalidFutureGeneration)
+    /// so that a single message can not force an unbounded amount of work. The default
+    /// is 1024. If the application knows that messages were legitimately dropped, it can
+    /// move past them with [`Group::fast_forward_sender`](crate::Group::fast_forward_sender).
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    pub fn max_ratchet_skip(self, max_ratchet_skip: u32) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.max_ratchet_skip = max_ratchet_skip;
+        ClientBuilder(c)
+    }
+
+    /// Set the limits applied to messages received by groups of this client
+    /// before their contents are validated.
+    ///
+    /// Messages exceeding the limits are rejected with
+    /// [`MlsError::DecodeLimitExceeded`](crate::error::MlsError::DecodeLimitExceeded).
+    /// By default, [`DecodeLimits::default`] is used.
+    pub fn decode_limits(self, decode_limits: DecodeLimits) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.decode_limits = decode_limits;
+        ClientBuilder(c)
+    }
+
+    /// Set the policy used by groups of this client to check lifetimes of
+    /// leaf nodes added to the group. The policy can be overridden per group
+    /// with [`GroupConfigOverrides::with_lifetime_policy`](crate::group::GroupConfigOverrides::with_lifetime_policy).
+    ///
+    /// By default, [`LifetimePolicy::default`] is used, which accepts no leaf
+    /// node outside of its lifetime.
+    pub fn lifetime_policy(
+        self,
+        lifetime_policy: LifetimePolicy,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.lifetime_policy = lifetime_policy;
+        ClientBuilder(c)
+    }
+
+    /// Set the limits of the buffer holding messages that arrived too early
+    /// to be processed by
+    /// [`Group::process_incoming_message_buffered`](crate::Group::process_incoming_message_buffered).
+    ///
+    /// By default, [`MessageBufferLimits::default`] is used.
+    pub fn message_buffer_limits(
+        self,
+        message_buffer_limits: MessageBufferLimits,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.message_buffer_limits = message_buffer_limits;
+        ClientBuilder(c)
+    }
+
+    /// Set how thoroughly the ratchet tree is validated when joining a group
+    /// with a Welcome message.
+    ///
+    /// By default, [`TreeValidationLevel::Full`] is used as required by
+    /// RFC 9420. Lower levels must only be set if Welcome messages and
+    /// ratchet trees come from trusted infrastructure.
+    pub fn tree_validation_level(
+        self,
+        tree_validation_level: TreeValidationLevel,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.tree_validation_level = tree_validation_level;
+        ClientBuilder(c)
+    }
+
+    /// Set the maximum number of leaf nodes and parent hash chains that are
+    /// validated at the same time when joining a group.
+    ///
+    /// With the `rayon` feature, validation runs on the rayon thread pool and
+    /// this limits how many of its threads are used. In async builds, up to
+    /// 16 validations are awaited concurrently by default. A value of 1
+    /// validates the tree sequentially.
+    pub fn tree_validation_parallelism(
+        self,
+        max_parallelism: usize,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.tree_validation_parallelism = Some(max_parallelism);
+        ClientBuilder(c)
+    }
+
+    /// Set how members using a credential type that is not supported by the
+    /// identity provider are handled.
+    ///
+    /// By default, [`UnsupportedCredentialPolicy::Reject`] is used and such
+    /// members are invalid.
+    pub fn unsupported_credential_policy(
+        self,
+        unsupported_credential_policy: UnsupportedCredentialPolicy,
+    ) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.unsupported_credential_policy = unsupported_credential_policy;
+        ClientBuilder(c)
+    }
+
+    /// Attach a description of the inbound message to errors returned by
+    /// [`Group::process_incoming_message`](crate::Group::process_incoming_message)
+    /// and [`Group::process_incoming_message_with_time`](crate::Group::process_incoming_message_with_time).
+    ///
+    /// Errors are then wrapped in
+    /// [`MlsError::MessageProcessingFailed`](crate::error::MlsError::MessageProcessingFailed)
+    /// with a [`MessageContext`](crate::group::MessageContext). This requires
+    /// hashing every processed message and is disabled by default.
+    pub fn message_error_context(self, enabled: bool) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.message_error_context = enabled;
+        ClientBuilder(c)
+    }
+
+    /// Remember the last `size` application messages and proposals sent by
+    /// each group, so that processing them when the delivery service sends
+    /// them back returns
+    /// [`ReceivedMessage::OwnMessageEcho`](crate::group::ReceivedMessage::OwnMessageEcho)
+    /// instead of failing.
+    ///
+    /// Every sent message is hashed while this is enabled. The remembered
+    /// messages are kept in memory only and are not persisted. Commits are
+    /// recognized through the pending commit instead. Disabled by default.
+    pub fn outbound_echo_cache_size(self, size: usize) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.outbound_echo_cache_size = size;
+        ClientBuilder(c)
+    }
+
+    /// Keep the key schedule of the next epoch derived when creating a commit,
+    /// so that [`Group::apply_pending_commit`](crate::Group::apply_pending_commit)
+    /// does not derive it again.
+    ///
+    /// The derived secrets are kept in memory next to the pending commit and
+    /// are not persisted. A pending commit restored from storage is applied
+    /// by deriving the key schedule as usual. Disabled by default.
+    pub fn prewarm_key_schedule(self, enabled: bool) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.prewarm_key_schedule = enabled;
+        ClientBuilder(c)
+    }
+
+    /// Write the group state to the [`GroupStateStorage`] whenever a commit or
+    /// a proposal is created, before the message is returned.
+    ///
+    /// A process crashing after sending a commit or proposal then restores
+    /// the pending commit and the proposal cache that match the sent message.
+    /// If the state can not be written, the commit or proposal is discarded
+    /// and the error is returned, so that no message is sent without the
+    /// state needed to process it. Disabled by default, in which case the
+    /// application is responsible for calling
+    /// [`Group::write_to_storage`](crate::Group::write_to_storage) before
+    /// sending messages.
+    pub fn persist_pending_state(self, enabled: bool) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.persist_pending_state = enabled;
+        ClientBuilder(c)
+    }
+
+    /// Encrypt the prior epochs written to the [`GroupStateStorage`] with
+    /// keys chained from the current epoch.
+    ///
+    /// Each prior epoch is encrypted with a key derived from the secrets of
+    /// the epoch that follows it, and carries the secret needed to decrypt
+    /// the epoch before it. The head of the chain is derived from the epoch
+    /// secret with a dedicated label and kept in the group state. It is
+    /// independent of the exporter secret, so exported secrets do not
+    /// decrypt stored epochs. A prior epoch read from storage without the
+    /// group state and all prior epochs that follow it reveals nothing.
+    ///
+    /// Reading a prior epoch decrypts all prior epochs that follow it. Prior
+    /// epochs that can not be read because an epoch that follows them was
+    /// deleted from storage are handled as if they were deleted. Deleting the
+    /// oldest epochs, as done by
+    /// [`Group::compact_storage`](crate::Group::compact_storage) and by
+    /// retention limits of storage providers, keeps the remaining epochs
+    /// readable.
+    ///
+    /// Encrypted epochs are marked as such in storage. Epochs written before
+    /// enabling this setting remain readable. Encrypted epochs that can not be
+    /// decrypted, because this setting was disabled or because an epoch that
+    /// follows them was written while it was disabled, fail with
+    /// [`MlsError::UnreadableEpochRecord`]. Disabled by default.
+    #[cfg(feature = "prior_epoch")]
+    pub fn chained_epoch_encryption(self, enabled: bool) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.chained_epoch_encryption = enabled;
+        ClientBuilder(c)
+    }
+
+    /// Set the capabilities advertised in leaf nodes generated by the client.
+    ///
+    /// By default, the capabilities are derived from the configuration: the
+    /// cipher suites of the [`CryptoProvider`], the credential types of the
+    /// [`IdentityProvider`], the registered extension and custom proposal
+    /// types, and the types of the
+    /// [leaf node extensions](ClientBuilder::leaf_node_extensions). The given
+    /// `capabilities` are used as is instead, and must be kept consistent
+    /// with the configuration by the application.
+    pub fn capabilities(self, capabilities: Capabilities) -> ClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.capabilities = Some(capabilities);
+        ClientBuilder(c)
+    }
+
     /// Set the key package repository to be used by the client.
     ///
     /// By default, an in-memory repository is used.
@@ -450,6 +768,40 @@ impl<C: IntoConfig> ClientBuilder<C> {
         }))
     }
 
+    /// Register a handler for a custom credential type.
+    ///
+    /// The current identity provider is wrapped in a [`CustomCredentialProvider`]
+    /// that dispatches credentials of the type handled by `handler` to it. The
+    /// handled type is also advertised in the capabilities of leaf nodes
+    /// generated by the client. This function must therefore be called after
+    /// [`ClientBuilder::identity_provider`], and may be called multiple times to
+    /// register multiple custom credential types.
+    pub fn custom_credential<H>(
+        self,
+        handler: H,
+    ) -> ClientBuilder<
+        WithIdentityProvider<CustomCredentialProvider<<C as IntoConfig>::IdentityProvider, H>, C>,
+    >
+    where
+        H: CustomCredentialHandler,
+        <C as IntoConfig>::IdentityProvider: IdentityProvider,
+    {
+        let Config(c) = self.0.into_config();
+
+        ClientBuilder(Config(ConfigInner {
+            settings: c.settings,
+            key_package_repo: c.key_package_repo,
+            psk_store: c.psk_store,
+            group_state_storage: c.group_state_storage,
+            identity_provider: CustomCredentialProvider::new(c.identity_provider, handler),
+            mls_rules: c.mls_rules,
+            crypto_provider: c.crypto_provider,
+            signer: c.signer,
+            signing_identity: c.signing_identity,
+            version: c.version,
+        }))
+    }
+
     /// Set the crypto provider to be used by the client.
     pub fn crypto_provider<Cp>(
         self,
@@ -562,11 +914,58 @@ where
         c
     }
 
-    /// Build a client.
+    /// Build a client after checking that the configured services can satisfy the
+    /// configuration and are compatible with each other.
+    ///
+    /// This returns
+    /// - [`MlsError::UnsupportedCipherSuite`] if the cipher suite of the signing identity is
+    ///   not supported by the [crypto provider](ClientBuilder::crypto_provider),
+    /// - [`MlsError::UnsupportedAeadSizes`] if the AEAD key or nonce size reported for it
+    ///   cannot be used for message protection,
+    /// - [`MlsError::UnsupportedSigningCredential`] if the credential of the signing identity
+    ///   is not supported by the [identity provider](ClientBuilder::identity_provider),
+    /// - [`MlsError::UnsupportedStoredKeyPackage`] if the
+    ///   [key package repository](ClientBuilder::key_package_repo) holds a key package for a
+    ///   cipher suite or protocol version the client can not use. Repositories that can not
+    ///   enumerate their key packages are not checked.
     ///
     /// See [`ClientBuilder`] documentation if the return type of this function needs to be spelled
     /// out.
-    pub fn build(self) -> Client<IntoConfigOutput<C>> {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn try_build(self) -> Result<Client<IntoConfigOutput<C>>, MlsError> {
+        let client = self.build_unchecked();
+
+        if let Some((identity, cipher_suite)) = &client.signing_identity {
+            let cipher_suite_provider = client
+                .config
+                .crypto_provider()
+                .cipher_suite_provider(*cipher_suite)
+                .ok_or(MlsError::UnsupportedCipherSuite(*cipher_suite))?;
+
+            validate_aead_sizes(&cipher_suite_provider)?;
+
+            let credential_type = identity.credential.credential_type();
+
+            if !client
+                .config
+                .identity_provider()
+                .supported_types()
+                .contains(&credential_type)
+            {
+                return Err(MlsError::UnsupportedSigningCredential(credential_type));
+            }
+        }
+
+        validate_stored_key_packages(&client.config).await?;
+
+        Ok(client)
+    }
+
+    /// Build a client without checking the configured services against each other.
+    ///
+    /// Incompatibilities detected by [`ClientBuilder::try_build`] then surface when the client
+    /// is first used.
+    pub fn build_unchecked(self) -> Client<IntoConfigOutput<C>> {
         let mut c = self.build_config();
         let version = c.0.version;
         let signer = c.0.signer.take();
@@ -574,6 +973,59 @@ where
 
         Client::new(c, signer, signing_identity, version)
     }
+
+    /// Build a client without checking the configured services against each other.
+    ///
+    /// See [`ClientBuilder`] documentation if the return type of this function needs to be spelled
+    /// out.
+    #[deprecated(
+        note = "use `try_build` to check the configuration, or `build_unchecked` to skip the checks"
+    )]
+    pub fn build(self) -> Client<IntoConfigOutput<C>> {
+        self.build_unchecked()
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn validate_stored_key_packages<C: ClientConfig>(config: &C) -> Result<(), MlsError> {
+    let repo = config.key_package_repo();
+
+    // Storage that can not enumerate its key packages is not checked.
+    let Some(ids) = repo
+        .key_package_ids()
+        .await
+        .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?
+    else {
+        return Ok(());
+    };
+
+    for id in ids {
+        let Some(data) = repo
+            .get(&id)
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?
+        else {
+            continue;
+        };
+
+        let key_package = KeyPackage::mls_decode(&mut &*data.key_package_bytes)?;
+
+        let supported = config
+            .crypto_provider()
+            .supported_cipher_suites()
+            .contains(&key_package.cipher_suite)
+            && config.version_supported(key_package.version);
+
+        if !supported {
+            return Err(MlsError::UnsupportedStoredKeyPackage(
+                KeyPackageRef::from(id),
+                key_package.version,
+                key_package.cipher_suite,
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 impl<C: IntoConfig<PskStore = InMemoryPreSharedKeyStorage>> ClientBuilder<C> {
@@ -763,6 +1215,93 @@ where
     fn supported_custom_proposals(&self) -> Vec<crate::group::proposal::ProposalType> {
         self.settings.custom_proposal_types.clone()
     }
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.settings
+            .custom_proposal_metadata
+            .iter()
+            .find_map(|(t, metadata)| (*t == proposal_type).then_some(*metadata))
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_rejection_ttl(&self) -> Option<u64> {
+        self.settings.proposal_rejection_ttl
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_ttl(&self) -> Option<u64> {
+        self.settings.proposal_ttl
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn by_value_proposal_types(&self) -> Vec<ProposalType> {
+        self.settings.by_value_proposal_types.clone()
+    }
+
+    #[cfg(feature = "private_message")]
+    fn aead_usage_limits(&self, cipher_suite: CipherSuite) -> AeadUsageLimits {
+        self.settings
+            .aead_usage_limits
+            .iter()
+            .find_map(|(cs, limits)| (*cs == cipher_suite).then_some(*limits))
+            .unwrap_or_default()
+    }
+
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    fn max_ratchet_skip(&self) -> u32 {
+        self.settings.max_ratchet_skip
+    }
+
+    fn decode_limits(&self) -> DecodeLimits {
+        self.settings.decode_limits
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.settings.lifetime_policy
+    }
+
+    fn message_buffer_limits(&self) -> MessageBufferLimits {
+        self.settings.message_buffer_limits
+    }
+
+    fn tree_validation_level(&self) -> TreeValidationLevel {
+        self.settings.tree_validation_level
+    }
+
+    fn tree_validation_parallelism(&self) -> Option<usize> {
+        self.settings.tree_validation_parallelism
+    }
+
+    fn unsupported_credential_policy(&self) -> UnsupportedCredentialPolicy {
+        self.settings.unsupported_credential_policy
+    }
+
+    fn message_error_context(&self) -> bool {
+        self.settings.message_error_context
+    }
+
+    fn outbound_echo_cache_size(&self) -> usize {
+        self.settings.outbound_echo_cache_size
+    }
+
+    fn prewarm_key_schedule(&self) -> bool {
+        self.settings.prewarm_key_schedule
+    }
+
+    fn persist_pending_state(&self) -> bool {
+        self.settings.persist_pending_state
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    fn chained_epoch_encryption(&self) -> bool {
+        self.settings.chained_epoch_encryption
+    }
+
+    fn capabilities_override(&self) -> Option<Capabilities> {
+        self.settings.capabilities.clone()
+    }
 }
 
 impl<Kpr, Ps, Gss, Ip, Pr, Cp> Sealed for Config<Kpr, Ps, Gss, Ip, Pr, Cp> {}
@@ -863,6 +1402,85 @@ impl<T: MlsConfig> ClientConfig for T {
     fn supported_credential_types(&self) -> Vec<CredentialType> {
         self.get().supported_credential_types()
     }
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.get().custom_proposal_metadata(proposal_type)
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_rejection_ttl(&self) -> Option<u64> {
+        self.get().proposal_rejection_ttl()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_ttl(&self) -> Option<u64> {
+        self.get().proposal_ttl()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn by_value_proposal_types(&self) -> Vec<ProposalType> {
+        self.get().by_value_proposal_types()
+    }
+
+    #[cfg(feature = "private_message")]
+    fn aead_usage_limits(&self, cipher_suite: CipherSuite) -> AeadUsageLimits {
+        self.get().aead_usage_limits(cipher_suite)
+    }
+
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    fn max_ratchet_skip(&self) -> u32 {
+        self.get().max_ratchet_skip()
+    }
+
+    fn decode_limits(&self) -> DecodeLimits {
+        self.get().decode_limits()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.get().lifetime_policy()
+    }
+
+    fn message_buffer_limits(&self) -> MessageBufferLimits {
+        self.get().message_buffer_limits()
+    }
+
+    fn tree_validation_level(&self) -> TreeValidationLevel {
+        self.get().tree_validation_level()
+    }
+
+    fn tree_validation_parallelism(&self) -> Option<usize> {
+        self.get().tree_validation_parallelism()
+    }
+
+    fn unsupported_credential_policy(&self) -> UnsupportedCredentialPolicy {
+        self.get().unsupported_credential_policy()
+    }
+
+    fn message_error_context(&self) -> bool {
+        self.get().message_error_context()
+    }
+
+    fn outbound_echo_cache_size(&self) -> usize {
+        self.get().outbound_echo_cache_size()
+    }
+
+    fn prewarm_key_schedule(&self) -> bool {
+        self.get().prewarm_key_schedule()
+    }
+
+    fn persist_pending_state(&self) -> bool {
+        self.get().persist_pending_state()
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    fn chained_epoch_encryption(&self) -> bool {
+        self.get().chained_epoch_encryption()
+    }
+
+    fn capabilities_override(&self) -> Option<Capabilities> {
+        self.get().capabilities_override()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -870,9 +1488,34 @@ pub(crate) struct Settings {
     pub(crate) extension_types: Vec<ExtensionType>,
     pub(crate) protocol_versions: Vec<ProtocolVersion>,
     pub(crate) custom_proposal_types: Vec<ProposalType>,
+    #[cfg(feature = "custom_proposal")]
+    pub(crate) custom_proposal_metadata: Vec<(ProposalType, CustomProposalMetadata)>,
     pub(crate) key_package_extensions: ExtensionList,
     pub(crate) leaf_node_extensions: ExtensionList,
     pub(crate) lifetime_in_s: u64,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) proposal_rejection_ttl: Option<u64>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) proposal_ttl: Option<u64>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) by_value_proposal_types: Vec<ProposalType>,
+    #[cfg(feature = "private_message")]
+    pub(crate) aead_usage_limits: Vec<(CipherSuite, AeadUsageLimits)>,
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    pub(crate) max_ratchet_skip: u32,
+    pub(crate) decode_limits: DecodeLimits,
+    pub(crate) lifetime_policy: LifetimePolicy,
+    pub(crate) message_buffer_limits: MessageBufferLimits,
+    pub(crate) tree_validation_level: TreeValidationLevel,
+    pub(crate) tree_validation_parallelism: Option<usize>,
+    pub(crate) unsupported_credential_policy: UnsupportedCredentialPolicy,
+    pub(crate) message_error_context: bool,
+    pub(crate) outbound_echo_cache_size: usize,
+    pub(crate) prewarm_key_schedule: bool,
+    pub(crate) persist_pending_state: bool,
+    #[cfg(feature = "prior_epoch")]
+    pub(crate) chained_epoch_encryption: bool,
+    pub(crate) capabilities: Option<Capabilities>,
     #[cfg(any(test, feature = "test_util"))]
     pub(crate) key_package_not_before: Option<u64>,
 }
@@ -886,6 +1529,31 @@ impl Default for Settings {
             leaf_node_extensions: Default::default(),
             lifetime_in_s: 365 * 24 * 3600,
             custom_proposal_types: Default::default(),
+            #[cfg(feature = "custom_proposal")]
+            custom_proposal_metadata: Default::default(),
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_rejection_ttl: None,
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_ttl: None,
+            #[cfg(feature = "by_ref_proposal")]
+            by_value_proposal_types: Default::default(),
+            #[cfg(feature = "private_message")]
+            aead_usage_limits: Default::default(),
+            #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+            max_ratchet_skip: MAX_RATCHET_BACK_HISTORY,
+            decode_limits: Default::default(),
+            lifetime_policy: Default::default(),
+            message_buffer_limits: Default::default(),
+            tree_validation_level: Default::default(),
+            tree_validation_parallelism: None,
+            unsupported_credential_policy: Default::default(),
+            message_error_context: false,
+            outbound_echo_cache_size: 0,
+            prewarm_key_schedule: false,
+            persist_pending_state: false,
+            #[cfg(feature = "prior_epoch")]
+            chained_epoch_encryption: false,
+            capabilities: None,
             #[cfg(any(test, feature = "test_util"))]
             key_package_not_before: None,
         }
@@ -903,12 +1571,46 @@ pub(crate) fn recreate_config<T: ClientConfig>(
             extension_types: c.supported_extensions(),
             protocol_versions: c.supported_protocol_versions(),
             custom_proposal_types: c.supported_custom_proposals(),
+            #[cfg(feature = "custom_proposal")]
+            custom_proposal_metadata: c
+                .supported_custom_proposals()
+                .into_iter()
+                .map(|t| (t, c.custom_proposal_metadata(t)))
+                .collect(),
             key_package_extensions: c.key_package_extensions(),
             leaf_node_extensions: c.leaf_node_extensions(),
             lifetime_in_s: {
                 let l = c.lifetime();
                 l.not_after - l.not_before
             },
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_rejection_ttl: c.proposal_rejection_ttl(),
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_ttl: c.proposal_ttl(),
+            #[cfg(feature = "by_ref_proposal")]
+            by_value_proposal_types: c.by_value_proposal_types(),
+            #[cfg(feature = "private_message")]
+            aead_usage_limits: c
+                .crypto_provider()
+                .supported_cipher_suites()
+                .into_iter()
+                .map(|cs| (cs, c.aead_usage_limits(cs)))
+                .collect(),
+            #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+            max_ratchet_skip: c.max_ratchet_skip(),
+            decode_limits: c.decode_limits(),
+            lifetime_policy: c.lifetime_policy(),
+            message_buffer_limits: c.message_buffer_limits(),
+            tree_validation_level: c.tree_validation_level(),
+            tree_validation_parallelism: c.tree_validation_parallelism(),
+            unsupported_credential_policy: c.unsupported_credential_policy(),
+            message_error_context: c.message_error_context(),
+            outbound_echo_cache_size: c.outbound_echo_cache_size(),
+            prewarm_key_schedule: c.prewarm_key_schedule(),
+            persist_pending_state: c.persist_pending_state(),
+            #[cfg(feature = "prior_epoch")]
+            chained_epoch_encryption: c.chained_epoch_encryption(),
+            capabilities: c.capabilities_override(),
             #[cfg(any(test, feature = "test_util"))]
             key_package_not_before: None,
         },
@@ -977,8 +1679,10 @@ mod private {
     }
 }
 
+use mls_rs_codec::MlsDecode;
 use mls_rs_core::{
     crypto::{CryptoProvider, SignatureSecretKey},
+    error::IntoAnyError,
     extension::{ExtensionError, ExtensionList},
     group::GroupStateStorage,
     identity::IdentityProvider,
@@ -2,10 +2,19 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+#[cfg(feature = "private_message")]
+use crate::{group::AeadUsageLimits, CipherSuite};
+
+#[cfg(feature = "custom_proposal")]
+use crate::group::proposal::CustomProposalMetadata;
+
 use crate::{
     extension::ExtensionType,
-    group::{mls_rules::MlsRules, proposal::ProposalType},
-    identity::CredentialType,
+    group::{
+        mls_rules::MlsRules, proposal::ProposalType, DecodeLimits, LifetimePolicy,
+        MessageBufferLimits, TreeValidationLevel,
+    },
+    identity::{CredentialType, PolicyIdentityProvider, UnsupportedCredentialPolicy},
     protocol_version::ProtocolVersion,
     tree_kem::{leaf_node::ConfigProperties, Capabilities, Lifetime},
     ExtensionList,
@@ -26,6 +35,9 @@ pub trait ClientConfig: Send + Sync + Clone {
 
     fn supported_extensions(&self) -> Vec<ExtensionType>;
     fn supported_custom_proposals(&self) -> Vec<ProposalType>;
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata;
     fn supported_protocol_versions(&self) -> Vec<ProtocolVersion>;
 
     fn key_package_repo(&self) -> Self::KeyPackageRepository;
@@ -41,11 +53,70 @@ pub trait ClientConfig: Send + Sync + Clone {
     fn leaf_node_extensions(&self) -> ExtensionList;
     fn lifetime(&self) -> Lifetime;
 
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_rejection_ttl(&self) -> Option<u64>;
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_ttl(&self) -> Option<u64>;
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn by_value_proposal_types(&self) -> Vec<ProposalType>;
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn by_reference_allowed(&self, proposal_type: ProposalType) -> bool {
+        #[cfg(feature = "custom_proposal")]
+        if self.custom_proposal_metadata(proposal_type).by_value_only {
+            return false;
+        }
+
+        !self.by_value_proposal_types().contains(&proposal_type)
+    }
+
+    #[cfg(feature = "private_message")]
+    fn aead_usage_limits(&self, cipher_suite: CipherSuite) -> AeadUsageLimits;
+
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    fn max_ratchet_skip(&self) -> u32;
+
+    fn decode_limits(&self) -> DecodeLimits;
+    fn lifetime_policy(&self) -> LifetimePolicy;
+    fn message_buffer_limits(&self) -> MessageBufferLimits;
+    fn tree_validation_level(&self) -> TreeValidationLevel;
+    fn tree_validation_parallelism(&self) -> Option<usize>;
+    fn unsupported_credential_policy(&self) -> UnsupportedCredentialPolicy;
+    fn message_error_context(&self) -> bool;
+    fn outbound_echo_cache_size(&self) -> usize;
+    fn prewarm_key_schedule(&self) -> bool;
+    fn persist_pending_state(&self) -> bool;
+    #[cfg(feature = "prior_epoch")]
+    fn chained_epoch_encryption(&self) -> bool;
+    fn capabilities_override(&self) -> Option<Capabilities>;
+
     fn capabilities(&self) -> Capabilities {
+        if let Some(capabilities) = self.capabilities_override() {
+            return capabilities;
+        }
+
+        let crypto_provider = self.crypto_provider();
+        let mut extensions = self.supported_extensions();
+
+        // Leaf nodes are only valid if they support their own extensions
+        for extension in self.leaf_node_extensions().iter() {
+            let extension_type = extension.extension_type;
+
+            if !extension_type.is_default() && !extensions.contains(&extension_type) {
+                extensions.push(extension_type);
+            }
+        }
+
         Capabilities {
             protocol_versions: self.supported_protocol_versions(),
-            cipher_suites: self.crypto_provider().supported_cipher_suites(),
-            extensions: self.supported_extensions(),
+            cipher_suites: crypto_provider
+                .supported_cipher_suites()
+                .into_iter()
+                .filter(|cs| crypto_provider.cipher_suite_provider(*cs).is_some())
+                .collect(),
+            extensions,
             proposals: self.supported_custom_proposals(),
             credentials: self.supported_credential_types(),
         }
@@ -59,6 +130,15 @@ pub trait ClientConfig: Send + Sync + Clone {
         self.identity_provider().supported_types()
     }
 
+    /// Identity provider used to validate group members, which applies the
+    /// [`UnsupportedCredentialPolicy`] on top of [`Self::identity_provider`].
+    fn member_identity_provider(&self) -> PolicyIdentityProvider<Self::IdentityProvider> {
+        PolicyIdentityProvider::new(
+            self.identity_provider(),
+            self.unsupported_credential_policy(),
+        )
+    }
+
     fn leaf_properties(&self) -> ConfigProperties {
         ConfigProperties {
             capabilities: self.capabilities(),
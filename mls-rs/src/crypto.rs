@@ -11,6 +11,87 @@ pub use mls_rs_core::crypto::{
 
 pub use mls_rs_core::secret::Secret;
 
+use crate::{cipher_suite::CipherSuite, client::MlsError};
+
+/// Smallest AEAD nonce size supported by message framing, which XORs a
+/// 4-byte reuse guard into the nonce of each private message.
+const MIN_AEAD_NONCE_SIZE: usize = 4;
+
+/// Parameters of a cipher suite as implemented by a
+/// [`CryptoProvider`](mls_rs_core::crypto::CryptoProvider).
+///
+/// Capabilities of all cipher suites supported by a client can be obtained using
+/// [`Client::cipher_suite_capabilities`](crate::Client::cipher_suite_capabilities).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CipherSuiteCapabilities {
+    /// Cipher suite identifier.
+    pub cipher_suite: CipherSuite,
+    /// HPKE KEM identifier as defined by RFC 9180, if `cipher_suite` is one of
+    /// the default MLS cipher suites.
+    pub kem_id: Option<u16>,
+    /// Output size of the hash function and of the KDF extract operation.
+    pub hash_size: usize,
+    /// Size of AEAD keys.
+    pub aead_key_size: usize,
+    /// Size of AEAD nonces.
+    pub aead_nonce_size: usize,
+}
+
+impl CipherSuiteCapabilities {
+    pub(crate) fn new<P: CipherSuiteProvider>(cipher_suite_provider: &P) -> Self {
+        let cipher_suite = cipher_suite_provider.cipher_suite();
+
+        Self {
+            cipher_suite,
+            kem_id: default_kem_id(cipher_suite),
+            hash_size: cipher_suite_provider.kdf_extract_size(),
+            aead_key_size: cipher_suite_provider.aead_key_size(),
+            aead_nonce_size: cipher_suite_provider.aead_nonce_size(),
+        }
+    }
+}
+
+/// Check that the AEAD key and nonce sizes of `cipher_suite_provider` can be
+/// used by the key schedule, the secret tree and message framing.
+pub(crate) fn validate_aead_sizes<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+) -> Result<(), MlsError> {
+    check_aead_sizes(
+        cipher_suite_provider.cipher_suite(),
+        cipher_suite_provider.aead_key_size(),
+        cipher_suite_provider.aead_nonce_size(),
+    )
+}
+
+fn check_aead_sizes(
+    cipher_suite: CipherSuite,
+    key_size: usize,
+    nonce_size: usize,
+) -> Result<(), MlsError> {
+    // Keys and nonces are derived using `ExpandWithLabel` whose output length
+    // is encoded as a u16.
+    let max_size = u16::MAX as usize;
+
+    let valid = (1..=max_size).contains(&key_size)
+        && (MIN_AEAD_NONCE_SIZE..=max_size).contains(&nonce_size);
+
+    valid
+        .then_some(())
+        .ok_or(MlsError::UnsupportedAeadSizes(cipher_suite))
+}
+
+fn default_kem_id(cipher_suite: CipherSuite) -> Option<u16> {
+    match cipher_suite {
+        CipherSuite::CURVE25519_AES128 | CipherSuite::CURVE25519_CHACHA => Some(0x0020),
+        CipherSuite::P256_AES128 => Some(0x0010),
+        CipherSuite::CURVE448_AES256 | CipherSuite::CURVE448_CHACHA => Some(0x0021),
+        CipherSuite::P521_AES256 => Some(0x0012),
+        CipherSuite::P384_AES256 => Some(0x0011),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test_utils {
     use cfg_if::cfg_if;
@@ -41,3 +122,41 @@ pub(crate) mod test_utils {
         TestCryptoProvider::new().cipher_suite_provider(CipherSuite::from(cipher_suite))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::CryptoProvider;
+
+    use crate::{cipher_suite::CipherSuite, client::MlsError};
+
+    use super::{check_aead_sizes, test_utils::TestCryptoProvider, validate_aead_sizes};
+
+    #[test]
+    fn aead_sizes_of_test_provider_are_valid() {
+        let crypto = TestCryptoProvider::new();
+
+        for cipher_suite in crypto.supported_cipher_suites() {
+            let cs = crypto.cipher_suite_provider(cipher_suite).unwrap();
+            assert!(validate_aead_sizes(&cs).is_ok());
+        }
+    }
+
+    #[test]
+    fn non_standard_aead_sizes_are_valid() {
+        // AES-256-SIV with a 16 byte nonce
+        assert!(check_aead_sizes(CipherSuite::from(0xF000), 64, 16).is_ok());
+    }
+
+    #[test]
+    fn unusable_aead_sizes_are_rejected() {
+        let cipher_suite = CipherSuite::from(0xF000);
+
+        for (key_size, nonce_size) in [(0, 12), (16, 3), (1 << 16, 12), (16, 1 << 16)] {
+            assert_matches!(
+                check_aead_sizes(cipher_suite, key_size, nonce_size),
+                Err(MlsError::UnsupportedAeadSizes(cs)) if cs == cipher_suite
+            );
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{boxed::Box, string::String};
+use mls_rs_core::error::IntoAnyError;
+
+use crate::{
+    client::MlsError,
+    group::{ExportedTree, GroupInfo},
+    KeyPackage, MlsMessage,
+};
+
+#[derive(Debug)]
+struct DebugJsonError(serde_json::Error);
+
+impl IntoAnyError for DebugJsonError {
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.0.into())
+    }
+}
+
+/// Canonical JSON rendering of protocol structures, meant for debugging and
+/// golden tests.
+///
+/// Object keys are the field and variant names of the Rust structures and are
+/// emitted in sorted order. Byte strings are hex encoded. Two values with the
+/// same MLS encoding always produce the same JSON, so the output can be diffed
+/// across versions and implementations. The format is not meant to be parsed
+/// by other MLS implementations and is not a replacement for the MLS encoding.
+pub trait DebugJson: serde::Serialize {
+    fn to_debug_json(&self) -> Result<String, MlsError> {
+        serde_json::to_value(self)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .map_err(|e| MlsError::SerializationError(DebugJsonError(e).into_any_error()))
+    }
+}
+
+impl DebugJson for MlsMessage {}
+impl DebugJson for GroupInfo {}
+impl DebugJson for KeyPackage {}
+impl DebugJson for ExportedTree<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_group,
+        key_package::test_utils::test_key_package_message,
+        MlsMessage,
+    };
+
+    use super::DebugJson;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn debug_json_is_canonical_and_round_trips() {
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let json = key_package.to_debug_json().unwrap();
+        assert_eq!(json, key_package.clone().to_debug_json().unwrap());
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["payload", "version"]);
+
+        let decoded: MlsMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, key_package);
+
+        let group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let tree = group.group.export_tree();
+
+        assert!(tree.to_debug_json().unwrap().contains("Leaf"));
+    }
+}
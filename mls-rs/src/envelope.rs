@@ -0,0 +1,427 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use std::io::Read;
+
+use crate::{client::MlsError, group::DecodeLimits, MlsMessage};
+
+/// Prefix of every envelope. Encoded [`MlsMessage`]s start with the protocol
+/// version, which never matches these bytes.
+const ENVELOPE_MAGIC: &[u8] = b"MLSZ";
+
+/// Version of the envelope format written by this library.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Compression algorithm of a [`MessageEnvelope`].
+#[derive(
+    Clone, Copy, Eq, Hash, PartialOrd, Ord, PartialEq, MlsSize, MlsEncode, MlsDecode, Debug,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+pub struct CompressionAlgorithm(u16);
+
+impl CompressionAlgorithm {
+    /// Raw deflate stream as defined by RFC 1951.
+    pub const DEFLATE: CompressionAlgorithm = CompressionAlgorithm(1);
+    /// Zstandard frame as defined by RFC 8878.
+    pub const ZSTD: CompressionAlgorithm = CompressionAlgorithm(2);
+
+    pub const fn new(value: u16) -> CompressionAlgorithm {
+        CompressionAlgorithm(value)
+    }
+
+    pub const fn raw_value(&self) -> u16 {
+        self.0
+    }
+
+    /// Algorithms enabled by the features of this crate, in order of
+    /// preference.
+    pub fn supported() -> Vec<CompressionAlgorithm> {
+        [Self::ZSTD, Self::DEFLATE]
+            .into_iter()
+            .filter(Self::is_supported)
+            .collect()
+    }
+
+    /// Whether the feature enabling this algorithm is enabled.
+    pub fn is_supported(&self) -> bool {
+        match *self {
+            #[cfg(feature = "compression_deflate")]
+            Self::DEFLATE => true,
+            #[cfg(feature = "compression_zstd")]
+            Self::ZSTD => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, MlsSize, MlsEncode, MlsDecode)]
+struct EnvelopeHeader {
+    version: u8,
+    algorithm: CompressionAlgorithm,
+    decompressed_size: u32,
+}
+
+/// Transport encoding of [`MlsMessage`]s that compresses large messages.
+///
+/// Commits and welcome messages of large groups contain many similar
+/// structures and compress well. Messages are wrapped with
+/// [`wrap`](Self::wrap) using an algorithm negotiated with the recipients
+/// through [`negotiate`](Self::negotiate), for example based on algorithms
+/// advertised by the application. [`unwrap`](Self::unwrap) accepts both
+/// envelopes and plain encoded messages, so that recipients can
+/// auto-detect the encoding.
+///
+/// Decompression is limited to
+/// [`max_decompressed_size`](Self::max_decompressed_size) bytes, regardless
+/// of the size declared by the envelope. The decompressed message is then
+/// decoded with the [`DecodeLimits`] passed to [`unwrap`](Self::unwrap).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MessageEnvelope {
+    /// Algorithms used by this client in order of preference.
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Maximum size of a decompressed message.
+    pub max_decompressed_size: u32,
+    /// Size of encoded messages under which they are sent without
+    /// compression.
+    pub min_compressed_size: u32,
+}
+
+impl Default for MessageEnvelope {
+    fn default() -> Self {
+        Self {
+            algorithms: CompressionAlgorithm::supported(),
+            max_decompressed_size: 1 << 24,
+            min_compressed_size: 256,
+        }
+    }
+}
+
+impl MessageEnvelope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_algorithms(self, algorithms: Vec<CompressionAlgorithm>) -> Self {
+        Self { algorithms, ..self }
+    }
+
+    pub fn with_max_decompressed_size(self, max_decompressed_size: u32) -> Self {
+        Self {
+            max_decompressed_size,
+            ..self
+        }
+    }
+
+    pub fn with_min_compressed_size(self, min_compressed_size: u32) -> Self {
+        Self {
+            min_compressed_size,
+            ..self
+        }
+    }
+
+    /// Preferred algorithm of this client that is also supported by a peer
+    /// supporting `peer_algorithms`, or `None` if there is no such algorithm.
+    pub fn negotiate(
+        &self,
+        peer_algorithms: &[CompressionAlgorithm],
+    ) -> Option<CompressionAlgorithm> {
+        self.algorithms
+            .iter()
+            .find(|algorithm| algorithm.is_supported() && peer_algorithms.contains(algorithm))
+            .copied()
+    }
+
+    /// Whether `data` is an envelope rather than a plain encoded message.
+    pub fn is_envelope(data: &[u8]) -> bool {
+        data.starts_with(ENVELOPE_MAGIC)
+    }
+
+    /// Encode `message`, compressed with `algorithm` if one is given.
+    ///
+    /// The plain encoding of the message is returned if it is smaller than
+    /// [`min_compressed_size`](Self::min_compressed_size) or if compression
+    /// does not reduce its size.
+    pub fn wrap(
+        &self,
+        message: &MlsMessage,
+        algorithm: Option<CompressionAlgorithm>,
+    ) -> Result<Vec<u8>, MlsError> {
+        let encoded = message.to_bytes()?;
+
+        let Some(algorithm) = algorithm else {
+            return Ok(encoded);
+        };
+
+        if encoded.len() < self.min_compressed_size as usize {
+            return Ok(encoded);
+        }
+
+        let header = EnvelopeHeader {
+            version: ENVELOPE_VERSION,
+            algorithm,
+            decompressed_size: encoded
+                .len()
+                .try_into()
+                .map_err(|_| MlsError::DecompressedSizeLimitExceeded(encoded.len() as u64))?,
+        };
+
+        let compressed = compress(algorithm, &encoded)?;
+
+        if ENVELOPE_MAGIC.len() + header.mls_encoded_len() + compressed.len() >= encoded.len() {
+            return Ok(encoded);
+        }
+
+        let mut envelope = ENVELOPE_MAGIC.to_vec();
+        header.mls_encode(&mut envelope)?;
+        envelope.extend(compressed);
+
+        Ok(envelope)
+    }
+
+    /// Decode a message produced by [`wrap`](Self::wrap), or a plain encoded
+    /// message.
+    ///
+    /// Envelopes are only accepted if their algorithm is one of
+    /// [`algorithms`](Self::algorithms). The message is decoded with
+    /// [`MlsMessage::from_bytes_with_limits`] using `limits`, after
+    /// decompression if `data` is an envelope.
+    pub fn unwrap(&self, data: &[u8], limits: &DecodeLimits) -> Result<MlsMessage, MlsError> {
+        let Some(mut data) = data.strip_prefix(ENVELOPE_MAGIC) else {
+            return MlsMessage::from_bytes_with_limits(data, limits);
+        };
+
+        let header = EnvelopeHeader::mls_decode(&mut data)?;
+
+        if header.version != ENVELOPE_VERSION {
+            return Err(MlsError::UnsupportedEnvelopeVersion(header.version));
+        }
+
+        if !self.algorithms.contains(&header.algorithm) {
+            return Err(MlsError::UnsupportedCompressionAlgorithm(
+                header.algorithm.raw_value(),
+            ));
+        }
+
+        if header.decompressed_size > self.max_decompressed_size {
+            return Err(MlsError::DecompressedSizeLimitExceeded(
+                header.decompressed_size.into(),
+            ));
+        }
+
+        let decompressed = decompress(header.algorithm, data, header.decompressed_size)?;
+
+        MlsMessage::from_bytes_with_limits(&decompressed, limits)
+    }
+}
+
+fn compress(algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>, MlsError> {
+    match algorithm {
+        #[cfg(feature = "compression_deflate")]
+        CompressionAlgorithm::DEFLATE => {
+            use std::io::Write;
+
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+
+            encoder
+                .write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|_| MlsError::CompressionFailed)
+        }
+        #[cfg(feature = "compression_zstd")]
+        CompressionAlgorithm::ZSTD => {
+            zstd::bulk::compress(data, 0).map_err(|_| MlsError::CompressionFailed)
+        }
+        _ => Err(MlsError::UnsupportedCompressionAlgorithm(
+            algorithm.raw_value(),
+        )),
+    }
+}
+
+/// Decompress exactly `size` bytes, failing as soon as the output would
+/// exceed `size`.
+fn decompress(
+    algorithm: CompressionAlgorithm,
+    data: &[u8],
+    size: u32,
+) -> Result<Vec<u8>, MlsError> {
+    let decoder: Box<dyn Read + '_> = match algorithm {
+        #[cfg(feature = "compression_deflate")]
+        CompressionAlgorithm::DEFLATE => Box::new(flate2::read::DeflateDecoder::new(data)),
+        #[cfg(feature = "compression_zstd")]
+        CompressionAlgorithm::ZSTD => Box::new(
+            zstd::stream::read::Decoder::with_buffer(data)
+                .map_err(|_| MlsError::InvalidCompressedMessage)?,
+        ),
+        _ => {
+            return Err(MlsError::UnsupportedCompressionAlgorithm(
+                algorithm.raw_value(),
+            ))
+        }
+    };
+
+    let mut decompressed = Vec::new();
+
+    decoder
+        .take(u64::from(size) + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| MlsError::InvalidCompressedMessage)?;
+
+    if decompressed.len() != size as usize {
+        return Err(MlsError::InvalidCompressedMessage);
+    }
+
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{test_utils::test_group, DecodeLimit, DecodeLimits},
+        MlsMessage,
+    };
+
+    use super::{CompressionAlgorithm, MessageEnvelope, ENVELOPE_MAGIC};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn large_commit() -> MlsMessage {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let mut builder = alice.group.commit_builder();
+
+        for i in 0..8 {
+            let name = alloc::format!("member {i}");
+
+            let (_, key_package) =
+                test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, &name).await;
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        builder.build().await.unwrap().commit_message
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn envelope_round_trip() {
+        let message = large_commit().await;
+        let plain = message.to_bytes().unwrap();
+        let envelope = MessageEnvelope::new();
+
+        for algorithm in CompressionAlgorithm::supported() {
+            let wrapped = envelope.wrap(&message, Some(algorithm)).unwrap();
+
+            assert!(MessageEnvelope::is_envelope(&wrapped));
+            assert!(wrapped.len() < plain.len());
+            assert_eq!(
+                envelope.unwrap(&wrapped, &DecodeLimits::new()).unwrap(),
+                message
+            );
+        }
+
+        let wrapped = envelope.wrap(&message, None).unwrap();
+        assert_eq!(wrapped, plain);
+        assert_eq!(
+            envelope.unwrap(&plain, &DecodeLimits::new()).unwrap(),
+            message
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn decompression_is_limited() {
+        let message = large_commit().await;
+        let plain_len = message.to_bytes().unwrap().len() as u32;
+
+        for algorithm in CompressionAlgorithm::supported() {
+            let wrapped = MessageEnvelope::new()
+                .wrap(&message, Some(algorithm))
+                .unwrap();
+
+            let res = MessageEnvelope::new()
+                .with_max_decompressed_size(plain_len - 1)
+                .unwrap(&wrapped, &DecodeLimits::new());
+
+            assert_matches!(res, Err(MlsError::DecompressedSizeLimitExceeded(_)));
+
+            // Understating the size in the header does not bypass the limit
+            let mut understated = wrapped.clone();
+            let size_offset = ENVELOPE_MAGIC.len() + 3;
+            understated[size_offset..size_offset + 4].copy_from_slice(&16u32.to_be_bytes());
+
+            let res = MessageEnvelope::new().unwrap(&understated, &DecodeLimits::new());
+            assert_matches!(res, Err(MlsError::InvalidCompressedMessage));
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn decode_limits_apply_to_decompressed_messages() {
+        let message = large_commit().await;
+        let envelope = MessageEnvelope::new();
+        let limits = DecodeLimits::new().with_max_proposals(1);
+
+        let plain = envelope.wrap(&message, None).unwrap();
+        let res = envelope.unwrap(&plain, &limits);
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::Proposals))
+        );
+
+        for algorithm in CompressionAlgorithm::supported() {
+            let wrapped = envelope.wrap(&message, Some(algorithm)).unwrap();
+            let res = envelope.unwrap(&wrapped, &limits);
+            assert_matches!(
+                res,
+                Err(MlsError::DecodeLimitExceeded(DecodeLimit::Proposals))
+            );
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn algorithms_are_negotiated() {
+        let envelope = MessageEnvelope::new();
+        let unknown = CompressionAlgorithm::new(0xffff);
+
+        assert_eq!(envelope.negotiate(&[unknown]), None);
+
+        for algorithm in CompressionAlgorithm::supported() {
+            assert_eq!(envelope.negotiate(&[unknown, algorithm]), Some(algorithm));
+        }
+
+        let message = large_commit().await;
+
+        for algorithm in CompressionAlgorithm::supported() {
+            let wrapped = envelope.wrap(&message, Some(algorithm)).unwrap();
+            let res = envelope
+                .clone()
+                .with_algorithms(Vec::new())
+                .unwrap(&wrapped, &DecodeLimits::new());
+
+            assert_matches!(res, Err(MlsError::UnsupportedCompressionAlgorithm(_)));
+        }
+
+        let res = envelope.wrap(&message, Some(unknown));
+        assert_matches!(res, Err(MlsError::UnsupportedCompressionAlgorithm(0xffff)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn small_messages_are_not_compressed() {
+        let message = large_commit().await;
+        let envelope = MessageEnvelope::new().with_min_compressed_size(u32::MAX);
+
+        for algorithm in CompressionAlgorithm::supported() {
+            let wrapped = envelope.wrap(&message, Some(algorithm)).unwrap();
+            assert_eq!(wrapped, message.to_bytes().unwrap());
+        }
+    }
+}
@@ -11,15 +11,26 @@ use mls_rs_core::{group::ProposalType, identity::CredentialType};
 
 #[cfg(feature = "by_ref_proposal")]
 use mls_rs_core::{
+    crypto::SignaturePublicKey,
+    error::IntoAnyError,
     extension::ExtensionList,
-    identity::{IdentityProvider, SigningIdentity},
+    identity::{BasicCredential, IdentityProvider, SigningIdentity},
     time::MlsTime,
 };
 
+#[cfg(all(feature = "by_ref_proposal", feature = "x509"))]
+use mls_rs_core::identity::CertificateChain;
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::client::MlsError;
+
 use crate::group::ExportedTree;
 
 use mls_rs_core::crypto::HpkePublicKey;
 
+#[cfg(feature = "psk")]
+use mls_rs_core::psk::ExternalPskId;
+
 /// Application specific identifier.
 ///
 /// A custom application level identifier that can be optionally stored
@@ -111,6 +122,7 @@ impl MlsCodecExtension for RatchetTreeExt {
     safer_ffi_gen::ffi_type(clone, opaque)
 )]
 #[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequiredCapabilitiesExt {
     pub extensions: Vec<ExtensionType>,
     pub proposals: Vec<ProposalType>,
@@ -228,6 +240,92 @@ impl ExternalSendersExt {
     }
 }
 
+#[cfg(feature = "by_ref_proposal")]
+impl ExternalSendersExt {
+    /// Allow `sender` to send external proposals.
+    ///
+    /// Fails with [`MlsError::ExternalSenderAlreadyAllowed`] if a sender with
+    /// the same signature key is already allowed.
+    pub fn with_sender(mut self, sender: SigningIdentity) -> Result<Self, MlsError> {
+        let signature_key = &sender.signature_key;
+
+        if self
+            .allowed_senders
+            .iter()
+            .any(|other| &other.signature_key == signature_key)
+        {
+            return Err(MlsError::ExternalSenderAlreadyAllowed);
+        }
+
+        self.allowed_senders.push(sender);
+        Ok(self)
+    }
+
+    /// Allow the sender with a basic credential containing `identifier` and
+    /// the signature key `signature_key` to send external proposals.
+    pub fn with_basic_sender(
+        self,
+        identifier: Vec<u8>,
+        signature_key: SignaturePublicKey,
+    ) -> Result<Self, MlsError> {
+        let credential = BasicCredential::new(identifier).into_credential();
+        self.with_sender(SigningIdentity::new(credential, signature_key))
+    }
+
+    /// Allow the sender with the X.509 certificate chain `chain` to send
+    /// external proposals. `signature_key` must be the public key of the leaf
+    /// certificate of `chain`, which is checked by the identity provider when
+    /// the extension is used.
+    #[cfg(feature = "x509")]
+    pub fn with_x509_sender(
+        self,
+        chain: CertificateChain,
+        signature_key: SignaturePublicKey,
+    ) -> Result<Self, MlsError> {
+        self.with_sender(SigningIdentity::new(chain.into_credential(), signature_key))
+    }
+
+    /// Check that senders have distinct signature keys and credential types
+    /// in `supported_types`.
+    pub(crate) fn check_senders(&self, supported_types: &[CredentialType]) -> Result<(), MlsError> {
+        for (i, sender) in self.allowed_senders.iter().enumerate() {
+            let credential_type = sender.credential.credential_type();
+
+            if !supported_types.contains(&credential_type) {
+                return Err(MlsError::UnsupportedExternalSenderCredential(
+                    credential_type,
+                ));
+            }
+
+            if self.allowed_senders[..i]
+                .iter()
+                .any(|other| other.signature_key == sender.signature_key)
+            {
+                return Err(MlsError::ExternalSenderAlreadyAllowed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// [`Self::check_senders`] against the types supported by `provider`,
+    /// followed by the validation of each credential and its signature key by
+    /// `provider`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn validate<I: IdentityProvider>(
+        &self,
+        provider: &I,
+        timestamp: Option<MlsTime>,
+        group_context_extensions: &ExtensionList,
+    ) -> Result<(), MlsError> {
+        self.check_senders(&provider.supported_types())?;
+
+        self.verify_all(provider, timestamp, group_context_extensions)
+            .await
+            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
+    }
+}
+
 #[cfg(feature = "by_ref_proposal")]
 impl MlsCodecExtension for ExternalSendersExt {
     fn extension_type() -> ExtensionType {
@@ -235,6 +333,231 @@ impl MlsCodecExtension for ExternalSendersExt {
     }
 }
 
+/// Require new members joining through a Welcome message to know an
+/// external PSK.
+///
+/// When this extension is part of the group context, commits that add members
+/// automatically include a [`PreSharedKeyProposal`](crate::group::proposal::PreSharedKeyProposal)
+/// for `psk_id`. Existing members reject commits that add members without
+/// using it, and new members reject Welcome messages that do not use it.
+/// This includes a commit that sets the extension and adds members at the same
+/// time.
+///
+/// The PSK is part of the key schedule of these commits, so the committer,
+/// the new members and every existing member processing them must have the
+/// PSK in their [`PreSharedKeyStorage`](crate::PreSharedKeyStorage). A member
+/// without it fails to process the commit with
+/// [`MlsError::PskFailure`](crate::error::MlsError::PskFailure).
+#[cfg(feature = "psk")]
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct RequiredJoinPskExt {
+    /// External PSK that new members must know.
+    pub psk_id: ExternalPskId,
+}
+
+#[cfg(feature = "psk")]
+#[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
+impl RequiredJoinPskExt {
+    pub fn new(psk_id: ExternalPskId) -> Self {
+        Self { psk_id }
+    }
+
+    #[cfg(feature = "ffi")]
+    pub fn psk_id(&self) -> &ExternalPskId {
+        &self.psk_id
+    }
+}
+
+#[cfg(feature = "psk")]
+impl MlsCodecExtension for RequiredJoinPskExt {
+    fn extension_type() -> ExtensionType {
+        ExtensionType::REQUIRED_JOIN_PSK
+    }
+}
+
+/// Escrow the exporter secret of every epoch to a set of HPKE public keys,
+/// any `threshold` of which can recover it.
+///
+/// When this extension is part of the group context, each commit splits the
+/// exporter secret of the new epoch into one share per key in `escrow_keys`
+/// and produces an [`EscrowedExporterSecret`](crate::group::EscrowedExporterSecret)
+/// for each, returned in
+/// [`CommitOutput::escrowed_exporter_secrets`](crate::group::CommitOutput::escrowed_exporter_secrets).
+/// The threshold must be between 1 and the number of escrow keys, which is at
+/// most 255. As for any group context extension, all members must support it.
+#[cfg(feature = "message_escrow")]
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ExporterEscrowExt {
+    pub escrow_keys: Vec<HpkePublicKey>,
+    pub threshold: u8,
+}
+
+#[cfg(feature = "message_escrow")]
+#[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
+impl ExporterEscrowExt {
+    pub fn new(escrow_keys: Vec<HpkePublicKey>, threshold: u8) -> Self {
+        Self {
+            escrow_keys,
+            threshold,
+        }
+    }
+
+    #[cfg(feature = "ffi")]
+    pub fn escrow_keys(&self) -> &[HpkePublicKey] {
+        &self.escrow_keys
+    }
+
+    #[cfg(feature = "ffi")]
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+}
+
+#[cfg(feature = "message_escrow")]
+impl MlsCodecExtension for ExporterEscrowExt {
+    fn extension_type() -> ExtensionType {
+        ExtensionType::EXPORTER_ESCROW
+    }
+}
+
+/// Key that members may escrow their application message receive keys to.
+///
+/// Receive keys are only escrowed by an explicit call to
+/// [`Group::escrow_receive_keys`](crate::Group::escrow_receive_keys), which
+/// fails unless this extension is part of the group context. Placing the key
+/// in the group context makes the escrow visible to every member, and changing
+/// it requires a commit.
+#[cfg(feature = "message_escrow")]
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MessageEscrowExt {
+    pub escrow_key: HpkePublicKey,
+}
+
+#[cfg(feature = "message_escrow")]
+#[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
+impl MessageEscrowExt {
+    pub fn new(escrow_key: HpkePublicKey) -> Self {
+        Self { escrow_key }
+    }
+
+    #[cfg(feature = "ffi")]
+    pub fn escrow_key(&self) -> &HpkePublicKey {
+        &self.escrow_key
+    }
+}
+
+#[cfg(feature = "message_escrow")]
+impl MlsCodecExtension for MessageEscrowExt {
+    fn extension_type() -> ExtensionType {
+        ExtensionType::MESSAGE_ESCROW
+    }
+}
+
+/// Small application values owned by individual members.
+///
+/// Each member can set the values of its own slots by sending a
+/// [`SetMemberSlotProposal`](crate::group::SetMemberSlotProposal). Slots are
+/// only replicated by groups using
+/// [`MemberSlotRules`](crate::group::MemberSlotRules), which describes the
+/// conflict rules. Adding this extension to the group context enables the
+/// slots. Slots are ordered by leaf index and key.
+#[cfg(feature = "custom_proposal")]
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MemberSlotsExt {
+    pub slots: Vec<MemberSlot>,
+}
+
+/// Slot of a [`MemberSlotsExt`].
+#[cfg(feature = "custom_proposal")]
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct MemberSlot {
+    /// Leaf index of the member owning the slot.
+    pub leaf_index: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub key: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub value: Vec<u8>,
+}
+
+#[cfg(feature = "custom_proposal")]
+#[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
+impl MemberSlotsExt {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Value of the slot `key` of the member at `leaf_index`.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn get(&self, leaf_index: u32, key: &[u8]) -> Option<&[u8]> {
+        self.position(leaf_index, key)
+            .ok()
+            .map(|i| self.slots[i].value.as_slice())
+    }
+
+    /// Slots of the member at `leaf_index`, ordered by key.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn member_slots(&self, leaf_index: u32) -> impl Iterator<Item = &MemberSlot> {
+        self.slots
+            .iter()
+            .filter(move |slot| slot.leaf_index == leaf_index)
+    }
+
+    #[cfg(feature = "ffi")]
+    pub fn slots(&self) -> &[MemberSlot] {
+        &self.slots
+    }
+
+    pub(crate) fn set(&mut self, leaf_index: u32, key: Vec<u8>, value: Option<Vec<u8>>) {
+        match (self.position(leaf_index, &key), value) {
+            (Ok(i), Some(value)) => self.slots[i].value = value,
+            (Ok(i), None) => {
+                self.slots.remove(i);
+            }
+            (Err(i), Some(value)) => self.slots.insert(
+                i,
+                MemberSlot {
+                    leaf_index,
+                    key,
+                    value,
+                },
+            ),
+            (Err(_), None) => {}
+        }
+    }
+
+    fn position(&self, leaf_index: u32, key: &[u8]) -> Result<usize, usize> {
+        self.slots
+            .binary_search_by(|slot| (slot.leaf_index, slot.key.as_slice()).cmp(&(leaf_index, key)))
+    }
+}
+
+#[cfg(feature = "custom_proposal")]
+impl MlsCodecExtension for MemberSlotsExt {
+    fn extension_type() -> ExtensionType {
+        ExtensionType::MEMBER_SLOTS
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,6 +568,9 @@ mod tests {
         client::test_utils::TEST_CIPHER_SUITE, identity::test_utils::get_test_signing_identity,
     };
 
+    #[cfg(feature = "by_ref_proposal")]
+    use assert_matches::assert_matches;
+
     use mls_rs_core::extension::MlsExtension;
 
     use mls_rs_core::identity::BasicCredential;
@@ -315,6 +641,44 @@ mod tests {
         assert_eq!(ext, restored)
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[test]
+    fn external_senders_builder_rejects_duplicate_keys() {
+        let ext = ExternalSendersExt::new(vec![])
+            .with_basic_sender(b"a".to_vec(), vec![1].into())
+            .unwrap()
+            .with_basic_sender(b"b".to_vec(), vec![2].into())
+            .unwrap();
+
+        assert_eq!(ext.allowed_senders.len(), 2);
+        ext.check_senders(&[CredentialType::BASIC]).unwrap();
+
+        assert_matches!(
+            ext.check_senders(&[CredentialType::X509]),
+            Err(MlsError::UnsupportedExternalSenderCredential(
+                CredentialType::BASIC
+            ))
+        );
+
+        let res = ext.with_basic_sender(b"c".to_vec(), vec![1].into());
+
+        assert_matches!(res, Err(MlsError::ExternalSenderAlreadyAllowed));
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "x509"))]
+    #[test]
+    fn external_senders_builder_encodes_x509_credentials() {
+        let chain = CertificateChain::from(vec![vec![1, 2, 3]]);
+
+        let ext = ExternalSendersExt::new(vec![])
+            .with_x509_sender(chain.clone(), vec![1].into())
+            .unwrap();
+
+        let expected = SigningIdentity::new(chain.into_credential(), vec![1].into());
+
+        assert_eq!(ext.allowed_senders, vec![expected]);
+    }
+
     #[test]
     fn test_external_pub() {
         let ext = ExternalPubExt {
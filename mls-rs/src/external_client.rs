@@ -8,9 +8,13 @@ use crate::{
     KeyPackage,
 };
 
+mod authenticity;
+mod batch;
 pub mod builder;
+mod commit_authorizer;
 mod config;
-mod group;
+pub(crate) mod group;
+mod roster_group;
 
 pub(crate) use config::ExternalClientConfig;
 use mls_rs_core::{
@@ -20,7 +24,13 @@ use mls_rs_core::{
 
 use builder::{ExternalBaseConfig, ExternalClientBuilder};
 
-pub use group::{ExternalGroup, ExternalReceivedMessage, ExternalSnapshot};
+pub use authenticity::{MembershipTagStatus, MessageAuthenticityReport};
+pub use batch::{ExternalBatchItem, ExternalBatchOutput};
+pub use commit_authorizer::{
+    ApproveAllCommits, CommitAuthorizer, CommitDecision, CommitDenial, CommitSummary,
+};
+pub use group::{ExternalEpochRecord, ExternalGroup, ExternalReceivedMessage, ExternalSnapshot};
+pub use roster_group::{ExternalRosterGroup, ExternalRosterMessage, ExternalRosterSnapshot};
 
 /// A client capable of observing a group's state without having
 /// private keys required to read content.
@@ -97,6 +107,31 @@ where
         ExternalGroup::from_snapshot(self.config.clone(), snapshot).await
     }
 
+    /// Begin observing a group in roster-only mode, keeping only the roster
+    /// and the group context. See [ExternalRosterGroup] and
+    /// [observe_group](ExternalClient::observe_group).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn observe_group_roster_only(
+        &self,
+        group_info: MlsMessage,
+        tree_data: Option<ExportedTree<'_>>,
+    ) -> Result<ExternalRosterGroup<C>, MlsError> {
+        self.observe_group(group_info, tree_data)
+            .await
+            .map(ExternalGroup::into_roster_only)
+    }
+
+    /// Load a group observed in roster-only mode from a snapshot that was
+    /// generated by
+    /// [ExternalRosterGroup::snapshot](self::ExternalRosterGroup::snapshot).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn load_roster_group(
+        &self,
+        snapshot: ExternalRosterSnapshot,
+    ) -> Result<ExternalRosterGroup<C>, MlsError> {
+        ExternalRosterGroup::from_snapshot(self.config.clone(), snapshot).await
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn validate_key_package(
         &self,
@@ -116,7 +151,14 @@ where
 
         let id = self.config.identity_provider();
 
-        validate_key_package(&key_package, version, &cs, &id).await?;
+        validate_key_package(
+            &key_package,
+            version,
+            &cs,
+            &id,
+            self.config.lifetime_policy(),
+        )
+        .await?;
 
         Ok(key_package)
     }
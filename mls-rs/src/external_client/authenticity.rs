@@ -0,0 +1,152 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_core::{crypto::CipherSuiteProvider, identity::SigningIdentity};
+
+use crate::{
+    group::{
+        framing::{Content, PublicMessage},
+        message_signature::{AuthenticatedContent, MessageSigningContext},
+        state::GroupState,
+        Sender,
+    },
+    signer::Signable,
+    tree_kem::node::LeafIndex,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::{extension::ExternalSendersExt, group::proposal::Proposal};
+
+/// Outcome of checking the membership tag of a plaintext message.
+///
+/// External observers do not have access to the membership key of the group
+/// and can therefore only check that a tag is present when it is required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MembershipTagStatus {
+    /// The sender is a member and the message carries a membership tag. The
+    /// value of the tag can only be verified by group members.
+    Present,
+    /// The sender is a member but the message does not carry a membership tag.
+    Missing,
+    /// The sender is not a member and the message correctly carries no tag.
+    NotRequired,
+    /// The sender is not a member but the message carries a membership tag.
+    Unexpected,
+}
+
+impl MembershipTagStatus {
+    /// Determine if the message is acceptable with regards to its membership tag.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Present | Self::NotRequired)
+    }
+}
+
+/// Structured result of the authenticity checks performed on a plaintext
+/// message by [`ExternalGroup::message_authenticity`](crate::external_client::ExternalGroup::message_authenticity).
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct MessageAuthenticityReport {
+    /// Sender of the message as stated in the message.
+    pub sender: Sender,
+    /// Signing identity of the sender according to the current group state,
+    /// if one could be found.
+    pub sender_identity: Option<SigningIdentity>,
+    /// Whether the group id and epoch of the message match the current
+    /// state of the group.
+    pub epoch_matches: bool,
+    /// Result of checking the membership tag.
+    pub membership_tag: MembershipTagStatus,
+    /// Whether the signature verifies under the key of `sender_identity`.
+    pub signature_valid: bool,
+}
+
+impl MessageAuthenticityReport {
+    /// Determine if every check that can be performed by an external observer passed.
+    pub fn is_authentic(&self) -> bool {
+        self.epoch_matches && self.membership_tag.is_valid() && self.signature_valid
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        plaintext: &PublicMessage,
+        state: &GroupState,
+    ) -> Self {
+        let context = &state.context;
+        let auth_content = AuthenticatedContent::from(plaintext.clone());
+        let sender = auth_content.content.sender;
+
+        let epoch_matches = auth_content.content.group_id == context.group_id
+            && auth_content.content.epoch == context.epoch;
+
+        let membership_tag = match (sender, &plaintext.membership_tag) {
+            (Sender::Member(_), Some(_)) => MembershipTagStatus::Present,
+            (Sender::Member(_), None) => MembershipTagStatus::Missing,
+            (_, None) => MembershipTagStatus::NotRequired,
+            (_, Some(_)) => MembershipTagStatus::Unexpected,
+        };
+
+        let sender_identity = sender_identity(state, &auth_content);
+
+        let signature_valid = match &sender_identity {
+            Some(identity) => {
+                let signing_context = MessageSigningContext {
+                    group_context: Some(context),
+                    protocol_version: context.protocol_version,
+                };
+
+                auth_content
+                    .verify(
+                        cipher_suite_provider,
+                        &identity.signature_key,
+                        &signing_context,
+                    )
+                    .await
+                    .is_ok()
+            }
+            None => false,
+        };
+
+        Self {
+            sender,
+            sender_identity,
+            epoch_matches,
+            membership_tag,
+            signature_valid,
+        }
+    }
+}
+
+fn sender_identity(
+    state: &GroupState,
+    auth_content: &AuthenticatedContent,
+) -> Option<SigningIdentity> {
+    match (auth_content.content.sender, &auth_content.content.content) {
+        (Sender::Member(index), _) => state
+            .public_tree
+            .get_leaf_node(LeafIndex(index))
+            .ok()
+            .map(|leaf| leaf.signing_identity.clone()),
+        #[cfg(feature = "by_ref_proposal")]
+        (Sender::External(index), _) => state
+            .context
+            .extensions
+            .get_as::<ExternalSendersExt>()
+            .ok()
+            .flatten()
+            .and_then(|ext| ext.allowed_senders.get(index as usize).cloned()),
+        (Sender::NewMemberCommit, Content::Commit(commit)) => commit
+            .path
+            .as_ref()
+            .map(|path| path.leaf_node.signing_identity.clone()),
+        #[cfg(feature = "by_ref_proposal")]
+        (Sender::NewMemberProposal, Content::Proposal(proposal)) => match proposal.as_ref() {
+            Proposal::Add(add) => Some(add.key_package.leaf_node.signing_identity.clone()),
+            _ => None,
+        },
+        #[cfg(any(feature = "private_message", feature = "by_ref_proposal"))]
+        _ => None,
+    }
+}
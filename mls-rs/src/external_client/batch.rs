@@ -0,0 +1,219 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+
+use crate::{client::MlsError, group::framing::MlsMessage};
+
+use super::{ExternalClient, ExternalClientConfig, ExternalReceivedMessage, ExternalSnapshot};
+
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+use rayon::prelude::*;
+
+#[cfg(mls_build_async)]
+use futures::StreamExt;
+
+/// Messages for a single observed group, processed with
+/// [`ExternalClient::process_batch`].
+///
+/// The group is loaded from `snapshot` once and `messages` are processed in
+/// order against it.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExternalBatchItem {
+    pub snapshot: ExternalSnapshot,
+    pub messages: Vec<MlsMessage>,
+}
+
+impl ExternalBatchItem {
+    pub fn new(snapshot: ExternalSnapshot, messages: Vec<MlsMessage>) -> Self {
+        Self { snapshot, messages }
+    }
+}
+
+/// Outcome of processing an [`ExternalBatchItem`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ExternalBatchOutput {
+    /// Snapshot of the group after processing all messages of the item.
+    pub snapshot: ExternalSnapshot,
+    /// Result of processing each message of the item, in the same order.
+    /// A message that fails to process does not prevent processing the
+    /// following ones.
+    pub results: Vec<Result<ExternalReceivedMessage, MlsError>>,
+}
+
+impl<C> ExternalClient<C>
+where
+    C: ExternalClientConfig + Clone,
+{
+    /// Process messages for many observed groups at once.
+    ///
+    /// Items are processed concurrently, with at most `max_parallelism` items
+    /// in flight at any time. Each group is loaded from its snapshot only once
+    /// for all of its messages. The returned vector has one entry per item, in
+    /// the same order, which is an error only if the group could not be loaded
+    /// from the snapshot.
+    ///
+    /// Without the `rayon` feature, synchronous builds process items
+    /// sequentially.
+    #[cfg(not(mls_build_async))]
+    pub fn process_batch(
+        &self,
+        items: Vec<ExternalBatchItem>,
+        max_parallelism: usize,
+    ) -> Vec<Result<ExternalBatchOutput, MlsError>> {
+        #[cfg(feature = "rayon")]
+        {
+            let mut items = items;
+            let mut outputs = Vec::with_capacity(items.len());
+
+            while !items.is_empty() {
+                let rest = items.split_off(max_parallelism.clamp(1, items.len()));
+
+                let chunk: Vec<_> = items
+                    .into_par_iter()
+                    .map(|item| self.process_batch_item(item))
+                    .collect();
+
+                outputs.extend(chunk);
+                items = rest;
+            }
+
+            outputs
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let _ = max_parallelism;
+
+            items
+                .into_iter()
+                .map(|item| self.process_batch_item(item))
+                .collect()
+        }
+    }
+
+    /// Process messages for many observed groups at once.
+    ///
+    /// Items are processed concurrently, with at most `max_parallelism` items
+    /// in flight at any time. Each group is loaded from its snapshot only once
+    /// for all of its messages. The returned vector has one entry per item, in
+    /// the same order, which is an error only if the group could not be loaded
+    /// from the snapshot.
+    #[cfg(mls_build_async)]
+    pub async fn process_batch(
+        &self,
+        items: Vec<ExternalBatchItem>,
+        max_parallelism: usize,
+    ) -> Vec<Result<ExternalBatchOutput, MlsError>> {
+        futures::stream::iter(items)
+            .map(|item| self.process_batch_item(item))
+            .buffered(max_parallelism.max(1))
+            .collect()
+            .await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn process_batch_item(
+        &self,
+        item: ExternalBatchItem,
+    ) -> Result<ExternalBatchOutput, MlsError> {
+        let mut group = self.load_group(item.snapshot).await?;
+        let mut results = Vec::with_capacity(item.messages.len());
+
+        for message in item.messages {
+            results.push(group.process_incoming_message(message).await);
+        }
+
+        Ok(ExternalBatchOutput {
+            snapshot: group.snapshot(),
+            results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        external_client::{
+            group::test_utils::make_external_group, tests_utils::TestExternalClientBuilder,
+            ExternalReceivedMessage,
+        },
+        group::test_utils::test_group,
+    };
+
+    use super::ExternalBatchItem;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn batch_processes_messages_of_each_group_in_order() {
+        let server = TestExternalClientBuilder::new_for_test().build();
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let mut carol = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let alice_snapshot = make_external_group(&alice).await.snapshot();
+        let carol_snapshot = make_external_group(&carol).await.snapshot();
+
+        let mut alice_commits = vec![];
+
+        for _ in 0..2 {
+            let commit = alice.group.commit(vec![]).await.unwrap();
+            alice.group.apply_pending_commit().await.unwrap();
+            alice_commits.push(commit.commit_message);
+        }
+
+        // Replaying the first commit fails without affecting the others
+        alice_commits.push(alice_commits[0].clone());
+
+        let carol_commit = carol.group.commit(vec![]).await.unwrap().commit_message;
+        carol.group.apply_pending_commit().await.unwrap();
+
+        let items = vec![
+            ExternalBatchItem::new(alice_snapshot, alice_commits),
+            ExternalBatchItem::new(carol_snapshot, vec![carol_commit]),
+        ];
+
+        let outputs = server.process_batch(items, 2).await;
+
+        let [alice_output, carol_output] = outputs.as_slice() else {
+            panic!("expected one output per item");
+        };
+
+        let alice_output = alice_output.as_ref().unwrap();
+
+        assert_matches!(
+            alice_output.results.as_slice(),
+            [
+                Ok(ExternalReceivedMessage::Commit(_)),
+                Ok(ExternalReceivedMessage::Commit(_)),
+                Err(MlsError::InvalidEpoch)
+            ]
+        );
+
+        let alice_server = server
+            .load_group(alice_output.snapshot.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(alice_server.group_context(), alice.group.context());
+
+        let carol_output = carol_output.as_ref().unwrap();
+        assert_matches!(carol_output.results.as_slice(), [Ok(_)]);
+
+        let carol_server = server
+            .load_group(carol_output.snapshot.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(carol_server.group_context(), carol.group.context());
+    }
+}
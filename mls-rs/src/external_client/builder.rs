@@ -6,26 +6,30 @@
 //!
 //! See [`ExternalClientBuilder`].
 
+#[cfg(feature = "custom_proposal")]
+use crate::group::proposal::CustomProposalMetadata;
 use crate::{
     crypto::SignaturePublicKey,
     extension::ExtensionType,
-    external_client::{ExternalClient, ExternalClientConfig},
+    external_client::{ApproveAllCommits, CommitAuthorizer, ExternalClient, ExternalClientConfig},
     group::{
         mls_rules::{DefaultMlsRules, MlsRules},
         proposal::ProposalType,
+        DecodeLimits, LifetimePolicy,
     },
     identity::CredentialType,
     protocol_version::ProtocolVersion,
     tree_kem::Capabilities,
     CryptoProvider, Sealed,
 };
+
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
 };
 
 /// Base client configuration type when instantiating `ExternalClientBuilder`
-pub type ExternalBaseConfig = Config<Missing, DefaultMlsRules, Missing>;
+pub type ExternalBaseConfig = Config<Missing, DefaultMlsRules, Missing, ApproveAllCommits>;
 
 /// Builder for [`ExternalClient`]
 ///
@@ -112,6 +116,7 @@ impl ExternalClientBuilder<ExternalBaseConfig> {
             identity_provider: Missing,
             mls_rules: DefaultMlsRules::new(),
             crypto_provider: Missing,
+            commit_authorizer: ApproveAllCommits::new(),
             signing_data: None,
         }))
     }
@@ -154,6 +159,23 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
         ExternalClientBuilder(c)
     }
 
+    /// Add a custom proposal type to the list of proposal types supported by the client,
+    /// along with `metadata` describing how proposals of this type are validated.
+    ///
+    /// See [`ClientBuilder::custom_proposal_type_with_metadata`](crate::client_builder::ClientBuilder::custom_proposal_type_with_metadata).
+    #[cfg(feature = "custom_proposal")]
+    pub fn custom_proposal_type_with_metadata(
+        self,
+        type_: ProposalType,
+        metadata: CustomProposalMetadata,
+    ) -> ExternalClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        let settings = &mut c.0.settings;
+        settings.custom_proposal_types.push(type_);
+        settings.custom_proposal_metadata.insert(type_, metadata);
+        ExternalClientBuilder(c)
+    }
+
     /// Add a protocol version to the list of protocol versions supported by the client.
     ///
     /// If no protocol version is explicitly added, the client will support all protocol versions
@@ -198,6 +220,59 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
         ExternalClientBuilder(c)
     }
 
+    /// Specify the number of past epochs whose public state (group context and
+    /// roster) is retained by each external group and can be queried with
+    /// [`ExternalGroup::epoch_record`](crate::external_client::ExternalGroup::epoch_record).
+    ///
+    /// By default, no past epochs are retained.
+    pub fn epoch_history(self, epochs: usize) -> ExternalClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.epoch_history = epochs;
+        ExternalClientBuilder(c)
+    }
+
+    /// Enable the [proposal rejection cache](crate::group::ProposalRejectionCache)
+    /// of external groups.
+    ///
+    /// Proposals recorded as rejected by the application are remembered for
+    /// `ttl_in_epochs` epochs after the epoch in which they were rejected. By
+    /// default, the cache is disabled.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_ttl(
+        self,
+        ttl_in_epochs: u64,
+    ) -> ExternalClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.proposal_rejection_ttl = Some(ttl_in_epochs);
+        ExternalClientBuilder(c)
+    }
+
+    /// Set the limits applied to messages received by external groups before
+    /// their contents are validated.
+    ///
+    /// By default, [`DecodeLimits::default`] is used.
+    pub fn decode_limits(
+        self,
+        decode_limits: DecodeLimits,
+    ) -> ExternalClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.decode_limits = decode_limits;
+        ExternalClientBuilder(c)
+    }
+
+    /// Set the policy used by external groups to check lifetimes of leaf
+    /// nodes added to the group.
+    ///
+    /// By default, [`LifetimePolicy::default`] is used.
+    pub fn lifetime_policy(
+        self,
+        lifetime_policy: LifetimePolicy,
+    ) -> ExternalClientBuilder<IntoConfigOutput<C>> {
+        let mut c = self.0.into_config();
+        c.0.settings.lifetime_policy = lifetime_policy;
+        ExternalClientBuilder(c)
+    }
+
     /// Specify whether processed proposals should be cached by the external group. In case they
     /// are not cached by the group, they should be cached externally and inserted using
     /// `ExternalGroup::insert_proposal` before processing the next commit.
@@ -224,6 +299,7 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider,
             mls_rules: c.mls_rules,
             crypto_provider: c.crypto_provider,
+            commit_authorizer: c.commit_authorizer,
             signing_data: c.signing_data,
         }))
     }
@@ -244,6 +320,7 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider: c.identity_provider,
             mls_rules: c.mls_rules,
             crypto_provider,
+            commit_authorizer: c.commit_authorizer,
             signing_data: c.signing_data,
         }))
     }
@@ -265,6 +342,32 @@ impl<C: IntoConfig> ExternalClientBuilder<C> {
             identity_provider: c.identity_provider,
             mls_rules,
             crypto_provider: c.crypto_provider,
+            commit_authorizer: c.commit_authorizer,
+            signing_data: c.signing_data,
+        }))
+    }
+
+    /// Set the policy deciding which commits received by external groups may
+    /// be applied.
+    ///
+    /// The authorizer is called with a [`CommitSummary`](crate::external_client::CommitSummary)
+    /// of each valid commit. If it denies the commit, processing fails with
+    /// [`MlsError::CommitDenied`](crate::client::MlsError::CommitDenied) and
+    /// the group is left unchanged. By default, all commits are approved.
+    pub fn commit_authorizer<Ca>(
+        self,
+        commit_authorizer: Ca,
+    ) -> ExternalClientBuilder<WithCommitAuthorizer<Ca, C>>
+    where
+        Ca: CommitAuthorizer,
+    {
+        let Config(c) = self.0.into_config();
+        ExternalClientBuilder(Config(ConfigInner {
+            settings: c.settings,
+            identity_provider: c.identity_provider,
+            mls_rules: c.mls_rules,
+            crypto_provider: c.crypto_provider,
+            commit_authorizer,
             signing_data: c.signing_data,
         }))
     }
@@ -286,6 +389,7 @@ where
     C::IdentityProvider: IdentityProvider + Clone,
     C::MlsRules: MlsRules + Clone,
     C::CryptoProvider: CryptoProvider + Clone,
+    C::CommitAuthorizer: CommitAuthorizer + Clone,
 {
     pub(crate) fn build_config(self) -> IntoConfigOutput<C> {
         let mut c = self.0.into_config();
@@ -315,37 +419,62 @@ pub struct Missing;
 /// Change the identity validator used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::identity_provider`].
-pub type WithIdentityProvider<I, C> =
-    Config<I, <C as IntoConfig>::MlsRules, <C as IntoConfig>::CryptoProvider>;
+pub type WithIdentityProvider<I, C> = Config<
+    I,
+    <C as IntoConfig>::MlsRules,
+    <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::CommitAuthorizer,
+>;
 
 /// Change the proposal filter used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::mls_rules`].
-pub type WithMlsRules<Pr, C> =
-    Config<<C as IntoConfig>::IdentityProvider, Pr, <C as IntoConfig>::CryptoProvider>;
+pub type WithMlsRules<Pr, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    Pr,
+    <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::CommitAuthorizer,
+>;
 
 /// Change the crypto provider used by a client configuration.
 ///
 /// See [`ExternalClientBuilder::crypto_provider`].
-pub type WithCryptoProvider<Cp, C> =
-    Config<<C as IntoConfig>::IdentityProvider, <C as IntoConfig>::MlsRules, Cp>;
+pub type WithCryptoProvider<Cp, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    <C as IntoConfig>::MlsRules,
+    Cp,
+    <C as IntoConfig>::CommitAuthorizer,
+>;
+
+/// Change the commit authorizer used by a client configuration.
+///
+/// See [`ExternalClientBuilder::commit_authorizer`].
+pub type WithCommitAuthorizer<Ca, C> = Config<
+    <C as IntoConfig>::IdentityProvider,
+    <C as IntoConfig>::MlsRules,
+    <C as IntoConfig>::CryptoProvider,
+    Ca,
+>;
 
 /// Helper alias for `Config`.
 pub type IntoConfigOutput<C> = Config<
     <C as IntoConfig>::IdentityProvider,
     <C as IntoConfig>::MlsRules,
     <C as IntoConfig>::CryptoProvider,
+    <C as IntoConfig>::CommitAuthorizer,
 >;
 
-impl<Ip, Pr, Cp> ExternalClientConfig for ConfigInner<Ip, Pr, Cp>
+impl<Ip, Pr, Cp, Ca> ExternalClientConfig for ConfigInner<Ip, Pr, Cp, Ca>
 where
     Ip: IdentityProvider + Clone,
     Pr: MlsRules + Clone,
     Cp: CryptoProvider + Clone,
+    Ca: CommitAuthorizer + Clone,
 {
     type IdentityProvider = Ip;
     type MlsRules = Pr;
     type CryptoProvider = Cp;
+    type CommitAuthorizer = Ca;
 
     fn supported_extensions(&self) -> Vec<ExtensionType> {
         self.settings.extension_types.clone()
@@ -374,6 +503,10 @@ where
         self.mls_rules.clone()
     }
 
+    fn commit_authorizer(&self) -> Self::CommitAuthorizer {
+        self.commit_authorizer.clone()
+    }
+
     fn max_epoch_jitter(&self) -> Option<u64> {
         self.settings.max_epoch_jitter
     }
@@ -382,20 +515,46 @@ where
         self.settings.cache_proposals
     }
 
+    fn epoch_history(&self) -> usize {
+        self.settings.epoch_history
+    }
+
+    fn proposal_rejection_ttl(&self) -> Option<u64> {
+        self.settings.proposal_rejection_ttl
+    }
+
+    fn decode_limits(&self) -> DecodeLimits {
+        self.settings.decode_limits
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.settings.lifetime_policy
+    }
+
     fn supported_custom_proposals(&self) -> Vec<ProposalType> {
         self.settings.custom_proposal_types.clone()
     }
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.settings
+            .custom_proposal_metadata
+            .get(&proposal_type)
+            .copied()
+            .unwrap_or_default()
+    }
 }
 
-impl<Ip, Mpf, Cp> Sealed for Config<Ip, Mpf, Cp> {}
+impl<Ip, Mpf, Cp, Ca> Sealed for Config<Ip, Mpf, Cp, Ca> {}
 
-impl<Ip, Pr, Cp> MlsConfig for Config<Ip, Pr, Cp>
+impl<Ip, Pr, Cp, Ca> MlsConfig for Config<Ip, Pr, Cp, Ca>
 where
     Ip: IdentityProvider + Clone,
     Pr: MlsRules + Clone,
     Cp: CryptoProvider + Clone,
+    Ca: CommitAuthorizer + Clone,
 {
-    type Output = ConfigInner<Ip, Pr, Cp>;
+    type Output = ConfigInner<Ip, Pr, Cp, Ca>;
 
     fn get(&self) -> &Self::Output {
         &self.0
@@ -420,6 +579,7 @@ impl<T: MlsConfig> ExternalClientConfig for T {
     type IdentityProvider = <T::Output as ExternalClientConfig>::IdentityProvider;
     type MlsRules = <T::Output as ExternalClientConfig>::MlsRules;
     type CryptoProvider = <T::Output as ExternalClientConfig>::CryptoProvider;
+    type CommitAuthorizer = <T::Output as ExternalClientConfig>::CommitAuthorizer;
 
     fn supported_extensions(&self) -> Vec<ExtensionType> {
         self.get().supported_extensions()
@@ -433,6 +593,11 @@ impl<T: MlsConfig> ExternalClientConfig for T {
         self.get().supported_custom_proposals()
     }
 
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.get().custom_proposal_metadata(proposal_type)
+    }
+
     fn identity_provider(&self) -> Self::IdentityProvider {
         self.get().identity_provider()
     }
@@ -449,6 +614,10 @@ impl<T: MlsConfig> ExternalClientConfig for T {
         self.get().mls_rules()
     }
 
+    fn commit_authorizer(&self) -> Self::CommitAuthorizer {
+        self.get().commit_authorizer()
+    }
+
     fn cache_proposals(&self) -> bool {
         self.get().cache_proposals()
     }
@@ -457,6 +626,22 @@ impl<T: MlsConfig> ExternalClientConfig for T {
         self.get().max_epoch_jitter()
     }
 
+    fn epoch_history(&self) -> usize {
+        self.get().epoch_history()
+    }
+
+    fn proposal_rejection_ttl(&self) -> Option<u64> {
+        self.get().proposal_rejection_ttl()
+    }
+
+    fn decode_limits(&self) -> DecodeLimits {
+        self.get().decode_limits()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.get().lifetime_policy()
+    }
+
     fn capabilities(&self) -> Capabilities {
         self.get().capabilities()
     }
@@ -474,18 +659,29 @@ impl<T: MlsConfig> ExternalClientConfig for T {
 pub(crate) struct Settings {
     pub(crate) extension_types: Vec<ExtensionType>,
     pub(crate) custom_proposal_types: Vec<ProposalType>,
+    #[cfg(feature = "custom_proposal")]
+    pub(crate) custom_proposal_metadata: HashMap<ProposalType, CustomProposalMetadata>,
     pub(crate) protocol_versions: Vec<ProtocolVersion>,
     pub(crate) external_signing_keys: HashMap<Vec<u8>, SignaturePublicKey>,
     pub(crate) max_epoch_jitter: Option<u64>,
     pub(crate) cache_proposals: bool,
+    pub(crate) epoch_history: usize,
+    pub(crate) proposal_rejection_ttl: Option<u64>,
+    pub(crate) decode_limits: DecodeLimits,
+    pub(crate) lifetime_policy: LifetimePolicy,
 }
 
 impl Debug for Settings {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Settings")
-            .field("extension_types", &self.extension_types)
-            .field("custom_proposal_types", &self.custom_proposal_types)
-            .field("protocol_versions", &self.protocol_versions)
+        let mut f = f.debug_struct("Settings");
+
+        f.field("extension_types", &self.extension_types)
+            .field("custom_proposal_types", &self.custom_proposal_types);
+
+        #[cfg(feature = "custom_proposal")]
+        f.field("custom_proposal_metadata", &self.custom_proposal_metadata);
+
+        f.field("protocol_versions", &self.protocol_versions)
             .field(
                 "external_signing_keys",
                 &mls_rs_core::debug::pretty_with(|f| {
@@ -500,6 +696,10 @@ impl Debug for Settings {
             )
             .field("max_epoch_jitter", &self.max_epoch_jitter)
             .field("cache_proposals", &self.cache_proposals)
+            .field("epoch_history", &self.epoch_history)
+            .field("proposal_rejection_ttl", &self.proposal_rejection_ttl)
+            .field("decode_limits", &self.decode_limits)
+            .field("lifetime_policy", &self.lifetime_policy)
             .finish()
     }
 }
@@ -513,6 +713,12 @@ impl Default for Settings {
             external_signing_keys: Default::default(),
             max_epoch_jitter: None,
             custom_proposal_types: vec![],
+            #[cfg(feature = "custom_proposal")]
+            custom_proposal_metadata: Default::default(),
+            epoch_history: 0,
+            proposal_rejection_ttl: None,
+            decode_limits: Default::default(),
+            lifetime_policy: Default::default(),
         }
     }
 }
@@ -525,14 +731,15 @@ mod private {
     use super::{IntoConfigOutput, Settings};
 
     #[derive(Clone, Debug)]
-    pub struct Config<Ip, Pr, Cp>(pub(crate) ConfigInner<Ip, Pr, Cp>);
+    pub struct Config<Ip, Pr, Cp, Ca>(pub(crate) ConfigInner<Ip, Pr, Cp, Ca>);
 
     #[derive(Clone, Debug)]
-    pub struct ConfigInner<Ip, Mpf, Cp> {
+    pub struct ConfigInner<Ip, Mpf, Cp, Ca> {
         pub(crate) settings: Settings,
         pub(crate) identity_provider: Ip,
         pub(crate) mls_rules: Mpf,
         pub(crate) crypto_provider: Cp,
+        pub(crate) commit_authorizer: Ca,
         pub(crate) signing_data: Option<(SignatureSecretKey, SigningIdentity)>,
     }
 
@@ -540,14 +747,16 @@ mod private {
         type IdentityProvider;
         type MlsRules;
         type CryptoProvider;
+        type CommitAuthorizer;
 
         fn into_config(self) -> IntoConfigOutput<Self>;
     }
 
-    impl<Ip, Pr, Cp> IntoConfig for Config<Ip, Pr, Cp> {
+    impl<Ip, Pr, Cp, Ca> IntoConfig for Config<Ip, Pr, Cp, Ca> {
         type IdentityProvider = Ip;
         type MlsRules = Pr;
         type CryptoProvider = Cp;
+        type CommitAuthorizer = Ca;
 
         fn into_config(self) -> Self {
             self
@@ -0,0 +1,121 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::convert::Infallible;
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    error::IntoAnyError,
+    extension::ExtensionList,
+    group::{Member, MemberUpdate},
+};
+
+use crate::client::MlsError;
+
+/// Summary of a commit received by an
+/// [`ExternalGroup`](crate::external_client::ExternalGroup), provided to a
+/// [`CommitAuthorizer`] after the commit was validated and before it is
+/// applied.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct CommitSummary {
+    /// Epoch in which the commit was sent.
+    pub epoch: u64,
+    /// Member that sent the commit. For external commits, this is the member
+    /// joining the group.
+    pub committer: Member,
+    /// Whether the commit is an external commit.
+    pub is_external: bool,
+    /// Members added by the commit.
+    pub added: Vec<Member>,
+    /// Members removed by the commit, as they appear in the current epoch.
+    pub removed: Vec<Member>,
+    /// Members updating their leaf with an update proposal covered by the
+    /// commit.
+    pub updated: Vec<MemberUpdate>,
+    /// Whether the commit contains an update path.
+    pub has_path: bool,
+    /// New group context extensions, if the commit changes them.
+    pub new_extensions: Option<ExtensionList>,
+}
+
+/// Reason for denying a commit, surfaced as [`MlsError::CommitDenied`].
+///
+/// A denial can be serialized to relay it to the sender of the commit.
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct CommitDenial {
+    /// Application defined code identifying the reason.
+    pub code: u16,
+    /// Human readable description of the reason.
+    pub reason: String,
+}
+
+impl CommitDenial {
+    pub fn new(code: u16, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    /// Serialize the denial.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        Ok(self.mls_encode_to_vec()?)
+    }
+
+    /// Deserialize a denial.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Ok(Self::mls_decode(&mut &*bytes)?)
+    }
+}
+
+/// Decision of a [`CommitAuthorizer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommitDecision {
+    Approve,
+    Deny(CommitDenial),
+}
+
+/// Application policy approving or denying commits before they are applied
+/// by an [`ExternalGroup`](crate::external_client::ExternalGroup).
+///
+/// A delivery service can use an authorizer to decide which commits become
+/// canonical. Commits are only passed to the authorizer once they are known
+/// to be valid, and a denied commit leaves the group unchanged.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait CommitAuthorizer: Send + Sync {
+    /// Error type that the authorizer returns on internal failure.
+    type Error: IntoAnyError;
+
+    /// Decide whether the commit summarized by `summary` may be applied.
+    async fn authorize_commit(
+        &self,
+        summary: &CommitSummary,
+    ) -> Result<CommitDecision, Self::Error>;
+}
+
+/// Authorizer approving all commits, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+#[non_exhaustive]
+pub struct ApproveAllCommits;
+
+impl ApproveAllCommits {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl CommitAuthorizer for ApproveAllCommits {
+    type Error = Infallible;
+
+    async fn authorize_commit(
+        &self,
+        _summary: &CommitSummary,
+    ) -> Result<CommitDecision, Self::Error> {
+        Ok(CommitDecision::Approve)
+    }
+}
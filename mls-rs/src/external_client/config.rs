@@ -4,10 +4,14 @@
 
 use mls_rs_core::identity::IdentityProvider;
 
+#[cfg(feature = "custom_proposal")]
+use crate::group::proposal::CustomProposalMetadata;
+
 use crate::{
     crypto::SignaturePublicKey,
     extension::ExtensionType,
-    group::{mls_rules::MlsRules, proposal::ProposalType},
+    external_client::CommitAuthorizer,
+    group::{mls_rules::MlsRules, proposal::ProposalType, DecodeLimits, LifetimePolicy},
     identity::CredentialType,
     protocol_version::ProtocolVersion,
     tree_kem::Capabilities,
@@ -18,15 +22,22 @@ pub trait ExternalClientConfig: Send + Sync + Clone {
     type IdentityProvider: IdentityProvider + Clone;
     type MlsRules: MlsRules + Clone;
     type CryptoProvider: CryptoProvider;
+    type CommitAuthorizer: CommitAuthorizer + Clone;
 
     fn supported_extensions(&self) -> Vec<ExtensionType>;
     fn supported_custom_proposals(&self) -> Vec<ProposalType>;
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, _proposal_type: ProposalType) -> CustomProposalMetadata {
+        CustomProposalMetadata::default()
+    }
     fn supported_protocol_versions(&self) -> Vec<ProtocolVersion>;
     fn identity_provider(&self) -> Self::IdentityProvider;
     fn crypto_provider(&self) -> Self::CryptoProvider;
     fn external_signing_key(&self, external_key_id: &[u8]) -> Option<SignaturePublicKey>;
 
     fn mls_rules(&self) -> Self::MlsRules;
+    fn commit_authorizer(&self) -> Self::CommitAuthorizer;
 
     fn cache_proposals(&self) -> bool;
 
@@ -34,6 +45,22 @@ pub trait ExternalClientConfig: Send + Sync + Clone {
         None
     }
 
+    fn epoch_history(&self) -> usize {
+        0
+    }
+
+    fn proposal_rejection_ttl(&self) -> Option<u64> {
+        None
+    }
+
+    fn decode_limits(&self) -> DecodeLimits {
+        DecodeLimits::default()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        LifetimePolicy::default()
+    }
+
     fn capabilities(&self) -> Capabilities {
         Capabilities {
             protocol_versions: self.supported_protocol_versions(),
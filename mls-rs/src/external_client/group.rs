@@ -4,19 +4,25 @@
 
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use mls_rs_core::{
-    crypto::SignatureSecretKey, error::IntoAnyError, extension::ExtensionList, group::Member,
+    crypto::SignatureSecretKey,
+    error::IntoAnyError,
+    extension::ExtensionList,
+    group::{Member, MemberUpdate},
     identity::IdentityProvider,
 };
 
 use crate::{
     cipher_suite::CipherSuite,
     client::MlsError,
-    external_client::ExternalClientConfig,
+    external_client::{
+        CommitAuthorizer, CommitDecision, CommitSummary, ExternalClientConfig,
+        MessageAuthenticityReport,
+    },
     group::{
         cipher_suite_provider,
         confirmation_tag::ConfirmationTag,
-        framing::PublicMessage,
-        member_from_leaf_node,
+        framing::{MlsMessagePayload, PublicMessage},
+        member_from_key_package, member_from_leaf_node,
         message_processor::{
             ApplicationMessageDescription, CommitMessageDescription, EventOrContent,
             MessageProcessor, ProposalMessageDescription, ProvisionalState,
@@ -24,8 +30,8 @@ use crate::{
         snapshot::RawGroupState,
         state::GroupState,
         transcript_hash::InterimTranscriptHash,
-        validate_group_info_joiner, ContentType, ExportedTree, GroupContext, GroupInfo, Roster,
-        Welcome,
+        validate_group_info_joiner, ContentType, DecodeLimits, ExportedTree, GroupContext,
+        GroupInfo, LifetimePolicy, Roster, Welcome,
     },
     identity::SigningIdentity,
     protocol_version::ProtocolVersion,
@@ -37,12 +43,9 @@ use crate::{
 #[cfg(feature = "by_ref_proposal")]
 use crate::{
     group::{
-        framing::{Content, MlsMessagePayload},
-        message_processor::CachedProposal,
-        message_signature::AuthenticatedContent,
-        proposal::Proposal,
-        proposal_ref::ProposalRef,
-        Sender,
+        framing::Content, message_processor::CachedProposal,
+        message_signature::AuthenticatedContent, proposal::Proposal, proposal_ref::ProposalRef,
+        ProposalRejectionCache, Sender,
     },
     WireFormat,
 };
@@ -50,6 +53,9 @@ use crate::{
 #[cfg(all(feature = "by_ref_proposal", feature = "custom_proposal"))]
 use crate::group::proposal::CustomProposal;
 
+#[cfg(feature = "custom_proposal")]
+use crate::group::proposal::{CustomProposalMetadata, ProposalType};
+
 #[cfg(feature = "by_ref_proposal")]
 use mls_rs_core::{crypto::CipherSuiteProvider, psk::ExternalPskId};
 
@@ -71,6 +77,8 @@ use crate::{
 use crate::group::framing::PrivateMessage;
 
 use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
 
 /// The result of processing an [ExternalGroup](ExternalGroup) message using
 /// [process_incoming_message](ExternalGroup::process_incoming_message)
@@ -91,6 +99,42 @@ pub enum ExternalReceivedMessage {
     KeyPackage(KeyPackage),
 }
 
+/// Public state of a single epoch of an [ExternalGroup](ExternalGroup).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalEpochRecord {
+    context: GroupContext,
+    members: Vec<Member>,
+}
+
+impl ExternalEpochRecord {
+    fn new(state: &GroupState) -> Self {
+        Self {
+            context: state.context.clone(),
+            members: state.public_tree.roster().members(),
+        }
+    }
+
+    /// Epoch this record describes.
+    pub fn epoch(&self) -> u64 {
+        self.context.epoch
+    }
+
+    /// Group context of the epoch.
+    pub fn group_context(&self) -> &GroupContext {
+        &self.context
+    }
+
+    /// Members of the group in the epoch.
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Tree hash of the epoch.
+    pub fn tree_hash(&self) -> &[u8] {
+        &self.context.tree_hash
+    }
+}
+
 /// A handle to an observed group that can track plaintext control messages
 /// and the resulting group state.
 #[derive(Clone)]
@@ -102,6 +146,9 @@ where
     pub(crate) cipher_suite_provider: <C::CryptoProvider as CryptoProvider>::CipherSuiteProvider,
     pub(crate) state: GroupState,
     pub(crate) signing_data: Option<(SignatureSecretKey, SigningIdentity)>,
+    pub(crate) epoch_history: VecDeque<ExternalEpochRecord>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) rejected_proposals: ProposalRejectionCache,
 }
 
 impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
@@ -133,6 +180,10 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
             tree_data,
             &config.identity_provider(),
             &cipher_suite_provider,
+            &config.decode_limits(),
+            &config.mls_rules(),
+            Default::default(),
+            None,
         )
         .await?;
 
@@ -144,7 +195,6 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         .await?;
 
         Ok(Self {
-            config,
             signing_data,
             state: GroupState::new(
                 group_info.group_context,
@@ -153,6 +203,10 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
                 group_info.confirmation_tag,
             ),
             cipher_suite_provider,
+            epoch_history: VecDeque::new(),
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: ProposalRejectionCache::new(config.proposal_rejection_ttl()),
+            config,
         })
     }
 
@@ -189,6 +243,31 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         .await
     }
 
+    /// Check the authenticity of a plaintext message without processing it.
+    ///
+    /// The returned report describes the result of each check individually,
+    /// allowing server policy to decide how to handle messages that fail
+    /// some of them. The group state is not modified.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MlsError::UnexpectedMessageType`] if `message` is not a
+    /// [`PublicMessage`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn message_authenticity(
+        &self,
+        message: &MlsMessage,
+    ) -> Result<MessageAuthenticityReport, MlsError> {
+        let MlsMessagePayload::Plain(plaintext) = &message.payload else {
+            return Err(MlsError::UnexpectedMessageType);
+        };
+
+        Ok(
+            MessageAuthenticityReport::new(&self.cipher_suite_provider, plaintext, &self.state)
+                .await,
+        )
+    }
+
     /// Replay a proposal message into the group skipping all validation steps.
     #[cfg(feature = "by_ref_proposal")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -410,6 +489,18 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         proposal: CustomProposal,
         authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
+        let metadata = self.custom_proposal_metadata(proposal.proposal_type());
+
+        if !metadata.external_senders_allowed {
+            return Err(MlsError::InvalidProposalTypeForSender);
+        }
+
+        if metadata.by_value_only {
+            return Err(MlsError::ProposalTypeRequiresByValue(
+                proposal.proposal_type(),
+            ));
+        }
+
         self.propose(Proposal::Custom(proposal), authenticated_data)
             .await
     }
@@ -516,6 +607,84 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
         &self.group_state().context.tree_hash
     }
 
+    /// Compute the tree hash of the current ratchet tree without modifying
+    /// the group.
+    ///
+    /// The result is [`ExternalGroup::tree_hash`]. It is answered from the
+    /// cached hashes of the tree when they are initialized, which is the case
+    /// after processing a commit and after
+    /// [`ExternalGroup::ensure_tree_hashes`]. Otherwise every node is hashed.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compute_tree_hash(&self) -> Result<Vec<u8>, MlsError> {
+        self.group_state()
+            .public_tree
+            .compute_tree_hash(&self.cipher_suite_provider)
+            .await
+    }
+
+    /// Initialize the cached hashes of the current ratchet tree, so that
+    /// [`ExternalGroup::compute_tree_hash`] can be answered from a shared
+    /// reference without hashing the whole tree.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn ensure_tree_hashes(&mut self) -> Result<(), MlsError> {
+        self.state
+            .public_tree
+            .ensure_hashes(&self.cipher_suite_provider)
+            .await
+    }
+
+    /// Get the public state of `epoch`.
+    ///
+    /// Past epochs are available if they are among the most recent ones
+    /// retained according to
+    /// [ExternalClientBuilder::epoch_history](crate::external_client::builder::ExternalClientBuilder::epoch_history).
+    /// Returns `None` for epochs that are not retained.
+    pub fn epoch_record(&self, epoch: u64) -> Option<ExternalEpochRecord> {
+        if epoch == self.group_context().epoch {
+            return Some(ExternalEpochRecord::new(self.group_state()));
+        }
+
+        self.epoch_history
+            .iter()
+            .find(|record| record.epoch() == epoch)
+            .cloned()
+    }
+
+    /// Iterate over the retained past epochs, oldest first.
+    ///
+    /// The retained epochs are not part of an
+    /// [ExternalSnapshot](ExternalSnapshot).
+    pub fn epoch_history(&self) -> impl Iterator<Item = &ExternalEpochRecord> {
+        self.epoch_history.iter()
+    }
+
+    /// Determine if the proposal identified by `proposal_ref` was recorded as
+    /// rejected within the configured
+    /// [rejection TTL](crate::external_client::builder::ExternalClientBuilder::proposal_rejection_ttl).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn is_proposal_rejected(&self, proposal_ref: &ProposalRef) -> bool {
+        self.rejected_proposals
+            .is_rejected(proposal_ref, self.group_context().epoch)
+    }
+
+    /// Cache of by-reference proposals that were recorded as rejected with
+    /// [proposal_rejection_cache_mut](Self::proposal_rejection_cache_mut).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_cache(&self) -> &ProposalRejectionCache {
+        &self.rejected_proposals
+    }
+
+    /// Mutable access to the cache of rejected proposals, allowing applications
+    /// to record proposals they rejected.
+    ///
+    /// Proposals left out of a commit are not recorded automatically since the
+    /// committer may not have received them. They are listed in
+    /// [StateUpdate::unused_proposals](crate::group::StateUpdate::unused_proposals).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_cache_mut(&mut self) -> &mut ProposalRejectionCache {
+        &mut self.rejected_proposals
+    }
+
     /// Find a member based on their identity.
     ///
     /// Identities are matched based on the
@@ -553,6 +722,72 @@ impl<C: ExternalClientConfig + Clone> ExternalGroup<C> {
     }
 }
 
+impl<C> ExternalGroup<C>
+where
+    C: ExternalClientConfig + Clone,
+{
+    fn commit_summary(
+        &self,
+        provisional_state: &ProvisionalState,
+        committer: LeafIndex,
+        is_external: bool,
+        has_path: bool,
+    ) -> Result<CommitSummary, MlsError> {
+        let proposals = &provisional_state.applied_proposals;
+        let current_tree = &self.state.public_tree;
+        let new_tree = &provisional_state.public_tree;
+
+        let added = proposals
+            .additions
+            .iter()
+            .zip(&provisional_state.indexes_of_added_kpkgs)
+            .map(|(p, index)| member_from_key_package(&p.proposal.key_package, *index))
+            .collect();
+
+        let removed = proposals
+            .removals
+            .iter()
+            .map(|p| {
+                let index = p.proposal.to_remove;
+                Ok(member_from_leaf_node(
+                    current_tree.get_leaf_node(index)?,
+                    index,
+                ))
+            })
+            .collect::<Result<_, MlsError>>()?;
+
+        #[cfg(feature = "by_ref_proposal")]
+        let updated = proposals
+            .updates
+            .iter()
+            .zip(&proposals.update_senders)
+            .map(|(p, index)| {
+                Ok(MemberUpdate::new(
+                    member_from_leaf_node(current_tree.get_leaf_node(*index)?, *index),
+                    member_from_leaf_node(&p.proposal.leaf_node, *index),
+                ))
+            })
+            .collect::<Result<_, MlsError>>()?;
+
+        #[cfg(not(feature = "by_ref_proposal"))]
+        let updated = Vec::new();
+
+        let new_extensions = &provisional_state.group_context.extensions;
+
+        Ok(CommitSummary {
+            epoch: self.state.context.epoch,
+            committer: member_from_leaf_node(new_tree.get_leaf_node(committer)?, committer),
+            is_external,
+            added,
+            removed,
+            updated,
+            has_path,
+            new_extensions: (new_extensions != &self.state.context.extensions)
+                .then(|| new_extensions.clone()),
+        })
+    }
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
 #[cfg_attr(
@@ -578,6 +813,14 @@ where
         self.config.mls_rules()
     }
 
+    fn decode_limits(&self) -> DecodeLimits {
+        self.config.decode_limits()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.config.lifetime_policy()
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn verify_plaintext_authentication(
         &self,
@@ -612,6 +855,20 @@ where
         confirmation_tag: &ConfirmationTag,
         provisional_public_state: ProvisionalState,
     ) -> Result<(), MlsError> {
+        let epoch_history = self.config.epoch_history();
+
+        if epoch_history > 0 {
+            if self.epoch_history.len() >= epoch_history {
+                self.epoch_history.pop_front();
+            }
+
+            self.epoch_history
+                .push_back(ExternalEpochRecord::new(&self.state));
+        }
+
+        #[cfg(feature = "by_ref_proposal")]
+        self.rejected_proposals.prune(self.state.context.epoch);
+
         self.state.context = provisional_public_state.group_context;
         #[cfg(feature = "by_ref_proposal")]
         self.state.proposals.clear();
@@ -642,6 +899,28 @@ where
         true
     }
 
+    async fn authorize_commit(
+        &self,
+        provisional_state: &ProvisionalState,
+        committer: LeafIndex,
+        is_external: bool,
+        has_path: bool,
+    ) -> Result<(), MlsError> {
+        let summary = self.commit_summary(provisional_state, committer, is_external, has_path)?;
+
+        let decision = self
+            .config
+            .commit_authorizer()
+            .authorize_commit(&summary)
+            .await
+            .map_err(|e| MlsError::CommitAuthorizerError(e.into_any_error()))?;
+
+        match decision {
+            CommitDecision::Approve => Ok(()),
+            CommitDecision::Deny(denial) => Err(MlsError::CommitDenied(denial)),
+        }
+    }
+
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64> {
         self.config
@@ -649,6 +928,11 @@ where
             .map(|j| self.state.context.epoch - j)
     }
 
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.config.custom_proposal_metadata(proposal_type)
+    }
+
     fn cipher_suite_provider(&self) -> &Self::CipherSuiteProvider {
         &self.cipher_suite_provider
     }
@@ -701,7 +985,6 @@ where
         )?;
 
         Ok(ExternalGroup {
-            config,
             signing_data: snapshot.signing_data,
             state: snapshot
                 .state
@@ -711,6 +994,10 @@ where
                 )
                 .await?,
             cipher_suite_provider,
+            epoch_history: VecDeque::new(),
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: ProposalRejectionCache::new(config.proposal_rejection_ttl()),
+            config,
         })
     }
 }
@@ -807,14 +1094,15 @@ mod tests {
         external_client::{
             group::test_utils::make_external_group_with_config,
             tests_utils::{TestExternalClientBuilder, TestExternalClientConfig},
-            ExternalGroup, ExternalReceivedMessage, ExternalSnapshot,
+            CommitAuthorizer, CommitDecision, CommitDenial, CommitSummary, ExternalGroup,
+            ExternalReceivedMessage, ExternalSnapshot, MembershipTagStatus,
         },
         group::{
             framing::{Content, MlsMessagePayload},
             proposal::{AddProposal, Proposal, ProposalOrRef},
             proposal_ref::ProposalRef,
             test_utils::{test_group, TestGroup},
-            ProposalMessageDescription,
+            ProposalMessageDescription, Sender,
         },
         identity::{test_utils::get_test_signing_identity, SigningIdentity},
         key_package::test_utils::{test_key_package, test_key_package_message},
@@ -822,7 +1110,9 @@ mod tests {
         ExtensionList, MlsMessage,
     };
     use assert_matches::assert_matches;
+    use core::convert::Infallible;
     use mls_rs_codec::{MlsDecode, MlsEncode};
+    use std::sync::{Arc, Mutex};
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn test_group_with_one_commit(v: ProtocolVersion, cs: CipherSuite) -> TestGroup {
@@ -893,6 +1183,62 @@ mod tests {
         assert_eq!(alice.group.state, server.state);
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_retains_configured_epoch_history() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .epoch_history(2)
+            .build_config();
+
+        let mut server = make_external_group_with_config(&alice, config).await;
+        let first_epoch = server.group_context().epoch;
+        let first_roster = server.roster().members();
+
+        for i in 0..3 {
+            let key_package = test_key_package_message(
+                TEST_PROTOCOL_VERSION,
+                TEST_CIPHER_SUITE,
+                &format!("member {i}"),
+            )
+            .await;
+
+            let commit_output = alice
+                .group
+                .commit_builder()
+                .add_member(key_package)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            alice.group.apply_pending_commit().await.unwrap();
+
+            server
+                .process_incoming_message(commit_output.commit_message)
+                .await
+                .unwrap();
+        }
+
+        let retained = server
+            .epoch_history()
+            .map(|record| record.epoch())
+            .collect::<Vec<_>>();
+
+        assert_eq!(retained, vec![first_epoch + 1, first_epoch + 2]);
+        assert_eq!(server.epoch_record(first_epoch), None);
+
+        let record = server.epoch_record(first_epoch + 1).unwrap();
+        assert_eq!(record.members().len(), first_roster.len() + 1);
+
+        let current = server.epoch_record(first_epoch + 3).unwrap();
+        assert_eq!(current.members(), server.roster().members());
+        assert_eq!(current.tree_hash(), server.tree_hash());
+
+        let tree_hash = server.compute_tree_hash().await.unwrap();
+        assert_eq!(tree_hash, server.tree_hash());
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn external_group_can_process_proposals_by_reference() {
         let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
@@ -997,6 +1343,63 @@ mod tests {
         assert_matches!(res, Err(MlsError::InvalidSignature));
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_reports_message_authenticity() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let server = make_external_group(&alice).await;
+
+        let commit_output = alice.group.commit(Vec::new()).await.unwrap();
+
+        let report = server
+            .message_authenticity(&commit_output.commit_message)
+            .await
+            .unwrap();
+
+        assert!(report.is_authentic());
+        assert_eq!(report.sender, Sender::Member(0));
+        assert_eq!(report.membership_tag, MembershipTagStatus::Present);
+
+        assert_eq!(
+            report.sender_identity.as_ref(),
+            Some(
+                &alice
+                    .group
+                    .current_member_signing_identity()
+                    .unwrap()
+                    .clone()
+            )
+        );
+
+        let mut tampered = commit_output.commit_message.clone();
+
+        match tampered.payload {
+            MlsMessagePayload::Plain(ref mut plain) => {
+                plain.auth.signature = Vec::new().into();
+                plain.membership_tag = None;
+            }
+            _ => panic!("Unexpected non-plaintext data"),
+        };
+
+        let report = server.message_authenticity(&tampered).await.unwrap();
+
+        assert!(!report.is_authentic());
+        assert!(report.epoch_matches);
+        assert!(!report.signature_valid);
+        assert_eq!(report.membership_tag, MembershipTagStatus::Missing);
+
+        let mut wrong_epoch = commit_output.commit_message;
+
+        match wrong_epoch.payload {
+            MlsMessagePayload::Plain(ref mut plain) => plain.content.epoch = 0,
+            _ => panic!("Unexpected non-plaintext data"),
+        };
+
+        let report = server.message_authenticity(&wrong_epoch).await.unwrap();
+
+        assert!(!report.epoch_matches);
+        assert!(!report.is_authentic());
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn external_group_rejects_unencrypted_application_message() {
         let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
@@ -1161,6 +1564,95 @@ mod tests {
         test_external_proposal(&mut server, &mut alice, external_proposal).await
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_remembers_recorded_proposals_for_ttl() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .proposal_rejection_ttl(1)
+            .build_config();
+
+        let mut server = make_external_group_with_config(&alice, config).await;
+
+        // Alice does not receive the update and commits without it
+        let update = bob.group.propose_update(vec![]).await.unwrap();
+        let received = server.process_incoming_message(update).await.unwrap();
+
+        let ExternalReceivedMessage::Proposal(ProposalMessageDescription { proposal_ref, .. }) =
+            received
+        else {
+            panic!("expected a proposal");
+        };
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.process_pending_commit().await.unwrap();
+        server.process_incoming_message(commit).await.unwrap();
+
+        // The committer may never have received the update, so it is not recorded
+        assert!(!server.is_proposal_rejected(&proposal_ref));
+
+        let epoch = server.group_context().epoch;
+
+        server
+            .proposal_rejection_cache_mut()
+            .insert(proposal_ref.clone(), epoch);
+
+        assert!(server.is_proposal_rejected(&proposal_ref));
+
+        for _ in 0..2 {
+            let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+            alice.process_pending_commit().await.unwrap();
+            server.process_incoming_message(commit).await.unwrap();
+        }
+
+        assert!(!server.is_proposal_rejected(&proposal_ref));
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "custom_proposal"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_proposal_metadata_can_forbid_external_senders() {
+        use crate::{
+            client::test_utils::TEST_CUSTOM_PROPOSAL_TYPE,
+            group::proposal::{CustomProposal, CustomProposalMetadata},
+        };
+
+        let (server_identity, server_key, mut alice) = setup_extern_proposal_test(true).await;
+
+        let mut server = make_external_group(&alice).await;
+        server.signing_data = Some((server_key.clone(), server_identity.clone()));
+
+        let proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![]);
+        let metadata = CustomProposalMetadata::new().with_external_senders_allowed(false);
+
+        let external_proposal = server
+            .propose_custom(proposal.clone(), vec![])
+            .await
+            .unwrap();
+
+        alice
+            .group
+            .config
+            .0
+            .settings
+            .custom_proposal_metadata
+            .push((TEST_CUSTOM_PROPOSAL_TYPE, metadata));
+
+        let res = alice.process_message(external_proposal).await;
+        assert_matches!(res, Err(MlsError::InvalidProposalTypeForSender));
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .custom_proposal_type_with_metadata(TEST_CUSTOM_PROPOSAL_TYPE, metadata)
+            .build_config();
+
+        let mut server = make_external_group_with_config(&alice, config).await;
+        server.signing_data = Some((server_key, server_identity));
+
+        let res = server.propose_custom(proposal, vec![]).await;
+        assert_matches!(res, Err(MlsError::InvalidProposalTypeForSender));
+    }
+
     #[cfg(feature = "by_ref_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn external_group_external_proposal_not_allowed() {
@@ -1351,4 +1843,172 @@ mod tests {
 
         assert_matches!(update, ExternalReceivedMessage::Welcome);
     }
+
+    #[derive(Clone, Debug, Default)]
+    struct DenyRemovals {
+        summaries: Arc<Mutex<Vec<CommitSummary>>>,
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl CommitAuthorizer for DenyRemovals {
+        type Error = Infallible;
+
+        async fn authorize_commit(
+            &self,
+            summary: &CommitSummary,
+        ) -> Result<CommitDecision, Self::Error> {
+            self.summaries.lock().unwrap().push(summary.clone());
+
+            if summary.removed.is_empty() {
+                Ok(CommitDecision::Approve)
+            } else {
+                let denial = CommitDenial::new(1, "members may not be removed");
+                Ok(CommitDecision::Deny(denial))
+            }
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_applies_authorized_commit() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let authorizer = DenyRemovals::default();
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .commit_authorizer(authorizer.clone())
+            .build_config();
+
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let mut server = ExternalGroup::join(config, None, group_info, None)
+            .await
+            .unwrap();
+
+        let bob_key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let commit_output = alice
+            .group
+            .commit_builder()
+            .add_member(bob_key_package.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        server
+            .process_incoming_message(commit_output.commit_message)
+            .await
+            .unwrap();
+
+        let summaries = authorizer.summaries.lock().unwrap();
+        let [summary] = summaries.as_slice() else {
+            panic!("expected one commit summary");
+        };
+
+        let bob_identity = &bob_key_package
+            .into_key_package()
+            .unwrap()
+            .leaf_node
+            .signing_identity;
+
+        assert_eq!(summary.epoch, 1);
+        assert_eq!(summary.committer.index, 0);
+        assert!(!summary.is_external);
+        assert_eq!(summary.added.len(), 1);
+        assert_eq!(&summary.added[0].signing_identity, bob_identity);
+        assert!(summary.removed.is_empty());
+        assert!(summary.new_extensions.is_none());
+        assert_eq!(server.group_context().epoch, 2);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_rejects_denied_commit() {
+        let mut alice = test_group_two_members(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            #[cfg(feature = "by_ref_proposal")]
+            None,
+        )
+        .await;
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .commit_authorizer(DenyRemovals::default())
+            .build_config();
+
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let mut server = ExternalGroup::join(config, None, group_info, None)
+            .await
+            .unwrap();
+
+        let epoch = server.group_context().epoch;
+
+        let commit_output = alice
+            .group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let res = server
+            .process_incoming_message(commit_output.commit_message)
+            .await;
+
+        let Err(MlsError::CommitDenied(denial)) = res else {
+            panic!("expected commit to be denied");
+        };
+
+        assert_eq!(denial.code, 1);
+        assert_eq!(server.group_context().epoch, epoch);
+        assert_eq!(server.roster().members_iter().count(), 2);
+
+        // The denial can be relayed to the committer
+        let bytes = denial.to_bytes().unwrap();
+        assert_eq!(CommitDenial::from_bytes(&bytes).unwrap(), denial);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_group_does_not_authorize_commit_with_invalid_path() {
+        let mut alice = test_group_with_one_commit(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let authorizer = DenyRemovals::default();
+
+        let config = TestExternalClientBuilder::new_for_test()
+            .commit_authorizer(authorizer.clone())
+            .build_config();
+
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let mut server = ExternalGroup::join(config, None, group_info, None)
+            .await
+            .unwrap();
+
+        alice.group.commit_modifiers.modify_leaf = |leaf, _| {
+            leaf.signature = vec![0; leaf.signature.len()];
+            None
+        };
+
+        let commit_output = alice.group.commit(vec![]).await.unwrap();
+
+        let res = server
+            .process_incoming_message(commit_output.commit_message)
+            .await;
+
+        assert!(res.is_err());
+        assert!(authorizer.summaries.lock().unwrap().is_empty());
+    }
 }
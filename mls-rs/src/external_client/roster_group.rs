@@ -0,0 +1,531 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{error::IntoAnyError, group::MemberUpdate};
+
+use crate::{
+    client::MlsError,
+    external_client::{
+        CommitAuthorizer, CommitDecision, CommitSummary, ExternalClientConfig, ExternalGroup,
+    },
+    group::{
+        cipher_suite_provider,
+        framing::{Content, MlsMessagePayload, PublicMessage, Sender},
+        member_from_key_package, member_from_leaf_node,
+        message_processor::path_update_required,
+        message_signature::AuthenticatedContent,
+        mls_rules::TreeLayoutRules,
+        snapshot::RawGroupState,
+        state::GroupState,
+        transcript_hash::{ConfirmedTranscriptHash, InterimTranscriptHash},
+        Commit, ContentType, GroupContext, Roster,
+    },
+    tree_kem::node::LeafIndex,
+    CryptoProvider, MlsMessage,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::proposal_ref::ProposalRef;
+
+#[cfg(not(feature = "by_ref_proposal"))]
+use crate::group::proposal_cache::resolve_for_commit;
+
+/// The result of processing a message with
+/// [ExternalRosterGroup::process_incoming_message](ExternalRosterGroup::process_incoming_message).
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum ExternalRosterMessage {
+    /// Summary of a commit applied to the roster.
+    Commit(CommitSummary),
+    /// Reference of a proposal cached for a later commit.
+    #[cfg(feature = "by_ref_proposal")]
+    Proposal(ProposalRef),
+    /// Encrypted message that can not be processed.
+    Ciphertext(ContentType),
+}
+
+/// An observed group that only tracks the roster and the group context.
+///
+/// The group keeps the leaves of the ratchet tree but discards its parent
+/// nodes, which makes it much smaller than an [ExternalGroup] for large
+/// groups. It is meant for servers that route messages and only need to know
+/// the members of each group.
+///
+/// # Warning
+///
+/// Commits are only validated structurally. They must be public messages of
+/// the current epoch sent by a member, or external commits, the proposals
+/// they reference must be cached and the resulting roster must be valid
+/// according to the [IdentityProvider](crate::IdentityProvider). Signatures,
+/// membership tags and update paths are not verified and the tree hash is not
+/// computed, since all of them require the parent nodes. The tree hash of the
+/// [group context](ExternalRosterGroup::group_context) is empty after the
+/// first commit.
+#[derive(Clone)]
+pub struct ExternalRosterGroup<C>
+where
+    C: ExternalClientConfig,
+{
+    config: C,
+    cipher_suite_provider: <C::CryptoProvider as CryptoProvider>::CipherSuiteProvider,
+    state: GroupState,
+}
+
+impl<C> ExternalGroup<C>
+where
+    C: ExternalClientConfig + Clone,
+{
+    /// Stop tracking the full ratchet tree, keeping only the roster and the
+    /// group context. See [ExternalRosterGroup].
+    pub fn into_roster_only(self) -> ExternalRosterGroup<C> {
+        ExternalRosterGroup::new(self.config, self.cipher_suite_provider, self.state)
+    }
+}
+
+impl<C> ExternalRosterGroup<C>
+where
+    C: ExternalClientConfig + Clone,
+{
+    fn new(
+        config: C,
+        cipher_suite_provider: <C::CryptoProvider as CryptoProvider>::CipherSuiteProvider,
+        mut state: GroupState,
+    ) -> Self {
+        state.public_tree.discard_parent_nodes();
+
+        Self {
+            config,
+            cipher_suite_provider,
+            state,
+        }
+    }
+
+    /// Get the current group context. The tree hash is only known until the
+    /// first commit, see [ExternalRosterGroup].
+    pub fn group_context(&self) -> &GroupContext {
+        &self.state.context
+    }
+
+    /// Get the current roster of the group.
+    pub fn roster(&self) -> Roster {
+        self.state.public_tree.roster()
+    }
+
+    /// Process a message that was sent to the group.
+    ///
+    /// Commits are applied to the roster, proposals are cached and encrypted
+    /// messages result in [ExternalRosterMessage::Ciphertext]. Other messages
+    /// are rejected.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn process_incoming_message(
+        &mut self,
+        message: MlsMessage,
+    ) -> Result<ExternalRosterMessage, MlsError> {
+        if message.version != self.state.context.protocol_version {
+            return Err(MlsError::ProtocolVersionMismatch);
+        }
+
+        match message.payload {
+            MlsMessagePayload::Plain(plaintext) => self.process_public_message(plaintext).await,
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => {
+                Ok(ExternalRosterMessage::Ciphertext(ciphertext.content_type))
+            }
+            _ => Err(MlsError::UnexpectedMessageType),
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn process_public_message(
+        &mut self,
+        message: PublicMessage,
+    ) -> Result<ExternalRosterMessage, MlsError> {
+        if message.content.group_id != self.state.context.group_id {
+            return Err(MlsError::GroupIdMismatch);
+        }
+
+        if message.content.epoch != self.state.context.epoch {
+            return Err(MlsError::InvalidEpoch);
+        }
+
+        let auth_content = AuthenticatedContent::from(message);
+
+        match &auth_content.content.content {
+            Content::Commit(commit) => {
+                let commit = commit.as_ref().clone();
+
+                self.process_commit(&auth_content, commit)
+                    .await
+                    .map(ExternalRosterMessage::Commit)
+            }
+            #[cfg(feature = "by_ref_proposal")]
+            Content::Proposal(proposal) => {
+                if let Sender::Member(index) = auth_content.content.sender {
+                    self.state.public_tree.get_leaf_node(LeafIndex(index))?;
+                }
+
+                let proposal_ref =
+                    ProposalRef::from_content(&self.cipher_suite_provider, &auth_content).await?;
+
+                self.state.proposals.insert(
+                    proposal_ref.clone(),
+                    proposal.as_ref().clone(),
+                    auth_content.content.sender,
+                );
+
+                Ok(ExternalRosterMessage::Proposal(proposal_ref))
+            }
+            #[cfg(feature = "private_message")]
+            Content::Application(_) => Err(MlsError::UnencryptedApplicationMessage),
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn process_commit(
+        &mut self,
+        auth_content: &AuthenticatedContent,
+        commit: Commit,
+    ) -> Result<CommitSummary, MlsError> {
+        let sender = auth_content.content.sender;
+
+        let committer = match sender {
+            Sender::Member(index) => {
+                self.state.public_tree.get_leaf_node(LeafIndex(index))?;
+                Some(LeafIndex(index))
+            }
+            Sender::NewMemberCommit => None,
+            #[cfg(feature = "by_ref_proposal")]
+            _ => return Err(MlsError::InvalidSender),
+        };
+
+        let confirmation_tag = auth_content
+            .auth
+            .confirmation_tag
+            .clone()
+            .ok_or(MlsError::InvalidConfirmationTag)?;
+
+        #[cfg(feature = "by_ref_proposal")]
+        let proposals = self
+            .state
+            .proposals
+            .resolve_for_commit(sender, commit.proposals)?;
+
+        #[cfg(not(feature = "by_ref_proposal"))]
+        let proposals = resolve_for_commit(sender, commit.proposals)?;
+
+        if commit.path.is_none() && (committer.is_none() || path_update_required(&proposals)) {
+            return Err(MlsError::CommitMissingPath);
+        }
+
+        if committer.is_none() && proposals.external_init_proposals().is_empty() {
+            return Err(MlsError::ExternalCommitMissingExternalInit);
+        }
+
+        let context = &self.state.context;
+
+        let extensions = proposals
+            .group_context_extensions
+            .last()
+            .map(|p| p.proposal.clone())
+            .unwrap_or_else(|| context.extensions.clone());
+
+        let id_provider = self.config.identity_provider();
+        let mls_rules = self.config.mls_rules();
+        let mut tree = self.state.public_tree.clone();
+
+        #[cfg(feature = "by_ref_proposal")]
+        let mut updated = Vec::with_capacity(proposals.updates.len());
+
+        #[cfg(not(feature = "by_ref_proposal"))]
+        let updated = Vec::new();
+
+        #[cfg(feature = "by_ref_proposal")]
+        for p in &proposals.updates {
+            let index = match p.sender {
+                Sender::Member(index) => LeafIndex(index),
+                _ => return Err(MlsError::InvalidSender),
+            };
+
+            if Some(index) == committer {
+                return Err(MlsError::InvalidCommitSelfUpdate);
+            }
+
+            let old_leaf = tree.remove_leaf(index, &id_provider, &extensions).await?;
+            let new_leaf = p.proposal.leaf_node.clone();
+
+            updated.push(MemberUpdate::new(
+                member_from_leaf_node(&old_leaf, index),
+                member_from_leaf_node(&new_leaf, index),
+            ));
+
+            tree.add_leaf(new_leaf, &id_provider, &extensions, index)
+                .await?;
+        }
+
+        let mut removed = Vec::with_capacity(proposals.removals.len());
+
+        for p in &proposals.removals {
+            let index = p.proposal.to_remove;
+
+            if Some(index) == committer {
+                return Err(MlsError::CommitterSelfRemoval);
+            }
+
+            let old_leaf = tree.remove_leaf(index, &id_provider, &extensions).await?;
+            removed.push(member_from_leaf_node(&old_leaf, index));
+        }
+
+        let mut added = Vec::with_capacity(proposals.additions.len());
+        let mut start = LeafIndex(0);
+
+        for p in &proposals.additions {
+            let key_package = &p.proposal.key_package;
+
+            if key_package.version != context.protocol_version {
+                return Err(MlsError::ProtocolVersionMismatch);
+            }
+
+            if key_package.cipher_suite != context.cipher_suite {
+                return Err(MlsError::CipherSuiteMismatch);
+            }
+
+            let leaf = key_package.leaf_node.clone();
+            let (index, leftmost) = tree.place_new_leaf(&leaf, &mls_rules, start)?;
+
+            tree.add_leaf(leaf, &id_provider, &extensions, index)
+                .await?;
+
+            if leftmost {
+                start = index;
+            }
+
+            added.push(member_from_key_package(key_package, index));
+        }
+
+        let has_path = commit.path.is_some();
+
+        let committer = match (committer, commit.path) {
+            (Some(index), Some(path)) => {
+                tree.remove_leaf(index, &id_provider, &extensions).await?;
+
+                tree.add_leaf(path.leaf_node, &id_provider, &extensions, index)
+                    .await?
+            }
+            (Some(index), None) => index,
+            (None, Some(path)) => {
+                let (index, _) = tree.place_new_leaf(&path.leaf_node, &mls_rules, start)?;
+
+                tree.add_leaf(path.leaf_node, &id_provider, &extensions, index)
+                    .await?
+            }
+            (None, None) => return Err(MlsError::CommitMissingPath),
+        };
+
+        tree.nodes.truncate(mls_rules.truncation(&extensions)?);
+        tree.discard_parent_nodes();
+
+        let summary = CommitSummary {
+            epoch: context.epoch,
+            committer: member_from_leaf_node(tree.get_leaf_node(committer)?, committer),
+            is_external: sender == Sender::NewMemberCommit,
+            added,
+            removed,
+            updated,
+            has_path,
+            new_extensions: (extensions != context.extensions).then(|| extensions.clone()),
+        };
+
+        let decision = self
+            .config
+            .commit_authorizer()
+            .authorize_commit(&summary)
+            .await
+            .map_err(|e| MlsError::CommitAuthorizerError(e.into_any_error()))?;
+
+        if let CommitDecision::Deny(denial) = decision {
+            return Err(MlsError::CommitDenied(denial));
+        }
+
+        let confirmed_transcript_hash = ConfirmedTranscriptHash::create(
+            &self.cipher_suite_provider,
+            &self.state.interim_transcript_hash,
+            auth_content,
+        )
+        .await?;
+
+        let interim_transcript_hash = InterimTranscriptHash::create(
+            &self.cipher_suite_provider,
+            &confirmed_transcript_hash,
+            &confirmation_tag,
+        )
+        .await?;
+
+        let context = &mut self.state.context;
+        context.epoch += 1;
+        context.tree_hash = Vec::new();
+        context.confirmed_transcript_hash = confirmed_transcript_hash;
+        context.extensions = extensions;
+
+        #[cfg(feature = "by_ref_proposal")]
+        self.state.proposals.clear();
+        self.state.public_tree = tree;
+        self.state.interim_transcript_hash = interim_transcript_hash;
+        self.state.confirmation_tag = confirmation_tag;
+
+        Ok(summary)
+    }
+
+    /// Create a snapshot of this group's current internal state.
+    pub fn snapshot(&self) -> ExternalRosterSnapshot {
+        ExternalRosterSnapshot {
+            version: 1,
+            state: RawGroupState::export(&self.state),
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn from_snapshot(
+        config: C,
+        snapshot: ExternalRosterSnapshot,
+    ) -> Result<Self, MlsError> {
+        #[cfg(feature = "tree_index")]
+        let identity_provider = config.identity_provider();
+
+        let cipher_suite_provider = cipher_suite_provider(
+            config.crypto_provider(),
+            snapshot.state.context.cipher_suite,
+        )?;
+
+        let state = snapshot
+            .state
+            .import(
+                #[cfg(feature = "tree_index")]
+                &identity_provider,
+            )
+            .await?;
+
+        Ok(Self::new(config, cipher_suite_provider, state))
+    }
+}
+
+/// Serializable snapshot of an [ExternalRosterGroup](ExternalRosterGroup) state.
+#[derive(Debug, MlsEncode, MlsSize, MlsDecode, PartialEq, Clone)]
+pub struct ExternalRosterSnapshot {
+    version: u16,
+    state: RawGroupState,
+}
+
+impl ExternalRosterSnapshot {
+    /// Serialize the snapshot
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        Ok(self.mls_encode_to_vec()?)
+    }
+
+    /// Deserialize the snapshot
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Ok(Self::mls_decode(&mut &*bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        external_client::{
+            group::test_utils::make_external_group, tests_utils::TestExternalClientBuilder,
+        },
+        group::test_utils::{test_group, TestGroup},
+        key_package::test_utils::test_key_package_message,
+    };
+
+    use super::{ExternalRosterMessage, ExternalRosterSnapshot};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn roster_group_tracks_members_without_parent_nodes() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let mut server = make_external_group(&alice).await.into_roster_only();
+
+        assert!(server
+            .state
+            .public_tree
+            .nodes
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(Option::is_none));
+
+        let carol =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let commit = bob
+            .group
+            .commit_builder()
+            .add_member(carol)
+            .unwrap()
+            .remove_member(0)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        bob.group.apply_pending_commit().await.unwrap();
+
+        let summary = server.process_incoming_message(commit).await.unwrap();
+
+        assert_matches!(
+            summary,
+            ExternalRosterMessage::Commit(summary)
+                if summary.committer.index == 1
+                    && summary.added.len() == 1
+                    && summary.removed.len() == 1
+                    && summary.has_path
+        );
+
+        assert_eq!(server.group_context().epoch, bob.group.current_epoch());
+        assert_eq!(
+            server.group_context().confirmed_transcript_hash,
+            bob.group.context().confirmed_transcript_hash
+        );
+        assert_eq!(server.roster().members(), bob.group.roster().members());
+
+        let snapshot =
+            ExternalRosterSnapshot::from_bytes(&server.snapshot().to_bytes().unwrap()).unwrap();
+
+        let client = TestExternalClientBuilder::new_for_test().build();
+
+        let mut server = client.load_roster_group(snapshot).await.unwrap();
+
+        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
+        bob.group.apply_pending_commit().await.unwrap();
+
+        server.process_incoming_message(commit).await.unwrap();
+        assert_eq!(server.group_context().epoch, bob.group.current_epoch());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn roster_group_rejects_commits_of_other_epochs() {
+        let mut alice: TestGroup = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let mut server = make_external_group(&alice).await.into_roster_only();
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        let res = server.process_incoming_message(commit).await;
+
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+}
@@ -55,6 +55,12 @@ impl GroupInfo {
     pub fn grease<P: CipherSuiteProvider>(&mut self, cs: &P) -> Result<(), MlsError> {
         grease_functions::grease_extensions(&mut self.extensions, cs).map(|_| ())
     }
+
+    pub fn ungreased_extensions(&self) -> ExtensionList {
+        let mut extensions = self.extensions.clone();
+        grease_functions::ungrease_extensions(&mut extensions);
+        extensions
+    }
 }
 
 impl NewMemberInfo {
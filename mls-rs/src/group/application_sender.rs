@@ -0,0 +1,273 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::crypto::{CipherSuiteProvider, SignatureSecretKey};
+
+#[cfg(feature = "psk")]
+use mls_rs_core::psk::PreSharedKey;
+
+use crate::{
+    client::MlsError, client_config::ClientConfig, protocol_version::ProtocolVersion,
+    tree_kem::node::LeafIndex, CryptoProvider, Group,
+};
+
+use super::{
+    ciphertext_processor::{CiphertextProcessor, GroupStateProvider},
+    epoch::EpochSecrets,
+    framing::{Content, MlsMessage, MlsMessagePayload, Sender, WireFormat},
+    message_signature::AuthenticatedContent,
+    padding::PaddingMode,
+    secret_tree::KeyType,
+    GroupContext,
+};
+
+/// Encrypts application messages for the current epoch of a [`Group`]
+/// without borrowing it.
+///
+/// A sender owns a range of generations of the application ratchet of this
+/// member that was reserved with [`Group::application_sender`]. Since ranges
+/// never overlap, any number of senders can encrypt concurrently, for
+/// example from different tasks, while the group itself stays available for
+/// commits and for processing incoming messages.
+///
+/// Messages produced by a sender always belong to the epoch in which it was
+/// created. Once the group moves to a new epoch, a new sender should be
+/// reserved.
+pub struct ApplicationSender<CP: CipherSuiteProvider> {
+    cipher_suite_provider: CP,
+    protocol_version: ProtocolVersion,
+    context: GroupContext,
+    self_index: LeafIndex,
+    epoch_secrets: EpochSecrets,
+    signer: SignatureSecretKey,
+    padding_mode: PaddingMode,
+    end_generation: u32,
+}
+
+impl<CP: CipherSuiteProvider + Clone> ApplicationSender<CP> {
+    /// Encrypt an application message, consuming one of the reserved
+    /// generations.
+    ///
+    /// `authenticated_data` will be sent unencrypted along with the contents
+    /// of the message. Fails with [`MlsError::ApplicationSenderExhausted`]
+    /// once all reserved generations were used.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn encrypt_application_message(
+        &mut self,
+        message: &[u8],
+        authenticated_data: Vec<u8>,
+    ) -> Result<MlsMessage, MlsError> {
+        if self.remaining() == 0 {
+            return Err(MlsError::ApplicationSenderExhausted);
+        }
+
+        let auth_content = AuthenticatedContent::new_signed(
+            &self.cipher_suite_provider,
+            &self.context,
+            Sender::Member(*self.self_index),
+            Content::Application(message.to_vec().into()),
+            &self.signer,
+            WireFormat::PrivateMessage,
+            authenticated_data,
+        )
+        .await?;
+
+        let padding_mode = self.padding_mode;
+
+        let ciphertext = CiphertextProcessor::new(self, self.cipher_suite_provider.clone())
+            .seal(auth_content, padding_mode)
+            .await?;
+
+        Ok(MlsMessage::new(
+            self.protocol_version,
+            MlsMessagePayload::Cipher(ciphertext),
+        ))
+    }
+
+    /// Number of messages this sender can still encrypt.
+    pub fn remaining(&self) -> u32 {
+        let generation = self
+            .epoch_secrets
+            .secret_tree
+            .key_generation(&self.self_index.node_index(), KeyType::Application);
+
+        self.end_generation.saturating_sub(generation)
+    }
+
+    /// Epoch of the messages encrypted by this sender.
+    pub fn epoch(&self) -> u64 {
+        self.context.epoch
+    }
+}
+
+impl<CP: CipherSuiteProvider> GroupStateProvider for ApplicationSender<CP> {
+    fn group_context(&self) -> &GroupContext {
+        &self.context
+    }
+
+    fn self_index(&self) -> LeafIndex {
+        self.self_index
+    }
+
+    fn epoch_secrets_mut(&mut self) -> &mut EpochSecrets {
+        &mut self.epoch_secrets
+    }
+
+    fn epoch_secrets(&self) -> &EpochSecrets {
+        &self.epoch_secrets
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Reserve the next `capacity` generations of this member's application
+    /// ratchet for a new [`ApplicationSender`].
+    ///
+    /// The reserved generations are skipped by this group and by any other
+    /// sender, so the group state must be written to storage before messages
+    /// from the sender are delivered, as with
+    /// [`Group::encrypt_application_message`]. Receivers must accept skipping
+    /// at least `capacity` generations, see
+    /// [`ClientBuilder::max_ratchet_skip`](crate::client_builder::ClientBuilder::max_ratchet_skip).
+    ///
+    /// Fails with [`MlsError::AeadUsageLimitReached`] if reserving `capacity`
+    /// generations would exceed the configured AEAD usage limits.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn application_sender(
+        &mut self,
+        capacity: u32,
+    ) -> Result<
+        ApplicationSender<<C::CryptoProvider as CryptoProvider>::CipherSuiteProvider>,
+        MlsError,
+    > {
+        #[cfg(feature = "by_ref_proposal")]
+        if !self.state.proposals.is_empty() {
+            return Err(MlsError::CommitRequired);
+        }
+
+        let max_messages = self
+            .config
+            .aead_usage_limits(self.cipher_suite_provider.cipher_suite())
+            .max_messages;
+
+        let end_generation = self
+            .application_messages_sent()
+            .checked_add(capacity)
+            .filter(|end| *end <= max_messages)
+            .ok_or(MlsError::AeadUsageLimitReached)?;
+
+        let padding_mode = self.encryption_options()?.padding_mode;
+        let self_index = self.private_tree.self_index;
+
+        let secret_tree = self
+            .epoch_secrets
+            .secret_tree
+            .split_off(
+                &self.cipher_suite_provider,
+                self_index.node_index(),
+                KeyType::Application,
+                capacity,
+            )
+            .await?;
+
+        let epoch_secrets = EpochSecrets {
+            #[cfg(feature = "psk")]
+            resumption_secret: PreSharedKey::new(Vec::new()),
+            sender_data_secret: self.epoch_secrets.sender_data_secret.clone(),
+            secret_tree,
+        };
+
+        Ok(ApplicationSender {
+            cipher_suite_provider: self.cipher_suite_provider.clone(),
+            protocol_version: self.protocol_version(),
+            context: self.context().clone(),
+            self_index,
+            epoch_secrets,
+            signer: self.signer.clone(),
+            padding_mode,
+            end_generation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            key_usage::AeadUsageLimits,
+            test_utils::{test_group, test_group_custom_config},
+            ReceivedMessage,
+        },
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn senders_encrypt_with_disjoint_generations() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let mut first = alice.group.application_sender(2).await.unwrap();
+        let mut second = alice.group.application_sender(2).await.unwrap();
+
+        assert_eq!(alice.group.application_messages_sent(), 4);
+        assert_eq!(first.epoch(), alice.group.current_epoch());
+
+        let mut messages = vec![];
+
+        for i in 0..2u8 {
+            messages.push(first.encrypt_application_message(&[i], vec![]).await);
+        }
+
+        for i in 2..4u8 {
+            messages.push(second.encrypt_application_message(&[i], vec![]).await);
+        }
+
+        messages.push(alice.group.encrypt_application_message(&[4], vec![]).await);
+
+        assert_eq!(first.remaining(), 0);
+
+        let res = first.encrypt_application_message(b"hello", vec![]).await;
+        assert_matches!(res, Err(MlsError::ApplicationSenderExhausted));
+
+        let mut received = vec![];
+
+        for message in messages {
+            let message = bob
+                .group
+                .process_incoming_message(message.unwrap())
+                .await
+                .unwrap();
+
+            let ReceivedMessage::ApplicationMessage(message) = message else {
+                panic!("expected application message");
+            };
+
+            received.push(message.data()[0]);
+        }
+
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn reservation_is_bounded_by_usage_limits() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.aead_usage_limits(TEST_CIPHER_SUITE, AeadUsageLimits::new(2, 4).unwrap())
+        })
+        .await;
+
+        alice.group.application_sender(3).await.unwrap();
+
+        let res = alice.group.application_sender(2).await.err();
+        assert_matches!(res, Some(MlsError::AeadUsageLimitReached));
+    }
+}
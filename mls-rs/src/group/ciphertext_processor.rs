@@ -13,13 +13,10 @@ use super::{
     framing::{ContentType, FramedContent, Sender, WireFormat},
     message_signature::AuthenticatedContent,
     padding::PaddingMode,
-    secret_tree::{KeyType, MessageKeyData},
+    secret_tree::{KeyType, MessageKeyData, MAX_RATCHET_BACK_HISTORY},
     GroupContext,
 };
-use crate::{
-    client::MlsError,
-    tree_kem::node::{LeafIndex, NodeIndex},
-};
+use crate::{client::MlsError, tree_kem::node::LeafIndex};
 use mls_rs_codec::MlsEncode;
 use mls_rs_core::{crypto::CipherSuiteProvider, error::IntoAnyError};
 use zeroize::Zeroizing;
@@ -29,11 +26,66 @@ mod reuse_guard;
 mod sender_data_key;
 
 #[cfg(feature = "private_message")]
-use super::framing::{PrivateContentAAD, PrivateMessage, PrivateMessageContent};
+use super::{
+    decode_limits::DecodeLimits,
+    framing::{PrivateContentAAD, PrivateMessage, PrivateMessageContent},
+};
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test_vectors"))]
 pub use sender_data_key::test_utils::*;
 
+#[cfg(any(test, feature = "test_vectors"))]
+pub(crate) mod test_vectors;
+
+/// Step at which the decryption of a private message failed.
+///
+/// The AEAD used by MLS does not tell a wrong key from a modified ciphertext,
+/// so the reason is a best-effort classification based on which of the two
+/// encryption layers of the message could not be opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecryptionFailureReason {
+    /// The sender data could not be decrypted with the key schedule of the
+    /// epoch of the message.
+    ///
+    /// The message was encrypted in a different epoch with the same number,
+    /// for example by a member whose state diverged from the local state, or
+    /// its header, encrypted sender data or the beginning of its ciphertext
+    /// was modified.
+    SenderData,
+    /// The sender data was authentic but the content could not be decrypted
+    /// with the key of the [`generation`](DecryptionFailure::generation)
+    /// indicated by the sender.
+    ///
+    /// Since the sender data and the content are encrypted with keys of the
+    /// same epoch, this usually means that the ciphertext or the
+    /// authenticated data of the message was modified.
+    Content,
+}
+
+/// Report of a private message that could not be decrypted, returned with
+/// [`MlsError::PrivateMessageDecryptionFailed`].
+///
+/// Messages of an epoch that is not available anymore fail with
+/// [`MlsError::EpochNotFound`], messages whose key was already used or
+/// deleted fail with [`MlsError::KeyMissing`] and messages too far ahead in
+/// the ratchet of their sender fail with
+/// [`MlsError::InvalidFutureGeneration`]. Messages failing with this report
+/// can not be decrypted later and should be requested again from the sender,
+/// or treated as an attack if they keep failing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecryptionFailure {
+    pub reason: DecryptionFailureReason,
+    /// Epoch of the key schedule used to decrypt the message.
+    pub epoch: u64,
+    /// Index of the sender, if the sender data was decrypted.
+    pub sender: Option<u32>,
+    /// Generation of the key used to decrypt the content, if the sender data
+    /// was decrypted.
+    pub generation: Option<u32>,
+}
+
 pub(crate) trait GroupStateProvider {
     fn group_context(&self) -> &GroupContext;
     fn self_index(&self) -> LeafIndex;
@@ -48,6 +100,9 @@ where
 {
     group_state: &'a mut GS,
     cipher_suite_provider: CP,
+    max_ratchet_skip: u32,
+    #[cfg(feature = "private_message")]
+    decode_limits: DecodeLimits,
 }
 
 impl<'a, GS, CP> CiphertextProcessor<'a, GS, CP>
@@ -62,6 +117,28 @@ where
         Self {
             group_state,
             cipher_suite_provider,
+            max_ratchet_skip: MAX_RATCHET_BACK_HISTORY,
+            #[cfg(feature = "private_message")]
+            decode_limits: Default::default(),
+        }
+    }
+
+    /// Set the maximum number of generations a sender ratchet is moved forward
+    /// to decrypt a single message.
+    pub fn with_max_ratchet_skip(self, max_ratchet_skip: u32) -> Self {
+        Self {
+            max_ratchet_skip,
+            ..self
+        }
+    }
+
+    /// Set the limits enforced while decoding the content of decrypted
+    /// messages.
+    #[cfg(feature = "private_message")]
+    pub fn with_decode_limits(self, decode_limits: DecodeLimits) -> Self {
+        Self {
+            decode_limits,
+            ..self
         }
     }
 
@@ -70,7 +147,7 @@ where
         &mut self,
         key_type: KeyType,
     ) -> Result<MessageKeyData, MlsError> {
-        let self_index = NodeIndex::from(self.group_state.self_index());
+        let self_index = self.group_state.self_index().node_index();
 
         self.group_state
             .epoch_secrets_mut()
@@ -86,12 +163,18 @@ where
         key_type: KeyType,
         generation: u32,
     ) -> Result<MessageKeyData, MlsError> {
-        let sender = NodeIndex::from(sender);
+        let sender = sender.node_index();
 
         self.group_state
             .epoch_secrets_mut()
             .secret_tree
-            .message_key_generation(&self.cipher_suite_provider, sender, key_type, generation)
+            .message_key_generation(
+                &self.cipher_suite_provider,
+                sender,
+                key_type,
+                generation,
+                self.max_ratchet_skip,
+            )
             .await
     }
 
@@ -213,7 +296,15 @@ where
 
         let sender_data = sender_data_key
             .open(&ciphertext.encrypted_sender_data, &sender_data_aad)
-            .await?;
+            .await
+            .map_err(|_| {
+                MlsError::PrivateMessageDecryptionFailed(DecryptionFailure {
+                    reason: DecryptionFailureReason::SenderData,
+                    epoch: ciphertext.epoch,
+                    sender: None,
+                    generation: None,
+                })
+            })?;
 
         if self.group_state.self_index() == sender_data.sender {
             return Err(MlsError::CantProcessMessageFromSelf);
@@ -240,10 +331,20 @@ where
                 &sender_data.reuse_guard,
             )
             .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
-
-        let ciphertext_content =
-            PrivateMessageContent::mls_decode(&mut &**decrypted_content, ciphertext.content_type)?;
+            .map_err(|_| {
+                MlsError::PrivateMessageDecryptionFailed(DecryptionFailure {
+                    reason: DecryptionFailureReason::Content,
+                    epoch: ciphertext.epoch,
+                    sender: Some(*sender_data.sender),
+                    generation: Some(sender_data.generation),
+                })
+            })?;
+
+        let ciphertext_content = PrivateMessageContent::mls_decode(
+            &mut &**decrypted_content,
+            ciphertext.content_type,
+            &self.decode_limits,
+        )?;
 
         // Build the MLS plaintext object and process it
         let auth_content = AuthenticatedContent {
@@ -272,7 +373,7 @@ mod test {
             CipherSuiteProvider,
         },
         group::{
-            framing::{ApplicationData, Content, Sender, WireFormat},
+            framing::{ApplicationData, Content, ContentType, Sender, WireFormat},
             message_signature::AuthenticatedContent,
             padding::PaddingMode,
             test_utils::{random_bytes, test_group, TestGroup},
@@ -280,7 +381,10 @@ mod test {
         tree_kem::node::LeafIndex,
     };
 
-    use super::{CiphertextProcessor, GroupStateProvider, MlsError};
+    use super::{
+        CiphertextProcessor, DecryptionFailure, DecryptionFailureReason, GroupStateProvider,
+        MlsError,
+    };
 
     use alloc::vec;
     use assert_matches::assert_matches;
@@ -407,4 +511,74 @@ mod test {
 
         assert!(res.is_err());
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tampered_message_failure_is_reported() {
+        let mut test_data = test_data(TEST_CIPHER_SUITE).await;
+        let mut receiver_group = test_data.group.clone();
+        receiver_group.group.private_tree.self_index = LeafIndex::new(1);
+
+        let ciphertext = test_processor(&mut test_data.group, TEST_CIPHER_SUITE)
+            .seal(test_data.content.clone(), PaddingMode::StepFunction)
+            .await
+            .unwrap();
+
+        let epoch = ciphertext.epoch;
+
+        let sender_data_failure = DecryptionFailure {
+            reason: DecryptionFailureReason::SenderData,
+            epoch,
+            sender: None,
+            generation: None,
+        };
+
+        let content_failure = DecryptionFailure {
+            reason: DecryptionFailureReason::Content,
+            epoch,
+            sender: Some(0),
+            generation: Some(0),
+        };
+
+        let mut tampered_authenticated_data = ciphertext.clone();
+        tampered_authenticated_data.authenticated_data = b"tampered".to_vec();
+
+        let mut tampered_content_type = ciphertext.clone();
+        tampered_content_type.content_type = ContentType::Proposal;
+
+        let mut tampered_sender_data = ciphertext.clone();
+        tampered_sender_data.encrypted_sender_data[0] ^= 1;
+
+        // The sender data key is derived from the beginning of the ciphertext
+        let mut tampered_ciphertext_start = ciphertext.clone();
+        tampered_ciphertext_start.ciphertext[0] ^= 1;
+
+        let mut tampered_ciphertext_end = ciphertext.clone();
+        *tampered_ciphertext_end.ciphertext.last_mut().unwrap() ^= 1;
+
+        let cases = [
+            (tampered_authenticated_data, &content_failure),
+            (tampered_content_type, &sender_data_failure),
+            (tampered_sender_data, &sender_data_failure),
+            (tampered_ciphertext_start, &sender_data_failure),
+            (tampered_ciphertext_end, &content_failure),
+        ];
+
+        for (tampered, expected) in cases {
+            // Decrypting the content consumes the key of its generation
+            let mut receiver = receiver_group.clone();
+
+            let res = test_processor(&mut receiver, TEST_CIPHER_SUITE)
+                .open(&tampered)
+                .await;
+
+            assert_matches!(res, Err(MlsError::PrivateMessageDecryptionFailed(f)) if &f == expected);
+        }
+
+        let decrypted = test_processor(&mut receiver_group, TEST_CIPHER_SUITE)
+            .open(&ciphertext)
+            .await
+            .unwrap();
+
+        assert_eq!(decrypted, test_data.content);
+    }
 }
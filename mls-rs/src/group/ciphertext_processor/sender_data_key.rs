@@ -136,11 +136,13 @@ impl<'a, CP: CipherSuiteProvider> SenderDataKey<'a, CP> {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test_vectors"))]
 pub(crate) mod test_utils {
     use alloc::vec::Vec;
     use mls_rs_core::crypto::CipherSuiteProvider;
 
+    use crate::test_vectors::{ensure_eq, CheckResult, TestVectorError};
+
     use super::SenderDataKey;
 
     #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -156,7 +158,7 @@ pub(crate) mod test_utils {
     }
 
     impl InteropSenderData {
-        #[cfg(not(mls_build_async))]
+        #[cfg(all(test, not(mls_build_async)))]
         #[cfg_attr(coverage_nightly, coverage(off))]
         pub(crate) fn new<P: CipherSuiteProvider>(cs: &P) -> Self {
             let secret = cs.random_bytes_vec(cs.kdf_extract_size()).unwrap().into();
@@ -173,15 +175,17 @@ pub(crate) mod test_utils {
         }
 
         #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-        pub async fn verify<P: CipherSuiteProvider>(&self, cs: &P) {
+        pub async fn verify<P: CipherSuiteProvider>(&self, cs: &P) -> Result<(), TestVectorError> {
             let secret = self.sender_data_secret.clone().into();
 
             let key = SenderDataKey::new(&secret, &self.ciphertext, cs)
                 .await
-                .unwrap();
+                .check("deriving sender data key")?;
+
+            ensure_eq!(key.key.to_vec(), self.key, "sender data key mismatch");
+            ensure_eq!(key.nonce.to_vec(), self.nonce, "sender data nonce mismatch");
 
-            assert_eq!(key.key.to_vec(), self.key, "sender data key mismatch");
-            assert_eq!(key.nonce.to_vec(), self.nonce, "sender data nonce mismatch");
+            Ok(())
         }
     }
 }
@@ -0,0 +1,282 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec;
+use alloc::vec::Vec;
+use mls_rs_codec::MlsDecode;
+use mls_rs_core::{
+    crypto::{CipherSuiteProvider, SignaturePublicKey},
+    extension::ExtensionList,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    group::{
+        epoch::EpochSecrets,
+        framing::{Content, MlsMessage, MlsMessagePayload},
+        membership_tag::MembershipTag,
+        message_signature::AuthenticatedContent,
+        message_verifier::{verify_auth_content_signature, SignaturePublicKeysContainer},
+        padding::PaddingMode,
+        proposal::Proposal,
+        secret_tree::SecretTree,
+        Commit, GroupContext,
+    },
+    protocol_version::ProtocolVersion,
+    test_vectors::{ensure_eq, fail, CheckResult, TestVectorError},
+    tree_kem::node::LeafIndex,
+};
+
+use super::{CiphertextProcessor, GroupStateProvider};
+
+#[cfg(feature = "psk")]
+use mls_rs_core::psk::PreSharedKey;
+
+// Test vectors describe a group with the sender at leaf 1 and the receiver
+// at leaf 0.
+const N_LEAVES: u32 = 2;
+const RECEIVER: LeafIndex = LeafIndex(0);
+const SENDER: LeafIndex = LeafIndex(1);
+
+/// Test case in the format of
+/// https://github.com/mlswg/mls-implementations/blob/main/test-vectors/message-protection.json
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct MessageProtectionTestCase {
+    pub(crate) cipher_suite: u16,
+    #[serde(with = "hex::serde")]
+    pub(crate) group_id: Vec<u8>,
+    pub(crate) epoch: u64,
+    #[serde(with = "hex::serde")]
+    pub(crate) tree_hash: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) confirmed_transcript_hash: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) signature_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) signature_pub: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) encryption_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) sender_data_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) membership_key: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) proposal: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) proposal_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) proposal_pub: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) commit: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) commit_priv: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) commit_pub: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) application: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) application_priv: Vec<u8>,
+}
+
+struct TestGroupState {
+    context: GroupContext,
+    self_index: LeafIndex,
+    secrets: EpochSecrets,
+}
+
+impl GroupStateProvider for TestGroupState {
+    fn group_context(&self) -> &GroupContext {
+        &self.context
+    }
+
+    fn self_index(&self) -> LeafIndex {
+        self.self_index
+    }
+
+    fn epoch_secrets_mut(&mut self) -> &mut EpochSecrets {
+        &mut self.secrets
+    }
+
+    fn epoch_secrets(&self) -> &EpochSecrets {
+        &self.secrets
+    }
+}
+
+impl MessageProtectionTestCase {
+    /// Unprotect all messages of the test case with `cs`, protect the
+    /// private ones again and return an error on the first value that does
+    /// not match.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider + Clone>(
+        &self,
+        cs: &P,
+    ) -> Result<(), TestVectorError> {
+        let proposal = Proposal::mls_decode(&mut &*self.proposal).check("decoding proposal")?;
+        let commit = Commit::mls_decode(&mut &*self.commit).check("decoding commit")?;
+
+        for message in [&self.proposal_priv, &self.proposal_pub] {
+            let content = self.unprotect(message, cs).await?;
+
+            ensure_eq!(
+                content,
+                Content::Proposal(proposal.clone().into()),
+                "proposal mismatch"
+            );
+        }
+
+        for message in [&self.commit_priv, &self.commit_pub] {
+            let content = self.unprotect(message, cs).await?;
+
+            ensure_eq!(
+                content,
+                Content::Commit(commit.clone().into()),
+                "commit mismatch"
+            );
+        }
+
+        let content = self.unprotect(&self.application_priv, cs).await?;
+
+        ensure_eq!(
+            content,
+            Content::Application(self.application.clone().into()),
+            "application data mismatch"
+        );
+
+        for message in [
+            &self.proposal_priv,
+            &self.commit_priv,
+            &self.application_priv,
+        ] {
+            self.reprotect(message, cs).await?;
+        }
+
+        Ok(())
+    }
+
+    fn context(&self) -> GroupContext {
+        GroupContext {
+            protocol_version: ProtocolVersion::MLS_10,
+            cipher_suite: self.cipher_suite.into(),
+            group_id: self.group_id.clone(),
+            epoch: self.epoch,
+            tree_hash: self.tree_hash.clone(),
+            confirmed_transcript_hash: self.confirmed_transcript_hash.clone().into(),
+            extensions: ExtensionList::new(),
+        }
+    }
+
+    fn state(&self, self_index: LeafIndex) -> TestGroupState {
+        TestGroupState {
+            context: self.context(),
+            self_index,
+            secrets: EpochSecrets {
+                #[cfg(feature = "psk")]
+                resumption_secret: PreSharedKey::new(vec![]),
+                sender_data_secret: self.sender_data_secret.clone().into(),
+                secret_tree: SecretTree::new(
+                    N_LEAVES,
+                    Zeroizing::new(self.encryption_secret.clone()),
+                ),
+            },
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn unprotect<P: CipherSuiteProvider + Clone>(
+        &self,
+        message: &[u8],
+        cs: &P,
+    ) -> Result<Content, TestVectorError> {
+        let message = MlsMessage::mls_decode(&mut &*message).check("decoding message")?;
+
+        let auth_content = match message.payload {
+            MlsMessagePayload::Plain(plaintext) => {
+                let tag = plaintext.membership_tag.clone();
+                let auth_content = AuthenticatedContent::from(plaintext);
+
+                let expected_tag =
+                    MembershipTag::create(&auth_content, &self.context(), &self.membership_key, cs)
+                        .await
+                        .check("computing membership tag")?;
+
+                ensure_eq!(tag, Some(expected_tag), "membership tag mismatch");
+
+                auth_content
+            }
+            MlsMessagePayload::Cipher(ciphertext) => {
+                let mut state = self.state(RECEIVER);
+
+                CiphertextProcessor::new(&mut state, cs.clone())
+                    .open(&ciphertext)
+                    .await
+                    .check("opening private message")?
+            }
+            _ => fail!("expected public or private message"),
+        };
+
+        self.verify_signature(&auth_content, cs).await?;
+
+        Ok(auth_content.content.content)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn reprotect<P: CipherSuiteProvider + Clone>(
+        &self,
+        message: &[u8],
+        cs: &P,
+    ) -> Result<(), TestVectorError> {
+        let message = MlsMessage::mls_decode(&mut &*message).check("decoding message")?;
+
+        let MlsMessagePayload::Cipher(ciphertext) = message.payload else {
+            fail!("expected private message");
+        };
+
+        let auth_content = CiphertextProcessor::new(&mut self.state(RECEIVER), cs.clone())
+            .open(&ciphertext)
+            .await
+            .check("opening private message")?;
+
+        let ciphertext = CiphertextProcessor::new(&mut self.state(SENDER), cs.clone())
+            .seal(auth_content.clone(), PaddingMode::None)
+            .await
+            .check("sealing private message")?;
+
+        let reopened = CiphertextProcessor::new(&mut self.state(RECEIVER), cs.clone())
+            .open(&ciphertext)
+            .await
+            .check("opening private message")?;
+
+        ensure_eq!(reopened, auth_content, "protected message mismatch");
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn verify_signature<P: CipherSuiteProvider>(
+        &self,
+        auth_content: &AuthenticatedContent,
+        cs: &P,
+    ) -> Result<(), TestVectorError> {
+        let keys = [
+            None,
+            Some(SignaturePublicKey::from(self.signature_pub.clone())),
+        ];
+
+        verify_auth_content_signature(
+            cs,
+            SignaturePublicKeysContainer::List(&keys),
+            &self.context(),
+            auth_content,
+            #[cfg(feature = "by_ref_proposal")]
+            &[],
+        )
+        .await
+        .check("verifying signature")
+    }
+}
@@ -10,6 +10,7 @@ use mls_rs_core::{
     crypto::{CipherSuiteProvider, SignatureSecretKey},
     error::IntoAnyError,
 };
+use zeroize::Zeroizing;
 
 use crate::{
     cipher_suite::CipherSuite,
@@ -25,8 +26,8 @@ use crate::{
     ExtensionList, MlsRules,
 };
 
-#[cfg(all(not(mls_build_async), feature = "rayon"))]
-use {crate::iter::ParallelIteratorExt, rayon::prelude::*};
+#[cfg(feature = "by_ref_proposal")]
+use crate::extension::ExtensionType;
 
 use crate::tree_kem::leaf_node::LeafNode;
 
@@ -35,20 +36,29 @@ use crate::WireFormat;
 
 #[cfg(feature = "psk")]
 use crate::{
+    extension::RequiredJoinPskExt,
     group::{JustPreSharedKeyID, PskGroupId, ResumptionPSKUsage, ResumptionPsk},
+    mls_rules::{ProposalBundle, ProposalSource},
     psk::ExternalPskId,
 };
 
+#[cfg(feature = "psk")]
+use super::util::uses_required_join_psk;
+
+#[cfg(feature = "message_escrow")]
+use super::escrow::EscrowedExporterSecret;
+
 use super::{
     confirmation_tag::ConfirmationTag,
+    epoch::EpochSecrets,
     framing::{Content, MlsMessage, MlsMessagePayload, Sender},
     key_schedule::{KeySchedule, WelcomeSecret},
-    message_processor::{path_update_required, MessageProcessor},
+    message_processor::MessageProcessor,
     message_signature::AuthenticatedContent,
     mls_rules::CommitDirection,
     proposal::{Proposal, ProposalOrRef},
     ConfirmedTranscriptHash, EncryptedGroupSecrets, ExportedTree, Group, GroupContext, GroupInfo,
-    Welcome,
+    Member, Welcome,
 };
 
 #[cfg(not(feature = "by_ref_proposal"))]
@@ -74,6 +84,16 @@ pub(super) struct CommitGeneration {
     pub commit_message_hash: CommitHash,
 }
 
+/// Key schedule of the epoch created by the pending commit, derived when
+/// creating the commit. It is only kept in memory.
+#[derive(Clone)]
+pub(super) struct PrewarmedKeySchedule {
+    pub group_context: GroupContext,
+    pub key_schedule: KeySchedule,
+    pub confirmation_key: Zeroizing<Vec<u8>>,
+    pub epoch_secrets: EpochSecrets,
+}
+
 #[derive(Clone, PartialEq, MlsEncode, MlsDecode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct CommitHash(
@@ -130,9 +150,34 @@ pub struct CommitOutput {
     /// functionality. This value is set if [`MlsRules::commit_options`] returns
     /// `allow_external_commit` set to true.
     pub external_commit_group_info: Option<MlsMessage>,
+    /// Whether the commit includes an [`UpdatePath`]. This is decided based on the
+    /// proposals being committed, [`MlsRules::commit_options`] and
+    /// [`CommitBuilder::force_path_update`] or [`CommitBuilder::omit_path_update`].
+    pub contains_update_path: bool,
     /// Proposals that were received in the prior epoch but not included in the following commit.
+    ///
+    /// Proposals are listed by type and, within a type, by [`ProposalRef`](crate::mls_rules::ProposalRef),
+    /// so that replicas caching the same proposals produce the same list.
     #[cfg(feature = "by_ref_proposal")]
     pub unused_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    /// Proposals received by reference that were left out of the commit because they could
+    /// not be applied, each with the reason it was rejected. These are also part of
+    /// [`unused_proposals`](Self::unused_proposals).
+    ///
+    /// Rejected removals are listed first, followed by updates and adds, each ordered by
+    /// [`ProposalRef`](crate::mls_rules::ProposalRef).
+    #[cfg(feature = "by_ref_proposal")]
+    pub rejected_proposals: Vec<crate::mls_rules::RejectedProposal>,
+    /// A group info for the new epoch that can be re-published to external
+    /// senders. This value is set if the commit changes the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// of the group context.
+    #[cfg(feature = "by_ref_proposal")]
+    pub external_senders_group_info: Option<MlsMessage>,
+    /// Shares of the exporter secret of the new epoch encrypted to each escrow key if the
+    /// new group context contains [`ExporterEscrowExt`](crate::extension::built_in::ExporterEscrowExt).
+    #[cfg(feature = "message_escrow")]
+    pub escrowed_exporter_secrets: Vec<EscrowedExporterSecret>,
 }
 
 #[cfg_attr(all(feature = "ffi", not(test)), ::safer_ffi_gen::safer_ffi_gen)]
@@ -165,11 +210,48 @@ impl CommitOutput {
         self.external_commit_group_info.as_ref()
     }
 
+    /// Whether the commit includes an [`UpdatePath`].
+    #[cfg(feature = "ffi")]
+    pub fn contains_update_path(&self) -> bool {
+        self.contains_update_path
+    }
+
     /// Proposals that were received in the prior epoch but not included in the following commit.
     #[cfg(all(feature = "ffi", feature = "by_ref_proposal"))]
     pub fn unused_proposals(&self) -> &[crate::mls_rules::ProposalInfo<Proposal>] {
         &self.unused_proposals
     }
+
+    /// A group info for the new epoch that can be re-published to external
+    /// senders. This value is set if the commit changes the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// of the group context.
+    #[cfg(all(feature = "ffi", feature = "by_ref_proposal"))]
+    pub fn external_senders_group_info(&self) -> Option<&MlsMessage> {
+        self.external_senders_group_info.as_ref()
+    }
+
+    /// Shares of the exporter secret of the new epoch encrypted to each escrow key if the
+    /// new group context contains [`ExporterEscrowExt`](crate::extension::built_in::ExporterEscrowExt).
+    #[cfg(all(feature = "ffi", feature = "message_escrow"))]
+    pub fn escrowed_exporter_secrets(&self) -> &[EscrowedExporterSecret] {
+        &self.escrowed_exporter_secrets
+    }
+}
+
+/// Outcome of validating a single proposal with [`Group::validate_proposals`].
+#[derive(Debug)]
+pub enum ProposalVerdict {
+    /// The proposal can be committed.
+    Valid,
+    /// The proposal would be rejected with the contained error.
+    Invalid(MlsError),
+}
+
+impl ProposalVerdict {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, ProposalVerdict::Valid)
+    }
 }
 
 /// Build a commit with multiple proposals by-value.
@@ -189,6 +271,8 @@ where
     group_info_extensions: ExtensionList,
     new_signer: Option<SignatureSecretKey>,
     new_signing_identity: Option<SigningIdentity>,
+    path_update: Option<bool>,
+    ratchet_tree_extension: Option<bool>,
 }
 
 impl<'a, C> CommitBuilder<'a, C>
@@ -228,6 +312,74 @@ where
         Ok(self)
     }
 
+    /// Insert a [`RemoveProposal`](crate::group::proposal::RemoveProposal) into
+    /// the current commit for every member of the current epoch matching
+    /// `predicate`, for instance all members whose credential was issued by
+    /// a given certificate authority.
+    ///
+    /// The committer is never removed, even if it matches `predicate`. Members
+    /// already staged for removal by this builder are skipped.
+    pub fn remove_where<F>(mut self, mut predicate: F) -> Result<Self, MlsError>
+    where
+        F: FnMut(&Member) -> bool,
+    {
+        let committer = self.group.current_member_index();
+
+        let to_remove = self
+            .group
+            .roster()
+            .members_iter()
+            .filter(|member| member.index != committer && predicate(member))
+            .map(|member| member.index)
+            .collect::<Vec<_>>();
+
+        for index in to_remove {
+            let already_staged = self
+                .proposals
+                .iter()
+                .any(|p| matches!(p, Proposal::Remove(remove) if *remove.to_remove == index));
+
+            if !already_staged {
+                let proposal = self.group.remove_proposal(index)?;
+                self.proposals.push(proposal);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Move the member at `index` to the leaf chosen by
+    /// [`MlsRules::leaf_placement`](crate::MlsRules::leaf_placement) by removing it and adding
+    /// it back with `key_package`, a new key package of the same member. With the default
+    /// placement, the member moves to the leftmost blank leaf, which lets the tree shrink once
+    /// all [relocation candidates](crate::group::Roster::relocation_candidates) have moved.
+    ///
+    /// The relocated member rejoins the group from the resulting welcome message. A committer
+    /// can not relocate itself.
+    pub fn relocate_member(
+        mut self,
+        index: u32,
+        key_package: MlsMessage,
+    ) -> Result<Self, MlsError> {
+        let member = self.group.roster().member_with_index(index)?;
+        let add = self.group.add_proposal(key_package)?;
+
+        let same_member = matches!(
+            &add,
+            Proposal::Add(add) if add.key_package.leaf_node.signing_identity.credential
+                == member.signing_identity.credential
+        );
+
+        if !same_member {
+            return Err(MlsError::InvalidSuccessor);
+        }
+
+        let remove = self.group.remove_proposal(index)?;
+        self.proposals.extend([remove, add]);
+
+        Ok(self)
+    }
+
     /// Insert a
     /// [`GroupContextExtensions`](crate::group::proposal::Proposal::GroupContextExtensions)
     /// into the current commit that is being built.
@@ -237,6 +389,34 @@ where
         Ok(self)
     }
 
+    /// Insert a
+    /// [`GroupContextExtensions`](crate::group::proposal::Proposal::GroupContextExtensions)
+    /// adding `sender` to the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// into the current commit that is being built.
+    ///
+    /// See [`Group::propose_add_external_sender`] for details.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn add_external_sender(mut self, sender: SigningIdentity) -> Result<Self, MlsError> {
+        let proposal = self.group.add_external_sender_proposal(sender)?;
+        self.proposals.push(proposal);
+        Ok(self)
+    }
+
+    /// Insert a
+    /// [`GroupContextExtensions`](crate::group::proposal::Proposal::GroupContextExtensions)
+    /// removing `sender` from the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// into the current commit that is being built.
+    ///
+    /// See [`Group::propose_remove_external_sender`] for details.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn remove_external_sender(mut self, sender: &SigningIdentity) -> Result<Self, MlsError> {
+        let proposal = self.group.remove_external_sender_proposal(sender)?;
+        self.proposals.push(proposal);
+        Ok(self)
+    }
+
     /// Insert a
     /// [`PreSharedKeyProposal`](crate::group::proposal::PreSharedKeyProposal) with
     /// an external PSK into the current commit that is being built.
@@ -337,6 +517,50 @@ where
         }
     }
 
+    /// Include an [`UpdatePath`] in the commit even if it is neither required
+    /// by the proposals being committed nor by
+    /// [`MlsRules::commit_options`](crate::MlsRules::commit_options).
+    ///
+    /// This refreshes the committer's path secrets, providing post-compromise
+    /// security for the new epoch.
+    pub fn force_path_update(self) -> Self {
+        Self {
+            path_update: Some(true),
+            ..self
+        }
+    }
+
+    /// Omit the [`UpdatePath`] from the commit even if
+    /// [`MlsRules::commit_options`](crate::MlsRules::commit_options) requires
+    /// it.
+    ///
+    /// If the proposals being committed require a path according to the MLS
+    /// RFC, e.g. because they contain an update or remove proposal, or
+    /// because they contain a custom proposal registered with
+    /// [`CustomProposalMetadata::path_required`](crate::group::proposal::CustomProposalMetadata::path_required),
+    /// [`CommitBuilder::build`] fails with [`MlsError::CommitMissingPath`].
+    pub fn omit_path_update(self) -> Self {
+        Self {
+            path_update: Some(false),
+            ..self
+        }
+    }
+
+    /// Override whether the ratchet tree extension is included in the group
+    /// info of the welcome messages and of the external commit group info
+    /// created by this commit, regardless of
+    /// [`CommitOptions::ratchet_tree_extension`](crate::mls_rules::CommitOptions::ratchet_tree_extension).
+    ///
+    /// If the extension is not included, the new tree is returned in
+    /// [`CommitOutput::ratchet_tree`] and must be delivered to new members
+    /// out of band.
+    pub fn ratchet_tree_extension(self, enabled: bool) -> Self {
+        Self {
+            ratchet_tree_extension: Some(enabled),
+            ..self
+        }
+    }
+
     /// Finalize the commit to send.
     ///
     /// # Errors
@@ -355,6 +579,8 @@ where
                 self.group_info_extensions,
                 self.new_signer,
                 self.new_signing_identity,
+                self.path_update,
+                self.ratchet_tree_extension,
             )
             .await
     }
@@ -413,6 +639,8 @@ where
             Default::default(),
             None,
             None,
+            None,
+            None,
         )
         .await
     }
@@ -427,7 +655,94 @@ where
             group_info_extensions: Default::default(),
             new_signer: Default::default(),
             new_signing_identity: Default::default(),
+            path_update: Default::default(),
+            ratchet_tree_extension: Default::default(),
+        }
+    }
+
+    /// Check whether `proposals` would be accepted if committed by value in the
+    /// current epoch, without creating a commit or modifying the group.
+    ///
+    /// The proposals are checked in order, together with the proposals cached
+    /// for commit, using the same [`MlsRules`] and tree validation as
+    /// [`Group::commit`]. A proposal is reported invalid if it is rejected on
+    /// its own or in combination with the valid proposals preceding it.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn validate_proposals(
+        &self,
+        proposals: &[Proposal],
+    ) -> Result<Vec<ProposalVerdict>, MlsError> {
+        if self.pending_commit.is_some() {
+            return Err(MlsError::ExistingPendingCommit);
+        }
+
+        if self.state.pending_reinit.is_some() {
+            return Err(MlsError::GroupUsedAfterReInit);
+        }
+
+        self.check_not_removed()?;
+
+        if self.simulate_commit(proposals.to_vec()).await.is_ok() {
+            return Ok(proposals.iter().map(|_| ProposalVerdict::Valid).collect());
         }
+
+        let mut accepted = Vec::new();
+        let mut verdicts = Vec::with_capacity(proposals.len());
+
+        for proposal in proposals {
+            accepted.push(proposal.clone());
+
+            match self.simulate_commit(accepted.clone()).await {
+                Ok(_) => verdicts.push(ProposalVerdict::Valid),
+                Err(e) => {
+                    accepted.pop();
+                    verdicts.push(ProposalVerdict::Invalid(e));
+                }
+            }
+        }
+
+        Ok(verdicts)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn simulate_commit(&self, proposals: Vec<Proposal>) -> Result<(), MlsError> {
+        let sender = Sender::Member(*self.private_tree.self_index);
+
+        #[cfg(feature = "std")]
+        let time = Some(crate::time::MlsTime::now());
+
+        #[cfg(not(feature = "std"))]
+        let time = None;
+
+        #[cfg(feature = "by_ref_proposal")]
+        let proposals =
+            self.state
+                .proposals
+                .prepare_commit(sender, proposals, time, |proposal_type| {
+                    self.config.by_reference_allowed(proposal_type)
+                });
+
+        #[cfg(not(feature = "by_ref_proposal"))]
+        let proposals = prepare_commit(sender, proposals);
+
+        #[cfg(feature = "psk")]
+        let proposals = self.add_required_join_psk(proposals, sender)?;
+
+        self.state
+            .apply_resolved(
+                sender,
+                proposals,
+                None,
+                &self.config.member_identity_provider(),
+                &self.cipher_suite_provider,
+                &self.config.secret_store(),
+                &self.config.mls_rules(),
+                time,
+                self.lifetime_policy(),
+                CommitDirection::Send,
+            )
+            .await
+            .map(|_| ())
     }
 
     /// Returns commit and optional [`MlsMessage`] containing a welcome message
@@ -442,6 +757,8 @@ where
         mut welcome_group_info_extensions: ExtensionList,
         new_signer: Option<SignatureSecretKey>,
         new_signing_identity: Option<SigningIdentity>,
+        path_update: Option<bool>,
+        ratchet_tree_extension: Option<bool>,
     ) -> Result<CommitOutput, MlsError> {
         if self.pending_commit.is_some() {
             return Err(MlsError::ExistingPendingCommit);
@@ -451,6 +768,8 @@ where
             return Err(MlsError::GroupUsedAfterReInit);
         }
 
+        self.check_not_removed()?;
+
         let mls_rules = self.config.mls_rules();
 
         let is_external = external_leaf.is_some();
@@ -474,26 +793,45 @@ where
         let time = None;
 
         #[cfg(feature = "by_ref_proposal")]
-        let proposals = self.state.proposals.prepare_commit(sender, proposals);
+        let proposals =
+            self.state
+                .proposals
+                .prepare_commit(sender, proposals, time, |proposal_type| {
+                    self.config.by_reference_allowed(proposal_type)
+                });
 
         #[cfg(not(feature = "by_ref_proposal"))]
         let proposals = prepare_commit(sender, proposals);
 
+        #[cfg(feature = "psk")]
+        let proposals = self.add_required_join_psk(proposals, sender)?;
+
         let mut provisional_state = self
             .state
             .apply_resolved(
                 sender,
                 proposals,
                 external_leaf,
-                &self.config.identity_provider(),
+                &self.config.member_identity_provider(),
                 &self.cipher_suite_provider,
                 &self.config.secret_store(),
                 &mls_rules,
                 time,
+                self.lifetime_policy(),
                 CommitDirection::Send,
             )
             .await?;
 
+        // Cached proposals left out before filtering are unused as well
+        #[cfg(feature = "by_ref_proposal")]
+        provisional_state.unused_proposals.extend(
+            self.state
+                .proposals
+                .excluded_from_commit(time, |proposal_type| {
+                    self.config.by_reference_allowed(proposal_type)
+                }),
+        );
+
         let (mut provisional_private_tree, _) =
             self.provisional_private_tree(&provisional_state)?;
 
@@ -510,16 +848,28 @@ where
         // Decide whether to populate the path field: If the path field is required based on the
         // proposals that are in the commit (see above), then it MUST be populated. Otherwise, the
         // sender MAY omit the path field at its discretion.
-        let commit_options = mls_rules
+        let mut commit_options = mls_rules
             .commit_options(
                 &provisional_state.public_tree.roster(),
                 &provisional_group_context.extensions,
                 &provisional_state.applied_proposals,
             )
+            .map(|options| self.config_overrides.commit_options(options))
             .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
 
-        let perform_path_update = commit_options.path_required
-            || path_update_required(&provisional_state.applied_proposals);
+        if let Some(ratchet_tree_extension) = ratchet_tree_extension {
+            commit_options.ratchet_tree_extension = ratchet_tree_extension;
+        }
+
+        let path_update_required = self.commit_path_required(&provisional_state.applied_proposals);
+
+        // The path may be forced or omitted by the committer, but never omitted if
+        // it is required by the proposals.
+        let perform_path_update = match path_update {
+            Some(false) if path_update_required => return Err(MlsError::CommitMissingPath),
+            Some(perform) => perform,
+            None => commit_options.path_required || path_update_required,
+        };
 
         let (update_path, path_secrets, commit_secret) = if perform_path_update {
             // If populating the path field: Create an UpdatePath using the new tree. Any new
@@ -612,12 +962,14 @@ where
 
         provisional_group_context.confirmed_transcript_hash = confirmed_transcript_hash;
 
+        // The epoch secrets are sized for the new tree so that they can be kept
+        // as a prewarmed key schedule
         let key_schedule_result = KeySchedule::from_key_schedule(
             &self.key_schedule,
             &commit_secret,
             &provisional_group_context,
             #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-            self.state.public_tree.total_leaf_count(),
+            provisional_state.public_tree.total_leaf_count(),
             &psk_secret,
             &self.cipher_suite_provider,
         )
@@ -632,6 +984,14 @@ where
 
         auth_content.auth.confirmation_tag = Some(confirmation_tag.clone());
 
+        #[cfg(feature = "message_escrow")]
+        let escrowed_exporter_secrets = EscrowedExporterSecret::seal_all(
+            &self.cipher_suite_provider,
+            &provisional_group_context,
+            key_schedule_result.key_schedule.exporter_secret(),
+        )
+        .await?;
+
         let ratchet_tree_ext = commit_options
             .ratchet_tree_extension
             .then(|| RatchetTreeExt {
@@ -671,6 +1031,39 @@ where
             false => None,
         };
 
+        // Re-publish the group info if the set of external senders changed
+        #[cfg(feature = "by_ref_proposal")]
+        let external_senders_group_info = if provisional_group_context
+            .extensions
+            .get(ExtensionType::EXTERNAL_SENDERS)
+            != self
+                .context()
+                .extensions
+                .get(ExtensionType::EXTERNAL_SENDERS)
+        {
+            let mut extensions = ExtensionList::new();
+
+            if let Some(ref ratchet_tree_ext) = ratchet_tree_ext {
+                extensions.set_from(ratchet_tree_ext.clone())?;
+            }
+
+            let info = self
+                .make_group_info(
+                    &provisional_group_context,
+                    extensions,
+                    &confirmation_tag,
+                    new_signer_ref,
+                )
+                .await?;
+
+            Some(MlsMessage::new(
+                self.protocol_version(),
+                MlsMessagePayload::GroupInfo(info),
+            ))
+        } else {
+            None
+        };
+
         // Build the group info that will be placed into the welcome messages.
         // Add the ratchet tree extension if necessary
         if let Some(ratchet_tree_ext) = ratchet_tree_ext {
@@ -702,47 +1095,24 @@ where
         // Encrypt path secrets and joiner secret to new members
         let path_secrets = path_secrets.as_ref();
 
-        #[cfg(not(any(mls_build_async, not(feature = "rayon"))))]
-        let encrypted_path_secrets: Vec<_> = added_key_pkgs
-            .into_par_iter()
+        let new_members = added_key_pkgs
+            .into_iter()
             .zip(provisional_state.indexes_of_added_kpkgs)
             .map(|(key_package, leaf_index)| {
-                self.encrypt_group_secrets(
-                    &key_package,
+                self.group_secrets(
                     leaf_index,
                     &key_schedule_result.joiner_secret,
                     path_secrets,
                     #[cfg(feature = "psk")]
                     psks.clone(),
-                    &encrypted_group_info,
                 )
+                .map(|secrets| (key_package, secrets))
             })
-            .try_collect()?;
-
-        #[cfg(any(mls_build_async, not(feature = "rayon")))]
-        let encrypted_path_secrets = {
-            let mut secrets = Vec::new();
-
-            for (key_package, leaf_index) in added_key_pkgs
-                .into_iter()
-                .zip(provisional_state.indexes_of_added_kpkgs)
-            {
-                secrets.push(
-                    self.encrypt_group_secrets(
-                        &key_package,
-                        leaf_index,
-                        &key_schedule_result.joiner_secret,
-                        path_secrets,
-                        #[cfg(feature = "psk")]
-                        psks.clone(),
-                        &encrypted_group_info,
-                    )
-                    .await?,
-                );
-            }
+            .collect::<Result<Vec<_>, MlsError>>()?;
 
-            secrets
-        };
+        let encrypted_path_secrets = self
+            .encrypt_group_secrets(&new_members, &encrypted_group_info)
+            .await?;
 
         let welcome_messages =
             if commit_options.single_welcome_message && !encrypted_path_secrets.is_empty() {
@@ -766,11 +1136,39 @@ where
 
         self.pending_commit = Some(pending_commit);
 
+        self.prewarmed_key_schedule =
+            self.config
+                .prewarm_key_schedule()
+                .then(|| PrewarmedKeySchedule {
+                    group_context: provisional_group_context,
+                    key_schedule: key_schedule_result.key_schedule,
+                    confirmation_key: key_schedule_result.confirmation_key,
+                    epoch_secrets: key_schedule_result.epoch_secrets,
+                });
+
         let ratchet_tree = (!commit_options.ratchet_tree_extension)
             .then(|| ExportedTree::new(provisional_state.public_tree.nodes));
 
-        if let Some(signer) = new_signer {
-            self.signer = signer;
+        let previous_signer = new_signer.map(|signer| core::mem::replace(&mut self.signer, signer));
+
+        // The commit is only returned once the state needed to apply it is written
+        if let Err(e) = self.persist_pending_state().await {
+            self.clear_pending_commit();
+
+            if let Some(signer) = previous_signer {
+                self.signer = signer;
+            }
+
+            return Err(e);
+        }
+
+        #[cfg(feature = "by_ref_proposal")]
+        {
+            let epoch = self.current_epoch();
+            self.rejected_proposals.prune(epoch);
+
+            self.rejected_proposals
+                .insert_unused(&provisional_state.unused_proposals, epoch);
         }
 
         Ok(CommitOutput {
@@ -778,11 +1176,52 @@ where
             welcome_messages,
             ratchet_tree,
             external_commit_group_info,
+            contains_update_path: perform_path_update,
             #[cfg(feature = "by_ref_proposal")]
             unused_proposals: provisional_state.unused_proposals,
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: provisional_state.rejected_proposals,
+            #[cfg(feature = "by_ref_proposal")]
+            external_senders_group_info,
+            #[cfg(feature = "message_escrow")]
+            escrowed_exporter_secrets,
         })
     }
 
+    /// Include the PSK required by [`RequiredJoinPskExt`] if the commit adds
+    /// members and does not already use that PSK.
+    #[cfg(feature = "psk")]
+    fn add_required_join_psk(
+        &self,
+        mut proposals: ProposalBundle,
+        sender: Sender,
+    ) -> Result<ProposalBundle, MlsError> {
+        #[cfg(test)]
+        if self.commit_modifiers.skip_required_join_psk {
+            return Ok(proposals);
+        }
+
+        if proposals.add_proposals().is_empty() {
+            return Ok(proposals);
+        }
+
+        // Joiners see the group context resulting from this commit
+        let extensions = proposals
+            .group_context_extensions_proposal()
+            .map_or(&self.context().extensions, |p| &p.proposal);
+
+        let Some(required) = extensions.get_as::<RequiredJoinPskExt>()? else {
+            return Ok(proposals);
+        };
+
+        if !uses_required_join_psk(&proposals, &required) {
+            let proposal = self.psk_proposal(JustPreSharedKeyID::External(required.psk_id))?;
+            proposals.add(proposal, sender, ProposalSource::ByValue);
+        }
+
+        Ok(proposals)
+    }
+
     // Construct a GroupInfo reflecting the new state
     // Group ID, epoch, tree, and confirmed transcript hash from the new state
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -795,7 +1234,7 @@ where
     ) -> Result<GroupInfo, MlsError> {
         let mut group_info = GroupInfo {
             group_context: group_context.clone(),
-            extensions,
+            extensions: self.group_info_extensions(group_context, extensions)?,
             confirmation_tag: confirmation_tag.clone(), // The confirmation_tag from the MlsPlaintext object
             signer: LeafIndex(self.current_member_index()),
             signature: vec![],
@@ -841,6 +1280,8 @@ pub(crate) mod test_utils {
         pub modify_leaf: fn(&mut LeafNode, &SignatureSecretKey) -> Option<SignatureSecretKey>,
         pub modify_tree: fn(&mut TreeKemPublic),
         pub modify_path: fn(Vec<UpdatePathNode>) -> Vec<UpdatePathNode>,
+        #[cfg(feature = "psk")]
+        pub skip_required_join_psk: bool,
     }
 
     impl Default for CommitModifiers {
@@ -849,6 +1290,8 @@ pub(crate) mod test_utils {
                 modify_leaf: |_, _| None,
                 modify_tree: |_| (),
                 modify_path: |a| a,
+                #[cfg(feature = "psk")]
+                skip_required_join_psk: false,
             }
         }
     }
@@ -857,6 +1300,7 @@ pub(crate) mod test_utils {
 #[cfg(test)]
 mod tests {
     use alloc::boxed::Box;
+    use assert_matches::assert_matches;
 
     use mls_rs_core::{
         error::IntoAnyError,
@@ -1048,6 +1492,46 @@ mod tests {
         assert_commit_builder_output(group, commit_output, vec![expected_remove], 0);
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_commit_builder_remove_where() {
+        let mut group = test_commit_builder_group().await;
+        let mut builder = group.commit_builder();
+
+        for name in ["bob", "carol", "dave"] {
+            let key_package =
+                test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, name).await;
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        builder.build().await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+
+        // Every member but carol, including the committer, matches
+        let commit_output = group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .remove_where(|member| {
+                member
+                    .signing_identity
+                    .credential
+                    .as_basic()
+                    .map_or(false, |c| c.identifier != b"carol")
+            })
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let expected = vec![
+            group.remove_proposal(1).unwrap(),
+            group.remove_proposal(3).unwrap(),
+        ];
+
+        assert_commit_builder_output(group, commit_output, expected, 0);
+    }
+
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_commit_builder_psk() {
@@ -1181,6 +1665,74 @@ mod tests {
         assert_commit_builder_output(group, commit_output, vec![], 0);
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_builder_can_force_path_update() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let kp = test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let commit_output = groups[0]
+            .group
+            .commit_builder()
+            .add_member(kp.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(!commit_output.contains_update_path);
+        groups[0].group.clear_pending_commit();
+
+        let commit_output = groups[0]
+            .group
+            .commit_builder()
+            .add_member(kp)
+            .unwrap()
+            .force_path_update()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(commit_output.contains_update_path);
+
+        let commit = commit_output
+            .commit_message
+            .clone()
+            .into_plaintext()
+            .unwrap();
+        assert_matches!(commit.content.content, Content::Commit(commit) if commit.path.is_some());
+
+        groups[0].process_pending_commit().await.unwrap();
+
+        groups[1]
+            .process_message(commit_output.commit_message)
+            .await
+            .unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_builder_can_omit_path_update_if_allowed() {
+        let mut group = test_commit_builder_group().await;
+        group.config.0.mls_rules.commit_options.path_required = true;
+
+        let kp = test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let commit_output = group
+            .commit_builder()
+            .add_member(kp)
+            .unwrap()
+            .omit_path_update()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(!commit_output.contains_update_path);
+        group.clear_pending_commit();
+
+        let res = group.commit_builder().omit_path_update().build().await;
+        assert_matches!(res, Err(MlsError::CommitMissingPath));
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_commit_builder_authenticated_data() {
         let mut group = test_commit_builder_group().await;
@@ -1335,6 +1887,47 @@ mod tests {
         assert!(commit.ratchet_tree.is_none());
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_builder_overrides_ratchet_tree_ext() {
+        for enabled in [false, true] {
+            let mut group = test_group_custom(
+                TEST_PROTOCOL_VERSION,
+                TEST_CIPHER_SUITE,
+                Default::default(),
+                None,
+                Some(CommitOptions::new().with_ratchet_tree_extension(!enabled)),
+            )
+            .await
+            .group;
+
+            let (bob, key_package) =
+                test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+            let commit = group
+                .commit_builder()
+                .add_member(key_package)
+                .unwrap()
+                .ratchet_tree_extension(enabled)
+                .build()
+                .await
+                .unwrap();
+
+            assert_eq!(commit.ratchet_tree.is_none(), enabled);
+
+            let preview = bob
+                .preview_welcome(&commit.welcome_messages[0])
+                .await
+                .unwrap();
+
+            assert_eq!(
+                preview
+                    .group_info_extensions
+                    .has_extension(ExtensionType::RATCHET_TREE),
+                enabled
+            );
+        }
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn commit_includes_external_commit_group_info_if_requested() {
         let mut group = test_group_custom(
@@ -1596,6 +2189,35 @@ mod tests {
             .extension_types(vec![TEST_EXTENSION_TYPE.into()])
             .identity_provider(IdentityProviderWithExtension(BasicIdentityProvider::new()))
             .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
-            .build()
+            .build_unchecked()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn validating_proposals_reports_verdicts_without_changing_state() {
+        let group = test_commit_builder_group().await;
+
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let add = group.add_proposal(key_package).unwrap();
+
+        let remove = Proposal::Remove(crate::group::proposal::RemoveProposal {
+            to_remove: LeafIndex(5),
+        });
+
+        let verdicts = group
+            .validate_proposals(&[add.clone(), add.clone(), remove])
+            .await
+            .unwrap();
+
+        assert!(verdicts[0].is_valid());
+        assert_matches!(verdicts[1], ProposalVerdict::Invalid(_));
+        assert_matches!(verdicts[2], ProposalVerdict::Invalid(_));
+
+        let verdicts = group.validate_proposals(&[add]).await.unwrap();
+        assert!(verdicts.iter().all(ProposalVerdict::is_valid));
+
+        assert!(!group.has_pending_commit());
+        assert_eq!(group.roster().members_iter().count(), 1);
     }
 }
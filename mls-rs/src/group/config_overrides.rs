@@ -0,0 +1,148 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::extension::{ExtensionList, ExtensionType};
+
+use crate::{
+    client::MlsError,
+    extension::RequiredCapabilitiesExt,
+    group::{mls_rules::CommitOptions, padding::PaddingMode, ExternalCommitPolicy, LifetimePolicy},
+    tree_kem::Lifetime,
+};
+
+#[cfg(feature = "private_message")]
+use crate::group::mls_rules::EncryptionOptions;
+
+/// Per-group overrides of the configuration of a [`Client`](crate::Client).
+///
+/// Overrides are provided when a group is created or joined and are persisted
+/// together with the group state. Any value that is not overridden is taken
+/// from the client configuration.
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct GroupConfigOverrides {
+    /// Lifetime of leaf nodes generated when creating the group or joining it
+    /// via external commit.
+    pub lifetime: Option<Lifetime>,
+    /// Commit options used instead of those returned by
+    /// [`MlsRules::commit_options`](crate::MlsRules::commit_options).
+    pub commit_options: Option<CommitOptions>,
+    /// Padding mode used instead of the one returned by
+    /// [`MlsRules::encryption_options`](crate::MlsRules::encryption_options).
+    /// Only used with the `private_message` feature.
+    pub padding_mode: Option<PaddingMode>,
+    /// Policy used instead of the one configured with
+    /// [`ClientBuilder::lifetime_policy`](crate::client_builder::ClientBuilder::lifetime_policy)
+    /// to check lifetimes of leaf nodes added to the group.
+    pub lifetime_policy: Option<LifetimePolicy>,
+    /// Forms of external commits accepted by this member. If not set, every
+    /// valid external commit is accepted.
+    pub external_commit_policy: Option<ExternalCommitPolicy>,
+    /// Capabilities required from members when creating the group. Ignored if
+    /// the group context extensions provided at creation already require
+    /// capabilities.
+    pub required_capabilities: Option<RequiredCapabilitiesExt>,
+}
+
+impl GroupConfigOverrides {
+    /// Create an empty set of overrides that defers to the client configuration.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Override the lifetime of leaf nodes generated for this group.
+    pub fn with_lifetime(self, lifetime: Lifetime) -> Self {
+        Self {
+            lifetime: Some(lifetime),
+            ..self
+        }
+    }
+
+    /// Override commit options.
+    pub fn with_commit_options(self, commit_options: CommitOptions) -> Self {
+        Self {
+            commit_options: Some(commit_options),
+            ..self
+        }
+    }
+
+    /// Override the padding mode of encrypted messages.
+    pub fn with_padding_mode(self, padding_mode: PaddingMode) -> Self {
+        Self {
+            padding_mode: Some(padding_mode),
+            ..self
+        }
+    }
+
+    /// Override the policy used to check lifetimes of added leaf nodes.
+    pub fn with_lifetime_policy(self, lifetime_policy: LifetimePolicy) -> Self {
+        Self {
+            lifetime_policy: Some(lifetime_policy),
+            ..self
+        }
+    }
+
+    pub(crate) fn lifetime(&self, default: Lifetime) -> Lifetime {
+        self.lifetime.clone().unwrap_or(default)
+    }
+
+    /// Restrict the forms of external commits accepted by this member.
+    pub fn with_external_commit_policy(self, external_commit_policy: ExternalCommitPolicy) -> Self {
+        Self {
+            external_commit_policy: Some(external_commit_policy),
+            ..self
+        }
+    }
+
+    /// Require capabilities from members of a group created with these
+    /// overrides.
+    pub fn with_required_capabilities(
+        self,
+        required_capabilities: RequiredCapabilitiesExt,
+    ) -> Self {
+        Self {
+            required_capabilities: Some(required_capabilities),
+            ..self
+        }
+    }
+
+    pub(crate) fn group_context_extensions(
+        &self,
+        mut extensions: ExtensionList,
+    ) -> Result<ExtensionList, MlsError> {
+        if let Some(required_capabilities) = &self.required_capabilities {
+            if !extensions.has_extension(ExtensionType::REQUIRED_CAPABILITIES) {
+                extensions.set_from(required_capabilities.clone())?;
+            }
+        }
+
+        Ok(extensions)
+    }
+
+    pub(crate) fn commit_options(&self, default: CommitOptions) -> CommitOptions {
+        self.commit_options.unwrap_or(default)
+    }
+
+    pub(crate) fn lifetime_policy(&self, default: LifetimePolicy) -> LifetimePolicy {
+        self.lifetime_policy.unwrap_or(default)
+    }
+
+    pub(crate) fn external_commit_policy(&self) -> ExternalCommitPolicy {
+        self.external_commit_policy.unwrap_or_default()
+    }
+
+    #[cfg(feature = "private_message")]
+    pub(crate) fn encryption_options(&self, default: EncryptionOptions) -> EncryptionOptions {
+        EncryptionOptions {
+            padding_mode: self.padding_mode.unwrap_or(default.padding_mode),
+            ..default
+        }
+    }
+}
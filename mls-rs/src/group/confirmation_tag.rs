@@ -64,7 +64,7 @@ impl ConfirmationTag {
         )
         .await?;
 
-        Ok(&tag == self)
+        Ok(cipher_suite_provider.constant_time_eq(&tag, self))
     }
 }
 
@@ -0,0 +1,214 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsEncode, MlsSize};
+use mls_rs_core::{crypto::CipherSuiteProvider, error::IntoAnyError};
+
+use crate::{client::MlsError, client_config::ClientConfig, Group};
+
+use super::GroupContext;
+
+#[cfg(feature = "external_client")]
+use crate::external_client::{ExternalClientConfig, ExternalGroup};
+
+const CONTENT_BINDING_LABEL: &[u8] = b"MLS 1.0 content binding";
+
+#[derive(MlsSize, MlsEncode)]
+struct ContentBindingInput<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    label: &'a [u8],
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    application_label: &'a [u8],
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    confirmed_transcript_hash: &'a [u8],
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    content: &'a [u8],
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn content_binding<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    context: &GroupContext,
+    application_label: &[u8],
+    content: &[u8],
+) -> Result<Vec<u8>, MlsError> {
+    let input = ContentBindingInput {
+        label: CONTENT_BINDING_LABEL,
+        application_label,
+        group_id: &context.group_id,
+        epoch: context.epoch,
+        confirmed_transcript_hash: &context.confirmed_transcript_hash,
+        content,
+    };
+
+    cipher_suite_provider
+        .hash(&input.mls_encode_to_vec()?)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_content_binding<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    context: &GroupContext,
+    application_label: &[u8],
+    content: &[u8],
+    binding: &[u8],
+) -> Result<(), MlsError> {
+    let expected =
+        content_binding(cipher_suite_provider, context, application_label, content).await?;
+
+    (expected == binding)
+        .then_some(())
+        .ok_or(MlsError::InvalidContentBinding)
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Compute a hash binding `content` to the current epoch of the group.
+    ///
+    /// The binding covers the group id, the epoch, the confirmed transcript
+    /// hash and `application_label`, which separates the uses of bindings by
+    /// the application. It can be attached to artifacts exchanged outside of
+    /// the group, such as file uploads or calls, and checked by members and
+    /// external observers of the same epoch with
+    /// [`verify_content_binding`](Self::verify_content_binding).
+    ///
+    /// The binding is not secret and does not authenticate its creator.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn content_binding(
+        &self,
+        application_label: &[u8],
+        content: &[u8],
+    ) -> Result<Vec<u8>, MlsError> {
+        content_binding(
+            &self.cipher_suite_provider,
+            self.context(),
+            application_label,
+            content,
+        )
+        .await
+    }
+
+    /// Check that `binding` was computed by
+    /// [`content_binding`](Self::content_binding) for `content` in the
+    /// current epoch of the group.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_content_binding(
+        &self,
+        application_label: &[u8],
+        content: &[u8],
+        binding: &[u8],
+    ) -> Result<(), MlsError> {
+        verify_content_binding(
+            &self.cipher_suite_provider,
+            self.context(),
+            application_label,
+            content,
+            binding,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "external_client")]
+impl<C> ExternalGroup<C>
+where
+    C: ExternalClientConfig + Clone,
+{
+    /// Check that `binding` was computed by
+    /// [`Group::content_binding`] for `content` in the current epoch of the
+    /// group.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn verify_content_binding(
+        &self,
+        application_label: &[u8],
+        content: &[u8],
+        binding: &[u8],
+    ) -> Result<(), MlsError> {
+        verify_content_binding(
+            &self.cipher_suite_provider,
+            self.group_context(),
+            application_label,
+            content,
+            binding,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group,
+    };
+
+    #[cfg(feature = "external_client")]
+    use crate::external_client::group::test_utils::make_external_group;
+
+    const LABEL: &[u8] = b"file upload";
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn content_binding_is_verified_by_group_observers() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        let binding = alice.group.content_binding(LABEL, b"file").await.unwrap();
+
+        bob.group
+            .verify_content_binding(LABEL, b"file", &binding)
+            .await
+            .unwrap();
+
+        #[cfg(feature = "external_client")]
+        make_external_group(&alice)
+            .await
+            .verify_content_binding(LABEL, b"file", &binding)
+            .await
+            .unwrap();
+
+        let res = bob
+            .group
+            .verify_content_binding(b"call", b"file", &binding)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidContentBinding));
+
+        let res = bob
+            .group
+            .verify_content_binding(LABEL, b"other file", &binding)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidContentBinding));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn content_binding_is_not_valid_in_other_epoch() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let binding = alice.group.content_binding(LABEL, b"file").await.unwrap();
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let res = alice
+            .group
+            .verify_content_binding(LABEL, b"file", &binding)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidContentBinding));
+    }
+}
@@ -0,0 +1,706 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{boxed::Box, vec::Vec};
+use mls_rs_codec::{iter::mls_decode_split_on_collection, MlsDecode};
+use mls_rs_core::{
+    crypto::CipherSuite,
+    extension::{ExtensionList, ExtensionType},
+    identity::SigningIdentity,
+    protocol_version::ProtocolVersion,
+};
+
+use crate::{
+    client::MlsError,
+    crypto::HpkePublicKey,
+    group::{
+        framing::{
+            Content, ContentType, FramedContent, MlsMessage, MlsMessagePayload, PublicMessage,
+            Sender, WireFormat,
+        },
+        message_signature::FramedContentAuthData,
+        proposal::{AddProposal, Proposal, ProposalOrRef, ProposalType, ReInitProposal},
+        Commit, ConfirmationTag, ConfirmedTranscriptHash, ExportedTree, GroupContext, GroupInfo,
+        MembershipTag, Welcome,
+    },
+    key_package::KeyPackage,
+    tree_kem::{
+        leaf_node::{LeafNode, LeafNodeSource},
+        node::{LeafIndex, Node, NodeVec, Parent},
+        Capabilities, UpdatePath, UpdatePathNode,
+    },
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::{group::proposal::UpdateProposal, mls_rules::ProposalRef};
+
+#[cfg(feature = "private_message")]
+use crate::group::framing::{ApplicationData, PrivateMessage};
+
+/// Limits applied to messages received from other parties before their
+/// contents are validated.
+///
+/// The message size alone does not bound the memory used by decoding, as
+/// small encodings such as blank tree nodes decode into much larger values.
+/// Limits on the number of items in a collection are therefore enforced
+/// while the collection is decoded by [`MlsMessage::from_bytes_with_limits`],
+/// when joining from a ratchet tree extension and when decrypting private
+/// messages, so decoding stops at the first item beyond the limit.
+///
+/// Messages decoded without limits, e.g. with [`MlsMessage::from_bytes`], are
+/// checked against the same limits when they are processed by a group. These
+/// checks happen after decoding and only bound the work done by validation.
+///
+/// A limit that is exceeded results in
+/// [`MlsError::DecodeLimitExceeded`] naming the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecodeLimits {
+    /// Maximum size in bytes of a message passed to
+    /// [`MlsMessage::from_bytes_with_limits`].
+    pub max_message_size: usize,
+    /// Maximum size in bytes of the ciphertext of a private message.
+    pub max_ciphertext_size: usize,
+    /// Maximum number of extensions in any extension list.
+    pub max_extensions: usize,
+    /// Maximum number of proposals in a commit.
+    pub max_proposals: usize,
+    /// Maximum number of leaves in a ratchet tree provided to join a group.
+    pub max_tree_leaves: u32,
+}
+
+/// Limit of [`DecodeLimits`] that was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeLimit {
+    MessageSize,
+    CiphertextSize,
+    Extensions,
+    Proposals,
+    TreeLeaves,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: 1 << 24,
+            max_ciphertext_size: 1 << 24,
+            max_extensions: 1 << 8,
+            max_proposals: 1 << 12,
+            max_tree_leaves: 1 << 16,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the maximum size in bytes of a message.
+    pub fn with_max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size,
+            ..self
+        }
+    }
+
+    /// Set the maximum size in bytes of the ciphertext of a private message.
+    pub fn with_max_ciphertext_size(self, max_ciphertext_size: usize) -> Self {
+        Self {
+            max_ciphertext_size,
+            ..self
+        }
+    }
+
+    /// Set the maximum number of extensions in any extension list.
+    pub fn with_max_extensions(self, max_extensions: usize) -> Self {
+        Self {
+            max_extensions,
+            ..self
+        }
+    }
+
+    /// Set the maximum number of proposals in a commit.
+    pub fn with_max_proposals(self, max_proposals: usize) -> Self {
+        Self {
+            max_proposals,
+            ..self
+        }
+    }
+
+    /// Set the maximum number of leaves in a ratchet tree.
+    pub fn with_max_tree_leaves(self, max_tree_leaves: u32) -> Self {
+        Self {
+            max_tree_leaves,
+            ..self
+        }
+    }
+
+    pub(crate) fn check_message(&self, message: &MlsMessage) -> Result<(), MlsError> {
+        match &message.payload {
+            MlsMessagePayload::Plain(plaintext) => self.check_content(&plaintext.content.content),
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => check(
+                DecodeLimit::CiphertextSize,
+                ciphertext.ciphertext.len(),
+                self.max_ciphertext_size,
+            ),
+            MlsMessagePayload::GroupInfo(group_info) => self.check_group_info(group_info),
+            MlsMessagePayload::KeyPackage(key_package) => self.check_key_package(key_package),
+            MlsMessagePayload::Welcome(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_content(&self, content: &Content) -> Result<(), MlsError> {
+        match content {
+            #[cfg(feature = "private_message")]
+            Content::Application(_) => Ok(()),
+            #[cfg(feature = "by_ref_proposal")]
+            Content::Proposal(proposal) => self.check_proposal(proposal),
+            Content::Commit(commit) => {
+                check(
+                    DecodeLimit::Proposals,
+                    commit.proposals.len(),
+                    self.max_proposals,
+                )?;
+
+                commit
+                    .proposals
+                    .iter()
+                    .try_for_each(|proposal| match proposal {
+                        ProposalOrRef::Proposal(proposal) => self.check_proposal(proposal),
+                        #[cfg(feature = "by_ref_proposal")]
+                        ProposalOrRef::Reference(_) => Ok(()),
+                    })?;
+
+                commit
+                    .path
+                    .iter()
+                    .try_for_each(|path| self.check_extensions(&path.leaf_node.extensions))
+            }
+        }
+    }
+
+    pub(crate) fn check_group_info(&self, group_info: &GroupInfo) -> Result<(), MlsError> {
+        self.check_extensions(&group_info.group_context.extensions)?;
+        self.check_extensions(&group_info.extensions)
+    }
+
+    pub(crate) fn check_tree_leaves(&self, leaves: u32) -> Result<(), MlsError> {
+        check(
+            DecodeLimit::TreeLeaves,
+            leaves as usize,
+            self.max_tree_leaves as usize,
+        )
+    }
+
+    fn check_proposal(&self, proposal: &Proposal) -> Result<(), MlsError> {
+        match proposal {
+            Proposal::Add(add) => self.check_key_package(&add.key_package),
+            #[cfg(feature = "by_ref_proposal")]
+            Proposal::Update(update) => self.check_extensions(&update.leaf_node.extensions),
+            Proposal::ReInit(reinit) => self.check_extensions(&reinit.extensions),
+            Proposal::GroupContextExtensions(extensions) => self.check_extensions(extensions),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_key_package(&self, key_package: &KeyPackage) -> Result<(), MlsError> {
+        self.check_extensions(&key_package.extensions)?;
+        self.check_extensions(&key_package.leaf_node.extensions)
+    }
+
+    fn check_extensions(&self, extensions: &ExtensionList) -> Result<(), MlsError> {
+        check(
+            DecodeLimit::Extensions,
+            extensions.len(),
+            self.max_extensions,
+        )
+    }
+
+    pub(crate) fn decode_message(&self, reader: &mut &[u8]) -> Result<MlsMessage, MlsError> {
+        let version = ProtocolVersion::mls_decode(reader)?;
+
+        let payload = match WireFormat::mls_decode(reader)? {
+            WireFormat::PublicMessage => MlsMessagePayload::Plain(self.decode_public(reader)?),
+            #[cfg(feature = "private_message")]
+            WireFormat::PrivateMessage => {
+                let ciphertext = PrivateMessage::mls_decode(reader)?;
+
+                check(
+                    DecodeLimit::CiphertextSize,
+                    ciphertext.ciphertext.len(),
+                    self.max_ciphertext_size,
+                )?;
+
+                MlsMessagePayload::Cipher(ciphertext)
+            }
+            WireFormat::Welcome => MlsMessagePayload::Welcome(Welcome::mls_decode(reader)?),
+            WireFormat::GroupInfo => MlsMessagePayload::GroupInfo(self.decode_group_info(reader)?),
+            WireFormat::KeyPackage => {
+                MlsMessagePayload::KeyPackage(self.decode_key_package(reader)?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err(mls_rs_codec::Error::UnsupportedEnumDiscriminant.into()),
+        };
+
+        Ok(MlsMessage::new(version, payload))
+    }
+
+    pub(crate) fn decode_content(
+        &self,
+        reader: &mut &[u8],
+        content_type: ContentType,
+    ) -> Result<Content, MlsError> {
+        Ok(match content_type {
+            #[cfg(feature = "private_message")]
+            ContentType::Application => Content::Application(ApplicationData::mls_decode(reader)?),
+            #[cfg(feature = "by_ref_proposal")]
+            ContentType::Proposal => Content::Proposal(Box::new(self.decode_proposal(reader)?)),
+            ContentType::Commit => Content::Commit(Box::new(self.decode_commit(reader)?)),
+        })
+    }
+
+    pub(crate) fn decode_group_info(&self, reader: &mut &[u8]) -> Result<GroupInfo, MlsError> {
+        Ok(GroupInfo {
+            group_context: self.decode_group_context(reader)?,
+            extensions: self.decode_extensions(reader)?,
+            confirmation_tag: ConfirmationTag::mls_decode(reader)?,
+            signer: LeafIndex::mls_decode(reader)?,
+            signature: mls_rs_codec::byte_vec::mls_decode(reader)?,
+        })
+    }
+
+    pub(crate) fn decode_tree(
+        &self,
+        reader: &mut &[u8],
+    ) -> Result<ExportedTree<'static>, MlsError> {
+        let nodes = decode_bounded(
+            reader,
+            // Leaf count of a tree with `nodes` nodes, see `NodeVec::total_leaf_count`
+            |nodes| self.check_tree_leaves((nodes as u32 / 2 + 1).next_power_of_two()),
+            |reader| match u8::mls_decode(reader)? {
+                0 => Ok(None),
+                1 => self.decode_node(reader).map(Some),
+                n => Err(mls_rs_codec::Error::OptionOutOfRange(n).into()),
+            },
+        )?;
+
+        Ok(ExportedTree::new(NodeVec::from(nodes)))
+    }
+
+    fn decode_node(&self, reader: &mut &[u8]) -> Result<Node, MlsError> {
+        match u8::mls_decode(reader)? {
+            1 => self.decode_leaf_node(reader).map(Node::Leaf),
+            2 => Ok(Node::Parent(Parent::mls_decode(reader)?)),
+            _ => Err(mls_rs_codec::Error::UnsupportedEnumDiscriminant.into()),
+        }
+    }
+
+    fn decode_public(&self, reader: &mut &[u8]) -> Result<PublicMessage, MlsError> {
+        let content = FramedContent {
+            group_id: mls_rs_codec::byte_vec::mls_decode(reader)?,
+            epoch: u64::mls_decode(reader)?,
+            sender: Sender::mls_decode(reader)?,
+            authenticated_data: mls_rs_codec::byte_vec::mls_decode(reader)?,
+            content: {
+                let content_type = ContentType::mls_decode(reader)?;
+                self.decode_content(reader, content_type)?
+            },
+        };
+
+        let auth = FramedContentAuthData::mls_decode(reader, content.content_type())?;
+
+        let membership_tag = match content.sender {
+            Sender::Member(_) => Some(MembershipTag::mls_decode(reader)?),
+            _ => None,
+        };
+
+        Ok(PublicMessage {
+            content,
+            auth,
+            membership_tag,
+        })
+    }
+
+    fn decode_commit(&self, reader: &mut &[u8]) -> Result<Commit, MlsError> {
+        let proposals = decode_bounded(
+            reader,
+            |proposals| check(DecodeLimit::Proposals, proposals, self.max_proposals),
+            |reader| match u8::mls_decode(reader)? {
+                1 => Ok(ProposalOrRef::Proposal(Box::new(
+                    self.decode_proposal(reader)?,
+                ))),
+                #[cfg(feature = "by_ref_proposal")]
+                2 => Ok(ProposalOrRef::Reference(ProposalRef::mls_decode(reader)?)),
+                _ => Err(mls_rs_codec::Error::UnsupportedEnumDiscriminant.into()),
+            },
+        )?;
+
+        let path = match u8::mls_decode(reader)? {
+            0 => None,
+            1 => Some(UpdatePath {
+                leaf_node: self.decode_leaf_node(reader)?,
+                nodes: Vec::<UpdatePathNode>::mls_decode(reader)?,
+            }),
+            n => return Err(mls_rs_codec::Error::OptionOutOfRange(n).into()),
+        };
+
+        Ok(Commit { proposals, path })
+    }
+
+    fn decode_proposal(&self, reader: &mut &[u8]) -> Result<Proposal, MlsError> {
+        let mut body = *reader;
+
+        let proposal = match ProposalType::mls_decode(&mut body)? {
+            t if t == ProposalType::ADD => Proposal::Add(Box::new(AddProposal {
+                key_package: self.decode_key_package(&mut body)?,
+            })),
+            #[cfg(feature = "by_ref_proposal")]
+            t if t == ProposalType::UPDATE => Proposal::Update(UpdateProposal {
+                leaf_node: self.decode_leaf_node(&mut body)?,
+            }),
+            t if t == ProposalType::RE_INIT => Proposal::ReInit(ReInitProposal {
+                group_id: mls_rs_codec::byte_vec::mls_decode(&mut body)?,
+                version: ProtocolVersion::mls_decode(&mut body)?,
+                cipher_suite: CipherSuite::mls_decode(&mut body)?,
+                extensions: self.decode_extensions(&mut body)?,
+            }),
+            t if t == ProposalType::GROUP_CONTEXT_EXTENSIONS => {
+                Proposal::GroupContextExtensions(self.decode_extensions(&mut body)?)
+            }
+            // Other proposals contain no collection with a limit
+            _ => return Ok(Proposal::mls_decode(reader)?),
+        };
+
+        *reader = body;
+
+        Ok(proposal)
+    }
+
+    fn decode_key_package(&self, reader: &mut &[u8]) -> Result<KeyPackage, MlsError> {
+        Ok(KeyPackage {
+            version: ProtocolVersion::mls_decode(reader)?,
+            cipher_suite: CipherSuite::mls_decode(reader)?,
+            hpke_init_key: HpkePublicKey::mls_decode(reader)?,
+            leaf_node: self.decode_leaf_node(reader)?,
+            extensions: self.decode_extensions(reader)?,
+            signature: mls_rs_codec::byte_vec::mls_decode(reader)?,
+        })
+    }
+
+    fn decode_leaf_node(&self, reader: &mut &[u8]) -> Result<LeafNode, MlsError> {
+        Ok(LeafNode {
+            public_key: HpkePublicKey::mls_decode(reader)?,
+            signing_identity: SigningIdentity::mls_decode(reader)?,
+            capabilities: Capabilities::mls_decode(reader)?,
+            leaf_node_source: LeafNodeSource::mls_decode(reader)?,
+            extensions: self.decode_extensions(reader)?,
+            signature: mls_rs_codec::byte_vec::mls_decode(reader)?,
+        })
+    }
+
+    fn decode_group_context(&self, reader: &mut &[u8]) -> Result<GroupContext, MlsError> {
+        Ok(GroupContext {
+            protocol_version: ProtocolVersion::mls_decode(reader)?,
+            cipher_suite: CipherSuite::mls_decode(reader)?,
+            group_id: mls_rs_codec::byte_vec::mls_decode(reader)?,
+            epoch: u64::mls_decode(reader)?,
+            tree_hash: mls_rs_codec::byte_vec::mls_decode(reader)?,
+            confirmed_transcript_hash: ConfirmedTranscriptHash::mls_decode(reader)?,
+            extensions: self.decode_extensions(reader)?,
+        })
+    }
+
+    fn decode_extensions(&self, reader: &mut &[u8]) -> Result<ExtensionList, MlsError> {
+        // Count the extensions by skipping over their data, so that the list is
+        // only decoded if it is within the limit.
+        let (mut data, _) = mls_decode_split_on_collection(&mut &**reader)?;
+        let mut extensions = 0;
+
+        while !data.is_empty() {
+            extensions += 1;
+            check(DecodeLimit::Extensions, extensions, self.max_extensions)?;
+            ExtensionType::mls_decode(&mut data)?;
+            data = mls_decode_split_on_collection(&mut data)?.1;
+        }
+
+        Ok(ExtensionList::mls_decode(reader)?)
+    }
+}
+
+fn check(limit: DecodeLimit, value: usize, max: usize) -> Result<(), MlsError> {
+    (value <= max)
+        .then_some(())
+        .ok_or(MlsError::DecodeLimitExceeded(limit))
+}
+
+/// Decode a vector item by item, calling `check_len` with the number of items
+/// before decoding each of them.
+fn decode_bounded<T>(
+    reader: &mut &[u8],
+    check_len: impl Fn(usize) -> Result<(), MlsError>,
+    mut decode_item: impl FnMut(&mut &[u8]) -> Result<T, MlsError>,
+) -> Result<Vec<T>, MlsError> {
+    let (mut data, rest) = mls_decode_split_on_collection(reader)?;
+    let mut items = Vec::new();
+
+    while !data.is_empty() {
+        check_len(items.len() + 1)?;
+        items.push(decode_item(&mut data)?);
+    }
+
+    *reader = rest;
+
+    Ok(items)
+}
+
+impl MlsMessage {
+    /// Deserialize a message from transport, rejecting it if it exceeds
+    /// `limits`.
+    ///
+    /// The size of `bytes` is checked before decoding and all other limits are
+    /// enforced while decoding. Limits on the contents of private messages are
+    /// enforced when they are decrypted by
+    /// [`Group::process_incoming_message`](crate::Group::process_incoming_message).
+    #[inline(never)]
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, MlsError> {
+        check(
+            DecodeLimit::MessageSize,
+            bytes.len(),
+            limits.max_message_size,
+        )?;
+
+        limits.decode_message(&mut &*bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mls_rs_codec::MlsEncode;
+
+    use crate::{
+        client::test_utils::{
+            test_client_with_key_pkg_custom, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION,
+        },
+        client::MlsError,
+        group::test_utils::test_group,
+        key_package::test_utils::test_key_package_message,
+    };
+
+    use super::{DecodeLimit, DecodeLimits, MlsMessage};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn message_larger_than_limit_is_rejected_before_decoding() {
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let bytes = key_package.to_bytes().unwrap();
+        let limits = DecodeLimits::new().with_max_message_size(bytes.len());
+
+        assert!(MlsMessage::from_bytes_with_limits(&bytes, &limits).is_ok());
+
+        let limits = limits.with_max_message_size(bytes.len() - 1);
+        let res = MlsMessage::from_bytes_with_limits(&bytes, &limits);
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::MessageSize))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_with_too_many_proposals_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let mut builder = alice.group.commit_builder();
+
+        for name in ["carol", "dave"] {
+            let key_package =
+                test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, name).await;
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        let commit = builder.build().await.unwrap().commit_message;
+        let bytes = commit.to_bytes().unwrap();
+
+        let limits = DecodeLimits::new().with_max_proposals(1);
+        let res = MlsMessage::from_bytes_with_limits(&bytes, &limits);
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::Proposals))
+        );
+
+        bob.group.config.0.settings.decode_limits = limits;
+        let res = bob.group.process_incoming_message(commit).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::Proposals))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn proposals_beyond_limit_are_not_decoded() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let mut builder = alice.group.commit_builder();
+        let mut last_key_package = Vec::new();
+
+        for name in ["bob", "carol"] {
+            let key_package =
+                test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, name).await;
+
+            last_key_package = key_package
+                .clone()
+                .into_key_package()
+                .unwrap()
+                .mls_encode_to_vec()
+                .unwrap();
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        let commit = builder.build().await.unwrap().commit_message;
+        let mut bytes = commit.to_bytes().unwrap();
+
+        // Corrupt the tag of the second proposal, which precedes its proposal
+        // type and key package
+        let offset = bytes
+            .windows(last_key_package.len())
+            .position(|w| w == last_key_package)
+            .unwrap();
+
+        bytes[offset - 3] = 0xff;
+
+        let res = MlsMessage::from_bytes_with_limits(&bytes, &DecodeLimits::new());
+        assert_matches!(res, Err(MlsError::SerializationError(_)));
+
+        let limits = DecodeLimits::new().with_max_proposals(1);
+        let res = MlsMessage::from_bytes_with_limits(&bytes, &limits);
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::Proposals))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_info_with_too_many_extensions_is_rejected() {
+        let alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        // Contains the external public key and ratchet tree extensions
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let bytes = group_info.to_bytes().unwrap();
+
+        let limits = DecodeLimits::new().with_max_extensions(1);
+        let res = MlsMessage::from_bytes_with_limits(&bytes, &limits);
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::Extensions))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn group_info_with_extensions_at_limit_round_trips() {
+        let alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let extensions = group_info.as_group_info().unwrap().extensions.len();
+        assert!(extensions >= 2);
+
+        let bytes = group_info.to_bytes().unwrap();
+        let limits = DecodeLimits::new().with_max_extensions(extensions);
+        let decoded = MlsMessage::from_bytes_with_limits(&bytes, &limits).unwrap();
+
+        assert_eq!(decoded, group_info);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn joining_with_too_many_tree_leaves_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        alice.join("bob").await;
+
+        let (carol, key_package) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "carol",
+            |c| c.0.settings.decode_limits = DecodeLimits::new().with_max_tree_leaves(2),
+        )
+        .await;
+
+        let commit_output = alice
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let res = carol
+            .join_group(
+                Some(alice.group.export_tree()),
+                &commit_output.welcome_messages[0],
+            )
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::TreeLeaves))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tree_extension_with_too_many_leaves_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        alice.join("bob").await;
+
+        let (carol, key_package) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "carol",
+            |c| c.0.settings.decode_limits = DecodeLimits::new().with_max_tree_leaves(2),
+        )
+        .await;
+
+        let commit_output = alice
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .ratchet_tree_extension(true)
+            .build()
+            .await
+            .unwrap();
+
+        let res = carol
+            .join_group(None, &commit_output.welcome_messages[0])
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::DecodeLimitExceeded(DecodeLimit::TreeLeaves))
+        );
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    group::{
+        framing::{Content, MlsMessage, Sender},
+        message_signature::{AuthenticatedContent, MessageSigningContext},
+        proposal::Proposal,
+        proposal_ref::ProposalRef,
+    },
+    signer::Signable,
+    Group,
+};
+
+#[cfg(not(feature = "private_message"))]
+use crate::group::framing::WireFormat;
+
+/// Proposal created by [`Group::unsigned_proposal`] that still needs the
+/// signature of the proposer.
+///
+/// The signature can be produced outside of the group, for example by an
+/// approver holding the signature key of the member on an air-gapped device,
+/// by signing [`signature_input`](Self::signature_input) with the signature
+/// algorithm of the cipher suite. The proposal is completed with
+/// [`Group::attach_proposal_signature`] in the same epoch it was created in.
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct UnsignedProposal {
+    content: AuthenticatedContent,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    signature_input: Vec<u8>,
+}
+
+impl UnsignedProposal {
+    /// Bytes to be signed with the signature key of the proposer. These are
+    /// the bytes passed to the signature algorithm by `SignWithLabel`, so no
+    /// further encoding or hashing is needed before signing.
+    pub fn signature_input(&self) -> &[u8] {
+        &self.signature_input
+    }
+
+    /// Proposal waiting for a signature.
+    pub fn proposal(&self) -> Option<&Proposal> {
+        match &self.content.content.content {
+            Content::Proposal(proposal) => Some(proposal),
+            _ => None,
+        }
+    }
+
+    /// Epoch in which the proposal was created.
+    pub fn epoch(&self) -> u64 {
+        self.content.content.epoch
+    }
+
+    /// Deserialize an unsigned proposal.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize an unsigned proposal, for example to store it until the
+    /// signature is available.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a proposal sent by the current member without signing it.
+    ///
+    /// This is meant for workflows where the signature key of the member is
+    /// not available to the group, see [`UnsignedProposal`].
+    pub fn unsigned_proposal(
+        &self,
+        proposal: Proposal,
+        authenticated_data: Vec<u8>,
+    ) -> Result<UnsignedProposal, MlsError> {
+        let proposal_type = proposal.proposal_type();
+
+        if !self.config.by_reference_allowed(proposal_type) {
+            return Err(MlsError::ProposalTypeRequiresByValue(proposal_type));
+        }
+
+        let sender = Sender::Member(*self.private_tree.self_index);
+
+        #[cfg(feature = "private_message")]
+        let wire_format = self.encryption_options()?.control_wire_format(sender);
+
+        #[cfg(not(feature = "private_message"))]
+        let wire_format = WireFormat::PublicMessage;
+
+        let content = AuthenticatedContent::new(
+            self.context(),
+            sender,
+            Content::Proposal(Box::new(proposal)),
+            authenticated_data,
+            wire_format,
+        );
+
+        let signature_input = content.signature_input(&self.signing_context())?;
+
+        Ok(UnsignedProposal {
+            content,
+            signature_input,
+        })
+    }
+
+    /// Complete `unsigned` with a `signature` of its
+    /// [`signature_input`](UnsignedProposal::signature_input) and create the
+    /// proposal message to send to the group.
+    ///
+    /// The signature is verified with the signature key of the current
+    /// member. The proposal is cached like proposals created with the
+    /// `propose_*` functions and committed by the next commit of this member.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn attach_proposal_signature(
+        &mut self,
+        unsigned: UnsignedProposal,
+        signature: Vec<u8>,
+    ) -> Result<MlsMessage, MlsError> {
+        let mut auth_content = unsigned.content;
+
+        (auth_content.content.group_id == self.context().group_id)
+            .then_some(())
+            .ok_or(MlsError::GroupIdMismatch)?;
+
+        (auth_content.content.epoch == self.context().epoch)
+            .then_some(())
+            .ok_or(MlsError::InvalidEpoch)?;
+
+        (auth_content.content.sender == Sender::Member(*self.private_tree.self_index))
+            .then_some(())
+            .ok_or(MlsError::InvalidSender)?;
+
+        let proposal = match &auth_content.content.content {
+            Content::Proposal(proposal) => proposal.as_ref().clone(),
+            _ => return Err(MlsError::UnexpectedMessageType),
+        };
+
+        auth_content.write_signature(signature);
+
+        let signature_key = &self.current_member_signing_identity()?.signature_key;
+
+        auth_content
+            .verify(
+                &self.cipher_suite_provider,
+                signature_key,
+                &self.signing_context(),
+            )
+            .await?;
+
+        let proposal_ref =
+            ProposalRef::from_content(&self.cipher_suite_provider, &auth_content).await?;
+
+        self.state
+            .proposals
+            .insert(proposal_ref, proposal, auth_content.content.sender);
+
+        self.format_for_wire(auth_content).await
+    }
+
+    fn signing_context(&self) -> MessageSigningContext<'_> {
+        MessageSigningContext {
+            group_context: Some(self.context()),
+            protocol_version: self.protocol_version(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        crypto::test_utils::test_cipher_suite_provider,
+        group::{
+            proposal::{AddProposal, Proposal},
+            test_utils::{test_group, TestGroup},
+            ReceivedMessage,
+        },
+        CipherSuiteProvider,
+    };
+
+    use super::UnsignedProposal;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn add_carol(group: &TestGroup) -> UnsignedProposal {
+        let (_, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let key_package = key_package.into_key_package().unwrap();
+        let proposal = Proposal::Add(alloc::boxed::Box::new(AddProposal { key_package }));
+
+        group.group.unsigned_proposal(proposal, vec![]).unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn proposal_signed_elsewhere_can_be_committed() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let unsigned = add_carol(&alice).await;
+        let unsigned = UnsignedProposal::from_bytes(&unsigned.to_bytes().unwrap()).unwrap();
+
+        // Signed by the approver holding the signature key of alice
+        let signature = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .sign(&alice.group.signer, unsigned.signature_input())
+            .await
+            .unwrap();
+
+        let message = alice
+            .group
+            .attach_proposal_signature(unsigned, signature)
+            .await
+            .unwrap();
+
+        let received = bob.group.process_incoming_message(message).await.unwrap();
+        assert_matches!(received, ReceivedMessage::Proposal(_));
+
+        let commit = alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.process_message(commit.commit_message).await.unwrap();
+
+        assert_eq!(alice.group.roster().members().len(), 3);
+        assert_eq!(bob.group.roster().members().len(), 3);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn invalid_or_outdated_signature_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let unsigned = add_carol(&alice).await;
+        let (other_key, _) = cs.signature_key_generate().await.unwrap();
+        let signature = cs
+            .sign(&other_key, unsigned.signature_input())
+            .await
+            .unwrap();
+
+        let res = alice
+            .group
+            .attach_proposal_signature(unsigned, signature)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidSignature));
+
+        let unsigned = add_carol(&alice).await;
+        let signature = cs
+            .sign(&alice.group.signer, unsigned.signature_input())
+            .await
+            .unwrap();
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let res = alice
+            .group
+            .attach_proposal_signature(unsigned, signature)
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsEncode, MlsSize};
+use mls_rs_core::secret::Secret;
+use zeroize::Zeroizing;
+
+use crate::{client::MlsError, client_config::ClientConfig, CipherSuiteProvider, Group};
+
+use super::key_schedule::kdf_expand_with_label;
+
+const EPOCH_RANDOMNESS_EXPORTER_LABEL: &[u8] = b"mls-rs epoch randomness";
+const RANDOMNESS_LABEL: &[u8] = b"randomness";
+const RANDOM_BELOW_LABEL: &[u8] = b"random below";
+
+/// Randomness of an epoch exported from the key schedule. It is computed once
+/// per epoch and then reused for every contribution.
+#[derive(Clone)]
+pub(crate) struct EpochRandomness {
+    epoch: u64,
+    seed: Zeroizing<Vec<u8>>,
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct RandomBelowContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    contribution: &'a [u8],
+    bound: u64,
+    counter: u32,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Derive `len` bytes of randomness shared by all members in the current
+    /// epoch, bound to `contribution`.
+    ///
+    /// All members computing the randomness with the same `contribution` in
+    /// the same epoch get the same value, which is unpredictable to anyone
+    /// outside the group. Applications performing leader elections or
+    /// verifiable shuffles should use a `contribution` that commits to
+    /// values revealed by the participants, e.g. the concatenation of their
+    /// revealed nonces, so that no single member controls the result.
+    ///
+    /// The randomness is derived from the exporter secret of the epoch with a
+    /// dedicated label, so it is independent from any value computed with
+    /// [`Group::export_secret`] using another label.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn epoch_randomness(
+        &mut self,
+        contribution: &[u8],
+        len: usize,
+    ) -> Result<Secret, MlsError> {
+        let seed = self.epoch_randomness_seed().await?;
+
+        kdf_expand_with_label(
+            &self.cipher_suite_provider,
+            &seed,
+            RANDOMNESS_LABEL,
+            contribution,
+            Some(len),
+        )
+        .await
+        .map(Into::into)
+    }
+
+    /// Derive an integer uniformly distributed in `0..bound` from the
+    /// randomness of the current epoch, bound to `contribution`. See
+    /// [`Group::epoch_randomness`].
+    ///
+    /// The integer is unbiased, candidates that would favor small values are
+    /// rejected and replaced by further derivations. Returns
+    /// [`MlsError::InvalidRandomBound`] if `bound` is zero.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn epoch_random_below(
+        &mut self,
+        contribution: &[u8],
+        bound: u64,
+    ) -> Result<u64, MlsError> {
+        if bound == 0 {
+            return Err(MlsError::InvalidRandomBound);
+        }
+
+        let seed = self.epoch_randomness_seed().await?;
+
+        // Values below 2^64 mod bound would make the smallest results more
+        // likely than the others.
+        let threshold = bound.wrapping_neg() % bound;
+
+        for counter in 0..=u32::MAX {
+            let context = RandomBelowContext {
+                contribution,
+                bound,
+                counter,
+            }
+            .mls_encode_to_vec()?;
+
+            let candidate = kdf_expand_with_label(
+                &self.cipher_suite_provider,
+                &seed,
+                RANDOM_BELOW_LABEL,
+                &context,
+                Some(8),
+            )
+            .await?;
+
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&candidate);
+            let candidate = u64::from_be_bytes(bytes);
+
+            if candidate >= threshold {
+                return Ok(candidate % bound);
+            }
+        }
+
+        Err(MlsError::InvalidRandomBound)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn epoch_randomness_seed(&mut self) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+        let epoch = self.context().epoch;
+
+        if let Some(cached) = self.epoch_randomness.as_ref().filter(|r| r.epoch == epoch) {
+            return Ok(cached.seed.clone());
+        }
+
+        let seed = self
+            .key_schedule
+            .export_secret(
+                EPOCH_RANDOMNESS_EXPORTER_LABEL,
+                &[],
+                self.cipher_suite_provider.kdf_extract_size(),
+                &self.cipher_suite_provider,
+            )
+            .await?;
+
+        self.epoch_randomness = Some(EpochRandomness {
+            epoch,
+            seed: seed.clone(),
+        });
+
+        Ok(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn epoch_randomness_is_shared_and_changes_with_epoch() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let alice_random = alice.group.epoch_randomness(b"round 1", 32).await.unwrap();
+        let bob_random = bob.group.epoch_randomness(b"round 1", 32).await.unwrap();
+        assert_eq!(alice_random, bob_random);
+
+        let other_round = alice.group.epoch_randomness(b"round 2", 32).await.unwrap();
+        assert_ne!(alice_random, other_round);
+
+        let alice_leader = alice.group.epoch_random_below(b"leader", 2).await.unwrap();
+        let bob_leader = bob.group.epoch_random_below(b"leader", 2).await.unwrap();
+        assert_eq!(alice_leader, bob_leader);
+        assert!(alice_leader < 2);
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.process_message(commit).await.unwrap();
+
+        let alice_next = alice.group.epoch_randomness(b"round 1", 32).await.unwrap();
+        let bob_next = bob.group.epoch_randomness(b"round 1", 32).await.unwrap();
+        assert_eq!(alice_next, bob_next);
+        assert_ne!(alice_next, alice_random);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn epoch_random_below_stays_in_range() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        for bound in [1, 3, 7, u64::MAX / 2 + 2, u64::MAX] {
+            for i in 0u8..8 {
+                let value = alice.group.epoch_random_below(&[i], bound).await.unwrap();
+                assert!(value < bound);
+            }
+        }
+
+        let res = alice.group.epoch_random_below(b"", 0).await;
+        assert_matches!(res, Err(MlsError::InvalidRandomBound));
+    }
+}
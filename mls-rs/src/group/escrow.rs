@@ -0,0 +1,807 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, Debug};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::{CipherSuiteProvider, HpkeCiphertext, HpkePublicKey, HpkeSecretKey},
+    error::IntoAnyError,
+    extension::ExtensionList,
+    secret::Secret,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    client::MlsError,
+    extension::{ExporterEscrowExt, MessageEscrowExt},
+    tree_kem::hpke_encryption::{decrypt_with_label, encrypt_with_label},
+};
+
+use super::{
+    key_schedule::export_from_exporter_secret,
+    secret_tree::{MessageKeyData, SecretKeyRatchet},
+    GroupContext,
+};
+
+const ESCROW_LABEL: &[u8] = b"ExporterEscrow";
+
+const RECEIVE_KEYS_LABEL: &[u8] = b"ReceiveKeyEscrow";
+
+#[derive(MlsSize, MlsEncode)]
+struct EscrowContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+}
+
+#[derive(MlsSize, MlsEncode)]
+struct ShareContext<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+    threshold: u8,
+    share_index: u8,
+}
+
+/// Share of the exporter secret of an epoch encrypted to an escrow key listed
+/// in the [`ExporterEscrowExt`] group context extension.
+///
+/// The exporter secret is split with Shamir's secret sharing so that any
+/// [`threshold`](Self::threshold) of the escrow key holders can recover it by
+/// opening their shares with [`EscrowedExporterSecret::open`] and combining
+/// them with [`RecoveredExporterSecret::combine`]. Fewer shares reveal nothing
+/// about the secret. The recovered secret computes any value that group
+/// members compute with [`Group::export_secret`](crate::Group::export_secret)
+/// in that epoch. No other epoch secret is escrowed.
+///
+/// Shares are produced by the committer alone and are not part of the commit.
+/// Other members can not verify that the escrow was made or that the shares
+/// are correct, and escrow key holders can not verify that the recovered
+/// secret is the exporter secret of the epoch.
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EscrowedExporterSecret {
+    /// Escrow key the share is encrypted to.
+    pub escrow_key: HpkePublicKey,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub group_id: Vec<u8>,
+    pub epoch: u64,
+    /// Number of shares needed to recover the exporter secret.
+    pub threshold: u8,
+    /// Index of the share, starting at 1.
+    pub share_index: u8,
+    pub ciphertext: HpkeCiphertext,
+}
+
+impl EscrowedExporterSecret {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn seal_all<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        context: &GroupContext,
+        exporter_secret: &[u8],
+    ) -> Result<Vec<Self>, MlsError> {
+        let Some(ext) = context.extensions.get_as::<ExporterEscrowExt>()? else {
+            return Ok(Vec::new());
+        };
+
+        let share_count = validate_threshold(&ext)?;
+
+        let shares = split_secret(
+            cipher_suite_provider,
+            exporter_secret,
+            ext.threshold,
+            share_count,
+        )?;
+
+        let mut escrowed = Vec::with_capacity(ext.escrow_keys.len());
+
+        for ((escrow_key, share), share_index) in
+            ext.escrow_keys.into_iter().zip(shares).zip(1..=share_count)
+        {
+            let share_context = ShareContext {
+                group_id: &context.group_id,
+                epoch: context.epoch,
+                threshold: ext.threshold,
+                share_index,
+            }
+            .mls_encode_to_vec()?;
+
+            let ciphertext = encrypt_with_label(
+                cipher_suite_provider,
+                &escrow_key,
+                ESCROW_LABEL,
+                &share_context,
+                &share,
+            )
+            .await?;
+
+            escrowed.push(Self {
+                escrow_key,
+                group_id: context.group_id.clone(),
+                epoch: context.epoch,
+                threshold: ext.threshold,
+                share_index,
+                ciphertext,
+            });
+        }
+
+        Ok(escrowed)
+    }
+
+    /// Decrypt the share using the escrow secret key matching
+    /// [`escrow_key`](Self::escrow_key).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn open<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        escrow_secret_key: &HpkeSecretKey,
+    ) -> Result<ExporterSecretShare, MlsError> {
+        let share_context = ShareContext {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+            threshold: self.threshold,
+            share_index: self.share_index,
+        }
+        .mls_encode_to_vec()?;
+
+        let share = decrypt_with_label(
+            cipher_suite_provider,
+            escrow_secret_key,
+            &self.escrow_key,
+            ESCROW_LABEL,
+            &share_context,
+            &self.ciphertext,
+        )
+        .await?;
+
+        Ok(ExporterSecretShare {
+            group_id: self.group_id.clone(),
+            epoch: self.epoch,
+            threshold: self.threshold,
+            share_index: self.share_index,
+            share,
+        })
+    }
+}
+
+/// Share of an exporter secret decrypted from an [`EscrowedExporterSecret`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExporterSecretShare {
+    group_id: Vec<u8>,
+    epoch: u64,
+    threshold: u8,
+    share_index: u8,
+    share: Zeroizing<Vec<u8>>,
+}
+
+impl Debug for ExporterSecretShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExporterSecretShare")
+            .field(
+                "group_id",
+                &mls_rs_core::debug::pretty_group_id(&self.group_id),
+            )
+            .field("epoch", &self.epoch)
+            .field("threshold", &self.threshold)
+            .field("share_index", &self.share_index)
+            .finish()
+    }
+}
+
+impl ExporterSecretShare {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn share_index(&self) -> u8 {
+        self.share_index
+    }
+}
+
+/// Exporter secret recovered from [`ExporterSecretShare`]s.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveredExporterSecret {
+    secret: Zeroizing<Vec<u8>>,
+}
+
+impl Debug for RecoveredExporterSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        mls_rs_core::debug::pretty_bytes(&self.secret)
+            .named("RecoveredExporterSecret")
+            .fmt(f)
+    }
+}
+
+impl RecoveredExporterSecret {
+    /// Recover the exporter secret from at least
+    /// [`threshold`](ExporterSecretShare::threshold) distinct shares of the
+    /// same epoch.
+    pub fn combine(shares: &[ExporterSecretShare]) -> Result<Self, MlsError> {
+        let first = shares
+            .first()
+            .ok_or(MlsError::NotEnoughEscrowShares(0, 1))?;
+
+        let consistent = shares.iter().enumerate().all(|(i, share)| {
+            share.group_id == first.group_id
+                && share.epoch == first.epoch
+                && share.threshold == first.threshold
+                && share.share.len() == first.share.len()
+                && shares[..i]
+                    .iter()
+                    .all(|other| other.share_index != share.share_index)
+        });
+
+        if !consistent {
+            return Err(MlsError::InconsistentEscrowShares);
+        }
+
+        if shares.len() < first.threshold as usize {
+            return Err(MlsError::NotEnoughEscrowShares(
+                shares.len(),
+                first.threshold,
+            ));
+        }
+
+        Ok(Self {
+            secret: combine_shares(&shares[..first.threshold as usize]),
+        })
+    }
+
+    /// Compute the same value as [`Group::export_secret`](crate::Group::export_secret)
+    /// for the escrowed epoch.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_secret<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        label: &[u8],
+        context: &[u8],
+        len: usize,
+    ) -> Result<Secret, MlsError> {
+        export_from_exporter_secret(cipher_suite_provider, &self.secret, label, context, len)
+            .await
+            .map(Into::into)
+    }
+}
+
+/// Check that the [`ExporterEscrowExt`] in `extensions`, if any, has a
+/// threshold that its escrow keys can reach.
+pub(crate) fn validate_exporter_escrow(extensions: &ExtensionList) -> Result<(), MlsError> {
+    extensions
+        .get_as::<ExporterEscrowExt>()?
+        .map_or(Ok(()), |ext| validate_threshold(&ext).map(|_| ()))
+}
+
+fn validate_threshold(ext: &ExporterEscrowExt) -> Result<u8, MlsError> {
+    u8::try_from(ext.escrow_keys.len())
+        .ok()
+        .filter(|&share_count| (1..=share_count).contains(&ext.threshold))
+        .ok_or(MlsError::InvalidEscrowThreshold(
+            ext.threshold,
+            ext.escrow_keys.len(),
+        ))
+}
+
+/// Split `secret` into `share_count` shares, any `threshold` of which recover
+/// it. Share `x` is the evaluation at `x` of a random polynomial of degree
+/// `threshold - 1` over GF(2^8) whose constant term is the secret, computed
+/// independently for every byte.
+fn split_secret<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    secret: &[u8],
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<Zeroizing<Vec<u8>>>, MlsError> {
+    let degree = threshold as usize - 1;
+
+    let coefficients = Zeroizing::new(
+        cipher_suite_provider
+            .random_bytes_vec(secret.len() * degree)
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?,
+    );
+
+    let shares = (1..=share_count)
+        .map(|x| {
+            let share = secret
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| {
+                    let higher_terms = coefficients[i * degree..(i + 1) * degree]
+                        .iter()
+                        .rev()
+                        .fold(0, |acc, &c| gf_mul(acc, x) ^ c);
+
+                    gf_mul(higher_terms, x) ^ byte
+                })
+                .collect();
+
+            Zeroizing::new(share)
+        })
+        .collect();
+
+    Ok(shares)
+}
+
+/// Lagrange interpolation at 0 of shares with distinct indices.
+fn combine_shares(shares: &[ExporterSecretShare]) -> Zeroizing<Vec<u8>> {
+    let mut secret = Zeroizing::new(vec![0; shares[0].share.len()]);
+
+    for (i, share) in shares.iter().enumerate() {
+        let x_i = share.share_index;
+
+        let mut basis = 1;
+
+        for (_, other) in shares.iter().enumerate().filter(|&(j, _)| j != i) {
+            let x_j = other.share_index;
+            basis = gf_mul(basis, gf_mul(x_j, gf_inv(x_j ^ x_i)));
+        }
+
+        secret
+            .iter_mut()
+            .zip(share.share.iter())
+            .for_each(|(s, &y)| *s ^= gf_mul(basis, y));
+    }
+
+    secret
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without branches
+/// on the operands.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1b & (a >> 7).wrapping_neg());
+        b >>= 1;
+    }
+
+    product
+}
+
+/// Inverse in GF(2^8), computed as `a^254`.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exp = 254u8;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+
+    result
+}
+
+/// Escrow key of the [`MessageEscrowExt`] in `context`.
+pub(crate) fn message_escrow_key(context: &GroupContext) -> Result<HpkePublicKey, MlsError> {
+    context
+        .extensions
+        .get_as::<MessageEscrowExt>()?
+        .map(|ext| ext.escrow_key)
+        .ok_or(MlsError::MessageEscrowNotEnabled)
+}
+
+#[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub(crate) struct EscrowedRatchet {
+    pub(crate) leaf_index: u32,
+    pub(crate) generation: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub(crate) secret: Zeroizing<Vec<u8>>,
+}
+
+/// Application message receive keys of an epoch encrypted to the escrow key
+/// of the [`MessageEscrowExt`] group context extension.
+///
+/// For every other member of the group, the escrow holds the application
+/// ratchet of that member starting at the generation it had reached when
+/// [`Group::escrow_receive_keys`](crate::Group::escrow_receive_keys) was
+/// called. The holder of the escrow secret key can derive the keys of the
+/// application messages sent by those members during the rest of the epoch,
+/// but not of earlier messages, of handshake messages or of any other epoch.
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EscrowedReceiveKeys {
+    /// Escrow key the receive keys are encrypted to.
+    pub escrow_key: HpkePublicKey,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub group_id: Vec<u8>,
+    pub epoch: u64,
+    pub ciphertext: HpkeCiphertext,
+}
+
+impl EscrowedReceiveKeys {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn seal<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        context: &GroupContext,
+        escrow_key: HpkePublicKey,
+        ratchets: Vec<EscrowedRatchet>,
+    ) -> Result<Self, MlsError> {
+        let escrow_context = EscrowContext {
+            group_id: &context.group_id,
+            epoch: context.epoch,
+        }
+        .mls_encode_to_vec()?;
+
+        let ratchets = Zeroizing::new(ratchets.mls_encode_to_vec()?);
+
+        let ciphertext = encrypt_with_label(
+            cipher_suite_provider,
+            &escrow_key,
+            RECEIVE_KEYS_LABEL,
+            &escrow_context,
+            &ratchets,
+        )
+        .await?;
+
+        Ok(Self {
+            escrow_key,
+            group_id: context.group_id.clone(),
+            epoch: context.epoch,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt the receive keys using the escrow secret key matching
+    /// [`escrow_key`](Self::escrow_key).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn open<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        escrow_secret_key: &HpkeSecretKey,
+    ) -> Result<RecoveredReceiveKeys, MlsError> {
+        let escrow_context = EscrowContext {
+            group_id: &self.group_id,
+            epoch: self.epoch,
+        }
+        .mls_encode_to_vec()?;
+
+        let ratchets = decrypt_with_label(
+            cipher_suite_provider,
+            escrow_secret_key,
+            &self.escrow_key,
+            RECEIVE_KEYS_LABEL,
+            &escrow_context,
+            &self.ciphertext,
+        )
+        .await?;
+
+        Ok(RecoveredReceiveKeys {
+            ratchets: Vec::mls_decode(&mut &**ratchets)?,
+        })
+    }
+}
+
+/// Receive keys recovered from an [`EscrowedReceiveKeys`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveredReceiveKeys {
+    ratchets: Vec<EscrowedRatchet>,
+}
+
+impl Debug for RecoveredReceiveKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecoveredReceiveKeys")
+            .field("senders", &self.ratchets.len())
+            .finish()
+    }
+}
+
+impl RecoveredReceiveKeys {
+    /// First generation of the application messages of the member at
+    /// `leaf_index` that can be decrypted, or `None` if the keys of that
+    /// member were not escrowed.
+    pub fn first_generation(&self, leaf_index: u32) -> Option<u32> {
+        self.ratchet(leaf_index).map(|r| r.generation)
+    }
+
+    /// Derive the key and nonce of the application message sent by the member
+    /// at `leaf_index` with generation `generation`.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn message_key<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+        leaf_index: u32,
+        generation: u32,
+    ) -> Result<MessageKeyData, MlsError> {
+        let ratchet = self
+            .ratchet(leaf_index)
+            .ok_or(MlsError::LeafNotFound(leaf_index))?;
+
+        SecretKeyRatchet::message_key_from(
+            cipher_suite_provider,
+            &ratchet.secret,
+            ratchet.generation,
+            generation,
+        )
+        .await
+    }
+
+    fn ratchet(&self, leaf_index: u32) -> Option<&EscrowedRatchet> {
+        self.ratchets.iter().find(|r| r.leaf_index == leaf_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::{
+        crypto::{CipherSuiteProvider, HpkePublicKey},
+        extension::ExtensionType,
+    };
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        crypto::test_utils::test_cipher_suite_provider,
+        extension::{ExporterEscrowExt, MessageEscrowExt},
+        group::{
+            secret_tree::KeyType,
+            test_utils::{test_group, test_group_custom_config, TestGroup},
+            CommitOutput,
+        },
+        tree_kem::node::LeafIndex,
+        ExtensionList,
+    };
+
+    use super::RecoveredExporterSecret;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn exporter_escrow_group() -> TestGroup {
+        test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.extension_type(ExtensionType::EXPORTER_ESCROW)
+        })
+        .await
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn set_exporter_escrow(
+        group: &mut TestGroup,
+        ext: ExporterEscrowExt,
+    ) -> Result<CommitOutput, MlsError> {
+        let mut extensions = ExtensionList::new();
+        extensions.set_from(ext).unwrap();
+
+        group
+            .group
+            .commit_builder()
+            .set_group_context_ext(extensions)?
+            .build()
+            .await
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn escrow_keys_can_recover_exported_secrets() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (escrow_sk, escrow_pk) = cs.kem_generate().await.unwrap();
+
+        let mut alice = exporter_escrow_group().await;
+
+        let output = alice.group.commit(vec![]).await.unwrap();
+        assert!(output.escrowed_exporter_secrets.is_empty());
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let ext = ExporterEscrowExt::new(vec![escrow_pk.clone()], 1);
+        let output = set_exporter_escrow(&mut alice, ext).await.unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let [escrowed] = output.escrowed_exporter_secrets.as_slice() else {
+            panic!("expected a single escrowed secret");
+        };
+
+        assert_eq!(escrowed.escrow_key, escrow_pk);
+        assert_eq!(escrowed.epoch, alice.group.current_epoch());
+
+        let share = escrowed.open(&cs, &escrow_sk).await.unwrap();
+        let recovered = RecoveredExporterSecret::combine(&[share]).unwrap();
+
+        let expected = alice
+            .group
+            .export_secret(b"label", b"context", 32)
+            .await
+            .unwrap();
+
+        let exported = recovered
+            .export_secret(&cs, b"label", b"context", 32)
+            .await
+            .unwrap();
+
+        assert_eq!(exported, expected);
+
+        let (other_sk, _) = cs.kem_generate().await.unwrap();
+        let res = escrowed.open(&cs, &other_sk).await;
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+
+        // Every following commit escrows the new epoch
+        let output = alice.group.commit(vec![]).await.unwrap();
+        assert_eq!(output.escrowed_exporter_secrets.len(), 1);
+        assert_eq!(
+            output.escrowed_exporter_secrets[0].epoch,
+            alice.group.current_epoch() + 1
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn threshold_of_escrow_keys_recover_exported_secrets() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut escrow_keys = vec![];
+
+        for _ in 0..3 {
+            escrow_keys.push(cs.kem_generate().await.unwrap());
+        }
+
+        let mut alice = exporter_escrow_group().await;
+
+        let public_keys = escrow_keys.iter().map(|(_, pk)| pk.clone()).collect();
+        let ext = ExporterEscrowExt::new(public_keys, 2);
+        let output = set_exporter_escrow(&mut alice, ext).await.unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let mut shares = vec![];
+
+        for (escrowed, (sk, pk)) in output.escrowed_exporter_secrets.iter().zip(&escrow_keys) {
+            assert_eq!(&escrowed.escrow_key, pk);
+            assert_eq!(escrowed.threshold, 2);
+            shares.push(escrowed.open(&cs, sk).await.unwrap());
+        }
+
+        assert_eq!(shares.len(), 3);
+
+        let expected = alice
+            .group
+            .export_secret(b"label", b"context", 32)
+            .await
+            .unwrap();
+
+        for pair in [[0, 1], [0, 2], [2, 1]] {
+            let pair = pair.map(|i| shares[i].clone());
+            let recovered = RecoveredExporterSecret::combine(&pair).unwrap();
+
+            let exported = recovered
+                .export_secret(&cs, b"label", b"context", 32)
+                .await
+                .unwrap();
+
+            assert_eq!(exported, expected);
+        }
+
+        let res = RecoveredExporterSecret::combine(&shares[..1]);
+        assert_matches!(res, Err(MlsError::NotEnoughEscrowShares(1, 2)));
+
+        let res = RecoveredExporterSecret::combine(&[shares[0].clone(), shares[0].clone()]);
+        assert_matches!(res, Err(MlsError::InconsistentEscrowShares));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn escrow_threshold_must_be_reachable() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (_, escrow_pk) = cs.kem_generate().await.unwrap();
+
+        let mut alice = exporter_escrow_group().await;
+
+        for threshold in [0, 2] {
+            let ext = ExporterEscrowExt::new(vec![escrow_pk.clone()], threshold);
+            let res = set_exporter_escrow(&mut alice, ext).await;
+            assert_matches!(res, Err(MlsError::InvalidEscrowThreshold(t, 1)) if t == threshold);
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn message_escrow_groups(escrow_key: HpkePublicKey) -> (TestGroup, TestGroup) {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.extension_type(ExtensionType::MESSAGE_ESCROW)
+        })
+        .await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", true, |c| {
+                c.0.settings
+                    .extension_types
+                    .push(ExtensionType::MESSAGE_ESCROW)
+            })
+            .await
+            .unwrap();
+
+        let mut extensions = ExtensionList::new();
+        extensions
+            .set_from(MessageEscrowExt::new(escrow_key))
+            .unwrap();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.process_message(commit).await.unwrap();
+
+        (alice, bob)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receive_keys_are_not_escrowed_without_extension() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let res = alice.group.escrow_receive_keys().await;
+        assert_matches!(res, Err(MlsError::MessageEscrowNotEnabled));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn escrowed_receive_keys_derive_later_keys_of_other_members() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (escrow_sk, escrow_pk) = cs.kem_generate().await.unwrap();
+        let (mut alice, mut bob) = message_escrow_groups(escrow_pk.clone()).await;
+
+        let message = alice
+            .group
+            .encrypt_application_message(b"before escrow", vec![])
+            .await
+            .unwrap();
+
+        bob.process_message(message).await.unwrap();
+
+        let escrowed = bob.group.escrow_receive_keys().await.unwrap();
+
+        assert_eq!(escrowed.escrow_key, escrow_pk);
+        assert_eq!(escrowed.epoch, bob.group.current_epoch());
+
+        let recovered = escrowed.open(&cs, &escrow_sk).await.unwrap();
+
+        // Keys of bob and of the message received before the escrow are not included
+        assert_eq!(recovered.first_generation(0), Some(1));
+        assert_eq!(recovered.first_generation(1), None);
+
+        let res = recovered.message_key(&cs, 0, 0).await;
+        assert_matches!(res, Err(MlsError::KeyMissing(0)));
+
+        let res = recovered.message_key(&cs, 1, 0).await;
+        assert_matches!(res, Err(MlsError::LeafNotFound(1)));
+
+        for generation in 1..4 {
+            let expected = alice
+                .group
+                .epoch_secrets
+                .secret_tree
+                .next_message_key(&cs, LeafIndex(0).node_index(), KeyType::Application)
+                .await
+                .unwrap();
+
+            let recovered_key = recovered.message_key(&cs, 0, generation).await.unwrap();
+            assert_eq!(recovered_key, expected);
+        }
+
+        let (other_sk, _) = cs.kem_generate().await.unwrap();
+        let res = escrowed.open(&cs, &other_sk).await;
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+    }
+}
@@ -5,13 +5,17 @@
 use alloc::{borrow::Cow, vec::Vec};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
-use crate::{client::MlsError, tree_kem::node::NodeVec};
+use crate::{
+    client::MlsError,
+    tree_kem::{node::NodeVec, tree_diff::TreeDiff},
+};
 
 #[cfg_attr(
     all(feature = "ffi", not(test)),
     safer_ffi_gen::ffi_type(clone, opaque)
 )]
 #[derive(Debug, MlsSize, MlsEncode, MlsDecode, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExportedTree<'a>(pub(crate) Cow<'a, NodeVec>);
 
 #[cfg_attr(all(feature = "ffi", not(test)), ::safer_ffi_gen::safer_ffi_gen)]
@@ -35,6 +39,14 @@ impl<'a> ExportedTree<'a> {
     pub fn into_owned(self) -> ExportedTree<'static> {
         ExportedTree(Cow::Owned(self.0.into_owned()))
     }
+
+    /// Nodes that differ between `self` and `other`, for example to check
+    /// that replicas of a group agree on the tree or to inspect the changes
+    /// made by commits.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn diff(&self, other: &ExportedTree<'_>) -> TreeDiff {
+        self.0.diff(&other.0)
+    }
 }
 
 #[cfg_attr(all(feature = "ffi", not(test)), ::safer_ffi_gen::safer_ffi_gen)]
@@ -0,0 +1,224 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::extension::Extension;
+
+use crate::{
+    client::MlsError, client_config::ClientConfig, extension::RequiredCapabilitiesExt,
+    group::ExtensionSupport, Group, MlsMessage,
+};
+
+/// Rollout of a new group context extension, staged with
+/// [`Group::stage_extension_rollout`].
+///
+/// Every member must support a group context extension before it can be
+/// committed. The rollout waits until enough members announce support in
+/// their capabilities, then creates the proposal and reports the remaining
+/// members, which have to update their capabilities or be removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionRollout {
+    extension: Extension,
+    threshold: u8,
+    enforce: bool,
+}
+
+impl ExtensionRollout {
+    /// Roll out `extension` once every member supports it, and require
+    /// support for it from members joining afterwards.
+    pub fn new(extension: Extension) -> Self {
+        Self {
+            extension,
+            threshold: 100,
+            enforce: true,
+        }
+    }
+
+    /// Stage the proposal once `percent` of the members support the
+    /// extension. Values above 100 are treated as 100.
+    pub fn with_threshold(self, percent: u8) -> Self {
+        Self {
+            threshold: percent.min(100),
+            ..self
+        }
+    }
+
+    /// Whether the extension type is added to the
+    /// [`RequiredCapabilitiesExt`] of the group, so that members without
+    /// support can no longer join. Enabled by default.
+    pub fn with_enforcement(self, enforce: bool) -> Self {
+        Self { enforce, ..self }
+    }
+
+    /// The extension rolled out.
+    pub fn extension(&self) -> &Extension {
+        &self.extension
+    }
+}
+
+/// Result of [`Group::stage_extension_rollout`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ExtensionRolloutStage {
+    /// Fewer members than the threshold support the extension. No proposal
+    /// was created.
+    Waiting(ExtensionSupport),
+    /// Group context extensions proposal that must be sent to the group.
+    ///
+    /// The proposal can only be committed once the
+    /// [laggards](ExtensionSupport::laggards) have updated their capabilities
+    /// or are removed by the same commit.
+    Staged {
+        proposal: MlsMessage,
+        support: ExtensionSupport,
+    },
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Check how many members support the extension of `rollout` and, if the
+    /// threshold is met, create a proposal setting it in the group context.
+    ///
+    /// Other group context extensions are kept unchanged. If the rollout is
+    /// enforced, the extension type is added to the
+    /// [`RequiredCapabilitiesExt`] by the same proposal.
+    ///
+    /// `authenticated_data` will be sent unencrypted along with the contents
+    /// of the proposal message.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn stage_extension_rollout(
+        &mut self,
+        rollout: &ExtensionRollout,
+        authenticated_data: Vec<u8>,
+    ) -> Result<ExtensionRolloutStage, MlsError> {
+        let extension_type = rollout.extension.extension_type;
+        let support = self.roster().extension_support(extension_type);
+
+        if support.percent() < rollout.threshold {
+            return Ok(ExtensionRolloutStage::Waiting(support));
+        }
+
+        let mut extensions = self.context().extensions.clone();
+        extensions.set(rollout.extension.clone());
+
+        if rollout.enforce && !extension_type.is_default() {
+            let mut required = extensions
+                .get_as::<RequiredCapabilitiesExt>()?
+                .unwrap_or_default();
+
+            if !required.extensions.contains(&extension_type) {
+                required.extensions.push(extension_type);
+            }
+
+            extensions.set_from(required)?;
+        }
+
+        let proposal = self
+            .propose_group_context_extensions(extensions, authenticated_data)
+            .await?;
+
+        Ok(ExtensionRolloutStage::Staged { proposal, support })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::extension::{Extension, ExtensionType};
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        extension::RequiredCapabilitiesExt,
+        group::test_utils::{test_group_custom_config, TestGroup},
+    };
+
+    use super::{ExtensionRollout, ExtensionRolloutStage};
+
+    const TEST_EXTENSION_TYPE: ExtensionType = ExtensionType::new(65000);
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_groups() -> (TestGroup, TestGroup, TestGroup) {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.extension_type(TEST_EXTENSION_TYPE)
+        })
+        .await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", true, |c| {
+                c.0.settings.extension_types.push(TEST_EXTENSION_TYPE)
+            })
+            .await
+            .unwrap();
+
+        let (carol, commit) = alice.join("carol").await;
+        bob.process_message(commit).await.unwrap();
+
+        (alice, bob, carol)
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn rollout_waits_for_threshold() {
+        let (mut alice, _, _) = test_groups().await;
+        let rollout = ExtensionRollout::new(Extension::new(TEST_EXTENSION_TYPE, vec![1]));
+
+        let stage = alice
+            .group
+            .stage_extension_rollout(&rollout, vec![])
+            .await
+            .unwrap();
+
+        let support = assert_matches!(stage, ExtensionRolloutStage::Waiting(s) => s);
+
+        assert_eq!(support.supporting, vec![0, 1]);
+        assert_eq!(support.laggards, vec![2]);
+        assert_eq!(support.percent(), 66);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn staged_rollout_is_enforced_once_laggards_are_removed() {
+        let (mut alice, mut bob, _) = test_groups().await;
+        let extension = Extension::new(TEST_EXTENSION_TYPE, vec![1]);
+        let rollout = ExtensionRollout::new(extension.clone()).with_threshold(60);
+
+        let stage = alice
+            .group
+            .stage_extension_rollout(&rollout, vec![])
+            .await
+            .unwrap();
+
+        let (proposal, support) = assert_matches!(
+            stage,
+            ExtensionRolloutStage::Staged { proposal, support } => (proposal, support)
+        );
+
+        assert_eq!(support.laggards, vec![2]);
+        bob.process_message(proposal).await.unwrap();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.process_pending_commit().await.unwrap();
+        bob.process_message(commit.commit_message).await.unwrap();
+
+        let extensions = &bob.group.context().extensions;
+        assert_eq!(extensions.get(TEST_EXTENSION_TYPE), Some(extension));
+
+        let required = extensions
+            .get_as::<RequiredCapabilitiesExt>()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(required.extensions, vec![TEST_EXTENSION_TYPE]);
+    }
+}
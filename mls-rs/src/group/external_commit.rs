@@ -2,7 +2,12 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use mls_rs_core::{crypto::SignatureSecretKey, identity::SigningIdentity};
+use mls_rs_core::{
+    crypto::SignatureSecretKey,
+    error::IntoAnyError,
+    extension::ExtensionList,
+    identity::{IdentityProvider, SigningIdentity},
+};
 
 use crate::{
     client_config::ClientConfig,
@@ -11,8 +16,10 @@ use crate::{
         epoch::SenderDataSecret,
         key_schedule::{InitSecret, KeySchedule},
         proposal::{ExternalInit, Proposal, RemoveProposal},
-        EpochSecrets, ExternalPubExt, LeafIndex, LeafNode, MlsError, TreeKemPrivate,
+        EpochSecrets, ExternalPubExt, GroupConfigOverrides, LeafIndex, LeafNode, MlsError,
+        TreeKemPrivate,
     },
+    tree_kem::TreeKemPublic,
     Group, MlsMessage,
 };
 
@@ -49,9 +56,11 @@ pub struct ExternalCommitBuilder<C: ClientConfig> {
     config: C,
     tree_data: Option<ExportedTree<'static>>,
     to_remove: Option<u32>,
+    resync: bool,
     #[cfg(feature = "psk")]
     external_psks: Vec<ExternalPskId>,
     authenticated_data: Vec<u8>,
+    config_overrides: GroupConfigOverrides,
     #[cfg(feature = "custom_proposal")]
     custom_proposals: Vec<Proposal>,
     #[cfg(feature = "custom_proposal")]
@@ -67,7 +76,9 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         Self {
             tree_data: None,
             to_remove: None,
+            resync: false,
             authenticated_data: Vec::new(),
+            config_overrides: Default::default(),
             signer,
             signing_identity,
             config,
@@ -100,6 +111,22 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         }
     }
 
+    #[must_use]
+    /// Rejoin the group by replacing the leaf of an old version of the client,
+    /// for example one whose state was lost.
+    ///
+    /// The replaced leaf is the first leaf whose signing identity the
+    /// configured [`IdentityProvider`] accepts as a valid predecessor of the
+    /// signing identity of the client. Building the commit fails with
+    /// [`MlsError::ExternalCommitResyncLeafNotFound`] if there is no such
+    /// leaf. An index provided with [`Self::with_removal`] takes precedence.
+    pub fn with_resync(self) -> Self {
+        Self {
+            resync: true,
+            ..self
+        }
+    }
+
     #[must_use]
     /// Add plaintext authenticated data to the resulting commit message.
     pub fn with_authenticated_data(self, data: Vec<u8>) -> Self {
@@ -109,6 +136,15 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         }
     }
 
+    #[must_use]
+    /// Use per-group overrides of the client configuration for the joined group.
+    pub fn with_config_overrides(self, config_overrides: GroupConfigOverrides) -> Self {
+        Self {
+            config_overrides,
+            ..self
+        }
+    }
+
     #[cfg(feature = "psk")]
     #[must_use]
     /// Add an external psk to the group as part of the external commit.
@@ -167,17 +203,35 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
             protocol_version,
             &group_info,
             self.tree_data,
-            &self.config.identity_provider(),
+            &self.config.member_identity_provider(),
             &cipher_suite,
+            &self.config.decode_limits(),
+            &self.config.mls_rules(),
+            Default::default(),
+            self.config.tree_validation_parallelism(),
         )
         .await?;
 
+        let to_remove = match self.to_remove {
+            Some(index) => Some(LeafIndex(index)),
+            None if self.resync => Some(
+                find_predecessor(
+                    &public_tree,
+                    &self.signing_identity,
+                    &self.config.member_identity_provider(),
+                    &group_info.group_context.extensions,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
         let (leaf_node, _) = LeafNode::generate(
             &cipher_suite,
             self.config.leaf_properties(),
             self.signing_identity,
             &self.signer,
-            self.config.lifetime(),
+            self.config_overrides.lifetime(self.config.lifetime()),
         )
         .await?;
 
@@ -204,6 +258,8 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         )
         .await?;
 
+        group.config_overrides = self.config_overrides;
+
         #[cfg(feature = "psk")]
         let psk_ids = self
             .external_psks
@@ -242,10 +298,8 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
                 .await?;
         }
 
-        if let Some(r) = self.to_remove {
-            proposals.push(Proposal::Remove(RemoveProposal {
-                to_remove: LeafIndex(r),
-            }));
+        if let Some(to_remove) = to_remove {
+            proposals.push(Proposal::Remove(RemoveProposal { to_remove }));
         }
 
         let commit_output = group
@@ -256,6 +310,8 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
                 Default::default(),
                 None,
                 None,
+                None,
+                None,
             )
             .await?;
 
@@ -264,3 +320,24 @@ impl<C: ClientConfig> ExternalCommitBuilder<C> {
         Ok((group, commit_output.commit_message))
     }
 }
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn find_predecessor<I: IdentityProvider>(
+    tree: &TreeKemPublic,
+    signing_identity: &SigningIdentity,
+    identity_provider: &I,
+    extensions: &ExtensionList,
+) -> Result<LeafIndex, MlsError> {
+    for (index, leaf) in tree.non_empty_leaves() {
+        let is_predecessor = identity_provider
+            .valid_successor(&leaf.signing_identity, signing_identity, extensions)
+            .await
+            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+
+        if is_predecessor {
+            return Ok(index);
+        }
+    }
+
+    Err(MlsError::ExternalCommitResyncLeafNotFound)
+}
@@ -0,0 +1,220 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::client::MlsError;
+
+use super::proposal_filter::ProposalBundle;
+
+/// Forms of external commits that members of a group accept.
+///
+/// The policy is checked by members when processing an external commit, after
+/// the commit was validated according to RFC 9420. The default policy accepts
+/// every valid external commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ExternalCommitPolicy {
+    /// Accept external commits removing the previous leaf of the committer,
+    /// used by members to resync after losing their state.
+    pub allow_resync: bool,
+    /// Accept external commits adding a new member without removing a leaf.
+    pub allow_join: bool,
+    /// Accept external commits including pre-shared key proposals.
+    pub allow_psk: bool,
+    /// Reject external commits not including a pre-shared key proposal. This
+    /// allows to only accept joiners that received a pre-shared key, for
+    /// instance from the server distributing signed group info messages.
+    pub require_psk: bool,
+}
+
+impl Default for ExternalCommitPolicy {
+    fn default() -> Self {
+        Self {
+            allow_resync: true,
+            allow_join: true,
+            allow_psk: true,
+            require_psk: false,
+        }
+    }
+}
+
+/// Reason for rejecting an external commit according to an
+/// [`ExternalCommitPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExternalCommitRejection {
+    /// The commit removes the previous leaf of the committer.
+    Resync,
+    /// The commit adds a new member without removing a leaf.
+    Join,
+    /// The commit includes a pre-shared key proposal.
+    PreSharedKey,
+    /// The commit does not include a pre-shared key proposal.
+    MissingPreSharedKey,
+}
+
+impl ExternalCommitPolicy {
+    /// Create the default policy, accepting every valid external commit.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set whether external commits resyncing a member are accepted.
+    pub fn with_resync(self, allow_resync: bool) -> Self {
+        Self {
+            allow_resync,
+            ..self
+        }
+    }
+
+    /// Set whether external commits adding a new member are accepted.
+    pub fn with_join(self, allow_join: bool) -> Self {
+        Self { allow_join, ..self }
+    }
+
+    /// Set whether external commits may include pre-shared key proposals.
+    pub fn with_psk(self, allow_psk: bool) -> Self {
+        Self { allow_psk, ..self }
+    }
+
+    /// Set whether external commits must include a pre-shared key proposal.
+    pub fn with_required_psk(self, require_psk: bool) -> Self {
+        Self {
+            require_psk,
+            ..self
+        }
+    }
+
+    pub(crate) fn check(&self, proposals: &ProposalBundle) -> Result<(), MlsError> {
+        let is_resync = !proposals.remove_proposals().is_empty();
+
+        #[cfg(feature = "psk")]
+        let has_psk = !proposals.psk_proposals().is_empty();
+
+        #[cfg(not(feature = "psk"))]
+        let has_psk = false;
+
+        let rejection = if is_resync && !self.allow_resync {
+            Some(ExternalCommitRejection::Resync)
+        } else if !is_resync && !self.allow_join {
+            Some(ExternalCommitRejection::Join)
+        } else if has_psk && !self.allow_psk {
+            Some(ExternalCommitRejection::PreSharedKey)
+        } else if !has_psk && self.require_psk {
+            Some(ExternalCommitRejection::MissingPreSharedKey)
+        } else {
+            None
+        };
+
+        match rejection {
+            Some(rejection) => Err(MlsError::ExternalCommitRejected(rejection)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{
+                TestClientBuilder, TestClientConfig, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION,
+            },
+            MlsError,
+        },
+        group::{test_utils::test_group, GroupConfigOverrides},
+        identity::test_utils::get_test_signing_identity,
+        Client,
+    };
+
+    use super::{ExternalCommitPolicy, ExternalCommitRejection};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_client(name: &str) -> Client<TestClientConfig> {
+        let (identity, secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, name.as_bytes()).await;
+
+        TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_commits_are_checked_against_policy() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let group_info = alice
+            .group
+            .group_info_message_allowing_ext_commit(true)
+            .await
+            .unwrap();
+
+        let (_, resync) = test_client("bob")
+            .await
+            .external_commit_builder()
+            .unwrap()
+            .with_resync()
+            .build(group_info.clone())
+            .await
+            .unwrap();
+
+        let (_, join) = test_client("carol")
+            .await
+            .external_commit_builder()
+            .unwrap()
+            .build(group_info)
+            .await
+            .unwrap();
+
+        let policy = ExternalCommitPolicy::new().with_resync(false);
+        alice.group.config_overrides =
+            GroupConfigOverrides::new().with_external_commit_policy(policy);
+
+        let res = alice.group.process_incoming_message(resync).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ExternalCommitRejected(
+                ExternalCommitRejection::Resync
+            ))
+        );
+
+        alice
+            .group
+            .process_incoming_message(join.clone())
+            .await
+            .unwrap();
+
+        let policy = ExternalCommitPolicy::new().with_join(false);
+        bob.group.config_overrides =
+            GroupConfigOverrides::new().with_external_commit_policy(policy);
+
+        let res = bob.group.process_incoming_message(join.clone()).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ExternalCommitRejected(
+                ExternalCommitRejection::Join
+            ))
+        );
+
+        let policy = ExternalCommitPolicy::new().with_required_psk(true);
+        bob.group.config_overrides =
+            GroupConfigOverrides::new().with_external_commit_policy(policy);
+
+        let res = bob.group.process_incoming_message(join).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ExternalCommitRejected(
+                ExternalCommitRejection::MissingPreSharedKey
+            ))
+        );
+    }
+}
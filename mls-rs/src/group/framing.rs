@@ -21,13 +21,14 @@ use mls_rs_core::{
 use zeroize::ZeroizeOnDrop;
 
 #[cfg(feature = "private_message")]
-use alloc::boxed::Box;
+use super::decode_limits::DecodeLimits;
 
 #[cfg(feature = "custom_proposal")]
 use crate::group::proposal::{CustomProposal, ProposalOrRef};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ContentType {
     #[cfg(feature = "private_message")]
@@ -145,6 +146,7 @@ impl Content {
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct PublicMessage {
     pub content: FramedContent,
     pub auth: FramedContentAuthData,
@@ -233,16 +235,9 @@ impl PrivateMessageContent {
     pub(crate) fn mls_decode(
         reader: &mut &[u8],
         content_type: ContentType,
-    ) -> Result<Self, mls_rs_codec::Error> {
-        let content = match content_type {
-            ContentType::Application => Content::Application(ApplicationData::mls_decode(reader)?),
-            #[cfg(feature = "by_ref_proposal")]
-            ContentType::Proposal => Content::Proposal(Box::new(Proposal::mls_decode(reader)?)),
-            ContentType::Commit => {
-                Content::Commit(alloc::boxed::Box::new(Commit::mls_decode(reader)?))
-            }
-        };
-
+        limits: &DecodeLimits,
+    ) -> Result<Self, MlsError> {
+        let content = limits.decode_content(reader, content_type)?;
         let auth = FramedContentAuthData::mls_decode(reader, content.content_type())?;
 
         if reader.iter().any(|&i| i != 0u8) {
@@ -252,7 +247,7 @@ impl PrivateMessageContent {
             // ));
 
             // #[cfg(not(feature = "std"))]
-            return Err(mls_rs_codec::Error::Custom(5));
+            return Err(mls_rs_codec::Error::Custom(5).into());
         }
 
         Ok(Self { content, auth })
@@ -291,16 +286,21 @@ impl Debug for PrivateContentAAD {
 #[cfg(feature = "private_message")]
 #[derive(Clone, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrivateMessage {
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
     pub group_id: Vec<u8>,
     pub epoch: u64,
     pub content_type: ContentType,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
     pub authenticated_data: Vec<u8>,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
     pub encrypted_sender_data: Vec<u8>,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
     pub ciphertext: Vec<u8>,
 }
 
@@ -348,6 +348,7 @@ impl From<&PrivateMessage> for PrivateContentAAD {
     ::safer_ffi_gen::ffi_type(clone, opaque)
 )]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A MLS protocol message for sending data over the wire.
 pub struct MlsMessage {
     pub(crate) version: ProtocolVersion,
@@ -458,6 +459,12 @@ impl MlsMessage {
     }
 
     /// Deserialize a message from transport.
+    ///
+    /// Values unknown to this library at the extension points of the
+    /// protocol, such as extensions, credential types and capabilities, are
+    /// kept as opaque data. A received message, e.g. a group info to be
+    /// re-published, is therefore re-encoded byte-exact by
+    /// [`MlsMessage::to_bytes`].
     #[inline(never)]
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
         Self::mls_decode(&mut &*bytes).map_err(Into::into)
@@ -542,6 +549,7 @@ impl MlsMessage {
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub(crate) enum MlsMessagePayload {
     Plain(PublicMessage) = 1u16,
@@ -688,9 +696,12 @@ mod tests {
         let mut encoded = ciphertext_content.mls_encode_to_vec().unwrap();
         encoded.extend_from_slice(&[0u8; 128]);
 
-        let decoded =
-            PrivateMessageContent::mls_decode(&mut &*encoded, (&ciphertext_content.content).into())
-                .unwrap();
+        let decoded = PrivateMessageContent::mls_decode(
+            &mut &*encoded,
+            (&ciphertext_content.content).into(),
+            &Default::default(),
+        )
+        .unwrap();
 
         assert_eq!(ciphertext_content, decoded);
     }
@@ -702,10 +713,13 @@ mod tests {
         let mut encoded = ciphertext_content.mls_encode_to_vec().unwrap();
         encoded.extend_from_slice(&[1u8; 128]);
 
-        let decoded =
-            PrivateMessageContent::mls_decode(&mut &*encoded, (&ciphertext_content.content).into());
+        let decoded = PrivateMessageContent::mls_decode(
+            &mut &*encoded,
+            (&ciphertext_content.content).into(),
+            &Default::default(),
+        );
 
-        assert_matches!(decoded, Err(mls_rs_codec::Error::Custom(_)));
+        assert_matches!(decoded, Err(MlsError::SerializationError(_)));
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
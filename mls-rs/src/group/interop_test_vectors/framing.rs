@@ -24,7 +24,10 @@ use crate::{
     },
     mls_rules::DefaultMlsRules,
     test_utils::is_edwards,
-    tree_kem::{leaf_node::test_utils::get_basic_test_node, node::LeafIndex},
+    tree_kem::{
+        leaf_node::test_utils::get_basic_test_node,
+        node::{LeafIndex, NodeIndex},
+    },
 };
 
 const FRAMING_N_LEAVES: u32 = 2;
@@ -427,7 +430,8 @@ async fn make_group<P: CipherSuiteProvider>(
 
     // Set the group context and secrets
     let context = GroupContext::from(test_case.context.clone());
-    let secret_tree = get_test_tree(test_case.encryption_secret.clone(), FRAMING_N_LEAVES);
+    let secret_tree =
+        get_test_tree::<NodeIndex>(test_case.encryption_secret.clone(), FRAMING_N_LEAVES);
 
     let secrets = EpochSecrets {
         secret_tree,
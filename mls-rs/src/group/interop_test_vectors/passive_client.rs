@@ -177,7 +177,7 @@ async fn interop_passive_client() {
 
         let client = client_builder
             .signing_identity(id, key, cs.cipher_suite())
-            .build();
+            .build_unchecked();
 
         let key_pckg_gen = KeyPackageGeneration {
             reference: key_package.to_reference(&cs).await.unwrap(),
@@ -248,7 +248,7 @@ async fn invite_passive_client<P: CipherSuiteProvider>(
         .key_package_lifetime(ETERNAL_LIFETIME.not_after - ETERNAL_LIFETIME.not_before)
         .key_package_not_before(ETERNAL_LIFETIME.not_before)
         .signing_identity(identity.clone(), secret_key.clone(), cs.cipher_suite())
-        .build();
+        .build_unchecked();
 
     let key_pckg = client.generate_key_package_message().await.unwrap();
 
@@ -2,14 +2,19 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+use alloc::vec;
 use alloc::vec::Vec;
 use mls_rs_codec::{MlsDecode, MlsEncode};
 
-use mls_rs_core::extension::ExtensionList;
+use mls_rs_core::{
+    extension::{Extension, ExtensionList, ExtensionType},
+    group::ProposalType,
+    identity::{Credential, CredentialType, CustomCredential},
+};
 
 use crate::{
     group::{
-        framing::ContentType,
+        framing::{ContentType, MlsMessagePayload},
         proposal::{
             AddProposal, ExternalInit, PreSharedKeyProposal, ReInitProposal, RemoveProposal,
             UpdateProposal,
@@ -167,3 +172,84 @@ async fn serialization() {
         message.into_ciphertext().unwrap();
     }
 }
+
+fn unknown_extensions() -> [Extension; 2] {
+    // A GREASE value followed by a value of the private use range
+    [
+        Extension::new(ExtensionType::new(0x0a0a), vec![1, 2, 3]),
+        Extension::new(ExtensionType::new(0xff00), vec![]),
+    ]
+}
+
+fn insert_unknown_extensions(list: &mut ExtensionList) {
+    let mut extensions = unknown_extensions().to_vec();
+    extensions.extend(list.iter().cloned());
+
+    // Unknown extensions come first to check that the order is kept
+    *list = ExtensionList::mls_decode(&mut &*extensions.mls_encode_to_vec().unwrap()).unwrap();
+}
+
+fn assert_reencoded(message: MlsMessage) -> MlsMessage {
+    let bytes = message.to_bytes().unwrap();
+    let decoded = MlsMessage::from_bytes(&bytes).unwrap();
+
+    assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    assert_eq!(decoded, message);
+
+    decoded
+}
+
+// Messages produced by other implementations are extended with values this
+// implementation does not know, which must survive re-publishing byte-exact.
+#[test]
+fn unknown_values_survive_reencoding() {
+    let test_cases: Vec<TestCase> = load_test_case_json!(serialization, Vec::<TestCase>::new());
+
+    for test_case in test_cases.into_iter() {
+        let mut message = MlsMessage::from_bytes(&test_case.mls_group_info).unwrap();
+
+        let MlsMessagePayload::GroupInfo(group_info) = &mut message.payload else {
+            panic!("expected group info");
+        };
+
+        insert_unknown_extensions(&mut group_info.group_context.extensions);
+        insert_unknown_extensions(&mut group_info.extensions);
+
+        let decoded = assert_reencoded(message).into_group_info().unwrap();
+
+        assert!(decoded.extensions.starts_with(&unknown_extensions()));
+
+        assert!(decoded
+            .group_context
+            .extensions
+            .starts_with(&unknown_extensions()));
+
+        let mut message = MlsMessage::from_bytes(&test_case.mls_key_package).unwrap();
+
+        let MlsMessagePayload::KeyPackage(key_package) = &mut message.payload else {
+            panic!("expected key package");
+        };
+
+        insert_unknown_extensions(&mut key_package.extensions);
+
+        let leaf_node = &mut key_package.leaf_node;
+        insert_unknown_extensions(&mut leaf_node.extensions);
+
+        leaf_node.signing_identity.credential = Credential::Custom(CustomCredential::new(
+            CredentialType::new(0xf00d),
+            vec![4, 5, 6],
+        ));
+
+        let capabilities = &mut leaf_node.capabilities;
+        capabilities.extensions.push(ExtensionType::new(0x0a0a));
+        capabilities.proposals.push(ProposalType::new(0xff00));
+        capabilities.credentials.push(CredentialType::new(0xf00d));
+
+        let decoded = assert_reencoded(message).into_key_package().unwrap();
+
+        assert!(decoded
+            .leaf_node
+            .extensions
+            .starts_with(&unknown_extensions()));
+    }
+}
@@ -112,7 +112,7 @@ async fn tree_kem() {
                 let secret = leaf
                     .path_secrets
                     .iter()
-                    .find_map(|s| (s.node == dp).then_some(s.path_secret.clone()));
+                    .find_map(|s| (s.node == dp.value()).then_some(s.path_secret.clone()));
 
                 let private_key = if let Some(secret) = secret {
                     let (secret_key, public_key) = PathSecret::from(secret)
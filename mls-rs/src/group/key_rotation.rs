@@ -0,0 +1,348 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::time::MlsTime;
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    group::{proposal::Proposal, CommitOutput, Sender},
+    mls_rules::ProposalRef,
+    Group, MlsMessage,
+};
+
+const KEY_ROTATION_REQUEST_PREFIX: &[u8] = b"mls-rs key rotation request";
+
+/// Request sent by a committer as an application message, asking every
+/// member to propose an update before a deadline.
+///
+/// Receivers recognize the request with
+/// [`from_application_data`](Self::from_application_data) and answer it
+/// with [`Group::respond_to_key_rotation`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct KeyRotationRequest {
+    epoch: u64,
+    deadline: u64,
+}
+
+impl KeyRotationRequest {
+    /// Epoch in which the update proposals are collected.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Time after which the committer commits the collected proposals.
+    pub fn deadline(&self) -> MlsTime {
+        MlsTime::from_duration_since_epoch(core::time::Duration::from_secs(self.deadline))
+    }
+
+    /// Parse the data of an application message as a request. Returns `None`
+    /// for application data that is not a request.
+    pub fn from_application_data(data: &[u8]) -> Option<Self> {
+        let mut encoded = data.strip_prefix(KEY_ROTATION_REQUEST_PREFIX)?;
+        let request = Self::mls_decode(&mut encoded).ok()?;
+
+        encoded.is_empty().then_some(request)
+    }
+
+    fn to_application_data(&self) -> Result<Vec<u8>, MlsError> {
+        let mut data = KEY_ROTATION_REQUEST_PREFIX.to_vec();
+        self.mls_encode(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// State kept by the committer of a key rotation between
+/// [`Group::request_key_rotation`] and [`Group::complete_key_rotation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyRotationCeremony {
+    request: KeyRotationRequest,
+    members: Vec<u32>,
+}
+
+impl KeyRotationCeremony {
+    /// The request sent to the group.
+    pub fn request(&self) -> &KeyRotationRequest {
+        &self.request
+    }
+
+    /// Whether the deadline of the request has passed at `time`.
+    pub fn is_due(&self, time: MlsTime) -> bool {
+        time >= self.request.deadline()
+    }
+}
+
+/// Members that did and did not propose an update during a key rotation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyRotationStatus {
+    /// Members whose update proposal was received.
+    pub rotated: Vec<u32>,
+    /// Members that have not proposed an update yet.
+    pub pending: Vec<u32>,
+}
+
+/// Result of [`Group::complete_key_rotation`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct KeyRotationOutcome {
+    /// Commit of the collected update proposals, including an update path
+    /// for the committer. It becomes effective with
+    /// [`Group::apply_pending_commit`].
+    pub commit_output: CommitOutput,
+    /// Members whose keys are replaced by the commit, including the
+    /// committer.
+    pub rotated: Vec<u32>,
+    /// Members that did not propose a valid update. Their keys are unchanged.
+    pub failed: Vec<u32>,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Start a group-wide key rotation.
+    ///
+    /// Returns the state of the ceremony along with an application message
+    /// asking every member to propose an update until `deadline`. Once the
+    /// deadline has passed, the proposals received in the meantime are committed
+    /// with [`Group::complete_key_rotation`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn request_key_rotation(
+        &mut self,
+        deadline: MlsTime,
+    ) -> Result<(KeyRotationCeremony, MlsMessage), MlsError> {
+        let request = KeyRotationRequest {
+            epoch: self.current_epoch(),
+            deadline: deadline.seconds_since_epoch(),
+        };
+
+        let message = self
+            .encrypt_application_message(&request.to_application_data()?, Vec::new())
+            .await?;
+
+        let self_index = self.current_member_index();
+
+        let members = self
+            .roster()
+            .members_iter()
+            .map(|member| member.index)
+            .filter(|index| *index != self_index)
+            .collect();
+
+        Ok((KeyRotationCeremony { request, members }, message))
+    }
+
+    /// Answer a key rotation request with an update proposal that must be
+    /// sent to the group.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn respond_to_key_rotation(
+        &mut self,
+        request: &KeyRotationRequest,
+    ) -> Result<MlsMessage, MlsError> {
+        if request.epoch != self.current_epoch() {
+            return Err(MlsError::KeyRotationEpochMismatch(request.epoch));
+        }
+
+        self.propose_update(Vec::new()).await
+    }
+
+    /// Members that have proposed an update for the key rotation so far.
+    pub fn key_rotation_status(
+        &self,
+        ceremony: &KeyRotationCeremony,
+    ) -> Result<KeyRotationStatus, MlsError> {
+        self.check_key_rotation_epoch(ceremony)?;
+
+        let (rotated, pending) = ceremony
+            .members
+            .iter()
+            .partition(|index| self.cached_update(**index).is_some());
+
+        Ok(KeyRotationStatus { rotated, pending })
+    }
+
+    /// Commit the update proposals collected for the key rotation, together
+    /// with an update path for the committer.
+    ///
+    /// Other cached proposals are committed as well. Members that did not
+    /// propose an update, or whose update was rejected, are reported as
+    /// failed so that the application can follow up, for example by removing
+    /// them.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn complete_key_rotation(
+        &mut self,
+        ceremony: KeyRotationCeremony,
+    ) -> Result<KeyRotationOutcome, MlsError> {
+        self.check_key_rotation_epoch(&ceremony)?;
+
+        let updates = ceremony
+            .members
+            .iter()
+            .filter_map(|index| Some((*index, self.cached_update(*index)?.clone())))
+            .collect::<Vec<_>>();
+
+        let commit_output = self.commit_builder().force_path_update().build().await?;
+
+        let rejected = commit_output
+            .rejected_proposals
+            .iter()
+            .filter_map(|rejected| rejected.proposal_ref())
+            .collect::<Vec<_>>();
+
+        let mut rotated = Vec::from([self.current_member_index()]);
+
+        rotated.extend(
+            updates
+                .iter()
+                .filter(|(_, update_ref)| !rejected.contains(&update_ref))
+                .map(|(index, _)| *index),
+        );
+
+        let failed = ceremony
+            .members
+            .into_iter()
+            .filter(|index| !rotated.contains(index))
+            .collect();
+
+        Ok(KeyRotationOutcome {
+            commit_output,
+            rotated,
+            failed,
+        })
+    }
+
+    fn check_key_rotation_epoch(&self, ceremony: &KeyRotationCeremony) -> Result<(), MlsError> {
+        if ceremony.request.epoch != self.current_epoch() {
+            return Err(MlsError::KeyRotationEpochMismatch(ceremony.request.epoch));
+        }
+
+        Ok(())
+    }
+
+    fn cached_update(&self, index: u32) -> Option<&ProposalRef> {
+        self.state
+            .proposals
+            .sorted()
+            .into_iter()
+            .find(|(_, cached)| {
+                matches!(cached.proposal, Proposal::Update(_))
+                    && cached.sender == Sender::Member(index)
+            })
+            .map(|(proposal_ref, _)| proposal_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use core::time::Duration;
+    use mls_rs_core::time::MlsTime;
+
+    use crate::{
+        client::{
+            test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{test_utils::test_group, ReceivedMessage},
+    };
+
+    use super::KeyRotationRequest;
+
+    fn deadline() -> MlsTime {
+        MlsTime::from_duration_since_epoch(Duration::from_secs(1000))
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_rotation_commits_updates_and_reports_missing_members() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (bob, bob_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let (carol, carol_kp) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .add_member(bob_kp)
+            .unwrap()
+            .add_member(carol_kp)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let welcome = &commit.welcome_messages[0];
+        let (mut bob, _) = bob.join_group(None, welcome).await.unwrap();
+        let (mut carol, _) = carol.join_group(None, welcome).await.unwrap();
+
+        let (ceremony, request) = alice.group.request_key_rotation(deadline()).await.unwrap();
+
+        let received = bob.process_incoming_message(request).await.unwrap();
+
+        let message = assert_matches!(received, ReceivedMessage::ApplicationMessage(m) => m);
+        let request = KeyRotationRequest::from_application_data(message.data()).unwrap();
+        assert_eq!(request.deadline(), deadline());
+
+        let update = bob.respond_to_key_rotation(&request).await.unwrap();
+
+        alice
+            .group
+            .process_incoming_message(update.clone())
+            .await
+            .unwrap();
+        carol.process_incoming_message(update).await.unwrap();
+
+        let status = alice.group.key_rotation_status(&ceremony).unwrap();
+        assert_eq!(status.rotated, vec![1]);
+        assert_eq!(status.pending, vec![2]);
+
+        let outcome = alice.group.complete_key_rotation(ceremony).await.unwrap();
+
+        assert_eq!(outcome.rotated, vec![0, 1]);
+        assert_eq!(outcome.failed, vec![2]);
+        assert!(outcome.commit_output.contains_update_path);
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let commit = outcome.commit_output.commit_message;
+        carol.process_incoming_message(commit).await.unwrap();
+
+        assert_eq!(
+            carol.epoch_authenticator().unwrap(),
+            alice.group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn key_rotation_of_previous_epoch_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (ceremony, _) = alice.group.request_key_rotation(deadline()).await.unwrap();
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let res = alice
+            .group
+            .respond_to_key_rotation(ceremony.request())
+            .await;
+        assert_matches!(res, Err(MlsError::KeyRotationEpochMismatch(0)));
+
+        let res = alice.group.complete_key_rotation(ceremony).await;
+        assert_matches!(res, Err(MlsError::KeyRotationEpochMismatch(0)));
+    }
+
+    #[test]
+    fn other_application_data_is_not_a_request() {
+        assert_eq!(KeyRotationRequest::from_application_data(b"hello"), None);
+    }
+}
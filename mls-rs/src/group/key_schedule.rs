@@ -26,6 +26,9 @@ use crate::crypto::{HpkeContextR, HpkeContextS, HpkePublicKey, HpkeSecretKey};
 use super::epoch::{EpochSecrets, SenderDataSecret};
 use super::message_signature::AuthenticatedContent;
 
+#[cfg(any(test, feature = "test_vectors"))]
+pub(crate) mod test_vectors;
+
 #[derive(Clone, PartialEq, Eq, Default, MlsEncode, MlsDecode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeySchedule {
@@ -42,6 +45,9 @@ pub struct KeySchedule {
     #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::zeroizing_serde"))]
     membership_key: Zeroizing<Vec<u8>>,
     init_secret: InitSecret,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::zeroizing_serde"))]
+    epoch_record_secret: Zeroizing<Vec<u8>>,
 }
 
 impl Debug for KeySchedule {
@@ -64,6 +70,10 @@ impl Debug for KeySchedule {
                 &mls_rs_core::debug::pretty_bytes(&self.membership_key),
             )
             .field("init_secret", &self.init_secret)
+            .field(
+                "epoch_record_secret",
+                &mls_rs_core::debug::pretty_bytes(&self.epoch_record_secret),
+            )
             .finish()
     }
 }
@@ -216,6 +226,7 @@ impl KeySchedule {
             external_secret: secrets_producer.derive(b"external").await?,
             membership_key: secrets_producer.derive(b"membership").await?,
             init_secret: InitSecret(secrets_producer.derive(b"init").await?),
+            epoch_record_secret: secrets_producer.derive(EPOCH_RECORD_LABEL).await?,
         };
 
         Ok(KeyScheduleDerivationResult {
@@ -234,14 +245,60 @@ impl KeySchedule {
         len: usize,
         cipher_suite: &P,
     ) -> Result<Zeroizing<Vec<u8>>, MlsError> {
-        let secret = kdf_derive_secret(cipher_suite, &self.exporter_secret, label).await?;
+        export_from_exporter_secret(cipher_suite, &self.exporter_secret, label, context, len).await
+    }
 
-        let context_hash = cipher_suite
-            .hash(context)
-            .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+    pub(crate) fn exporter_secret(&self) -> &[u8] {
+        &self.exporter_secret
+    }
+
+    /// Secret of the chain encrypting prior epochs in storage, derived from
+    /// the epoch secret. Unlike the exporter secret, it is never output.
+    ///
+    /// Key schedules restored from snapshots written before this secret was
+    /// kept derive it from the init secret instead.
+    #[cfg(feature = "prior_epoch")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn epoch_record_secret<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite: &P,
+    ) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+        if !self.epoch_record_secret.is_empty() {
+            return Ok(self.epoch_record_secret.clone());
+        }
+
+        kdf_derive_secret(cipher_suite, &self.init_secret.0, EPOCH_RECORD_LABEL).await
+    }
+
+    /// Key schedule decoded from a snapshot written before the epoch record
+    /// secret was kept.
+    pub(crate) fn without_epoch_record_secret(
+        exporter_secret: Zeroizing<Vec<u8>>,
+        authentication_secret: Zeroizing<Vec<u8>>,
+        external_secret: Zeroizing<Vec<u8>>,
+        membership_key: Zeroizing<Vec<u8>>,
+        init_secret: InitSecret,
+    ) -> Self {
+        Self {
+            exporter_secret,
+            authentication_secret,
+            external_secret,
+            membership_key,
+            init_secret,
+            epoch_record_secret: Default::default(),
+        }
+    }
 
-        kdf_expand_with_label(cipher_suite, &secret, b"exported", &context_hash, Some(len)).await
+    pub(crate) fn external_secret(&self) -> &[u8] {
+        &self.external_secret
+    }
+
+    pub(crate) fn membership_key(&self) -> &[u8] {
+        &self.membership_key
+    }
+
+    pub(crate) fn init_secret(&self) -> &[u8] {
+        &self.init_secret.0
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -319,6 +376,42 @@ pub(crate) async fn kdf_expand_with_label<P: CipherSuiteProvider>(
         .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
 }
 
+/// Derive one secret per `(label, context, len)` element of `labels` from
+/// `secret`, using a single call to [`CipherSuiteProvider::kdf_expand_batch`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn kdf_expand_with_labels<P: CipherSuiteProvider, const N: usize>(
+    cipher_suite_provider: &P,
+    secret: &[u8],
+    labels: [(&[u8], &[u8], Option<usize>); N],
+) -> Result<[Zeroizing<Vec<u8>>; N], MlsError> {
+    let extract_size = cipher_suite_provider.kdf_extract_size();
+
+    let encoded = labels
+        .iter()
+        .map(|(label, context, len)| {
+            let len = len.unwrap_or(extract_size);
+            let label = Label::new(len as u16, label, context).mls_encode_to_vec()?;
+            Ok((label, len))
+        })
+        .collect::<Result<Vec<_>, MlsError>>()?;
+
+    let infos = encoded
+        .iter()
+        .map(|(label, len)| (label.as_slice(), *len))
+        .collect::<Vec<_>>();
+
+    let outputs = cipher_suite_provider
+        .kdf_expand_batch(secret, &infos)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    let count = outputs.len();
+
+    outputs
+        .try_into()
+        .map_err(|_| MlsError::KdfBatchSizeMismatch(count, N))
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 pub(crate) async fn kdf_derive_secret<P: CipherSuiteProvider>(
     cipher_suite_provider: &P,
@@ -328,6 +421,31 @@ pub(crate) async fn kdf_derive_secret<P: CipherSuiteProvider>(
     kdf_expand_with_label(cipher_suite_provider, secret, label, &[], None).await
 }
 
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn export_from_exporter_secret<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    exporter_secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    let secret = kdf_derive_secret(cipher_suite_provider, exporter_secret, label).await?;
+
+    let context_hash = cipher_suite_provider
+        .hash(context)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    kdf_expand_with_label(
+        cipher_suite_provider,
+        &secret,
+        b"exported",
+        &context_hash,
+        Some(len),
+    )
+    .await
+}
+
 #[derive(Clone, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 pub(crate) struct JoinerSecret(#[mls_codec(with = "mls_rs_codec::byte_vec")] Zeroizing<Vec<u8>>);
 
@@ -381,6 +499,9 @@ impl<'a, P: CipherSuiteProvider> SecretsProducer<'a, P> {
 
 const EXPORTER_CONTEXT: &[u8] = b"MLS 1.0 external init secret";
 
+// Not defined by RFC 9420, hence the prefix
+const EPOCH_RECORD_LABEL: &[u8] = b"mls-rs epoch record";
+
 #[derive(Clone, Eq, PartialEq, MlsEncode, MlsDecode, MlsSize, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InitSecret(
@@ -533,8 +654,9 @@ pub(crate) mod test_utils {
             exporter_secret: fake_secret.clone(),
             authentication_secret: fake_secret.clone(),
             external_secret: fake_secret.clone(),
-            membership_key: fake_secret,
+            membership_key: fake_secret.clone(),
             init_secret: InitSecret::new(vec![0u8; key_size]),
+            epoch_record_secret: fake_secret,
         }
     }
 
@@ -564,106 +686,48 @@ pub(crate) mod test_utils {
 
 #[cfg(test)]
 mod tests {
-    use crate::client::test_utils::TEST_PROTOCOL_VERSION;
     use crate::crypto::test_utils::try_test_cipher_suite_provider;
     use crate::group::key_schedule::{
-        get_welcome_secret, kdf_derive_secret, kdf_expand_with_label,
+        kdf_derive_secret, kdf_expand_with_label, kdf_expand_with_labels,
     };
-    use crate::group::GroupContext;
     use alloc::string::String;
     use alloc::vec::Vec;
-    use mls_rs_codec::MlsEncode;
-    use mls_rs_core::crypto::CipherSuiteProvider;
-    use mls_rs_core::extension::ExtensionList;
 
     #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
     use crate::{
+        client::test_utils::TEST_PROTOCOL_VERSION,
         crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
         group::{
-            key_schedule::KeyScheduleDerivationResult, test_utils::random_bytes, InitSecret,
-            PskSecret,
+            key_schedule::{get_welcome_secret, KeyScheduleDerivationResult},
+            test_utils::random_bytes,
+            GroupContext, InitSecret, PskSecret,
         },
     };
 
     #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
     use alloc::{string::ToString, vec};
 
-    #[cfg(target_arch = "wasm32")]
-    use wasm_bindgen_test::wasm_bindgen_test as test;
-    use zeroize::Zeroizing;
-
-    use super::test_utils::get_test_key_schedule;
-    use super::KeySchedule;
-
-    #[derive(serde::Deserialize, serde::Serialize)]
-    struct TestCase {
-        cipher_suite: u16,
-        #[serde(with = "hex::serde")]
-        group_id: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        initial_init_secret: Vec<u8>,
-        epochs: Vec<KeyScheduleEpoch>,
-    }
-
-    #[derive(serde::Deserialize, serde::Serialize)]
-    struct KeyScheduleEpoch {
-        #[serde(with = "hex::serde")]
-        commit_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        psk_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        confirmed_transcript_hash: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        tree_hash: Vec<u8>,
-
-        #[serde(with = "hex::serde")]
-        group_context: Vec<u8>,
-
-        #[serde(with = "hex::serde")]
-        joiner_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        welcome_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        init_secret: Vec<u8>,
+    #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
+    use mls_rs_codec::MlsEncode;
 
-        #[serde(with = "hex::serde")]
-        sender_data_secret: Vec<u8>,
-        #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-        #[serde(with = "hex::serde")]
-        encryption_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        exporter_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        epoch_authenticator: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        external_secret: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        confirmation_key: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        membership_key: Vec<u8>,
-        #[cfg(feature = "psk")]
-        #[serde(with = "hex::serde")]
-        resumption_psk: Vec<u8>,
+    #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
+    use mls_rs_core::crypto::CipherSuiteProvider;
 
-        #[serde(with = "hex::serde")]
-        external_pub: Vec<u8>,
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
 
-        exporter: KeyScheduleExporter,
-    }
+    #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
+    use super::{
+        test_utils::get_test_key_schedule,
+        test_vectors::{KeyScheduleEpoch, KeyScheduleExporter},
+        KeySchedule,
+    };
 
-    #[derive(serde::Deserialize, serde::Serialize)]
-    struct KeyScheduleExporter {
-        label: String,
-        #[serde(with = "hex::serde")]
-        context: Vec<u8>,
-        length: usize,
-        #[serde(with = "hex::serde")]
-        secret: Vec<u8>,
-    }
+    use super::test_vectors::KeyScheduleTestCase;
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_key_schedule() {
-        let test_cases: Vec<TestCase> =
+        let test_cases: Vec<KeyScheduleTestCase> =
             load_test_case_json!(key_schedule_test_vector, generate_test_vector());
 
         for test_case in test_cases {
@@ -671,108 +735,13 @@ mod tests {
                 continue;
             };
 
-            let mut key_schedule = get_test_key_schedule(cs_provider.cipher_suite());
-            key_schedule.init_secret.0 = Zeroizing::new(test_case.initial_init_secret);
-
-            for (i, epoch) in test_case.epochs.into_iter().enumerate() {
-                let context = GroupContext {
-                    protocol_version: TEST_PROTOCOL_VERSION,
-                    cipher_suite: cs_provider.cipher_suite(),
-                    group_id: test_case.group_id.clone(),
-                    epoch: i as u64,
-                    tree_hash: epoch.tree_hash,
-                    confirmed_transcript_hash: epoch.confirmed_transcript_hash.into(),
-                    extensions: ExtensionList::new(),
-                };
-
-                assert_eq!(context.mls_encode_to_vec().unwrap(), epoch.group_context);
-
-                let psk = epoch.psk_secret.into();
-                let commit = epoch.commit_secret.into();
-
-                let key_schedule_res = KeySchedule::from_key_schedule(
-                    &key_schedule,
-                    &commit,
-                    &context,
-                    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-                    32,
-                    &psk,
-                    &cs_provider,
-                )
-                .await
-                .unwrap();
-
-                key_schedule = key_schedule_res.key_schedule;
-
-                let welcome =
-                    get_welcome_secret(&cs_provider, &key_schedule_res.joiner_secret, &psk)
-                        .await
-                        .unwrap();
-
-                assert_eq!(*welcome, epoch.welcome_secret);
-
-                let expected: Vec<u8> = key_schedule_res.joiner_secret.into();
-                assert_eq!(epoch.joiner_secret, expected);
-
-                assert_eq!(&key_schedule.init_secret.0.to_vec(), &epoch.init_secret);
-
-                assert_eq!(
-                    epoch.sender_data_secret,
-                    *key_schedule_res.epoch_secrets.sender_data_secret.to_vec()
-                );
-
-                #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-                assert_eq!(
-                    epoch.encryption_secret,
-                    *key_schedule_res.epoch_secrets.secret_tree.get_root_secret()
-                );
-
-                assert_eq!(epoch.exporter_secret, key_schedule.exporter_secret.to_vec());
-
-                assert_eq!(
-                    epoch.epoch_authenticator,
-                    key_schedule.authentication_secret.to_vec()
-                );
-
-                assert_eq!(epoch.external_secret, key_schedule.external_secret.to_vec());
-
-                assert_eq!(
-                    epoch.confirmation_key,
-                    key_schedule_res.confirmation_key.to_vec()
-                );
-
-                assert_eq!(epoch.membership_key, key_schedule.membership_key.to_vec());
-
-                #[cfg(feature = "psk")]
-                {
-                    let expected: Vec<u8> =
-                        key_schedule_res.epoch_secrets.resumption_secret.to_vec();
-
-                    assert_eq!(epoch.resumption_psk, expected);
-                }
-
-                let (_external_sec, external_pub) = key_schedule
-                    .get_external_key_pair(&cs_provider)
-                    .await
-                    .unwrap();
-
-                assert_eq!(epoch.external_pub, *external_pub);
-
-                let exp = epoch.exporter;
-
-                let exported = key_schedule
-                    .export_secret(exp.label.as_bytes(), &exp.context, exp.length, &cs_provider)
-                    .await
-                    .unwrap();
-
-                assert_eq!(exported.to_vec(), exp.secret);
-            }
+            test_case.verify(&cs_provider).await.unwrap();
         }
     }
 
     #[cfg(all(not(mls_build_async), feature = "rfc_compliant"))]
     #[cfg_attr(coverage_nightly, coverage(off))]
-    fn generate_test_vector() -> Vec<TestCase> {
+    fn generate_test_vector() -> Vec<KeyScheduleTestCase> {
         let mut test_cases = vec![];
 
         for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
@@ -843,7 +812,7 @@ mod tests {
                 &cs_provider,
             );
 
-            let test_case = TestCase {
+            let test_case = KeyScheduleTestCase {
                 cipher_suite: cs_provider.cipher_suite().into(),
                 group_id: group_context.group_id.clone(),
                 initial_init_secret: initial_init_secret.0.to_vec(),
@@ -857,7 +826,7 @@ mod tests {
     }
 
     #[cfg(not(all(not(mls_build_async), feature = "rfc_compliant")))]
-    fn generate_test_vector() -> Vec<TestCase> {
+    fn generate_test_vector() -> Vec<KeyScheduleTestCase> {
         panic!("Tests cannot be generated in async mode");
     }
 
@@ -985,4 +954,41 @@ mod tests {
             }
         }
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn batched_expand_with_labels_matches_single_expansions() {
+        let test_cases: Vec<InteropTestCase> =
+            load_test_case_json!(basic_crypto, Vec::<InteropTestCase>::new());
+
+        for test_case in test_cases {
+            let Some(cs) = try_test_cipher_suite_provider(test_case.cipher_suite) else {
+                continue;
+            };
+
+            let test_exp = &test_case.expand_with_label;
+
+            let [expanded, derived] = kdf_expand_with_labels(
+                &cs,
+                &test_exp.secret,
+                [
+                    (
+                        test_exp.label.as_bytes(),
+                        &test_exp.context,
+                        Some(test_exp.length),
+                    ),
+                    (b"secret", &[], None),
+                ],
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(&expanded.to_vec(), &test_exp.out);
+
+            let expected = kdf_derive_secret(&cs, &test_exp.secret, b"secret")
+                .await
+                .unwrap();
+
+            assert_eq!(derived, expected);
+        }
+    }
 }
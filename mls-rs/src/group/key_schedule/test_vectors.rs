@@ -0,0 +1,232 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use mls_rs_codec::MlsEncode;
+use mls_rs_core::{crypto::CipherSuiteProvider, extension::ExtensionList};
+use zeroize::Zeroizing;
+
+use crate::{
+    group::GroupContext,
+    protocol_version::ProtocolVersion,
+    test_vectors::{ensure_eq, CheckResult, TestVectorError},
+};
+
+#[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+use crate::{group::SecretTree, tree_kem::node::NodeIndex};
+
+use super::{get_welcome_secret, InitSecret, KeySchedule};
+
+// Size of the secret tree derived in each epoch. Only its root, the
+// encryption secret, is compared with the test vector.
+#[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+const SECRET_TREE_SIZE: u32 = 32;
+
+/// Test case in the format of
+/// https://github.com/mlswg/mls-implementations/blob/main/test-vectors/key-schedule.json
+#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) struct KeyScheduleTestCase {
+    pub(crate) cipher_suite: u16,
+    #[serde(with = "hex::serde")]
+    pub(crate) group_id: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) initial_init_secret: Vec<u8>,
+    pub(crate) epochs: Vec<KeyScheduleEpoch>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) struct KeyScheduleEpoch {
+    #[serde(with = "hex::serde")]
+    pub(crate) commit_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) psk_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) confirmed_transcript_hash: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) tree_hash: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) group_context: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) joiner_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) welcome_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) init_secret: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) sender_data_secret: Vec<u8>,
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    #[serde(with = "hex::serde")]
+    pub(crate) encryption_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) exporter_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) epoch_authenticator: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) external_secret: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) confirmation_key: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) membership_key: Vec<u8>,
+    #[cfg(feature = "psk")]
+    #[serde(with = "hex::serde")]
+    pub(crate) resumption_psk: Vec<u8>,
+
+    #[serde(with = "hex::serde")]
+    pub(crate) external_pub: Vec<u8>,
+
+    pub(crate) exporter: KeyScheduleExporter,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) struct KeyScheduleExporter {
+    pub(crate) label: String,
+    #[serde(with = "hex::serde")]
+    pub(crate) context: Vec<u8>,
+    pub(crate) length: usize,
+    #[serde(with = "hex::serde")]
+    pub(crate) secret: Vec<u8>,
+}
+
+impl KeyScheduleTestCase {
+    /// Derive all epochs of the test case with `cs` and return an error on the
+    /// first value that does not match.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider>(
+        self,
+        cs: &P,
+    ) -> Result<(), TestVectorError> {
+        let mut key_schedule =
+            KeySchedule::new(InitSecret(Zeroizing::new(self.initial_init_secret)));
+
+        for (i, epoch) in self.epochs.into_iter().enumerate() {
+            let context = GroupContext {
+                protocol_version: ProtocolVersion::MLS_10,
+                cipher_suite: cs.cipher_suite(),
+                group_id: self.group_id.clone(),
+                epoch: i as u64,
+                tree_hash: epoch.tree_hash,
+                confirmed_transcript_hash: epoch.confirmed_transcript_hash.into(),
+                extensions: ExtensionList::new(),
+            };
+
+            ensure_eq!(
+                context
+                    .mls_encode_to_vec()
+                    .check("encoding group context")?,
+                epoch.group_context,
+                "group context mismatch"
+            );
+
+            let psk = epoch.psk_secret.into();
+            let commit = epoch.commit_secret.into();
+
+            let key_schedule_res = KeySchedule::from_key_schedule(
+                &key_schedule,
+                &commit,
+                &context,
+                #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+                SECRET_TREE_SIZE,
+                &psk,
+                cs,
+            )
+            .await
+            .check("deriving key schedule")?;
+
+            key_schedule = key_schedule_res.key_schedule;
+
+            let welcome = get_welcome_secret(cs, &key_schedule_res.joiner_secret, &psk)
+                .await
+                .check("deriving welcome secret")?;
+
+            ensure_eq!(*welcome, epoch.welcome_secret, "welcome secret mismatch");
+
+            ensure_eq!(
+                *key_schedule_res.joiner_secret.0,
+                epoch.joiner_secret,
+                "joiner secret mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule.init_secret.0,
+                epoch.init_secret,
+                "init secret mismatch"
+            );
+
+            ensure_eq!(
+                **key_schedule_res.epoch_secrets.sender_data_secret,
+                epoch.sender_data_secret,
+                "sender data secret mismatch"
+            );
+
+            #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+            ensure_eq!(
+                key_schedule_res.epoch_secrets.secret_tree,
+                SecretTree::<NodeIndex>::new(
+                    SECRET_TREE_SIZE,
+                    Zeroizing::new(epoch.encryption_secret)
+                ),
+                "encryption secret mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule.exporter_secret,
+                epoch.exporter_secret,
+                "exporter secret mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule.authentication_secret,
+                epoch.epoch_authenticator,
+                "epoch authenticator mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule.external_secret,
+                epoch.external_secret,
+                "external secret mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule_res.confirmation_key,
+                epoch.confirmation_key,
+                "confirmation key mismatch"
+            );
+
+            ensure_eq!(
+                *key_schedule.membership_key,
+                epoch.membership_key,
+                "membership key mismatch"
+            );
+
+            #[cfg(feature = "psk")]
+            ensure_eq!(
+                *key_schedule_res.epoch_secrets.resumption_secret,
+                epoch.resumption_psk,
+                "resumption psk mismatch"
+            );
+
+            let (_external_sec, external_pub) = key_schedule
+                .get_external_key_pair(cs)
+                .await
+                .check("deriving external key pair")?;
+
+            ensure_eq!(*external_pub, epoch.external_pub, "external pub mismatch");
+
+            let exp = epoch.exporter;
+
+            let exported = key_schedule
+                .export_secret(exp.label.as_bytes(), &exp.context, exp.length, cs)
+                .await
+                .check("exporting secret")?;
+
+            ensure_eq!(*exported, exp.secret, "exported secret mismatch");
+        }
+
+        Ok(())
+    }
+}
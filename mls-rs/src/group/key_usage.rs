@@ -0,0 +1,204 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_core::crypto::CipherSuiteProvider;
+
+use crate::{client::MlsError, client_config::ClientConfig, group::secret_tree::KeyType, Group};
+
+/// Limits on the number of messages a member encrypts with keys derived from
+/// its application ratchet within a single epoch.
+///
+/// AEAD algorithms have confidentiality and integrity bounds on the number of
+/// encryptions performed under related keys. Since every epoch starts a new
+/// secret tree, committing resets the usage counter.
+///
+/// Only application messages encrypted by this member are counted. The size
+/// of encrypted messages, handshake messages encrypted with the `private_message`
+/// feature and messages decrypted from other senders are not taken into
+/// account, so limits should be chosen with some margin if those matter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AeadUsageLimits {
+    /// Number of messages after which a rekey is recommended.
+    pub recommended_messages: u32,
+    /// Number of messages after which encryption of application messages is
+    /// refused until the epoch changes.
+    pub max_messages: u32,
+}
+
+impl AeadUsageLimits {
+    /// Create limits recommending a rekey after `recommended_messages` and
+    /// refusing encryption after `max_messages`.
+    ///
+    /// Fails with [`MlsError::InvalidAeadUsageLimits`] if
+    /// `recommended_messages` is greater than `max_messages`.
+    pub fn new(recommended_messages: u32, max_messages: u32) -> Result<Self, MlsError> {
+        if recommended_messages > max_messages {
+            return Err(MlsError::InvalidAeadUsageLimits);
+        }
+
+        Ok(Self {
+            recommended_messages,
+            max_messages,
+        })
+    }
+
+    /// Status for a sender that has encrypted `messages` messages.
+    pub fn status(&self, messages: u32) -> RekeyStatus {
+        if messages >= self.max_messages {
+            RekeyStatus::Required
+        } else if messages >= self.recommended_messages {
+            RekeyStatus::Recommended
+        } else {
+            RekeyStatus::NotNeeded
+        }
+    }
+}
+
+impl Default for AeadUsageLimits {
+    fn default() -> Self {
+        Self {
+            recommended_messages: 1 << 20,
+            max_messages: 1 << 23,
+        }
+    }
+}
+
+/// Indication of whether a member should change epoch before continuing to
+/// encrypt application messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RekeyStatus {
+    NotNeeded,
+    /// The recommended limit was reached. The member should commit, for
+    /// example with an update path, to move to a new epoch.
+    Recommended,
+    /// The maximum limit was reached. Encrypting further application messages
+    /// in this epoch fails with [`MlsError::AeadUsageLimitReached`].
+    Required,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Number of application messages encrypted by this member in the
+    /// current epoch.
+    pub fn application_messages_sent(&self) -> u32 {
+        self.epoch_secrets.secret_tree.key_generation(
+            &self.private_tree.self_index.node_index(),
+            KeyType::Application,
+        )
+    }
+
+    /// Whether this member should change epoch before sending further
+    /// application messages, according to the limits configured for the
+    /// group cipher suite with
+    /// [`ClientBuilder::aead_usage_limits`](crate::client_builder::ClientBuilder::aead_usage_limits).
+    pub fn rekey_status(&self) -> RekeyStatus {
+        self.config
+            .aead_usage_limits(self.cipher_suite_provider.cipher_suite())
+            .status(self.application_messages_sent())
+    }
+
+    pub(crate) fn check_aead_usage(&self) -> Result<(), MlsError> {
+        match self.rekey_status() {
+            RekeyStatus::Required => Err(MlsError::AeadUsageLimitReached),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::{test_group_custom_config, test_n_member_group},
+    };
+
+    use super::{AeadUsageLimits, RekeyStatus};
+
+    #[test]
+    fn status_follows_limits() {
+        let limits = AeadUsageLimits::new(2, 4).unwrap();
+
+        assert_eq!(limits.status(1), RekeyStatus::NotNeeded);
+        assert_eq!(limits.status(2), RekeyStatus::Recommended);
+        assert_eq!(limits.status(4), RekeyStatus::Required);
+    }
+
+    #[test]
+    fn recommended_limit_cannot_exceed_max_limit() {
+        let res = AeadUsageLimits::new(4, 2);
+        assert_matches!(res, Err(MlsError::InvalidAeadUsageLimits));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn usage_is_tracked_and_reset_by_commit() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        assert_eq!(groups[0].group.application_messages_sent(), 0);
+
+        for _ in 0..3 {
+            groups[0]
+                .group
+                .encrypt_application_message(b"hello", vec![])
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(groups[0].group.application_messages_sent(), 3);
+        assert_eq!(groups[1].group.application_messages_sent(), 0);
+
+        groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(groups[0].group.application_messages_sent(), 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn encryption_is_refused_after_max_limit() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.aead_usage_limits(TEST_CIPHER_SUITE, AeadUsageLimits::new(1, 2).unwrap())
+        })
+        .await;
+
+        assert_eq!(alice.group.rekey_status(), RekeyStatus::NotNeeded);
+
+        alice
+            .group
+            .encrypt_application_message(b"1", vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(alice.group.rekey_status(), RekeyStatus::Recommended);
+
+        alice
+            .group
+            .encrypt_application_message(b"2", vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(alice.group.rekey_status(), RekeyStatus::Required);
+
+        let res = alice.group.encrypt_application_message(b"3", vec![]).await;
+
+        assert_matches!(res, Err(MlsError::AeadUsageLimitReached));
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(alice.group.rekey_status(), RekeyStatus::NotNeeded);
+
+        alice
+            .group
+            .encrypt_application_message(b"3", vec![])
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{time::MlsTime, tree_kem::Lifetime};
+
+use super::Member;
+
+/// Tolerance applied when checking the lifetime of leaf nodes against the
+/// current time.
+///
+/// Clocks of different parties are rarely synchronized. A leaf node whose
+/// lifetime started or ended at most `grace_period` seconds before or after
+/// the current time is accepted, and can be reported by
+/// [`Roster::lifetime_warnings`](super::Roster::lifetime_warnings) instead.
+///
+/// The default policy has no grace period, which rejects any leaf node
+/// outside of its lifetime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct LifetimePolicy {
+    /// Number of seconds by which the lifetime of a leaf node may be missed
+    /// while the leaf node is still accepted.
+    pub grace_period: u64,
+    /// Number of seconds before the end of the lifetime of a leaf node from
+    /// which the leaf node is reported as expiring.
+    pub warning_period: u64,
+}
+
+/// Reason for reporting a member by
+/// [`Roster::lifetime_warnings`](super::Roster::lifetime_warnings).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LifetimeWarningKind {
+    /// The lifetime of the member's leaf node has not started yet.
+    NotYetValid,
+    /// The lifetime of the member's leaf node ends within the warning period.
+    ExpiringSoon,
+    /// The lifetime of the member's leaf node has ended.
+    Expired,
+}
+
+/// Member whose leaf node is close to or outside of its lifetime.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct LifetimeWarning {
+    pub member: Member,
+    pub kind: LifetimeWarningKind,
+}
+
+impl LifetimePolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the number of seconds by which the lifetime of a leaf node may be
+    /// missed.
+    pub fn with_grace_period(self, grace_period: u64) -> Self {
+        Self {
+            grace_period,
+            ..self
+        }
+    }
+
+    /// Set the number of seconds before the end of a lifetime from which
+    /// leaf nodes are reported as expiring.
+    pub fn with_warning_period(self, warning_period: u64) -> Self {
+        Self {
+            warning_period,
+            ..self
+        }
+    }
+
+    pub(crate) fn accepts(&self, lifetime: &Lifetime, time: MlsTime) -> bool {
+        lifetime.within_lifetime(time, self.grace_period)
+    }
+
+    pub(crate) fn warning(
+        &self,
+        lifetime: &Lifetime,
+        time: MlsTime,
+    ) -> Option<LifetimeWarningKind> {
+        let time = time.seconds_since_epoch();
+
+        if time < lifetime.not_before {
+            Some(LifetimeWarningKind::NotYetValid)
+        } else if time > lifetime.not_after {
+            Some(LifetimeWarningKind::Expired)
+        } else if lifetime.not_after - time <= self.warning_period {
+            Some(LifetimeWarningKind::ExpiringSoon)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{time::MlsTime, tree_kem::Lifetime};
+
+    use super::{LifetimePolicy, LifetimeWarningKind};
+
+    fn time(seconds: u64) -> MlsTime {
+        MlsTime::from_duration_since_epoch(Duration::from_secs(seconds))
+    }
+
+    #[test]
+    fn default_policy_only_accepts_lifetime() {
+        let lifetime = Lifetime::new(100, 200);
+        let policy = LifetimePolicy::new();
+
+        assert!(!policy.accepts(&lifetime, time(99)));
+        assert!(policy.accepts(&lifetime, time(100)));
+        assert!(policy.accepts(&lifetime, time(200)));
+        assert!(!policy.accepts(&lifetime, time(201)));
+    }
+
+    #[test]
+    fn grace_period_extends_lifetime() {
+        let lifetime = Lifetime::new(100, 200);
+        let policy = LifetimePolicy::new().with_grace_period(10);
+
+        assert!(!policy.accepts(&lifetime, time(89)));
+        assert!(policy.accepts(&lifetime, time(90)));
+        assert!(policy.accepts(&lifetime, time(210)));
+        assert!(!policy.accepts(&lifetime, time(211)));
+    }
+
+    #[test]
+    fn warnings_are_reported_around_lifetime() {
+        let lifetime = Lifetime::new(100, 200);
+        let policy = LifetimePolicy::new().with_warning_period(20);
+
+        let warnings = [99, 100, 179, 180, 200, 201].map(|t| policy.warning(&lifetime, time(t)));
+
+        assert_eq!(
+            warnings,
+            [
+                Some(LifetimeWarningKind::NotYetValid),
+                None,
+                None,
+                Some(LifetimeWarningKind::ExpiringSoon),
+                Some(LifetimeWarningKind::ExpiringSoon),
+                Some(LifetimeWarningKind::Expired),
+            ]
+        );
+    }
+}
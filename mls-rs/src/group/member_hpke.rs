@@ -0,0 +1,235 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{
+    crypto::{CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkeSecretKey},
+    error::IntoAnyError,
+};
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    tree_kem::{
+        hpke_encryption::{decrypt_with_label, encrypt_context_info, encrypt_with_label},
+        node::LeafIndex,
+    },
+    Group,
+};
+
+type GroupCipherSuiteProvider<C> =
+    <<C as ClientConfig>::CryptoProvider as CryptoProvider>::CipherSuiteProvider;
+
+const APPLICATION_LABEL_PREFIX: &[u8] = b"Application ";
+
+fn application_label(label: &[u8]) -> Vec<u8> {
+    [APPLICATION_LABEL_PREFIX, label].concat()
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Encrypt `plaintext` to the leaf node HPKE public key of the member at
+    /// `member_index` using the cipher suite of the group.
+    ///
+    /// Encryption follows `EncryptWithLabel` from RFC 9420 with `label` prefixed
+    /// by `"Application "`, so that the resulting ciphertext can never be
+    /// confused with one produced by the MLS protocol itself.
+    ///
+    /// # Warning
+    ///
+    /// Leaf node keys change whenever the recipient updates its leaf. The
+    /// recipient can only decrypt with [`Group::hpke_open`] while its leaf
+    /// node is the one that was used for encryption.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn hpke_seal_to_member(
+        &self,
+        member_index: u32,
+        label: &[u8],
+        context: &[u8],
+        plaintext: &[u8],
+    ) -> Result<HpkeCiphertext, MlsError> {
+        let leaf_node = self
+            .current_epoch_tree()
+            .get_leaf_node(LeafIndex(member_index))?;
+
+        encrypt_with_label(
+            &self.cipher_suite_provider,
+            &leaf_node.public_key,
+            &application_label(label),
+            context,
+            plaintext,
+        )
+        .await
+    }
+
+    /// Decrypt a ciphertext produced by [`Group::hpke_seal_to_member`] for
+    /// the current member using its leaf node HPKE secret key.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn hpke_open(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        ciphertext: &HpkeCiphertext,
+    ) -> Result<Vec<u8>, MlsError> {
+        let secret_key = self.leaf_secret_key()?;
+
+        let plaintext = decrypt_with_label(
+            &self.cipher_suite_provider,
+            secret_key,
+            &self.current_user_leaf_node()?.public_key,
+            &application_label(label),
+            context,
+            ciphertext,
+        )
+        .await?;
+
+        Ok(plaintext.to_vec())
+    }
+
+    /// Set up an HPKE sender context to the leaf node HPKE public key of the
+    /// member at `member_index`, allowing multiple messages to be encrypted.
+    ///
+    /// The context is bound to `label` and `context` in the same way as
+    /// [`Group::hpke_seal_to_member`]. The returned KEM output must be provided to
+    /// the recipient, which calls [`Group::hpke_setup_receiver`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn hpke_setup_sender_to_member(
+        &self,
+        member_index: u32,
+        label: &[u8],
+        context: &[u8],
+    ) -> Result<
+        (
+            Vec<u8>,
+            <GroupCipherSuiteProvider<C> as CipherSuiteProvider>::HpkeContextS,
+        ),
+        MlsError,
+    > {
+        let leaf_node = self
+            .current_epoch_tree()
+            .get_leaf_node(LeafIndex(member_index))?;
+
+        let info = encrypt_context_info(&application_label(label), context)?;
+
+        self.cipher_suite_provider
+            .hpke_setup_s(&leaf_node.public_key, &info)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+    }
+
+    /// Set up an HPKE receiver context for a `kem_output` generated by
+    /// [`Group::hpke_setup_sender_to_member`] for the current member.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn hpke_setup_receiver(
+        &self,
+        kem_output: &[u8],
+        label: &[u8],
+        context: &[u8],
+    ) -> Result<<GroupCipherSuiteProvider<C> as CipherSuiteProvider>::HpkeContextR, MlsError> {
+        let secret_key = self.leaf_secret_key()?;
+
+        let info = encrypt_context_info(&application_label(label), context)?;
+
+        self.cipher_suite_provider
+            .hpke_setup_r(
+                kem_output,
+                secret_key,
+                &self.current_user_leaf_node()?.public_key,
+                &info,
+            )
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+    }
+
+    fn leaf_secret_key(&self) -> Result<&HpkeSecretKey, MlsError> {
+        self.private_tree
+            .secret_keys
+            .first()
+            .and_then(Option::as_ref)
+            .ok_or(MlsError::InvalidTreeKemPrivateKey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mls_rs_core::crypto::{HpkeContextR, HpkeContextS};
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_n_member_group,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn member_can_open_data_sealed_to_its_leaf() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 3).await;
+
+        let ciphertext = groups[0]
+            .group
+            .hpke_seal_to_member(1, b"escrow", b"context", b"secret")
+            .await
+            .unwrap();
+
+        let plaintext = groups[1]
+            .group
+            .hpke_open(b"escrow", b"context", &ciphertext)
+            .await
+            .unwrap();
+
+        assert_eq!(plaintext, b"secret");
+
+        let res = groups[1]
+            .group
+            .hpke_open(b"other", b"context", &ciphertext)
+            .await;
+
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+
+        let res = groups[2]
+            .group
+            .hpke_open(b"escrow", b"context", &ciphertext)
+            .await;
+
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn member_can_set_up_hpke_context() {
+        let groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let (kem_output, mut sender) = groups[0]
+            .group
+            .hpke_setup_sender_to_member(1, b"stream", b"")
+            .await
+            .unwrap();
+
+        let mut receiver = groups[1]
+            .group
+            .hpke_setup_receiver(&kem_output, b"stream", b"")
+            .await
+            .unwrap();
+
+        for data in [b"first".to_vec(), b"second".to_vec()] {
+            let ciphertext = sender.seal(None, &data).await.unwrap();
+            let plaintext = receiver.open(None, &ciphertext).await.unwrap();
+            assert_eq!(plaintext, data);
+        }
+
+        let sender_secret = sender.export(b"exporter", 32).await.unwrap();
+        let receiver_secret = receiver.export(b"exporter", 32).await.unwrap();
+        assert_eq!(sender_secret, receiver_secret);
+
+        let res = groups[0]
+            .group
+            .hpke_seal_to_member(5, b"stream", b"", &[0])
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidNodeIndex(_)));
+    }
+}
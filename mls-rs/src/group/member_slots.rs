@@ -0,0 +1,683 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    error::IntoAnyError,
+    extension::{ExtensionList, ExtensionType},
+    group::ProposalType,
+    identity::SigningIdentity,
+};
+
+use crate::{
+    client::MlsError,
+    extension::MemberSlotsExt,
+    group::{
+        mls_rules::{
+            CommitDirection, CommitOptions, CommitSource, DefaultMlsRules, EncryptionOptions,
+            LeafPlacement, TreeTruncation,
+        },
+        proposal::{CustomProposal, MlsCustomProposal},
+        proposal_filter::{ProposalBundle, ProposalInfo, ProposalSource},
+        GroupContext, Roster, Sender,
+    },
+    MlsRules,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::proposal::Proposal;
+
+#[cfg(feature = "private_message")]
+use crate::group::mls_rules::MessageDecision;
+
+#[cfg(feature = "private_message")]
+use mls_rs_core::group::Member;
+
+/// Proposal setting or clearing a slot of its sender in the
+/// [`MemberSlotsExt`] group context extension.
+///
+/// The proposal must be sent by a member, either by reference with
+/// [`Group::propose_custom`](crate::Group::propose_custom) or by value with
+/// [`CommitBuilder::custom_proposal`](crate::group::CommitBuilder::custom_proposal).
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+pub struct SetMemberSlotProposal {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    pub key: Vec<u8>,
+    /// New value of the slot. `None` clears the slot.
+    pub value: Option<Vec<u8>>,
+}
+
+impl SetMemberSlotProposal {
+    pub fn set(key: Vec<u8>, value: Vec<u8>) -> Self {
+        Self {
+            key,
+            value: Some(value),
+        }
+    }
+
+    pub fn clear(key: Vec<u8>) -> Self {
+        Self { key, value: None }
+    }
+}
+
+impl MlsCustomProposal for SetMemberSlotProposal {
+    fn proposal_type() -> ProposalType {
+        ProposalType::SET_MEMBER_SLOT
+    }
+}
+
+/// Limits enforced by [`MemberSlotRules`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemberSlotLimits {
+    pub max_key_size: usize,
+    pub max_value_size: usize,
+    pub max_slots_per_member: usize,
+}
+
+impl Default for MemberSlotLimits {
+    fn default() -> Self {
+        Self {
+            max_key_size: 32,
+            max_value_size: 256,
+            max_slots_per_member: 8,
+        }
+    }
+}
+
+impl MemberSlotLimits {
+    pub fn new(max_key_size: usize, max_value_size: usize, max_slots_per_member: usize) -> Self {
+        Self {
+            max_key_size,
+            max_value_size,
+            max_slots_per_member,
+        }
+    }
+}
+
+/// [`MlsRules`] replicating the [`MemberSlotsExt`] group context extension,
+/// on top of the `inner` rules.
+///
+/// When a commit changes slots, the committer writes the resulting slots to
+/// the group context with a group context extensions proposal sent by value
+/// in the commit, or merges them into the group context extensions proposal
+/// of the commit. Receivers recompute the slots and reject the commit if they
+/// differ from the committed extension. The following rules apply:
+///
+/// * A member can only change its own slots, and only if the group context
+///   contains [`MemberSlotsExt`].
+/// * Proposals are applied in the order of the commit, so the last proposal
+///   for a slot wins.
+/// * Slots of members removed by the commit, and of leaves that are already
+///   blank, are cleared.
+/// * Slots can not be changed by a group context extensions proposal. If such a
+///   proposal keeps [`MemberSlotsExt`], its slots must be the result of the
+///   rules above. If it removes the extension, all slots are dropped.
+/// * Proposals exceeding the [`MemberSlotLimits`] are invalid.
+///
+/// Invalid proposals received by reference are left out when preparing a
+/// commit, and any other invalid proposal fails the commit with
+/// [`MlsError::InvalidMemberSlotProposal`]. Slots are not changed by external
+/// commits. All members must use these rules with the same limits.
+#[derive(Clone, Debug)]
+pub struct MemberSlotRules<R = DefaultMlsRules> {
+    inner: R,
+    limits: MemberSlotLimits,
+}
+
+impl<R> MemberSlotRules<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            limits: Default::default(),
+        }
+    }
+
+    pub fn with_limits(self, limits: MemberSlotLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    fn apply_proposal(
+        &self,
+        slots: &mut MemberSlotsExt,
+        proposal: &ProposalInfo<CustomProposal>,
+    ) -> Result<(), MlsError> {
+        let Sender::Member(leaf_index) = proposal.sender else {
+            return Err(MlsError::InvalidMemberSlotProposal(
+                "sender is not a member",
+            ));
+        };
+
+        let SetMemberSlotProposal { key, value } =
+            SetMemberSlotProposal::from_custom_proposal(&proposal.proposal)?;
+
+        if key.len() > self.limits.max_key_size {
+            return Err(MlsError::InvalidMemberSlotProposal("key too large"));
+        }
+
+        if value.as_ref().map_or(0, Vec::len) > self.limits.max_value_size {
+            return Err(MlsError::InvalidMemberSlotProposal("value too large"));
+        }
+
+        slots.set(leaf_index, key, value);
+
+        if slots.member_slots(leaf_index).count() > self.limits.max_slots_per_member {
+            return Err(MlsError::InvalidMemberSlotProposal("too many slots"));
+        }
+
+        Ok(())
+    }
+
+    fn update_slots(
+        &self,
+        direction: CommitDirection,
+        committer: u32,
+        current_roster: &Roster,
+        extension_list: &ExtensionList,
+        mut proposals: ProposalBundle,
+    ) -> Result<ProposalBundle, MlsError> {
+        let current = extension_list.get_as::<MemberSlotsExt>()?;
+        let mut slots = current.clone().unwrap_or_default();
+        let mut index = 0;
+
+        while let Some(proposal) = proposals.custom_proposals.get(index) {
+            if proposal.proposal.proposal_type() != ProposalType::SET_MEMBER_SLOT {
+                index += 1;
+                continue;
+            }
+
+            let mut updated = slots.clone();
+
+            let res = match current {
+                Some(_) => self.apply_proposal(&mut updated, proposal),
+                None => Err(MlsError::InvalidMemberSlotProposal(
+                    "member slots not enabled",
+                )),
+            };
+
+            match res {
+                Ok(()) => {
+                    slots = updated;
+                    index += 1;
+                }
+                Err(_) if direction == CommitDirection::Send && proposal.is_by_reference() => {
+                    proposals.custom_proposals.remove(index);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let removed = proposals
+            .remove_proposals()
+            .iter()
+            .map(|p| p.proposal.to_remove())
+            .collect::<Vec<_>>();
+
+        slots.slots.retain(|slot| {
+            !removed.contains(&slot.leaf_index) && !current_roster.is_blank(slot.leaf_index)
+        });
+
+        if direction == CommitDirection::Send {
+            Self::write_slots(committer, extension_list, current, slots, &mut proposals)?;
+        } else {
+            Self::check_slots(current, slots, &proposals)?;
+        }
+
+        Ok(proposals)
+    }
+
+    fn write_slots(
+        committer: u32,
+        extension_list: &ExtensionList,
+        current: Option<MemberSlotsExt>,
+        slots: MemberSlotsExt,
+        proposals: &mut ProposalBundle,
+    ) -> Result<(), MlsError> {
+        if let Some(gce) = proposals.group_context_extensions.first_mut() {
+            if !gce.proposal.has_extension(ExtensionType::MEMBER_SLOTS) {
+                return Ok(());
+            }
+
+            if !gce.is_by_reference() {
+                return gce.proposal.set_from(slots).map_err(Into::into);
+            }
+
+            if gce.proposal.get_as::<MemberSlotsExt>()?.as_ref() == Some(&slots) {
+                return Ok(());
+            }
+
+            // A proposal by reference can not be rewritten, so it is left out
+            // like any other invalid proposal.
+            proposals.group_context_extensions.clear();
+        }
+
+        if current
+            .as_ref()
+            .filter(|current| **current != slots)
+            .is_some()
+        {
+            let mut new_extensions = extension_list.clone();
+            new_extensions.set_from(slots)?;
+
+            proposals.group_context_extensions.push(ProposalInfo {
+                proposal: new_extensions,
+                sender: Sender::Member(committer),
+                source: ProposalSource::ByValue,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_slots(
+        current: Option<MemberSlotsExt>,
+        slots: MemberSlotsExt,
+        proposals: &ProposalBundle,
+    ) -> Result<(), MlsError> {
+        let committed = match proposals.group_context_extensions.first() {
+            Some(gce) if gce.proposal.has_extension(ExtensionType::MEMBER_SLOTS) => {
+                gce.proposal.get_as::<MemberSlotsExt>()?
+            }
+            Some(_) => return Ok(()),
+            None => current,
+        };
+
+        match committed {
+            Some(committed) if committed != slots => Err(MlsError::InvalidMemberSlotProposal(
+                "committed slots do not match proposals",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for MemberSlotRules {
+    fn default() -> Self {
+        Self::new(DefaultMlsRules::new())
+    }
+}
+
+fn inner_error<E: IntoAnyError>(e: E) -> MlsError {
+    MlsError::MlsRulesError(e.into_any_error())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<R: MlsRules> MlsRules for MemberSlotRules<R> {
+    type Error = MlsError;
+
+    async fn filter_proposals(
+        &self,
+        direction: CommitDirection,
+        source: CommitSource,
+        current_roster: &Roster,
+        extension_list: &ExtensionList,
+        proposals: ProposalBundle,
+    ) -> Result<ProposalBundle, Self::Error> {
+        let proposals = self
+            .inner
+            .filter_proposals(
+                direction,
+                source.clone(),
+                current_roster,
+                extension_list,
+                proposals,
+            )
+            .await
+            .map_err(inner_error)?;
+
+        match source {
+            CommitSource::ExistingMember(committer) => self.update_slots(
+                direction,
+                committer.index,
+                current_roster,
+                extension_list,
+                proposals,
+            ),
+            CommitSource::NewMember(_) => Ok(proposals),
+        }
+    }
+
+    fn commit_options(
+        &self,
+        new_roster: &Roster,
+        new_extension_list: &ExtensionList,
+        proposals: &ProposalBundle,
+    ) -> Result<CommitOptions, Self::Error> {
+        self.inner
+            .commit_options(new_roster, new_extension_list, proposals)
+            .map_err(inner_error)
+    }
+
+    fn encryption_options(
+        &self,
+        current_roster: &Roster,
+        current_extension_list: &ExtensionList,
+    ) -> Result<EncryptionOptions, Self::Error> {
+        self.inner
+            .encryption_options(current_roster, current_extension_list)
+            .map_err(inner_error)
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    async fn revalidate_proposal(
+        &self,
+        proposal: &ProposalInfo<Proposal>,
+        new_roster: &Roster,
+        new_extension_list: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        self.inner
+            .revalidate_proposal(proposal, new_roster, new_extension_list)
+            .await
+            .map_err(inner_error)
+    }
+
+    fn leaf_placement(
+        &self,
+        roster: &Roster,
+        new_member: &SigningIdentity,
+    ) -> Result<LeafPlacement, Self::Error> {
+        self.inner
+            .leaf_placement(roster, new_member)
+            .map_err(inner_error)
+    }
+
+    fn tree_truncation(
+        &self,
+        group_extensions: &ExtensionList,
+    ) -> Result<TreeTruncation, Self::Error> {
+        self.inner
+            .tree_truncation(group_extensions)
+            .map_err(inner_error)
+    }
+
+    fn group_info_extensions(
+        &self,
+        group_context: &GroupContext,
+    ) -> Result<ExtensionList, Self::Error> {
+        self.inner
+            .group_info_extensions(group_context)
+            .map_err(inner_error)
+    }
+
+    #[cfg(feature = "private_message")]
+    fn authorize_application_message(
+        &self,
+        sender: &Member,
+        authenticated_data: &[u8],
+    ) -> Result<MessageDecision, Self::Error> {
+        self.inner
+            .authorize_application_message(sender, authenticated_data)
+            .map_err(inner_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use assert_matches::assert_matches;
+    use mls_rs_core::{
+        extension::{ExtensionList, ExtensionType},
+        group::ProposalType,
+    };
+
+    use crate::{
+        client::{
+            test_utils::{TestClientBuilder, TEST_CIPHER_SUITE},
+            MlsError,
+        },
+        client_config::ClientConfig,
+        extension::MemberSlotsExt,
+        group::proposal::MlsCustomProposal,
+        Client, Group,
+    };
+
+    use super::{MemberSlotLimits, MemberSlotRules, SetMemberSlotProposal};
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn slot_client(name: &str) -> Client<impl ClientConfig> {
+        TestClientBuilder::new_for_test()
+            .with_random_signing_identity(name, TEST_CIPHER_SUITE)
+            .await
+            .mls_rules(MemberSlotRules::default().with_limits(MemberSlotLimits::new(4, 4, 2)))
+            .extension_type(ExtensionType::MEMBER_SLOTS)
+            .custom_proposal_type(ProposalType::SET_MEMBER_SLOT)
+            .build_unchecked()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn slot_groups<C: ClientConfig>(
+        alice: &Client<C>,
+        bob: &Client<C>,
+        extensions: ExtensionList,
+    ) -> (Group<C>, Group<C>) {
+        let mut alice_group = alice.create_group(extensions).await.unwrap();
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        (alice_group, bob_group)
+    }
+
+    fn enabled() -> ExtensionList {
+        let mut extensions = ExtensionList::new();
+        extensions.set_from(MemberSlotsExt::new()).unwrap();
+        extensions
+    }
+
+    fn slots<C: ClientConfig>(group: &Group<C>) -> MemberSlotsExt {
+        group
+            .context()
+            .extensions
+            .get_as::<MemberSlotsExt>()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn set(key: &[u8], value: &[u8]) -> crate::group::proposal::CustomProposal {
+        SetMemberSlotProposal::set(key.to_vec(), value.to_vec())
+            .to_custom_proposal()
+            .unwrap()
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_set_their_own_slots() {
+        let (alice, bob) = (slot_client("alice").await, slot_client("bob").await);
+        let (mut alice_group, mut bob_group) = slot_groups(&alice, &bob, enabled()).await;
+
+        let proposal = bob_group
+            .propose_custom(set(b"k", b"b"), vec![])
+            .await
+            .unwrap();
+        alice_group
+            .process_incoming_message(proposal)
+            .await
+            .unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .custom_proposal(set(b"k", b"a1"))
+            .custom_proposal(set(b"k", b"a2"))
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+        bob_group
+            .process_incoming_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        let alice_slots = slots(&alice_group);
+
+        assert_eq!(alice_slots, slots(&bob_group));
+        assert_eq!(alice_slots.get(0, b"k"), Some(&b"a2"[..]));
+        assert_eq!(alice_slots.get(1, b"k"), Some(&b"b"[..]));
+
+        let proposal = SetMemberSlotProposal::clear(b"k".to_vec())
+            .to_custom_proposal()
+            .unwrap();
+
+        let commit = bob_group
+            .commit_builder()
+            .custom_proposal(proposal)
+            .build()
+            .await
+            .unwrap();
+
+        bob_group.apply_pending_commit().await.unwrap();
+        alice_group
+            .process_incoming_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        assert_eq!(slots(&alice_group).get(1, b"k"), None);
+        assert_eq!(slots(&alice_group), slots(&bob_group));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn removing_a_member_clears_its_slots() {
+        let (alice, bob) = (slot_client("alice").await, slot_client("bob").await);
+        let (mut alice_group, mut bob_group) = slot_groups(&alice, &bob, enabled()).await;
+
+        let commit = bob_group
+            .commit_builder()
+            .custom_proposal(set(b"k", b"b"))
+            .build()
+            .await
+            .unwrap();
+
+        bob_group.apply_pending_commit().await.unwrap();
+        alice_group
+            .process_incoming_message(commit.commit_message)
+            .await
+            .unwrap();
+
+        assert_eq!(slots(&alice_group).member_slots(1).count(), 1);
+
+        alice_group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(slots(&alice_group), MemberSlotsExt::new());
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn invalid_slot_proposals_are_rejected() {
+        let (alice, bob) = (slot_client("alice").await, slot_client("bob").await);
+        let (mut alice_group, mut bob_group) = slot_groups(&alice, &bob, enabled()).await;
+
+        for proposals in [
+            vec![set(b"k", b"large value")],
+            vec![set(b"large key", b"v")],
+            vec![set(b"k1", b"v"), set(b"k2", b"v"), set(b"k3", b"v")],
+        ] {
+            let res = proposals
+                .into_iter()
+                .fold(alice_group.commit_builder(), |builder, proposal| {
+                    builder.custom_proposal(proposal)
+                })
+                .build()
+                .await
+                .map(|_| ());
+
+            assert_matches!(res, Err(MlsError::MlsRulesError(_)));
+        }
+
+        let proposal = bob_group
+            .propose_custom(set(b"k", b"large value"), vec![])
+            .await
+            .unwrap();
+
+        alice_group
+            .process_incoming_message(proposal)
+            .await
+            .unwrap();
+
+        alice_group.commit(Vec::new()).await.unwrap();
+        alice_group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(slots(&alice_group), MemberSlotsExt::new());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn slot_changes_must_be_committed() {
+        let alice = TestClientBuilder::new_for_test()
+            .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
+            .await
+            .extension_type(ExtensionType::MEMBER_SLOTS)
+            .custom_proposal_type(ProposalType::SET_MEMBER_SLOT)
+            .build_unchecked();
+
+        let bob = slot_client("bob").await;
+
+        let mut alice_group = alice.create_group(enabled()).await.unwrap();
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice_group.apply_pending_commit().await.unwrap();
+
+        let (mut bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        let commit = alice_group
+            .commit_builder()
+            .custom_proposal(set(b"k", b"v"))
+            .build()
+            .await
+            .unwrap();
+
+        let res = bob_group
+            .process_incoming_message(commit.commit_message)
+            .await;
+
+        assert_matches!(res, Err(MlsError::MlsRulesError(_)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn slots_must_be_enabled() {
+        let (alice, bob) = (slot_client("alice").await, slot_client("bob").await);
+        let (mut alice_group, _) = slot_groups(&alice, &bob, ExtensionList::new()).await;
+
+        let res = alice_group
+            .commit_builder()
+            .custom_proposal(set(b"k", b"v"))
+            .build()
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::MlsRulesError(_)));
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{crypto::CipherSuiteProvider, error::IntoAnyError};
+use zeroize::Zeroizing;
+
+use crate::{cipher_suite::CipherSuite, client::MlsError, client_config::ClientConfig, Group};
+
+use super::{cipher_suite_provider, key_schedule::kdf_expand_with_label, snapshot::Snapshot};
+
+const EXPORT_KEY_LABEL: &[u8] = b"membership export key";
+const EXPORT_NONCE_LABEL: &[u8] = b"membership export nonce";
+
+#[derive(MlsSize, MlsEncode)]
+struct ExportContext<'a> {
+    cipher_suite: CipherSuite,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch: u64,
+}
+
+/// Private state of a group member created by [`Group::export_membership`],
+/// encrypted under a key provided by the application.
+///
+/// The membership is restored in another process with
+/// [`Client::import_membership`](crate::Client::import_membership), without
+/// the member being added to the group again with a Welcome message.
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ExportedMembership {
+    cipher_suite: CipherSuite,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: Vec<u8>,
+    epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    salt: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    ciphertext: Vec<u8>,
+}
+
+impl ExportedMembership {
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    pub fn group_id(&self) -> &[u8] {
+        &self.group_id
+    }
+
+    /// Epoch of the group at the time of the export.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Deserialize an exported membership.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize an exported membership.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    fn context(&self) -> ExportContext<'_> {
+        ExportContext {
+            cipher_suite: self.cipher_suite,
+            group_id: &self.group_id,
+            epoch: self.epoch,
+        }
+    }
+}
+
+struct ExportKey {
+    key: Zeroizing<Vec<u8>>,
+    nonce: Zeroizing<Vec<u8>>,
+}
+
+impl ExportKey {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn derive<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        export_key: &[u8],
+        salt: &[u8],
+        context: &[u8],
+    ) -> Result<Self, MlsError> {
+        let prk = cipher_suite_provider
+            .kdf_extract(salt, export_key)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let key = kdf_expand_with_label(
+            cipher_suite_provider,
+            &prk,
+            EXPORT_KEY_LABEL,
+            context,
+            Some(cipher_suite_provider.aead_key_size()),
+        )
+        .await?;
+
+        let nonce = kdf_expand_with_label(
+            cipher_suite_provider,
+            &prk,
+            EXPORT_NONCE_LABEL,
+            context,
+            Some(cipher_suite_provider.aead_nonce_size()),
+        )
+        .await?;
+
+        Ok(Self { key, nonce })
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Export the private state of the current member, encrypted under
+    /// `export_key`, so that another process of the same client can continue
+    /// using the membership.
+    ///
+    /// The export contains the private keys of the member in the tree, its
+    /// signature key and the key schedule of the current epoch. The
+    /// `export_key` should be a uniformly random secret shared by the
+    /// processes, e.g. derived from a device key.
+    ///
+    /// # Warning
+    ///
+    /// The exported and the imported groups share the leaf of the member and
+    /// derive the same message keys. The application must make sure that only
+    /// one copy at a time encrypts messages, proposes or commits. Other copies
+    /// may only process incoming messages or be reloaded from the shared
+    /// storage.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_membership(
+        &self,
+        export_key: &[u8],
+    ) -> Result<ExportedMembership, MlsError> {
+        let cs = &self.cipher_suite_provider;
+
+        let salt = cs
+            .random_bytes_vec(cs.kdf_extract_size())
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let mut exported = ExportedMembership {
+            cipher_suite: self.context().cipher_suite,
+            group_id: self.context().group_id.clone(),
+            epoch: self.context().epoch,
+            salt,
+            ciphertext: Vec::new(),
+        };
+
+        let context = exported.context().mls_encode_to_vec()?;
+
+        let export_key = ExportKey::derive(cs, export_key, &exported.salt, &context).await?;
+
+        let snapshot = Zeroizing::new(self.snapshot().mls_encode_to_vec()?);
+
+        exported.ciphertext = cs
+            .aead_seal(
+                &export_key.key,
+                &snapshot,
+                Some(&context),
+                &export_key.nonce,
+            )
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        Ok(exported)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn import_membership(
+        config: C,
+        exported: &ExportedMembership,
+        export_key: &[u8],
+    ) -> Result<Self, MlsError> {
+        let cs = cipher_suite_provider(config.crypto_provider(), exported.cipher_suite)?;
+        let context = exported.context().mls_encode_to_vec()?;
+
+        let export_key = ExportKey::derive(&cs, export_key, &exported.salt, &context).await?;
+
+        let snapshot = cs
+            .aead_open(
+                &export_key.key,
+                &exported.ciphertext,
+                Some(&context),
+                &export_key.nonce,
+            )
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let snapshot = Snapshot::from_bytes(&snapshot)?;
+        let mut group = Group::from_snapshot(config, snapshot).await?;
+        group.write_to_storage().await?;
+
+        Ok(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TestClientBuilder, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group,
+        storage_provider::in_memory::InMemoryGroupStateStorage,
+    };
+
+    #[cfg(feature = "private_message")]
+    use crate::group::ReceivedMessage;
+
+    use super::ExportedMembership;
+
+    const EXPORT_KEY: &[u8] = &[42; 32];
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn imported_membership_continues_in_group() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let exported = bob.group.export_membership(EXPORT_KEY).await.unwrap();
+        let exported = ExportedMembership::from_bytes(&exported.to_bytes().unwrap()).unwrap();
+
+        // Another process of bob using its own storage
+        let storage = InMemoryGroupStateStorage::new();
+
+        let other_process = TestClientBuilder::new_for_test()
+            .group_state_storage(storage.clone())
+            .build_unchecked();
+
+        let mut bob_copy = other_process
+            .import_membership(&exported, EXPORT_KEY)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            bob_copy.current_member_index(),
+            bob.group.current_member_index()
+        );
+        assert!(storage
+            .stored_groups()
+            .contains(&bob_copy.group_id().to_vec()));
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+
+        bob_copy
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+
+        bob.process_message(commit).await.unwrap();
+
+        #[cfg(feature = "private_message")]
+        {
+            let message = alice
+                .group
+                .encrypt_application_message(b"hello", vec![])
+                .await
+                .unwrap();
+
+            let received = bob_copy.process_incoming_message(message).await.unwrap();
+            assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello");
+        }
+
+        // The copy can send, provided the original does not
+        let commit = bob_copy.commit(vec![]).await.unwrap().commit_message;
+        bob_copy.apply_pending_commit().await.unwrap();
+        alice.process_message(commit).await.unwrap();
+
+        assert_eq!(alice.group.current_epoch(), bob_copy.current_epoch());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn membership_can_not_be_imported_with_wrong_key() {
+        let alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let exported = alice.group.export_membership(EXPORT_KEY).await.unwrap();
+
+        let res = TestClientBuilder::new_for_test()
+            .build_unchecked()
+            .import_membership(&exported, &[0; 32])
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{client::MlsError, group::framing::MlsMessage, time::MlsTime};
+
+/// Limits of the buffer used by
+/// [`Group::process_incoming_message_buffered`](crate::Group::process_incoming_message_buffered)
+/// to hold messages that can not be processed yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MessageBufferLimits {
+    /// Maximum number of buffered messages.
+    pub max_messages: usize,
+    /// Maximum number of epochs that a buffered message may be ahead of the
+    /// current epoch of the group.
+    pub max_epochs_ahead: u64,
+    /// Number of seconds after which a buffered message is discarded.
+    pub max_age: u64,
+}
+
+impl Default for MessageBufferLimits {
+    fn default() -> Self {
+        Self {
+            max_messages: 64,
+            max_epochs_ahead: 1,
+            max_age: 300,
+        }
+    }
+}
+
+impl MessageBufferLimits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the maximum number of buffered messages.
+    pub fn with_max_messages(self, max_messages: usize) -> Self {
+        Self {
+            max_messages,
+            ..self
+        }
+    }
+
+    /// Set the maximum number of epochs that a buffered message may be ahead
+    /// of the current epoch.
+    pub fn with_max_epochs_ahead(self, max_epochs_ahead: u64) -> Self {
+        Self {
+            max_epochs_ahead,
+            ..self
+        }
+    }
+
+    /// Set the number of seconds after which a buffered message is discarded.
+    pub fn with_max_age(self, max_age: u64) -> Self {
+        Self { max_age, ..self }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct BufferedMessage {
+    pub(crate) message: MlsMessage,
+    pub(crate) epoch: u64,
+    received_at: MlsTime,
+}
+
+/// Messages waiting for the group to reach their epoch or for earlier
+/// generations of their sender to arrive. The buffer lives in memory only and
+/// is not part of the group state written to storage.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MessageBuffer {
+    messages: VecDeque<BufferedMessage>,
+}
+
+impl MessageBuffer {
+    pub(crate) fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        message: MlsMessage,
+        epoch: u64,
+        time: MlsTime,
+        limits: &MessageBufferLimits,
+    ) -> Result<(), MlsError> {
+        self.expire(time, limits);
+
+        if self.messages.len() >= limits.max_messages {
+            return Err(MlsError::MessageBufferFull);
+        }
+
+        self.messages.push_back(BufferedMessage {
+            message,
+            epoch,
+            received_at: time,
+        });
+
+        Ok(())
+    }
+
+    /// Put back a message taken with [`Self::take_ready`], keeping the time
+    /// it was first received.
+    pub(crate) fn restore(&mut self, message: BufferedMessage) {
+        self.messages.push_back(message);
+    }
+
+    /// Remove and return, in the order they were received, all messages that
+    /// are not ahead of `epoch`.
+    pub(crate) fn take_ready(&mut self, epoch: u64) -> Vec<BufferedMessage> {
+        let (ready, waiting): (Vec<_>, Vec<_>) =
+            self.messages.drain(..).partition(|m| m.epoch <= epoch);
+
+        self.messages = waiting.into();
+
+        ready
+    }
+
+    pub(crate) fn expire(&mut self, time: MlsTime, limits: &MessageBufferLimits) {
+        let time = time.seconds_since_epoch();
+
+        self.messages.retain(|m| {
+            m.received_at
+                .seconds_since_epoch()
+                .saturating_add(limits.max_age)
+                >= time
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use core::time::Duration;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client::MlsError,
+        group::{test_utils::test_group, ReceivedMessage},
+        time::MlsTime,
+    };
+
+    use super::MessageBufferLimits;
+
+    fn time(seconds: u64) -> MlsTime {
+        MlsTime::from_duration_since_epoch(Duration::from_secs(seconds))
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn messages_from_next_epoch_are_processed_after_commit() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let first = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let second = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let now = time(100);
+
+        let received = bob
+            .group
+            .process_incoming_message_buffered(second, now)
+            .await
+            .unwrap();
+
+        assert!(received.is_empty());
+        assert_eq!(bob.group.buffered_message_count(), 1);
+
+        let received = bob
+            .group
+            .process_incoming_message_buffered(first, now)
+            .await
+            .unwrap();
+
+        assert_eq!(received.len(), 2);
+        assert!(received
+            .iter()
+            .all(|r| matches!(r, ReceivedMessage::Commit(_))));
+
+        assert_eq!(bob.group.buffered_message_count(), 0);
+        assert_eq!(bob.group.current_epoch(), alice.group.current_epoch());
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_message_sent_after_commit_is_buffered() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let message = alice
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let now = time(100);
+
+        let received = bob
+            .group
+            .process_incoming_message_buffered(message, now)
+            .await
+            .unwrap();
+
+        assert!(received.is_empty());
+
+        let received = bob
+            .group
+            .process_incoming_message_buffered(commit, now)
+            .await
+            .unwrap();
+
+        assert_matches!(&received[..], [ReceivedMessage::Commit(_), ReceivedMessage::ApplicationMessage(m)] if m.data() == b"hello");
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn buffer_limits_are_enforced() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        bob.group.config.0.settings.message_buffer_limits = MessageBufferLimits::new()
+            .with_max_messages(1)
+            .with_max_age(10);
+
+        let mut commits = vec![];
+
+        for _ in 0..3 {
+            commits.push(alice.group.commit(vec![]).await.unwrap().commit_message);
+            alice.group.apply_pending_commit().await.unwrap();
+        }
+
+        let res = bob
+            .group
+            .process_incoming_message_buffered(commits[2].clone(), time(100))
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+
+        bob.group
+            .process_incoming_message_buffered(commits[1].clone(), time(100))
+            .await
+            .unwrap();
+
+        let res = bob
+            .group
+            .process_incoming_message_buffered(commits[1].clone(), time(100))
+            .await;
+
+        assert_matches!(res, Err(MlsError::MessageBufferFull));
+
+        // The buffered commit expired
+        let received = bob
+            .group
+            .process_incoming_message_buffered(commits[0].clone(), time(111))
+            .await
+            .unwrap();
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(bob.group.buffered_message_count(), 0);
+    }
+}
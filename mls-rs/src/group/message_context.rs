@@ -0,0 +1,143 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use core::fmt::{self, Debug};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use mls_rs_codec::MlsEncode;
+use mls_rs_core::crypto::CipherSuiteProvider;
+
+use crate::client::MlsError;
+
+use super::{
+    framing::{MlsMessage, MlsMessagePayload, WireFormat},
+    ContentType, Sender,
+};
+
+/// Description of an inbound message that could not be processed, attached
+/// to [`MlsError::MessageProcessingFailed`] if enabled with
+/// [`ClientBuilder::message_error_context`](crate::client_builder::ClientBuilder::message_error_context).
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MessageContext {
+    pub wire_format: WireFormat,
+    /// Content type of a public or private message.
+    pub content_type: Option<ContentType>,
+    /// Epoch of the message as stated in the message.
+    pub epoch: Option<u64>,
+    /// Sender of a public message. The sender of a private message is only
+    /// known after its decryption.
+    pub sender: Option<Sender>,
+    /// Hash of the encoded message computed with the hash function of the
+    /// cipher suite of the group, used to find the message in logs.
+    pub message_hash: Vec<u8>,
+}
+
+impl Debug for MessageContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageContext")
+            .field("wire_format", &self.wire_format)
+            .field("content_type", &self.content_type)
+            .field("epoch", &self.epoch)
+            .field("sender", &self.sender)
+            .field(
+                "message_hash",
+                &mls_rs_core::debug::pretty_bytes(&self.message_hash),
+            )
+            .finish()
+    }
+}
+
+impl MessageContext {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn new<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        message: &MlsMessage,
+    ) -> Self {
+        let (content_type, sender) = match &message.payload {
+            MlsMessagePayload::Plain(plaintext) => (
+                Some(ContentType::from(&plaintext.content.content)),
+                Some(plaintext.content.sender),
+            ),
+            #[cfg(feature = "private_message")]
+            MlsMessagePayload::Cipher(ciphertext) => (Some(ciphertext.content_type), None),
+            _ => (None, None),
+        };
+
+        // The context is best effort and must not hide the original error
+        let message_hash = match message.mls_encode_to_vec() {
+            Ok(bytes) => cipher_suite_provider.hash(&bytes).await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            wire_format: message.wire_format(),
+            content_type,
+            epoch: message.epoch(),
+            sender,
+            message_hash,
+        }
+    }
+
+    pub(crate) fn wrap(self, error: MlsError) -> MlsError {
+        MlsError::MessageProcessingFailed(Box::new(self), Box::new(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        crypto::test_utils::test_cipher_suite_provider,
+        group::{framing::WireFormat, test_utils::test_group, ContentType, Sender},
+        CipherSuiteProvider,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn processing_errors_carry_message_context() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |c| c.0.settings.message_error_context = true)
+            .await
+            .unwrap();
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.group
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+
+        // Processing the same commit again fails
+        let res = bob.group.process_incoming_message(commit.clone()).await;
+
+        let message_hash = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .hash(&commit.to_bytes().unwrap())
+            .await
+            .unwrap();
+
+        assert_matches!(
+            res,
+            Err(MlsError::MessageProcessingFailed(context, error))
+                if context.wire_format == WireFormat::PublicMessage
+                    && context.content_type == Some(ContentType::Commit)
+                    && context.epoch == Some(1)
+                    && context.sender == Some(Sender::Member(0))
+                    && context.message_hash == message_hash
+                    && matches!(*error, MlsError::InvalidEpoch)
+        );
+
+        // Without the option, errors are returned as is
+        let res = alice.group.process_incoming_message(commit).await;
+        assert_matches!(res, Err(MlsError::InvalidEpoch));
+    }
+}
@@ -5,6 +5,7 @@
 use super::{
     commit_sender,
     confirmation_tag::ConfirmationTag,
+    decode_limits::DecodeLimits,
     framing::{
         ApplicationData, Content, ContentType, MlsMessage, MlsMessagePayload, PublicMessage, Sender,
     },
@@ -13,7 +14,8 @@ use super::{
     proposal_filter::ProposalBundle,
     state::GroupState,
     transcript_hash::InterimTranscriptHash,
-    transcript_hashes, validate_group_info_member, GroupContext, GroupInfo, Welcome,
+    transcript_hashes, validate_group_info_member, GroupContext, GroupInfo, LifetimePolicy,
+    OwnMessageEcho, Welcome,
 };
 use crate::{
     client::MlsError,
@@ -36,7 +38,7 @@ use mls_rs_core::{
 };
 
 #[cfg(feature = "by_ref_proposal")]
-use super::proposal_ref::ProposalRef;
+use super::{proposal_cache::proposal_expiry, proposal_ref::ProposalRef};
 
 #[cfg(not(feature = "by_ref_proposal"))]
 use crate::group::proposal_cache::resolve_for_commit;
@@ -54,17 +56,32 @@ use mls_rs_core::{
 };
 
 #[cfg(all(feature = "state_update", feature = "psk"))]
-use mls_rs_core::psk::ExternalPskId;
+use crate::psk::{ExternalPskId, PskUsage};
 
 #[cfg(feature = "state_update")]
 use crate::tree_kem::UpdatePath;
 
 #[cfg(feature = "state_update")]
-use super::{member_from_key_package, member_from_leaf_node};
+use super::member_from_key_package;
+
+#[cfg(any(feature = "state_update", feature = "private_message"))]
+use super::member_from_leaf_node;
+
+#[cfg(feature = "private_message")]
+use super::mls_rules::MessageDecision;
+
+#[cfg(feature = "private_message")]
+use mls_rs_core::error::IntoAnyError;
 
 #[cfg(all(feature = "state_update", feature = "custom_proposal"))]
 use super::proposal::CustomProposal;
 
+#[cfg(feature = "custom_proposal")]
+use super::proposal::CustomProposalMetadata;
+
+#[cfg(feature = "custom_proposal")]
+use super::proposal::ProposalType;
+
 #[cfg(feature = "private_message")]
 use crate::group::framing::PrivateMessage;
 
@@ -80,6 +97,11 @@ pub(crate) struct ProvisionalState {
     pub(crate) indexes_of_added_kpkgs: Vec<LeafIndex>,
     #[cfg(feature = "by_ref_proposal")]
     pub(crate) unused_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg_attr(not(feature = "state_update"), allow(dead_code))]
+    pub(crate) stale_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) rejected_proposals: Vec<crate::mls_rules::RejectedProposal>,
 }
 
 //By default, the path field of a Commit MUST be populated. The path field MAY be omitted if
@@ -110,6 +132,8 @@ pub struct StateUpdate {
     pub(crate) roster_update: RosterUpdate,
     #[cfg(feature = "psk")]
     pub(crate) added_psks: Vec<ExternalPskId>,
+    #[cfg(feature = "psk")]
+    pub(crate) psks: Vec<PskUsage>,
     pub(crate) pending_reinit: Option<CipherSuite>,
     pub(crate) active: bool,
     pub(crate) epoch: u64,
@@ -117,6 +141,10 @@ pub struct StateUpdate {
     pub(crate) custom_proposals: Vec<ProposalInfo<CustomProposal>>,
     #[cfg(feature = "by_ref_proposal")]
     pub(crate) unused_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) stale_proposals: Vec<crate::mls_rules::ProposalInfo<Proposal>>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) expired_proposals: Vec<ProposalRef>,
 }
 
 #[cfg(not(feature = "state_update"))]
@@ -137,6 +165,12 @@ impl StateUpdate {
         &self.added_psks
     }
 
+    /// All pre-shared keys, external and resumption, used by the commit.
+    #[cfg(feature = "psk")]
+    pub fn psks(&self) -> &[PskUsage] {
+        &self.psks
+    }
+
     /// Flag to indicate if the group is now pending reinitialization due to
     /// receiving a [`ReInit`](crate::group::proposal::Proposal::ReInit)
     /// proposal.
@@ -145,7 +179,8 @@ impl StateUpdate {
     }
 
     /// Flag to indicate the group is still active. This will be false if the
-    /// member processing the commit has been removed from the group.
+    /// member processing the commit has been removed from the group, see
+    /// [`Group::is_removed`](crate::Group::is_removed).
     pub fn is_active(&self) -> bool {
         self.active
     }
@@ -167,6 +202,25 @@ impl StateUpdate {
         &self.unused_proposals
     }
 
+    /// Proposals that were received in the prior epoch but not committed to and
+    /// that are no longer valid in the new epoch, either according to standard MLS
+    /// rules or to [`MlsRules::revalidate_proposal`](crate::MlsRules::revalidate_proposal).
+    ///
+    /// Stale proposals are not included in
+    /// [`unused_proposals`](StateUpdate::unused_proposals).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn stale_proposals(&self) -> &[crate::mls_rules::ProposalInfo<Proposal>] {
+        &self.stale_proposals
+    }
+
+    /// References of the by-reference proposals committed by another member
+    /// although they had expired for this member, see
+    /// [`ClientBuilder::proposal_ttl`](crate::client_builder::ClientBuilder::proposal_ttl).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn expired_proposals(&self) -> &[ProposalRef] {
+        &self.expired_proposals
+    }
+
     pub fn pending_reinit_ciphersuite(&self) -> Option<CipherSuite> {
         self.pending_reinit
     }
@@ -193,6 +247,10 @@ pub enum ReceivedMessage {
     Welcome,
     /// Validated key package
     KeyPackage(KeyPackage),
+    /// Message sent by this member and delivered back to it. Only reported
+    /// for the messages recorded according to
+    /// [`ClientBuilder::outbound_echo_cache_size`](crate::client_builder::ClientBuilder::outbound_echo_cache_size).
+    OwnMessageEcho(OwnMessageEcho),
 }
 
 impl TryFrom<ApplicationMessageDescription> for ReceivedMessage {
@@ -478,6 +536,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
         message: MlsMessage,
     ) -> Result<EventOrContent<Self::OutputType>, MlsError> {
         self.check_metadata(&message)?;
+        self.decode_limits().check_message(&message)?;
 
         match message.payload {
             MlsMessagePayload::Plain(plaintext) => {
@@ -553,7 +612,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
                 .map(Self::OutputType::from),
             #[cfg(feature = "by_ref_proposal")]
             Content::Proposal(ref proposal) => self
-                .process_proposal(&auth_content, proposal, cache_proposal)
+                .process_proposal(&auth_content, proposal, cache_proposal, time_sent)
                 .await
                 .map(Self::OutputType::from),
         }?;
@@ -572,6 +631,23 @@ pub(crate) trait MessageProcessor: Send + Sync {
             return Err(MlsError::InvalidSender);
         };
 
+        let leaf_node = self
+            .group_state()
+            .public_tree
+            .get_leaf_node(LeafIndex(sender_index))?;
+
+        let decision = self
+            .mls_rules()
+            .authorize_application_message(
+                &member_from_leaf_node(leaf_node, LeafIndex(sender_index)),
+                &authenticated_data,
+            )
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        if let MessageDecision::Reject(rejection) = decision {
+            return Err(MlsError::MessageRejected(sender_index, rejection));
+        }
+
         Ok(ApplicationMessageDescription {
             authenticated_data,
             sender_index,
@@ -586,10 +662,37 @@ pub(crate) trait MessageProcessor: Send + Sync {
         auth_content: &AuthenticatedContent,
         proposal: &Proposal,
         cache_proposal: bool,
+        time_sent: Option<MlsTime>,
     ) -> Result<ProposalMessageDescription, MlsError> {
+        if self.removed_from_group() {
+            return Err(MlsError::RemovedFromGroup);
+        }
+
+        #[cfg(feature = "custom_proposal")]
+        if matches!(proposal, Proposal::Custom(_))
+            && matches!(auth_content.content.sender, Sender::External(_))
+            && !self
+                .custom_proposal_metadata(proposal.proposal_type())
+                .external_senders_allowed
+        {
+            return Err(MlsError::InvalidProposalTypeForSender);
+        }
+
+        #[cfg(feature = "custom_proposal")]
+        if matches!(proposal, Proposal::Custom(_))
+            && self
+                .custom_proposal_metadata(proposal.proposal_type())
+                .by_value_only
+        {
+            return Err(MlsError::ProposalTypeRequiresByValue(
+                proposal.proposal_type(),
+            ));
+        }
+
         let proposal_ref =
             ProposalRef::from_content(self.cipher_suite_provider(), auth_content).await?;
 
+        let expires_at = proposal_expiry(time_sent, self.proposal_ttl());
         let group_state = self.group_state_mut();
 
         if cache_proposal {
@@ -600,6 +703,8 @@ pub(crate) trait MessageProcessor: Send + Sync {
                 proposal.clone(),
                 auth_content.content.sender,
             );
+
+            group_state.proposals.set_expiry(&proposal_ref, expires_at);
         }
 
         Ok(ProposalMessageDescription {
@@ -685,15 +790,26 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .applied_proposals
             .psks
             .iter()
-            .filter_map(|psk| psk.proposal.external_psk_id().cloned())
+            .map(|psk| PskUsage::from(&psk.proposal.psk.key_id))
             .collect::<Vec<_>>();
 
+        #[cfg(feature = "psk")]
+        let added_psks = psks
+            .iter()
+            .filter_map(|psk| match psk {
+                PskUsage::External(id) => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+
         let roster_update = RosterUpdate::new(added, removed, updated);
 
         let update = StateUpdate {
             roster_update,
             #[cfg(feature = "psk")]
-            added_psks: psks,
+            added_psks,
+            #[cfg(feature = "psk")]
+            psks,
             pending_reinit: provisional
                 .applied_proposals
                 .reinitializations
@@ -705,6 +821,10 @@ pub(crate) trait MessageProcessor: Send + Sync {
             custom_proposals: provisional.applied_proposals.custom_proposals.clone(),
             #[cfg(feature = "by_ref_proposal")]
             unused_proposals: provisional.unused_proposals.clone(),
+            #[cfg(feature = "by_ref_proposal")]
+            stale_proposals: provisional.stale_proposals.clone(),
+            #[cfg(feature = "by_ref_proposal")]
+            expired_proposals: Vec::new(),
         };
 
         Ok(update)
@@ -719,6 +839,10 @@ pub(crate) trait MessageProcessor: Send + Sync {
             return Err(MlsError::GroupUsedAfterReInit);
         }
 
+        if self.removed_from_group() {
+            return Err(MlsError::RemovedFromGroup);
+        }
+
         // Update the new GroupContext's confirmed and interim transcript hashes using the new Commit.
         let (interim_transcript_hash, confirmed_transcript_hash) = transcript_hashes(
             self.cipher_suite_provider(),
@@ -739,6 +863,11 @@ pub(crate) trait MessageProcessor: Send + Sync {
         let group_state = self.group_state();
         let id_provider = self.identity_provider();
 
+        #[cfg(all(feature = "by_ref_proposal", feature = "state_update"))]
+        let expired_proposals = group_state
+            .proposals
+            .expired_references(&commit.proposals, time_sent);
+
         #[cfg(feature = "by_ref_proposal")]
         let proposals = group_state
             .proposals
@@ -757,6 +886,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
                 &self.psk_storage(),
                 &self.mls_rules(),
                 time_sent,
+                self.lifetime_policy(),
                 CommitDirection::Receive,
             )
             .await?;
@@ -768,28 +898,23 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .make_state_update(&provisional_state, commit.path.as_ref(), sender)
             .await?;
 
+        #[cfg(all(feature = "by_ref_proposal", feature = "state_update"))]
+        {
+            state_update.expired_proposals = expired_proposals;
+        }
+
         #[cfg(not(feature = "state_update"))]
         let state_update = StateUpdate {};
 
         //Verify that the path value is populated if the proposals vector contains any Update
         // or Remove proposals, or if it's empty. Otherwise, the path value MAY be omitted.
-        if path_update_required(&provisional_state.applied_proposals) && commit.path.is_none() {
+        if self.commit_path_required(&provisional_state.applied_proposals) && commit.path.is_none()
+        {
             return Err(MlsError::CommitMissingPath);
         }
 
-        if !self.can_continue_processing(&provisional_state) {
-            #[cfg(feature = "state_update")]
-            {
-                state_update.active = false;
-            }
-
-            return Ok(CommitMessageDescription {
-                is_external: matches!(auth_content.content.sender, Sender::NewMemberCommit),
-                authenticated_data: auth_content.content.authenticated_data,
-                committer: *sender,
-                state_update,
-            });
-        }
+        let is_external = matches!(auth_content.content.sender, Sender::NewMemberCommit);
+        let has_path = commit.path.is_some();
 
         let update_path = match commit.path {
             Some(update_path) => Some(
@@ -806,6 +931,45 @@ pub(crate) trait MessageProcessor: Send + Sync {
             None => None,
         };
 
+        if !self.can_continue_processing(&provisional_state) {
+            // A removed member can not decrypt the path or derive the new epoch to verify the
+            // confirmation tag. The rest of the commit is validated before this member considers
+            // itself removed.
+            if let Some(update_path) = &update_path {
+                provisional_state
+                    .public_tree
+                    .apply_update_path(
+                        sender,
+                        update_path,
+                        &provisional_state.group_context.extensions,
+                        self.identity_provider(),
+                        self.cipher_suite_provider(),
+                    )
+                    .await?;
+            }
+
+            if auth_content.auth.confirmation_tag.is_none() {
+                return Err(MlsError::InvalidConfirmationTag);
+            }
+
+            self.authorize_commit(&provisional_state, sender, is_external, has_path)
+                .await?;
+
+            self.set_removed_from_group();
+
+            #[cfg(feature = "state_update")]
+            {
+                state_update.active = false;
+            }
+
+            return Ok(CommitMessageDescription {
+                is_external,
+                authenticated_data: auth_content.content.authenticated_data,
+                committer: *sender,
+                state_update,
+            });
+        }
+
         let new_secrets = match update_path {
             Some(update_path) => {
                 self.apply_update_path(sender, &update_path, &mut provisional_state)
@@ -829,6 +993,11 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .tree_hash(self.cipher_suite_provider())
             .await?;
 
+        // The commit is only passed on for authorization once the update path and tree were
+        // validated, and nothing was applied to the group yet.
+        self.authorize_commit(&provisional_state, sender, is_external, has_path)
+            .await?;
+
         if let Some(reinit) = provisional_state.applied_proposals.reinitializations.pop() {
             self.group_state_mut().pending_reinit = Some(reinit.proposal);
 
@@ -849,7 +1018,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
             .await?;
 
             Ok(CommitMessageDescription {
-                is_external: matches!(auth_content.content.sender, Sender::NewMemberCommit),
+                is_external,
                 authenticated_data: auth_content.content.authenticated_data,
                 committer: *sender,
                 state_update,
@@ -864,14 +1033,65 @@ pub(crate) trait MessageProcessor: Send + Sync {
     #[cfg(feature = "private_message")]
     fn self_index(&self) -> Option<LeafIndex>;
     fn mls_rules(&self) -> Self::MlsRules;
+    fn decode_limits(&self) -> DecodeLimits;
+    fn lifetime_policy(&self) -> LifetimePolicy;
     fn identity_provider(&self) -> Self::IdentityProvider;
     fn cipher_suite_provider(&self) -> &Self::CipherSuiteProvider;
     fn psk_storage(&self) -> Self::PreSharedKeyStorage;
     fn can_continue_processing(&self, provisional_state: &ProvisionalState) -> bool;
 
+    /// Determine if a previously processed commit removed this member from
+    /// the group, in which case no further handshake messages are processed.
+    fn removed_from_group(&self) -> bool {
+        false
+    }
+
+    /// Record that the commit being processed removes this member from the
+    /// group.
+    fn set_removed_from_group(&mut self) {}
+
+    /// Decide whether a valid commit resulting in `provisional_state` may be
+    /// applied.
+    async fn authorize_commit(
+        &self,
+        _provisional_state: &ProvisionalState,
+        _committer: LeafIndex,
+        _is_external: bool,
+        _has_path: bool,
+    ) -> Result<(), MlsError> {
+        Ok(())
+    }
+
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64>;
 
+    /// Number of seconds after which received proposals expire, or `None` if
+    /// they are cached until the next commit.
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_ttl(&self) -> Option<u64> {
+        None
+    }
+
+    /// Metadata registered for the custom proposal type `proposal_type`.
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, _proposal_type: ProposalType) -> CustomProposalMetadata {
+        CustomProposalMetadata::default()
+    }
+
+    /// Determine if a commit covering `proposals` must contain a path, taking
+    /// the metadata of custom proposal types into account.
+    fn commit_path_required(&self, proposals: &ProposalBundle) -> bool {
+        let res = path_update_required(proposals);
+
+        #[cfg(feature = "custom_proposal")]
+        let res = res
+            || proposals
+                .custom_proposal_types()
+                .any(|t| self.custom_proposal_metadata(t).path_required);
+
+        res
+    }
+
     fn check_metadata(&self, message: &MlsMessage) -> Result<(), MlsError> {
         let context = &self.group_state().context;
 
@@ -970,7 +1190,7 @@ pub(crate) trait MessageProcessor: Send + Sync {
         let cs = self.cipher_suite_provider();
         let id = self.identity_provider();
 
-        validate_key_package(key_package, version, cs, &id).await
+        validate_key_package(key_package, version, cs, &id, self.lifetime_policy()).await
     }
 
     #[cfg(feature = "private_message")]
@@ -1018,8 +1238,9 @@ pub(crate) async fn validate_key_package<C: CipherSuiteProvider, I: IdentityProv
     version: ProtocolVersion,
     cs: &C,
     id: &I,
+    lifetime_policy: LifetimePolicy,
 ) -> Result<(), MlsError> {
-    let validator = LeafNodeValidator::new(cs, id, None);
+    let validator = LeafNodeValidator::new(cs, id, None).with_lifetime_policy(lifetime_policy);
 
     #[cfg(feature = "std")]
     let context = Some(MlsTime::now());
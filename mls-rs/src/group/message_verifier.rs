@@ -60,7 +60,7 @@ pub(crate) async fn verify_plaintext_authentication<P: CipherSuiteProvider>(
 
                 let plaintext_tag = tag.as_ref().ok_or(MlsError::InvalidMembershipTag)?;
 
-                if expected_tag != plaintext_tag {
+                if !cipher_suite_provider.constant_time_eq(expected_tag, plaintext_tag) {
                     return Err(MlsError::InvalidMembershipTag);
                 }
             }
@@ -312,6 +312,7 @@ mod tests {
                 None,
                 bob_client.config,
                 bob_client.signer.unwrap(),
+                Default::default(),
             )
             .await
             .unwrap();
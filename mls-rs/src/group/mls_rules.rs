@@ -2,7 +2,13 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use crate::group::{proposal_filter::ProposalBundle, Roster};
+use crate::{
+    client::MlsError,
+    group::{proposal_filter::ProposalBundle, GroupContext, Roster},
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::{proposal::Proposal, proposal_filter::ProposalInfo};
 
 #[cfg(feature = "private_message")]
 use crate::{
@@ -11,7 +17,10 @@ use crate::{
 };
 
 use alloc::boxed::Box;
+#[cfg(feature = "private_message")]
+use alloc::string::String;
 use core::convert::Infallible;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use mls_rs_core::{
     error::IntoAnyError, extension::ExtensionList, group::Member, identity::SigningIdentity,
 };
@@ -31,7 +40,8 @@ pub enum CommitSource {
 }
 
 /// Options controlling commit generation
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct CommitOptions {
     pub path_required: bool,
@@ -112,6 +122,79 @@ impl EncryptionOptions {
     }
 }
 
+/// Choice of the leaf at which a new member is inserted into the ratchet tree,
+/// returned by [`MlsRules::leaf_placement`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeafPlacement {
+    /// Insert the member at the leftmost blank leaf, or after the last leaf if
+    /// there is no blank leaf. This is the placement mandated by RFC 9420.
+    #[default]
+    LeftmostBlank,
+    /// Insert the member at the rightmost blank leaf, or after the last leaf if
+    /// there is no blank leaf.
+    RightmostBlank,
+    /// Insert the member at the given leaf index, which must either be blank or
+    /// be equal to [`Roster::leaf_count`] to insert the member after the last leaf.
+    Index(u32),
+}
+
+/// Removal of blank leaves at the right edge of the ratchet tree after a
+/// commit, returned by [`MlsRules::tree_truncation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TreeTruncation {
+    /// Remove all trailing blank leaves. This is the behavior mandated by
+    /// RFC 9420.
+    #[default]
+    Trailing,
+    /// Remove trailing blank leaves, but keep at least the given number of
+    /// leaves so that the size of the tree stays stable while the group is
+    /// below that size.
+    KeepLeaves(u32),
+    /// Never remove blank leaves.
+    Never,
+}
+
+impl TreeTruncation {
+    /// Whether a tree with `leaf_count` leaves may end with a blank leaf.
+    pub(crate) fn allows_trailing_blanks(&self, leaf_count: u32) -> bool {
+        match self {
+            TreeTruncation::Trailing => false,
+            TreeTruncation::KeepLeaves(min_leaves) => leaf_count <= *min_leaves,
+            TreeTruncation::Never => true,
+        }
+    }
+}
+
+/// Reason for rejecting a message, surfaced as [`MlsError::MessageRejected`].
+#[cfg(feature = "private_message")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageRejection {
+    /// Application defined code identifying the reason.
+    pub code: u16,
+    /// Human readable description of the reason.
+    pub reason: String,
+}
+
+#[cfg(feature = "private_message")]
+impl MessageRejection {
+    pub fn new(code: u16, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Decision of [`MlsRules::authorize_application_message`].
+#[cfg(feature = "private_message")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageDecision {
+    Accept,
+    Reject(MessageRejection),
+}
+
 /// A set of user controlled rules that customize the behavior of MLS.
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
@@ -170,6 +253,94 @@ pub trait MlsRules: Send + Sync {
         current_roster: &Roster,
         current_extension_list: &ExtensionList,
     ) -> Result<EncryptionOptions, Self::Error>;
+
+    /// This is called after applying a commit for each by-reference proposal of the
+    /// prior epoch that was not committed and that is still valid under standard MLS
+    /// rules, to determine if it is still valid in the new epoch.
+    ///
+    /// The `new_roster` and `new_extension_list` describe the group state after the commit.
+    /// Proposals for which this returns `false` are reported as
+    /// [stale](crate::group::StateUpdate::stale_proposals) instead of
+    /// [unused](crate::group::StateUpdate::unused_proposals). By default, all proposals
+    /// are kept.
+    #[cfg(feature = "by_ref_proposal")]
+    async fn revalidate_proposal(
+        &self,
+        _proposal: &ProposalInfo<Proposal>,
+        _new_roster: &Roster,
+        _new_extension_list: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    /// This is called when preparing or receiving a commit, once for each member added by the
+    /// commit in the order the members are added, and for the new member joining via external
+    /// commit, to choose the leaf of the new member.
+    ///
+    /// The `roster` describes the ratchet tree with removals, updates and all prior additions
+    /// applied.
+    ///
+    /// Each member of a group MUST use the same leaf placement in order to maintain a working
+    /// group. Placements other than [`LeafPlacement::LeftmostBlank`], which is the default, are
+    /// not compatible with clients following RFC 9420.
+    fn leaf_placement(
+        &self,
+        _roster: &Roster,
+        _new_member: &SigningIdentity,
+    ) -> Result<LeafPlacement, Self::Error> {
+        Ok(LeafPlacement::LeftmostBlank)
+    }
+
+    /// This is called when preparing or receiving a commit, and when joining a group, to
+    /// determine which blank leaves at the right edge of the ratchet tree are removed, and
+    /// which ones are accepted in the tree of a group being joined.
+    ///
+    /// The `group_extensions` are the group context extensions of the epoch the tree belongs to,
+    /// i.e. of the epoch created by the commit or of the group being joined.
+    ///
+    /// Each member of a group MUST use the same truncation in order to maintain a working
+    /// group. Truncations other than [`TreeTruncation::Trailing`], which is the default, are
+    /// not compatible with clients following RFC 9420.
+    fn tree_truncation(
+        &self,
+        _group_extensions: &ExtensionList,
+    ) -> Result<TreeTruncation, Self::Error> {
+        Ok(TreeTruncation::Trailing)
+    }
+
+    /// This is called when creating a group info, either for a welcome message or with
+    /// [`Group::group_info_message`](crate::Group::group_info_message), to determine the
+    /// application extensions to include in it.
+    ///
+    /// The `group_context` is the context of the epoch the group info describes. Extensions
+    /// set by the library, such as the ratchet tree, and extensions passed to
+    /// [`CommitBuilder::set_group_info_ext`](crate::group::CommitBuilder::set_group_info_ext)
+    /// take precedence over extensions of the same type returned here. New members can read
+    /// the extensions from [`NewMemberInfo`](crate::group::NewMemberInfo) or
+    /// [`WelcomePreview`](crate::group::WelcomePreview).
+    fn group_info_extensions(
+        &self,
+        _group_context: &GroupContext,
+    ) -> Result<ExtensionList, Self::Error> {
+        Ok(ExtensionList::new())
+    }
+
+    /// This is called when receiving an application message, after it was decrypted and its
+    /// sender authenticated, and before its content is returned.
+    ///
+    /// The `sender` is the member that sent the message, including its credential, and
+    /// `authenticated_data` is the additional authenticated data of the message. A rejected
+    /// message fails with [`MlsError::MessageRejected`]. Unlike the other rules, this rule
+    /// does not need to be the same for all members of a group, which makes it suitable for
+    /// local policies such as muting members. By default, all messages are accepted.
+    #[cfg(feature = "private_message")]
+    fn authorize_application_message(
+        &self,
+        _sender: &Member,
+        _authenticated_data: &[u8],
+    ) -> Result<MessageDecision, Self::Error> {
+        Ok(MessageDecision::Accept)
+    }
 }
 
 macro_rules! delegate_mls_rules {
@@ -209,6 +380,50 @@ macro_rules! delegate_mls_rules {
             ) -> Result<EncryptionOptions, Self::Error> {
                 (**self).encryption_options(roster, extension_list)
             }
+
+            #[cfg(feature = "by_ref_proposal")]
+            #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+            async fn revalidate_proposal(
+                &self,
+                proposal: &ProposalInfo<Proposal>,
+                new_roster: &Roster,
+                new_extension_list: &ExtensionList,
+            ) -> Result<bool, Self::Error> {
+                (**self)
+                    .revalidate_proposal(proposal, new_roster, new_extension_list)
+                    .await
+            }
+
+            fn leaf_placement(
+                &self,
+                roster: &Roster,
+                new_member: &SigningIdentity,
+            ) -> Result<LeafPlacement, Self::Error> {
+                (**self).leaf_placement(roster, new_member)
+            }
+
+            fn tree_truncation(
+                &self,
+                group_extensions: &ExtensionList,
+            ) -> Result<TreeTruncation, Self::Error> {
+                (**self).tree_truncation(group_extensions)
+            }
+
+            fn group_info_extensions(
+                &self,
+                group_context: &GroupContext,
+            ) -> Result<ExtensionList, Self::Error> {
+                (**self).group_info_extensions(group_context)
+            }
+
+            #[cfg(feature = "private_message")]
+            fn authorize_application_message(
+                &self,
+                sender: &Member,
+                authenticated_data: &[u8],
+            ) -> Result<MessageDecision, Self::Error> {
+                (**self).authorize_application_message(sender, authenticated_data)
+            }
         }
     };
 }
@@ -222,6 +437,9 @@ delegate_mls_rules!(&T);
 pub struct DefaultMlsRules {
     pub commit_options: CommitOptions,
     pub encryption_options: EncryptionOptions,
+    pub leaf_placement: LeafPlacement,
+    pub tree_truncation: TreeTruncation,
+    pub group_info_extensions: ExtensionList,
 }
 
 impl DefaultMlsRules {
@@ -235,15 +453,42 @@ impl DefaultMlsRules {
     pub fn with_commit_options(self, commit_options: CommitOptions) -> Self {
         Self {
             commit_options,
-            encryption_options: self.encryption_options,
+            ..self
         }
     }
 
     /// Set encryption options.
     pub fn with_encryption_options(self, encryption_options: EncryptionOptions) -> Self {
         Self {
-            commit_options: self.commit_options,
             encryption_options,
+            ..self
+        }
+    }
+
+    /// Set the placement of new members in the ratchet tree. See
+    /// [`MlsRules::leaf_placement`].
+    pub fn with_leaf_placement(self, leaf_placement: LeafPlacement) -> Self {
+        Self {
+            leaf_placement,
+            ..self
+        }
+    }
+
+    /// Set the removal of trailing blank leaves from the ratchet tree. See
+    /// [`MlsRules::tree_truncation`].
+    pub fn with_tree_truncation(self, tree_truncation: TreeTruncation) -> Self {
+        Self {
+            tree_truncation,
+            ..self
+        }
+    }
+
+    /// Set the application extensions included in every group info. See
+    /// [`MlsRules::group_info_extensions`].
+    pub fn with_group_info_extensions(self, group_info_extensions: ExtensionList) -> Self {
+        Self {
+            group_info_extensions,
+            ..self
         }
     }
 }
@@ -280,4 +525,48 @@ impl MlsRules for DefaultMlsRules {
     ) -> Result<EncryptionOptions, Self::Error> {
         Ok(self.encryption_options)
     }
+
+    fn leaf_placement(
+        &self,
+        _: &Roster,
+        _: &SigningIdentity,
+    ) -> Result<LeafPlacement, Self::Error> {
+        Ok(self.leaf_placement)
+    }
+
+    fn tree_truncation(&self, _: &ExtensionList) -> Result<TreeTruncation, Self::Error> {
+        Ok(self.tree_truncation)
+    }
+
+    fn group_info_extensions(&self, _: &GroupContext) -> Result<ExtensionList, Self::Error> {
+        Ok(self.group_info_extensions.clone())
+    }
+}
+
+/// Object-safe view of [`MlsRules::leaf_placement`] and [`MlsRules::tree_truncation`] used
+/// while applying proposals to the ratchet tree.
+pub(crate) trait TreeLayoutRules: Send + Sync {
+    fn place(
+        &self,
+        roster: &Roster,
+        new_member: &SigningIdentity,
+    ) -> Result<LeafPlacement, MlsError>;
+
+    fn truncation(&self, group_extensions: &ExtensionList) -> Result<TreeTruncation, MlsError>;
+}
+
+impl<T: MlsRules + ?Sized> TreeLayoutRules for T {
+    fn place(
+        &self,
+        roster: &Roster,
+        new_member: &SigningIdentity,
+    ) -> Result<LeafPlacement, MlsError> {
+        self.leaf_placement(roster, new_member)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
+
+    fn truncation(&self, group_extensions: &ExtensionList) -> Result<TreeTruncation, MlsError> {
+        self.tree_truncation(group_extensions)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
+    }
 }
@@ -2,26 +2,29 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+use alloc::borrow::Cow;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug};
-use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_codec::{MlsDecode, MlsDecodeBorrowed, MlsEncode, MlsSize};
 use mls_rs_core::error::IntoAnyError;
 use mls_rs_core::secret::Secret;
 use mls_rs_core::time::MlsTime;
+use zeroize::Zeroizing;
 
 use crate::cipher_suite::CipherSuite;
 use crate::client::MlsError;
 use crate::client_config::ClientConfig;
 use crate::crypto::{HpkeCiphertext, SignatureSecretKey};
 use crate::extension::RatchetTreeExt;
-use crate::identity::SigningIdentity;
-use crate::key_package::{KeyPackage, KeyPackageRef};
+use crate::identity::{PolicyIdentityProvider, SigningIdentity};
+use crate::key_package::{KeyPackage, KeyPackageGeneration, KeyPackageRef};
 use crate::protocol_version::ProtocolVersion;
 use crate::psk::secret::PskSecret;
 use crate::psk::PreSharedKeyID;
 use crate::signer::Signable;
-use crate::tree_kem::hpke_encryption::HpkeEncryptable;
+use crate::tree_kem::hpke_encryption::{encrypt_with_label_multi, HpkeEncryptable};
+
 use crate::tree_kem::kem::TreeKem;
 use crate::tree_kem::node::LeafIndex;
 use crate::tree_kem::path_secret::PathSecret;
@@ -33,22 +36,32 @@ use crate::tree_kem::{
 use crate::tree_kem::{math as tree_math, ValidatedUpdatePath};
 use crate::tree_kem::{TreeKemPrivate, TreeKemPublic};
 use crate::{CipherSuiteProvider, CryptoProvider};
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+use rayon::prelude::*;
 
 #[cfg(feature = "by_ref_proposal")]
 use crate::crypto::{HpkePublicKey, HpkeSecretKey};
 
+#[cfg(feature = "by_ref_proposal")]
+use crate::extension::{ExtensionType, ExternalSendersExt};
+
 use crate::extension::ExternalPubExt;
 
+use self::mls_rules::MlsRules;
+
 #[cfg(feature = "private_message")]
-use self::mls_rules::{EncryptionOptions, MlsRules};
+use self::mls_rules::EncryptionOptions;
 
 #[cfg(feature = "psk")]
 pub use self::resumption::ReinitClient;
 
 #[cfg(feature = "psk")]
-use crate::psk::{
-    resolver::PskResolver, secret::PskSecretInput, ExternalPskId, JustPreSharedKeyID, PskGroupId,
-    ResumptionPSKUsage, ResumptionPsk,
+use crate::{
+    client::PskFailureKind,
+    psk::{
+        psk_failure, resolver::PskResolver, secret::PskSecretInput, ExternalPskId,
+        JustPreSharedKeyID, PskGroupId, ResumptionPSKUsage, ResumptionPsk,
+    },
 };
 
 #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
@@ -61,8 +74,10 @@ use confirmation_tag::*;
 use framing::*;
 use key_schedule::*;
 use membership_tag::*;
+use message_buffer::MessageBuffer;
 use message_signature::*;
 use message_verifier::*;
+use outbound_echo::OutboundEchoCache;
 use proposal::*;
 #[cfg(feature = "by_ref_proposal")]
 use proposal_cache::*;
@@ -85,6 +100,7 @@ use secret_tree::*;
 use self::epoch::PriorEpoch;
 
 use self::epoch::EpochSecrets;
+use self::epoch_randomness::EpochRandomness;
 pub use self::message_processor::{
     ApplicationMessageDescription, CommitMessageDescription, ProposalMessageDescription,
     ProposalSender, ReceivedMessage, StateUpdate,
@@ -96,9 +112,52 @@ use self::state_repo::GroupStateRepository;
 pub use group_info::GroupInfo;
 
 pub use self::framing::{ContentType, Sender};
+pub use crate::tree_kem::tree_diff::{
+    LeafDiff, LeafDiffKind, ParentDiff, ParentDiffKind, TreeDiff,
+};
+pub use crate::tree_kem::tree_validator::TreeValidationLevel;
 pub use commit::*;
+pub use config_overrides::GroupConfigOverrides;
 pub use context::GroupContext;
+pub use decode_limits::{DecodeLimit, DecodeLimits};
+#[cfg(feature = "by_ref_proposal")]
+pub use detached_proposal::UnsignedProposal;
+#[cfg(feature = "message_escrow")]
+pub use escrow::{
+    EscrowedExporterSecret, EscrowedReceiveKeys, ExporterSecretShare, RecoveredExporterSecret,
+    RecoveredReceiveKeys,
+};
+pub use external_commit_policy::{ExternalCommitPolicy, ExternalCommitRejection};
+pub use lifetime_policy::{LifetimePolicy, LifetimeWarning, LifetimeWarningKind};
+#[cfg(feature = "custom_proposal")]
+pub use member_slots::{MemberSlotLimits, MemberSlotRules, SetMemberSlotProposal};
+pub use membership_export::ExportedMembership;
+pub use message_buffer::MessageBufferLimits;
+pub use message_context::MessageContext;
+pub use outbound_echo::OwnMessageEcho;
+pub use replay::{
+    ReplayDivergence, ReplayDivergenceKind, ReplayEntry, ReplayReport, ReplayedEpoch,
+};
 pub use roster::*;
+pub use roster_export::{RosterDocument, RosterEntry, ROSTER_DOCUMENT_VERSION};
+pub use snapshot::CompactionReport;
+pub use state_report::{
+    GroupStateReport, LeafReport, ParentReport, PendingCommitReport, PendingProposalReport,
+    SecretDigests, TreeReport,
+};
+
+#[cfg(feature = "private_message")]
+pub use application_sender::ApplicationSender;
+#[cfg(feature = "private_message")]
+pub use ciphertext_processor::{DecryptionFailure, DecryptionFailureReason};
+#[cfg(feature = "by_ref_proposal")]
+pub use extension_rollout::{ExtensionRollout, ExtensionRolloutStage};
+#[cfg(all(feature = "by_ref_proposal", feature = "private_message"))]
+pub use key_rotation::{
+    KeyRotationCeremony, KeyRotationOutcome, KeyRotationRequest, KeyRotationStatus,
+};
+#[cfg(feature = "private_message")]
+pub use key_usage::{AeadUsageLimits, RekeyStatus};
 
 pub(crate) use transcript_hash::ConfirmedTranscriptHash;
 pub(crate) use util::*;
@@ -106,34 +165,66 @@ pub(crate) use util::*;
 #[cfg(all(feature = "by_ref_proposal", feature = "external_client"))]
 pub use self::message_processor::CachedProposal;
 
+#[cfg(feature = "by_ref_proposal")]
+pub use self::proposal_rejection::ProposalRejectionCache;
+
+#[cfg(feature = "private_message")]
+mod application_sender;
 #[cfg(feature = "private_message")]
-mod ciphertext_processor;
+pub(crate) mod ciphertext_processor;
 
 mod commit;
+mod config_overrides;
 pub(crate) mod confirmation_tag;
+mod content_binding;
 mod context;
+mod decode_limits;
+#[cfg(feature = "by_ref_proposal")]
+mod detached_proposal;
 pub(crate) mod epoch;
+mod epoch_randomness;
+#[cfg(feature = "message_escrow")]
+mod escrow;
+#[cfg(feature = "by_ref_proposal")]
+mod extension_rollout;
+mod external_commit_policy;
 pub(crate) mod framing;
 mod group_info;
+#[cfg(all(feature = "by_ref_proposal", feature = "private_message"))]
+mod key_rotation;
 pub(crate) mod key_schedule;
+#[cfg(feature = "private_message")]
+mod key_usage;
+mod lifetime_policy;
+mod member_hpke;
+#[cfg(feature = "custom_proposal")]
+mod member_slots;
+mod membership_export;
 mod membership_tag;
+mod message_buffer;
+mod message_context;
 pub(crate) mod message_processor;
 pub(crate) mod message_signature;
 pub(crate) mod message_verifier;
 pub mod mls_rules;
-#[cfg(feature = "private_message")]
+mod outbound_echo;
 pub(crate) mod padding;
 /// Proposals to evolve a MLS [`Group`]
 pub mod proposal;
-mod proposal_cache;
+pub(crate) mod proposal_cache;
 pub(crate) mod proposal_filter;
 #[cfg(feature = "by_ref_proposal")]
 pub(crate) mod proposal_ref;
+#[cfg(feature = "by_ref_proposal")]
+mod proposal_rejection;
+mod replay;
 #[cfg(feature = "psk")]
 mod resumption;
 mod roster;
+mod roster_export;
 pub(crate) mod snapshot;
 pub(crate) mod state;
+mod state_report;
 
 #[cfg(feature = "prior_epoch")]
 pub(crate) mod state_repo;
@@ -182,6 +273,7 @@ impl HpkeEncryptable for GroupSecrets {
 
 #[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct EncryptedGroupSecrets {
     pub new_member: KeyPackageRef,
     pub encrypted_group_secrets: HpkeCiphertext,
@@ -189,13 +281,71 @@ pub(crate) struct EncryptedGroupSecrets {
 
 #[derive(Clone, Eq, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Welcome {
     pub cipher_suite: CipherSuite,
     pub secrets: Vec<EncryptedGroupSecrets>,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
     pub encrypted_group_info: Vec<u8>,
 }
 
+/// Borrowed form of a [`Welcome`].
+///
+/// Decoding a welcome with [`MlsDecodeBorrowed`] references the encrypted
+/// group info in the input buffer instead of copying it. Only the ciphertext
+/// is borrowed: the group info is decrypted into a new buffer and decoded,
+/// together with the ratchet tree it may carry, into owned values.
+pub(crate) struct WelcomeRef<'a> {
+    pub cipher_suite: CipherSuite,
+    pub secrets: Cow<'a, [EncryptedGroupSecrets]>,
+    pub encrypted_group_info: &'a [u8],
+}
+
+impl<'a> WelcomeRef<'a> {
+    /// Decode an [`MlsMessage`] carrying a welcome without copying the
+    /// encrypted group info.
+    pub(crate) fn from_message_bytes(bytes: &'a [u8]) -> Result<(ProtocolVersion, Self), MlsError> {
+        let reader = &mut &*bytes;
+        let version = ProtocolVersion::mls_decode(reader)?;
+
+        if WireFormat::mls_decode(reader)? != WireFormat::Welcome {
+            return Err(MlsError::UnexpectedMessageType);
+        }
+
+        Ok((version, Self::mls_decode_borrowed(reader)?))
+    }
+}
+
+impl<'a> MlsDecodeBorrowed<'a> for WelcomeRef<'a> {
+    fn mls_decode_borrowed(reader: &mut &'a [u8]) -> Result<Self, mls_rs_codec::Error> {
+        Ok(Self {
+            cipher_suite: CipherSuite::mls_decode(reader)?,
+            secrets: Cow::Owned(Vec::mls_decode(reader)?),
+            encrypted_group_info: <&[u8]>::mls_decode_borrowed(reader)?,
+        })
+    }
+}
+
+impl<'a> From<&'a Welcome> for WelcomeRef<'a> {
+    fn from(welcome: &'a Welcome) -> Self {
+        Self {
+            cipher_suite: welcome.cipher_suite,
+            secrets: Cow::Borrowed(&welcome.secrets),
+            encrypted_group_info: &welcome.encrypted_group_info,
+        }
+    }
+}
+
+/// Secrets and group info of a welcome decrypted by the new member.
+struct DecryptedWelcome<P> {
+    cipher_suite_provider: P,
+    key_package_generation: KeyPackageGeneration,
+    group_secrets: GroupSecrets,
+    psk_secret: PskSecret,
+    group_info: GroupInfo,
+}
+
 impl Debug for Welcome {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Welcome")
@@ -222,6 +372,19 @@ pub struct NewMemberInfo {
     pub group_info_extensions: ExtensionList,
 }
 
+/// Information about the group a welcome message invites to, returned by
+/// [`Client::preview_welcome`](crate::Client::preview_welcome) before joining.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct WelcomePreview {
+    /// Context of the group at the epoch the new member joins in.
+    pub group_context: GroupContext,
+    /// Group info extensions found within the welcome message.
+    pub group_info_extensions: ExtensionList,
+    /// Reference of the key package the welcome message is addressed to.
+    pub key_package_reference: KeyPackageRef,
+}
+
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
 impl NewMemberInfo {
     pub(crate) fn new(group_info_extensions: ExtensionList) -> Self {
@@ -258,7 +421,7 @@ pub struct Group<C>
 where
     C: ClientConfig,
 {
-    config: C,
+    pub(crate) config: C,
     cipher_suite_provider: <C::CryptoProvider as CryptoProvider>::CipherSuiteProvider,
     state_repo: GroupStateRepository<C::GroupStateStorage, C::KeyPackageRepository>,
     pub(crate) state: GroupState,
@@ -272,9 +435,18 @@ where
     pending_commit: Option<CommitGeneration>,
     #[cfg(feature = "psk")]
     previous_psk: Option<PskSecretInput>,
+    #[cfg(feature = "by_ref_proposal")]
+    rejected_proposals: ProposalRejectionCache,
     #[cfg(test)]
     pub(crate) commit_modifiers: CommitModifiers,
     pub(crate) signer: SignatureSecretKey,
+    config_overrides: GroupConfigOverrides,
+    message_buffer: MessageBuffer,
+    outbound_echoes: OutboundEchoCache,
+    prewarmed_key_schedule: Option<PrewarmedKeySchedule>,
+    epoch_randomness: Option<EpochRandomness>,
+    removed: bool,
+    unverified_members: Vec<Member>,
 }
 
 #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen)]
@@ -282,6 +454,7 @@ impl<C> Group<C>
 where
     C: ClientConfig + Clone,
 {
+    #[allow(clippy::too_many_arguments)]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub(crate) async fn new(
         config: C,
@@ -291,19 +464,22 @@ where
         signing_identity: SigningIdentity,
         group_context_extensions: ExtensionList,
         signer: SignatureSecretKey,
+        config_overrides: GroupConfigOverrides,
     ) -> Result<Self, MlsError> {
         let cipher_suite_provider = cipher_suite_provider(config.crypto_provider(), cipher_suite)?;
+        let group_context_extensions =
+            config_overrides.group_context_extensions(group_context_extensions)?;
 
         let (leaf_node, leaf_node_secret) = LeafNode::generate(
             &cipher_suite_provider,
             config.leaf_properties(),
             signing_identity,
             &signer,
-            config.lifetime(),
+            config_overrides.lifetime(config.lifetime()),
         )
         .await?;
 
-        let identity_provider = config.identity_provider();
+        let identity_provider = config.member_identity_provider();
 
         let leaf_node_validator = LeafNodeValidator::new(
             &cipher_suite_provider,
@@ -315,10 +491,17 @@ where
             .check_if_valid(&leaf_node, ValidationContext::Add(None))
             .await?;
 
+        #[cfg(feature = "by_ref_proposal")]
+        if let Some(ext_senders) = group_context_extensions.get_as::<ExternalSendersExt>()? {
+            ext_senders
+                .validate(&identity_provider, None, &group_context_extensions)
+                .await?;
+        }
+
         let (mut public_tree, private_tree) = TreeKemPublic::derive(
             leaf_node,
             leaf_node_secret,
-            &config.identity_provider(),
+            &config.member_identity_provider(),
             &group_context_extensions,
         )
         .await?;
@@ -368,8 +551,8 @@ where
         )
         .await?;
 
-        Ok(Self {
-            config,
+        #[allow(unused_mut)]
+        let mut group = Self {
             state: GroupState::new(context, public_tree, interim_hash, confirmation_tag),
             private_tree,
             key_schedule: key_schedule_result.key_schedule,
@@ -383,8 +566,25 @@ where
             cipher_suite_provider,
             #[cfg(feature = "psk")]
             previous_psk: None,
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: ProposalRejectionCache::new(config.proposal_rejection_ttl()),
+            config,
             signer,
-        })
+            config_overrides,
+            message_buffer: Default::default(),
+            outbound_echoes: Default::default(),
+            prewarmed_key_schedule: None,
+            epoch_randomness: None,
+            removed: false,
+            unverified_members: Vec::new(),
+        };
+
+        group.update_unverified_members();
+
+        #[cfg(feature = "prior_epoch")]
+        group.update_epoch_chain().await?;
+
+        Ok(group)
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -393,8 +593,9 @@ where
         tree_data: Option<ExportedTree<'_>>,
         config: C,
         signer: SignatureSecretKey,
+        config_overrides: GroupConfigOverrides,
     ) -> Result<(Self, NewMemberInfo), MlsError> {
-        Self::from_welcome_message(
+        let (mut group, new_member_info) = Self::from_welcome_message(
             welcome,
             tree_data,
             config,
@@ -402,7 +603,37 @@ where
             #[cfg(feature = "psk")]
             None,
         )
-        .await
+        .await?;
+
+        group.config_overrides = config_overrides;
+
+        Ok((group, new_member_info))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn join_from_bytes(
+        welcome: &[u8],
+        tree_data: Option<ExportedTree<'_>>,
+        config: C,
+        signer: SignatureSecretKey,
+        config_overrides: GroupConfigOverrides,
+    ) -> Result<(Self, NewMemberInfo), MlsError> {
+        let (protocol_version, welcome) = WelcomeRef::from_message_bytes(welcome)?;
+
+        let (mut group, new_member_info) = Self::from_welcome(
+            protocol_version,
+            welcome,
+            tree_data,
+            config,
+            signer,
+            #[cfg(feature = "psk")]
+            None,
+        )
+        .await?;
+
+        group.config_overrides = config_overrides;
+
+        Ok((group, new_member_info))
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -423,89 +654,52 @@ where
             return Err(MlsError::UnexpectedMessageType);
         };
 
-        let cipher_suite_provider =
-            cipher_suite_provider(config.crypto_provider(), welcome.cipher_suite)?;
-
-        let (encrypted_group_secrets, key_package_generation) =
-            find_key_package_generation(&config.key_package_repo(), &welcome.secrets).await?;
-
-        let key_package_version = key_package_generation.key_package.version;
-
-        if key_package_version != protocol_version {
-            return Err(MlsError::ProtocolVersionMismatch);
-        }
-
-        // Decrypt the encrypted_group_secrets using HPKE with the algorithms indicated by the
-        // cipher suite and the HPKE private key corresponding to the GroupSecrets. If a
-        // PreSharedKeyID is part of the GroupSecrets and the client is not in possession of
-        // the corresponding PSK, return an error
-        let group_secrets = GroupSecrets::decrypt(
-            &cipher_suite_provider,
-            &key_package_generation.init_secret_key,
-            &key_package_generation.key_package.hpke_init_key,
-            &welcome.encrypted_group_info,
-            &encrypted_group_secrets.encrypted_group_secrets,
+        Self::from_welcome(
+            protocol_version,
+            welcome.into(),
+            tree_data,
+            config,
+            signer,
+            #[cfg(feature = "psk")]
+            additional_psk,
         )
-        .await?;
-
-        #[cfg(feature = "psk")]
-        let psk_secret = if let Some(psk) = additional_psk {
-            let psk_id = group_secrets
-                .psks
-                .first()
-                .ok_or(MlsError::UnexpectedPskId)?;
-
-            match &psk_id.key_id {
-                JustPreSharedKeyID::Resumption(r) if r.usage != ResumptionPSKUsage::Application => {
-                    Ok(())
-                }
-                _ => Err(MlsError::UnexpectedPskId),
-            }?;
-
-            let mut psk = psk;
-            psk.id.psk_nonce = psk_id.psk_nonce.clone();
-            PskSecret::calculate(&[psk], &cipher_suite_provider).await?
-        } else {
-            PskResolver::<
-                <C as ClientConfig>::GroupStateStorage,
-                <C as ClientConfig>::KeyPackageRepository,
-                <C as ClientConfig>::PskStore,
-            > {
-                group_context: None,
-                current_epoch: None,
-                prior_epochs: None,
-                psk_store: &config.secret_store(),
-            }
-            .resolve_to_secret(&group_secrets.psks, &cipher_suite_provider)
-            .await?
-        };
-
-        #[cfg(not(feature = "psk"))]
-        let psk_secret = PskSecret::new(&cipher_suite_provider);
+        .await
+    }
 
-        // From the joiner_secret in the decrypted GroupSecrets object and the PSKs specified in
-        // the GroupSecrets, derive the welcome_secret and using that the welcome_key and
-        // welcome_nonce.
-        let welcome_secret = WelcomeSecret::from_joiner_secret(
-            &cipher_suite_provider,
-            &group_secrets.joiner_secret,
-            &psk_secret,
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn from_welcome(
+        protocol_version: ProtocolVersion,
+        welcome: WelcomeRef<'_>,
+        tree_data: Option<ExportedTree<'_>>,
+        config: C,
+        signer: SignatureSecretKey,
+        #[cfg(feature = "psk")] additional_psk: Option<PskSecretInput>,
+    ) -> Result<(Self, NewMemberInfo), MlsError> {
+        let DecryptedWelcome {
+            cipher_suite_provider,
+            key_package_generation,
+            group_secrets,
+            psk_secret,
+            group_info,
+        } = Self::decrypt_welcome(
+            protocol_version,
+            welcome,
+            &config,
+            #[cfg(feature = "psk")]
+            additional_psk,
         )
         .await?;
 
-        // Use the key and nonce to decrypt the encrypted_group_info field.
-        let decrypted_group_info = welcome_secret
-            .decrypt(&welcome.encrypted_group_info)
-            .await?;
-
-        let group_info = GroupInfo::mls_decode(&mut &**decrypted_group_info)?;
-
         let public_tree = validate_group_info_joiner(
             protocol_version,
             &group_info,
             tree_data,
-            &config.identity_provider(),
+            &config.member_identity_provider(),
             &cipher_suite_provider,
+            &config.decode_limits(),
+            &config.mls_rules(),
+            config.tree_validation_level(),
+            config.tree_validation_parallelism(),
         )
         .await?;
 
@@ -573,82 +767,262 @@ where
         .await
     }
 
-    #[allow(clippy::too_many_arguments)]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn join_with(
-        config: C,
-        group_info: GroupInfo,
-        public_tree: TreeKemPublic,
-        key_schedule: KeySchedule,
-        epoch_secrets: EpochSecrets,
-        private_tree: TreeKemPrivate,
-        used_key_package_ref: Option<KeyPackageRef>,
-        signer: SignatureSecretKey,
-    ) -> Result<(Self, NewMemberInfo), MlsError> {
-        let cs = group_info.group_context.cipher_suite;
-
-        let cs = config
-            .crypto_provider()
-            .cipher_suite_provider(cs)
-            .ok_or(MlsError::UnsupportedCipherSuite(cs))?;
-
-        // Use the confirmed transcript hash and confirmation tag to compute the interim transcript
-        // hash in the new state.
-        let interim_transcript_hash = InterimTranscriptHash::create(
-            &cs,
-            &group_info.group_context.confirmed_transcript_hash,
-            &group_info.confirmation_tag,
-        )
-        .await?;
-
-        let state_repo = GroupStateRepository::new(
-            #[cfg(feature = "prior_epoch")]
-            group_info.group_context.group_id.clone(),
-            config.group_state_storage(),
-            config.key_package_repo(),
-            used_key_package_ref,
-        )?;
+    pub(crate) async fn preview_welcome(
+        welcome: &MlsMessage,
+        config: &C,
+    ) -> Result<WelcomePreview, MlsError> {
+        let MlsMessagePayload::Welcome(welcome_payload) = &welcome.payload else {
+            return Err(MlsError::UnexpectedMessageType);
+        };
 
-        let group = Group {
+        let decrypted = Self::decrypt_welcome(
+            welcome.version,
+            welcome_payload.into(),
             config,
-            state: GroupState::new(
-                group_info.group_context,
-                public_tree,
-                interim_transcript_hash,
-                group_info.confirmation_tag,
-            ),
-            private_tree,
-            key_schedule,
-            #[cfg(feature = "by_ref_proposal")]
-            pending_updates: Default::default(),
-            pending_commit: None,
-            #[cfg(test)]
-            commit_modifiers: Default::default(),
-            epoch_secrets,
-            state_repo,
-            cipher_suite_provider: cs,
             #[cfg(feature = "psk")]
-            previous_psk: None,
-            signer,
-        };
+            None,
+        )
+        .await?;
 
-        Ok((group, NewMemberInfo::new(group_info.extensions)))
+        Ok(WelcomePreview {
+            group_info_extensions: decrypted.group_info.ungreased_extensions(),
+            group_context: decrypted.group_info.group_context,
+            key_package_reference: decrypted.key_package_generation.reference,
+        })
     }
 
-    #[inline(always)]
-    pub(crate) fn current_epoch_tree(&self) -> &TreeKemPublic {
-        &self.state.public_tree
-    }
+    /// Decrypt the group secrets and the group info of `welcome` with the key package it
+    /// is addressed to, without validating the group info or using the key package.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn decrypt_welcome(
+        protocol_version: ProtocolVersion,
+        welcome: WelcomeRef<'_>,
+        config: &C,
+        #[cfg(feature = "psk")] additional_psk: Option<PskSecretInput>,
+    ) -> Result<
+        DecryptedWelcome<<C::CryptoProvider as CryptoProvider>::CipherSuiteProvider>,
+        MlsError,
+    > {
+        if !config.version_supported(protocol_version) {
+            return Err(MlsError::UnsupportedProtocolVersion(protocol_version));
+        }
 
-    /// The current epoch of the group. This value is incremented each
-    /// time a [`Group::commit`] message is processed.
-    #[inline(always)]
-    pub fn current_epoch(&self) -> u64 {
-        self.context().epoch
-    }
+        let cipher_suite_provider =
+            cipher_suite_provider(config.crypto_provider(), welcome.cipher_suite)?;
 
-    /// Index within the group's state for the local group instance.
-    ///
+        let (encrypted_group_secrets, key_package_generation) =
+            find_key_package_generation(&config.key_package_repo(), &welcome.secrets).await?;
+
+        let key_package_version = key_package_generation.key_package.version;
+
+        if key_package_version != protocol_version {
+            return Err(MlsError::ProtocolVersionMismatch);
+        }
+
+        // Decrypt the encrypted_group_secrets using HPKE with the algorithms indicated by the
+        // cipher suite and the HPKE private key corresponding to the GroupSecrets. If a
+        // PreSharedKeyID is part of the GroupSecrets and the client is not in possession of
+        // the corresponding PSK, return an error
+        let group_secrets = GroupSecrets::decrypt(
+            &cipher_suite_provider,
+            &key_package_generation.init_secret_key,
+            &key_package_generation.key_package.hpke_init_key,
+            welcome.encrypted_group_info,
+            &encrypted_group_secrets.encrypted_group_secrets,
+        )
+        .await?;
+
+        #[cfg(feature = "psk")]
+        let resumption_join = additional_psk.is_some();
+
+        #[cfg(feature = "psk")]
+        let psk_secret = if let Some(psk) = additional_psk {
+            let psk_id = group_secrets
+                .psks
+                .first()
+                .ok_or(MlsError::UnexpectedPskId)?;
+
+            match &psk_id.key_id {
+                JustPreSharedKeyID::Resumption(r) if r.usage != ResumptionPSKUsage::Application => {
+                    Ok(())
+                }
+                _ => Err(MlsError::UnexpectedPskId),
+            }?;
+
+            let mut psk = psk;
+            psk.id.psk_nonce = psk_id.psk_nonce.clone();
+            PskSecret::calculate(&[psk], &cipher_suite_provider).await?
+        } else {
+            PskResolver::<
+                <C as ClientConfig>::GroupStateStorage,
+                <C as ClientConfig>::KeyPackageRepository,
+                <C as ClientConfig>::PskStore,
+            > {
+                group_context: None,
+                current_epoch: None,
+                prior_epochs: None,
+                psk_store: &config.secret_store(),
+            }
+            .resolve_to_secret(&group_secrets.psks, &cipher_suite_provider)
+            .await?
+        };
+
+        #[cfg(not(feature = "psk"))]
+        let psk_secret = PskSecret::new(&cipher_suite_provider);
+
+        // From the joiner_secret in the decrypted GroupSecrets object and the PSKs specified in
+        // the GroupSecrets, derive the welcome_secret and using that the welcome_key and
+        // welcome_nonce.
+        let welcome_secret = WelcomeSecret::from_joiner_secret(
+            &cipher_suite_provider,
+            &group_secrets.joiner_secret,
+            &psk_secret,
+        )
+        .await?;
+
+        // Use the key and nonce to decrypt the encrypted_group_info field.
+        let decrypted_group_info = welcome_secret.decrypt(welcome.encrypted_group_info).await;
+
+        // The group secrets were decrypted with our init key, so if the group
+        // info does not decrypt, the PSK values differ from the sender's
+        #[cfg(feature = "psk")]
+        let decrypted_group_info = decrypted_group_info.map_err(|e| {
+            if group_secrets.psks.is_empty() {
+                e
+            } else {
+                psk_failure(&group_secrets.psks, PskFailureKind::ValueMismatch)
+            }
+        });
+
+        let decrypted_group_info = decrypted_group_info?;
+
+        let group_info = config
+            .decode_limits()
+            .decode_group_info(&mut &**decrypted_group_info)?;
+
+        #[cfg(feature = "psk")]
+        if !resumption_join {
+            check_required_join_psk(&group_info.group_context, &group_secrets.psks)?;
+        }
+
+        Ok(DecryptedWelcome {
+            cipher_suite_provider,
+            key_package_generation,
+            group_secrets,
+            psk_secret,
+            group_info,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn join_with(
+        config: C,
+        group_info: GroupInfo,
+        public_tree: TreeKemPublic,
+        key_schedule: KeySchedule,
+        epoch_secrets: EpochSecrets,
+        private_tree: TreeKemPrivate,
+        used_key_package_ref: Option<KeyPackageRef>,
+        signer: SignatureSecretKey,
+    ) -> Result<(Self, NewMemberInfo), MlsError> {
+        let cs = group_info.group_context.cipher_suite;
+
+        let cs = config
+            .crypto_provider()
+            .cipher_suite_provider(cs)
+            .ok_or(MlsError::UnsupportedCipherSuite(cs))?;
+
+        // Use the confirmed transcript hash and confirmation tag to compute the interim transcript
+        // hash in the new state.
+        let interim_transcript_hash = InterimTranscriptHash::create(
+            &cs,
+            &group_info.group_context.confirmed_transcript_hash,
+            &group_info.confirmation_tag,
+        )
+        .await?;
+
+        let state_repo = GroupStateRepository::new(
+            #[cfg(feature = "prior_epoch")]
+            group_info.group_context.group_id.clone(),
+            config.group_state_storage(),
+            config.key_package_repo(),
+            used_key_package_ref,
+        )?;
+
+        #[allow(unused_mut)]
+        let mut group = Group {
+            state: GroupState::new(
+                group_info.group_context,
+                public_tree,
+                interim_transcript_hash,
+                group_info.confirmation_tag,
+            ),
+            private_tree,
+            key_schedule,
+            #[cfg(feature = "by_ref_proposal")]
+            pending_updates: Default::default(),
+            pending_commit: None,
+            #[cfg(test)]
+            commit_modifiers: Default::default(),
+            epoch_secrets,
+            state_repo,
+            cipher_suite_provider: cs,
+            #[cfg(feature = "psk")]
+            previous_psk: None,
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: ProposalRejectionCache::new(config.proposal_rejection_ttl()),
+            config,
+            signer,
+            config_overrides: Default::default(),
+            message_buffer: Default::default(),
+            outbound_echoes: Default::default(),
+            prewarmed_key_schedule: None,
+            epoch_randomness: None,
+            removed: false,
+            unverified_members: Vec::new(),
+        };
+
+        group.update_unverified_members();
+
+        #[cfg(feature = "prior_epoch")]
+        group.update_epoch_chain().await?;
+
+        Ok((group, NewMemberInfo::new(group_info.extensions)))
+    }
+
+    /// Move the head of the chain encrypting prior epochs in storage to the
+    /// current epoch, if enabled.
+    #[cfg(feature = "prior_epoch")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn update_epoch_chain(&mut self) -> Result<(), MlsError> {
+        if self.config.chained_epoch_encryption() {
+            let secret = self
+                .key_schedule
+                .epoch_record_secret(&self.cipher_suite_provider)
+                .await?;
+
+            self.state_repo.set_chain_head(self.current_epoch(), secret);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn current_epoch_tree(&self) -> &TreeKemPublic {
+        &self.state.public_tree
+    }
+
+    /// The current epoch of the group. This value is incremented each
+    /// time a [`Group::commit`] message is processed.
+    #[inline(always)]
+    pub fn current_epoch(&self) -> u64 {
+        self.context().epoch
+    }
+
+    /// Index within the group's state for the local group instance.
+    ///
     /// This index corresponds to indexes in content descriptions within
     /// [`ReceivedMessage`].
     #[inline(always)]
@@ -656,6 +1030,27 @@ where
         self.private_tree.self_index.0
     }
 
+    /// Determine if this member was removed from the group by a processed
+    /// commit.
+    ///
+    /// A removed group is read-only. It remains in the last epoch this member
+    /// was part of, so that the roster of that epoch can be inspected and
+    /// application messages of retained epochs can still be decrypted.
+    /// Sending messages, proposing, committing and processing further
+    /// handshake messages fail with [`MlsError::RemovedFromGroup`].
+    #[inline(always)]
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
+
+    fn check_not_removed(&self) -> Result<(), MlsError> {
+        if self.removed {
+            Err(MlsError::RemovedFromGroup)
+        } else {
+            Ok(())
+        }
+    }
+
     fn current_user_leaf_node(&self) -> Result<&LeafNode, MlsError> {
         self.current_epoch_tree()
             .get_leaf_node(self.private_tree.self_index)
@@ -687,6 +1082,14 @@ where
         proposal: Proposal,
         authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
+        self.check_not_removed()?;
+
+        let proposal_type = proposal.proposal_type();
+
+        if !self.config.by_reference_allowed(proposal_type) {
+            return Err(MlsError::ProposalTypeRequiresByValue(proposal_type));
+        }
+
         let sender = Sender::Member(*self.private_tree.self_index);
 
         let auth_content = AuthenticatedContent::new_signed(
@@ -708,9 +1111,20 @@ where
 
         self.state
             .proposals
-            .insert(proposal_ref, proposal, auth_content.content.sender);
+            .insert(proposal_ref.clone(), proposal, auth_content.content.sender);
 
-        self.format_for_wire(auth_content).await
+        let expires_at = proposal_expiry(None, self.config.proposal_ttl());
+        self.state.proposals.set_expiry(&proposal_ref, expires_at);
+
+        let message = self.format_for_wire(auth_content).await?;
+
+        if let Err(e) = self.persist_pending_state().await {
+            // The proposal could not be committed by reference after a restart
+            self.state.proposals.remove(&proposal_ref);
+            return Err(e);
+        }
+
+        Ok(message)
     }
 
     /// Unique identifier for this group.
@@ -775,21 +1189,18 @@ where
         Ok((provisional_private_tree, new_signer))
     }
 
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn encrypt_group_secrets(
+    fn group_secrets(
         &self,
-        key_package: &KeyPackage,
         leaf_index: LeafIndex,
         joiner_secret: &JoinerSecret,
         path_secrets: Option<&Vec<Option<PathSecret>>>,
         #[cfg(feature = "psk")] psks: Vec<PreSharedKeyID>,
-        encrypted_group_info: &[u8],
-    ) -> Result<EncryptedGroupSecrets, MlsError> {
+    ) -> Result<GroupSecrets, MlsError> {
         let path_secret = path_secrets
             .map(|secrets| {
                 secrets
                     .get(
-                        tree_math::leaf_lca_level(*self.private_tree.self_index, *leaf_index)
+                        tree_math::leaf_lca_level(self.private_tree.self_index, leaf_index)
                             as usize
                             - 1,
                     )
@@ -802,26 +1213,82 @@ where
         #[cfg(not(feature = "psk"))]
         let psks = Vec::new();
 
-        let group_secrets = GroupSecrets {
+        Ok(GroupSecrets {
             joiner_secret: joiner_secret.clone(),
             path_secret,
             psks,
+        })
+    }
+
+    /// Encrypt the group secrets of each new member to the init key of its
+    /// key package. All secrets share the same HPKE info, which lets the
+    /// crypto provider compute values depending only on it once.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn encrypt_group_secrets(
+        &self,
+        new_members: &[(KeyPackage, GroupSecrets)],
+        encrypted_group_info: &[u8],
+    ) -> Result<Vec<EncryptedGroupSecrets>, MlsError> {
+        let plaintexts = new_members
+            .iter()
+            .map(|(_, secrets)| secrets.get_bytes().map(Zeroizing::new))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let recipients = new_members
+            .iter()
+            .zip(&plaintexts)
+            .map(|((key_package, _), plaintext)| (&key_package.hpke_init_key, plaintext.as_slice()))
+            .collect::<Vec<_>>();
+
+        let cs = &self.cipher_suite_provider;
+        let label = GroupSecrets::ENCRYPT_LABEL.as_bytes();
+
+        #[cfg(any(mls_build_async, not(feature = "rayon")))]
+        let ciphertexts =
+            encrypt_with_label_multi(cs, &recipients, label, encrypted_group_info).await?;
+
+        // Chunks of recipients are encrypted in parallel
+        #[cfg(not(any(mls_build_async, not(feature = "rayon"))))]
+        let ciphertexts = {
+            let threads = rayon::current_num_threads();
+            let chunk_size = (recipients.len() + threads - 1) / threads;
+
+            recipients
+                .par_chunks(chunk_size.max(1))
+                .map(|chunk| encrypt_with_label_multi(cs, chunk, label, encrypted_group_info))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
         };
 
-        let encrypted_group_secrets = group_secrets
-            .encrypt(
-                &self.cipher_suite_provider,
-                &key_package.hpke_init_key,
-                encrypted_group_info,
-            )
-            .await?;
+        #[cfg(any(mls_build_async, not(feature = "rayon")))]
+        let references = {
+            let mut references = Vec::with_capacity(new_members.len());
 
-        Ok(EncryptedGroupSecrets {
-            new_member: key_package
-                .to_reference(&self.cipher_suite_provider)
-                .await?,
-            encrypted_group_secrets,
-        })
+            for (key_package, _) in new_members {
+                references.push(key_package.to_reference(cs).await?);
+            }
+
+            references
+        };
+
+        #[cfg(not(any(mls_build_async, not(feature = "rayon"))))]
+        let references = new_members
+            .par_iter()
+            .map(|(key_package, _)| key_package.to_reference(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(references
+            .into_iter()
+            .zip(ciphertexts)
+            .map(
+                |(new_member, encrypted_group_secrets)| EncryptedGroupSecrets {
+                    new_member,
+                    encrypted_group_secrets,
+                },
+            )
+            .collect())
     }
 
     /// Create a proposal message that adds a new member to the group.
@@ -1043,6 +1510,12 @@ where
         cipher_suite: CipherSuite,
         extensions: ExtensionList,
     ) -> Result<Proposal, MlsError> {
+        // The credentials are validated when the new group is created
+        #[cfg(feature = "by_ref_proposal")]
+        if let Some(ext_senders) = extensions.get_as::<ExternalSendersExt>()? {
+            ext_senders.check_senders(&self.config.supported_credential_types())?;
+        }
+
         let group_id = group_id.map(Ok).unwrap_or_else(|| {
             self.cipher_suite_provider
                 .random_bytes_vec(self.cipher_suite_provider.kdf_extract_size())
@@ -1085,53 +1558,238 @@ where
         Proposal::GroupContextExtensions(extensions)
     }
 
-    /// Create a custom proposal message.
+    /// Create a proposal message that adds `sender` to the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// stored in the group context. All other group context extensions are
+    /// kept unchanged.
+    ///
+    /// This function fails with [`MlsError::RequiredCredentialNotFound`] if
+    /// the credential type of `sender` is not supported by every member of
+    /// the group.
     ///
     /// `authenticated_data` will be sent unencrypted along with the contents
     /// of the proposal message.
-    #[cfg(all(feature = "custom_proposal", feature = "by_ref_proposal"))]
+    #[cfg(feature = "by_ref_proposal")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn propose_custom(
+    pub async fn propose_add_external_sender(
         &mut self,
-        proposal: CustomProposal,
+        sender: SigningIdentity,
         authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
-        self.proposal_message(Proposal::Custom(proposal), authenticated_data)
-            .await
+        let proposal = self.add_external_sender_proposal(sender)?;
+        self.proposal_message(proposal, authenticated_data).await
     }
 
-    /// Delete all sent and received proposals cached for commit.
+    /// Create a proposal message that removes `sender` from the
+    /// [`ExternalSendersExt`](crate::extension::built_in::ExternalSendersExt)
+    /// stored in the group context. The extension is removed entirely once
+    /// its last sender is removed.
+    ///
+    /// `authenticated_data` will be sent unencrypted along with the contents
+    /// of the proposal message.
     #[cfg(feature = "by_ref_proposal")]
-    pub fn clear_proposal_cache(&mut self) {
-        self.state.proposals.clear()
-    }
-
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub(crate) async fn format_for_wire(
+    pub async fn propose_remove_external_sender(
         &mut self,
-        content: AuthenticatedContent,
+        sender: &SigningIdentity,
+        authenticated_data: Vec<u8>,
     ) -> Result<MlsMessage, MlsError> {
-        #[cfg(feature = "private_message")]
-        let payload = if content.wire_format == WireFormat::PrivateMessage {
-            MlsMessagePayload::Cipher(self.create_ciphertext(content).await?)
-        } else {
-            MlsMessagePayload::Plain(self.create_plaintext(content).await?)
-        };
-        #[cfg(not(feature = "private_message"))]
-        let payload = MlsMessagePayload::Plain(self.create_plaintext(content).await?);
-
-        Ok(MlsMessage::new(self.protocol_version(), payload))
+        let proposal = self.remove_external_sender_proposal(sender)?;
+        self.proposal_message(proposal, authenticated_data).await
     }
 
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn create_plaintext(
-        &self,
-        auth_content: AuthenticatedContent,
-    ) -> Result<PublicMessage, MlsError> {
-        let membership_tag = if matches!(auth_content.content.sender, Sender::Member(_)) {
-            let tag = self
-                .key_schedule
-                .get_membership_tag(&auth_content, self.context(), &self.cipher_suite_provider)
+    #[cfg(feature = "by_ref_proposal")]
+    fn add_external_sender_proposal(&self, sender: SigningIdentity) -> Result<Proposal, MlsError> {
+        let credential_type = sender.credential.credential_type();
+
+        let supported = self
+            .roster()
+            .members_iter()
+            .all(|member| member.capabilities.credentials.contains(&credential_type));
+
+        if !supported {
+            return Err(MlsError::RequiredCredentialNotFound(credential_type));
+        }
+
+        self.external_senders_proposal(|senders| {
+            if senders.contains(&sender) {
+                return Err(MlsError::ExternalSenderAlreadyAllowed);
+            }
+
+            senders.push(sender);
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn remove_external_sender_proposal(
+        &self,
+        sender: &SigningIdentity,
+    ) -> Result<Proposal, MlsError> {
+        self.external_senders_proposal(|senders| {
+            let index = senders
+                .iter()
+                .position(|s| s == sender)
+                .ok_or(MlsError::ExternalSenderNotFound)?;
+
+            senders.remove(index);
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    fn external_senders_proposal<F>(&self, update: F) -> Result<Proposal, MlsError>
+    where
+        F: FnOnce(&mut Vec<SigningIdentity>) -> Result<(), MlsError>,
+    {
+        let mut extensions = self.context().extensions.clone();
+
+        let mut senders = extensions
+            .get_as::<ExternalSendersExt>()?
+            .map(|ext| ext.allowed_senders)
+            .unwrap_or_default();
+
+        update(&mut senders)?;
+
+        if senders.is_empty() {
+            extensions.remove(ExtensionType::EXTERNAL_SENDERS);
+        } else {
+            extensions.set_from(ExternalSendersExt::new(senders))?;
+        }
+
+        Ok(self.group_context_extensions_proposal(extensions))
+    }
+
+    /// Create a custom proposal message.
+    ///
+    /// `authenticated_data` will be sent unencrypted along with the contents
+    /// of the proposal message.
+    #[cfg(all(feature = "custom_proposal", feature = "by_ref_proposal"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn propose_custom(
+        &mut self,
+        proposal: CustomProposal,
+        authenticated_data: Vec<u8>,
+    ) -> Result<MlsMessage, MlsError> {
+        self.proposal_message(Proposal::Custom(proposal), authenticated_data)
+            .await
+    }
+
+    /// Delete all sent and received proposals cached for commit.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn clear_proposal_cache(&mut self) {
+        self.state.proposals.clear()
+    }
+
+    /// Time after which the cached proposal identified by `proposal_ref` can
+    /// no longer be committed, or `None` if it is cached until the next commit.
+    ///
+    /// See [`ClientBuilder::proposal_ttl`](crate::client_builder::ClientBuilder::proposal_ttl).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_expiry(&self, proposal_ref: &ProposalRef) -> Option<MlsTime> {
+        self.state.proposals.expiry(proposal_ref)
+    }
+
+    /// Override the expiry of the cached proposal identified by
+    /// `proposal_ref`. With `None`, the proposal is cached until the next
+    /// commit.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn set_proposal_expiry(
+        &mut self,
+        proposal_ref: &ProposalRef,
+        expires_at: Option<MlsTime>,
+    ) -> Result<(), MlsError> {
+        if !self.state.proposals.contains(proposal_ref) {
+            return Err(MlsError::ProposalNotFound);
+        }
+
+        self.state.proposals.set_expiry(proposal_ref, expires_at);
+
+        Ok(())
+    }
+
+    /// Determine if the proposal identified by `proposal_ref` was dropped by
+    /// the proposal filter while preparing a commit within the configured
+    /// [rejection TTL](crate::client_builder::ClientBuilder::proposal_rejection_ttl).
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn is_proposal_rejected(&self, proposal_ref: &ProposalRef) -> bool {
+        self.rejected_proposals
+            .is_rejected(proposal_ref, self.current_epoch())
+    }
+
+    /// Cache of by-reference proposals that were dropped by the proposal filter
+    /// while preparing a commit.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_cache(&self) -> &ProposalRejectionCache {
+        &self.rejected_proposals
+    }
+
+    /// Mutable access to the cache of rejected proposals, allowing applications
+    /// to record proposals they rejected by other means.
+    #[cfg(feature = "by_ref_proposal")]
+    pub fn proposal_rejection_cache_mut(&mut self) -> &mut ProposalRejectionCache {
+        &mut self.rejected_proposals
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn format_for_wire(
+        &mut self,
+        content: AuthenticatedContent,
+    ) -> Result<MlsMessage, MlsError> {
+        let content_type = content.content.content_type();
+
+        #[cfg(feature = "private_message")]
+        let payload = if content.wire_format == WireFormat::PrivateMessage {
+            MlsMessagePayload::Cipher(self.create_ciphertext(content).await?)
+        } else {
+            MlsMessagePayload::Plain(self.create_plaintext(content).await?)
+        };
+        #[cfg(not(feature = "private_message"))]
+        let payload = MlsMessagePayload::Plain(self.create_plaintext(content).await?);
+
+        let message = MlsMessage::new(self.protocol_version(), payload);
+        let capacity = self.config.outbound_echo_cache_size();
+
+        if capacity > 0 && content_type != ContentType::Commit {
+            let hash = CommitHash::compute(&self.cipher_suite_provider, &message).await?;
+
+            self.outbound_echoes
+                .insert(hash, self.context().epoch, content_type, capacity);
+        }
+
+        Ok(message)
+    }
+
+    /// Description of `message` if it is one of the last messages sent by this
+    /// member, as configured by
+    /// [`ClientBuilder::outbound_echo_cache_size`](crate::client_builder::ClientBuilder::outbound_echo_cache_size).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn own_message_echo(
+        &self,
+        message: &MlsMessage,
+    ) -> Result<Option<OwnMessageEcho>, MlsError> {
+        let Some(epoch) = message.epoch() else {
+            return Ok(None);
+        };
+
+        if !self.outbound_echoes.has_epoch(epoch) {
+            return Ok(None);
+        }
+
+        let hash = CommitHash::compute(&self.cipher_suite_provider, message).await?;
+
+        Ok(self.outbound_echoes.find(&hash))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn create_plaintext(
+        &self,
+        auth_content: AuthenticatedContent,
+    ) -> Result<PublicMessage, MlsError> {
+        let membership_tag = if matches!(auth_content.content.sender, Sender::Member(_)) {
+            let tag = self
+                .key_schedule
+                .get_membership_tag(&auth_content, self.context(), &self.cipher_suite_provider)
                 .await?;
 
             Some(tag)
@@ -1172,11 +1830,25 @@ where
     ) -> Result<MlsMessage, MlsError> {
         // A group member that has observed one or more proposals within an epoch MUST send a Commit message
         // before sending application data
+        self.check_not_removed()?;
+
         #[cfg(feature = "by_ref_proposal")]
         if !self.state.proposals.is_empty() {
             return Err(MlsError::CommitRequired);
         }
 
+        self.check_aead_usage()?;
+
+        if !self
+            .config
+            .unsupported_credential_policy()
+            .allows_sending_to_unverified()
+        {
+            if let Some(member) = self.unverified_members().first() {
+                return Err(MlsError::UnverifiedRecipient(member.index));
+            }
+        }
+
         let auth_content = AuthenticatedContent::new_signed(
             &self.cipher_suite_provider,
             self.context(),
@@ -1198,9 +1870,14 @@ where
         message: &PrivateMessage,
     ) -> Result<AuthenticatedContent, MlsError> {
         let epoch_id = message.epoch;
+        let max_ratchet_skip = self.config.max_ratchet_skip();
+
+        let decode_limits = self.config.decode_limits();
 
         let auth_content = if epoch_id == self.context().epoch {
             let content = CiphertextProcessor::new(self, self.cipher_suite_provider.clone())
+                .with_max_ratchet_skip(max_ratchet_skip)
+                .with_decode_limits(decode_limits)
                 .open(message)
                 .await?;
 
@@ -1220,11 +1897,13 @@ where
             {
                 let epoch = self
                     .state_repo
-                    .get_epoch_mut(epoch_id)
+                    .get_epoch_mut(epoch_id, &self.cipher_suite_provider)
                     .await?
                     .ok_or(MlsError::EpochNotFound)?;
 
                 let content = CiphertextProcessor::new(epoch, self.cipher_suite_provider.clone())
+                    .with_max_ratchet_skip(max_ratchet_skip)
+                    .with_decode_limits(decode_limits)
                     .open(message)
                     .await?;
 
@@ -1272,7 +1951,8 @@ where
     /// commit message is processed using [`Group::process_incoming_message`]
     /// before [`Group::apply_pending_commit`] is called.
     pub fn clear_pending_commit(&mut self) {
-        self.pending_commit = None
+        self.pending_commit = None;
+        self.prewarmed_key_schedule = None;
     }
 
     /// Process an inbound message for this group.
@@ -1288,6 +1968,22 @@ where
     pub async fn process_incoming_message(
         &mut self,
         message: MlsMessage,
+    ) -> Result<ReceivedMessage, MlsError> {
+        if !self.config.message_error_context() {
+            return self.process_incoming_message_inner(message).await;
+        }
+
+        let context = MessageContext::new(&self.cipher_suite_provider, &message).await;
+
+        self.process_incoming_message_inner(message)
+            .await
+            .map_err(|e| context.wrap(e))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn process_incoming_message_inner(
+        &mut self,
+        message: MlsMessage,
     ) -> Result<ReceivedMessage, MlsError> {
         if let Some(pending) = &self.pending_commit {
             let message_hash = CommitHash::compute(&self.cipher_suite_provider, &message).await?;
@@ -1299,6 +1995,10 @@ where
             }
         }
 
+        if let Some(echo) = self.own_message_echo(&message).await? {
+            return Ok(ReceivedMessage::OwnMessageEcho(echo));
+        }
+
         MessageProcessor::process_incoming_message(
             self,
             message,
@@ -1330,6 +2030,136 @@ where
         message: MlsMessage,
         time: MlsTime,
     ) -> Result<ReceivedMessage, MlsError> {
+        if let Some(echo) = self.own_message_echo(&message).await? {
+            return Ok(ReceivedMessage::OwnMessageEcho(echo));
+        }
+
+        let context = match self.config.message_error_context() {
+            true => Some(MessageContext::new(&self.cipher_suite_provider, &message).await),
+            false => None,
+        };
+
+        MessageProcessor::process_incoming_message_with_time(
+            self,
+            message,
+            #[cfg(feature = "by_ref_proposal")]
+            true,
+            Some(time),
+        )
+        .await
+        .map_err(|e| match context {
+            Some(context) => context.wrap(e),
+            None => e,
+        })
+    }
+
+    /// Process an inbound message for this group, holding it back if it
+    /// arrived before the messages it depends on.
+    ///
+    /// Messages sent in a future epoch are buffered instead of being
+    /// rejected, as are private messages too far ahead of the previous
+    /// messages of their sender. Buffered messages are processed as soon as
+    /// the messages they depend on were processed by this function. All
+    /// messages processed by a single call are returned in processing order,
+    /// and an empty result means that `message` was buffered.
+    ///
+    /// `time` is the time at which `message` was received. It is used to
+    /// expire buffered messages according to the
+    /// [`MessageBufferLimits`] of the client and is passed to the identity
+    /// provider as in [`Group::process_incoming_message_with_time`].
+    ///
+    /// Buffered messages that fail to be processed once their dependencies
+    /// arrived are discarded. The buffer is held in memory only and is not
+    /// persisted by [`Group::write_to_storage`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub async fn process_incoming_message_buffered(
+        &mut self,
+        message: MlsMessage,
+        time: MlsTime,
+    ) -> Result<Vec<ReceivedMessage>, MlsError> {
+        let limits = self.config.message_buffer_limits();
+        self.message_buffer.expire(time, &limits);
+
+        let context = self.context();
+        let current_epoch = context.epoch;
+
+        if let Some(epoch) = message.epoch().filter(|epoch| *epoch > current_epoch) {
+            if message.group_id() != Some(&context.group_id)
+                || epoch - current_epoch > limits.max_epochs_ahead
+            {
+                return Err(MlsError::InvalidEpoch);
+            }
+
+            self.message_buffer.insert(message, epoch, time, &limits)?;
+
+            return Ok(Vec::new());
+        }
+
+        let retry = (message.wire_format() == WireFormat::PrivateMessage).then(|| message.clone());
+
+        let res = self.process_message_at(message, time).await;
+
+        if let (Err(MlsError::InvalidFutureGeneration(_)), Some(message)) = (&res, retry) {
+            self.message_buffer
+                .insert(message, current_epoch, time, &limits)?;
+
+            return Ok(Vec::new());
+        }
+
+        let mut processed = vec![res?];
+
+        loop {
+            let mut progress = false;
+
+            for buffered in self.message_buffer.take_ready(self.context().epoch) {
+                match self
+                    .process_message_at(buffered.message.clone(), time)
+                    .await
+                {
+                    Ok(received) => {
+                        processed.push(received);
+                        progress = true;
+                    }
+                    Err(MlsError::InvalidFutureGeneration(_)) => {
+                        self.message_buffer.restore(buffered)
+                    }
+                    Err(_) => {}
+                }
+            }
+
+            if !progress {
+                return Ok(processed);
+            }
+        }
+    }
+
+    /// Number of messages held back by
+    /// [`Group::process_incoming_message_buffered`].
+    pub fn buffered_message_count(&self) -> usize {
+        self.message_buffer.len()
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn process_message_at(
+        &mut self,
+        message: MlsMessage,
+        time: MlsTime,
+    ) -> Result<ReceivedMessage, MlsError> {
+        if let Some(pending) = &self.pending_commit {
+            let message_hash = CommitHash::compute(&self.cipher_suite_provider, &message).await?;
+
+            if message_hash == pending.commit_message_hash {
+                let message_description = self.apply_pending_commit().await?;
+
+                return Ok(ReceivedMessage::Commit(message_description));
+            }
+        }
+
+        if let Some(echo) = self.own_message_echo(&message).await? {
+            return Ok(ReceivedMessage::OwnMessageEcho(echo));
+        }
+
         MessageProcessor::process_incoming_message_with_time(
             self,
             message,
@@ -1416,7 +2246,7 @@ where
 
         let mut info = GroupInfo {
             group_context: self.context().clone(),
-            extensions: initial_extensions,
+            extensions: self.group_info_extensions(self.context(), initial_extensions)?,
             confirmation_tag: self.state.confirmation_tag.clone(),
             signer: self.private_tree.self_index,
             signature: Vec::new(),
@@ -1433,6 +2263,24 @@ where
         ))
     }
 
+    /// Application extensions of a group info for the epoch of `group_context`,
+    /// overridden by `extensions`.
+    pub(crate) fn group_info_extensions(
+        &self,
+        group_context: &GroupContext,
+        extensions: ExtensionList,
+    ) -> Result<ExtensionList, MlsError> {
+        let mut group_info_extensions = self
+            .config
+            .mls_rules()
+            .group_info_extensions(group_context)
+            .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        group_info_extensions.extend(extensions.iter().cloned());
+
+        Ok(group_info_extensions)
+    }
+
     /// Get the current group context summarizing various information about the group.
     #[inline(always)]
     pub fn context(&self) -> &GroupContext {
@@ -1459,6 +2307,58 @@ where
             .map(Into::into)
     }
 
+    /// Encrypt the application message receive keys of the current epoch to
+    /// the escrow key of the [`MessageEscrowExt`](crate::extension::MessageEscrowExt)
+    /// group context extension, for applications required to retain messages.
+    ///
+    /// This weakens forward secrecy: the holder of the escrow secret key can
+    /// decrypt the application messages other members send in the rest of
+    /// the current epoch, as described in [`EscrowedReceiveKeys`]. The keys
+    /// of this member, of handshake messages and of messages received before
+    /// this call are not escrowed, nor is any other epoch secret such as the
+    /// init or commit secret. Keys kept for out of order messages are not
+    /// escrowed either.
+    ///
+    /// Fails with [`MlsError::MessageEscrowNotEnabled`] unless the group
+    /// context, which all members agree on, contains the escrow key.
+    #[cfg(feature = "message_escrow")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn escrow_receive_keys(&mut self) -> Result<EscrowedReceiveKeys, MlsError> {
+        let escrow_key = escrow::message_escrow_key(self.context())?;
+        let self_index = self.private_tree.self_index;
+
+        let senders = self
+            .current_epoch_tree()
+            .non_empty_leaves()
+            .map(|(index, _)| index)
+            .filter(|index| *index != self_index)
+            .collect::<Vec<_>>();
+
+        let mut ratchets = Vec::with_capacity(senders.len());
+
+        for leaf_index in senders {
+            let (generation, secret) = self
+                .epoch_secrets
+                .secret_tree
+                .application_ratchet(&self.cipher_suite_provider, leaf_index.node_index())
+                .await?;
+
+            ratchets.push(escrow::EscrowedRatchet {
+                leaf_index: *leaf_index,
+                generation,
+                secret,
+            });
+        }
+
+        EscrowedReceiveKeys::seal(
+            &self.cipher_suite_provider,
+            self.context(),
+            escrow_key,
+            ratchets,
+        )
+        .await
+    }
+
     /// Export the current epoch's ratchet tree in serialized format.
     ///
     /// This function is used to provide the current group tree to new members
@@ -1467,6 +2367,31 @@ where
         ExportedTree::new_borrowed(&self.current_epoch_tree().nodes)
     }
 
+    /// Compute the tree hash of the current epoch's ratchet tree without
+    /// modifying the group.
+    ///
+    /// The result is the tree hash of the group context. It is answered from
+    /// the cached hashes of the tree when they are initialized, which is the
+    /// case after processing a commit and after
+    /// [`Group::ensure_tree_hashes`]. Otherwise every node is hashed.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compute_tree_hash(&self) -> Result<Vec<u8>, MlsError> {
+        self.current_epoch_tree()
+            .compute_tree_hash(&self.cipher_suite_provider)
+            .await
+    }
+
+    /// Initialize the cached hashes of the current epoch's ratchet tree, so
+    /// that [`Group::compute_tree_hash`] can be answered from a shared
+    /// reference without hashing the whole tree.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn ensure_tree_hashes(&mut self) -> Result<(), MlsError> {
+        self.state
+            .public_tree
+            .ensure_hashes(&self.cipher_suite_provider)
+            .await
+    }
+
     /// Current version of the MLS protocol in use by this group.
     pub fn protocol_version(&self) -> ProtocolVersion {
         self.context().protocol_version
@@ -1477,11 +2402,50 @@ where
         self.context().cipher_suite
     }
 
+    /// Per-group overrides of the client configuration in use by this group.
+    pub fn config_overrides(&self) -> &GroupConfigOverrides {
+        &self.config_overrides
+    }
+
     /// Current roster
     pub fn roster(&self) -> Roster<'_> {
         self.group_state().public_tree.roster()
     }
 
+    /// Members whose leaf node is close to or outside of its lifetime at
+    /// `time`, according to the lifetime policy of this group. See
+    /// [`Roster::lifetime_warnings`].
+    pub fn lifetime_warnings(&self, time: MlsTime) -> Vec<LifetimeWarning> {
+        self.roster()
+            .lifetime_warnings(time, &self.lifetime_policy())
+    }
+
+    /// Members using a credential type that is not supported by the identity
+    /// provider, whose credential was therefore not validated. Such members
+    /// are only accepted with
+    /// [`UnsupportedCredentialPolicy::AllowUnverified`](crate::identity::UnsupportedCredentialPolicy::AllowUnverified)
+    /// or a similar policy.
+    pub fn unverified_members(&self) -> &[Member] {
+        &self.unverified_members
+    }
+
+    fn update_unverified_members(&mut self) {
+        self.unverified_members = if self
+            .config
+            .unsupported_credential_policy()
+            .allows_unverified()
+        {
+            let supported = self.config.supported_credential_types();
+
+            self.roster()
+                .members_iter()
+                .filter(|m| !supported.contains(&m.signing_identity.credential.credential_type()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+    }
+
     /// Determines equality of two different groups internal states.
     /// Useful for testing.
     ///
@@ -1525,6 +2489,7 @@ where
         self.config
             .mls_rules()
             .encryption_options(&self.roster(), self.group_context().extensions())
+            .map(|options| self.config_overrides.encryption_options(options))
             .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))
     }
 
@@ -1541,7 +2506,7 @@ where
             .secret_tree
             .next_message_key(
                 &self.cipher_suite_provider,
-                crate::tree_kem::node::NodeIndex::from(self.private_tree.self_index),
+                self.private_tree.self_index.node_index(),
                 KeyType::Application,
             )
             .await
@@ -1558,22 +2523,65 @@ where
             .secret_tree
             .message_key_generation(
                 &self.cipher_suite_provider,
-                crate::tree_kem::node::NodeIndex::from(sender),
+                crate::tree_kem::node::NodeIndex::new(sender),
                 KeyType::Application,
                 generation,
+                self.config.max_ratchet_skip(),
             )
             .await
     }
-}
 
-#[cfg(feature = "private_message")]
-impl<C> GroupStateProvider for Group<C>
-where
-    C: ClientConfig + Clone,
-{
-    fn group_context(&self) -> &GroupContext {
-        self.context()
-    }
+    /// Move the application message ratchet of the member at leaf index `sender`
+    /// forward so that the next message accepted from it has generation
+    /// `generation`.
+    ///
+    /// This is meant for applications that know that messages from `sender`
+    /// were legitimately dropped, for example because of a gap in delivery
+    /// the application is aware of, and that would otherwise be rejected by
+    /// [`ClientBuilder::max_ratchet_skip`](crate::client_builder::ClientBuilder::max_ratchet_skip).
+    /// Keys of the skipped generations are never derived, so the skipped messages
+    /// can no longer be decrypted. Generations the ratchet already moved past are
+    /// ignored.
+    ///
+    /// A single call skips at most `max_ratchet_skip` generations as well, larger
+    /// gaps fail with [`MlsError::InvalidFutureGeneration`] and must be covered
+    /// by several calls.
+    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn fast_forward_sender(
+        &mut self,
+        sender: u32,
+        generation: u32,
+    ) -> Result<(), MlsError> {
+        let sender = LeafIndex(sender);
+
+        if sender == self.private_tree.self_index {
+            return Err(MlsError::InvalidSender);
+        }
+
+        self.state.public_tree.get_leaf_node(sender)?;
+
+        self.epoch_secrets
+            .secret_tree
+            .fast_forward(
+                &self.cipher_suite_provider,
+                sender.node_index(),
+                KeyType::Application,
+                generation,
+                self.config.max_ratchet_skip(),
+            )
+            .await
+    }
+}
+
+#[cfg(feature = "private_message")]
+impl<C> GroupStateProvider for Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    fn group_context(&self) -> &GroupContext {
+        self.context()
+    }
 
     fn self_index(&self) -> LeafIndex {
         self.private_tree.self_index
@@ -1599,7 +2607,7 @@ where
     C: ClientConfig + Clone,
 {
     type MlsRules = C::MlsRules;
-    type IdentityProvider = C::IdentityProvider;
+    type IdentityProvider = PolicyIdentityProvider<C::IdentityProvider>;
     type PreSharedKeyStorage = C::PskStore;
     type OutputType = ReceivedMessage;
     type CipherSuiteProvider = <C::CryptoProvider as CryptoProvider>::CipherSuiteProvider;
@@ -1722,36 +2730,70 @@ where
             _ => self.key_schedule.clone(),
         };
 
-        #[cfg(feature = "psk")]
-        let (psk, _) = self
-            .get_psk(&provisional_state.applied_proposals.psks)
-            .await?;
+        // The key schedule prewarmed when creating the pending commit is only
+        // used if it was derived for the same group context
+        let prewarmed = self.prewarmed_key_schedule.take().filter(|p| {
+            self.pending_commit.is_some() && p.group_context == provisional_state.group_context
+        });
+
+        let (new_key_schedule, confirmation_key, epoch_secrets) = match prewarmed {
+            Some(prewarmed) => (
+                prewarmed.key_schedule,
+                prewarmed.confirmation_key,
+                prewarmed.epoch_secrets,
+            ),
+            None => {
+                #[cfg(feature = "psk")]
+                let (psk, _) = self
+                    .get_psk(&provisional_state.applied_proposals.psks)
+                    .await?;
 
-        #[cfg(not(feature = "psk"))]
-        let psk = self.get_psk();
+                #[cfg(not(feature = "psk"))]
+                let psk = self.get_psk();
 
-        let key_schedule_result = KeySchedule::from_key_schedule(
-            &key_schedule,
-            &commit_secret,
-            &provisional_state.group_context,
-            #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-            provisional_state.public_tree.total_leaf_count(),
-            &psk,
-            &self.cipher_suite_provider,
-        )
-        .await?;
+                let result = KeySchedule::from_key_schedule(
+                    &key_schedule,
+                    &commit_secret,
+                    &provisional_state.group_context,
+                    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
+                    provisional_state.public_tree.total_leaf_count(),
+                    &psk,
+                    &self.cipher_suite_provider,
+                )
+                .await?;
+
+                (
+                    result.key_schedule,
+                    result.confirmation_key,
+                    result.epoch_secrets,
+                )
+            }
+        };
 
         // Use the confirmation_key for the new epoch to compute the confirmation tag for
         // this message, as described below, and verify that it is the same as the
         // confirmation_tag field in the MlsPlaintext object.
-        let new_confirmation_tag = ConfirmationTag::create(
-            &key_schedule_result.confirmation_key,
-            &provisional_state.group_context.confirmed_transcript_hash,
-            &self.cipher_suite_provider,
-        )
-        .await?;
+        let matches = confirmation_tag
+            .matches(
+                &confirmation_key,
+                &provisional_state.group_context.confirmed_transcript_hash,
+                &self.cipher_suite_provider,
+            )
+            .await?;
+
+        #[cfg(feature = "psk")]
+        if !matches && !provisional_state.applied_proposals.psks.is_empty() {
+            let psks = provisional_state
+                .applied_proposals
+                .psks
+                .iter()
+                .map(|p| p.proposal.psk.clone())
+                .collect::<Vec<_>>();
+
+            return Err(psk_failure(&psks, PskFailureKind::ValueMismatch));
+        }
 
-        if &new_confirmation_tag != confirmation_tag {
+        if !matches {
             return Err(MlsError::InvalidConfirmationTag);
         }
 
@@ -1774,12 +2816,21 @@ where
         #[cfg(feature = "prior_epoch")]
         self.state_repo.insert(past_epoch).await?;
 
-        self.epoch_secrets = key_schedule_result.epoch_secrets;
+        // Proposals left out of received commits are reported in the state update
+        // rather than recorded, since the committer may never have received them
+        #[cfg(feature = "by_ref_proposal")]
+        self.rejected_proposals.prune(self.current_epoch());
+
+        self.epoch_secrets = epoch_secrets;
         self.state.context = provisional_state.group_context;
         self.state.interim_transcript_hash = interim_transcript_hash;
-        self.key_schedule = key_schedule_result.key_schedule;
+        self.key_schedule = new_key_schedule;
         self.state.public_tree = provisional_state.public_tree;
-        self.state.confirmation_tag = new_confirmation_tag;
+        self.state.confirmation_tag = confirmation_tag.clone();
+        self.update_unverified_members();
+
+        #[cfg(feature = "prior_epoch")]
+        self.update_epoch_chain().await?;
 
         // Clear the proposals list
         #[cfg(feature = "by_ref_proposal")]
@@ -1800,8 +2851,33 @@ where
         self.config.mls_rules()
     }
 
+    fn decode_limits(&self) -> DecodeLimits {
+        self.config.decode_limits()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.config_overrides
+            .lifetime_policy(self.config.lifetime_policy())
+    }
+
+    async fn authorize_commit(
+        &self,
+        provisional_state: &ProvisionalState,
+        _committer: LeafIndex,
+        is_external: bool,
+        _has_path: bool,
+    ) -> Result<(), MlsError> {
+        if !is_external {
+            return Ok(());
+        }
+
+        self.config_overrides
+            .external_commit_policy()
+            .check(&provisional_state.applied_proposals)
+    }
+
     fn identity_provider(&self) -> Self::IdentityProvider {
-        self.config.identity_provider()
+        self.config.member_identity_provider()
     }
 
     fn psk_storage(&self) -> Self::PreSharedKeyStorage {
@@ -1825,11 +2901,29 @@ where
             && self.pending_commit.is_none())
     }
 
+    fn removed_from_group(&self) -> bool {
+        self.removed
+    }
+
+    fn set_removed_from_group(&mut self) {
+        self.removed = true;
+    }
+
     #[cfg(feature = "private_message")]
     fn min_epoch_available(&self) -> Option<u64> {
         None
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    fn proposal_ttl(&self) -> Option<u64> {
+        self.config.proposal_ttl()
+    }
+
+    #[cfg(feature = "custom_proposal")]
+    fn custom_proposal_metadata(&self, proposal_type: ProposalType) -> CustomProposalMetadata {
+        self.config.custom_proposal_metadata(proposal_type)
+    }
+
     fn cipher_suite_provider(&self) -> &Self::CipherSuiteProvider {
         &self.cipher_suite_provider
     }
@@ -1845,10 +2939,11 @@ mod tests {
             test_client_with_key_pkg, TestClientBuilder, TEST_CIPHER_SUITE,
             TEST_CUSTOM_PROPOSAL_TYPE, TEST_PROTOCOL_VERSION,
         },
+        client::{LeafDataKind, LeafNodeSourceKind},
         client_builder::{test_utils::TestClientConfig, ClientBuilder, MlsConfig},
         crypto::test_utils::TestCryptoProvider,
         group::{
-            mls_rules::{CommitDirection, CommitSource},
+            mls_rules::{CommitDirection, CommitSource, TreeTruncation},
             proposal_filter::ProposalBundle,
         },
         identity::{
@@ -1859,32 +2954,38 @@ mod tests {
         mls_rules::CommitOptions,
         tree_kem::{
             leaf_node::{test_utils::get_test_capabilities, LeafNodeSource},
-            UpdatePathNode,
+            node::NodeIndex,
+            Lifetime, UpdatePathNode,
         },
     };
 
     #[cfg(any(feature = "private_message", feature = "custom_proposal"))]
     use crate::group::mls_rules::DefaultMlsRules;
 
-    #[cfg(feature = "prior_epoch")]
     use crate::group::padding::PaddingMode;
 
-    use crate::{extension::RequiredCapabilitiesExt, key_package::test_utils::test_key_package};
+    #[cfg(feature = "private_message")]
+    use crate::mls_rules::{MessageDecision, MessageRejection};
+
+    use crate::{
+        extension::{test_utils::TestExtension, RequiredCapabilitiesExt},
+        key_package::test_utils::test_key_package,
+    };
 
-    #[cfg(all(feature = "by_ref_proposal", feature = "custom_proposal"))]
     use super::test_utils::test_group_custom_config;
 
     #[cfg(feature = "psk")]
-    use crate::{client::Client, psk::PreSharedKey};
+    use crate::{
+        client::{test_utils::test_client_with_key_pkg_custom, Client},
+        extension::RequiredJoinPskExt,
+        psk::PreSharedKey,
+    };
 
     #[cfg(any(feature = "by_ref_proposal", feature = "private_message"))]
     use crate::group::test_utils::random_bytes;
 
     #[cfg(feature = "by_ref_proposal")]
-    use crate::{
-        extension::test_utils::TestExtension, identity::test_utils::get_test_basic_credential,
-        time::MlsTime,
-    };
+    use crate::{identity::test_utils::get_test_basic_credential, time::MlsTime};
 
     use super::{
         test_utils::{
@@ -1900,7 +3001,7 @@ mod tests {
     use mls_rs_core::identity::{Credential, CredentialType, CustomCredential};
 
     #[cfg(feature = "by_ref_proposal")]
-    use mls_rs_core::identity::CertificateChain;
+    use mls_rs_core::identity::{BasicCredential, CertificateChain};
 
     #[cfg(feature = "state_update")]
     use itertools::Itertools;
@@ -1916,6 +3017,19 @@ mod tests {
 
     use mls_rs_core::extension::MlsExtension;
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tree_hash_is_computed_from_shared_reference() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let tree_hash = alice.group.compute_tree_hash().await.unwrap();
+        assert_eq!(tree_hash, alice.group.context().tree_hash);
+
+        bob.group.ensure_tree_hashes().await.unwrap();
+        let tree_hash = bob.group.compute_tree_hash().await.unwrap();
+        assert_eq!(tree_hash, bob.group.context().tree_hash);
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_create_group() {
         for (protocol_version, cipher_suite) in ProtocolVersion::all().flat_map(|p| {
@@ -1951,6 +3065,99 @@ mod tests {
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn config_overrides_are_applied_and_persisted() {
+        let (alice_client, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let lifetime = Lifetime::years(3).unwrap();
+
+        let required_capabilities = RequiredCapabilitiesExt::new(vec![], vec![], vec![]);
+
+        let overrides = GroupConfigOverrides::new()
+            .with_lifetime(lifetime.clone())
+            .with_commit_options(CommitOptions::new().with_allow_external_commit(true))
+            .with_padding_mode(PaddingMode::None)
+            .with_required_capabilities(required_capabilities.clone());
+
+        let mut alice = alice_client
+            .create_group_with_overrides(Default::default(), overrides.clone())
+            .await
+            .unwrap();
+
+        let group_extensions = &alice.context().extensions;
+
+        assert_eq!(
+            group_extensions
+                .get_as::<RequiredCapabilitiesExt>()
+                .unwrap(),
+            Some(required_capabilities)
+        );
+
+        assert_matches!(
+            &alice.current_user_leaf_node().unwrap().leaf_node_source,
+            LeafNodeSource::KeyPackage(lt) if lt == &lifetime
+        );
+
+        let commit = alice.commit(vec![]).await.unwrap();
+        assert!(commit.external_commit_group_info.is_some());
+        alice.apply_pending_commit().await.unwrap();
+
+        alice.write_to_storage().await.unwrap();
+
+        let mut alice = alice_client.load_group(alice.group_id()).await.unwrap();
+
+        assert_eq!(alice.config_overrides(), &overrides);
+
+        let commit = alice.commit(vec![]).await.unwrap();
+        assert!(commit.external_commit_group_info.is_some());
+
+        // Groups created without overrides use the client configuration
+        let mut bob = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let commit = bob.group.commit(vec![]).await.unwrap();
+        assert!(commit.external_commit_group_info.is_none());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn prewarmed_key_schedule_is_used_for_own_commit() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.prewarm_key_schedule(true)
+        })
+        .await;
+
+        // Adding bob grows the tree, the prewarmed secret tree must cover him
+        let (mut bob, _) = alice.join("bob").await;
+        assert!(alice.group.prewarmed_key_schedule.is_none());
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        assert!(alice.group.prewarmed_key_schedule.is_some());
+
+        alice.group.apply_pending_commit().await.unwrap();
+        assert!(alice.group.prewarmed_key_schedule.is_none());
+
+        bob.process_message(commit).await.unwrap();
+
+        assert_eq!(
+            alice.group.epoch_authenticator().unwrap(),
+            bob.group.epoch_authenticator().unwrap()
+        );
+
+        #[cfg(feature = "private_message")]
+        {
+            let message = alice
+                .group
+                .encrypt_application_message(b"hello", vec![])
+                .await
+                .unwrap();
+
+            bob.group.process_incoming_message(message).await.unwrap();
+        }
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.group.clear_pending_commit();
+        assert!(alice.group.prewarmed_key_schedule.is_none());
+    }
+
     #[cfg(feature = "private_message")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_pending_proposals_application_data() {
@@ -2188,6 +3395,7 @@ mod tests {
             None,
             bob_client.config,
             bob_client.signer.unwrap(),
+            Default::default(),
         )
         .await
         .map(|_| ());
@@ -2568,6 +3776,44 @@ mod tests {
         assert_matches!(res, Err(MlsError::UnencryptedApplicationMessage));
     }
 
+    #[cfg(all(feature = "state_update", feature = "psk"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn state_update_reports_resumption_psks() {
+        use crate::psk::{PskUsage, ResumptionPskKind};
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let epoch = alice.group.current_epoch();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .add_resumption_psk(epoch)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let alice_update = alice.process_pending_commit().await.unwrap().state_update;
+
+        let ReceivedMessage::Commit(description) =
+            bob.process_message(commit.commit_message).await.unwrap()
+        else {
+            panic!("expected a commit");
+        };
+
+        let expected = vec![PskUsage::Resumption {
+            kind: ResumptionPskKind::Application,
+            group_id: alice.group.group_id().to_vec(),
+            epoch,
+        }];
+
+        assert_eq!(description.state_update.psks(), expected);
+        assert_eq!(alice_update.psks(), expected);
+        assert!(description.state_update.added_psks().is_empty());
+    }
+
     #[cfg(feature = "state_update")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_state_update() {
@@ -2683,6 +3929,13 @@ mod tests {
                 .collect::<Vec<_>>()
         );
 
+        assert_eq!(
+            state_update_alice.psks,
+            (0..5)
+                .map(|i| crate::psk::PskUsage::External(ExternalPskId::new(vec![i])))
+                .collect::<Vec<_>>()
+        );
+
         let payload = bob
             .process_message(commit_output.commit_message)
             .await
@@ -2706,7 +3959,7 @@ mod tests {
 
         let bob = TestClientBuilder::new_for_test()
             .signing_identity(bob_identity, secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let (bob_group, commit) = bob
             .external_commit_builder()
@@ -2751,7 +4004,7 @@ mod tests {
 
         let bob = TestClientBuilder::new_for_test()
             .signing_identity(bob_identity, secret_key, TEST_CIPHER_SUITE)
-            .build();
+            .build_unchecked();
 
         let (_, commit) = bob
             .external_commit_builder()
@@ -2881,6 +4134,136 @@ mod tests {
         assert!(alice.group.private_tree.secret_keys[1].is_none());
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn filtered_by_ref_proposals_are_remembered() {
+        let mut alice =
+            test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |builder| {
+                builder.proposal_rejection_ttl(1)
+            })
+            .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+        let (mut charlie, commit) = alice.join("charlie").await;
+        bob.process_message(commit).await.unwrap();
+
+        let bob_remove = bob.group.propose_remove(2, vec![]).await.unwrap();
+        let alice_remove = alice.group.propose_remove(2, vec![]).await.unwrap();
+        alice.process_message(bob_remove.clone()).await.unwrap();
+        charlie.process_message(bob_remove).await.unwrap();
+        charlie.process_message(alice_remove).await.unwrap();
+
+        let commit_output = alice.group.commit(vec![]).await.unwrap();
+
+        let rejected = commit_output
+            .unused_proposals
+            .iter()
+            .find_map(|p| match &p.source {
+                crate::mls_rules::ProposalSource::ByReference(r) => Some(r.clone()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert!(alice.group.is_proposal_rejected(&rejected));
+
+        alice.process_pending_commit().await.unwrap();
+        assert!(alice.group.is_proposal_rejected(&rejected));
+
+        alice.group.commit(vec![]).await.unwrap();
+        alice.process_pending_commit().await.unwrap();
+        assert!(!alice.group.is_proposal_rejected(&rejected));
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "state_update"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn by_ref_proposals_left_out_of_received_commits_are_reported_not_remembered() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |c| {
+                c.0.settings.proposal_rejection_ttl = Some(1)
+            })
+            .await
+            .unwrap();
+
+        let (mut charlie, commit) = alice.join("charlie").await;
+        bob.process_message(commit).await.unwrap();
+
+        // Alice does not receive the update and commits without it
+        let update = charlie.group.propose_update(vec![]).await.unwrap();
+        let received = bob.process_message(update).await.unwrap();
+
+        let ReceivedMessage::Proposal(ProposalMessageDescription { proposal_ref, .. }) = received
+        else {
+            panic!("expected a proposal");
+        };
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+
+        let ReceivedMessage::Commit(CommitMessageDescription { state_update, .. }) =
+            bob.process_message(commit).await.unwrap()
+        else {
+            panic!("expected a commit");
+        };
+
+        assert_matches!(
+            state_update.unused_proposals(),
+            [crate::mls_rules::ProposalInfo {
+                source: crate::mls_rules::ProposalSource::ByReference(r),
+                ..
+            }] if r == &proposal_ref
+        );
+
+        assert!(!bob.group.is_proposal_rejected(&proposal_ref));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn by_value_proposal_types_are_not_sent_or_accepted_by_reference() {
+        let mut alice =
+            test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |builder| {
+                builder.by_value_proposal_type(ProposalType::REMOVE)
+            })
+            .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+        let (_, commit) = alice.join("charlie").await;
+        bob.process_message(commit).await.unwrap();
+
+        let res = alice.group.propose_remove(2, vec![]).await;
+        assert_matches!(
+            res,
+            Err(MlsError::ProposalTypeRequiresByValue(ProposalType::REMOVE))
+        );
+
+        // Alice does not commit Bob's remove proposal by reference
+        let bob_remove = bob.group.propose_remove(2, vec![]).await.unwrap();
+        alice.process_message(bob_remove).await.unwrap();
+
+        let bob_update = bob.group.propose_update(vec![]).await.unwrap();
+        alice.process_message(bob_update).await.unwrap();
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.process_pending_commit().await.unwrap();
+        bob.process_message(commit).await.unwrap();
+
+        assert_eq!(alice.group.roster().members_iter().count(), 3);
+
+        // Alice processes Bob's commit referencing a remove proposal
+        let bob_remove = bob.group.propose_remove(2, vec![]).await.unwrap();
+        alice.process_message(bob_remove).await.unwrap();
+
+        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
+        bob.process_pending_commit().await.unwrap();
+        alice.process_message(commit).await.unwrap();
+
+        assert_eq!(alice.group.roster().members_iter().count(), 2);
+        assert_eq!(
+            alice.group.epoch_authenticator().unwrap(),
+            bob.group.epoch_authenticator().unwrap()
+        );
+    }
+
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn only_selected_members_of_the_original_group_can_join_subgroup() {
@@ -2973,6 +4356,27 @@ mod tests {
         );
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn joining_with_bounded_tree_validation_parallelism() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        for name in ["bob", "carol", "dave", "eve"] {
+            alice_group.join(name).await;
+        }
+
+        for (name, max_parallelism) in [("frank", 1), ("grace", 3)] {
+            let (new_group, _) = alice_group
+                .join_with_custom_config(name, false, |c| {
+                    c.0.settings.tree_validation_parallelism = Some(max_parallelism)
+                })
+                .await
+                .unwrap();
+
+            let members = new_group.group.roster().members_iter().count();
+            assert_eq!(members, alice_group.group.roster().members_iter().count());
+        }
+    }
+
     #[cfg(feature = "private_message")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn member_can_see_sender_creds() {
@@ -3000,9 +4404,141 @@ mod tests {
         );
     }
 
-    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
-    async fn members_of_a_group_have_identical_authentication_secrets() {
-        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+    #[cfg(feature = "private_message")]
+    #[derive(Debug, Clone)]
+    struct MuteMembers(Vec<Vec<u8>>);
+
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl crate::MlsRules for MuteMembers {
+        type Error = MlsError;
+
+        async fn filter_proposals(
+            &self,
+            _: CommitDirection,
+            _: CommitSource,
+            _: &Roster,
+            _: &ExtensionList,
+            proposals: ProposalBundle,
+        ) -> Result<ProposalBundle, MlsError> {
+            Ok(proposals)
+        }
+
+        fn commit_options(
+            &self,
+            _: &Roster,
+            _: &ExtensionList,
+            _: &ProposalBundle,
+        ) -> Result<CommitOptions, MlsError> {
+            Ok(Default::default())
+        }
+
+        fn encryption_options(
+            &self,
+            _: &Roster,
+            _: &ExtensionList,
+        ) -> Result<crate::mls_rules::EncryptionOptions, MlsError> {
+            Ok(Default::default())
+        }
+
+        fn authorize_application_message(
+            &self,
+            sender: &Member,
+            authenticated_data: &[u8],
+        ) -> Result<MessageDecision, MlsError> {
+            if authenticated_data == b"spam" {
+                return Ok(MessageDecision::Reject(MessageRejection::new(2, "spam")));
+            }
+
+            let credential = sender.signing_identity.credential.as_basic().unwrap();
+
+            Ok(if self.0.contains(&credential.identifier) {
+                MessageDecision::Reject(MessageRejection::new(1, "muted"))
+            } else {
+                MessageDecision::Accept
+            })
+        }
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_messages_can_be_rejected_by_rules() {
+        let (signing_identity, signer) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let mut bob = ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(BasicIdentityProvider::new())
+            .signing_identity(signing_identity, signer, TEST_CIPHER_SUITE)
+            .mls_rules(MuteMembers(vec![b"carol".to_vec()]))
+            .build_unchecked()
+            .create_group(Default::default())
+            .await
+            .unwrap();
+
+        let (alice, alice_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let (carol, carol_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let welcome = bob
+            .commit_builder()
+            .add_member(alice_key_package)
+            .unwrap()
+            .add_member(carol_key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .welcome_messages
+            .remove(0);
+
+        bob.apply_pending_commit().await.unwrap();
+
+        let (mut alice, _) = alice.join_group(None, &welcome).await.unwrap();
+        let (mut carol, _) = carol.join_group(None, &welcome).await.unwrap();
+
+        let message = alice
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let received = bob.process_incoming_message(message).await.unwrap();
+        assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello");
+
+        let message = alice
+            .encrypt_application_message(b"buy now", b"spam".to_vec())
+            .await
+            .unwrap();
+
+        let res = bob.process_incoming_message(message).await;
+        assert_matches!(
+            res,
+            Err(MlsError::MessageRejected(
+                1,
+                MessageRejection { code: 2, .. }
+            ))
+        );
+
+        let message = carol
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let res = bob.process_incoming_message(message).await;
+        assert_matches!(
+            res,
+            Err(MlsError::MessageRejected(
+                2,
+                MessageRejection { code: 1, .. }
+            ))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_of_a_group_have_identical_authentication_secrets() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
         let (bob_group, _) = alice_group.join("bob").await;
 
         assert_eq!(
@@ -3039,6 +4575,67 @@ mod tests {
         assert_matches!(res, Err(MlsError::KeyMissing(0)));
     }
 
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sender_ratchet_skip_is_capped_until_fast_forward() {
+        let mut alice_group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob_group, _) = alice_group
+            .join_with_custom_config("bob", false, |c| c.0.settings.max_ratchet_skip = 2)
+            .await
+            .unwrap();
+
+        let mut messages = Vec::new();
+
+        for _ in 0..5 {
+            let message = alice_group
+                .group
+                .encrypt_application_message(b"foobar", Vec::new())
+                .await
+                .unwrap();
+
+            messages.push(message);
+        }
+
+        let res = bob_group
+            .group
+            .process_incoming_message(messages[3].clone())
+            .await;
+
+        assert_matches!(res, Err(MlsError::InvalidFutureGeneration(3)));
+
+        let received = bob_group
+            .group
+            .process_incoming_message(messages[2].clone())
+            .await
+            .unwrap();
+
+        assert_matches!(received, ReceivedMessage::ApplicationMessage(_));
+
+        let res = bob_group.group.fast_forward_sender(1, 4).await;
+        assert_matches!(res, Err(MlsError::InvalidSender));
+
+        let res = bob_group.group.fast_forward_sender(0, 100).await;
+        assert_matches!(res, Err(MlsError::InvalidFutureGeneration(100)));
+
+        bob_group.group.fast_forward_sender(0, 4).await.unwrap();
+
+        let res = bob_group
+            .group
+            .process_incoming_message(messages[3].clone())
+            .await;
+
+        assert_matches!(res, Err(MlsError::KeyMissing(3)));
+
+        let received = bob_group
+            .group
+            .process_incoming_message(messages[4].clone())
+            .await
+            .unwrap();
+
+        assert_matches!(received, ReceivedMessage::ApplicationMessage(_));
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn removing_requirements_allows_to_add() {
         let mut alice_group = test_group_custom(
@@ -3128,7 +4725,14 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Commit,
+                LeafNodeSourceKind::Update
+            ))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3200,7 +4804,10 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(_, LeafDataKind::EncryptionKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3257,7 +4864,10 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(_, LeafDataKind::SignatureKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3275,7 +4885,13 @@ mod tests {
             .process_message(commit_output.commit_message)
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidSignature));
+        assert_matches!(
+            res,
+            Err(MlsError::LeafNodeBindingMismatch(
+                0,
+                LeafNodeSourceKind::Commit
+            ))
+        );
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -3446,7 +5062,7 @@ mod tests {
             )
             .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
             .await
-            .build()
+            .build_unchecked()
             .create_group(core::iter::once(ext_senders).collect())
             .await
             .unwrap();
@@ -3482,7 +5098,7 @@ mod tests {
             )
             .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
             .await
-            .build()
+            .build_unchecked()
             .create_group(core::iter::once(ext_senders).collect())
             .await
             .unwrap();
@@ -3514,7 +5130,7 @@ mod tests {
             )
             .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
             .await
-            .build()
+            .build_unchecked()
             .create_group(Default::default())
             .await
             .unwrap();
@@ -3551,6 +5167,161 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_senders_can_be_added_and_removed() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let (server, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"server").await;
+
+        let proposal = alice
+            .group
+            .propose_add_external_sender(server.clone(), vec![])
+            .await
+            .unwrap();
+
+        bob.process_message(proposal).await.unwrap();
+
+        let commit = bob.group.commit(vec![]).await.unwrap();
+        assert!(commit.external_senders_group_info.is_some());
+
+        bob.process_pending_commit().await.unwrap();
+        alice.process_message(commit.commit_message).await.unwrap();
+
+        let ext_senders = alice
+            .group
+            .context()
+            .extensions
+            .get_as::<ExternalSendersExt>()
+            .unwrap();
+
+        assert_eq!(
+            ext_senders,
+            Some(ExternalSendersExt::new(vec![server.clone()]))
+        );
+
+        let res = alice
+            .group
+            .commit_builder()
+            .add_external_sender(server.clone())
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::ExternalSenderAlreadyAllowed));
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .remove_external_sender(&server)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(commit.external_senders_group_info.is_some());
+
+        alice.process_pending_commit().await.unwrap();
+
+        assert!(!alice
+            .group
+            .context()
+            .extensions
+            .has_extension(ExtensionType::EXTERNAL_SENDERS));
+
+        let res = alice
+            .group
+            .propose_remove_external_sender(&server, vec![])
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::ExternalSenderNotFound));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn creating_group_with_duplicate_external_senders_fails() {
+        let (server, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"server").await;
+
+        let mut renamed = server.clone();
+        renamed.credential = BasicCredential::new(b"other".to_vec()).into_credential();
+
+        let ext_senders = ExternalSendersExt::new(vec![server, renamed])
+            .into_extension()
+            .unwrap();
+
+        let group_creation =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice")
+                .await
+                .0
+                .create_group(core::iter::once(ext_senders).collect())
+                .await
+                .map(|_| ());
+
+        assert_matches!(group_creation, Err(MlsError::ExternalSenderAlreadyAllowed));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn reinit_with_unsupported_external_sender_fails() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (server, _) = get_test_signing_identity(TEST_CIPHER_SUITE, b"server").await;
+        let credential = CustomCredential::new(CredentialType::new(99), vec![]);
+
+        let ext_senders = ExternalSendersExt::new(vec![SigningIdentity::new(
+            Credential::Custom(credential),
+            server.signature_key,
+        )]);
+
+        let extensions = core::iter::once(ext_senders.into_extension().unwrap()).collect();
+
+        let res = alice
+            .group
+            .propose_reinit(
+                None,
+                TEST_PROTOCOL_VERSION,
+                TEST_CIPHER_SUITE,
+                extensions,
+                vec![],
+            )
+            .await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::UnsupportedExternalSenderCredential(t)) if t == CredentialType::new(99)
+        );
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn external_sender_with_unsupported_credential_type_is_rejected() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let mut ext_senders = make_x509_external_senders_ext().await;
+        let server = ext_senders.allowed_senders.remove(0);
+
+        let res = alice
+            .group
+            .propose_add_external_sender(server, vec![])
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::RequiredCredentialNotFound(CredentialType::X509))
+        );
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_without_external_sender_change_has_no_group_info() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let commit = alice.group.commit(vec![]).await.unwrap();
+
+        assert!(commit.external_senders_group_info.is_none());
+    }
+
     /*
      * Edge case paths
      */
@@ -3560,8 +5331,10 @@ mod tests {
         let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 10).await;
 
         groups[0].group.commit_modifiers.modify_tree = |tree: &mut TreeKemPublic| {
-            tree.update_node(get_test_25519_key(1u8), 1).unwrap();
-            tree.update_node(get_test_25519_key(1u8), 3).unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex::new(1))
+                .unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex::new(3))
+                .unwrap();
         };
 
         groups[0].group.commit_modifiers.modify_leaf = |leaf, sk| {
@@ -3601,7 +5374,8 @@ mod tests {
         }
 
         groups[0].group.commit_modifiers.modify_tree = |tree: &mut TreeKemPublic| {
-            tree.update_node(get_test_25519_key(1u8), 1).unwrap();
+            tree.update_node(get_test_25519_key(1u8), NodeIndex::new(1))
+                .unwrap();
         };
 
         groups[0].group.commit_modifiers.modify_path = |path: Vec<UpdatePathNode>| {
@@ -3741,13 +5515,289 @@ mod tests {
         assert_matches!(res, Err(MlsError::InvalidLifetime));
     }
 
-    #[cfg(feature = "custom_proposal")]
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn custom_proposal_setup() -> (TestGroup, TestGroup) {
-        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
-            b.custom_proposal_type(TEST_CUSTOM_PROPOSAL_TYPE)
-        })
-        .await;
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_commit_with_expired_proposal_succeeds() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let (mut bob, _) = alice
+            .join_with_custom_config("bob", false, |c| c.0.settings.proposal_ttl = Some(60))
+            .await
+            .unwrap();
+
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let proposal = alice.group.propose_add(key_package, vec![]).await.unwrap();
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+
+        let sent = MlsTime::now().seconds_since_epoch();
+
+        let received = bob
+            .group
+            .process_incoming_message_with_time(proposal, MlsTime::from(sent))
+            .await
+            .unwrap();
+
+        let ReceivedMessage::Proposal(ProposalMessageDescription { proposal_ref, .. }) = received
+        else {
+            panic!("expected a proposal");
+        };
+
+        let expiry = Some(MlsTime::from(sent + 60));
+        assert_eq!(bob.group.proposal_expiry(&proposal_ref), expiry);
+
+        // The expiry is written with the group state
+        let restored = Group::from_snapshot(bob.group.config.clone(), bob.group.snapshot())
+            .await
+            .unwrap();
+
+        assert_eq!(restored.proposal_expiry(&proposal_ref), expiry);
+
+        // Expiry only applies to commits created by bob, who is told about
+        // the expired proposals committed by others
+        let received = bob
+            .group
+            .process_incoming_message_with_time(commit, MlsTime::from(sent + 61))
+            .await
+            .unwrap();
+
+        assert_eq!(bob.group.roster().members_iter().count(), 3);
+
+        #[cfg(feature = "state_update")]
+        {
+            let ReceivedMessage::Commit(description) = received else {
+                panic!("expected a commit");
+            };
+
+            assert_eq!(
+                description.state_update.expired_proposals(),
+                &[proposal_ref]
+            );
+        }
+
+        #[cfg(not(feature = "state_update"))]
+        let _ = received;
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expired_proposals_are_left_out_of_commits() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let proposal = groups[1]
+            .group
+            .propose_add(key_package, vec![])
+            .await
+            .unwrap();
+
+        let received = groups[0].process_message(proposal).await.unwrap();
+
+        let ReceivedMessage::Proposal(ProposalMessageDescription { proposal_ref, .. }) = received
+        else {
+            panic!("expected a proposal");
+        };
+
+        groups[0]
+            .group
+            .set_proposal_expiry(&proposal_ref, Some(MlsTime::from(0)))
+            .unwrap();
+
+        groups[0].group.commit(vec![]).await.unwrap();
+        groups[0].process_pending_commit().await.unwrap();
+
+        assert_eq!(groups[0].group.roster().members_iter().count(), 2);
+
+        let res = groups[0].group.set_proposal_expiry(&proposal_ref, None);
+
+        assert_matches!(res, Err(MlsError::ProposalNotFound));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_commit_with_old_adds_within_grace_period_succeeds() {
+        let mut groups = test_n_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, 2).await;
+
+        let key_package =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "foobar").await;
+
+        let proposal = groups[0]
+            .group
+            .propose_add(key_package, vec![])
+            .await
+            .unwrap();
+
+        let commit = groups[0].group.commit(vec![]).await.unwrap().commit_message;
+
+        // Key packages are valid for one year, so this is 1 day past their lifetime
+        let future_time = MlsTime::now().seconds_since_epoch() + 366 * 24 * 3600;
+
+        let future_time =
+            MlsTime::from_duration_since_epoch(core::time::Duration::from_secs(future_time));
+
+        let policy = LifetimePolicy::new().with_grace_period(7 * 24 * 3600);
+        groups[1].group.config_overrides = GroupConfigOverrides::new().with_lifetime_policy(policy);
+
+        groups[1]
+            .group
+            .process_incoming_message(proposal)
+            .await
+            .unwrap();
+
+        groups[1]
+            .group
+            .process_incoming_message_with_time(commit, future_time)
+            .await
+            .unwrap();
+
+        let warnings = groups[1].group.lifetime_warnings(future_time);
+
+        let new_member = warnings
+            .iter()
+            .find(|w| w.member.index == 2)
+            .map(|w| w.kind);
+
+        assert_eq!(new_member, Some(LifetimeWarningKind::Expired));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn relocating_members_shrinks_the_tree() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let mut builder = alice.group.commit_builder();
+
+        for name in ["bob", "carol", "dave"] {
+            let (_, key_package) =
+                test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, name).await;
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        builder.build().await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        alice
+            .group
+            .commit_builder()
+            .remove_member(2)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let candidates = alice.group.roster().relocation_candidates();
+        assert_eq!(candidates.iter().map(|m| m.index).collect::<Vec<_>>(), [3]);
+
+        let (_, carol_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        let res = alice
+            .group
+            .commit_builder()
+            .relocate_member(3, carol_key_package)
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::InvalidSuccessor));
+
+        let (_, dave_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "dave").await;
+
+        let output = alice
+            .group
+            .commit_builder()
+            .relocate_member(3, dave_key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        assert_eq!(output.welcome_messages.len(), 1);
+        assert_eq!(alice.group.roster().leaf_count(), 3);
+        assert!(alice.group.roster().relocation_candidates().is_empty());
+
+        let dave = alice.group.roster().member_with_index(2).unwrap();
+        assert_eq!(
+            dave.signing_identity
+                .credential
+                .as_basic()
+                .unwrap()
+                .identifier,
+            b"dave"
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn trailing_blank_leaves_are_kept_according_to_rules() {
+        let truncation = TreeTruncation::KeepLeaves(4);
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        alice.group.config.0.mls_rules.tree_truncation = truncation;
+
+        alice.join("bob").await;
+        alice.join("carol").await;
+
+        let (mut dave, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "dave").await;
+
+        let output = alice
+            .group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .remove_member(2)
+            .unwrap()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        // Dave takes the leaf of bob and the leaf of carol is kept
+        assert_eq!(alice.group.roster().leaf_count(), 3);
+        assert!(alice.group.roster().is_blank(2));
+
+        let res = Group::join(
+            &output.welcome_messages[0],
+            None,
+            dave.config.clone(),
+            dave.signer.clone().unwrap(),
+            Default::default(),
+        )
+        .await
+        .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::UnexpectedTrailingBlanks));
+
+        dave.config.0.mls_rules.tree_truncation = truncation;
+
+        let (dave_group, _) = Group::join(
+            &output.welcome_messages[0],
+            None,
+            dave.config.clone(),
+            dave.signer.clone().unwrap(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(dave_group.roster().leaf_count(), 3);
+    }
+
+    #[cfg(feature = "custom_proposal")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn custom_proposal_setup() -> (TestGroup, TestGroup) {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.custom_proposal_type(TEST_CUSTOM_PROPOSAL_TYPE)
+        })
+        .await;
 
         let (bob, _) = alice
             .join_with_custom_config("bob", true, |c| {
@@ -3769,86 +5819,477 @@ mod tests {
         let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![0, 1, 2]);
 
         let commit = alice
-            .group
+            .group
+            .commit_builder()
+            .custom_proposal(custom_proposal.clone())
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let res = bob.group.process_incoming_message(commit).await.unwrap();
+
+        #[cfg(feature = "state_update")]
+        assert_matches!(res, ReceivedMessage::Commit(CommitMessageDescription { state_update: StateUpdate { custom_proposals, .. }, .. })
+            if custom_proposals.len() == 1 && custom_proposals[0].proposal == custom_proposal);
+
+        #[cfg(not(feature = "state_update"))]
+        assert_matches!(res, ReceivedMessage::Commit(_));
+    }
+
+    #[cfg(feature = "custom_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_proposal_by_reference() {
+        let (mut alice, mut bob) = custom_proposal_setup().await;
+
+        let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![0, 1, 2]);
+
+        let proposal = alice
+            .group
+            .propose_custom(custom_proposal.clone(), vec![])
+            .await
+            .unwrap();
+
+        let recv_prop = bob.group.process_incoming_message(proposal).await.unwrap();
+
+        assert_matches!(recv_prop, ReceivedMessage::Proposal(ProposalMessageDescription { proposal: Proposal::Custom(c), ..})
+            if c == custom_proposal);
+
+        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
+        let res = alice.group.process_incoming_message(commit).await.unwrap();
+
+        #[cfg(feature = "state_update")]
+        assert_matches!(res, ReceivedMessage::Commit(CommitMessageDescription { state_update: StateUpdate { custom_proposals, .. }, .. })
+            if custom_proposals.len() == 1 && custom_proposals[0].proposal == custom_proposal);
+
+        #[cfg(not(feature = "state_update"))]
+        assert_matches!(res, ReceivedMessage::Commit(_));
+    }
+
+    #[cfg(feature = "custom_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_proposal_metadata_can_require_path() {
+        let (mut alice, mut bob) = custom_proposal_setup().await;
+
+        let metadata = CustomProposalMetadata::new().with_path_required(true);
+
+        bob.group
+            .config
+            .0
+            .settings
+            .custom_proposal_metadata
+            .push((TEST_CUSTOM_PROPOSAL_TYPE, metadata));
+
+        let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![]);
+
+        // Alice did not register the metadata and omits the path
+        let commit = alice
+            .group
+            .commit_builder()
+            .custom_proposal(custom_proposal.clone())
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let res = bob.group.process_incoming_message(commit).await;
+        assert_matches!(res, Err(MlsError::CommitMissingPath));
+
+        let res = bob
+            .group
+            .commit_builder()
+            .custom_proposal(custom_proposal.clone())
+            .omit_path_update()
+            .build()
+            .await;
+
+        assert_matches!(res, Err(MlsError::CommitMissingPath));
+
+        let bob_pub_before = bob
+            .group
+            .current_user_leaf_node()
+            .unwrap()
+            .public_key
+            .clone();
+
+        bob.group
+            .commit_builder()
+            .custom_proposal(custom_proposal)
+            .build()
+            .await
+            .unwrap();
+
+        bob.group.apply_pending_commit().await.unwrap();
+
+        let bob_pub_after = &bob.group.current_user_leaf_node().unwrap().public_key;
+        assert_ne!(bob_pub_after, &bob_pub_before);
+    }
+
+    #[cfg(all(feature = "custom_proposal", feature = "by_ref_proposal"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_proposal_metadata_can_require_by_value() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.custom_proposal_type_with_metadata(
+                TEST_CUSTOM_PROPOSAL_TYPE,
+                CustomProposalMetadata::new().with_by_value_only(true),
+            )
+        })
+        .await;
+
+        let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![]);
+        let res = alice.group.propose_custom(custom_proposal, vec![]).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ProposalTypeRequiresByValue(
+                TEST_CUSTOM_PROPOSAL_TYPE
+            ))
+        );
+    }
+
+    #[cfg(all(feature = "custom_proposal", feature = "by_ref_proposal"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn by_value_only_custom_proposals_received_by_reference_are_rejected() {
+        let (mut alice, mut bob) = custom_proposal_setup().await;
+
+        bob.group.config.0.settings.custom_proposal_metadata.push((
+            TEST_CUSTOM_PROPOSAL_TYPE,
+            CustomProposalMetadata::new().with_by_value_only(true),
+        ));
+
+        let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![]);
+
+        let proposal = alice
+            .group
+            .propose_custom(custom_proposal, vec![])
+            .await
+            .unwrap();
+
+        let res = bob.process_message(proposal).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::ProposalTypeRequiresByValue(
+                TEST_CUSTOM_PROPOSAL_TYPE
+            ))
+        );
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn can_join_with_psk() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE)
+            .await
+            .group;
+
+        let (bob, key_pkg) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let psk_id = ExternalPskId::new(vec![0]);
+        let psk = PreSharedKey::from(vec![0]);
+
+        alice
+            .config
+            .secret_store()
+            .insert(psk_id.clone(), psk.clone());
+
+        bob.config.secret_store().insert(psk_id.clone(), psk);
+
+        let commit = alice
+            .commit_builder()
+            .add_member(key_pkg)
+            .unwrap()
+            .add_external_psk(psk_id)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        bob.join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn joining_with_wrong_psk_value_reports_psk() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE)
+            .await
+            .group;
+
+        let (bob, key_pkg) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let psk_id = ExternalPskId::new(vec![0]);
+
+        alice
+            .config
+            .secret_store()
+            .insert(psk_id.clone(), PreSharedKey::from(vec![0]));
+
+        bob.config
+            .secret_store()
+            .insert(psk_id.clone(), PreSharedKey::from(vec![1]));
+
+        let commit = alice
+            .commit_builder()
+            .add_member(key_pkg)
+            .unwrap()
+            .add_external_psk(psk_id.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let res = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::PskFailure(psks, PskFailureKind::ValueMismatch))
+                if psks == [crate::psk::PskUsage::External(psk_id)]
+        );
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_commit_with_wrong_psk_value_reports_psk() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let psk_id = ExternalPskId::new(vec![0]);
+
+        alice
+            .group
+            .config
+            .secret_store()
+            .insert(psk_id.clone(), PreSharedKey::from(vec![0]));
+
+        bob.group
+            .config
+            .secret_store()
+            .insert(psk_id.clone(), PreSharedKey::from(vec![1]));
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .add_external_psk(psk_id.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let res = bob.process_message(commit.commit_message).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::PskFailure(psks, PskFailureKind::ValueMismatch))
+                if psks == [crate::psk::PskUsage::External(psk_id)]
+        );
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn joining_requires_psk_from_group_context() {
+        let psk_id = ExternalPskId::new(vec![1]);
+        let psk = PreSharedKey::from(vec![1]);
+
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.extension_type(ExtensionType::REQUIRED_JOIN_PSK)
+                .psk(psk_id.clone(), psk.clone())
+        })
+        .await
+        .group;
+
+        let mut extensions = alice.context().extensions.clone();
+        extensions
+            .set_from(RequiredJoinPskExt::new(psk_id.clone()))
+            .unwrap();
+
+        alice
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.apply_pending_commit().await.unwrap();
+
+        let supports_ext = |c: &mut TestClientConfig| {
+            c.0.settings
+                .extension_types
+                .push(ExtensionType::REQUIRED_JOIN_PSK)
+        };
+
+        let (bob, bob_key_pkg) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "bob",
+            supports_ext,
+        )
+        .await;
+
+        let (charlie, charlie_key_pkg) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "charlie",
+            supports_ext,
+        )
+        .await;
+
+        bob.config.secret_store().insert(psk_id.clone(), psk);
+
+        // The PSK proposal is added without being requested explicitly
+        let commit = alice
+            .commit_builder()
+            .add_member(bob_key_pkg)
+            .unwrap()
+            .add_member(charlie_key_pkg)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        bob.join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        let res = charlie
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .map(|_| ());
+
+        assert_matches!(
+            res,
+            Err(MlsError::PskFailure(psks, crate::client::PskFailureKind::Missing))
+                if psks == [crate::psk::PskUsage::External(psk_id)]
+        );
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn commit_adding_members_without_required_join_psk_is_rejected() {
+        let psk_id = ExternalPskId::new(vec![1]);
+        let psk = PreSharedKey::from(vec![1]);
+
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.extension_type(ExtensionType::REQUIRED_JOIN_PSK)
+                .psk(psk_id.clone(), psk.clone())
+        })
+        .await
+        .group;
+
+        let supports_ext = |c: &mut TestClientConfig| {
+            c.0.settings
+                .extension_types
+                .push(ExtensionType::REQUIRED_JOIN_PSK)
+        };
+
+        let (bob, bob_key_pkg) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "bob",
+            supports_ext,
+        )
+        .await;
+
+        let (charlie, charlie_key_pkg) = test_client_with_key_pkg_custom(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            "charlie",
+            supports_ext,
+        )
+        .await;
+
+        bob.config
+            .secret_store()
+            .insert(psk_id.clone(), psk.clone());
+        charlie.config.secret_store().insert(psk_id.clone(), psk);
+
+        let commit = alice
             .commit_builder()
-            .custom_proposal(custom_proposal.clone())
+            .add_member(bob_key_pkg)
+            .unwrap()
             .build()
             .await
-            .unwrap()
-            .commit_message;
-
-        let res = bob.group.process_incoming_message(commit).await.unwrap();
+            .unwrap();
 
-        #[cfg(feature = "state_update")]
-        assert_matches!(res, ReceivedMessage::Commit(CommitMessageDescription { state_update: StateUpdate { custom_proposals, .. }, .. })
-            if custom_proposals.len() == 1 && custom_proposals[0].proposal == custom_proposal);
+        alice.apply_pending_commit().await.unwrap();
 
-        #[cfg(not(feature = "state_update"))]
-        assert_matches!(res, ReceivedMessage::Commit(_));
-    }
+        let (mut bob, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
 
-    #[cfg(feature = "custom_proposal")]
-    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
-    async fn custom_proposal_by_reference() {
-        let (mut alice, mut bob) = custom_proposal_setup().await;
+        let mut extensions = alice.context().extensions.clone();
+        extensions
+            .set_from(RequiredJoinPskExt::new(psk_id))
+            .unwrap();
 
-        let custom_proposal = CustomProposal::new(TEST_CUSTOM_PROPOSAL_TYPE, vec![0, 1, 2]);
+        let commit = alice
+            .commit_builder()
+            .set_group_context_ext(extensions)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
 
-        let proposal = alice
-            .group
-            .propose_custom(custom_proposal.clone(), vec![])
+        alice.apply_pending_commit().await.unwrap();
+        bob.process_incoming_message(commit.commit_message)
             .await
             .unwrap();
 
-        let recv_prop = bob.group.process_incoming_message(proposal).await.unwrap();
+        // Alice leaves out the PSK proposal required to add members
+        alice.commit_modifiers.skip_required_join_psk = true;
 
-        assert_matches!(recv_prop, ReceivedMessage::Proposal(ProposalMessageDescription { proposal: Proposal::Custom(c), ..})
-            if c == custom_proposal);
+        let commit = alice
+            .commit_builder()
+            .add_member(charlie_key_pkg)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
 
-        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
-        let res = alice.group.process_incoming_message(commit).await.unwrap();
+        let res = bob.process_incoming_message(commit.commit_message).await;
+        assert_matches!(res, Err(MlsError::MissingRequiredJoinPsk));
 
-        #[cfg(feature = "state_update")]
-        assert_matches!(res, ReceivedMessage::Commit(CommitMessageDescription { state_update: StateUpdate { custom_proposals, .. }, .. })
-            if custom_proposals.len() == 1 && custom_proposals[0].proposal == custom_proposal);
+        let res = charlie
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .map(|_| ());
 
-        #[cfg(not(feature = "state_update"))]
-        assert_matches!(res, ReceivedMessage::Commit(_));
+        assert_matches!(res, Err(MlsError::MissingRequiredJoinPsk));
     }
 
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
-    async fn can_join_with_psk() {
-        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE)
-            .await
-            .group;
-
-        let (bob, key_pkg) =
-            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+    async fn welcome_without_required_join_psk_is_rejected() {
+        let cs = crate::crypto::test_utils::test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut context = test_utils::get_test_group_context(1, TEST_CIPHER_SUITE).await;
 
-        let psk_id = ExternalPskId::new(vec![0]);
-        let psk = PreSharedKey::from(vec![0]);
+        context
+            .extensions
+            .set_from(RequiredJoinPskExt::new(ExternalPskId::new(vec![1])))
+            .unwrap();
 
-        alice
-            .config
-            .secret_store()
-            .insert(psk_id.clone(), psk.clone());
+        let psk = |id: Vec<u8>| {
+            PreSharedKeyID::new(JustPreSharedKeyID::External(ExternalPskId::new(id)), &cs).unwrap()
+        };
 
-        bob.config.secret_store().insert(psk_id.clone(), psk);
+        assert_matches!(
+            check_required_join_psk(&context, &[]),
+            Err(MlsError::MissingRequiredJoinPsk)
+        );
 
-        let commit = alice
-            .commit_builder()
-            .add_member(key_pkg)
-            .unwrap()
-            .add_external_psk(psk_id)
-            .unwrap()
-            .build()
-            .await
-            .unwrap();
+        assert_matches!(
+            check_required_join_psk(&context, &[psk(vec![2])]),
+            Err(MlsError::MissingRequiredJoinPsk)
+        );
 
-        bob.join_group(None, &commit.welcome_messages[0])
-            .await
-            .unwrap();
+        assert!(check_required_join_psk(&context, &[psk(vec![2]), psk(vec![1])]).is_ok());
     }
 
     #[cfg(feature = "by_ref_proposal")]
@@ -3868,7 +6309,7 @@ mod tests {
             .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
             .await
             .extension_type(EXTENSION_TYPE)
-            .build()
+            .build_unchecked()
             .create_group(group_extensions.clone())
             .await
             .unwrap();
@@ -3883,19 +6324,19 @@ mod tests {
                 TEST_CIPHER_SUITE,
             )
             .extension_type(EXTENSION_TYPE)
-            .build();
+            .build_unchecked();
 
         let carol_client = TestClientBuilder::new_for_test()
             .with_random_signing_identity("carol", TEST_CIPHER_SUITE)
             .await
             .extension_type(EXTENSION_TYPE)
-            .build();
+            .build_unchecked();
 
         let dave_client = TestClientBuilder::new_for_test()
             .with_random_signing_identity("dave", TEST_CIPHER_SUITE)
             .await
             .extension_type(EXTENSION_TYPE)
-            .build();
+            .build_unchecked();
 
         // Alice adds Bob, Carol and Dave to the group. They all support the mandatory extension.
         let commit = alice
@@ -3926,7 +6367,7 @@ mod tests {
             .signing_identity(bob_signing_identity, bob_secret_key, TEST_CIPHER_SUITE)
             .key_package_repo(bob.config.key_package_repo())
             .group_state_storage(bob.config.group_state_storage())
-            .build()
+            .build_unchecked()
             .load_group(alice.group_id())
             .await
             .unwrap();
@@ -4156,7 +6597,7 @@ mod tests {
             .signing_identity(signing_identity, signer, TEST_CIPHER_SUITE)
             .custom_proposal_type(TEST_CUSTOM_PROPOSAL_TYPE)
             .mls_rules(mls_rules)
-            .build()
+            .build_unchecked()
     }
 
     #[derive(Debug, Clone)]
@@ -4233,4 +6674,312 @@ mod tests {
 
         assert_eq!(update.committer, *group.private_tree.self_index);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_can_be_found_by_application_id() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let mut builder = alice.group.commit_builder();
+
+        for (name, id) in [
+            ("bob", b"shared"),
+            ("carol", b"shared"),
+            ("dave", b"dave__"),
+        ] {
+            let (_, key_package) = crate::client::test_utils::test_client_with_key_pkg_custom(
+                TEST_PROTOCOL_VERSION,
+                TEST_CIPHER_SUITE,
+                name,
+                |c| {
+                    c.0.settings
+                        .extension_types
+                        .push(ExtensionType::APPLICATION_ID);
+
+                    c.0.settings
+                        .leaf_node_extensions
+                        .set_from(crate::extension::ApplicationIdExt::new(id.to_vec()))
+                        .unwrap()
+                },
+            )
+            .await;
+
+            builder = builder.add_member(key_package).unwrap();
+        }
+
+        builder.build().await.unwrap();
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let snapshot = alice.group.snapshot().mls_encode_to_vec().unwrap();
+        let snapshot = snapshot::Snapshot::from_bytes(&snapshot).unwrap();
+
+        let mut group = Group::from_snapshot(alice.group.config.clone(), snapshot)
+            .await
+            .unwrap();
+
+        let roster = group.roster();
+
+        let member = roster.member_with_application_id(b"dave__").unwrap();
+        assert_eq!(member.index, 3);
+
+        let member = roster.member_with_application_id(b"shared").unwrap();
+        assert_eq!(member.index, 1);
+
+        assert!(roster.member_with_application_id(b"alice").is_none());
+
+        assert_eq!(
+            roster.duplicate_application_ids(),
+            vec![(b"shared".to_vec(), vec![1, 2])]
+        );
+
+        group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        group.apply_pending_commit().await.unwrap();
+
+        let roster = group.roster();
+
+        let member = roster.member_with_application_id(b"shared").unwrap();
+        assert_eq!(member.index, 2);
+
+        assert!(roster.duplicate_application_ids().is_empty());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_group_info_extensions_reach_new_members() {
+        let routing = Extension::new(ExtensionType::new(0xff0a), b"route".to_vec());
+
+        let mut alice = super::test_utils::test_group_custom_config(
+            TEST_PROTOCOL_VERSION,
+            TEST_CIPHER_SUITE,
+            |b| {
+                b.mls_rules(
+                    crate::group::mls_rules::DefaultMlsRules::new()
+                        .with_group_info_extensions(core::iter::once(routing.clone()).collect()),
+                )
+            },
+        )
+        .await;
+
+        let (bob, bob_key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+
+        let mut builder_extensions = ExtensionList::new();
+        builder_extensions
+            .set_from(TestExtension { foo: 1 })
+            .unwrap();
+
+        let welcome = alice
+            .group
+            .commit_builder()
+            .add_member(bob_key_package)
+            .unwrap()
+            .set_group_info_ext(builder_extensions)
+            .build()
+            .await
+            .unwrap()
+            .welcome_messages
+            .remove(0);
+
+        let preview = bob.preview_welcome(&welcome).await.unwrap();
+
+        assert_eq!(
+            preview.group_context.group_id,
+            alice.group.context().group_id
+        );
+        assert_eq!(preview.group_context.epoch, 1);
+        let key_package_reference = bob.welcome_key_package(&welcome).await.unwrap();
+        assert_eq!(preview.key_package_reference, key_package_reference);
+
+        // The preview does not use up the key package
+        let (_, new_member_info) = bob.join_group(None, &welcome).await.unwrap();
+
+        for extensions in [
+            &preview.group_info_extensions,
+            &new_member_info.group_info_extensions,
+        ] {
+            assert_eq!(
+                extensions.get(routing.extension_type),
+                Some(routing.clone())
+            );
+            assert_eq!(
+                extensions.get_as::<TestExtension>().unwrap(),
+                Some(TestExtension { foo: 1 })
+            );
+        }
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let group_info = alice
+            .group
+            .group_info_message(true)
+            .await
+            .unwrap()
+            .into_group_info()
+            .unwrap();
+
+        assert_eq!(
+            group_info
+                .ungreased_extensions()
+                .get(routing.extension_type),
+            Some(routing)
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unknown_leaf_and_key_package_extensions_are_preserved() {
+        let leaf_extension = Extension::new(ExtensionType::new(0xFA00), vec![1, 2, 3]);
+        let key_package_extension = Extension::new(ExtensionType::new(0xFA01), vec![4, 5]);
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (identity, secret_key) = get_test_signing_identity(TEST_CIPHER_SUITE, b"bob").await;
+
+        let bob = TestClientBuilder::new_for_test()
+            .extension_type(leaf_extension.extension_type)
+            .leaf_node_extensions(vec![leaf_extension.clone()].into())
+            .key_package_extensions(vec![key_package_extension.clone()].into())
+            .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked();
+
+        let bytes = bob
+            .generate_key_package_message()
+            .await
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+
+        let key_package_message = MlsMessage::from_bytes(&bytes).unwrap();
+        assert_eq!(key_package_message.to_bytes().unwrap(), bytes);
+
+        let key_package = key_package_message.clone().into_key_package().unwrap();
+
+        assert!(key_package
+            .extensions
+            .unknown_extensions(&[])
+            .any(|ext| ext == &key_package_extension));
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .add_member(key_package_message)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let (bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        // Carol validates the signature of the leaf of bob when joining
+        let (carol, _) = alice.join("carol").await;
+
+        let expected_leaf = key_package.leaf_node.mls_encode_to_vec().unwrap();
+
+        for group in [&alice.group, &bob_group, &carol.group] {
+            let leaf = group
+                .current_epoch_tree()
+                .get_leaf_node(LeafIndex(1))
+                .unwrap();
+            assert_eq!(leaf.mls_encode_to_vec().unwrap(), expected_leaf);
+
+            let member = group.roster().member_with_index(1).unwrap();
+            let unknown = member
+                .extensions
+                .unknown_extensions(&[])
+                .collect::<Vec<_>>();
+            assert_eq!(unknown, vec![&leaf_extension]);
+        }
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn removed_member_group_is_read_only() {
+        let (mut alice, mut bob) =
+            test_two_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, true).await;
+
+        let message = alice
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        assert!(!bob.group.is_removed());
+
+        bob.group
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+
+        assert!(bob.group.is_removed());
+        assert_eq!(bob.group.roster().members_iter().count(), 2);
+
+        let received = bob.group.process_incoming_message(message).await.unwrap();
+        assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello");
+
+        let res = bob.group.process_incoming_message(commit).await;
+        assert_matches!(res, Err(MlsError::RemovedFromGroup));
+
+        let res = bob.group.encrypt_application_message(b"bye", vec![]).await;
+        assert_matches!(res, Err(MlsError::RemovedFromGroup));
+
+        let res = bob.group.commit(vec![]).await;
+        assert_matches!(res, Err(MlsError::RemovedFromGroup));
+
+        #[cfg(feature = "by_ref_proposal")]
+        {
+            let res = bob.group.propose_update(vec![]).await;
+            assert_matches!(res, Err(MlsError::RemovedFromGroup));
+        }
+
+        let restored = Group::from_snapshot(bob.group.config.clone(), bob.group.snapshot())
+            .await
+            .unwrap();
+
+        assert!(restored.is_removed());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn removed_member_rejects_invalid_commit_before_removal() {
+        let (mut alice, mut bob) =
+            test_two_member_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, true).await;
+
+        alice.group.commit_modifiers.modify_leaf = |leaf, _| {
+            leaf.signature = vec![0; leaf.signature.len()];
+            None
+        };
+
+        let commit = alice
+            .group
+            .commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .commit_message;
+
+        let res = bob.group.process_incoming_message(commit).await;
+
+        assert!(res.is_err());
+        assert!(!bob.group.is_removed());
+    }
 }
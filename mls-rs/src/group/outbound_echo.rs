@@ -0,0 +1,164 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::VecDeque;
+
+use super::{commit::CommitHash, framing::ContentType};
+
+/// Message sent by this member and delivered back to it, reported by
+/// [`ReceivedMessage::OwnMessageEcho`](crate::group::ReceivedMessage::OwnMessageEcho).
+///
+/// The message was already accounted for when it was created, so recognizing
+/// it does not change the group state.
+#[cfg_attr(
+    all(feature = "ffi", not(test)),
+    safer_ffi_gen::ffi_type(clone, opaque)
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OwnMessageEcho {
+    /// Epoch in which the message was sent.
+    pub epoch: u64,
+    pub content_type: ContentType,
+}
+
+#[derive(Clone, Debug)]
+struct SentMessage {
+    hash: CommitHash,
+    echo: OwnMessageEcho,
+}
+
+/// Hashes of the last application messages and proposals sent by this member.
+/// The cache lives in memory only and is not part of the group state written
+/// to storage.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OutboundEchoCache {
+    sent: VecDeque<SentMessage>,
+}
+
+impl OutboundEchoCache {
+    pub(crate) fn insert(
+        &mut self,
+        hash: CommitHash,
+        epoch: u64,
+        content_type: ContentType,
+        capacity: usize,
+    ) {
+        while !self.sent.is_empty() && self.sent.len() >= capacity {
+            self.sent.pop_front();
+        }
+
+        let echo = OwnMessageEcho {
+            epoch,
+            content_type,
+        };
+
+        self.sent.push_back(SentMessage { hash, echo });
+    }
+
+    /// Whether a message of `epoch` may be an echo, so that the message only
+    /// has to be hashed if this returns `true`.
+    pub(crate) fn has_epoch(&self, epoch: u64) -> bool {
+        self.sent.iter().any(|sent| sent.echo.epoch == epoch)
+    }
+
+    pub(crate) fn find(&self, hash: &CommitHash) -> Option<OwnMessageEcho> {
+        self.sent
+            .iter()
+            .find(|sent| &sent.hash == hash)
+            .map(|sent| sent.echo.clone())
+    }
+}
+
+#[cfg(all(test, feature = "by_ref_proposal"))]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::{
+            framing::ContentType,
+            test_utils::{test_group, test_group_custom_config},
+            ReceivedMessage,
+        },
+    };
+
+    use super::OwnMessageEcho;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn own_messages_are_recognized_when_echoed() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.outbound_echo_cache_size(2)
+        })
+        .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+        let epoch = alice.group.current_epoch();
+
+        #[cfg(feature = "private_message")]
+        let sent = [
+            alice
+                .group
+                .encrypt_application_message(b"hello", vec![])
+                .await
+                .unwrap(),
+            alice.group.propose_update(vec![]).await.unwrap(),
+        ];
+
+        #[cfg(not(feature = "private_message"))]
+        let sent = [alice.group.propose_update(vec![]).await.unwrap()];
+
+        for message in sent.iter().cloned() {
+            bob.process_message(message).await.unwrap();
+        }
+
+        for (message, content_type) in sent.into_iter().rev().zip([
+            ContentType::Proposal,
+            #[cfg(feature = "private_message")]
+            ContentType::Application,
+        ]) {
+            let received = alice.process_message(message.clone()).await.unwrap();
+
+            assert_matches!(
+                received,
+                ReceivedMessage::OwnMessageEcho(OwnMessageEcho { epoch: e, content_type: c })
+                    if e == epoch && c == content_type
+            );
+
+            // Echoes are recognized every time they are delivered
+            let received = alice.process_message(message).await.unwrap();
+            assert_matches!(received, ReceivedMessage::OwnMessageEcho(_));
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn oldest_messages_are_evicted() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.outbound_echo_cache_size(1)
+        })
+        .await;
+
+        let first = alice.group.propose_update(vec![]).await.unwrap();
+        let second = alice.group.propose_update(vec![]).await.unwrap();
+
+        let received = alice.process_message(second).await.unwrap();
+        assert_matches!(received, ReceivedMessage::OwnMessageEcho(_));
+
+        let res = alice.process_message(first).await;
+        assert_matches!(res, Err(MlsError::CantProcessMessageFromSelf));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn own_messages_are_not_recognized_by_default() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let proposal = alice.group.propose_update(vec![]).await.unwrap();
+
+        let res = alice.process_message(proposal).await;
+        assert_matches!(res, Err(MlsError::CantProcessMessageFromSelf));
+    }
+}
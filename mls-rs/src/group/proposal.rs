@@ -281,6 +281,71 @@ impl CustomProposal {
     }
 }
 
+/// Properties of a custom proposal type, registered with
+/// [`ClientBuilder::custom_proposal_type_with_metadata`](crate::client_builder::ClientBuilder::custom_proposal_type_with_metadata).
+///
+/// All members of a group should register the same metadata for a proposal
+/// type. The default values are the ones assumed for custom proposal types
+/// registered without metadata.
+#[cfg(feature = "custom_proposal")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CustomProposalMetadata {
+    /// Commits covering a proposal of this type must contain a path.
+    pub path_required: bool,
+    /// Proposals of this type may be sent by external senders listed in the
+    /// [ExternalSendersExt](crate::extension::built_in::ExternalSendersExt).
+    pub external_senders_allowed: bool,
+    /// Proposals of this type must be sent by value. Unlike
+    /// [`ClientBuilder::by_value_proposal_type`](crate::client_builder::ClientBuilder::by_value_proposal_type),
+    /// which only applies to the commits of this member, proposals of this
+    /// type received by reference are rejected.
+    pub by_value_only: bool,
+}
+
+#[cfg(feature = "custom_proposal")]
+impl Default for CustomProposalMetadata {
+    fn default() -> Self {
+        Self {
+            path_required: false,
+            external_senders_allowed: true,
+            by_value_only: false,
+        }
+    }
+}
+
+#[cfg(feature = "custom_proposal")]
+impl CustomProposalMetadata {
+    /// Create the default metadata.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set whether commits covering a proposal of this type must contain a path.
+    pub fn with_path_required(self, path_required: bool) -> Self {
+        Self {
+            path_required,
+            ..self
+        }
+    }
+
+    /// Set whether proposals of this type may be sent by external senders.
+    pub fn with_external_senders_allowed(self, external_senders_allowed: bool) -> Self {
+        Self {
+            external_senders_allowed,
+            ..self
+        }
+    }
+
+    /// Set whether proposals of this type must be sent by value.
+    pub fn with_by_value_only(self, by_value_only: bool) -> Self {
+        Self {
+            by_value_only,
+            ..self
+        }
+    }
+}
+
 /// Trait to simplify creating custom proposals that are serialized with MLS
 /// encoding.
 #[cfg(feature = "custom_proposal")]
@@ -310,105 +375,60 @@ pub trait MlsCustomProposal: MlsSize + MlsEncode + MlsDecode + Sized {
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[mls_codec(tag_type = "ProposalType")]
 #[repr(u16)]
 #[non_exhaustive]
 /// An enum that represents all possible types of proposals.
 pub enum Proposal {
+    #[mls_codec(tag = "ProposalType::ADD")]
     Add(alloc::boxed::Box<AddProposal>),
     #[cfg(feature = "by_ref_proposal")]
+    #[mls_codec(tag = "ProposalType::UPDATE")]
     Update(UpdateProposal),
+    #[mls_codec(tag = "ProposalType::REMOVE")]
     Remove(RemoveProposal),
     #[cfg(feature = "psk")]
+    #[mls_codec(tag = "ProposalType::PSK")]
     Psk(PreSharedKeyProposal),
+    #[mls_codec(tag = "ProposalType::RE_INIT")]
     ReInit(ReInitProposal),
+    #[mls_codec(tag = "ProposalType::EXTERNAL_INIT")]
     ExternalInit(ExternalInit),
+    #[mls_codec(tag = "ProposalType::GROUP_CONTEXT_EXTENSIONS")]
     GroupContextExtensions(ExtensionList),
     #[cfg(feature = "custom_proposal")]
-    Custom(CustomProposal),
+    #[mls_codec(other)]
+    Custom(#[mls_codec(with = "custom_proposal_codec")] CustomProposal),
 }
 
-impl MlsSize for Proposal {
-    fn mls_encoded_len(&self) -> usize {
-        let inner_len = match self {
-            Proposal::Add(p) => p.mls_encoded_len(),
-            #[cfg(feature = "by_ref_proposal")]
-            Proposal::Update(p) => p.mls_encoded_len(),
-            Proposal::Remove(p) => p.mls_encoded_len(),
-            #[cfg(feature = "psk")]
-            Proposal::Psk(p) => p.mls_encoded_len(),
-            Proposal::ReInit(p) => p.mls_encoded_len(),
-            Proposal::ExternalInit(p) => p.mls_encoded_len(),
-            Proposal::GroupContextExtensions(p) => p.mls_encoded_len(),
-            #[cfg(feature = "custom_proposal")]
-            Proposal::Custom(p) => mls_rs_codec::byte_vec::mls_encoded_len(&p.data),
-        };
+#[cfg(feature = "custom_proposal")]
+mod custom_proposal_codec {
+    use alloc::vec::Vec;
+    use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
-        self.proposal_type().mls_encoded_len() + inner_len
-    }
-}
+    use super::{CustomProposal, ProposalType};
 
-impl MlsEncode for Proposal {
-    fn mls_encode(&self, writer: &mut Vec<u8>) -> Result<(), mls_rs_codec::Error> {
-        self.proposal_type().mls_encode(writer)?;
+    pub fn mls_encoded_len(p: &CustomProposal) -> usize {
+        p.proposal_type.mls_encoded_len() + mls_rs_codec::byte_vec::mls_encoded_len(&p.data)
+    }
 
-        match self {
-            Proposal::Add(p) => p.mls_encode(writer),
-            #[cfg(feature = "by_ref_proposal")]
-            Proposal::Update(p) => p.mls_encode(writer),
-            Proposal::Remove(p) => p.mls_encode(writer),
-            #[cfg(feature = "psk")]
-            Proposal::Psk(p) => p.mls_encode(writer),
-            Proposal::ReInit(p) => p.mls_encode(writer),
-            Proposal::ExternalInit(p) => p.mls_encode(writer),
-            Proposal::GroupContextExtensions(p) => p.mls_encode(writer),
-            #[cfg(feature = "custom_proposal")]
-            Proposal::Custom(p) => {
-                if p.proposal_type.raw_value() <= 7 {
-                    // #[cfg(feature = "std")]
-                    // return Err(mls_rs_codec::Error::Custom(
-                    //     "custom proposal types can not be set to defined values of 0-7".to_string(),
-                    // ));
-
-                    // #[cfg(not(feature = "std"))]
-                    return Err(mls_rs_codec::Error::Custom(2));
-                }
-                mls_rs_codec::byte_vec::mls_encode(&p.data, writer)
-            }
+    pub fn mls_encode(p: &CustomProposal, writer: &mut Vec<u8>) -> Result<(), mls_rs_codec::Error> {
+        if p.proposal_type.raw_value() <= 7 {
+            // Custom proposal types can not be set to defined values of 0-7
+            return Err(mls_rs_codec::Error::Custom(2));
         }
-    }
-}
 
-impl MlsDecode for Proposal {
-    fn mls_decode(reader: &mut &[u8]) -> Result<Self, mls_rs_codec::Error> {
-        let proposal_type = ProposalType::mls_decode(reader)?;
+        p.proposal_type.mls_encode(writer)?;
+        mls_rs_codec::byte_vec::mls_encode(&p.data, writer)
+    }
 
-        Ok(match proposal_type {
-            ProposalType::ADD => {
-                Proposal::Add(alloc::boxed::Box::new(AddProposal::mls_decode(reader)?))
-            }
-            #[cfg(feature = "by_ref_proposal")]
-            ProposalType::UPDATE => Proposal::Update(UpdateProposal::mls_decode(reader)?),
-            ProposalType::REMOVE => Proposal::Remove(RemoveProposal::mls_decode(reader)?),
-            #[cfg(feature = "psk")]
-            ProposalType::PSK => Proposal::Psk(PreSharedKeyProposal::mls_decode(reader)?),
-            ProposalType::RE_INIT => Proposal::ReInit(ReInitProposal::mls_decode(reader)?),
-            ProposalType::EXTERNAL_INIT => {
-                Proposal::ExternalInit(ExternalInit::mls_decode(reader)?)
-            }
-            ProposalType::GROUP_CONTEXT_EXTENSIONS => {
-                Proposal::GroupContextExtensions(ExtensionList::mls_decode(reader)?)
-            }
-            #[cfg(feature = "custom_proposal")]
-            custom => Proposal::Custom(CustomProposal {
-                proposal_type: custom,
-                data: mls_rs_codec::byte_vec::mls_decode(reader)?,
-            }),
-            // TODO fix test dependency on openssl loading codec with default features
-            #[cfg(not(feature = "custom_proposal"))]
-            _ => return Err(mls_rs_codec::Error::Custom(3)),
+    pub fn mls_decode(reader: &mut &[u8]) -> Result<CustomProposal, mls_rs_codec::Error> {
+        Ok(CustomProposal {
+            proposal_type: ProposalType::mls_decode(reader)?,
+            data: mls_rs_codec::byte_vec::mls_decode(reader)?,
         })
     }
 }
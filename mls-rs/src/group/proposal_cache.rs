@@ -7,7 +7,7 @@ use alloc::vec::Vec;
 use super::{
     message_processor::ProvisionalState,
     mls_rules::{CommitDirection, CommitSource, MlsRules},
-    GroupState, ProposalOrRef,
+    GroupState, LifetimePolicy, ProposalOrRef,
 };
 use crate::{
     client::MlsError,
@@ -19,10 +19,20 @@ use crate::{
 };
 
 #[cfg(feature = "by_ref_proposal")]
-use crate::group::{proposal_filter::FilterStrategy, ProposalRef, ProtocolVersion};
+use crate::{
+    group::{
+        proposal_filter::{FilterStrategy, ProposalInfo},
+        ProposalRef, ProposalType, ProtocolVersion,
+    },
+    tree_kem::{node::LeafIndex, TreeKemPublic},
+    ExtensionList,
+};
 
 use crate::tree_kem::leaf_node::LeafNode;
 
+#[cfg(feature = "psk")]
+use super::util::ensure_required_join_psk;
+
 #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
 use std::collections::HashMap;
 
@@ -54,6 +64,9 @@ pub(crate) struct ProposalCache {
     pub(crate) proposals: HashMap<ProposalRef, CachedProposal>,
     #[cfg(not(feature = "std"))]
     pub(crate) proposals: Vec<(ProposalRef, CachedProposal)>,
+    /// Times after which cached proposals can no longer be committed. Only
+    /// proposals with a TTL are listed.
+    expirations: Vec<(ProposalRef, MlsTime)>,
 }
 
 #[cfg(feature = "by_ref_proposal")]
@@ -66,6 +79,7 @@ impl Debug for ProposalCache {
                 &mls_rs_core::debug::pretty_group_id(&self.group_id),
             )
             .field("proposals", &self.proposals)
+            .field("expirations", &self.expirations)
             .finish()
     }
 }
@@ -77,6 +91,7 @@ impl ProposalCache {
             protocol_version,
             group_id,
             proposals: Default::default(),
+            expirations: Default::default(),
         }
     }
 
@@ -90,12 +105,30 @@ impl ProposalCache {
             protocol_version,
             group_id,
             proposals,
+            expirations: Default::default(),
         }
     }
 
+    /// Restore the expiry times written by [`Self::export_expirations`], as
+    /// seconds since the Unix epoch.
+    pub fn import_expirations(&mut self, expirations: Vec<(ProposalRef, u64)>) {
+        self.expirations = expirations
+            .into_iter()
+            .map(|(r, seconds)| (r, MlsTime::from(seconds)))
+            .collect();
+    }
+
+    pub fn export_expirations(&self) -> Vec<(ProposalRef, u64)> {
+        self.expirations
+            .iter()
+            .map(|(r, time)| (r.clone(), time.seconds_since_epoch()))
+            .collect()
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.proposals.clear();
+        self.expirations.clear();
     }
 
     #[cfg(feature = "private_message")]
@@ -115,13 +148,84 @@ impl ProposalCache {
         self.proposals.push((proposal_ref, cached_proposal));
     }
 
-    pub fn prepare_commit(
+    pub fn remove(&mut self, proposal_ref: &ProposalRef) {
+        #[cfg(feature = "std")]
+        self.proposals.remove(proposal_ref);
+
+        #[cfg(not(feature = "std"))]
+        self.proposals.retain(|(r, _)| r != proposal_ref);
+
+        self.expirations.retain(|(r, _)| r != proposal_ref);
+    }
+
+    pub fn contains(&self, proposal_ref: &ProposalRef) -> bool {
+        #[cfg(feature = "std")]
+        return self.proposals.contains_key(proposal_ref);
+
+        #[cfg(not(feature = "std"))]
+        return self.proposals.iter().any(|(r, _)| r == proposal_ref);
+    }
+
+    pub fn expiry(&self, proposal_ref: &ProposalRef) -> Option<MlsTime> {
+        self.expirations
+            .iter()
+            .find_map(|(r, time)| (r == proposal_ref).then_some(*time))
+    }
+
+    /// Set the time after which the cached proposal `proposal_ref` can no
+    /// longer be committed, or let it live until the next commit if
+    /// `expires_at` is `None`.
+    pub fn set_expiry(&mut self, proposal_ref: &ProposalRef, expires_at: Option<MlsTime>) {
+        self.expirations.retain(|(r, _)| r != proposal_ref);
+
+        if let Some(expires_at) = expires_at {
+            self.expirations.push((proposal_ref.clone(), expires_at));
+        }
+    }
+
+    fn is_expired(&self, proposal_ref: &ProposalRef, time: Option<MlsTime>) -> bool {
+        matches!(
+            (self.expiry(proposal_ref), current_time(time)),
+            (Some(expires_at), Some(time)) if time > expires_at
+        )
+    }
+
+    /// Cached proposals ordered by reference.
+    ///
+    /// The order depends neither on the order in which proposals were received
+    /// nor on the container of the cache, so that the commits of replicas
+    /// caching the same proposals list them in the same order.
+    pub fn sorted(&self) -> Vec<(&ProposalRef, &CachedProposal)> {
+        #[cfg(feature = "std")]
+        let mut proposals = self.proposals.iter().collect::<Vec<_>>();
+
+        #[cfg(not(feature = "std"))]
+        let mut proposals = self
+            .proposals
+            .iter()
+            .map(|(r, p)| (r, p))
+            .collect::<Vec<_>>();
+
+        proposals.sort_by_key(|(r, _)| *r);
+        proposals
+    }
+
+    /// Cached proposals that have not expired at `time` and whose type may be
+    /// committed by reference, followed by `additional_proposals`.
+    pub fn prepare_commit<F>(
         &self,
         sender: Sender,
         additional_proposals: Vec<Proposal>,
-    ) -> ProposalBundle {
-        self.proposals
-            .iter()
+        time: Option<MlsTime>,
+        by_reference_allowed: F,
+    ) -> ProposalBundle
+    where
+        F: Fn(ProposalType) -> bool,
+    {
+        self.sorted()
+            .into_iter()
+            .filter(|(r, _)| !self.is_expired(r, time))
+            .filter(|(_, p)| by_reference_allowed(p.proposal.proposal_type()))
             .map(|(r, p)| {
                 (
                     p.proposal.clone(),
@@ -137,6 +241,37 @@ impl ProposalCache {
             .collect()
     }
 
+    /// Cached proposals that [`prepare_commit`](Self::prepare_commit) leaves
+    /// out, because they expired at `time` or their type may not be committed
+    /// by reference.
+    pub fn excluded_from_commit<F>(
+        &self,
+        time: Option<MlsTime>,
+        by_reference_allowed: F,
+    ) -> Vec<ProposalInfo<Proposal>>
+    where
+        F: Fn(ProposalType) -> bool,
+    {
+        self.sorted()
+            .into_iter()
+            .filter(|(r, p)| {
+                self.is_expired(r, time) || !by_reference_allowed(p.proposal.proposal_type())
+            })
+            .map(|(r, p)| ProposalInfo {
+                proposal: p.proposal.clone(),
+                sender: p.sender,
+                source: ProposalSource::ByReference(r.clone()),
+            })
+            .collect()
+    }
+
+    /// Resolve the proposals of a received commit.
+    ///
+    /// Proposal expiry is a local policy for the commits created by this
+    /// member. Proposals referenced by the commits of other members are
+    /// resolved even if they expired, since other members may use different
+    /// clocks and TTLs. They are reported by
+    /// [`expired_references`](Self::expired_references) instead.
     pub fn resolve_for_commit(
         &self,
         sender: Sender,
@@ -169,6 +304,40 @@ impl ProposalCache {
 
         Ok(proposals)
     }
+
+    /// References of `proposal_list` to cached proposals that expired at `time`.
+    #[cfg_attr(not(any(test, feature = "state_update")), allow(dead_code))]
+    pub fn expired_references(
+        &self,
+        proposal_list: &[ProposalOrRef],
+        time: Option<MlsTime>,
+    ) -> Vec<ProposalRef> {
+        proposal_list
+            .iter()
+            .filter_map(|p| match p {
+                ProposalOrRef::Reference(r) if self.is_expired(r, time) => Some(r.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Time at which a proposal received or sent at `time` expires, given the
+/// `ttl` of proposals in seconds.
+#[cfg(feature = "by_ref_proposal")]
+pub(crate) fn proposal_expiry(time: Option<MlsTime>, ttl: Option<u64>) -> Option<MlsTime> {
+    let seconds = current_time(time)?.seconds_since_epoch();
+    Some(MlsTime::from(seconds.saturating_add(ttl?)))
+}
+
+/// `time` if known, and the current time otherwise.
+#[cfg(feature = "by_ref_proposal")]
+fn current_time(time: Option<MlsTime>) -> Option<MlsTime> {
+    #[cfg(feature = "std")]
+    return time.or_else(|| Some(MlsTime::now()));
+
+    #[cfg(not(feature = "std"))]
+    return time;
 }
 
 #[cfg(not(feature = "by_ref_proposal"))]
@@ -214,6 +383,7 @@ impl GroupState {
         psk_storage: &P,
         user_rules: &F,
         commit_time: Option<MlsTime>,
+        lifetime_policy: LifetimePolicy,
         direction: CommitDirection,
     ) -> Result<ProvisionalState, MlsError>
     where
@@ -256,6 +426,8 @@ impl GroupState {
             external_leaf,
             identity_provider,
             psk_storage,
+            lifetime_policy,
+            user_rules,
             #[cfg(feature = "by_ref_proposal")]
             &self.context.group_id,
         );
@@ -280,10 +452,10 @@ impl GroupState {
             .await?;
 
         #[cfg(feature = "by_ref_proposal")]
-        let unused_proposals = unused_proposals(
+        let mut unused_proposals = unused_proposals(
             match direction {
                 CommitDirection::Send => all_proposals,
-                CommitDirection::Receive => self.proposals.proposals.iter().collect(),
+                CommitDirection::Receive => self.proposals.sorted().into_iter().collect(),
             },
             &applier_output.applied_proposals,
         );
@@ -295,9 +467,39 @@ impl GroupState {
             group_context.extensions = ext;
         }
 
+        // Commits created by this member include the required PSK when they add
+        // members. Joiners see the group context resulting from the commit.
+        #[cfg(all(feature = "psk", feature = "by_ref_proposal"))]
+        if matches!(direction, CommitDirection::Receive) {
+            ensure_required_join_psk(&applier_output.applied_proposals, &group_context.extensions)?;
+        }
+
+        #[cfg(all(feature = "psk", not(feature = "by_ref_proposal")))]
+        if matches!(direction, CommitDirection::Receive) {
+            ensure_required_join_psk(&proposals, &group_context.extensions)?;
+        }
+
+        #[cfg(feature = "by_ref_proposal")]
+        let stale_proposals = match direction {
+            CommitDirection::Send => Vec::new(),
+            CommitDirection::Receive => {
+                take_stale_proposals(
+                    &mut unused_proposals,
+                    &applier_output.applied_proposals,
+                    &applier_output.new_tree,
+                    &group_context.extensions,
+                    user_rules,
+                )
+                .await?
+            }
+        };
+
         #[cfg(feature = "by_ref_proposal")]
         let proposals = applier_output.applied_proposals;
 
+        #[cfg(feature = "by_ref_proposal")]
+        let rejected_proposals = applier_output.rejected_proposals;
+
         Ok(ProvisionalState {
             public_tree: applier_output.new_tree,
             group_context,
@@ -306,6 +508,10 @@ impl GroupState {
             indexes_of_added_kpkgs: applier_output.indexes_of_added_kpkgs,
             #[cfg(feature = "by_ref_proposal")]
             unused_proposals,
+            #[cfg(feature = "by_ref_proposal")]
+            stale_proposals,
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals,
         })
     }
 }
@@ -341,6 +547,69 @@ fn unused_proposals(
         .collect()
 }
 
+/// Remove the proposals of the prior epoch that were not committed and are no
+/// longer valid after applying the commit from `unused_proposals` and return them.
+#[cfg(feature = "by_ref_proposal")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn take_stale_proposals<F: MlsRules>(
+    unused_proposals: &mut Vec<ProposalInfo<Proposal>>,
+    applied_proposals: &ProposalBundle,
+    new_tree: &TreeKemPublic,
+    new_extensions: &ExtensionList,
+    user_rules: &F,
+) -> Result<Vec<ProposalInfo<Proposal>>, MlsError> {
+    let new_roster = new_tree.roster();
+    let mut stale = Vec::new();
+
+    for proposal in core::mem::take(unused_proposals) {
+        let is_valid = still_valid(&proposal, applied_proposals, new_tree)
+            && user_rules
+                .revalidate_proposal(&proposal, &new_roster, new_extensions)
+                .await
+                .map_err(|e| MlsError::MlsRulesError(e.into_any_error()))?;
+
+        if is_valid {
+            unused_proposals.push(proposal);
+        } else {
+            stale.push(proposal);
+        }
+    }
+
+    Ok(stale)
+}
+
+#[cfg(feature = "by_ref_proposal")]
+fn still_valid(
+    proposal: &ProposalInfo<Proposal>,
+    applied_proposals: &ProposalBundle,
+    new_tree: &TreeKemPublic,
+) -> bool {
+    let removed = |index: LeafIndex| {
+        applied_proposals
+            .removals
+            .iter()
+            .any(|p| p.proposal.to_remove == index)
+    };
+
+    if matches!(proposal.sender, Sender::Member(index) if removed(LeafIndex(index))) {
+        return false;
+    }
+
+    match &proposal.proposal {
+        Proposal::Remove(remove) => {
+            !removed(remove.to_remove) && new_tree.get_leaf_node(remove.to_remove).is_ok()
+        }
+        Proposal::Add(add) => {
+            let signature_key = &add.key_package.leaf_node.signing_identity.signature_key;
+
+            !new_tree
+                .non_empty_leaves()
+                .any(|(_, leaf)| &leaf.signing_identity.signature_key == signature_key)
+        }
+        _ => true,
+    }
+}
+
 // TODO add tests for lite version of filtering
 #[cfg(all(feature = "by_ref_proposal", test))]
 pub(crate) mod test_utils {
@@ -564,6 +833,7 @@ pub(crate) mod test_utils {
                     psk_storage,
                     &user_rules,
                     None,
+                    Default::default(),
                     CommitDirection::Receive,
                 )
                 .await
@@ -596,7 +866,7 @@ pub(crate) mod test_utils {
                 ConfirmationTag::empty(cipher_suite_provider).await,
             );
 
-            let proposals = self.prepare_commit(sender, additional_proposals);
+            let proposals = self.prepare_commit(sender, additional_proposals, None, |_| true);
 
             state
                 .apply_resolved(
@@ -608,6 +878,7 @@ pub(crate) mod test_utils {
                     psk_storage,
                     &user_rules,
                     None,
+                    Default::default(),
                     CommitDirection::Send,
                 )
                 .await
@@ -622,7 +893,7 @@ mod tests {
 
     use super::test_utils::{make_proposal_cache, pass_through_rules, CommitReceiver};
     use super::{CachedProposal, ProposalCache};
-    use crate::client::MlsError;
+    use crate::client::{HpkeKeyHolder, LeafDataKind, LeafNodeSourceKind, MlsError};
     use crate::group::message_processor::ProvisionalState;
     use crate::group::mls_rules::{CommitDirection, CommitSource, EncryptionOptions};
     use crate::group::proposal_filter::{ProposalBundle, ProposalInfo, ProposalSource};
@@ -662,7 +933,7 @@ mod tests {
             Lifetime,
         },
     };
-    use crate::{KeyPackage, MlsRules};
+    use crate::{time::MlsTime, KeyPackage, MlsRules};
 
     use crate::extension::RequiredCapabilitiesExt;
 
@@ -674,9 +945,11 @@ mod tests {
 
     #[cfg(feature = "psk")]
     use crate::{
+        client::PskFailureKind,
+        extension::RequiredJoinPskExt,
         group::proposal::PreSharedKeyProposal,
         psk::{
-            ExternalPskId, JustPreSharedKeyID, PreSharedKeyID, PskGroupId, PskNonce,
+            ExternalPskId, JustPreSharedKeyID, PreSharedKeyID, PskGroupId, PskNonce, PskUsage,
             ResumptionPSKUsage, ResumptionPsk,
         },
     };
@@ -844,6 +1117,7 @@ mod tests {
                 &Default::default(),
                 &BasicIdentityProvider,
                 &cipher_suite_provider,
+                &DefaultMlsRules::new(),
                 true,
             )
             .await
@@ -856,6 +1130,10 @@ mod tests {
             indexes_of_added_kpkgs: vec![LeafIndex(1)],
             #[cfg(feature = "state_update")]
             unused_proposals: vec![],
+            #[cfg(feature = "by_ref_proposal")]
+            stale_proposals: vec![],
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: vec![],
             applied_proposals: bundle,
         };
 
@@ -1217,6 +1495,123 @@ mod tests {
         assert!(!cache.is_empty())
     }
 
+    #[test]
+    fn cached_proposals_are_committed_in_reference_order() {
+        let proposals = (0..8u8)
+            .map(|i| {
+                let proposal = Proposal::Remove(RemoveProposal {
+                    to_remove: LeafIndex(u32::from(i) + 1),
+                });
+
+                (ProposalRef::new_fake(vec![i; 32]), proposal)
+            })
+            .collect::<Vec<_>>();
+
+        let mut cache = make_proposal_cache();
+        let mut reversed = make_proposal_cache();
+
+        for (proposal_ref, proposal) in &proposals {
+            cache.insert(proposal_ref.clone(), proposal.clone(), Sender::Member(0));
+        }
+
+        for (proposal_ref, proposal) in proposals.iter().rev() {
+            reversed.insert(proposal_ref.clone(), proposal.clone(), Sender::Member(0));
+        }
+
+        let expected = proposals
+            .iter()
+            .map(|(proposal_ref, _)| ProposalOrRef::Reference(proposal_ref.clone()))
+            .collect::<Vec<_>>();
+
+        for cache in [cache, reversed] {
+            let committed = cache
+                .prepare_commit(Sender::Member(0), vec![], None, |_| true)
+                .into_proposals_or_refs();
+
+            assert_eq!(committed, expected);
+        }
+    }
+
+    #[test]
+    fn expired_proposals_are_not_committed() {
+        let mut cache = make_proposal_cache();
+
+        for i in 0..2u8 {
+            let proposal = Proposal::Remove(RemoveProposal {
+                to_remove: LeafIndex(u32::from(i) + 1),
+            });
+
+            cache.insert(
+                ProposalRef::new_fake(vec![i; 32]),
+                proposal,
+                Sender::Member(0),
+            );
+        }
+
+        let expiring = ProposalRef::new_fake(vec![0; 32]);
+        cache.set_expiry(&expiring, Some(MlsTime::from(100)));
+
+        let committed = |time: u64| {
+            cache
+                .prepare_commit(Sender::Member(0), vec![], Some(MlsTime::from(time)), |_| {
+                    true
+                })
+                .into_proposals_or_refs()
+                .len()
+        };
+
+        // Proposals are valid until their expiry time, inclusive
+        assert_eq!(committed(100), 2);
+        assert_eq!(committed(101), 1);
+
+        let excluded = cache.excluded_from_commit(Some(MlsTime::from(101)), |_| true);
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].source, ProposalSource::ByReference(expiring));
+    }
+
+    #[test]
+    fn commits_referencing_expired_proposals_are_accepted() {
+        let mut cache = make_proposal_cache();
+        let proposal_ref = ProposalRef::new_fake(vec![0; 32]);
+
+        let proposal = Proposal::Remove(RemoveProposal {
+            to_remove: LeafIndex(1),
+        });
+
+        cache.insert(proposal_ref.clone(), proposal, Sender::Member(0));
+        cache.set_expiry(&proposal_ref, Some(MlsTime::from(100)));
+
+        let proposal_list = vec![ProposalOrRef::Reference(proposal_ref.clone())];
+
+        let expired = cache.expired_references(&proposal_list, Some(MlsTime::from(101)));
+        assert_eq!(expired, vec![proposal_ref.clone()]);
+
+        let expired = cache.expired_references(&proposal_list, Some(MlsTime::from(100)));
+        assert!(expired.is_empty());
+
+        let res = cache.resolve_for_commit(Sender::Member(1), proposal_list);
+
+        assert_eq!(res.unwrap().remove_proposals().len(), 1);
+    }
+
+    #[test]
+    fn expiry_is_dropped_with_the_proposal() {
+        let mut cache = make_proposal_cache();
+        let proposal_ref = ProposalRef::new_fake(vec![0; 32]);
+
+        let proposal = Proposal::Remove(RemoveProposal {
+            to_remove: LeafIndex(1),
+        });
+
+        cache.insert(proposal_ref.clone(), proposal, Sender::Member(0));
+        cache.set_expiry(&proposal_ref, Some(MlsTime::from(100)));
+        assert_eq!(cache.expiry(&proposal_ref), Some(MlsTime::from(100)));
+
+        cache.remove(&proposal_ref);
+        assert_eq!(cache.expiry(&proposal_ref), None);
+        assert!(cache.export_expirations().is_empty());
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_proposal_cache_resolve() {
         let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
@@ -2033,6 +2428,94 @@ mod tests {
         key_package
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn key_package_with_init_key(key: crypto::HpkePublicKey) -> KeyPackage {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let (mut key_package, signer) =
+            test_key_package_with_signer(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "test").await;
+
+        key_package.hpke_init_key = key;
+        key_package.sign(&cs, &signer, &()).await.unwrap();
+
+        key_package
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_add_with_init_key_of_member_fails() {
+        let (alice, tree) = new_tree("alice").await;
+
+        let key_package =
+            key_package_with_init_key(tree.get_leaf_node(alice).unwrap().public_key.clone()).await;
+
+        let res = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .receive([Proposal::Add(Box::new(AddProposal { key_package }))])
+        .await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::HpkeKeyReuse(
+                HpkeKeyHolder::AddInitKey(0),
+                HpkeKeyHolder::Leaf(0)
+            ))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_adds_with_same_init_key_fails() {
+        let (alice, tree) = new_tree("alice").await;
+
+        let first = test_key_package(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await;
+        let second = key_package_with_init_key(first.hpke_init_key.clone()).await;
+
+        let res = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .receive(
+            [first, second].map(|key_package| Proposal::Add(Box::new(AddProposal { key_package }))),
+        )
+        .await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::HpkeKeyReuse(
+                HpkeKeyHolder::AddInitKey(1),
+                HpkeKeyHolder::AddInitKey(0)
+            ))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_add_with_hpke_key_of_removed_member_succeeds() {
+        let (alice, mut tree) = new_tree("alice").await;
+        let bob = add_member(&mut tree, "bob").await;
+
+        let key_package =
+            key_package_with_public_key(tree.get_leaf_node(bob).unwrap().public_key.clone()).await;
+
+        let res = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .receive([
+            Proposal::Remove(RemoveProposal { to_remove: bob }),
+            Proposal::Add(Box::new(AddProposal { key_package })),
+        ])
+        .await;
+
+        assert!(res.is_ok());
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn receiving_add_with_invalid_key_package_fails() {
         let (alice, tree) = new_tree("alice").await;
@@ -2106,7 +2589,10 @@ mod tests {
             .send()
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(_, LeafDataKind::EncryptionKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2160,7 +2646,14 @@ mod tests {
         .receive([proposal_ref])
         .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(_),
+                LeafNodeSourceKind::Update,
+                LeafNodeSourceKind::KeyPackage
+            ))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2285,6 +2778,74 @@ mod tests {
         assert_matches!(res, Err(MlsError::InvalidPskNonceLength,));
     }
 
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receiving_add_without_required_join_psk_fails() {
+        let (alice, tree) = new_tree("alice").await;
+
+        let extensions = ExtensionList::from(vec![RequiredJoinPskExt::new(ExternalPskId::new(
+            b"foo".to_vec(),
+        ))
+        .into_extension()
+        .unwrap()]);
+
+        let receiver = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .with_extensions(extensions);
+
+        let (signing_identity, secret_key) =
+            get_test_signing_identity(TEST_CIPHER_SUITE, b"frank").await;
+
+        let generator = KeyPackageGenerator {
+            protocol_version: TEST_PROTOCOL_VERSION,
+            cipher_suite_provider: &test_cipher_suite_provider(TEST_CIPHER_SUITE),
+            signing_identity: &signing_identity,
+            signing_key: &secret_key,
+            identity_provider: &BasicIdentityProvider,
+        };
+
+        let capabilities = Capabilities {
+            extensions: vec![mls_rs_core::extension::ExtensionType::REQUIRED_JOIN_PSK],
+            ..Default::default()
+        };
+
+        let key_package = generator
+            .generate(
+                Lifetime::years(1).unwrap(),
+                capabilities,
+                Default::default(),
+                Default::default(),
+            )
+            .await
+            .unwrap()
+            .key_package;
+
+        let add = Proposal::Add(Box::new(AddProposal { key_package }));
+
+        let res = receiver
+            .receive([add.clone(), Proposal::Psk(new_external_psk(b"bar"))])
+            .await;
+
+        assert_matches!(res, Err(MlsError::MissingRequiredJoinPsk));
+
+        let res = receiver
+            .receive([add, Proposal::Psk(new_external_psk(b"foo"))])
+            .await;
+
+        assert!(res.is_ok());
+
+        // Commits that do not add members do not need the PSK
+        let res = receiver
+            .receive([Proposal::Psk(new_external_psk(b"bar"))])
+            .await;
+
+        assert!(res.is_ok());
+    }
+
     #[cfg(feature = "psk")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn sending_additional_psk_with_invalid_nonce_fails() {
@@ -2678,7 +3239,10 @@ mod tests {
         ])
         .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(1, LeafDataKind::Identity))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2693,7 +3257,10 @@ mod tests {
             .send()
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(1, LeafDataKind::Identity))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2728,6 +3295,45 @@ mod tests {
         );
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn sending_add_proposals_for_same_client_reports_rejection_reason() {
+        let (alice, tree) = new_tree("alice").await;
+
+        let add_one = Proposal::Add(make_add_proposal().await);
+        let add_two = Proposal::Add(make_add_proposal().await);
+        let add_ref_one = make_proposal_ref(&add_one, alice).await;
+        let add_ref_two = make_proposal_ref(&add_two, alice).await;
+
+        let processed_proposals =
+            CommitSender::new(&tree, alice, test_cipher_suite_provider(TEST_CIPHER_SUITE))
+                .cache(add_ref_one.clone(), add_one, alice)
+                .cache(add_ref_two.clone(), add_two, alice)
+                .send()
+                .await
+                .unwrap();
+
+        let committed_add_ref = match &*processed_proposals.0 {
+            [ProposalOrRef::Reference(add_ref)] => add_ref,
+            _ => panic!("committed proposals list does not contain exactly one reference"),
+        };
+
+        let rejected_add_ref = if committed_add_ref == &add_ref_one {
+            add_ref_two
+        } else {
+            add_ref_one
+        };
+
+        assert_matches!(
+            &*processed_proposals.1.rejected_proposals,
+            [rejected] if rejected.proposal_ref() == Some(&rejected_add_ref)
+                && rejected.sender() == &Sender::Member(*alice)
+                && matches!(
+                    *rejected.reason,
+                    MlsError::DuplicateLeafData(1, LeafDataKind::Identity)
+                )
+        );
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn receiving_update_for_different_identity_fails() {
         let (alice, mut tree) = new_tree("alice").await;
@@ -2796,7 +3402,10 @@ mod tests {
         .receive([add])
         .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(1, LeafDataKind::SignatureKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -2823,7 +3432,10 @@ mod tests {
         .send()
         .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(1)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(1, LeafDataKind::SignatureKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -3820,7 +4432,11 @@ mod tests {
         .receive([Proposal::Psk(new_external_psk(b"abc"))])
         .await;
 
-        assert_matches!(res, Err(MlsError::MissingRequiredPsk));
+        assert_matches!(
+            res,
+            Err(MlsError::PskFailure(psks, PskFailureKind::Missing))
+                if psks == [PskUsage::External(ExternalPskId::new(b"abc".to_vec()))]
+        );
     }
 
     #[cfg(feature = "psk")]
@@ -3834,7 +4450,11 @@ mod tests {
             .send()
             .await;
 
-        assert_matches!(res, Err(MlsError::MissingRequiredPsk));
+        assert_matches!(
+            res,
+            Err(MlsError::PskFailure(psks, PskFailureKind::Missing))
+                if psks == [PskUsage::External(ExternalPskId::new(b"abc".to_vec()))]
+        );
     }
 
     #[cfg(feature = "psk")]
@@ -4213,4 +4833,108 @@ mod tests {
 
         assert_eq!(p.proposal_ref(), Some(&proposal_ref));
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn when_receiving_commit_invalidated_proposals_are_stale() {
+        let (alice, mut tree) = new_tree("alice").await;
+        let bob = add_member(&mut tree, "bob").await;
+
+        let proposal = Proposal::Remove(RemoveProposal { to_remove: bob });
+        let proposal_ref = make_proposal_ref(&proposal, alice).await;
+
+        let state = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .cache(proposal_ref.clone(), proposal.clone(), alice)
+        .receive([proposal])
+        .await
+        .unwrap();
+
+        assert!(state.unused_proposals.is_empty());
+
+        let [p] = &state.stale_proposals[..] else {
+            panic!(
+                "Expected single stale proposal but got {:?}",
+                state.stale_proposals
+            );
+        };
+
+        assert_eq!(p.proposal_ref(), Some(&proposal_ref));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn user_rules_can_mark_unused_proposals_stale() {
+        struct RejectUnused;
+
+        #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+        #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+        impl MlsRules for RejectUnused {
+            type Error = Infallible;
+
+            async fn filter_proposals(
+                &self,
+                _: CommitDirection,
+                _: CommitSource,
+                _: &Roster,
+                _: &ExtensionList,
+                proposals: ProposalBundle,
+            ) -> Result<ProposalBundle, Self::Error> {
+                Ok(proposals)
+            }
+
+            #[cfg_attr(coverage_nightly, coverage(off))]
+            fn commit_options(
+                &self,
+                _: &Roster,
+                _: &ExtensionList,
+                _: &ProposalBundle,
+            ) -> Result<CommitOptions, Self::Error> {
+                Ok(Default::default())
+            }
+
+            #[cfg_attr(coverage_nightly, coverage(off))]
+            fn encryption_options(
+                &self,
+                _: &Roster,
+                _: &ExtensionList,
+            ) -> Result<EncryptionOptions, Self::Error> {
+                Ok(Default::default())
+            }
+
+            async fn revalidate_proposal(
+                &self,
+                _: &ProposalInfo<Proposal>,
+                _: &Roster,
+                _: &ExtensionList,
+            ) -> Result<bool, Self::Error> {
+                Ok(false)
+            }
+        }
+
+        let (alice, tree) = new_tree("alice").await;
+
+        let proposal = Proposal::GroupContextExtensions(Default::default());
+        let proposal_ref = make_proposal_ref(&proposal, alice).await;
+
+        let state = CommitReceiver::new(
+            &tree,
+            alice,
+            alice,
+            test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .cache(proposal_ref.clone(), proposal, alice)
+        .with_user_rules(RejectUnused)
+        .receive([Proposal::Add(Box::new(AddProposal {
+            key_package: test_key_package(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "bob").await,
+        }))])
+        .await
+        .unwrap();
+
+        assert!(state.unused_proposals.is_empty());
+        assert_eq!(state.stale_proposals.len(), 1);
+        assert_eq!(state.stale_proposals[0].proposal_ref(), Some(&proposal_ref));
+    }
 }
@@ -14,6 +14,9 @@ use filtering_lite as filtering;
 
 pub use bundle::{ProposalBundle, ProposalInfo, ProposalSource};
 
+#[cfg(feature = "by_ref_proposal")]
+pub use bundle::RejectedProposal;
+
 #[cfg(feature = "by_ref_proposal")]
 pub(crate) use filtering::FilterStrategy;
 
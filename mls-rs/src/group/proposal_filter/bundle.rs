@@ -17,7 +17,16 @@ use crate::{
 };
 
 #[cfg(feature = "by_ref_proposal")]
-use crate::group::{proposal_cache::CachedProposal, LeafIndex, ProposalRef, UpdateProposal};
+use crate::{
+    client::MlsError,
+    group::{proposal_cache::CachedProposal, LeafIndex, ProposalRef, UpdateProposal},
+};
+
+#[cfg(all(feature = "by_ref_proposal", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+
+#[cfg(all(feature = "by_ref_proposal", not(target_has_atomic = "ptr")))]
+use portable_atomic_util::Arc;
 
 #[cfg(feature = "psk")]
 use crate::group::PreSharedKeyProposal;
@@ -583,6 +592,36 @@ impl<T> ProposalInfo<T> {
 #[cfg(all(feature = "ffi", not(test)))]
 safer_ffi_gen::specialize!(ProposalInfoFfi = ProposalInfo<Proposal>);
 
+/// Proposal received by reference that was left out of a commit because it
+/// could not be applied to the ratchet tree.
+#[cfg(feature = "by_ref_proposal")]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RejectedProposal {
+    /// The rejected proposal.
+    pub proposal: ProposalInfo<Proposal>,
+    /// The error that would have been returned had the proposal been
+    /// committed. Proposals rejected for the same reason share this value.
+    pub reason: Arc<MlsError>,
+}
+
+#[cfg(feature = "by_ref_proposal")]
+impl RejectedProposal {
+    pub(crate) fn new(proposal: ProposalInfo<Proposal>, reason: Arc<MlsError>) -> Self {
+        Self { proposal, reason }
+    }
+
+    /// The [`ProposalRef`] of the rejected proposal.
+    pub fn proposal_ref(&self) -> Option<&ProposalRef> {
+        self.proposal.proposal_ref()
+    }
+
+    /// The sender of the rejected proposal.
+    pub fn sender(&self) -> &Sender {
+        &self.proposal.sender
+    }
+}
+
 pub trait Proposable: Sized {
     const TYPE: ProposalType;
 
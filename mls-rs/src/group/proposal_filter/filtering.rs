@@ -20,7 +20,9 @@ use crate::{
     CipherSuiteProvider, ExtensionList,
 };
 
-use super::filtering_common::{filter_out_invalid_psks, ApplyProposalsOutput, ProposalApplier};
+use super::filtering_common::{
+    filter_out_invalid_psks, find_reused_hpke_keys, ApplyProposalsOutput, ProposalApplier,
+};
 
 #[cfg(feature = "by_ref_proposal")]
 use crate::extension::ExternalSendersExt;
@@ -138,12 +140,13 @@ where
 
         let mut new_tree = self.original_tree.clone();
 
-        let added = new_tree
+        let output = new_tree
             .batch_edit(
                 &mut applied_proposals,
                 group_extensions_in_use,
                 self.identity_provider,
                 self.cipher_suite_provider,
+                self.tree_rules,
                 strategy.is_ignore(),
             )
             .await?;
@@ -155,9 +158,10 @@ where
         Ok(ApplyProposalsOutput {
             applied_proposals,
             new_tree,
-            indexes_of_added_kpkgs: added,
+            indexes_of_added_kpkgs: output.added,
             external_init_index: None,
             new_context_extensions,
+            rejected_proposals: output.rejected,
         })
     }
 
@@ -173,7 +177,8 @@ where
             self.cipher_suite_provider,
             self.identity_provider,
             Some(group_extensions_in_use),
-        );
+        )
+        .with_lifetime_policy(self.lifetime_policy);
 
         let bad_indices: Vec<_> = wrap_iter(proposals.update_proposals())
             .zip(wrap_iter(proposals.update_proposal_senders()))
@@ -239,6 +244,13 @@ where
             .rev()
             .for_each(|i| proposals.remove::<AddProposal>(i));
 
+        find_reused_hpke_keys(self.original_tree, &proposals, |p, res| {
+            apply_strategy(strategy, p.is_by_reference(), res)
+        })?
+        .into_iter()
+        .rev()
+        .for_each(|i| proposals.remove::<AddProposal>(i));
+
         Ok(proposals)
     }
 }
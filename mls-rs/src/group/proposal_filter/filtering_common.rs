@@ -3,8 +3,8 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use crate::{
-    client::MlsError,
-    group::{proposal_filter::ProposalBundle, Sender},
+    client::{HpkeKeyHolder, MlsError},
+    group::{proposal_filter::ProposalBundle, AddProposal, Sender},
     key_package::{validate_key_package_properties, KeyPackage},
     protocol_version::ProtocolVersion,
     time::MlsTime,
@@ -27,10 +27,15 @@ use crate::extension::ExternalSendersExt;
 
 use mls_rs_core::error::IntoAnyError;
 
-use alloc::vec::Vec;
-use mls_rs_core::{identity::IdentityProvider, psk::PreSharedKeyStorage};
+use alloc::{collections::BTreeMap, vec::Vec};
+use mls_rs_core::{crypto::HpkePublicKey, identity::IdentityProvider, psk::PreSharedKeyStorage};
 
-use crate::group::{ExternalInit, ProposalType, RemoveProposal};
+use crate::group::{
+    mls_rules::TreeLayoutRules, ExternalInit, LifetimePolicy, ProposalType, RemoveProposal,
+};
+
+#[cfg(feature = "message_escrow")]
+use crate::group::escrow::validate_exporter_escrow;
 
 #[cfg(all(feature = "by_ref_proposal", feature = "psk"))]
 use crate::group::proposal::PreSharedKeyProposal;
@@ -38,16 +43,21 @@ use crate::group::proposal::PreSharedKeyProposal;
 #[cfg(feature = "psk")]
 use crate::group::{JustPreSharedKeyID, ResumptionPSKUsage, ResumptionPsk};
 
+#[cfg(feature = "psk")]
+use crate::{client::PskFailureKind, psk::psk_failure};
+
 #[cfg(all(feature = "std", feature = "psk"))]
 use std::collections::HashSet;
 
+#[cfg(feature = "by_ref_proposal")]
+use super::RejectedProposal;
+
 #[cfg(feature = "by_ref_proposal")]
 use super::filtering::{apply_strategy, filter_out_invalid_proposers, FilterStrategy};
 
 #[cfg(feature = "custom_proposal")]
 use super::filtering::filter_out_unsupported_custom_proposals;
 
-#[derive(Debug)]
 pub(crate) struct ProposalApplier<'a, C, P, CSP> {
     pub original_tree: &'a TreeKemPublic,
     pub protocol_version: ProtocolVersion,
@@ -56,6 +66,8 @@ pub(crate) struct ProposalApplier<'a, C, P, CSP> {
     pub external_leaf: Option<&'a LeafNode>,
     pub identity_provider: &'a C,
     pub psk_storage: &'a P,
+    pub lifetime_policy: LifetimePolicy,
+    pub tree_rules: &'a dyn TreeLayoutRules,
     #[cfg(feature = "by_ref_proposal")]
     pub group_id: &'a [u8],
 }
@@ -68,6 +80,8 @@ pub(crate) struct ApplyProposalsOutput {
     #[cfg(feature = "by_ref_proposal")]
     pub(crate) applied_proposals: ProposalBundle,
     pub(crate) new_context_extensions: Option<ExtensionList>,
+    #[cfg(feature = "by_ref_proposal")]
+    pub(crate) rejected_proposals: Vec<RejectedProposal>,
 }
 
 impl<'a, C, P, CSP> ProposalApplier<'a, C, P, CSP>
@@ -85,6 +99,8 @@ where
         external_leaf: Option<&'a LeafNode>,
         identity_provider: &'a C,
         psk_storage: &'a P,
+        lifetime_policy: LifetimePolicy,
+        tree_rules: &'a dyn TreeLayoutRules,
         #[cfg(feature = "by_ref_proposal")] group_id: &'a [u8],
     ) -> Self {
         Self {
@@ -95,6 +111,8 @@ where
             external_leaf,
             identity_provider,
             psk_storage,
+            lifetime_policy,
+            tree_rules,
             #[cfg(feature = "by_ref_proposal")]
             group_id,
         }
@@ -203,6 +221,7 @@ where
                 external_leaf.clone(),
                 self.identity_provider,
                 self.original_group_extensions,
+                self.tree_rules,
             )
             .await?,
         );
@@ -284,6 +303,10 @@ where
             })
             .map_or(Ok(()), |ext| Err(MlsError::UnsupportedGroupExtension(ext)));
 
+        #[cfg(feature = "message_escrow")]
+        let new_extensions_supported = new_extensions_supported
+            .and_then(|_| validate_exporter_escrow(&group_context_extensions_proposal.proposal));
+
         #[cfg(not(feature = "by_ref_proposal"))]
         {
             new_capabilities_supported.and(new_extensions_supported)?;
@@ -413,7 +436,10 @@ where
                     if found {
                         Ok(())
                     } else {
-                        Err(MlsError::MissingRequiredPsk)
+                        Err(psk_failure(
+                            core::slice::from_ref(&p.proposal.psk),
+                            PskFailureKind::Missing,
+                        ))
                     }
                 }),
             JustPreSharedKeyID::Resumption(_) => Ok(()),
@@ -476,6 +502,94 @@ where
     Ok(())
 }
 
+/// Find the add proposals whose init key or leaf encryption key is already
+/// used by a leaf remaining in the tree or by a previous add proposal.
+///
+/// `keep` decides, given the result of the check, whether an add proposal is
+/// kept. The positions of the add proposals that are not kept are returned.
+pub(super) fn find_reused_hpke_keys<F>(
+    tree: &TreeKemPublic,
+    proposals: &ProposalBundle,
+    mut keep: F,
+) -> Result<Vec<usize>, MlsError>
+where
+    F: FnMut(&ProposalInfo<AddProposal>, Result<(), MlsError>) -> Result<bool, MlsError>,
+{
+    // Leaves that are removed or updated by the commit no longer hold their key
+    let replaced = proposals
+        .remove_proposals()
+        .iter()
+        .map(|p| p.proposal.to_remove);
+
+    #[cfg(feature = "by_ref_proposal")]
+    let replaced = replaced.chain(proposals.update_proposal_senders().iter().copied());
+
+    let replaced = replaced.collect::<Vec<_>>();
+
+    let mut holders: BTreeMap<&HpkePublicKey, HpkeKeyHolder> = tree
+        .non_empty_leaves()
+        .filter(|(index, _)| !replaced.contains(index))
+        .map(|(index, leaf)| (&leaf.public_key, HpkeKeyHolder::Leaf(*index)))
+        .collect();
+
+    #[cfg(feature = "by_ref_proposal")]
+    holders.extend(
+        proposals
+            .update_proposals()
+            .iter()
+            .zip(proposals.update_proposal_senders())
+            .map(|(p, index)| {
+                (
+                    &p.proposal.leaf_node.public_key,
+                    HpkeKeyHolder::Leaf(**index),
+                )
+            }),
+    );
+
+    let mut rejected = Vec::new();
+
+    for (i, p) in proposals.add_proposals().iter().enumerate() {
+        let key_package = &p.proposal.key_package;
+
+        let keys = [
+            (&key_package.hpke_init_key, HpkeKeyHolder::AddInitKey(i)),
+            (
+                &key_package.leaf_node.public_key,
+                HpkeKeyHolder::AddLeafKey(i),
+            ),
+        ];
+
+        let res = keys
+            .iter()
+            .find_map(|(key, holder)| {
+                holders
+                    .get(key)
+                    .filter(|existing| !reported_by_tree_index(*holder, **existing))
+                    .map(|existing| MlsError::HpkeKeyReuse(*holder, *existing))
+            })
+            .map_or(Ok(()), Err);
+
+        if keep(p, res)? {
+            holders.extend(keys);
+        } else {
+            rejected.push(i);
+        }
+    }
+
+    Ok(rejected)
+}
+
+// The tree index reports leaves sharing an encryption key as
+// `DuplicateLeafData` once the new leaves are inserted.
+fn reported_by_tree_index(holder: HpkeKeyHolder, existing: HpkeKeyHolder) -> bool {
+    cfg!(feature = "tree_index")
+        && matches!(holder, HpkeKeyHolder::AddLeafKey(_))
+        && matches!(
+            existing,
+            HpkeKeyHolder::Leaf(_) | HpkeKeyHolder::AddLeafKey(_)
+        )
+}
+
 fn ensure_exactly_one_external_init(proposals: &ProposalBundle) -> Result<(), MlsError> {
     (proposals.by_type::<ExternalInit>().count() == 1)
         .then_some(())
@@ -573,7 +687,10 @@ async fn insert_external_leaf<I: IdentityProvider>(
     leaf_node: LeafNode,
     identity_provider: &I,
     extensions: &ExtensionList,
+    tree_rules: &dyn TreeLayoutRules,
 ) -> Result<LeafIndex, MlsError> {
-    tree.add_leaf(leaf_node, identity_provider, extensions, None)
+    let (index, _) = tree.place_new_leaf(&leaf_node, tree_rules, LeafIndex(0))?;
+
+    tree.add_leaf(leaf_node, identity_provider, extensions, index)
         .await
 }
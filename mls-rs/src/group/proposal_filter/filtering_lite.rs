@@ -12,7 +12,9 @@ use crate::{
     CipherSuiteProvider, ExtensionList,
 };
 
-use super::filtering_common::{filter_out_invalid_psks, ApplyProposalsOutput, ProposalApplier};
+use super::filtering_common::{
+    filter_out_invalid_psks, find_reused_hpke_keys, ApplyProposalsOutput, ProposalApplier,
+};
 
 #[cfg(feature = "by_ref_proposal")]
 use {crate::extension::ExternalSendersExt, mls_rs_core::error::IntoAnyError};
@@ -95,12 +97,13 @@ where
 
         let mut new_tree = self.original_tree.clone();
 
-        let added = new_tree
+        let output = new_tree
             .batch_edit_lite(
                 proposals,
                 group_extensions_in_use,
                 self.identity_provider,
                 self.cipher_suite_provider,
+                self.tree_rules,
             )
             .await?;
 
@@ -111,7 +114,7 @@ where
 
         Ok(ApplyProposalsOutput {
             new_tree,
-            indexes_of_added_kpkgs: added,
+            indexes_of_added_kpkgs: output.added,
             external_init_index: None,
             new_context_extensions,
         })
@@ -128,7 +131,8 @@ where
             self.cipher_suite_provider,
             self.identity_provider,
             Some(group_extensions_in_use),
-        );
+        )
+        .with_lifetime_policy(self.lifetime_policy);
 
         let adds = wrap_iter(proposals.add_proposals());
 
@@ -139,7 +143,11 @@ where
             .try_for_each(|p| {
                 self.validate_new_node(leaf_node_validator, &p.proposal.key_package, commit_time)
             })
-            .await
+            .await?;
+
+        find_reused_hpke_keys(self.original_tree, proposals, |_, res| res.map(|_| true))?;
+
+        Ok(())
     }
 }
 
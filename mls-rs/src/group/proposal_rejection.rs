@@ -0,0 +1,158 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::BTreeMap;
+
+use crate::group::{
+    proposal_filter::{ProposalInfo, ProposalSource},
+    Proposal, ProposalRef,
+};
+
+/// Cache of by-reference proposals that were dropped by the proposal filter
+/// while preparing a commit.
+///
+/// Proposals left out of received commits are not recorded, since the
+/// committer may not have received them. They are reported in
+/// [StateUpdate::unused_proposals](crate::group::StateUpdate::unused_proposals)
+/// instead.
+///
+/// Each entry is kept for `ttl` epochs after the epoch in which the proposal
+/// was rejected. Applications can query the cache before rebroadcasting
+/// proposals in order to avoid re-submitting proposals that are known to be
+/// invalid.
+///
+/// The cache is not part of the persisted group state and starts empty each
+/// time a group is loaded from storage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProposalRejectionCache {
+    ttl: Option<u64>,
+    rejected: BTreeMap<ProposalRef, u64>,
+}
+
+impl ProposalRejectionCache {
+    /// Create a new cache that retains entries for `ttl` epochs. If `ttl` is
+    /// `None`, the cache is disabled and no entries are recorded.
+    pub fn new(ttl: Option<u64>) -> Self {
+        Self {
+            ttl,
+            rejected: Default::default(),
+        }
+    }
+
+    /// Number of epochs an entry is retained for, if the cache is enabled.
+    pub fn ttl(&self) -> Option<u64> {
+        self.ttl
+    }
+
+    /// Record that `proposal_ref` was rejected in `epoch`.
+    pub fn insert(&mut self, proposal_ref: ProposalRef, epoch: u64) {
+        if self.ttl.is_some() {
+            self.rejected.insert(proposal_ref, epoch);
+        }
+    }
+
+    /// Determine if `proposal_ref` was rejected within the last `ttl` epochs
+    /// as of `current_epoch`.
+    pub fn is_rejected(&self, proposal_ref: &ProposalRef, current_epoch: u64) -> bool {
+        self.rejected
+            .get(proposal_ref)
+            .map_or(false, |epoch| self.is_live(*epoch, current_epoch))
+    }
+
+    /// Epoch in which `proposal_ref` was rejected, if it is still cached as of
+    /// `current_epoch`.
+    pub fn rejected_in_epoch(&self, proposal_ref: &ProposalRef, current_epoch: u64) -> Option<u64> {
+        self.rejected
+            .get(proposal_ref)
+            .copied()
+            .filter(|epoch| self.is_live(*epoch, current_epoch))
+    }
+
+    /// Iterate over all references that are still cached as of `current_epoch`.
+    pub fn rejected_proposals(&self, current_epoch: u64) -> impl Iterator<Item = &ProposalRef> {
+        self.rejected
+            .iter()
+            .filter(move |(_, epoch)| self.is_live(**epoch, current_epoch))
+            .map(|(r, _)| r)
+    }
+
+    /// Remove entries that have expired as of `current_epoch`.
+    pub fn prune(&mut self, current_epoch: u64) {
+        let Some(ttl) = self.ttl else {
+            return self.rejected.clear();
+        };
+
+        self.rejected
+            .retain(|_, epoch| epoch.saturating_add(ttl) >= current_epoch);
+    }
+
+    /// Remove all entries.
+    pub fn clear(&mut self) {
+        self.rejected.clear();
+    }
+
+    pub(crate) fn insert_unused(
+        &mut self,
+        unused_proposals: &[ProposalInfo<Proposal>],
+        epoch: u64,
+    ) {
+        unused_proposals.iter().for_each(|p| {
+            if let ProposalSource::ByReference(r) = &p.source {
+                self.insert(r.clone(), epoch)
+            }
+        })
+    }
+
+    fn is_live(&self, rejected_epoch: u64, current_epoch: u64) -> bool {
+        self.ttl.map_or(false, |ttl| {
+            rejected_epoch.saturating_add(ttl) >= current_epoch
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::group::proposal_ref::ProposalRef;
+
+    use super::ProposalRejectionCache;
+
+    fn proposal_ref(byte: u8) -> ProposalRef {
+        ProposalRef::new_fake(vec![byte])
+    }
+
+    #[test]
+    fn disabled_cache_records_nothing() {
+        let mut cache = ProposalRejectionCache::new(None);
+        cache.insert(proposal_ref(1), 0);
+
+        assert!(!cache.is_rejected(&proposal_ref(1), 0));
+        assert_eq!(cache.rejected_proposals(0).count(), 0);
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let mut cache = ProposalRejectionCache::new(Some(2));
+        cache.insert(proposal_ref(1), 3);
+
+        assert!(cache.is_rejected(&proposal_ref(1), 3));
+        assert!(cache.is_rejected(&proposal_ref(1), 5));
+        assert!(!cache.is_rejected(&proposal_ref(1), 6));
+        assert_eq!(cache.rejected_in_epoch(&proposal_ref(1), 4), Some(3));
+
+        cache.prune(6);
+        assert!(cache.rejected.is_empty());
+    }
+
+    #[test]
+    fn rejected_proposals_lists_live_entries() {
+        let mut cache = ProposalRejectionCache::new(Some(1));
+        cache.insert(proposal_ref(1), 0);
+        cache.insert(proposal_ref(2), 2);
+
+        let live = cache.rejected_proposals(2).cloned().collect::<Vec<_>>();
+        assert_eq!(live, vec![proposal_ref(2)]);
+    }
+}
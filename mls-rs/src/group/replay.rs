@@ -0,0 +1,368 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{error::IntoAnyError, group::GroupStateStorage, secret::Secret, time::MlsTime};
+
+use crate::{client::MlsError, client_config::ClientConfig, Group, MlsMessage};
+
+use super::{snapshot::Snapshot, GroupContext, ReceivedMessage};
+
+#[cfg(feature = "prior_epoch")]
+use mls_rs_codec::MlsDecode;
+
+#[cfg(feature = "prior_epoch")]
+use super::epoch::PriorEpoch;
+
+/// Message of the log replayed by
+/// [`Client::replay_group`](crate::Client::replay_group).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ReplayEntry {
+    pub message: MlsMessage,
+    /// Time at which the message was originally processed. If set, it is
+    /// used as in [`Group::process_incoming_message_with_time`] so that the
+    /// validity of credentials and key packages is checked as it was then.
+    pub time: Option<MlsTime>,
+}
+
+impl ReplayEntry {
+    pub fn new(message: MlsMessage) -> Self {
+        Self {
+            message,
+            time: None,
+        }
+    }
+
+    pub fn with_time(self, time: MlsTime) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+}
+
+impl From<MlsMessage> for ReplayEntry {
+    fn from(message: MlsMessage) -> Self {
+        Self::new(message)
+    }
+}
+
+/// Epoch reached by applying a commit of the replayed log.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ReplayedEpoch {
+    /// Index in the log of the commit creating the epoch.
+    pub message_index: usize,
+    pub epoch: u64,
+    /// Epoch authenticator computed by the replay.
+    pub epoch_authenticator: Secret,
+    /// Whether the epoch was compared with the epoch found in storage. Epochs
+    /// that are neither the current epoch of the stored group nor a stored
+    /// prior epoch can not be checked.
+    pub verified: bool,
+}
+
+/// Kind of a [`ReplayDivergence`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReplayDivergenceKind {
+    /// The message could not be processed.
+    Processing(MlsError),
+    /// The group context of the epoch differs from the stored one.
+    GroupContext,
+    /// The group contexts match but the epoch authenticator differs from the
+    /// one of the stored group, meaning that the secrets of the epoch differ.
+    EpochAuthenticator,
+}
+
+/// First point at which the replay differs from the stored group state.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ReplayDivergence {
+    /// Index in the log of the message at which the replay diverged.
+    pub message_index: usize,
+    /// Epoch of the replayed group after processing the message.
+    pub epoch: u64,
+    pub kind: ReplayDivergenceKind,
+}
+
+/// Result of [`Client::replay_group`](crate::Client::replay_group).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ReplayReport {
+    /// Epochs created by the commits of the log, up to the divergence.
+    pub epochs: Vec<ReplayedEpoch>,
+    /// First divergence, after which no further message was replayed.
+    pub divergence: Option<ReplayDivergence>,
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn replay(
+        config: C,
+        initial_state: &[u8],
+        log: Vec<ReplayEntry>,
+    ) -> Result<ReplayReport, MlsError> {
+        let snapshot = Snapshot::from_bytes(initial_state)?;
+        let mut group = Group::from_snapshot(config.clone(), snapshot).await?;
+
+        // Prior epochs in storage were modified after the initial state
+        #[cfg(feature = "prior_epoch")]
+        group.state_repo.detach_from_storage();
+
+        let group_id = group.group_id().to_vec();
+        let storage = config.group_state_storage();
+
+        let stored_state = storage
+            .state(&group_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        let stored = match stored_state {
+            Some(state) => {
+                let snapshot = Snapshot::from_bytes(&state)?;
+                Some(Group::from_snapshot(config, snapshot).await?)
+            }
+            None => None,
+        };
+
+        let mut report = ReplayReport {
+            epochs: Vec::new(),
+            divergence: None,
+        };
+
+        // The replayed group is never written to storage
+        for (message_index, entry) in log.into_iter().enumerate() {
+            let res = match entry.time {
+                Some(time) => {
+                    group
+                        .process_incoming_message_with_time(entry.message, time)
+                        .await
+                }
+                None => group.process_incoming_message(entry.message).await,
+            };
+
+            let divergence = match res {
+                Ok(ReceivedMessage::Commit(_)) => {
+                    let (replayed, divergence) = group
+                        .verify_replayed_epoch(stored.as_ref(), &storage, message_index)
+                        .await?;
+
+                    report.epochs.push(replayed);
+                    divergence
+                }
+                Ok(_) => None,
+                Err(e) => Some(ReplayDivergenceKind::Processing(e)),
+            };
+
+            if let Some(kind) = divergence {
+                report.divergence = Some(ReplayDivergence {
+                    message_index,
+                    epoch: group.current_epoch(),
+                    kind,
+                });
+
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn verify_replayed_epoch(
+        &self,
+        stored: Option<&Group<C>>,
+        storage: &C::GroupStateStorage,
+        message_index: usize,
+    ) -> Result<(ReplayedEpoch, Option<ReplayDivergenceKind>), MlsError> {
+        let epoch = self.current_epoch();
+        let epoch_authenticator = self.epoch_authenticator()?;
+
+        let check = match stored.filter(|stored| stored.current_epoch() == epoch) {
+            Some(stored) if stored.context() != self.context() => {
+                Some(Some(ReplayDivergenceKind::GroupContext))
+            }
+            Some(stored) if *stored.epoch_authenticator()? != *epoch_authenticator => {
+                Some(Some(ReplayDivergenceKind::EpochAuthenticator))
+            }
+            Some(_) => Some(None),
+            None => stored_prior_context(storage, self.group_id(), epoch)
+                .await?
+                .map(|context| {
+                    (&context != self.context()).then_some(ReplayDivergenceKind::GroupContext)
+                }),
+        };
+
+        let replayed = ReplayedEpoch {
+            message_index,
+            epoch,
+            epoch_authenticator,
+            verified: check.is_some(),
+        };
+
+        Ok((replayed, check.flatten()))
+    }
+}
+
+#[cfg(feature = "prior_epoch")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn stored_prior_context<S: GroupStateStorage>(
+    storage: &S,
+    group_id: &[u8],
+    epoch: u64,
+) -> Result<Option<GroupContext>, MlsError> {
+    let stored = storage
+        .epoch(group_id, epoch)
+        .await
+        .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+    stored
+        .map(|epoch| Ok(PriorEpoch::mls_decode(&mut &*epoch)?.context))
+        .transpose()
+}
+
+#[cfg(not(feature = "prior_epoch"))]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn stored_prior_context<S: GroupStateStorage>(
+    _storage: &S,
+    _group_id: &[u8],
+    _epoch: u64,
+) -> Result<Option<GroupContext>, MlsError> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mls_rs_codec::MlsEncode;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_group,
+        Group,
+    };
+
+    use super::{ReplayDivergence, ReplayDivergenceKind};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn replayed_log_matches_stored_state() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let initial_state = bob.group.snapshot().mls_encode_to_vec().unwrap();
+        let mut log = Vec::new();
+        let mut commit_indexes = Vec::new();
+
+        for _ in 0..2 {
+            let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+            alice.group.apply_pending_commit().await.unwrap();
+
+            bob.process_message(commit.clone()).await.unwrap();
+            commit_indexes.push(log.len());
+            log.push(commit.into());
+
+            #[cfg(feature = "private_message")]
+            {
+                let message = alice
+                    .group
+                    .encrypt_application_message(b"hello", vec![])
+                    .await
+                    .unwrap();
+
+                bob.group
+                    .process_incoming_message(message.clone())
+                    .await
+                    .unwrap();
+
+                log.push(message.into());
+            }
+
+            bob.group.write_to_storage().await.unwrap();
+        }
+
+        let report = Group::replay(bob.group.config.clone(), &initial_state, log)
+            .await
+            .unwrap();
+
+        assert!(report.divergence.is_none());
+        assert_eq!(report.epochs.len(), 2);
+
+        let last = &report.epochs[1];
+        assert_eq!(last.message_index, commit_indexes[1]);
+        assert_eq!(last.epoch, bob.group.current_epoch());
+        assert!(last.verified);
+
+        assert_eq!(
+            *last.epoch_authenticator,
+            *bob.group.epoch_authenticator().unwrap()
+        );
+
+        #[cfg(feature = "prior_epoch")]
+        assert!(report.epochs[0].verified);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn replay_reports_first_divergence() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (mut bob, _) = alice.join("bob").await;
+
+        let initial_state = bob.group.snapshot().mls_encode_to_vec().unwrap();
+        let epoch = bob.group.current_epoch();
+        let mut fork = alice.clone();
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+        alice.group.apply_pending_commit().await.unwrap();
+        bob.process_message(commit).await.unwrap();
+        bob.group.write_to_storage().await.unwrap();
+
+        // A different commit for the same epoch leads to a different state
+        let fork_commit = fork.group.commit(vec![]).await.unwrap().commit_message;
+
+        let report = Group::replay(
+            bob.group.config.clone(),
+            &initial_state,
+            vec![fork_commit.into()],
+        )
+        .await
+        .unwrap();
+
+        assert_matches!(
+            report.divergence,
+            Some(ReplayDivergence {
+                message_index: 0,
+                kind: ReplayDivergenceKind::GroupContext,
+                epoch: e,
+            }) if e == epoch + 1
+        );
+
+        // A log missing a commit can not be processed
+        let next_commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+
+        let report = Group::replay(
+            bob.group.config.clone(),
+            &initial_state,
+            vec![next_commit.into()],
+        )
+        .await
+        .unwrap();
+
+        assert!(report.epochs.is_empty());
+
+        assert_matches!(
+            report.divergence,
+            Some(ReplayDivergence {
+                message_index: 0,
+                kind: ReplayDivergenceKind::Processing(_),
+                epoch: e,
+            }) if e == epoch
+        );
+    }
+}
@@ -14,8 +14,9 @@ use mls_rs_core::{
 use crate::{client::MlsError, Client, Group, MlsMessage};
 
 use super::{
-    proposal::ReInitProposal, ClientConfig, ExportedTree, JustPreSharedKeyID, MessageProcessor,
-    NewMemberInfo, PreSharedKeyID, PskGroupId, PskSecretInput, ResumptionPSKUsage, ResumptionPsk,
+    proposal::ReInitProposal, ClientConfig, ExportedTree, GroupConfigOverrides, JustPreSharedKeyID,
+    MessageProcessor, NewMemberInfo, PreSharedKeyID, PskGroupId, PskSecretInput,
+    ResumptionPSKUsage, ResumptionPsk,
 };
 
 struct ResumptionGroupParameters<'a> {
@@ -29,6 +30,7 @@ pub struct ReinitClient<C: ClientConfig + Clone> {
     client: Client<C>,
     reinit: ReInitProposal,
     psk_input: PskSecretInput,
+    config_overrides: GroupConfigOverrides,
 }
 
 impl<C> Group<C>
@@ -66,6 +68,7 @@ where
             self.signer.clone(),
             #[cfg(any(feature = "private_message", feature = "psk"))]
             self.resumption_psk_input(ResumptionPSKUsage::Branch)?,
+            self.config_overrides.clone(),
         )
         .await
     }
@@ -92,6 +95,7 @@ where
             expected_new_group_prams,
             false,
             self.resumption_psk_input(ResumptionPSKUsage::Branch)?,
+            self.config_overrides.clone(),
         )
         .await
     }
@@ -129,6 +133,8 @@ where
             None => self.signer,
         };
 
+        let config_overrides = self.config_overrides;
+
         let client = Client::new(
             self.config,
             Some(new_signer),
@@ -140,6 +146,7 @@ where
             client,
             reinit,
             psk_input,
+            config_overrides,
         })
     }
 
@@ -196,6 +203,7 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             self.client.signer.unwrap(),
             #[cfg(any(feature = "private_message", feature = "psk"))]
             self.psk_input,
+            self.config_overrides,
         )
         .await
     }
@@ -225,6 +233,7 @@ impl<C: ClientConfig + Clone> ReinitClient<C> {
             expected_group_params,
             true,
             self.psk_input,
+            self.config_overrides,
         )
         .await
     }
@@ -238,6 +247,7 @@ async fn resumption_create_group<C: ClientConfig + Clone>(
     signing_identity: SigningIdentity,
     signer: SignatureSecretKey,
     psk_input: PskSecretInput,
+    config_overrides: GroupConfigOverrides,
 ) -> Result<(Group<C>, Vec<MlsMessage>), MlsError> {
     // Create a new group with new parameters
     let mut group = Group::new(
@@ -248,6 +258,7 @@ async fn resumption_create_group<C: ClientConfig + Clone>(
         signing_identity,
         new_group_params.extensions.clone(),
         signer,
+        config_overrides,
     )
     .await?;
 
@@ -270,6 +281,7 @@ async fn resumption_create_group<C: ClientConfig + Clone>(
     Ok((group, commit.welcome_messages))
 }
 
+#[allow(clippy::too_many_arguments)]
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 async fn resumption_join_group<C: ClientConfig + Clone>(
     config: C,
@@ -279,12 +291,15 @@ async fn resumption_join_group<C: ClientConfig + Clone>(
     expected_new_group_params: ResumptionGroupParameters<'_>,
     verify_group_id: bool,
     psk_input: PskSecretInput,
+    config_overrides: GroupConfigOverrides,
 ) -> Result<(Group<C>, NewMemberInfo), MlsError> {
     let psk_input = Some(psk_input);
 
-    let (group, new_member_info) =
+    let (mut group, new_member_info) =
         Group::<C>::from_welcome_message(welcome, tree_data, config, signer, psk_input).await?;
 
+    group.config_overrides = config_overrides;
+
     if group.protocol_version() != expected_new_group_params.version {
         Err(MlsError::ProtocolVersionMismatch)
     } else if group.cipher_suite() != expected_new_group_params.cipher_suite {
@@ -4,6 +4,8 @@
 
 use super::*;
 
+use crate::{extension::ExtensionType, tree_kem::leaf_node::LeafNodeSource};
+
 pub use mls_rs_core::group::Member;
 
 #[cfg(feature = "state_update")]
@@ -69,6 +71,34 @@ impl<'a> Roster<'a> {
             .map(|l| member_from_leaf_node(l, index))
     }
 
+    /// Number of leaves of the ratchet tree up to its last non-blank leaf, or
+    /// up to the last blank leaf kept by [`MlsRules::tree_truncation`](crate::MlsRules::tree_truncation).
+    pub fn leaf_count(&self) -> u32 {
+        self.public_tree.nodes.leaf_count()
+    }
+
+    /// Whether the leaf at `index` is blank. Leaves at or past
+    /// [`leaf_count`](Self::leaf_count) are considered blank.
+    pub fn is_blank(&self, index: u32) -> bool {
+        self.public_tree.get_leaf_node(LeafIndex(index)).is_err()
+    }
+
+    /// Members that can be moved to blank leaves on their left with
+    /// [`CommitBuilder::relocate_member`](crate::group::CommitBuilder::relocate_member)
+    /// so that the tree can be truncated to the number of members, starting
+    /// from the rightmost member.
+    pub fn relocation_candidates(&self) -> Vec<Member> {
+        let member_count = self.public_tree.non_empty_leaves().count() as u32;
+
+        let mut candidates = self
+            .members_iter()
+            .filter(|member| member.index >= member_count)
+            .collect::<Vec<_>>();
+
+        candidates.reverse();
+        candidates
+    }
+
     /// Iterator over member's signing identities.
     ///
     /// # Warning
@@ -82,6 +112,103 @@ impl<'a> Roster<'a> {
             .non_empty_leaves()
             .map(|(_, node)| &node.signing_identity)
     }
+
+    /// Retrieve the member whose [`ApplicationIdExt`](crate::extension::built_in::ApplicationIdExt)
+    /// leaf extension carries `application_id`. If several members use the
+    /// same identifier, the one with the lowest index is returned.
+    ///
+    /// The lookup takes time `O(1)` if the `tree_index` feature is enabled.
+    pub fn member_with_application_id(&self, application_id: &[u8]) -> Option<Member> {
+        let index = *self
+            .public_tree
+            .leaves_with_application_id(application_id)
+            .first()?;
+
+        self.public_tree
+            .get_leaf_node(index)
+            .ok()
+            .map(|l| member_from_leaf_node(l, index))
+    }
+
+    /// Application identifiers carried by more than one member, each
+    /// together with the indexes of these members in increasing order.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn duplicate_application_ids(&self) -> Vec<(Vec<u8>, Vec<u32>)> {
+        self.public_tree
+            .duplicate_application_ids()
+            .into_iter()
+            .map(|(id, leaves)| (id, leaves.into_iter().map(|i| *i).collect()))
+            .collect()
+    }
+
+    /// Members whose leaf node, at `time`, is not valid yet, has expired, or
+    /// expires within the [`LifetimePolicy::warning_period`] of `policy`.
+    ///
+    /// Only leaf nodes added from a key package have a lifetime. A leaf node
+    /// can be in the group outside of its lifetime if it was accepted within
+    /// the grace period of the policy, or as time advances.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn lifetime_warnings(
+        &self,
+        time: MlsTime,
+        policy: &LifetimePolicy,
+    ) -> Vec<LifetimeWarning> {
+        self.public_tree
+            .non_empty_leaves()
+            .filter_map(|(index, node)| {
+                let LeafNodeSource::KeyPackage(lifetime) = &node.leaf_node_source else {
+                    return None;
+                };
+
+                policy.warning(lifetime, time).map(|kind| LifetimeWarning {
+                    member: member_from_leaf_node(node, index),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    /// Members that do and do not support the extension type `extension`
+    /// according to their capabilities. Default extension types are supported
+    /// by every member.
+    #[cfg_attr(all(feature = "ffi", not(test)), safer_ffi_gen::safer_ffi_gen_ignore)]
+    pub fn extension_support(&self, extension: ExtensionType) -> ExtensionSupport {
+        let (supporting, laggards) = self
+            .public_tree
+            .non_empty_leaves()
+            .map(|(index, node)| (*index, node))
+            .partition::<Vec<_>, _>(|(_, node)| {
+                extension.is_default() || node.capabilities.extensions.contains(&extension)
+            });
+
+        ExtensionSupport {
+            supporting: supporting.into_iter().map(|(index, _)| index).collect(),
+            laggards: laggards.into_iter().map(|(index, _)| index).collect(),
+        }
+    }
+}
+
+/// Members supporting an extension type, reported by
+/// [`Roster::extension_support`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExtensionSupport {
+    /// Indexes of the members supporting the extension type.
+    pub supporting: Vec<u32>,
+    /// Indexes of the members not supporting the extension type.
+    pub laggards: Vec<u32>,
+}
+
+impl ExtensionSupport {
+    /// Percentage of members supporting the extension type, rounded down.
+    pub fn percent(&self) -> u8 {
+        let total = self.supporting.len() + self.laggards.len();
+
+        match total {
+            0 => 100,
+            _ => (self.supporting.len() * 100 / total) as u8,
+        }
+    }
 }
 
 impl TreeKemPublic {
@@ -0,0 +1,177 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{extension::ExtensionList, group::Capabilities, identity::SigningIdentity};
+
+use crate::{cipher_suite::CipherSuite, client::MlsError, client_config::ClientConfig, Group};
+
+use super::roster::member_from_leaf_node;
+
+/// Version of the [`RosterDocument`] format written by this library.
+pub const ROSTER_DOCUMENT_VERSION: u16 = 1;
+
+/// Membership of a group at a given epoch, created by [`Group::export_roster`].
+///
+/// The document is meant to be consumed by services that mirror the
+/// membership of a group without processing MLS messages. It can be encoded
+/// with the MLS encoding using [`to_bytes`](Self::to_bytes), or with any
+/// format supported by `serde` if the `serde` feature is enabled. The
+/// encoding of a given [`version`](Self::version) never changes.
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RosterDocument {
+    /// Version of the document format.
+    pub version: u16,
+    pub cipher_suite: CipherSuite,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub group_id: Vec<u8>,
+    pub epoch: u64,
+    /// Members of the group ordered by leaf index.
+    pub members: Vec<RosterEntry>,
+}
+
+/// Member of a group listed in a [`RosterDocument`].
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RosterEntry {
+    pub leaf_index: u32,
+    pub signing_identity: SigningIdentity,
+    pub capabilities: Capabilities,
+    pub extensions: ExtensionList,
+    /// Tree hash of the leaf of the member, which changes whenever the
+    /// member updates its leaf.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub leaf_hash: Vec<u8>,
+}
+
+impl RosterDocument {
+    /// Deserialize a roster document, failing if its version is not supported.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        let version = u16::mls_decode(&mut &*bytes)?;
+
+        if version != ROSTER_DOCUMENT_VERSION {
+            return Err(MlsError::UnsupportedRosterDocumentVersion(version));
+        }
+
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize a roster document.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Export the membership of the group at the current epoch.
+    ///
+    /// Capabilities and extensions of members are exported without the
+    /// GREASE values added by the library.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_roster(&self) -> Result<RosterDocument, MlsError> {
+        let tree = self.current_epoch_tree();
+        let mut members = Vec::new();
+
+        for (index, leaf_node) in tree.non_empty_leaves() {
+            let leaf_hash = tree.leaf_hash(index, &self.cipher_suite_provider).await?;
+            let member = member_from_leaf_node(leaf_node, index);
+
+            members.push(RosterEntry {
+                leaf_index: member.index,
+                signing_identity: member.signing_identity,
+                capabilities: member.capabilities,
+                extensions: member.extensions,
+                leaf_hash,
+            });
+        }
+
+        let context = self.context();
+
+        Ok(RosterDocument {
+            version: ROSTER_DOCUMENT_VERSION,
+            cipher_suite: context.cipher_suite,
+            group_id: context.group_id.clone(),
+            epoch: context.epoch,
+            members,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group,
+    };
+
+    use super::{RosterDocument, ROSTER_DOCUMENT_VERSION};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn exported_roster_lists_members() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        // The tree of a group with a single member is the leaf of that member
+        let roster = alice.group.export_roster().await.unwrap();
+        assert_eq!(roster.members[0].leaf_hash, alice.group.context().tree_hash);
+
+        let (bob, _) = alice.join("bob").await;
+        let roster = alice.group.export_roster().await.unwrap();
+
+        assert_eq!(roster.version, ROSTER_DOCUMENT_VERSION);
+        assert_eq!(roster.epoch, alice.group.current_epoch());
+        assert_eq!(roster.group_id, alice.group.context().group_id);
+        assert_eq!(roster.members.len(), 2);
+
+        let members = alice.group.roster().members();
+
+        for (entry, member) in roster.members.iter().zip(&members) {
+            assert_eq!(entry.leaf_index, member.index);
+            assert_eq!(entry.signing_identity, member.signing_identity);
+            assert_eq!(entry.capabilities, member.capabilities);
+            assert_eq!(entry.extensions, member.extensions);
+        }
+
+        let bob_roster = bob.group.export_roster().await.unwrap();
+        assert_eq!(roster, bob_roster);
+
+        let decoded = RosterDocument::from_bytes(&roster.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, roster);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&roster).unwrap();
+            assert_eq!(
+                serde_json::from_str::<RosterDocument>(&json).unwrap(),
+                roster
+            );
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unknown_roster_version_is_rejected() {
+        let alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        let mut roster = alice.group.export_roster().await.unwrap();
+        roster.version = ROSTER_DOCUMENT_VERSION + 1;
+
+        assert_matches!(
+            RosterDocument::from_bytes(&roster.to_bytes().unwrap()),
+            Err(MlsError::UnsupportedRosterDocumentVersion(2))
+        );
+    }
+}
@@ -21,7 +21,10 @@ use std::collections::HashMap;
 #[cfg(not(feature = "std"))]
 use alloc::collections::BTreeMap;
 
-use super::key_schedule::kdf_expand_with_label;
+use super::key_schedule::{kdf_expand_with_label, kdf_expand_with_labels};
+
+#[cfg(any(test, feature = "test_vectors"))]
+pub(crate) mod test_vectors;
 
 pub(crate) const MAX_RATCHET_BACK_HISTORY: u32 = 1024;
 
@@ -109,6 +112,10 @@ impl<T: TreeIndex> TreeSecretsVec<T> {
     fn take_node(&mut self, index: &T) -> Option<SecretTreeNode> {
         self.inner.remove(index)
     }
+
+    fn get_node(&self, index: &T) -> Option<&SecretTreeNode> {
+        self.inner.get(index)
+    }
 }
 
 #[cfg(not(feature = "std"))]
@@ -125,6 +132,10 @@ impl<T: TreeIndex> TreeSecretsVec<T> {
         self.find_node(index).map(|i| self.inner.remove(i).1)
     }
 
+    fn get_node(&self, index: &T) -> Option<&SecretTreeNode> {
+        self.find_node(index).map(|i| &self.inner[i].1)
+    }
+
     fn find_node(&self, index: &T) -> Option<usize> {
         use itertools::Itertools;
 
@@ -139,14 +150,14 @@ impl<T: TreeIndex> TreeSecretsVec<T> {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecretTree<T: TreeIndex> {
     known_secrets: TreeSecretsVec<T>,
-    leaf_count: T,
+    leaf_count: T::LeafCount,
 }
 
 impl<T: TreeIndex> SecretTree<T> {
     pub(crate) fn empty() -> SecretTree<T> {
         SecretTree {
             known_secrets: Default::default(),
-            leaf_count: T::zero(),
+            leaf_count: Default::default(),
         }
     }
 }
@@ -165,21 +176,29 @@ impl SecretRatchets {
         cipher_suite_provider: &P,
         generation: u32,
         key_type: KeyType,
+        max_skip: u32,
     ) -> Result<MessageKeyData, MlsError> {
         match key_type {
             KeyType::Handshake => {
                 self.handshake
-                    .get_message_key(cipher_suite_provider, generation)
+                    .get_message_key(cipher_suite_provider, generation, max_skip)
                     .await
             }
             KeyType::Application => {
                 self.application
-                    .get_message_key(cipher_suite_provider, generation)
+                    .get_message_key(cipher_suite_provider, generation, max_skip)
                     .await
             }
         }
     }
 
+    fn ratchet_mut(&mut self, key_type: KeyType) -> &mut SecretKeyRatchet {
+        match key_type {
+            KeyType::Handshake => &mut self.handshake,
+            KeyType::Application => &mut self.application,
+        }
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn next_message_key<P: CipherSuiteProvider>(
         &mut self,
@@ -194,11 +213,11 @@ impl SecretRatchets {
 }
 
 impl<T: TreeIndex> SecretTree<T> {
-    pub fn new(leaf_count: T, encryption_secret: Zeroizing<Vec<u8>>) -> SecretTree<T> {
+    pub fn new(leaf_count: T::LeafCount, encryption_secret: Zeroizing<Vec<u8>>) -> SecretTree<T> {
         let mut known_secrets = TreeSecretsVec::default();
 
         let root_secret = SecretTreeNode::Secret(TreeSecret::from(encryption_secret));
-        known_secrets.set_node(leaf_count.root(), root_secret);
+        known_secrets.set_node(T::root(leaf_count), root_secret);
 
         Self {
             known_secrets,
@@ -218,13 +237,12 @@ impl<T: TreeIndex> SecretTree<T> {
             let left_index = index.left().ok_or(MlsError::LeafNodeNoChildren)?;
             let right_index = index.right().ok_or(MlsError::LeafNodeNoChildren)?;
 
-            let left_secret =
-                kdf_expand_with_label(cipher_suite_provider, &secret, b"tree", b"left", None)
-                    .await?;
-
-            let right_secret =
-                kdf_expand_with_label(cipher_suite_provider, &secret, b"tree", b"right", None)
-                    .await?;
+            let [left_secret, right_secret] = kdf_expand_with_labels(
+                cipher_suite_provider,
+                &secret,
+                [(b"tree", b"left", None), (b"tree", b"right", None)],
+            )
+            .await?;
 
             self.known_secrets
                 .set_node(left_index, SecretTreeNode::Secret(left_secret.into()));
@@ -248,7 +266,7 @@ impl<T: TreeIndex> SecretTree<T> {
             Some(node) => node,
             None => {
                 // Start at the root node and work your way down consuming any intermediates needed
-                for i in node_index.direct_copath(&self.leaf_count).into_iter().rev() {
+                for i in node_index.direct_copath(self.leaf_count).into_iter().rev() {
                     self.consume_node(cipher_suite, &i.path).await?;
                 }
 
@@ -284,6 +302,21 @@ impl<T: TreeIndex> SecretTree<T> {
         Ok(res)
     }
 
+    /// Next generation of the `key_type` ratchet of the leaf at `leaf_index`,
+    /// i.e. the number of keys derived from it so far.
+    pub fn key_generation(&self, leaf_index: &T, key_type: KeyType) -> u32 {
+        match self.known_secrets.get_node(leaf_index) {
+            Some(SecretTreeNode::Ratchet(ratchets)) => match key_type {
+                KeyType::Handshake => ratchets.handshake.generation,
+                KeyType::Application => ratchets.application.generation,
+            },
+            _ => 0,
+        }
+    }
+
+    /// Derive the key at `generation` of the `key_type` ratchet of the leaf at
+    /// `leaf_index`. At most `max_skip` generations past the next one are
+    /// derived to reach it.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn message_key_generation<P: CipherSuiteProvider>(
         &mut self,
@@ -291,17 +324,105 @@ impl<T: TreeIndex> SecretTree<T> {
         leaf_index: T,
         key_type: KeyType,
         generation: u32,
+        max_skip: u32,
     ) -> Result<MessageKeyData, MlsError> {
         let mut ratchet = self.take_leaf_ratchet(cipher_suite, &leaf_index).await?;
 
         let res = ratchet
-            .message_key_generation(cipher_suite, generation, key_type)
-            .await?;
+            .message_key_generation(cipher_suite, generation, key_type, max_skip)
+            .await;
 
         self.known_secrets
             .set_node(leaf_index, SecretTreeNode::Ratchet(ratchet));
 
-        Ok(res)
+        res
+    }
+
+    /// Advance the `key_type` ratchet of the leaf at `leaf_index` so that its
+    /// next generation is `generation`, without keeping any of the skipped keys.
+    /// At most `max_skip` generations are skipped.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn fast_forward<P: CipherSuiteProvider>(
+        &mut self,
+        cipher_suite: &P,
+        leaf_index: T,
+        key_type: KeyType,
+        generation: u32,
+        max_skip: u32,
+    ) -> Result<(), MlsError> {
+        let mut ratchet = self.take_leaf_ratchet(cipher_suite, &leaf_index).await?;
+        let key_ratchet = ratchet.ratchet_mut(key_type);
+
+        let res = if generation.saturating_sub(key_ratchet.generation) > max_skip {
+            Err(MlsError::InvalidFutureGeneration(generation))
+        } else {
+            key_ratchet.fast_forward(cipher_suite, generation).await
+        };
+
+        self.known_secrets
+            .set_node(leaf_index, SecretTreeNode::Ratchet(ratchet));
+
+        res
+    }
+
+    /// Next generation and secret of the application ratchet of the leaf at
+    /// `leaf_index`.
+    #[cfg(feature = "message_escrow")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn application_ratchet<P: CipherSuiteProvider>(
+        &mut self,
+        cipher_suite: &P,
+        leaf_index: T,
+    ) -> Result<(u32, Zeroizing<Vec<u8>>), MlsError> {
+        let ratchet = self.take_leaf_ratchet(cipher_suite, &leaf_index).await?;
+
+        let generation = ratchet.application.generation;
+        let secret = ratchet.application.secret.0.clone();
+
+        self.known_secrets
+            .set_node(leaf_index, SecretTreeNode::Ratchet(ratchet));
+
+        Ok((generation, secret))
+    }
+
+    /// Move the next `count` generations of the `key_type` ratchet of the leaf
+    /// at `leaf_index` to a new tree holding only the ratchets of that leaf.
+    /// This tree continues from the first generation that was not moved, so
+    /// that no key is ever derived by both trees.
+    #[cfg(feature = "private_message")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn split_off<P: CipherSuiteProvider>(
+        &mut self,
+        cipher_suite: &P,
+        leaf_index: T,
+        key_type: KeyType,
+        count: u32,
+    ) -> Result<SecretTree<T>, MlsError> {
+        let mut ratchet = self.take_leaf_ratchet(cipher_suite, &leaf_index).await?;
+        let split = ratchet.clone();
+
+        let target = ratchet
+            .ratchet_mut(key_type)
+            .generation
+            .saturating_add(count);
+
+        let res = ratchet
+            .ratchet_mut(key_type)
+            .fast_forward(cipher_suite, target)
+            .await;
+
+        self.known_secrets
+            .set_node(leaf_index.clone(), SecretTreeNode::Ratchet(ratchet));
+
+        res?;
+
+        let mut known_secrets = TreeSecretsVec::default();
+        known_secrets.set_node(leaf_index, SecretTreeNode::Ratchet(split));
+
+        Ok(SecretTree {
+            known_secrets,
+            leaf_count: self.leaf_count,
+        })
     }
 }
 
@@ -454,11 +575,39 @@ impl SecretKeyRatchet {
         })
     }
 
+    /// Derive the key at `generation` of the ratchet whose secret at
+    /// generation `start` is `secret`.
+    #[cfg(feature = "message_escrow")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn message_key_from<P: CipherSuiteProvider>(
+        cipher_suite_provider: &P,
+        secret: &[u8],
+        start: u32,
+        generation: u32,
+    ) -> Result<MessageKeyData, MlsError> {
+        if generation < start {
+            return Err(MlsError::KeyMissing(generation));
+        }
+
+        let mut ratchet = Self {
+            secret: TreeSecret::from(secret.to_vec()),
+            generation: start,
+            #[cfg(feature = "out_of_order")]
+            history: Default::default(),
+        };
+
+        ratchet
+            .fast_forward(cipher_suite_provider, generation)
+            .await?;
+        ratchet.next_message_key(cipher_suite_provider).await
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn get_message_key<P: CipherSuiteProvider>(
         &mut self,
         cipher_suite_provider: &P,
         generation: u32,
+        max_skip: u32,
     ) -> Result<MessageKeyData, MlsError> {
         #[cfg(feature = "out_of_order")]
         if generation < self.generation {
@@ -474,9 +623,7 @@ impl SecretKeyRatchet {
             return Err(MlsError::KeyMissing(generation));
         }
 
-        let max_generation_allowed = self.generation + MAX_RATCHET_BACK_HISTORY;
-
-        if generation > max_generation_allowed {
+        if generation - self.generation > max_skip {
             return Err(MlsError::InvalidFutureGeneration(generation));
         }
 
@@ -494,43 +641,65 @@ impl SecretKeyRatchet {
         self.next_message_key(cipher_suite_provider).await
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn fast_forward<P: CipherSuiteProvider>(
+        &mut self,
+        cipher_suite_provider: &P,
+        generation: u32,
+    ) -> Result<(), MlsError> {
+        // Only the ratchet secret is needed to move forward, the keys and nonces of the skipped
+        // generations are never derived.
+        while self.generation < generation {
+            self.secret = self
+                .derive_secret(
+                    cipher_suite_provider,
+                    b"secret",
+                    cipher_suite_provider.kdf_extract_size(),
+                )
+                .await?
+                .into();
+
+            self.generation += 1;
+        }
+
+        Ok(())
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn next_message_key<P: CipherSuiteProvider>(
         &mut self,
         cipher_suite_provider: &P,
     ) -> Result<MessageKeyData, MlsError> {
         let generation = self.generation;
+        let context = generation.to_be_bytes();
 
-        let key = MessageKeyData {
-            nonce: self
-                .derive_secret(
-                    cipher_suite_provider,
+        let [nonce, key, secret] = kdf_expand_with_labels(
+            cipher_suite_provider,
+            self.secret.as_ref(),
+            [
+                (
                     b"nonce",
-                    cipher_suite_provider.aead_nonce_size(),
-                )
-                .await?,
-            key: self
-                .derive_secret(
-                    cipher_suite_provider,
+                    &context,
+                    Some(cipher_suite_provider.aead_nonce_size()),
+                ),
+                (
                     b"key",
-                    cipher_suite_provider.aead_key_size(),
-                )
-                .await?,
-            generation,
-        };
-
-        self.secret = self
-            .derive_secret(
-                cipher_suite_provider,
-                b"secret",
-                cipher_suite_provider.kdf_extract_size(),
-            )
-            .await?
-            .into();
+                    &context,
+                    Some(cipher_suite_provider.aead_key_size()),
+                ),
+                (b"secret", &context, None),
+            ],
+        )
+        .await?;
 
+        self.secret = secret.into();
         self.generation = generation + 1;
 
-        Ok(key)
+        Ok(MessageKeyData {
+            nonce,
+            key,
+            generation,
+        })
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -562,15 +731,18 @@ pub(crate) mod test_utils {
 
     use super::{KeyType, SecretKeyRatchet, SecretTree};
 
-    pub(crate) fn get_test_tree<T: TreeIndex>(secret: Vec<u8>, leaf_count: T) -> SecretTree<T> {
+    pub(crate) fn get_test_tree<T: TreeIndex>(
+        secret: Vec<u8>,
+        leaf_count: T::LeafCount,
+    ) -> SecretTree<T> {
         SecretTree::new(leaf_count, Zeroizing::new(secret))
     }
 
-    impl SecretTree<u32> {
+    impl<T: TreeIndex> SecretTree<T> {
         pub(crate) fn get_root_secret(&self) -> Vec<u8> {
             self.known_secrets
                 .clone()
-                .take_node(&self.leaf_count.root())
+                .take_node(&T::root(self.leaf_count))
                 .unwrap()
                 .into_secret()
                 .unwrap()
@@ -637,7 +809,7 @@ mod tests {
         crypto::test_utils::{
             test_cipher_suite_provider, try_test_cipher_suite_provider, TestCryptoProvider,
         },
-        tree_kem::node::NodeIndex,
+        tree_kem::node::{LeafIndex, NodeIndex},
     };
 
     #[cfg(not(mls_build_async))]
@@ -652,13 +824,13 @@ mod tests {
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_secret_tree() {
-        test_secret_tree_custom(16u32, (0..16).map(|i| 2 * i).collect(), true).await;
-        test_secret_tree_custom(1u64 << 62, (1..62).map(|i| 1u64 << i).collect(), false).await;
+        test_secret_tree_custom::<u32>(16, (0..16).map(|i| 2 * i).collect(), true).await;
+        test_secret_tree_custom::<u64>(1 << 62, (1..62).map(|i| 1 << i).collect(), false).await;
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn test_secret_tree_custom<T: TreeIndex>(
-        leaf_count: T,
+        leaf_count: T::LeafCount,
         leaves_to_check: Vec<T>,
         all_deleted: bool,
     ) {
@@ -666,7 +838,7 @@ mod tests {
             let cs_provider = test_cipher_suite_provider(cipher_suite);
 
             let test_secret = vec![0u8; cs_provider.kdf_extract_size()];
-            let mut test_tree = get_test_tree(test_secret, leaf_count.clone());
+            let mut test_tree = get_test_tree(test_secret, leaf_count);
 
             let mut secrets = Vec::<SecretRatchets>::new();
 
@@ -746,12 +918,18 @@ mod tests {
             let clone_2 = ratchet_clone.next_message_key(&provider).await.unwrap();
 
             // Going back in time should result in an error
-            let res = ratchet_clone.get_message_key(&provider, 0).await;
+            let res = ratchet_clone
+                .get_message_key(&provider, 0, MAX_RATCHET_BACK_HISTORY)
+                .await;
             assert!(res.is_err());
 
             // Calling get key should be the same as calling next until hitting the desired generation
             let second_key = ratchet
-                .get_message_key(&provider, ratchet_clone.generation - 1)
+                .get_message_key(
+                    &provider,
+                    ratchet_clone.generation - 1,
+                    MAX_RATCHET_BACK_HISTORY,
+                )
                 .await
                 .unwrap();
 
@@ -794,12 +972,21 @@ mod tests {
         let mut ordered_keys = Vec::<MessageKeyData>::new();
 
         for i in 0..=MAX_RATCHET_BACK_HISTORY {
-            ordered_keys.push(ratchet.get_message_key(&provider, i).await.unwrap());
+            ordered_keys.push(
+                ratchet
+                    .get_message_key(&provider, i, MAX_RATCHET_BACK_HISTORY)
+                    .await
+                    .unwrap(),
+            );
         }
 
         // Ask for a key at index MAX_RATCHET_BACK_HISTORY in the clone
         let last_key = ratchet_clone
-            .get_message_key(&provider, MAX_RATCHET_BACK_HISTORY)
+            .get_message_key(
+                &provider,
+                MAX_RATCHET_BACK_HISTORY,
+                MAX_RATCHET_BACK_HISTORY,
+            )
             .await
             .unwrap();
 
@@ -809,7 +996,12 @@ mod tests {
         let mut back_history_keys = Vec::<MessageKeyData>::new();
 
         for i in 0..MAX_RATCHET_BACK_HISTORY - 1 {
-            back_history_keys.push(ratchet_clone.get_message_key(&provider, i).await.unwrap());
+            back_history_keys.push(
+                ratchet_clone
+                    .get_message_key(&provider, i, MAX_RATCHET_BACK_HISTORY)
+                    .await
+                    .unwrap(),
+            );
         }
 
         assert_eq!(
@@ -828,8 +1020,13 @@ mod tests {
             .await
             .unwrap();
 
-        ratchet.get_message_key(&provider, 10).await.unwrap();
-        let res = ratchet.get_message_key(&provider, 9).await;
+        ratchet
+            .get_message_key(&provider, 10, MAX_RATCHET_BACK_HISTORY)
+            .await
+            .unwrap();
+        let res = ratchet
+            .get_message_key(&provider, 9, MAX_RATCHET_BACK_HISTORY)
+            .await;
         assert_matches!(res, Err(MlsError::KeyMissing(9)))
     }
 
@@ -843,7 +1040,11 @@ mod tests {
             .unwrap();
 
         let res = ratchet
-            .get_message_key(&provider, MAX_RATCHET_BACK_HISTORY + 1)
+            .get_message_key(
+                &provider,
+                MAX_RATCHET_BACK_HISTORY + 1,
+                MAX_RATCHET_BACK_HISTORY,
+            )
             .await;
 
         let invalid_generation = MAX_RATCHET_BACK_HISTORY + 1;
@@ -879,7 +1080,7 @@ mod tests {
 
         for index in 0..16 {
             let mut ratchets = secret_tree
-                .take_leaf_ratchet(&provider, &(index * 2))
+                .take_leaf_ratchet(&provider, &LeafIndex(index).node_index())
                 .await
                 .unwrap();
 
@@ -929,7 +1130,7 @@ mod tests {
                 let encryption_secret = random_bytes(provider.kdf_extract_size());
 
                 let mut secret_tree =
-                    SecretTree::new(16, Zeroizing::new(encryption_secret.clone()));
+                    SecretTree::<NodeIndex>::new(16, Zeroizing::new(encryption_secret.clone()));
 
                 TestCase {
                     cipher_suite: cipher_suite.into(),
@@ -954,7 +1155,8 @@ mod tests {
                 continue;
             };
 
-            let mut secret_tree = SecretTree::new(16, Zeroizing::new(case.encryption_secret));
+            let mut secret_tree =
+                SecretTree::<NodeIndex>::new(16, Zeroizing::new(case.encryption_secret));
             let ratchet_data = get_ratchet_data(&mut secret_tree, cs_provider.cipher_suite()).await;
 
             assert_eq!(ratchet_data, case.ratchets);
@@ -966,14 +1168,18 @@ mod tests {
 mod interop_tests {
     #[cfg(not(mls_build_async))]
     use mls_rs_core::crypto::{CipherSuite, CipherSuiteProvider};
+    #[cfg(not(mls_build_async))]
     use zeroize::Zeroizing;
 
-    use crate::{
-        crypto::test_utils::try_test_cipher_suite_provider,
-        group::{ciphertext_processor::InteropSenderData, secret_tree::KeyType},
-    };
+    use crate::crypto::test_utils::try_test_cipher_suite_provider;
+
+    #[cfg(not(mls_build_async))]
+    use crate::group::{ciphertext_processor::InteropSenderData, secret_tree::KeyType};
+
+    #[cfg(not(mls_build_async))]
+    use super::{test_vectors::SecretTreeLeaf, SecretTree};
 
-    use super::SecretTree;
+    use super::test_vectors::SecretTreeTestCase;
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn interop_test_vector() {
@@ -985,74 +1191,17 @@ mod interop_tests {
                 continue;
             };
 
-            case.sender_data.verify(&cs).await;
-
-            let mut tree = SecretTree::new(
-                case.leaves.len() as u32,
-                Zeroizing::new(case.encryption_secret),
-            );
-
-            for (index, leaves) in case.leaves.iter().enumerate() {
-                for leaf in leaves.iter() {
-                    let key = tree
-                        .message_key_generation(
-                            &cs,
-                            (index as u32) * 2,
-                            KeyType::Application,
-                            leaf.generation,
-                        )
-                        .await
-                        .unwrap();
-
-                    assert_eq!(key.key.to_vec(), leaf.application_key);
-                    assert_eq!(key.nonce.to_vec(), leaf.application_nonce);
-
-                    let key = tree
-                        .message_key_generation(
-                            &cs,
-                            (index as u32) * 2,
-                            KeyType::Handshake,
-                            leaf.generation,
-                        )
-                        .await
-                        .unwrap();
-
-                    assert_eq!(key.key.to_vec(), leaf.handshake_key);
-                    assert_eq!(key.nonce.to_vec(), leaf.handshake_nonce);
-                }
-            }
+            case.verify(&cs).await.unwrap();
         }
     }
 
-    #[derive(Debug, serde::Serialize, serde::Deserialize)]
-    struct InteropTestCase {
-        cipher_suite: u16,
-        #[serde(with = "hex::serde")]
-        encryption_secret: Vec<u8>,
-        sender_data: InteropSenderData,
-        leaves: Vec<Vec<InteropLeaf>>,
-    }
-
-    #[derive(Debug, serde::Serialize, serde::Deserialize)]
-    struct InteropLeaf {
-        generation: u32,
-        #[serde(with = "hex::serde")]
-        application_key: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        application_nonce: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        handshake_key: Vec<u8>,
-        #[serde(with = "hex::serde")]
-        handshake_nonce: Vec<u8>,
-    }
-
-    fn load_interop_test_cases() -> Vec<InteropTestCase> {
+    fn load_interop_test_cases() -> Vec<SecretTreeTestCase> {
         load_test_case_json!(secret_tree_interop, generate_test_vector())
     }
 
     #[cfg(not(mls_build_async))]
     #[cfg_attr(coverage_nightly, coverage(off))]
-    fn generate_test_vector() -> Vec<InteropTestCase> {
+    fn generate_test_vector() -> Vec<SecretTreeTestCase> {
         let mut test_cases = vec![];
 
         for cs in CipherSuite::all() {
@@ -1075,14 +1224,26 @@ mod interop_tests {
                                 let index = leaf * 2u32;
 
                                 let handshake_key = tree
-                                    .message_key_generation(&cs, index, KeyType::Handshake, gen)
+                                    .message_key_generation(
+                                        &cs,
+                                        index,
+                                        KeyType::Handshake,
+                                        gen,
+                                        u32::MAX,
+                                    )
                                     .unwrap();
 
                                 let app_key = tree
-                                    .message_key_generation(&cs, index, KeyType::Application, gen)
+                                    .message_key_generation(
+                                        &cs,
+                                        index,
+                                        KeyType::Application,
+                                        gen,
+                                        u32::MAX,
+                                    )
                                     .unwrap();
 
-                                InteropLeaf {
+                                SecretTreeLeaf {
                                     generation: gen,
                                     application_key: app_key.key.to_vec(),
                                     application_nonce: app_key.nonce.to_vec(),
@@ -1094,7 +1255,7 @@ mod interop_tests {
                     })
                     .collect();
 
-                let case = InteropTestCase {
+                let case = SecretTreeTestCase {
                     cipher_suite: *cs.cipher_suite(),
                     encryption_secret,
                     sender_data: InteropSenderData::new(&cs),
@@ -1109,7 +1270,7 @@ mod interop_tests {
     }
 
     #[cfg(mls_build_async)]
-    fn generate_test_vector() -> Vec<InteropTestCase> {
+    fn generate_test_vector() -> Vec<SecretTreeTestCase> {
         panic!("Tests cannot be generated in async mode");
     }
 }
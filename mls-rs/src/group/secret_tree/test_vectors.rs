@@ -0,0 +1,94 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::crypto::CipherSuiteProvider;
+use zeroize::Zeroizing;
+
+use crate::{
+    group::ciphertext_processor::InteropSenderData,
+    test_vectors::{ensure_eq, CheckResult, TestVectorError},
+};
+
+use super::{KeyType, SecretTree};
+
+/// Test case in the format of
+/// https://github.com/mlswg/mls-implementations/blob/main/test-vectors/secret-tree.json
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SecretTreeTestCase {
+    pub(crate) cipher_suite: u16,
+    #[serde(with = "hex::serde")]
+    pub(crate) encryption_secret: Vec<u8>,
+    pub(crate) sender_data: InteropSenderData,
+    pub(crate) leaves: Vec<Vec<SecretTreeLeaf>>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SecretTreeLeaf {
+    pub(crate) generation: u32,
+    #[serde(with = "hex::serde")]
+    pub(crate) application_key: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) application_nonce: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) handshake_key: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub(crate) handshake_nonce: Vec<u8>,
+}
+
+impl SecretTreeTestCase {
+    /// Derive the sender data key and all leaf keys of the test case with
+    /// `cs` and return an error on the first value that does not match.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn verify<P: CipherSuiteProvider>(
+        self,
+        cs: &P,
+    ) -> Result<(), TestVectorError> {
+        self.sender_data.verify(cs).await?;
+
+        let mut tree = SecretTree::new(
+            self.leaves.len() as u32,
+            Zeroizing::new(self.encryption_secret),
+        );
+
+        for (index, leaves) in self.leaves.iter().enumerate() {
+            for leaf in leaves.iter() {
+                let key = tree
+                    .message_key_generation(
+                        cs,
+                        (index as u32) * 2,
+                        KeyType::Application,
+                        leaf.generation,
+                        u32::MAX,
+                    )
+                    .await
+                    .check("deriving message key")?;
+
+                ensure_eq!(*key.key, leaf.application_key, "application key mismatch");
+
+                ensure_eq!(
+                    *key.nonce,
+                    leaf.application_nonce,
+                    "application nonce mismatch"
+                );
+
+                let key = tree
+                    .message_key_generation(
+                        cs,
+                        (index as u32) * 2,
+                        KeyType::Handshake,
+                        leaf.generation,
+                        u32::MAX,
+                    )
+                    .await
+                    .check("deriving message key")?;
+
+                ensure_eq!(*key.key, leaf.handshake_key, "handshake key mismatch");
+                ensure_eq!(*key.nonce, leaf.handshake_nonce, "handshake nonce mismatch");
+            }
+        }
+
+        Ok(())
+    }
+}
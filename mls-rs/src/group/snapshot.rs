@@ -6,8 +6,8 @@ use crate::{
     client::MlsError,
     client_config::ClientConfig,
     group::{
-        key_schedule::KeySchedule, CommitGeneration, ConfirmationTag, Group, GroupContext,
-        GroupState, InterimTranscriptHash, ReInitProposal, TreeKemPublic,
+        key_schedule::KeySchedule, CommitGeneration, ConfirmationTag, Group, GroupConfigOverrides,
+        GroupContext, GroupState, InterimTranscriptHash, ReInitProposal, TreeKemPublic,
     },
     tree_kem::TreeKemPrivate,
 };
@@ -19,7 +19,10 @@ use crate::{
 };
 
 #[cfg(feature = "by_ref_proposal")]
-use super::proposal_cache::{CachedProposal, ProposalCache};
+use super::{
+    proposal_cache::{CachedProposal, ProposalCache},
+    ProposalRejectionCache,
+};
 
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
@@ -35,6 +38,13 @@ use alloc::vec::Vec;
 
 use super::{cipher_suite_provider, epoch::EpochSecrets, state_repo::GroupStateRepository};
 
+mod migration;
+
+/// Version of the snapshot format written by [`Group::write_to_storage`].
+/// Snapshots written with any previous version can still be loaded, see
+/// [`migration`].
+pub(crate) const SNAPSHOT_VERSION: u16 = 2;
+
 #[derive(Debug, PartialEq, Clone, MlsEncode, MlsDecode, MlsSize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Snapshot {
@@ -49,6 +59,19 @@ pub(crate) struct Snapshot {
     pending_updates: Vec<(HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>))>,
     pending_commit: Option<CommitGeneration>,
     signer: SignatureSecretKey,
+    config_overrides: GroupConfigOverrides,
+    removed: bool,
+    /// Expiry times of cached proposals, in seconds since the Unix epoch.
+    #[cfg(feature = "by_ref_proposal")]
+    proposal_expirations: Vec<(ProposalRef, u64)>,
+}
+
+impl Snapshot {
+    /// Decode a snapshot written with any supported version, migrating it to
+    /// the current version.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        migration::decode_any_version(bytes)
+    }
 }
 
 #[derive(Debug, MlsEncode, MlsDecode, MlsSize, PartialEq, Clone)]
@@ -144,6 +167,30 @@ impl RawGroupState {
     }
 }
 
+/// Outcome of [`Group::compact_storage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompactionReport {
+    /// Size of the previously stored group state, if any.
+    pub snapshot_bytes_before: Option<u64>,
+    /// Size of the rewritten group state.
+    pub snapshot_bytes_after: u64,
+    /// Number of bytes of group data deleted by the storage backend, such as
+    /// prior epochs.
+    pub storage_bytes_reclaimed: u64,
+}
+
+impl CompactionReport {
+    /// Total number of bytes reclaimed by compaction.
+    pub fn reclaimed_bytes(&self) -> u64 {
+        let snapshot_bytes_reclaimed = self
+            .snapshot_bytes_before
+            .map_or(0, |before| before.saturating_sub(self.snapshot_bytes_after));
+
+        snapshot_bytes_reclaimed + self.storage_bytes_reclaimed
+    }
+}
+
 impl<C> Group<C>
 where
     C: ClientConfig + Clone,
@@ -153,7 +200,49 @@ where
     /// that is currently in use by the group.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn write_to_storage(&mut self) -> Result<(), MlsError> {
-        self.state_repo.write_to_storage(self.snapshot()).await
+        self.state_repo
+            .write_to_storage(self.snapshot(), &self.cipher_suite_provider)
+            .await
+    }
+
+    /// Rewrite the current state of the group to the
+    /// [`GroupStorageProvider`](crate::GroupStateStorage) and reclaim storage
+    /// space used by the group.
+    ///
+    /// If `max_retained_epochs` is set, prior epochs older than the last
+    /// `max_retained_epochs` epochs are deleted from storage. Messages from
+    /// deleted epochs can no longer be decrypted and their resumption secrets
+    /// are no longer available.
+    ///
+    /// Storage backends can perform additional maintenance by implementing
+    /// [`GroupStateStorage::compact`](crate::GroupStateStorage::compact).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compact_storage(
+        &mut self,
+        max_retained_epochs: Option<u64>,
+    ) -> Result<CompactionReport, MlsError> {
+        let delete_epoch_under =
+            max_retained_epochs.map(|retained| self.current_epoch().saturating_sub(retained));
+
+        self.state_repo
+            .compact(
+                self.snapshot(),
+                delete_epoch_under,
+                &self.cipher_suite_provider,
+            )
+            .await
+    }
+
+    /// Write the group state if the client writes it before returning
+    /// commits and proposals. See
+    /// [`ClientBuilder::persist_pending_state`](crate::client_builder::ClientBuilder::persist_pending_state).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn persist_pending_state(&mut self) -> Result<(), MlsError> {
+        if self.config.persist_pending_state() {
+            self.write_to_storage().await
+        } else {
+            Ok(())
+        }
     }
 
     pub(crate) fn snapshot(&self) -> Snapshot {
@@ -165,8 +254,12 @@ where
             pending_updates: self.pending_updates.clone(),
             pending_commit: self.pending_commit.clone(),
             epoch_secrets: self.epoch_secrets.clone(),
-            version: 1,
+            version: SNAPSHOT_VERSION,
             signer: self.signer.clone(),
+            config_overrides: self.config_overrides.clone(),
+            removed: self.removed,
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_expirations: self.state.proposals.export_expirations(),
         }
     }
 
@@ -178,7 +271,7 @@ where
         )?;
 
         #[cfg(feature = "tree_index")]
-        let identity_provider = config.identity_provider();
+        let identity_provider = config.member_identity_provider();
 
         let state_repo = GroupStateRepository::new(
             #[cfg(feature = "prior_epoch")]
@@ -188,8 +281,17 @@ where
             None,
         )?;
 
-        Ok(Group {
-            config,
+        // A pending commit written for another epoch than the group state can
+        // not be applied and is discarded
+        let context = &snapshot.state.context;
+
+        let pending_commit = snapshot.pending_commit.filter(|pending| {
+            let content = &pending.content.content;
+            content.epoch == context.epoch && content.group_id == context.group_id
+        });
+
+        #[allow(unused_mut)]
+        let mut group = Group {
             state: snapshot
                 .state
                 .import(
@@ -201,7 +303,7 @@ where
             key_schedule: snapshot.key_schedule,
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: snapshot.pending_updates,
-            pending_commit: snapshot.pending_commit,
+            pending_commit,
             #[cfg(test)]
             commit_modifiers: Default::default(),
             epoch_secrets: snapshot.epoch_secrets,
@@ -209,8 +311,31 @@ where
             cipher_suite_provider,
             #[cfg(feature = "psk")]
             previous_psk: None,
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: ProposalRejectionCache::new(config.proposal_rejection_ttl()),
+            config,
             signer: snapshot.signer,
-        })
+            config_overrides: snapshot.config_overrides,
+            message_buffer: Default::default(),
+            outbound_echoes: Default::default(),
+            prewarmed_key_schedule: None,
+            epoch_randomness: None,
+            removed: snapshot.removed,
+            unverified_members: Default::default(),
+        };
+
+        group.update_unverified_members();
+
+        #[cfg(feature = "by_ref_proposal")]
+        group
+            .state
+            .proposals
+            .import_expirations(snapshot.proposal_expirations);
+
+        #[cfg(feature = "prior_epoch")]
+        group.update_epoch_chain().await?;
+
+        Ok(group)
     }
 }
 
@@ -250,8 +375,12 @@ pub(crate) mod test_utils {
             #[cfg(feature = "by_ref_proposal")]
             pending_updates: Default::default(),
             pending_commit: None,
-            version: 1,
+            version: super::SNAPSHOT_VERSION,
             signer: vec![].into(),
+            config_overrides: Default::default(),
+            removed: false,
+            #[cfg(feature = "by_ref_proposal")]
+            proposal_expirations: Default::default(),
         }
     }
 }
@@ -260,14 +389,27 @@ pub(crate) mod test_utils {
 mod tests {
     use alloc::vec;
 
+    use assert_matches::assert_matches;
+    use mls_rs_codec::MlsEncode;
+
+    #[cfg(all(feature = "prior_epoch", feature = "private_message"))]
+    use crate::group::message_processor::ReceivedMessage;
+
     use crate::{
-        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client::{
+            test_utils::{TestClientConfig, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        client_config::ClientConfig,
         group::{
-            test_utils::{test_group, TestGroup},
+            test_utils::{test_group, test_group_custom_config, TestGroup},
             Group,
         },
+        GroupStateStorage,
     };
 
+    use super::{Snapshot, SNAPSHOT_VERSION};
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn snapshot_restore(group: TestGroup) {
         let snapshot = group.group.snapshot();
@@ -314,6 +456,245 @@ mod tests {
         snapshot_restore(group).await
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn load_from_storage(group: &TestGroup) -> Group<TestClientConfig> {
+        let bytes = group
+            .group
+            .config
+            .group_state_storage()
+            .state(group.group.group_id())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+
+        Group::from_snapshot(group.group.config.clone(), snapshot)
+            .await
+            .unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pending_commit_is_persisted_before_it_is_returned() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.persist_pending_state(true)
+        })
+        .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+
+        let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+
+        // The process restarts without writing the group state
+        let mut restored = load_from_storage(&alice).await;
+        assert!(restored.has_pending_commit());
+
+        restored
+            .process_incoming_message(commit.clone())
+            .await
+            .unwrap();
+        bob.process_message(commit).await.unwrap();
+
+        assert_eq!(
+            restored.epoch_authenticator().unwrap(),
+            bob.group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pending_proposal_is_persisted_before_it_is_returned() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.persist_pending_state(true)
+        })
+        .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+
+        let proposal = alice.group.propose_update(vec![]).await.unwrap();
+        bob.process_message(proposal).await.unwrap();
+
+        let commit = bob.group.commit(vec![]).await.unwrap().commit_message;
+        bob.process_pending_commit().await.unwrap();
+
+        // The restored group knows the proposal committed by reference
+        let mut restored = load_from_storage(&alice).await;
+        restored.process_incoming_message(commit).await.unwrap();
+
+        assert_eq!(
+            restored.epoch_authenticator().unwrap(),
+            bob.group.epoch_authenticator().unwrap()
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pending_commit_of_other_epoch_is_discarded_on_load() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        group.group.commit(vec![]).await.unwrap();
+
+        let mut snapshot = group.group.snapshot();
+
+        if let Some(pending) = snapshot.pending_commit.as_mut() {
+            pending.content.content.epoch += 1;
+        }
+
+        let restored = Group::from_snapshot(group.group.config.clone(), snapshot)
+            .await
+            .unwrap();
+
+        assert!(!restored.has_pending_commit());
+    }
+
+    #[cfg(feature = "prior_epoch")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn compacting_storage_deletes_old_epochs() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+
+        for _ in 0..3 {
+            group.group.commit(vec![]).await.unwrap();
+            group.process_pending_commit().await.unwrap();
+            group.group.write_to_storage().await.unwrap();
+        }
+
+        let storage = group.group.config.group_state_storage();
+        let group_id = group.group.group_id().to_vec();
+
+        let report = group.group.compact_storage(Some(1)).await.unwrap();
+
+        assert!(report.snapshot_bytes_before.is_some());
+        assert!(report.storage_bytes_reclaimed > 0);
+        assert!(report.reclaimed_bytes() >= report.storage_bytes_reclaimed);
+
+        let deleted = storage.epoch(&group_id, 1).await.unwrap();
+        assert!(deleted.is_none());
+
+        let retained = storage.epoch(&group_id, 2).await.unwrap();
+        assert!(retained.is_some());
+    }
+
+    #[cfg(all(feature = "prior_epoch", feature = "private_message"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chained_prior_epochs_can_be_read_after_loading() {
+        let mut alice = test_group_custom_config(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, |b| {
+            b.chained_epoch_encryption(true)
+        })
+        .await;
+
+        let (mut bob, _) = alice.join("bob").await;
+
+        let message = bob
+            .group
+            .encrypt_application_message(b"hello", vec![])
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            let commit = alice.group.commit(vec![]).await.unwrap().commit_message;
+            alice.process_pending_commit().await.unwrap();
+            bob.process_message(commit).await.unwrap();
+            alice.group.write_to_storage().await.unwrap();
+        }
+
+        let storage = alice.group.config.group_state_storage();
+        let record = storage.epoch(alice.group.group_id(), 1).await.unwrap();
+
+        // Encrypted epochs start with the reserved protocol version 0
+        assert_eq!(record.unwrap()[..2], [0, 0]);
+
+        let mut restored = load_from_storage(&alice).await;
+        let received = restored.process_incoming_message(message).await.unwrap();
+
+        assert_matches!(
+            received,
+            ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello"
+        );
+    }
+
+    // Snapshots of previous versions, each written by the code writing that
+    // version, with the default features. A fixture of the replaced version
+    // must be added whenever `SNAPSHOT_VERSION` is incremented, and existing
+    // fixtures must never be modified.
+    #[cfg(all(
+        feature = "std",
+        feature = "by_ref_proposal",
+        feature = "psk",
+        feature = "private_message",
+        feature = "tree_index"
+    ))]
+    const SNAPSHOT_FIXTURES: &[(u16, &[u8])] = &[(
+        1,
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_data/snapshot_v1.mls"
+        )),
+    )];
+
+    #[cfg(all(
+        feature = "std",
+        feature = "by_ref_proposal",
+        feature = "psk",
+        feature = "private_message",
+        feature = "tree_index"
+    ))]
+    #[test]
+    fn fixture_exists_for_every_previous_snapshot_version() {
+        let versions = SNAPSHOT_FIXTURES.iter().map(|(version, _)| *version);
+        assert!(versions.eq(1..SNAPSHOT_VERSION));
+    }
+
+    #[cfg(all(
+        feature = "std",
+        feature = "by_ref_proposal",
+        feature = "psk",
+        feature = "private_message",
+        feature = "tree_index"
+    ))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn snapshots_of_previous_versions_can_be_loaded() {
+        let config = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE)
+            .await
+            .group
+            .config;
+
+        for (version, bytes) in SNAPSHOT_FIXTURES {
+            let snapshot = Snapshot::from_bytes(bytes).unwrap();
+            assert_eq!(snapshot.version, SNAPSHOT_VERSION, "version {version}");
+
+            // Fixtures contain a pending empty commit
+            let mut group = Group::from_snapshot(config.clone(), snapshot)
+                .await
+                .unwrap();
+
+            group.apply_pending_commit().await.unwrap();
+            assert_eq!(group.current_epoch(), 1);
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn current_snapshot_round_trips() {
+        let mut group = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        group.group.commit(vec![]).await.unwrap();
+
+        let snapshot = group.group.snapshot();
+
+        // Maps are encoded in iteration order, so compare decoded values
+        let encoded = snapshot.mls_encode_to_vec().unwrap();
+        assert_eq!(Snapshot::from_bytes(&encoded).unwrap(), snapshot);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unknown_snapshot_version_is_rejected() {
+        let mut snapshot = super::test_utils::get_test_snapshot(TEST_CIPHER_SUITE, 1).await;
+        snapshot.version = SNAPSHOT_VERSION + 1;
+
+        let res = Snapshot::from_bytes(&snapshot.mls_encode_to_vec().unwrap());
+
+        assert_matches!(
+            res,
+            Err(MlsError::UnsupportedSnapshotVersion(v)) if v == SNAPSHOT_VERSION + 1
+        );
+    }
+
     #[cfg(feature = "serde")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn serde() {
@@ -0,0 +1,109 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Layouts of previous snapshot versions and their migration to the current
+//! version.
+//!
+//! Changing the encoding of [`Snapshot`] in a release requires incrementing
+//! [`SNAPSHOT_VERSION`], adding the replaced layout to this module together
+//! with a migration to the current version, and adding a fixture of the
+//! replaced version to `test_data`. Snapshots of every version listed here
+//! remain readable.
+
+use alloc::vec::Vec;
+use mls_rs_codec::MlsDecode;
+use mls_rs_core::crypto::SignatureSecretKey;
+use zeroize::Zeroizing;
+
+use crate::{
+    client::MlsError,
+    group::{
+        key_schedule::{InitSecret, KeySchedule},
+        CommitGeneration,
+    },
+    tree_kem::TreeKemPrivate,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::crypto::{HpkePublicKey, HpkeSecretKey};
+
+#[cfg(all(feature = "std", feature = "by_ref_proposal"))]
+use std::collections::HashMap;
+
+use super::{EpochSecrets, RawGroupState, Snapshot, SNAPSHOT_VERSION};
+
+/// Key schedule of snapshot version 1, written before the epoch record secret
+/// was kept.
+#[derive(MlsDecode)]
+struct KeyScheduleV1 {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    exporter_secret: Zeroizing<Vec<u8>>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    authentication_secret: Zeroizing<Vec<u8>>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    external_secret: Zeroizing<Vec<u8>>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    membership_key: Zeroizing<Vec<u8>>,
+    init_secret: InitSecret,
+}
+
+/// Snapshot version 1. The version number preceding it is decoded
+/// separately.
+#[derive(MlsDecode)]
+struct SnapshotV1 {
+    state: RawGroupState,
+    private_tree: TreeKemPrivate,
+    epoch_secrets: EpochSecrets,
+    key_schedule: KeyScheduleV1,
+    #[cfg(all(feature = "std", feature = "by_ref_proposal"))]
+    pending_updates: HashMap<HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>)>,
+    #[cfg(all(not(feature = "std"), feature = "by_ref_proposal"))]
+    pending_updates: Vec<(HpkePublicKey, (HpkeSecretKey, Option<SignatureSecretKey>))>,
+    pending_commit: Option<CommitGeneration>,
+    signer: SignatureSecretKey,
+}
+
+/// Version 1 to version 2. Groups restored from version 1 use no config
+/// overrides, were not removed since a removed member did not write its group
+/// state, and keep their cached proposals without expiry. They derive the
+/// epoch record secret of their current epoch from the init secret, see
+/// [`KeySchedule::epoch_record_secret`].
+fn migrate_v1(snapshot: SnapshotV1) -> Snapshot {
+    let key_schedule = snapshot.key_schedule;
+
+    Snapshot {
+        version: SNAPSHOT_VERSION,
+        state: snapshot.state,
+        private_tree: snapshot.private_tree,
+        epoch_secrets: snapshot.epoch_secrets,
+        key_schedule: KeySchedule::without_epoch_record_secret(
+            key_schedule.exporter_secret,
+            key_schedule.authentication_secret,
+            key_schedule.external_secret,
+            key_schedule.membership_key,
+            key_schedule.init_secret,
+        ),
+        #[cfg(feature = "by_ref_proposal")]
+        pending_updates: snapshot.pending_updates,
+        pending_commit: snapshot.pending_commit,
+        signer: snapshot.signer,
+        config_overrides: Default::default(),
+        removed: false,
+        #[cfg(feature = "by_ref_proposal")]
+        proposal_expirations: Default::default(),
+    }
+}
+
+/// Decode a snapshot of any supported version and migrate it to
+/// [`SNAPSHOT_VERSION`].
+pub(super) fn decode_any_version(bytes: &[u8]) -> Result<Snapshot, MlsError> {
+    let reader = &mut &*bytes;
+    let version = u16::mls_decode(reader)?;
+
+    match version {
+        1 => Ok(migrate_v1(SnapshotV1::mls_decode(reader)?)),
+        SNAPSHOT_VERSION => Ok(Snapshot::mls_decode(&mut &*bytes)?),
+        _ => Err(MlsError::UnsupportedSnapshotVersion(version)),
+    }
+}
@@ -5,14 +5,18 @@
 use crate::client::MlsError;
 use crate::{group::PriorEpoch, key_package::KeyPackageRef};
 
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::vec::Vec;
 use core::fmt::{self, Debug};
-use mls_rs_codec::{MlsDecode, MlsEncode};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::crypto::CipherSuiteProvider;
 use mls_rs_core::group::{EpochRecord, GroupState};
 use mls_rs_core::{error::IntoAnyError, group::GroupStateStorage, key_package::KeyPackageStorage};
+use zeroize::Zeroizing;
 
-use super::snapshot::Snapshot;
+use super::key_schedule::kdf_expand_with_label;
+
+use super::snapshot::{CompactionReport, Snapshot};
 
 #[cfg(feature = "psk")]
 use crate::group::ResumptionPsk;
@@ -28,6 +32,61 @@ struct EpochStorageCommit {
     pub(crate) updates: Vec<PriorEpoch>,
 }
 
+/// Secrets of the chain encrypting prior epochs in storage. The prior epoch
+/// `e` is encrypted with a key derived from the secret of epoch `e + 1` and
+/// contains the secret of epoch `e`.
+#[derive(Clone, Default)]
+struct EpochChain {
+    head_epoch: u64,
+    head_secret: Zeroizing<Vec<u8>>,
+    // Secrets of the pending inserts and of the prior epochs read from storage
+    known_secrets: BTreeMap<u64, Zeroizing<Vec<u8>>>,
+}
+
+#[cfg(any(feature = "psk", feature = "private_message"))]
+type ChainSecrets = Vec<(u64, Zeroizing<Vec<u8>>)>;
+
+impl EpochChain {
+    fn secret(&self, epoch_id: u64) -> Option<&Zeroizing<Vec<u8>>> {
+        if epoch_id == self.head_epoch {
+            Some(&self.head_secret)
+        } else {
+            self.known_secrets.get(&epoch_id)
+        }
+    }
+}
+
+/// Records of chained epochs start with this value in place of the protocol
+/// version of a [`PriorEpoch`]. It is reserved by RFC 9420, so that chained
+/// records are told apart from records written without chaining.
+const CHAINED_EPOCH_MARKER: u16 = 0;
+
+const CHAINED_EPOCH_VERSION: u16 = 1;
+
+#[derive(MlsEncode, MlsDecode, MlsSize)]
+struct EncryptedEpoch {
+    marker: u16,
+    version: u16,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    nonce: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    ciphertext: Vec<u8>,
+}
+
+#[derive(MlsEncode, MlsDecode, MlsSize)]
+struct ChainedEpoch {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    chain_secret: Zeroizing<Vec<u8>>,
+    epoch: PriorEpoch,
+}
+
+#[derive(MlsEncode, MlsSize)]
+struct ChainedEpochAad<'a> {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    group_id: &'a [u8],
+    epoch_id: u64,
+}
+
 #[derive(Clone)]
 pub(crate) struct GroupStateRepository<S, K>
 where
@@ -39,6 +98,8 @@ where
     group_id: Vec<u8>,
     storage: S,
     key_package_repo: K,
+    detached: bool,
+    chain: Option<EpochChain>,
 }
 
 impl<S, K> Debug for GroupStateRepository<S, K>
@@ -81,13 +142,36 @@ where
             pending_key_package_removal: key_package_to_remove,
             pending_commit: Default::default(),
             key_package_repo,
+            detached: false,
+            chain: None,
         })
     }
 
+    /// Encrypt prior epochs with keys chained from `secret`, the secret of
+    /// the current epoch `epoch_id`. The secret of the previous head is kept
+    /// as the secret of its epoch, which is expected to be a pending insert.
+    pub fn set_chain_head(&mut self, epoch_id: u64, secret: Zeroizing<Vec<u8>>) {
+        let chain = self.chain.get_or_insert_with(Default::default);
+        let previous_secret = core::mem::replace(&mut chain.head_secret, secret);
+        let previous_epoch = core::mem::replace(&mut chain.head_epoch, epoch_id);
+
+        if previous_epoch < epoch_id && !previous_secret.is_empty() {
+            chain.known_secrets.insert(previous_epoch, previous_secret);
+        }
+    }
+
+    /// Stop reading prior epochs from storage, so that only the epochs
+    /// inserted from now on are available.
+    pub fn detach_from_storage(&mut self) {
+        self.detached = true;
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn find_max_id(&self) -> Result<Option<u64>, MlsError> {
         if let Some(max) = self.pending_commit.inserts.back().map(|e| e.epoch_id()) {
             Ok(Some(max))
+        } else if self.detached {
+            Ok(None)
         } else {
             self.storage
                 .max_epoch_id(&self.group_id)
@@ -98,9 +182,10 @@ where
 
     #[cfg(feature = "psk")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn resumption_secret(
+    pub async fn resumption_secret<P: CipherSuiteProvider>(
         &self,
         psk_id: &ResumptionPsk,
+        cipher_suite_provider: &P,
     ) -> Result<Option<PreSharedKey>, MlsError> {
         // Search the local inserts cache
         if let Some(min) = self.pending_commit.inserts.front().map(|e| e.epoch_id()) {
@@ -125,20 +210,37 @@ where
             ));
         }
 
-        // Search the stored cache
+        if self.detached {
+            return Ok(None);
+        }
+
+        // Search the stored cache. Epochs of other groups are not chained
+        // from this group, and are only readable if stored without chaining.
+        if psk_id.psk_group_id.0 == self.group_id {
+            return Ok(self
+                .read_stored(psk_id.psk_epoch, cipher_suite_provider)
+                .await?
+                .map(|(epoch, _)| epoch.secrets.resumption_secret));
+        }
+
         self.storage
             .epoch(&psk_id.psk_group_id.0, psk_id.psk_epoch)
             .await
             .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
-            .map(|e| Ok(PriorEpoch::mls_decode(&mut &*e)?.secrets.resumption_secret))
+            .map(|e| {
+                Ok(decode_unchained(psk_id.psk_epoch, &e)?
+                    .secrets
+                    .resumption_secret)
+            })
             .transpose()
     }
 
     #[cfg(feature = "private_message")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn get_epoch_mut(
+    pub async fn get_epoch_mut<P: CipherSuiteProvider>(
         &mut self,
         epoch_id: u64,
+        cipher_suite_provider: &P,
     ) -> Result<Option<&mut PriorEpoch>, MlsError> {
         // Search the local inserts cache
         if let Some(min) = self.pending_commit.inserts.front().map(|e| e.epoch_id()) {
@@ -152,24 +254,26 @@ where
 
         // Look in the cached updates map, and if not found look in disk storage
         // and insert into the updates map for future caching
-        match self.find_pending(epoch_id) {
-            Some(i) => self.pending_commit.updates.get_mut(i).map(Ok),
-            None => self
-                .storage
-                .epoch(&self.group_id, epoch_id)
-                .await
-                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
-                .and_then(|epoch| {
-                    PriorEpoch::mls_decode(&mut &*epoch)
-                        .map(|epoch| {
-                            self.pending_commit.updates.push(epoch);
-                            self.pending_commit.updates.last_mut()
-                        })
-                        .transpose()
-                }),
+        if let Some(i) = self.find_pending(epoch_id) {
+            return Ok(self.pending_commit.updates.get_mut(i));
         }
-        .transpose()
-        .map_err(Into::into)
+
+        if self.detached {
+            return Ok(None);
+        }
+
+        let Some((epoch, secrets)) = self.read_stored(epoch_id, cipher_suite_provider).await?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(chain) = self.chain.as_mut() {
+            chain.known_secrets.extend(secrets);
+        }
+
+        self.pending_commit.updates.push(epoch);
+
+        Ok(self.pending_commit.updates.last_mut())
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -192,20 +296,22 @@ where
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn write_to_storage(&mut self, group_snapshot: Snapshot) -> Result<(), MlsError> {
-        let inserts = self
-            .pending_commit
-            .inserts
-            .iter()
-            .map(|e| Ok(EpochRecord::new(e.epoch_id(), e.mls_encode_to_vec()?)))
-            .collect::<Result<_, MlsError>>()?;
+    pub async fn write_to_storage<P: CipherSuiteProvider>(
+        &mut self,
+        group_snapshot: Snapshot,
+        cipher_suite_provider: &P,
+    ) -> Result<(), MlsError> {
+        let mut inserts = Vec::new();
 
-        let updates = self
-            .pending_commit
-            .updates
-            .iter()
-            .map(|e| Ok(EpochRecord::new(e.epoch_id(), e.mls_encode_to_vec()?)))
-            .collect::<Result<_, MlsError>>()?;
+        for epoch in self.pending_commit.inserts.iter() {
+            inserts.push(self.epoch_record(epoch, cipher_suite_provider).await?);
+        }
+
+        let mut updates = Vec::new();
+
+        for epoch in self.pending_commit.updates.iter() {
+            updates.push(self.epoch_record(epoch, cipher_suite_provider).await?);
+        }
 
         let group_state = GroupState {
             data: group_snapshot.mls_encode_to_vec()?,
@@ -227,9 +333,228 @@ where
         self.pending_commit.inserts.clear();
         self.pending_commit.updates.clear();
 
+        // Secrets of stored epochs are recovered from the chain when needed
+        if let Some(chain) = self.chain.as_mut() {
+            chain.known_secrets.clear();
+        }
+
         Ok(())
     }
 
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn epoch_record<P: CipherSuiteProvider>(
+        &self,
+        epoch: &PriorEpoch,
+        cipher_suite_provider: &P,
+    ) -> Result<EpochRecord, MlsError> {
+        let epoch_id = epoch.epoch_id();
+
+        let Some(chain) = &self.chain else {
+            return Ok(EpochRecord::new(epoch_id, epoch.mls_encode_to_vec()?));
+        };
+
+        let (Some(key_secret), Some(chain_secret)) =
+            (chain.secret(epoch_id + 1), chain.secret(epoch_id))
+        else {
+            return Err(MlsError::EpochNotFound);
+        };
+
+        let plaintext = Zeroizing::new(
+            ChainedEpoch {
+                chain_secret: chain_secret.clone(),
+                epoch: epoch.clone(),
+            }
+            .mls_encode_to_vec()?,
+        );
+
+        let nonce = cipher_suite_provider
+            .random_bytes_vec(cipher_suite_provider.aead_nonce_size())
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let ciphertext = cipher_suite_provider
+            .aead_seal(
+                &epoch_key(key_secret, cipher_suite_provider).await?,
+                &plaintext,
+                Some(&self.chained_epoch_aad(epoch_id)?),
+                &nonce,
+            )
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let data = EncryptedEpoch {
+            marker: CHAINED_EPOCH_MARKER,
+            version: CHAINED_EPOCH_VERSION,
+            nonce,
+            ciphertext,
+        }
+        .mls_encode_to_vec()?;
+
+        Ok(EpochRecord::new(epoch_id, data))
+    }
+
+    /// Read the stored epoch `epoch_id` of this group. Returns the epoch with
+    /// the chain secrets of the epochs decrypted to read it, or `None` if it
+    /// is not stored.
+    #[cfg(any(feature = "psk", feature = "private_message"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn read_stored<P: CipherSuiteProvider>(
+        &self,
+        epoch_id: u64,
+        cipher_suite_provider: &P,
+    ) -> Result<Option<(PriorEpoch, ChainSecrets)>, MlsError> {
+        let Some(record) = self
+            .storage
+            .epoch(&self.group_id, epoch_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+        else {
+            return Ok(None);
+        };
+
+        // Epochs written before chaining was enabled remain readable
+        if !is_chained(&record) {
+            return Ok(Some((PriorEpoch::mls_decode(&mut &*record)?, Vec::new())));
+        }
+
+        self.read_chained(epoch_id, cipher_suite_provider).await
+    }
+
+    /// Decrypt the stored epoch `epoch_id` and the stored epochs that follow
+    /// it, down from the first epoch whose chain secret is known. Returns the
+    /// epoch with the chain secrets of all decrypted epochs, or `None` if an
+    /// epoch following it was deleted.
+    ///
+    /// Fails with [`MlsError::UnreadableEpochRecord`] if chaining is disabled,
+    /// if an epoch following it was written without chaining or if a record
+    /// can not be decrypted.
+    #[cfg(any(feature = "psk", feature = "private_message"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn read_chained<P: CipherSuiteProvider>(
+        &self,
+        epoch_id: u64,
+        cipher_suite_provider: &P,
+    ) -> Result<Option<(PriorEpoch, ChainSecrets)>, MlsError> {
+        let Some(chain) = &self.chain else {
+            return Err(MlsError::UnreadableEpochRecord(epoch_id));
+        };
+
+        if epoch_id >= chain.head_epoch {
+            return Ok(None);
+        }
+
+        let mut next_id = epoch_id + 1;
+
+        let mut key_secret = loop {
+            match chain.secret(next_id) {
+                Some(secret) => break secret.clone(),
+                None => next_id += 1,
+            }
+        };
+
+        let mut secrets = Vec::new();
+
+        for id in (epoch_id..next_id).rev() {
+            let Some(record) = self
+                .storage
+                .epoch(&self.group_id, id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            else {
+                return Ok(None);
+            };
+
+            let Some(chained) = self
+                .open_epoch(id, &record, &key_secret, cipher_suite_provider)
+                .await?
+            else {
+                return Err(MlsError::UnreadableEpochRecord(epoch_id));
+            };
+
+            key_secret = chained.chain_secret.clone();
+            secrets.push((id, chained.chain_secret));
+
+            if id == epoch_id {
+                return Ok(Some((chained.epoch, secrets)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[cfg(any(feature = "psk", feature = "private_message"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn open_epoch<P: CipherSuiteProvider>(
+        &self,
+        epoch_id: u64,
+        record: &[u8],
+        key_secret: &[u8],
+        cipher_suite_provider: &P,
+    ) -> Result<Option<ChainedEpoch>, MlsError> {
+        let Ok(encrypted) = EncryptedEpoch::mls_decode(&mut &*record) else {
+            return Ok(None);
+        };
+
+        if encrypted.marker != CHAINED_EPOCH_MARKER || encrypted.version != CHAINED_EPOCH_VERSION {
+            return Ok(None);
+        }
+
+        let Ok(plaintext) = cipher_suite_provider
+            .aead_open(
+                &epoch_key(key_secret, cipher_suite_provider).await?,
+                &encrypted.ciphertext,
+                Some(&self.chained_epoch_aad(epoch_id)?),
+                &encrypted.nonce,
+            )
+            .await
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(ChainedEpoch::mls_decode(&mut &**plaintext)?))
+    }
+
+    fn chained_epoch_aad(&self, epoch_id: u64) -> Result<Vec<u8>, MlsError> {
+        Ok(ChainedEpochAad {
+            group_id: &self.group_id,
+            epoch_id,
+        }
+        .mls_encode_to_vec()?)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compact<P: CipherSuiteProvider>(
+        &mut self,
+        group_snapshot: Snapshot,
+        delete_epoch_under: Option<u64>,
+        cipher_suite_provider: &P,
+    ) -> Result<CompactionReport, MlsError> {
+        let group_id = self.group_id.clone();
+
+        let snapshot_bytes_before = self
+            .storage
+            .state(&group_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .map(|data| data.len() as u64);
+
+        let snapshot_bytes_after = group_snapshot.mls_encoded_len() as u64;
+
+        self.write_to_storage(group_snapshot, cipher_suite_provider)
+            .await?;
+
+        let storage_bytes_reclaimed = self
+            .storage
+            .compact(&group_id, delete_epoch_under)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        Ok(CompactionReport {
+            snapshot_bytes_before,
+            snapshot_bytes_after,
+            storage_bytes_reclaimed,
+        })
+    }
+
     #[cfg(any(feature = "psk", feature = "private_message"))]
     fn find_pending(&self, epoch_id: u64) -> Option<usize> {
         self.pending_commit
@@ -239,13 +564,46 @@ where
     }
 }
 
+#[cfg(any(feature = "psk", feature = "private_message"))]
+fn is_chained(record: &[u8]) -> bool {
+    record.starts_with(&CHAINED_EPOCH_MARKER.to_be_bytes())
+}
+
+/// Decode an epoch stored without chaining.
+#[cfg(feature = "psk")]
+fn decode_unchained(epoch_id: u64, record: &[u8]) -> Result<PriorEpoch, MlsError> {
+    if is_chained(record) {
+        return Err(MlsError::UnreadableEpochRecord(epoch_id));
+    }
+
+    Ok(PriorEpoch::mls_decode(&mut &*record)?)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn epoch_key<P: CipherSuiteProvider>(
+    key_secret: &[u8],
+    cipher_suite_provider: &P,
+) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    kdf_expand_with_label(
+        cipher_suite_provider,
+        key_secret,
+        b"epoch record key",
+        &[],
+        Some(cipher_suite_provider.aead_key_size()),
+    )
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec;
+    #[cfg(feature = "private_message")]
+    use assert_matches::assert_matches;
     use mls_rs_codec::MlsEncode;
 
     use crate::{
         client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        crypto::test_utils::test_cipher_suite_provider,
         group::{
             epoch::{test_utils::get_test_epoch_with_id, SenderDataSecret},
             test_utils::{random_bytes, test_member, TEST_GROUP},
@@ -274,6 +632,10 @@ mod tests {
         get_test_epoch_with_id(TEST_GROUP.to_vec(), TEST_CIPHER_SUITE, epoch_id)
     }
 
+    fn test_cs() -> impl CipherSuiteProvider {
+        test_cipher_suite_provider(TEST_CIPHER_SUITE)
+    }
+
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn test_snapshot(epoch_id: u64) -> Snapshot {
         crate::group::snapshot::test_utils::get_test_snapshot(TEST_CIPHER_SUITE, epoch_id).await
@@ -306,8 +668,15 @@ mod tests {
         };
 
         // Make sure you can recall an epoch sitting as a pending insert
-        let resumption = test_repo.resumption_secret(&psk_id).await.unwrap();
-        let prior_epoch = test_repo.get_epoch_mut(0).await.unwrap().cloned();
+        let resumption = test_repo
+            .resumption_secret(&psk_id, &test_cs())
+            .await
+            .unwrap();
+        let prior_epoch = test_repo
+            .get_epoch_mut(0, &test_cs())
+            .await
+            .unwrap()
+            .cloned();
 
         assert_eq!(
             prior_epoch.clone().unwrap().secrets.resumption_secret,
@@ -318,7 +687,10 @@ mod tests {
 
         // Write to the storage
         let snapshot = test_snapshot(test_epoch.epoch_id()).await;
-        test_repo.write_to_storage(snapshot.clone()).await.unwrap();
+        test_repo
+            .write_to_storage(snapshot.clone(), &test_cs())
+            .await
+            .unwrap();
 
         // Make sure the memory cache cleared
         assert!(test_repo.pending_commit.inserts.is_empty());
@@ -355,12 +727,16 @@ mod tests {
         test_repo.insert(test_epoch_0.clone()).await.unwrap();
 
         test_repo
-            .write_to_storage(test_snapshot(0).await)
+            .write_to_storage(test_snapshot(0).await, &test_cs())
             .await
             .unwrap();
 
         // Update the stored epoch
-        let to_update = test_repo.get_epoch_mut(0).await.unwrap().unwrap();
+        let to_update = test_repo
+            .get_epoch_mut(0, &test_cs())
+            .await
+            .unwrap()
+            .unwrap();
         assert_eq!(to_update, &test_epoch_0);
 
         let new_sender_secret = random_bytes(32);
@@ -382,12 +758,18 @@ mod tests {
             usage: ResumptionPSKUsage::Application,
         };
 
-        let owned = test_repo.resumption_secret(&psk_id).await.unwrap();
+        let owned = test_repo
+            .resumption_secret(&psk_id, &test_cs())
+            .await
+            .unwrap();
         assert_eq!(owned.as_ref(), Some(&to_update.secrets.resumption_secret));
 
         // Write the update to storage
         let snapshot = test_snapshot(1).await;
-        test_repo.write_to_storage(snapshot.clone()).await.unwrap();
+        test_repo
+            .write_to_storage(snapshot.clone(), &test_cs())
+            .await
+            .unwrap();
 
         assert!(test_repo.pending_commit.updates.is_empty());
         assert!(test_repo.pending_commit.inserts.is_empty());
@@ -420,12 +802,16 @@ mod tests {
         test_repo.insert(test_epoch_0).await.unwrap();
 
         test_repo
-            .write_to_storage(test_snapshot(0).await)
+            .write_to_storage(test_snapshot(0).await, &test_cs())
             .await
             .unwrap();
 
         // Update the stored epoch
-        let to_update = test_repo.get_epoch_mut(0).await.unwrap().unwrap();
+        let to_update = test_repo
+            .get_epoch_mut(0, &test_cs())
+            .await
+            .unwrap()
+            .unwrap();
         let new_sender_secret = random_bytes(32);
         to_update.secrets.sender_data_secret = SenderDataSecret::from(new_sender_secret);
         let to_update = to_update.clone();
@@ -435,7 +821,7 @@ mod tests {
         test_repo.insert(test_epoch_1.clone()).await.unwrap();
 
         test_repo
-            .write_to_storage(test_snapshot(1).await)
+            .write_to_storage(test_snapshot(1).await, &test_cs())
             .await
             .unwrap();
 
@@ -479,12 +865,15 @@ mod tests {
         }
 
         test_repo
-            .write_to_storage(test_snapshot(9).await)
+            .write_to_storage(test_snapshot(9).await, &test_cs())
             .await
             .unwrap();
 
         for mut epoch in epochs {
-            let res = test_repo.get_epoch_mut(epoch.epoch_id()).await.unwrap();
+            let res = test_repo
+                .get_epoch_mut(epoch.epoch_id(), &test_cs())
+                .await
+                .unwrap();
 
             assert_eq!(res, Some(&mut epoch));
         }
@@ -498,7 +887,7 @@ mod tests {
         test_repo.insert(test_epoch_0.clone()).await.unwrap();
 
         test_repo
-            .write_to_storage(test_snapshot(0).await)
+            .write_to_storage(test_snapshot(0).await, &test_cs())
             .await
             .unwrap();
 
@@ -515,14 +904,16 @@ mod tests {
         repo.insert(test_epoch(0)).await.unwrap();
         repo.insert(test_epoch(1)).await.unwrap();
 
-        repo.write_to_storage(test_snapshot(0).await).await.unwrap();
+        repo.write_to_storage(test_snapshot(0).await, &test_cs())
+            .await
+            .unwrap();
 
         let mut repo = GroupStateRepository {
             storage: repo.storage,
             ..test_group_state_repo(1)
         };
 
-        let res = repo.get_epoch_mut(0).await.unwrap();
+        let res = repo.get_epoch_mut(0, &test_cs()).await.unwrap();
 
         assert!(res.is_none());
     }
@@ -532,9 +923,13 @@ mod tests {
         let mut repo = test_group_state_repo(1);
 
         repo.insert(test_epoch(0)).await.unwrap();
-        repo.write_to_storage(test_snapshot(0).await).await.unwrap();
+        repo.write_to_storage(test_snapshot(0).await, &test_cs())
+            .await
+            .unwrap();
         repo.insert(test_epoch(1)).await.unwrap();
-        repo.write_to_storage(test_snapshot(1).await).await.unwrap();
+        repo.write_to_storage(test_snapshot(1).await, &test_cs())
+            .await
+            .unwrap();
 
         #[cfg(feature = "std")]
         let lock = repo.storage.inner.lock().unwrap();
@@ -566,8 +961,240 @@ mod tests {
 
         repo.key_package_repo.get(&key_package.reference).unwrap();
 
-        repo.write_to_storage(test_snapshot(4).await).await.unwrap();
+        repo.write_to_storage(test_snapshot(4).await, &test_cs())
+            .await
+            .unwrap();
 
         assert!(repo.key_package_repo.get(&key_package.reference).is_none());
     }
+
+    #[cfg(any(feature = "private_message", feature = "psk"))]
+    fn chain_secret(epoch_id: u64) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(vec![epoch_id as u8; 32])
+    }
+
+    // Stores the epochs `0..epochs` chained from the secret of epoch `epochs`,
+    // as done by a group committing from epoch 0.
+    #[cfg(any(feature = "private_message", feature = "psk"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn chained_repo(
+        retention_limit: usize,
+        epochs: u64,
+    ) -> (
+        GroupStateRepository<InMemoryGroupStateStorage, InMemoryKeyPackageStorage>,
+        Vec<PriorEpoch>,
+    ) {
+        let mut repo = test_group_state_repo(retention_limit);
+        let mut stored = Vec::new();
+
+        for epoch_id in 0..epochs {
+            let epoch = test_epoch(epoch_id);
+            stored.push(epoch.clone());
+
+            repo.set_chain_head(epoch_id, chain_secret(epoch_id));
+            repo.insert(epoch).await.unwrap();
+        }
+
+        repo.set_chain_head(epochs, chain_secret(epochs));
+
+        repo.write_to_storage(test_snapshot(epochs).await, &test_cs())
+            .await
+            .unwrap();
+
+        (repo, stored)
+    }
+
+    #[cfg(feature = "private_message")]
+    fn reload(
+        repo: GroupStateRepository<InMemoryGroupStateStorage, InMemoryKeyPackageStorage>,
+        head_epoch: u64,
+        head_secret: Zeroizing<Vec<u8>>,
+    ) -> GroupStateRepository<InMemoryGroupStateStorage, InMemoryKeyPackageStorage> {
+        let mut repo = GroupStateRepository {
+            storage: repo.storage,
+            ..test_group_state_repo(10)
+        };
+
+        repo.set_chain_head(head_epoch, head_secret);
+        repo
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chained_epochs_are_encrypted() {
+        let (repo, mut epochs) = chained_repo(10, 3).await;
+
+        let stored = repo.storage.epoch(TEST_GROUP, 0).await.unwrap().unwrap();
+        assert_ne!(stored, epochs[0].mls_encode_to_vec().unwrap());
+
+        let mut repo = reload(repo, 3, chain_secret(3));
+
+        for epoch_id in [0, 2, 1] {
+            let res = repo.get_epoch_mut(epoch_id, &test_cs()).await.unwrap();
+            assert_eq!(res, Some(&mut epochs[epoch_id as usize]));
+        }
+
+        let mut repo = reload(repo, 3, chain_secret(4));
+        let res = repo.get_epoch_mut(0, &test_cs()).await;
+
+        assert_matches!(res, Err(MlsError::UnreadableEpochRecord(0)));
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chained_epochs_can_be_updated() {
+        let (repo, _) = chained_repo(10, 3).await;
+        let mut repo = reload(repo, 3, chain_secret(3));
+
+        let to_update = repo.get_epoch_mut(0, &test_cs()).await.unwrap().unwrap();
+        to_update.secrets.sender_data_secret = SenderDataSecret::from(random_bytes(32));
+        let to_update = to_update.clone();
+
+        repo.write_to_storage(test_snapshot(3).await, &test_cs())
+            .await
+            .unwrap();
+
+        let mut repo = reload(repo, 3, chain_secret(3));
+        let res = repo.get_epoch_mut(0, &test_cs()).await.unwrap();
+
+        assert_eq!(res, Some(&mut to_update.clone()));
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn pruning_old_chained_epochs_keeps_the_chain() {
+        let (repo, mut epochs) = chained_repo(2, 5).await;
+        let mut repo = reload(repo, 5, chain_secret(5));
+
+        for epoch_id in 0..3 {
+            let res = repo.get_epoch_mut(epoch_id, &test_cs()).await.unwrap();
+            assert!(res.is_none());
+        }
+
+        for epoch_id in 3..5 {
+            let res = repo.get_epoch_mut(epoch_id, &test_cs()).await.unwrap();
+            assert_eq!(res, Some(&mut epochs[epoch_id as usize]));
+        }
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn damaged_chained_epoch_makes_older_epochs_unreadable() {
+        let (repo, _) = chained_repo(10, 3).await;
+
+        #[cfg(feature = "std")]
+        let mut lock = repo.storage.inner.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let mut lock = repo.storage.inner.lock();
+
+        let data = &mut lock
+            .get_mut(TEST_GROUP)
+            .unwrap()
+            .get_mut_epoch(1)
+            .unwrap()
+            .data;
+        let last = data.len() - 1;
+        data[last] ^= 1;
+
+        drop(lock);
+
+        let mut repo = reload(repo, 3, chain_secret(3));
+
+        let epoch = repo.get_epoch_mut(2, &test_cs()).await.unwrap();
+        assert!(epoch.is_some());
+
+        for epoch_id in [1, 0] {
+            let res = repo.get_epoch_mut(epoch_id, &test_cs()).await;
+            assert_matches!(res, Err(MlsError::UnreadableEpochRecord(id)) if id == epoch_id);
+        }
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn epochs_stored_before_chaining_remain_readable() {
+        let mut repo = test_group_state_repo(10);
+        let epoch = test_epoch(0);
+
+        repo.insert(epoch.clone()).await.unwrap();
+
+        repo.write_to_storage(test_snapshot(1).await, &test_cs())
+            .await
+            .unwrap();
+
+        let mut repo = reload(repo, 1, chain_secret(1));
+        let res = repo.get_epoch_mut(0, &test_cs()).await.unwrap();
+
+        assert_eq!(res, Some(&mut epoch.clone()));
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chained_epochs_are_not_read_without_chaining() {
+        let (repo, _) = chained_repo(10, 2).await;
+
+        let mut repo = GroupStateRepository {
+            storage: repo.storage,
+            ..test_group_state_repo(10)
+        };
+
+        let res = repo.get_epoch_mut(0, &test_cs()).await;
+
+        assert_matches!(res, Err(MlsError::UnreadableEpochRecord(0)));
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn chained_epochs_provide_resumption_secrets() {
+        let (repo, epochs) = chained_repo(10, 2).await;
+
+        let psk_id = ResumptionPsk {
+            psk_epoch: 0,
+            psk_group_id: PskGroupId(TEST_GROUP.to_vec()),
+            usage: ResumptionPSKUsage::Application,
+        };
+
+        let res = repo.resumption_secret(&psk_id, &test_cs()).await.unwrap();
+
+        assert_eq!(res, Some(epochs[0].secrets.resumption_secret.clone()));
+    }
+
+    #[cfg(feature = "psk")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn resumption_secrets_of_other_groups_are_read_with_chaining() {
+        let mut other_repo = GroupStateRepository::new(
+            b"other".to_vec(),
+            InMemoryGroupStateStorage::new(),
+            InMemoryKeyPackageStorage::default(),
+            None,
+        )
+        .unwrap();
+
+        let epoch = get_test_epoch_with_id(b"other".to_vec(), TEST_CIPHER_SUITE, 0);
+        other_repo.insert(epoch.clone()).await.unwrap();
+
+        let mut snapshot = test_snapshot(1).await;
+        snapshot.state.context.group_id = b"other".to_vec();
+
+        other_repo
+            .write_to_storage(snapshot, &test_cs())
+            .await
+            .unwrap();
+
+        let (repo, _) = chained_repo(10, 2).await;
+
+        let repo = GroupStateRepository {
+            storage: other_repo.storage,
+            ..repo
+        };
+
+        let psk_id = ResumptionPsk {
+            psk_epoch: 0,
+            psk_group_id: PskGroupId(b"other".to_vec()),
+            usage: ResumptionPSKUsage::Application,
+        };
+
+        let res = repo.resumption_secret(&psk_id, &test_cs()).await.unwrap();
+
+        assert_eq!(res, Some(epoch.secrets.resumption_secret));
+    }
 }
@@ -6,14 +6,15 @@ use crate::client::MlsError;
 use crate::key_package::KeyPackageRef;
 
 use alloc::vec::Vec;
-use mls_rs_codec::MlsEncode;
+use mls_rs_codec::{MlsEncode, MlsSize};
 use mls_rs_core::{
+    crypto::CipherSuiteProvider,
     error::IntoAnyError,
     group::{GroupState, GroupStateStorage},
     key_package::KeyPackageStorage,
 };
 
-use super::snapshot::Snapshot;
+use super::snapshot::{CompactionReport, Snapshot};
 
 #[derive(Debug, Clone)]
 pub(crate) struct GroupStateRepository<S, K>
@@ -45,7 +46,11 @@ where
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn write_to_storage(&mut self, group_snapshot: Snapshot) -> Result<(), MlsError> {
+    pub async fn write_to_storage<P: CipherSuiteProvider>(
+        &mut self,
+        group_snapshot: Snapshot,
+        _cipher_suite_provider: &P,
+    ) -> Result<(), MlsError> {
         let group_state = GroupState {
             data: group_snapshot.mls_encode_to_vec()?,
             id: group_snapshot.state.context.group_id,
@@ -65,12 +70,47 @@ where
 
         Ok(())
     }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compact<P: CipherSuiteProvider>(
+        &mut self,
+        group_snapshot: Snapshot,
+        delete_epoch_under: Option<u64>,
+        cipher_suite_provider: &P,
+    ) -> Result<CompactionReport, MlsError> {
+        let group_id = group_snapshot.state.context.group_id.clone();
+
+        let snapshot_bytes_before = self
+            .storage
+            .state(&group_id)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .map(|data| data.len() as u64);
+
+        let snapshot_bytes_after = group_snapshot.mls_encoded_len() as u64;
+
+        self.write_to_storage(group_snapshot, cipher_suite_provider)
+            .await?;
+
+        let storage_bytes_reclaimed = self
+            .storage
+            .compact(&group_id, delete_epoch_under)
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+        Ok(CompactionReport {
+            snapshot_bytes_before,
+            snapshot_bytes_after,
+            storage_bytes_reclaimed,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        crypto::test_utils::test_cipher_suite_provider,
         group::{
             snapshot::{test_utils::get_test_snapshot, Snapshot},
             test_utils::{test_member, TEST_GROUP},
@@ -97,7 +137,10 @@ mod tests {
         .unwrap();
 
         test_repo
-            .write_to_storage(test_snapshot(0).await)
+            .write_to_storage(
+                test_snapshot(0).await,
+                &test_cipher_suite_provider(TEST_CIPHER_SUITE),
+            )
             .await
             .unwrap();
 
@@ -125,7 +168,12 @@ mod tests {
 
         repo.key_package_repo.get(&key_package.reference).unwrap();
 
-        repo.write_to_storage(test_snapshot(4).await).await.unwrap();
+        repo.write_to_storage(
+            test_snapshot(4).await,
+            &test_cipher_suite_provider(TEST_CIPHER_SUITE),
+        )
+        .await
+        .unwrap();
 
         assert!(repo.key_package_repo.get(&key_package.reference).is_none());
     }
@@ -0,0 +1,434 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::CipherSuiteProvider, error::IntoAnyError, extension::ExtensionList,
+    group::ProposalType, protocol_version::ProtocolVersion,
+};
+
+use crate::{cipher_suite::CipherSuite, client::MlsError, client_config::ClientConfig, Group};
+
+use super::{framing::Content, Sender};
+
+/// Protocol state of a member of a group, created by
+/// [`Group::state_report`] for debugging.
+///
+/// Reports of two members of the same group can be compared with
+/// [`divergences`](Self::divergences) to find out where their states differ,
+/// for example when a commit created by one member is rejected by another.
+/// Secrets are never included. The key schedule is represented by hashes of
+/// its secrets. The report can be transferred between clients using
+/// [`to_bytes`](Self::to_bytes), or with any format supported by `serde` if
+/// the `serde` feature is enabled.
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct GroupStateReport {
+    pub protocol_version: ProtocolVersion,
+    pub cipher_suite: CipherSuite,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub group_id: Vec<u8>,
+    pub epoch: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub tree_hash: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub confirmed_transcript_hash: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub interim_transcript_hash: Vec<u8>,
+    /// Extensions of the group context.
+    pub extensions: ExtensionList,
+    pub tree: TreeReport,
+    pub secrets: SecretDigests,
+    /// Proposals received in the current epoch, ordered by reference.
+    pub pending_proposals: Vec<PendingProposalReport>,
+    /// Commit created by the member and not applied yet. This is local to the
+    /// member and is not compared by [`divergences`](Self::divergences).
+    pub pending_commit: Option<PendingCommitReport>,
+}
+
+/// Public ratchet tree as seen by a member, part of a [`GroupStateReport`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TreeReport {
+    pub total_leaf_count: u32,
+    /// Non-blank leaves ordered by leaf index.
+    pub leaves: Vec<LeafReport>,
+    /// Non-blank parent nodes ordered by node index.
+    pub parents: Vec<ParentReport>,
+}
+
+/// Non-blank leaf of a [`TreeReport`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct LeafReport {
+    pub leaf_index: u32,
+    /// Tree hash of the leaf, covering all of its content.
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub leaf_hash: Vec<u8>,
+}
+
+/// Non-blank parent node of a [`TreeReport`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ParentReport {
+    pub node_index: u32,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub public_key: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub parent_hash: Vec<u8>,
+    pub unmerged_leaves: Vec<u32>,
+}
+
+/// Hashes of the secrets of the key schedule of the current epoch, computed
+/// with the hash function of the cipher suite.
+///
+/// The init secret is derived in the previous epoch, while all other secrets
+/// are derived from the epoch secret of the current epoch. Members whose
+/// init secrets match but other secrets differ disagree on the group context
+/// or on the commit secret.
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SecretDigests {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub init_secret: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub sender_data_secret: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub exporter_secret: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub epoch_authenticator: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub external_secret: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub membership_key: Vec<u8>,
+}
+
+/// Proposal received by reference in the current epoch, part of a
+/// [`GroupStateReport`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PendingProposalReport {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    #[cfg_attr(feature = "serde", serde(with = "mls_rs_core::vec_serde"))]
+    pub proposal_ref: Vec<u8>,
+    pub proposal_type: ProposalType,
+    pub sender: Sender,
+}
+
+/// Commit created by a member and not applied yet, part of a
+/// [`GroupStateReport`].
+#[derive(Clone, Debug, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PendingCommitReport {
+    /// Number of proposals covered by the commit, by value or by reference.
+    pub proposal_count: u32,
+    pub contains_update_path: bool,
+}
+
+impl GroupStateReport {
+    /// Deserialize a report created by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize a report.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    /// Names of the fields whose values differ between `self` and `other`,
+    /// such as `"secrets.exporter_secret"` or `"tree.leaves[3]"`.
+    ///
+    /// Fields are listed in the order of the report. The pending commit is
+    /// not compared.
+    pub fn divergences(&self, other: &Self) -> Vec<String> {
+        let mut fields = Vec::new();
+
+        let mut compare = |name: &str, equal: bool| {
+            if !equal {
+                fields.push(name.to_string())
+            }
+        };
+
+        compare(
+            "protocol_version",
+            self.protocol_version == other.protocol_version,
+        );
+
+        compare("cipher_suite", self.cipher_suite == other.cipher_suite);
+        compare("group_id", self.group_id == other.group_id);
+        compare("epoch", self.epoch == other.epoch);
+        compare("tree_hash", self.tree_hash == other.tree_hash);
+
+        compare(
+            "confirmed_transcript_hash",
+            self.confirmed_transcript_hash == other.confirmed_transcript_hash,
+        );
+
+        compare(
+            "interim_transcript_hash",
+            self.interim_transcript_hash == other.interim_transcript_hash,
+        );
+
+        compare("extensions", self.extensions == other.extensions);
+
+        compare(
+            "tree.total_leaf_count",
+            self.tree.total_leaf_count == other.tree.total_leaf_count,
+        );
+
+        let (secrets, other_secrets) = (&self.secrets, &other.secrets);
+
+        let secret_fields = [
+            (
+                "init_secret",
+                &secrets.init_secret,
+                &other_secrets.init_secret,
+            ),
+            (
+                "sender_data_secret",
+                &secrets.sender_data_secret,
+                &other_secrets.sender_data_secret,
+            ),
+            (
+                "exporter_secret",
+                &secrets.exporter_secret,
+                &other_secrets.exporter_secret,
+            ),
+            (
+                "epoch_authenticator",
+                &secrets.epoch_authenticator,
+                &other_secrets.epoch_authenticator,
+            ),
+            (
+                "external_secret",
+                &secrets.external_secret,
+                &other_secrets.external_secret,
+            ),
+            (
+                "membership_key",
+                &secrets.membership_key,
+                &other_secrets.membership_key,
+            ),
+        ];
+
+        for (name, secret, other_secret) in secret_fields {
+            compare(&format!("secrets.{name}"), secret == other_secret);
+        }
+
+        compare(
+            "pending_proposals",
+            self.pending_proposals == other.pending_proposals,
+        );
+
+        let leaves = diverging_entries(&self.tree.leaves, &other.tree.leaves, |l| l.leaf_index);
+
+        let parents = diverging_entries(&self.tree.parents, &other.tree.parents, |p| p.node_index);
+
+        fields.extend(leaves.map(|index| format!("tree.leaves[{index}]")));
+        fields.extend(parents.map(|index| format!("tree.parents[{index}]")));
+
+        fields
+    }
+}
+
+fn diverging_entries<'a, T, F>(
+    entries: &'a [T],
+    other_entries: &'a [T],
+    index: F,
+) -> impl Iterator<Item = u32> + 'a
+where
+    T: PartialEq,
+    F: Fn(&T) -> u32 + Copy + 'a,
+{
+    let find = move |entries: &'a [T], i: u32| entries.iter().find(move |e| index(e) == i);
+
+    entries
+        .iter()
+        .chain(other_entries)
+        .map(index)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(move |i| find(entries, *i) != find(other_entries, *i))
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Report of the protocol state of this member for debugging.
+    ///
+    /// See [`GroupStateReport::divergences`] to compare the reports of two
+    /// members.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn state_report(&self) -> Result<GroupStateReport, MlsError> {
+        let context = self.context();
+        let tree = self.current_epoch_tree();
+        let mut leaves = Vec::new();
+
+        for (index, _) in tree.non_empty_leaves() {
+            leaves.push(LeafReport {
+                leaf_index: *index,
+                leaf_hash: tree.leaf_hash(index, &self.cipher_suite_provider).await?,
+            });
+        }
+
+        let parents = tree
+            .nodes
+            .non_empty_parents()
+            .map(|(index, parent)| ParentReport {
+                node_index: index.value(),
+                public_key: parent.public_key.to_vec(),
+                parent_hash: parent.parent_hash.to_vec(),
+                unmerged_leaves: parent.unmerged_leaves.iter().map(|l| **l).collect(),
+            })
+            .collect();
+
+        let key_schedule = &self.key_schedule;
+
+        let secrets = SecretDigests {
+            init_secret: self.secret_digest(key_schedule.init_secret()).await?,
+            sender_data_secret: self
+                .secret_digest(&self.epoch_secrets.sender_data_secret)
+                .await?,
+            exporter_secret: self.secret_digest(key_schedule.exporter_secret()).await?,
+            epoch_authenticator: self
+                .secret_digest(&key_schedule.authentication_secret)
+                .await?,
+            external_secret: self.secret_digest(key_schedule.external_secret()).await?,
+            membership_key: self.secret_digest(key_schedule.membership_key()).await?,
+        };
+
+        #[cfg(feature = "by_ref_proposal")]
+        let pending_proposals = self
+            .state
+            .proposals
+            .sorted()
+            .into_iter()
+            .map(|(proposal_ref, cached)| PendingProposalReport {
+                proposal_ref: proposal_ref.to_vec(),
+                proposal_type: cached.proposal.proposal_type(),
+                sender: cached.sender,
+            })
+            .collect();
+
+        #[cfg(not(feature = "by_ref_proposal"))]
+        let pending_proposals = Vec::new();
+
+        let pending_commit = self.pending_commit.as_ref().and_then(|pending| {
+            match &pending.content.content.content {
+                Content::Commit(commit) => Some(PendingCommitReport {
+                    proposal_count: commit.proposals.len() as u32,
+                    contains_update_path: commit.path.is_some(),
+                }),
+                #[allow(unreachable_patterns)]
+                _ => None,
+            }
+        });
+
+        Ok(GroupStateReport {
+            protocol_version: context.protocol_version,
+            cipher_suite: context.cipher_suite,
+            group_id: context.group_id.clone(),
+            epoch: context.epoch,
+            tree_hash: context.tree_hash.clone(),
+            confirmed_transcript_hash: context.confirmed_transcript_hash.to_vec(),
+            interim_transcript_hash: self.state.interim_transcript_hash.to_vec(),
+            extensions: context.extensions.clone(),
+            tree: TreeReport {
+                total_leaf_count: tree.total_leaf_count(),
+                leaves,
+                parents,
+            },
+            secrets,
+            pending_proposals,
+            pending_commit,
+        })
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn secret_digest(&self, secret: &[u8]) -> Result<Vec<u8>, MlsError> {
+        self.cipher_suite_provider
+            .hash(secret)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_group,
+    };
+
+    use super::GroupStateReport;
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn reports_of_synchronized_members_are_equal() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        let report = alice.group.state_report().await.unwrap();
+        let bob_report = bob.group.state_report().await.unwrap();
+
+        assert!(report.divergences(&bob_report).is_empty());
+        assert_eq!(report.tree.leaves.len(), 2);
+
+        let decoded = GroupStateReport::from_bytes(&report.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, report);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn divergences_pinpoint_differing_fields() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        // Alice moves to the next epoch with an update path that Bob never
+        // receives.
+        alice.group.commit(vec![]).await.unwrap();
+
+        let pending = alice.group.state_report().await.unwrap();
+        assert!(pending.pending_commit.unwrap().contains_update_path);
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let report = alice.group.state_report().await.unwrap();
+        let bob_report = bob.group.state_report().await.unwrap();
+
+        let divergences = report.divergences(&bob_report);
+
+        assert!(divergences.contains(&"epoch".into()));
+        assert!(divergences.contains(&"secrets.init_secret".into()));
+        assert!(divergences.contains(&"tree.leaves[0]".into()));
+        assert!(!divergences.contains(&"tree.leaves[1]".into()));
+        assert!(!divergences.contains(&"group_id".into()));
+    }
+}
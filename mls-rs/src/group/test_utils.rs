@@ -104,6 +104,7 @@ impl TestGroup {
             ratchet_tree,
             new_client.config.clone(),
             new_client.signer.clone().unwrap(),
+            Default::default(),
         )
         .await?;
 
@@ -246,7 +247,7 @@ pub(crate) async fn test_group_custom(
         .protocol_versions(ProtocolVersion::all())
         .used_protocol_version(protocol_version)
         .signing_identity(signing_identity.clone(), secret_key, cipher_suite)
-        .build()
+        .build_unchecked()
         .create_group_with_id(TEST_GROUP.to_vec(), group_extensions())
         .await
         .unwrap();
@@ -284,7 +285,7 @@ where
 
     let group = custom(client_builder)
         .signing_identity(signing_identity.clone(), secret_key, cipher_suite)
-        .build()
+        .build_unchecked()
         .create_group_with_id(TEST_GROUP.to_vec(), group_extensions())
         .await
         .unwrap();
@@ -342,7 +343,7 @@ pub(crate) async fn get_test_groups_with_features(
                 .extension_type(999.into())
                 .leaf_node_extensions(leaf_extensions.clone())
                 .signing_identity(identity, secret_key, TEST_CIPHER_SUITE)
-                .build(),
+                .build_unchecked(),
         );
     }
 
@@ -451,6 +452,14 @@ impl MessageProcessor for GroupWithoutKeySchedule {
         self.inner.mls_rules()
     }
 
+    fn decode_limits(&self) -> DecodeLimits {
+        self.inner.decode_limits()
+    }
+
+    fn lifetime_policy(&self) -> LifetimePolicy {
+        self.inner.lifetime_policy()
+    }
+
     fn identity_provider(&self) -> Self::IdentityProvider {
         self.inner.identity_provider()
     }
@@ -467,6 +476,14 @@ impl MessageProcessor for GroupWithoutKeySchedule {
         self.inner.can_continue_processing(provisional_state)
     }
 
+    fn removed_from_group(&self) -> bool {
+        self.inner.removed_from_group()
+    }
+
+    fn set_removed_from_group(&mut self) {
+        self.inner.set_removed_from_group()
+    }
+
     #[cfg(feature = "private_message")]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn min_epoch_available(&self) -> Option<u64> {
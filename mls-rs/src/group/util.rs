@@ -9,23 +9,40 @@ use mls_rs_core::{
 use crate::{
     cipher_suite::CipherSuite,
     client::MlsError,
-    extension::RatchetTreeExt,
+    crypto::validate_aead_sizes,
+    extension::{ExtensionType, RatchetTreeExt},
     key_package::KeyPackageGeneration,
     protocol_version::ProtocolVersion,
     signer::Signable,
-    tree_kem::{node::LeafIndex, tree_validator::TreeValidator, TreeKemPublic},
+    tree_kem::{
+        node::LeafIndex,
+        tree_validator::{TreeValidationLevel, TreeValidator},
+        TreeKemPublic,
+    },
     CipherSuiteProvider, CryptoProvider,
 };
 
 #[cfg(feature = "by_ref_proposal")]
 use crate::extension::ExternalSendersExt;
 
+#[cfg(feature = "psk")]
+use crate::{
+    extension::RequiredJoinPskExt,
+    psk::{JustPreSharedKeyID, PreSharedKeyID, ResumptionPSKUsage},
+    ExtensionList,
+};
+
+#[cfg(feature = "psk")]
+use super::{proposal_filter::ProposalBundle, GroupContext};
+
 use super::{
     framing::Sender, message_signature::AuthenticatedContent,
-    transcript_hash::InterimTranscriptHash, ConfirmedTranscriptHash, EncryptedGroupSecrets,
-    ExportedTree, GroupInfo, GroupState,
+    transcript_hash::InterimTranscriptHash, ConfirmedTranscriptHash, DecodeLimits,
+    EncryptedGroupSecrets, ExportedTree, GroupInfo, GroupState,
 };
 
+use super::mls_rules::TreeLayoutRules;
+
 use super::message_processor::ProvisionalState;
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -77,6 +94,7 @@ pub(crate) async fn validate_group_info_member<C: CipherSuiteProvider>(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 pub(crate) async fn validate_group_info_joiner<C, I>(
     msg_version: ProtocolVersion,
@@ -84,14 +102,29 @@ pub(crate) async fn validate_group_info_joiner<C, I>(
     tree: Option<ExportedTree<'_>>,
     id_provider: &I,
     cs: &C,
+    limits: &DecodeLimits,
+    tree_rules: &dyn TreeLayoutRules,
+    validation_level: TreeValidationLevel,
+    max_parallelism: Option<usize>,
 ) -> Result<TreeKemPublic, MlsError>
 where
     C: CipherSuiteProvider,
     I: IdentityProvider,
 {
-    let tree = match group_info.extensions.get_as::<RatchetTreeExt>()? {
-        Some(ext) => ext.tree_data,
-        None => tree.ok_or(MlsError::RatchetTreeNotFound)?,
+    limits.check_group_info(group_info)?;
+
+    let tree_ext = group_info
+        .extensions
+        .iter()
+        .find(|ext| ext.extension_type == ExtensionType::RATCHET_TREE);
+
+    let tree = match tree_ext {
+        Some(ext) => limits.decode_tree(&mut &*ext.extension_data)?,
+        None => {
+            let tree = tree.ok_or(MlsError::RatchetTreeNotFound)?;
+            limits.check_tree_leaves(tree.0.total_leaf_count())?;
+            tree
+        }
     };
 
     let context = &group_info.group_context;
@@ -101,6 +134,9 @@ where
 
     // Verify the integrity of the ratchet tree
     TreeValidator::new(cs, context, id_provider)
+        .with_truncation(tree_rules.truncation(&context.extensions)?)
+        .with_level(validation_level)
+        .with_max_parallelism(max_parallelism)
         .validate(&mut tree)
         .await?;
 
@@ -118,6 +154,64 @@ where
     Ok(tree)
 }
 
+/// Verify that the PSKs used by a Welcome include the one required by the
+/// [`RequiredJoinPskExt`] of the group being joined, if any.
+#[cfg(feature = "psk")]
+pub(crate) fn check_required_join_psk(
+    context: &GroupContext,
+    psks: &[PreSharedKeyID],
+) -> Result<(), MlsError> {
+    let Some(required) = context.extensions.get_as::<RequiredJoinPskExt>()? else {
+        return Ok(());
+    };
+
+    psks.iter()
+        .any(
+            |psk| matches!(&psk.key_id, JustPreSharedKeyID::External(id) if id == &required.psk_id),
+        )
+        .then_some(())
+        .ok_or(MlsError::MissingRequiredJoinPsk)
+}
+
+/// Determine if `proposals` use the PSK `required` by a [`RequiredJoinPskExt`].
+///
+/// Members joining a branched or reinitialized group already prove membership
+/// in the original group through the resumption PSK, which is accepted instead.
+#[cfg(feature = "psk")]
+pub(crate) fn uses_required_join_psk(
+    proposals: &ProposalBundle,
+    required: &RequiredJoinPskExt,
+) -> bool {
+    proposals
+        .psk_proposals()
+        .iter()
+        .any(|p| match &p.proposal.psk.key_id {
+            JustPreSharedKeyID::External(id) => id == &required.psk_id,
+            JustPreSharedKeyID::Resumption(r) => r.usage != ResumptionPSKUsage::Application,
+        })
+}
+
+/// Verify that a commit applying `proposals` uses the PSK required by the
+/// [`RequiredJoinPskExt`] in `extensions`, the group context extensions
+/// resulting from the commit, if it adds members.
+#[cfg(feature = "psk")]
+pub(crate) fn ensure_required_join_psk(
+    proposals: &ProposalBundle,
+    extensions: &ExtensionList,
+) -> Result<(), MlsError> {
+    if proposals.add_proposals().is_empty() {
+        return Ok(());
+    }
+
+    let Some(required) = extensions.get_as::<RequiredJoinPskExt>()? else {
+        return Ok(());
+    };
+
+    uses_required_join_psk(proposals, &required)
+        .then_some(())
+        .ok_or(MlsError::MissingRequiredJoinPsk)
+}
+
 pub(crate) fn commit_sender(
     sender: &Sender,
     provisional_state: &ProvisionalState,
@@ -186,7 +280,9 @@ pub(crate) async fn find_key_package_generation<'a, K: KeyPackageStorage>(
         }
     }
 
-    Err(MlsError::WelcomeKeyPackageNotFound)
+    Err(MlsError::NoMatchingWelcomeKeyPackage(
+        secrets.iter().map(|s| s.new_member.clone()).collect(),
+    ))
 }
 
 pub(crate) fn cipher_suite_provider<P>(
@@ -196,7 +292,11 @@ pub(crate) fn cipher_suite_provider<P>(
 where
     P: CryptoProvider,
 {
-    crypto
+    let cipher_suite_provider = crypto
         .cipher_suite_provider(cipher_suite)
-        .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))
+        .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))?;
+
+    validate_aead_sizes(&cipher_suite_provider)?;
+
+    Ok(cipher_suite_provider)
 }
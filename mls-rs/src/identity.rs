@@ -5,6 +5,14 @@
 /// Basic credential identity provider.
 pub mod basic;
 
+/// Support for application defined credential types.
+pub mod custom;
+
+mod unsupported;
+
+pub(crate) use unsupported::PolicyIdentityProvider;
+pub use unsupported::UnsupportedCredentialPolicy;
+
 /// X.509 certificate identity provider.
 #[cfg(feature = "x509")]
 pub mod x509 {
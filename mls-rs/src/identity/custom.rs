@@ -0,0 +1,476 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use crate::{
+    identity::{CredentialType, CustomCredential, SigningIdentity},
+    time::MlsTime,
+};
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use mls_rs_core::{
+    error::{AnyError, IntoAnyError},
+    extension::ExtensionList,
+    identity::IdentityProvider,
+};
+
+/// Validation logic for a single [`CustomCredential`] type.
+///
+/// A handler is registered on a client using
+/// [`ClientBuilder::custom_credential`](crate::client_builder::ClientBuilder::custom_credential),
+/// which makes the client advertise support for the handled credential type
+/// and dispatches every credential of that type to the handler.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+pub trait CustomCredentialHandler: Send + Sync {
+    /// Error type that this handler returns on internal failure.
+    type Error: IntoAnyError;
+
+    /// Custom credential type handled by this handler.
+    fn credential_type(&self) -> CredentialType;
+
+    /// Determine if `credential`, held by `signing_identity`, is valid.
+    ///
+    /// Implementations should check that the credential is bound to the
+    /// signature key of `signing_identity`.
+    async fn validate(
+        &self,
+        signing_identity: &SigningIdentity,
+        credential: &CustomCredential,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error>;
+
+    /// A unique identifier for the holder of `credential`.
+    async fn identity(
+        &self,
+        credential: &CustomCredential,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error>;
+
+    /// Determine if `successor` is a valid replacement for `predecessor`.
+    ///
+    /// By default, a successor is valid if it resolves to the same
+    /// [identity](CustomCredentialHandler::identity).
+    async fn valid_successor(
+        &self,
+        predecessor: &CustomCredential,
+        successor: &CustomCredential,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        let predecessor = self.identity(predecessor, extensions).await?;
+        let successor = self.identity(successor, extensions).await?;
+
+        Ok(predecessor == successor)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+/// Error returned by a [`CustomCredentialProvider`].
+pub enum CustomCredentialProviderError {
+    /// Error returned by the wrapped identity provider.
+    #[cfg_attr(feature = "std", error(transparent))]
+    IdentityProviderError(AnyError),
+    /// Error returned by the custom credential handler.
+    #[cfg_attr(feature = "std", error(transparent))]
+    CustomCredentialError(AnyError),
+    /// The predecessor and successor use different credential types, one of
+    /// which is handled by the custom credential handler.
+    #[cfg_attr(feature = "std", error("credential type changed from {0:?} to {1:?}"))]
+    CredentialTypeMismatch(CredentialType, CredentialType),
+    /// The wrapped identity provider returned a different number of
+    /// identities than requested.
+    #[cfg_attr(
+        feature = "std",
+        error("expected {0} identities from the wrapped provider, got {1}")
+    )]
+    IdentityCountMismatch(usize, usize),
+}
+
+impl IntoAnyError for CustomCredentialProviderError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+/// Identity provider that dispatches credentials of a custom type to a
+/// [`CustomCredentialHandler`] and every other credential to a wrapped
+/// identity provider.
+///
+/// Providers can be nested in order to support multiple custom credential types.
+#[derive(Clone, Debug)]
+pub struct CustomCredentialProvider<I, H> {
+    inner: I,
+    handler: H,
+}
+
+impl<I, H> CustomCredentialProvider<I, H>
+where
+    I: IdentityProvider,
+    H: CustomCredentialHandler,
+{
+    /// Create a new provider handling the credential type of `handler` and
+    /// delegating every other credential type to `inner`.
+    pub fn new(inner: I, handler: H) -> Self {
+        Self { inner, handler }
+    }
+
+    /// Identity provider used for credentials not handled by the handler.
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    /// Handler used for credentials of its custom type.
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    fn handled<'a>(&self, signing_identity: &'a SigningIdentity) -> Option<&'a CustomCredential> {
+        signing_identity
+            .credential
+            .as_custom()
+            .filter(|c| c.credential_type == self.handler.credential_type())
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<I, H> IdentityProvider for CustomCredentialProvider<I, H>
+where
+    I: IdentityProvider,
+    H: CustomCredentialHandler,
+{
+    type Error = CustomCredentialProviderError;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        match self.handled(signing_identity) {
+            Some(credential) => self
+                .handler
+                .validate(signing_identity, credential, timestamp, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::CustomCredentialError(e.into_any_error())
+                }),
+            None => self
+                .inner
+                .validate_member(signing_identity, timestamp, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::IdentityProviderError(e.into_any_error())
+                }),
+        }
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        match self.handled(signing_identity) {
+            Some(credential) => self
+                .handler
+                .validate(signing_identity, credential, timestamp, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::CustomCredentialError(e.into_any_error())
+                }),
+            None => self
+                .inner
+                .validate_external_sender(signing_identity, timestamp, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::IdentityProviderError(e.into_any_error())
+                }),
+        }
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        match self.handled(signing_identity) {
+            Some(credential) => self
+                .handler
+                .identity(credential, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::CustomCredentialError(e.into_any_error())
+                }),
+            None => self
+                .inner
+                .identity(signing_identity, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::IdentityProviderError(e.into_any_error())
+                }),
+        }
+    }
+
+    async fn identities(
+        &self,
+        signing_identities: &[&SigningIdentity],
+        extensions: &ExtensionList,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        // Resolve all identities not handled by the handler with a single call to the wrapped
+        // provider.
+        let unhandled = signing_identities
+            .iter()
+            .copied()
+            .filter(|signing_identity| self.handled(signing_identity).is_none())
+            .collect::<Vec<_>>();
+
+        let resolved = self
+            .inner
+            .identities(&unhandled, extensions)
+            .await
+            .map_err(|e| {
+                CustomCredentialProviderError::IdentityProviderError(e.into_any_error())
+            })?;
+
+        if resolved.len() != unhandled.len() {
+            return Err(CustomCredentialProviderError::IdentityCountMismatch(
+                unhandled.len(),
+                resolved.len(),
+            ));
+        }
+
+        let mut resolved = resolved.into_iter();
+
+        let mut identities = Vec::with_capacity(signing_identities.len());
+
+        for signing_identity in signing_identities {
+            let identity = match self.handled(signing_identity) {
+                Some(credential) => self
+                    .handler
+                    .identity(credential, extensions)
+                    .await
+                    .map_err(|e| {
+                        CustomCredentialProviderError::CustomCredentialError(e.into_any_error())
+                    })?,
+                None => resolved.next().unwrap_or_default(),
+            };
+
+            identities.push(identity);
+        }
+
+        Ok(identities)
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        match (self.handled(predecessor), self.handled(successor)) {
+            (Some(predecessor), Some(successor)) => self
+                .handler
+                .valid_successor(predecessor, successor, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::CustomCredentialError(e.into_any_error())
+                }),
+            (None, None) => self
+                .inner
+                .valid_successor(predecessor, successor, extensions)
+                .await
+                .map_err(|e| {
+                    CustomCredentialProviderError::IdentityProviderError(e.into_any_error())
+                }),
+            _ => Err(CustomCredentialProviderError::CredentialTypeMismatch(
+                predecessor.credential.credential_type(),
+                successor.credential.credential_type(),
+            )),
+        }
+    }
+
+    async fn validate_leaf_node_extensions(
+        &self,
+        signing_identity: &SigningIdentity,
+        leaf_node_extensions: &ExtensionList,
+        group_extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        // Leaf node extensions do not depend on the credential type
+        self.inner
+            .validate_leaf_node_extensions(signing_identity, leaf_node_extensions, group_extensions)
+            .await
+            .map_err(|e| CustomCredentialProviderError::IdentityProviderError(e.into_any_error()))
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        let mut types = self.inner.supported_types();
+        let handled = self.handler.credential_type();
+
+        if !types.contains(&handled) {
+            types.push(handled);
+        }
+
+        types
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::{
+        crypto::{CipherSuiteProvider, SignatureSecretKey},
+        error::IntoAnyError,
+        extension::ExtensionList,
+    };
+
+    use crate::{
+        client::{test_utils::TEST_CIPHER_SUITE, MlsError},
+        client_builder::ClientBuilder,
+        client_config::ClientConfig,
+        crypto::test_utils::{test_cipher_suite_provider, TestCryptoProvider},
+        identity::{
+            basic::BasicIdentityProvider, Credential, CredentialType, CustomCredential,
+            SigningIdentity,
+        },
+        time::MlsTime,
+        Client,
+    };
+
+    use super::CustomCredentialHandler;
+
+    const TEST_CREDENTIAL_TYPE: CredentialType = CredentialType::new(0xff00);
+
+    #[derive(Debug)]
+    #[cfg_attr(feature = "std", derive(thiserror::Error))]
+    #[cfg_attr(feature = "std", error("empty credential"))]
+    struct EmptyCredentialError;
+
+    impl IntoAnyError for EmptyCredentialError {
+        #[cfg(feature = "std")]
+        fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+            Ok(self.into())
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestHandler;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl CustomCredentialHandler for TestHandler {
+        type Error = EmptyCredentialError;
+
+        fn credential_type(&self) -> CredentialType {
+            TEST_CREDENTIAL_TYPE
+        }
+
+        async fn validate(
+            &self,
+            _signing_identity: &SigningIdentity,
+            credential: &CustomCredential,
+            _timestamp: Option<MlsTime>,
+            _extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            (!credential.data.is_empty())
+                .then_some(())
+                .ok_or(EmptyCredentialError)
+        }
+
+        async fn identity(
+            &self,
+            credential: &CustomCredential,
+            _extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            Ok(credential.data.clone())
+        }
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn custom_signing_identity(identity: &[u8]) -> (SigningIdentity, SignatureSecretKey) {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+
+        let credential = Credential::Custom(CustomCredential::new(
+            TEST_CREDENTIAL_TYPE,
+            identity.to_vec(),
+        ));
+
+        (SigningIdentity::new(credential, public_key), secret_key)
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn custom_client(identity: &[u8]) -> Client<impl ClientConfig> {
+        let (signing_identity, secret_key) = custom_signing_identity(identity).await;
+
+        ClientBuilder::new()
+            .crypto_provider(TestCryptoProvider::new())
+            .identity_provider(BasicIdentityProvider::new())
+            .custom_credential(TestHandler)
+            .signing_identity(signing_identity, secret_key, TEST_CIPHER_SUITE)
+            .build_unchecked()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn custom_credential_type_is_advertised() {
+        let client = custom_client(b"alice").await;
+        let credentials = client.config.capabilities().credentials;
+
+        assert!(credentials.contains(&CredentialType::BASIC));
+        assert!(credentials.contains(&TEST_CREDENTIAL_TYPE));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn members_with_custom_credentials_can_join() {
+        let alice = custom_client(b"alice").await;
+        let bob = custom_client(b"bob").await;
+
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+        let key_package = bob.generate_key_package_message().await.unwrap();
+
+        let commit = group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        group.apply_pending_commit().await.unwrap();
+
+        let (bob_group, _) = bob
+            .join_group(None, &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        let member = bob_group.roster().member_with_index(0).unwrap();
+        let identity = member.signing_identity.credential.as_custom().unwrap();
+
+        assert_eq!(identity.data, b"alice");
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn invalid_custom_credential_is_rejected() {
+        let alice = custom_client(b"alice").await;
+        let mallory = custom_client(b"").await;
+
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+
+        let key_package = mallory.generate_key_package_message().await.unwrap();
+        let res = group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::IdentityProviderError(_)));
+    }
+}
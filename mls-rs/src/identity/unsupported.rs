@@ -0,0 +1,334 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{
+    extension::ExtensionList,
+    identity::{CredentialType, IdentityProvider, SigningIdentity},
+    time::MlsTime,
+};
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+
+const UNVERIFIED_IDENTITY_PREFIX: &[u8] = b"mls-rs unverified member ";
+
+/// Handling of group members using a credential type that is not supported
+/// by the [IdentityProvider] of a client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsupportedCredentialPolicy {
+    /// Leaves using an unsupported credential type are invalid. Joining a
+    /// group that contains such a leaf fails.
+    #[default]
+    Reject,
+    /// Leaves using an unsupported credential type are accepted without
+    /// validating their credential. Such members are reported by
+    /// [Group::unverified_members](crate::Group::unverified_members).
+    ///
+    /// This is meant for groups where those members are observers that the
+    /// application does not need to authenticate. Adding this client to such
+    /// groups also requires advertising the credential types in the
+    /// [capabilities](crate::client_builder::ClientBuilder::capabilities).
+    AllowUnverified,
+    /// Same as [AllowUnverified](UnsupportedCredentialPolicy::AllowUnverified),
+    /// except that application messages are not encrypted while the group
+    /// has unverified members.
+    AllowUnverifiedReceiveOnly,
+}
+
+impl UnsupportedCredentialPolicy {
+    pub(crate) fn allows_unverified(&self) -> bool {
+        !matches!(self, UnsupportedCredentialPolicy::Reject)
+    }
+
+    #[cfg(feature = "private_message")]
+    pub(crate) fn allows_sending_to_unverified(&self) -> bool {
+        !matches!(
+            self,
+            UnsupportedCredentialPolicy::AllowUnverifiedReceiveOnly
+        )
+    }
+}
+
+/// [IdentityProvider] applying the [UnsupportedCredentialPolicy] of a client
+/// on top of the identity provider configured by the application.
+///
+/// Credentials of types supported by the inner provider are always handled
+/// by the inner provider. If unverified members are allowed, other
+/// credentials are accepted without validation and identified by their
+/// signature key, which is unique among the members of a group. An
+/// unverified member can only be succeeded by a member with the same
+/// credential.
+#[derive(Clone, Debug)]
+pub struct PolicyIdentityProvider<I> {
+    inner: I,
+    policy: UnsupportedCredentialPolicy,
+}
+
+impl<I: IdentityProvider> PolicyIdentityProvider<I> {
+    pub(crate) fn new(inner: I, policy: UnsupportedCredentialPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    fn is_unverified(&self, signing_identity: &SigningIdentity) -> bool {
+        self.policy.allows_unverified()
+            && !self
+                .inner
+                .supported_types()
+                .contains(&signing_identity.credential.credential_type())
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+impl<I: IdentityProvider> IdentityProvider for PolicyIdentityProvider<I> {
+    type Error = I::Error;
+
+    async fn validate_member(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        if self.is_unverified(signing_identity) {
+            return Ok(());
+        }
+
+        self.inner
+            .validate_member(signing_identity, timestamp, extensions)
+            .await
+    }
+
+    async fn validate_external_sender(
+        &self,
+        signing_identity: &SigningIdentity,
+        timestamp: Option<MlsTime>,
+        extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .validate_external_sender(signing_identity, timestamp, extensions)
+            .await
+    }
+
+    async fn identity(
+        &self,
+        signing_identity: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<Vec<u8>, Self::Error> {
+        if self.is_unverified(signing_identity) {
+            return Ok([UNVERIFIED_IDENTITY_PREFIX, &signing_identity.signature_key].concat());
+        }
+
+        self.inner.identity(signing_identity, extensions).await
+    }
+
+    async fn identities(
+        &self,
+        signing_identities: &[&SigningIdentity],
+        extensions: &ExtensionList,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        if !signing_identities.iter().any(|id| self.is_unverified(id)) {
+            return self.inner.identities(signing_identities, extensions).await;
+        }
+
+        let mut identities = Vec::with_capacity(signing_identities.len());
+
+        for signing_identity in signing_identities {
+            identities.push(self.identity(signing_identity, extensions).await?);
+        }
+
+        Ok(identities)
+    }
+
+    async fn valid_successor(
+        &self,
+        predecessor: &SigningIdentity,
+        successor: &SigningIdentity,
+        extensions: &ExtensionList,
+    ) -> Result<bool, Self::Error> {
+        if self.is_unverified(predecessor) || self.is_unverified(successor) {
+            return Ok(predecessor.credential == successor.credential);
+        }
+
+        self.inner
+            .valid_successor(predecessor, successor, extensions)
+            .await
+    }
+
+    async fn validate_leaf_node_extensions(
+        &self,
+        signing_identity: &SigningIdentity,
+        leaf_node_extensions: &ExtensionList,
+        group_extensions: Option<&ExtensionList>,
+    ) -> Result<(), Self::Error> {
+        if self.is_unverified(signing_identity) {
+            return Ok(());
+        }
+
+        self.inner
+            .validate_leaf_node_extensions(signing_identity, leaf_node_extensions, group_extensions)
+            .await
+    }
+
+    fn supported_types(&self) -> Vec<CredentialType> {
+        self.inner.supported_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "private_message")]
+    use alloc::vec;
+
+    #[cfg(any(feature = "tree_index", feature = "private_message"))]
+    use assert_matches::assert_matches;
+    use mls_rs_core::identity::{Credential, CredentialType, CustomCredential, SigningIdentity};
+
+    use crate::{
+        client::{test_utils::TEST_CIPHER_SUITE, MlsError},
+        client_builder::test_utils::{TestClientBuilder, TestClientConfig},
+        crypto::test_utils::test_cipher_suite_provider,
+        identity::test_utils::BasicWithCustomProvider,
+        tree_kem::{leaf_node::test_utils::get_test_capabilities, Capabilities},
+        CipherSuiteProvider, Group,
+    };
+
+    use super::UnsupportedCredentialPolicy;
+
+    const UNSUPPORTED_CREDENTIAL_TYPE: u16 = 43;
+
+    fn capabilities() -> Capabilities {
+        let mut capabilities = get_test_capabilities();
+
+        capabilities
+            .credentials
+            .push(UNSUPPORTED_CREDENTIAL_TYPE.into());
+
+        capabilities
+    }
+
+    fn tolerant_builder() -> TestClientBuilder {
+        let mut identity_provider = BasicWithCustomProvider::default();
+        identity_provider.allow_any_custom = true;
+
+        TestClientBuilder::new_for_test()
+            .identity_provider(identity_provider)
+            .capabilities(capabilities())
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn join_group_with_observer(
+        policy: UnsupportedCredentialPolicy,
+    ) -> Result<Group<TestClientConfig>, MlsError> {
+        let alice = tolerant_builder()
+            .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
+            .await
+            .build_unchecked();
+
+        let (secret_key, public_key) = test_cipher_suite_provider(TEST_CIPHER_SUITE)
+            .signature_key_generate()
+            .await
+            .unwrap();
+
+        let observer_credential = Credential::Custom(CustomCredential::new(
+            CredentialType::new(UNSUPPORTED_CREDENTIAL_TYPE),
+            b"observer".to_vec(),
+        ));
+
+        let observer = tolerant_builder()
+            .signing_identity(
+                SigningIdentity::new(observer_credential, public_key),
+                secret_key,
+                TEST_CIPHER_SUITE,
+            )
+            .build_unchecked();
+
+        let bob = TestClientBuilder::new_for_test()
+            .capabilities(capabilities())
+            .unsupported_credential_policy(policy)
+            .with_random_signing_identity("bob", TEST_CIPHER_SUITE)
+            .await
+            .build_unchecked();
+
+        let mut group = alice.create_group(Default::default()).await.unwrap();
+
+        let welcome = group
+            .commit_builder()
+            .add_member(observer.generate_key_package_message().await.unwrap())
+            .unwrap()
+            .add_member(bob.generate_key_package_message().await.unwrap())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .welcome_messages
+            .remove(0);
+
+        group.apply_pending_commit().await.unwrap();
+
+        let (group, _) = bob.join_group(None, &welcome).await?;
+
+        Ok(group)
+    }
+
+    // The test identity provider accepts every member, so the observer is only
+    // rejected when the tree index resolves its identity.
+    #[cfg(feature = "tree_index")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unsupported_credential_is_rejected_by_default() {
+        let res = join_group_with_observer(UnsupportedCredentialPolicy::Reject)
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(MlsError::IdentityProviderError(_)));
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unsupported_credential_is_tolerated_as_unverified() {
+        let mut bob = join_group_with_observer(UnsupportedCredentialPolicy::AllowUnverified)
+            .await
+            .unwrap();
+
+        let unverified = bob.unverified_members();
+        assert_eq!(unverified.len(), 1);
+        assert_eq!(unverified[0].index, 1);
+
+        let res = bob.encrypt_application_message(b"hello", vec![]).await;
+        assert!(res.is_ok());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn unverified_members_are_updated_in_new_epochs() {
+        let mut bob = join_group_with_observer(UnsupportedCredentialPolicy::AllowUnverified)
+            .await
+            .unwrap();
+
+        assert_eq!(bob.unverified_members().len(), 1);
+
+        bob.commit_builder()
+            .remove_member(1)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        bob.apply_pending_commit().await.unwrap();
+
+        assert!(bob.unverified_members().is_empty());
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn receive_only_policy_refuses_to_send_to_unverified_members() {
+        let mut bob =
+            join_group_with_observer(UnsupportedCredentialPolicy::AllowUnverifiedReceiveOnly)
+                .await
+                .unwrap();
+
+        let res = bob.encrypt_application_message(b"hello", vec![]).await;
+        assert_matches!(res, Err(MlsError::UnverifiedRecipient(1)));
+    }
+}
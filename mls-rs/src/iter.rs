@@ -4,9 +4,10 @@
 
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
 mod sync_rayon {
-    use rayon::{
-        iter::IterBridge,
-        prelude::{FromParallelIterator, IntoParallelIterator, ParallelBridge, ParallelIterator},
+    use alloc::vec::Vec;
+    use rayon::prelude::{
+        FromParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+        ParallelSlice,
     };
 
     pub fn wrap_iter<I>(it: I) -> I::Iter
@@ -16,15 +17,6 @@ mod sync_rayon {
         it.into_par_iter()
     }
 
-    pub fn wrap_impl_iter<I>(it: I) -> IterBridge<I::IntoIter>
-    where
-        I: IntoIterator,
-        I::IntoIter: Send,
-        I::Item: Send,
-    {
-        it.into_iter().par_bridge()
-    }
-
     pub trait ParallelIteratorExt {
         type Ok: Send;
         type Error: Send;
@@ -50,10 +42,39 @@ mod sync_rayon {
             self.collect()
         }
     }
+
+    /// Apply `f` to all `items` on the rayon thread pool, with at most
+    /// `max_parallelism` items processed at the same time if set.
+    pub fn try_map_bounded<T, R, E, F>(
+        items: &[T],
+        max_parallelism: Option<usize>,
+        f: F,
+    ) -> Result<Vec<R>, E>
+    where
+        T: Sync,
+        R: Send,
+        E: Send,
+        F: Fn(&T) -> Result<R, E> + Send + Sync,
+    {
+        let Some(max_parallelism) = max_parallelism else {
+            return items.par_iter().map(f).collect();
+        };
+
+        // Each of the at most `max_parallelism` chunks is processed sequentially
+        let max_parallelism = max_parallelism.max(1);
+        let chunk_len = ((items.len() + max_parallelism - 1) / max_parallelism).max(1);
+
+        let chunks = items
+            .par_chunks(chunk_len)
+            .map(|chunk| chunk.iter().map(&f).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(chunks.into_iter().flatten().collect())
+    }
 }
 
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
-pub use sync_rayon::{wrap_impl_iter, wrap_iter, ParallelIteratorExt};
+pub use sync_rayon::{try_map_bounded, wrap_iter, ParallelIteratorExt};
 
 #[cfg(not(any(mls_build_async, feature = "rayon")))]
 mod sync {
@@ -64,19 +85,32 @@ mod sync {
         it.into_iter()
     }
 
-    pub fn wrap_impl_iter<I>(it: I) -> I::IntoIter
+    /// Apply `f` to all `items` sequentially.
+    pub fn try_map_bounded<T, R, E, F>(
+        items: &[T],
+        _max_parallelism: Option<usize>,
+        f: F,
+    ) -> Result<alloc::vec::Vec<R>, E>
     where
-        I: IntoIterator,
+        F: Fn(&T) -> Result<R, E>,
     {
-        it.into_iter()
+        items.iter().map(f).collect()
     }
 }
 
 #[cfg(not(any(mls_build_async, feature = "rayon")))]
-pub use sync::{wrap_impl_iter, wrap_iter};
+pub use sync::{try_map_bounded, wrap_iter};
 
 #[cfg(mls_build_async)]
 mod async_ {
+    use alloc::vec::Vec;
+    use core::future::Future;
+    use futures::{StreamExt, TryStreamExt};
+
+    /// Number of items awaited at the same time by [`try_map_bounded`] if no
+    /// maximum is set.
+    const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
     pub fn wrap_iter<I>(it: I) -> futures::stream::Iter<I::IntoIter>
     where
         I: IntoIterator,
@@ -84,13 +118,26 @@ mod async_ {
         futures::stream::iter(it)
     }
 
-    pub fn wrap_impl_iter<I>(it: I) -> futures::stream::Iter<I::IntoIter>
+    /// Apply `f` to all `items`, awaiting at most `max_parallelism` items at
+    /// the same time. The results are in the order of `items`.
+    pub async fn try_map_bounded<'a, T, R, E, F, Fut>(
+        items: &'a [T],
+        max_parallelism: Option<usize>,
+        f: F,
+    ) -> Result<Vec<R>, E>
     where
-        I: IntoIterator,
+        F: FnMut(&'a T) -> Fut,
+        Fut: Future<Output = Result<R, E>>,
     {
-        futures::stream::iter(it)
+        let max_parallelism = max_parallelism.unwrap_or(DEFAULT_MAX_CONCURRENCY);
+
+        futures::stream::iter(items)
+            .map(f)
+            .buffered(max_parallelism.max(1))
+            .try_collect()
+            .await
     }
 }
 
 #[cfg(mls_build_async)]
-pub use async_::{wrap_impl_iter, wrap_iter};
+pub use async_::{try_map_bounded, wrap_iter};
@@ -0,0 +1,189 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+
+use crate::{
+    cipher_suite::CipherSuite, client::MlsError, client_config::ClientConfig, Client, Group,
+    MlsMessage,
+};
+
+/// Key package messages of a single client for multiple cipher suites,
+/// uploaded at once to a discovery service.
+///
+/// A bundle contains at most one key package per cipher suite. The inviter
+/// selects the key package matching the cipher suite of its group with
+/// [`Group::key_package_from_bundle`].
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct KeyPackageBundle {
+    key_packages: Vec<MlsMessage>,
+}
+
+impl KeyPackageBundle {
+    /// Generate a key package with each of `clients`.
+    ///
+    /// The clients typically share their configuration and differ by their
+    /// signing identity and cipher suite. To join a group, the welcome message
+    /// must be processed by the client whose cipher suite is the one of the
+    /// group, as returned by [`MlsMessage::cipher_suite`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn generate<C>(clients: &[Client<C>]) -> Result<Self, MlsError>
+    where
+        C: ClientConfig + Clone,
+    {
+        let mut key_packages = Vec::<MlsMessage>::with_capacity(clients.len());
+
+        for client in clients {
+            let (_, cipher_suite) = client.signing_identity()?;
+
+            if key_packages
+                .iter()
+                .any(|kp| kp.cipher_suite() == Some(cipher_suite))
+            {
+                return Err(MlsError::DuplicateCipherSuiteInKeyPackageBundle(
+                    cipher_suite,
+                ));
+            }
+
+            key_packages.push(client.generate_key_package_message().await?);
+        }
+
+        Ok(Self { key_packages })
+    }
+
+    /// Key package messages of the bundle.
+    pub fn key_packages(&self) -> &[MlsMessage] {
+        &self.key_packages
+    }
+
+    /// Cipher suites for which the bundle contains a key package.
+    pub fn cipher_suites(&self) -> impl Iterator<Item = CipherSuite> + '_ {
+        self.key_packages.iter().filter_map(|kp| kp.cipher_suite())
+    }
+
+    /// Key package message for `cipher_suite`, if any.
+    pub fn choose(&self, cipher_suite: CipherSuite) -> Option<&MlsMessage> {
+        self.key_packages
+            .iter()
+            .find(|kp| kp.cipher_suite() == Some(cipher_suite))
+    }
+
+    /// Deserialize a bundle from transport.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize a bundle for transport.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+}
+
+impl<C> Group<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Select the key package of `bundle` that can be added to this group,
+    /// which is the one using the cipher suite of the group.
+    pub fn key_package_from_bundle(
+        &self,
+        bundle: &KeyPackageBundle,
+    ) -> Result<MlsMessage, MlsError> {
+        let cipher_suite = self.cipher_suite();
+
+        bundle
+            .choose(cipher_suite)
+            .cloned()
+            .ok_or(MlsError::NoKeyPackageForCipherSuite(cipher_suite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        client::{
+            test_utils::{TestClientBuilder, TestClientConfig, TEST_PROTOCOL_VERSION},
+            MlsError,
+        },
+        group::test_utils::test_group,
+        identity::test_utils::get_test_signing_identity,
+        CipherSuite, Client,
+    };
+
+    use super::KeyPackageBundle;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn test_client(cipher_suite: CipherSuite) -> Client<TestClientConfig> {
+        let (identity, secret_key) = get_test_signing_identity(cipher_suite, b"bob").await;
+
+        TestClientBuilder::new_for_test()
+            .signing_identity(identity, secret_key, cipher_suite)
+            .build_unchecked()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn inviter_adds_key_package_of_group_cipher_suite() {
+        let suites = [CipherSuite::CURVE25519_AES128, CipherSuite::P256_AES128];
+        let mut clients = vec![];
+
+        for cs in suites {
+            clients.push(test_client(cs).await);
+        }
+
+        let bundle = KeyPackageBundle::generate(&clients).await.unwrap();
+        let bundle = KeyPackageBundle::from_bytes(&bundle.to_bytes().unwrap()).unwrap();
+
+        assert!(bundle.cipher_suites().eq(suites));
+
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, CipherSuite::P256_AES128).await;
+        let key_package = alice.group.key_package_from_bundle(&bundle).unwrap();
+
+        let welcome = alice
+            .group
+            .commit_builder()
+            .add_member(key_package)
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+            .welcome_messages
+            .remove(0);
+
+        alice.group.apply_pending_commit().await.unwrap();
+
+        let joiner = clients
+            .iter()
+            .find(|c| c.signing_identity().unwrap().1 == welcome.cipher_suite().unwrap())
+            .unwrap();
+
+        joiner.join_group(None, &welcome).await.unwrap();
+
+        let alice = test_group(TEST_PROTOCOL_VERSION, CipherSuite::P384_AES256).await;
+        let res = alice.group.key_package_from_bundle(&bundle);
+
+        assert_matches!(
+            res,
+            Err(MlsError::NoKeyPackageForCipherSuite(
+                CipherSuite::P384_AES256
+            ))
+        );
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn bundle_contains_one_key_package_per_cipher_suite() {
+        let client = test_client(CipherSuite::CURVE25519_AES128).await;
+        let res = KeyPackageBundle::generate(&[client.clone(), client]).await;
+
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateCipherSuiteInKeyPackageBundle(
+                CipherSuite::CURVE25519_AES128
+            ))
+        );
+    }
+}
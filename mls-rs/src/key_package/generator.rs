@@ -7,6 +7,9 @@ use alloc::vec::Vec;
 use mls_rs_codec::{MlsDecode, MlsEncode};
 use mls_rs_core::{error::IntoAnyError, identity::IdentityProvider, key_package::KeyPackageData};
 
+#[cfg(all(not(mls_build_async), feature = "rayon"))]
+use {crate::iter::ParallelIteratorExt, rayon::prelude::*};
+
 use crate::client::MlsError;
 use crate::{
     crypto::{HpkeSecretKey, SignatureSecretKey},
@@ -137,6 +140,59 @@ where
             reference,
         })
     }
+
+    /// Generate `count` key packages that share the same lifetime, capabilities
+    /// and extensions. Each key package has its own init and leaf node keys.
+    ///
+    /// Key packages are generated in parallel when the `rayon` feature is
+    /// enabled or concurrently when building async.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn generate_batch(
+        &self,
+        count: usize,
+        lifetime: Lifetime,
+        capabilities: Capabilities,
+        key_package_extensions: ExtensionList,
+        leaf_node_extensions: ExtensionList,
+    ) -> Result<Vec<KeyPackageGeneration>, MlsError> {
+        #[cfg(all(not(mls_build_async), feature = "rayon"))]
+        let generations = (0..count)
+            .into_par_iter()
+            .map(|_| {
+                self.generate(
+                    lifetime.clone(),
+                    capabilities.clone(),
+                    key_package_extensions.clone(),
+                    leaf_node_extensions.clone(),
+                )
+            })
+            .try_collect();
+
+        #[cfg(mls_build_async)]
+        let generations = futures::future::try_join_all((0..count).map(|_| {
+            self.generate(
+                lifetime.clone(),
+                capabilities.clone(),
+                key_package_extensions.clone(),
+                leaf_node_extensions.clone(),
+            )
+        }))
+        .await;
+
+        #[cfg(not(any(mls_build_async, feature = "rayon")))]
+        let generations = (0..count)
+            .map(|_| {
+                self.generate(
+                    lifetime.clone(),
+                    capabilities.clone(),
+                    key_package_extensions.clone(),
+                    leaf_node_extensions.clone(),
+                )
+            })
+            .collect();
+
+        generations
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use crate::cipher_suite::CipherSuite;
-use crate::client::MlsError;
+use crate::client::{LeafNodeSourceKind, MlsError};
 use crate::crypto::HpkePublicKey;
 use crate::hash_reference::HashReference;
 use crate::identity::SigningIdentity;
@@ -21,7 +21,11 @@ use mls_rs_codec::MlsEncode;
 use mls_rs_codec::MlsSize;
 use mls_rs_core::extension::ExtensionList;
 
+mod bundle;
+mod sweep;
 mod validator;
+pub use bundle::KeyPackageBundle;
+pub use sweep::KeyPackageSweepReport;
 pub(crate) use validator::*;
 
 pub(crate) mod generator;
@@ -64,6 +68,7 @@ impl Debug for KeyPackage {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(
     all(feature = "ffi", not(test)),
     safer_ffi_gen::ffi_type(clone, opaque)
@@ -134,7 +139,11 @@ impl KeyPackage {
         if let LeafNodeSource::KeyPackage(lifetime) = &self.leaf_node.leaf_node_source {
             Ok(lifetime.not_after)
         } else {
-            Err(MlsError::InvalidLeafNodeSource)
+            Err(MlsError::UnexpectedLeafNodeSource(
+                None,
+                LeafNodeSourceKind::KeyPackage,
+                self.leaf_node.leaf_node_source.kind(),
+            ))
         }
     }
 }
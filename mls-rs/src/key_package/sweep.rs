@@ -0,0 +1,95 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_core::{error::IntoAnyError, key_package::KeyPackageStorage};
+
+use crate::{client::MlsError, client_config::ClientConfig, time::MlsTime, Client};
+
+/// Key packages deleted by [`Client::sweep_expired_key_packages`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyPackageSweepReport {
+    /// Ids of the deleted key packages, as used by the
+    /// [`KeyPackageStorage`].
+    pub deleted: Vec<Vec<u8>>,
+}
+
+impl<C> Client<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Delete all key packages of the [`KeyPackageStorage`] that expired at
+    /// the current time, together with their private keys.
+    ///
+    /// Expired key packages can not be used to join a group, so their
+    /// private keys are only occupying storage. The expired key packages are
+    /// found with [`KeyPackageStorage::expired_before`].
+    #[cfg(feature = "std")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn sweep_expired_key_packages(&self) -> Result<KeyPackageSweepReport, MlsError> {
+        self.sweep_expired_key_packages_with_time(MlsTime::now())
+            .await
+    }
+
+    /// Delete all key packages of the [`KeyPackageStorage`] that expired at
+    /// `time`, together with their private keys. See
+    /// [`Client::sweep_expired_key_packages`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn sweep_expired_key_packages_with_time(
+        &self,
+        time: MlsTime,
+    ) -> Result<KeyPackageSweepReport, MlsError> {
+        let mut repo = self.config.key_package_repo();
+
+        let deleted = repo
+            .expired_before(time.seconds_since_epoch())
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+        for id in &deleted {
+            repo.delete(id)
+                .await
+                .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+        }
+
+        Ok(KeyPackageSweepReport { deleted })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        client::test_utils::{test_client_with_key_pkg, TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        time::MlsTime,
+    };
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn expired_key_packages_are_swept() {
+        let (client, key_package) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let expiration = key_package
+            .into_key_package()
+            .unwrap()
+            .expiration()
+            .unwrap();
+
+        let report = client
+            .sweep_expired_key_packages_with_time(MlsTime::from(expiration))
+            .await
+            .unwrap();
+
+        assert!(report.deleted.is_empty());
+        assert_eq!(client.key_package_store().key_packages().len(), 1);
+
+        let report = client
+            .sweep_expired_key_packages_with_time(MlsTime::from(expiration + 1))
+            .await
+            .unwrap();
+
+        assert_eq!(report.deleted.len(), 1);
+        assert!(client.key_package_store().key_packages().is_empty());
+    }
+}
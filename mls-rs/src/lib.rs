@@ -42,6 +42,32 @@
 //! - Extensive test suite including security and interop focused tests against
 //!   pre-computed test vectors.
 //!
+//! ## Feature Profiles
+//!
+//! The default features provide full RFC 9420 conformance. Deployments that only
+//! need a subset of the protocol can disable default features and select the
+//! `minimal` profile, which supports groups using public handshake messages and
+//! by-value proposals only, then enable individual features such as
+//! `private_message`, `by_ref_proposal` or `psk` on top of it. The profile enables
+//! `std` and `tree_index` and no other feature. Its public API is tested on its own
+//! and combined with the other features.
+//!
+//! ## Synchronous and Asynchronous APIs
+//!
+//! The API is synchronous by default and does not require an async runtime.
+//! Building with `RUSTFLAGS="--cfg mls_build_async"` makes the same API
+//! asynchronous instead: functions of `Client` and `Group` that perform I/O or
+//! cryptographic operations, as well as the methods of the storage, crypto and
+//! identity traits implemented by applications, become `async`. The choice
+//! applies to the whole build, so provider crates and application code always
+//! use the same mode. Synchronous builds are fully supported and tested, and are
+//! well suited for command line tools and tests.
+//!
+//! There are no separate synchronous traits. In a synchronous build, traits such
+//! as [`GroupStateStorage`], [`KeyPackageStorage`], [`PreSharedKeyStorage`],
+//! [`CryptoProvider`] and [`IdentityProvider`] are implemented with ordinary
+//! functions.
+//!
 //! ## Crypto Providers
 //!
 //! For cipher suite descriptions see the RFC documentation [here](https://www.rfc-editor.org/rfc/rfc9420.html#name-mls-cipher-suites)
@@ -133,6 +159,15 @@ pub mod client_builder;
 mod client_config;
 /// Dependencies of [`CryptoProvider`] and [`CipherSuiteProvider`]
 pub mod crypto;
+#[cfg(feature = "debug_serialization")]
+mod debug_json;
+/// Compressing transport encoding of [`MlsMessage`]s.
+#[cfg(any(feature = "compression_deflate", feature = "compression_zstd"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "compression_deflate", feature = "compression_zstd")))
+)]
+pub mod envelope;
 /// Extension utilities and built-in extension types.
 pub mod extension;
 /// Tools to observe groups without being a member, useful
@@ -150,6 +185,12 @@ mod iter;
 mod key_package;
 /// Pre-shared key support.
 pub mod psk;
+/// Event driven session layer owning a [`Group`].
+#[cfg(feature = "session")]
+#[cfg_attr(docsrs, doc(cfg(feature = "session")))]
+pub mod session;
+#[cfg(all(test, not(mls_build_async), feature = "side_channel_audit"))]
+mod side_channel;
 mod signer;
 /// Storage providers to use with
 /// [`ClientBuilder`](client_builder::ClientBuilder).
@@ -168,12 +209,18 @@ pub mod mls_rules {
     pub use crate::group::{
         mls_rules::{
             CommitDirection, CommitOptions, CommitSource, DefaultMlsRules, EncryptionOptions,
+            LeafPlacement, TreeTruncation,
         },
         proposal_filter::{ProposalBundle, ProposalInfo, ProposalSource},
     };
 
     #[cfg(feature = "by_ref_proposal")]
-    pub use crate::group::proposal_ref::ProposalRef;
+    pub use crate::group::{proposal_filter::RejectedProposal, proposal_ref::ProposalRef};
+
+    pub use crate::group::padding::PaddingMode;
+
+    #[cfg(feature = "private_message")]
+    pub use crate::group::mls_rules::{MessageDecision, MessageRejection};
 }
 
 pub use mls_rs_core::extension::{Extension, ExtensionList};
@@ -185,12 +232,18 @@ pub use crate::{
         mls_rules::MlsRules,
         Group,
     },
-    key_package::{KeyPackage, KeyPackageRef},
+    key_package::{KeyPackage, KeyPackageBundle, KeyPackageRef, KeyPackageSweepReport},
 };
 
+#[cfg(feature = "debug_serialization")]
+#[cfg_attr(docsrs, doc(cfg(feature = "debug_serialization")))]
+pub use debug_json::DebugJson;
+
 /// Error types.
 pub mod error {
-    pub use crate::client::MlsError;
+    #[cfg(feature = "psk")]
+    pub use crate::client::PskFailureKind;
+    pub use crate::client::{HpkeKeyHolder, LeafDataKind, MlsError};
     pub use mls_rs_core::error::{AnyError, IntoAnyError};
     pub use mls_rs_core::extension::ExtensionError;
 }
@@ -200,6 +253,17 @@ pub mod time {
     pub use mls_rs_core::time::*;
 }
 
+/// Conformance checks of crypto providers against the MLS test vectors.
+#[cfg(any(test, feature = "test_vectors"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test_vectors")))]
+pub mod test_vectors;
+
+/// Ratchet tree math utilities.
+pub mod tree_math {
+    pub use crate::tree_kem::math::TreeSize;
+    pub use crate::tree_kem::node::{LeafIndex, NodeIndex};
+}
+
 mod tree_kem;
 
 pub use mls_rs_codec;
@@ -17,7 +17,10 @@ use core::convert::Infallible;
 use core::fmt::{self, Debug};
 
 #[cfg(feature = "psk")]
-use crate::{client::MlsError, CipherSuiteProvider};
+use crate::{
+    client::{MlsError, PskFailureKind},
+    CipherSuiteProvider,
+};
 
 #[cfg(feature = "psk")]
 use mls_rs_core::error::IntoAnyError;
@@ -123,6 +126,54 @@ pub(crate) enum ResumptionPSKUsage {
     Branch = 3u8,
 }
 
+/// Pre-shared key consumed by a commit.
+#[cfg(feature = "psk")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PskUsage {
+    /// External PSK identified by an application-defined id.
+    External(ExternalPskId),
+    /// Resumption PSK derived from an epoch of a group.
+    Resumption {
+        kind: ResumptionPskKind,
+        group_id: Vec<u8>,
+        epoch: u64,
+    },
+}
+
+/// Purpose of a resumption PSK, as defined by RFC 9420.
+#[cfg(feature = "psk")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResumptionPskKind {
+    Application,
+    Reinit,
+    Branch,
+}
+
+#[cfg(feature = "psk")]
+impl From<&JustPreSharedKeyID> for PskUsage {
+    fn from(id: &JustPreSharedKeyID) -> Self {
+        match id {
+            JustPreSharedKeyID::External(id) => PskUsage::External(id.clone()),
+            JustPreSharedKeyID::Resumption(r) => PskUsage::Resumption {
+                kind: match r.usage {
+                    ResumptionPSKUsage::Application => ResumptionPskKind::Application,
+                    ResumptionPSKUsage::Reinit => ResumptionPskKind::Reinit,
+                    ResumptionPSKUsage::Branch => ResumptionPskKind::Branch,
+                },
+                group_id: r.psk_group_id.0.clone(),
+                epoch: r.psk_epoch,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "psk")]
+pub(crate) fn psk_failure(ids: &[PreSharedKeyID], kind: PskFailureKind) -> MlsError {
+    let psks = ids.iter().map(|id| PskUsage::from(&id.key_id)).collect();
+    MlsError::PskFailure(psks, kind)
+}
+
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode)]
 struct PSKLabel<'a> {
     id: &'a PreSharedKeyID,
@@ -12,12 +12,14 @@ use mls_rs_core::{
 };
 
 use crate::{
-    client::MlsError,
+    client::{MlsError, PskFailureKind},
     group::{epoch::EpochSecrets, state_repo::GroupStateRepository, GroupContext},
     psk::secret::PskSecret,
 };
 
-use super::{secret::PskSecretInput, JustPreSharedKeyID, PreSharedKeyID, ResumptionPsk};
+use super::{
+    psk_failure, secret::PskSecretInput, JustPreSharedKeyID, PreSharedKeyID, ResumptionPsk,
+};
 
 pub(crate) struct PskResolver<'a, GS, K, PS>
 where
@@ -35,44 +37,60 @@ impl<GS: GroupStateStorage, K: KeyPackageStorage, PS: PreSharedKeyStorage>
     PskResolver<'_, GS, K, PS>
 {
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn resolve_resumption(&self, psk_id: &ResumptionPsk) -> Result<PreSharedKey, MlsError> {
+    async fn resolve_resumption<P: CipherSuiteProvider>(
+        &self,
+        psk_id: &ResumptionPsk,
+        cipher_suite_provider: &P,
+    ) -> Result<Option<PreSharedKey>, MlsError> {
         if let Some(ctx) = self.group_context {
             if ctx.epoch == psk_id.psk_epoch && ctx.group_id == psk_id.psk_group_id.0 {
-                let epoch = self.current_epoch.ok_or(MlsError::OldGroupStateNotFound)?;
-                return Ok(epoch.resumption_secret.clone());
+                return Ok(self
+                    .current_epoch
+                    .map(|epoch| epoch.resumption_secret.clone()));
             }
         }
 
+        #[cfg(not(feature = "prior_epoch"))]
+        let _ = cipher_suite_provider;
+
         #[cfg(feature = "prior_epoch")]
         if let Some(eps) = self.prior_epochs {
-            if let Some(psk) = eps.resumption_secret(psk_id).await? {
-                return Ok(psk);
+            if let Some(psk) = eps.resumption_secret(psk_id, cipher_suite_provider).await? {
+                return Ok(Some(psk));
             }
         }
 
-        Err(MlsError::OldGroupStateNotFound)
+        Ok(None)
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn resolve_external(&self, psk_id: &ExternalPskId) -> Result<PreSharedKey, MlsError> {
+    async fn resolve_external(
+        &self,
+        psk_id: &ExternalPskId,
+    ) -> Result<Option<PreSharedKey>, MlsError> {
         self.psk_store
             .get(psk_id)
             .await
-            .map_err(|e| MlsError::PskStoreError(e.into_any_error()))?
-            .ok_or(MlsError::MissingRequiredPsk)
+            .map_err(|e| MlsError::PskStoreError(e.into_any_error()))
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn resolve(&self, id: &[PreSharedKeyID]) -> Result<Vec<PskSecretInput>, MlsError> {
+    async fn resolve<P: CipherSuiteProvider>(
+        &self,
+        id: &[PreSharedKeyID],
+        cipher_suite_provider: &P,
+    ) -> Result<Vec<PskSecretInput>, MlsError> {
         let mut secret_inputs = Vec::new();
 
         for id in id {
             let psk = match &id.key_id {
                 JustPreSharedKeyID::External(external) => self.resolve_external(external).await,
                 JustPreSharedKeyID::Resumption(resumption) => {
-                    self.resolve_resumption(resumption).await
+                    self.resolve_resumption(resumption, cipher_suite_provider)
+                        .await
                 }
-            }?;
+            }?
+            .ok_or_else(|| psk_failure(core::slice::from_ref(id), PskFailureKind::Missing))?;
 
             secret_inputs.push(PskSecretInput {
                 id: id.clone(),
@@ -89,7 +107,7 @@ impl<GS: GroupStateStorage, K: KeyPackageStorage, PS: PreSharedKeyStorage>
         id: &[PreSharedKeyID],
         cipher_suite_provider: &P,
     ) -> Result<PskSecret, MlsError> {
-        let psk = self.resolve(id).await?;
+        let psk = self.resolve(id, cipher_suite_provider).await?;
         PskSecret::calculate(&psk, cipher_suite_provider).await
     }
 }
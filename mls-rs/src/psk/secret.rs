@@ -42,7 +42,7 @@ impl Debug for PskSecret {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test_vectors"))]
 impl From<Vec<u8>> for PskSecret {
     fn from(value: Vec<u8>) -> Self {
         PskSecret(Zeroizing::new(value))
@@ -0,0 +1,347 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use mls_rs_core::group::Member;
+
+use crate::{
+    client::MlsError,
+    client_config::ClientConfig,
+    group::{
+        ApplicationMessageDescription, CommitBuilder, CommitMessageDescription, ReceivedMessage,
+    },
+    time::MlsTime,
+    Group, MlsMessage, WireFormat,
+};
+
+#[cfg(feature = "by_ref_proposal")]
+use crate::group::ProposalMessageDescription;
+
+/// An event emitted by a [`Session`] while processing inbound messages.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    /// An application message was decrypted.
+    MessageReceived(ApplicationMessageDescription),
+    /// A member was added by the commit reported by the next
+    /// [`SessionEvent::CommitApplied`].
+    MemberJoined(Member),
+    /// A member was removed by the commit reported by the next
+    /// [`SessionEvent::CommitApplied`].
+    MemberLeft(Member),
+    /// A commit was applied and the group moved to `epoch`.
+    CommitApplied { epoch: u64, committer: u32 },
+    /// A proposal was received and cached for the next commit.
+    #[cfg(feature = "by_ref_proposal")]
+    ProposalReceived(ProposalMessageDescription),
+    /// A commit removed this member from the group. The session is now
+    /// read-only, see [`Group::is_removed`].
+    RemovedFromGroup { committer: u32 },
+}
+
+/// Event driven wrapper of a [`Group`].
+///
+/// A session accepts raw inbound messages with [`Session::receive`] and
+/// returns what happened as [`SessionEvent`]s. Messages that arrive before
+/// the messages they depend on are held back and processed once possible,
+/// as done by [`Group::process_incoming_message_buffered`]. Messages created
+/// by the session are queued until they are collected with
+/// [`Session::take_outbound`] and handed to the delivery service.
+///
+/// Commits created with [`Session::commit`] are applied when the delivery
+/// service echoes them back to [`Session::receive`], or explicitly with
+/// [`Session::apply_pending_commit`]. The group state is not written to
+/// storage by the session, the group is available through
+/// [`Session::group_mut`] for that and any other operation.
+pub struct Session<C>
+where
+    C: ClientConfig + Clone,
+{
+    group: Group<C>,
+    outbound: VecDeque<MlsMessage>,
+}
+
+impl<C> Session<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Create a session driving `group`.
+    pub fn new(group: Group<C>) -> Self {
+        Self {
+            group,
+            outbound: Default::default(),
+        }
+    }
+
+    /// The group driven by this session.
+    pub fn group(&self) -> &Group<C> {
+        &self.group
+    }
+
+    /// Mutable access to the group driven by this session.
+    pub fn group_mut(&mut self) -> &mut Group<C> {
+        &mut self.group
+    }
+
+    /// Consume the session, returning its group. Queued outbound messages
+    /// are dropped.
+    pub fn into_group(self) -> Group<C> {
+        self.group
+    }
+
+    /// Process the serialized inbound `message`, received now. See
+    /// [`Session::receive_with_time`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn receive(&mut self, message: &[u8]) -> Result<Vec<SessionEvent>, MlsError> {
+        self.receive_with_time(message, MlsTime::now()).await
+    }
+
+    /// Process the serialized inbound `message`, received at `time`.
+    ///
+    /// Only public and private messages of the group are accepted. The
+    /// returned events cover `message` and all previously held back messages
+    /// that could be processed after it, in processing order. No events are
+    /// returned if `message` was held back.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn receive_with_time(
+        &mut self,
+        message: &[u8],
+        time: MlsTime,
+    ) -> Result<Vec<SessionEvent>, MlsError> {
+        let message = MlsMessage::from_bytes(message)?;
+
+        if !matches!(
+            message.wire_format(),
+            WireFormat::PublicMessage | WireFormat::PrivateMessage
+        ) {
+            return Err(MlsError::UnexpectedMessageType);
+        }
+
+        let mut events = Vec::new();
+
+        for received in self
+            .group
+            .process_incoming_message_buffered(message, time)
+            .await?
+        {
+            self.push_events(received, &mut events);
+        }
+
+        Ok(events)
+    }
+
+    /// Encrypt the application message `data` and queue it for sending.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn send(&mut self, data: &[u8]) -> Result<(), MlsError> {
+        let message = self
+            .group
+            .encrypt_application_message(data, Vec::new())
+            .await?;
+
+        self.outbound.push_back(message);
+
+        Ok(())
+    }
+
+    /// Create a commit configured by `build` and queue it for sending,
+    /// followed by its welcome messages, if any.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn commit<F>(&mut self, build: F) -> Result<(), MlsError>
+    where
+        F: for<'a> FnOnce(CommitBuilder<'a, C>) -> Result<CommitBuilder<'a, C>, MlsError>,
+    {
+        let output = build(self.group.commit_builder())?.build().await?;
+
+        self.outbound.push_back(output.commit_message);
+        self.outbound.extend(output.welcome_messages);
+
+        Ok(())
+    }
+
+    /// Apply the commit created with [`Session::commit`] without waiting for
+    /// it to be received back from the delivery service.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn apply_pending_commit(&mut self) -> Result<Vec<SessionEvent>, MlsError> {
+        let description = self.group.apply_pending_commit().await?;
+        let mut events = Vec::new();
+
+        self.push_commit_events(description, &mut events);
+
+        Ok(events)
+    }
+
+    /// Remove and return all queued outbound messages, oldest first.
+    pub fn take_outbound(&mut self) -> Vec<MlsMessage> {
+        self.outbound.drain(..).collect()
+    }
+
+    fn push_events(&self, received: ReceivedMessage, events: &mut Vec<SessionEvent>) {
+        match received {
+            ReceivedMessage::ApplicationMessage(message) => {
+                events.push(SessionEvent::MessageReceived(message))
+            }
+            ReceivedMessage::Commit(description) => self.push_commit_events(description, events),
+            #[cfg(feature = "by_ref_proposal")]
+            ReceivedMessage::Proposal(proposal) => {
+                events.push(SessionEvent::ProposalReceived(proposal))
+            }
+            _ => {}
+        }
+    }
+
+    fn push_commit_events(
+        &self,
+        description: CommitMessageDescription,
+        events: &mut Vec<SessionEvent>,
+    ) {
+        let committer = description.committer;
+
+        if self.group.is_removed() {
+            events.push(SessionEvent::RemovedFromGroup { committer });
+            return;
+        }
+
+        let roster_update = description.state_update.roster_update();
+
+        events.extend(
+            roster_update
+                .added()
+                .iter()
+                .cloned()
+                .map(SessionEvent::MemberJoined),
+        );
+
+        events.extend(
+            roster_update
+                .removed()
+                .iter()
+                .cloned()
+                .map(SessionEvent::MemberLeft),
+        );
+
+        events.push(SessionEvent::CommitApplied {
+            epoch: description.state_update.new_epoch(),
+            committer,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use assert_matches::assert_matches;
+    use mls_rs_codec::MlsEncode;
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        client::MlsError,
+        group::test_utils::test_group,
+        key_package::test_utils::test_key_package_message,
+        time::MlsTime,
+        MlsMessage,
+    };
+
+    use super::{Session, SessionEvent};
+
+    fn to_bytes(message: &MlsMessage) -> Vec<u8> {
+        message.mls_encode_to_vec().unwrap()
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn session_emits_events_in_dependency_order() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        let mut alice = Session::new(alice.group);
+        let mut bob = Session::new(bob.group);
+
+        let carol =
+            test_key_package_message(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "carol").await;
+
+        alice
+            .commit(|builder| builder.add_member(carol))
+            .await
+            .unwrap();
+
+        let outbound = alice.take_outbound();
+        assert_eq!(outbound.len(), 2);
+        assert!(alice.take_outbound().is_empty());
+
+        let events = alice.receive(&to_bytes(&outbound[0])).await.unwrap();
+
+        assert_matches!(
+            &events[..],
+            [
+                SessionEvent::MemberJoined(member),
+                SessionEvent::CommitApplied { epoch: 2, committer: 0 }
+            ] if member.index == 2
+        );
+
+        alice.send(b"hello").await.unwrap();
+        let message = alice.take_outbound().remove(0);
+
+        // The message of the new epoch is held back until the commit arrives
+        let time = MlsTime::now();
+        let events = bob
+            .receive_with_time(&to_bytes(&message), time)
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+
+        let events = bob
+            .receive_with_time(&to_bytes(&outbound[0]), time)
+            .await
+            .unwrap();
+
+        assert_matches!(
+            &events[..],
+            [
+                SessionEvent::MemberJoined(_),
+                SessionEvent::CommitApplied { epoch: 2, committer: 0 },
+                SessionEvent::MessageReceived(message)
+            ] if message.data() == b"hello"
+        );
+
+        let res = bob.receive(&to_bytes(&outbound[1])).await;
+        assert_matches!(res, Err(MlsError::UnexpectedMessageType));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn session_reports_removal() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        let mut alice = Session::new(alice.group);
+        let mut bob = Session::new(bob.group);
+
+        alice
+            .commit(|builder| builder.remove_member(1))
+            .await
+            .unwrap();
+
+        let events = alice.apply_pending_commit().await.unwrap();
+
+        assert_matches!(
+            &events[..],
+            [
+                SessionEvent::MemberLeft(member),
+                SessionEvent::CommitApplied { epoch: 2, committer: 0 }
+            ] if member.index == 1
+        );
+
+        let commit = alice.take_outbound().remove(0);
+        let events = bob.receive(&to_bytes(&commit)).await.unwrap();
+
+        assert_matches!(
+            &events[..],
+            [SessionEvent::RemovedFromGroup { committer: 0 }]
+        );
+
+        let res = bob.send(b"bye").await;
+        assert_matches!(res, Err(MlsError::RemovedFromGroup));
+        assert!(bob.into_group().is_removed());
+    }
+}
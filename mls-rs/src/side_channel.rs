@@ -0,0 +1,243 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Timing tests of the secret handling paths, for side-channel review.
+//!
+//! [`t_statistic`] implements the leakage test of dudect ("Dude, is my code
+//! constant time?", Reparaz, Balasch and Verbauwhede, 2017). An operation is
+//! run on inputs of two classes, chosen in random order, and Welch's t-test
+//! compares the running times of the two classes. A statistic whose absolute
+//! value exceeds [`LEAKAGE_THRESHOLD`] is evidence that the running time
+//! depends on the class of the input.
+//!
+//! The tests of this module compare, with the test crypto provider:
+//! - [`CipherSuiteProvider::constant_time_eq`](crate::CipherSuiteProvider::constant_time_eq)
+//!   on equal inputs and on random inputs,
+//! - the check of confirmation tags with the correct key and with random keys,
+//! - the check of membership tags against the correct tag and random tags,
+//! - the decryption of the group secrets of a Welcome with a fixed and with a
+//!   random init key. Finding the entry of the Welcome to decrypt only
+//!   compares public key package references,
+//! - the derivation of message keys from the secret tree for a fixed and for
+//!   random encryption secrets.
+//!
+//! The tests are ignored by default, as their result is only meaningful in
+//! an optimized build on an otherwise idle machine. They are run with
+//! `cargo test --release --features side_channel_audit side_channel -- --ignored`.
+//! A passing test is statistical evidence for the machine, crypto provider and
+//! build it ran with, not a proof that the code is constant time.
+
+use alloc::vec::Vec;
+use std::time::Instant;
+
+/// Absolute value of the t statistic above which dudect considers an
+/// operation to not be constant time.
+pub(crate) const LEAKAGE_THRESHOLD: f64 = 10.0;
+
+/// Run `op` once per entry of `classes` on the input of that class and return
+/// Welch's t statistic of the running times of the two classes.
+///
+/// `classes` should be random so that the order of the measurements does not
+/// bias the result. As done by dudect, the slowest tenth of the measurements is
+/// discarded to remove interruptions by the scheduler.
+pub(crate) fn t_statistic<I, F>(inputs: &[I; 2], classes: &[bool], mut op: F) -> f64
+where
+    F: FnMut(&I),
+{
+    let timings = classes
+        .iter()
+        .map(|&class| {
+            let input = &inputs[class as usize];
+            let start = Instant::now();
+            op(input);
+            (class, start.elapsed().as_nanos() as f64)
+        })
+        .collect::<Vec<_>>();
+
+    let mut sorted = timings.iter().map(|(_, t)| *t).collect::<Vec<_>>();
+    sorted.sort_by(f64::total_cmp);
+
+    let Some(&cutoff) = sorted.get(sorted.len() * 9 / 10) else {
+        return 0.0;
+    };
+
+    let [(mean_0, var_0, n_0), (mean_1, var_1, n_1)] = [false, true].map(|class| {
+        let samples = timings
+            .iter()
+            .filter(|(c, t)| *c == class && *t <= cutoff)
+            .map(|(_, t)| *t)
+            .collect::<Vec<_>>();
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let var = samples.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / (n - 1.0);
+
+        (mean, var, n)
+    });
+
+    let standard_error = (var_0 / n_0 + var_1 / n_1).sqrt();
+
+    if standard_error == 0.0 {
+        return if mean_0 == mean_1 { 0.0 } else { f64::INFINITY };
+    }
+
+    (mean_0 - mean_1) / standard_error
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use mls_rs_core::crypto::CipherSuiteProvider;
+    use zeroize::Zeroizing;
+
+    use crate::{
+        client::test_utils::TEST_CIPHER_SUITE,
+        crypto::test_utils::test_cipher_suite_provider,
+        group::{
+            confirmation_tag::ConfirmationTag,
+            framing::test_utils::get_test_auth_content,
+            key_schedule::test_utils::get_test_key_schedule,
+            secret_tree::{KeyType, SecretTree},
+            test_utils::get_test_group_context,
+            transcript_hash::ConfirmedTranscriptHash,
+        },
+        tree_kem::hpke_encryption::{decrypt_with_label, encrypt_with_label},
+    };
+
+    use super::{t_statistic, LEAKAGE_THRESHOLD};
+
+    fn random_classes(n: usize) -> Vec<bool> {
+        (0..n).map(|_| rand::random()).collect()
+    }
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|_| rand::random()).collect()
+    }
+
+    #[test]
+    #[ignore]
+    fn constant_time_eq_does_not_depend_on_the_inputs() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let expected = random_bytes(cs.kdf_extract_size());
+
+        // Random inputs differ from `expected` at the first byte with high probability.
+        let inputs = [expected.clone(), random_bytes(expected.len())];
+
+        let t = t_statistic(&inputs, &random_classes(100_000), |input| {
+            core::hint::black_box(cs.constant_time_eq(&expected, input));
+        });
+
+        assert!(t.abs() < LEAKAGE_THRESHOLD, "t = {t}");
+    }
+
+    #[test]
+    #[ignore]
+    fn tag_check_does_not_depend_on_the_result() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let key = random_bytes(cs.kdf_extract_size());
+        let hash = ConfirmedTranscriptHash::from(random_bytes(cs.kdf_extract_size()));
+        let tag = ConfirmationTag::create(&key, &hash, &cs).unwrap();
+
+        let inputs = [key.clone(), random_bytes(key.len())];
+
+        let t = t_statistic(&inputs, &random_classes(20_000), |key| {
+            core::hint::black_box(tag.matches(key, &hash, &cs).unwrap());
+        });
+
+        assert!(t.abs() < LEAKAGE_THRESHOLD, "t = {t}");
+    }
+
+    #[test]
+    #[ignore]
+    fn membership_tag_check_does_not_depend_on_the_result() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let key_schedule = get_test_key_schedule(TEST_CIPHER_SUITE);
+        let content = get_test_auth_content();
+        let context = get_test_group_context(1, TEST_CIPHER_SUITE);
+
+        let expected = key_schedule
+            .get_membership_tag(&content, &context, &cs)
+            .unwrap();
+
+        let inputs = [expected.to_vec(), random_bytes(expected.len())];
+
+        let t = t_statistic(&inputs, &random_classes(20_000), |tag| {
+            let expected = key_schedule
+                .get_membership_tag(&content, &context, &cs)
+                .unwrap();
+
+            core::hint::black_box(cs.constant_time_eq(&expected, tag));
+        });
+
+        assert!(t.abs() < LEAKAGE_THRESHOLD, "t = {t}");
+    }
+
+    #[test]
+    #[ignore]
+    fn welcome_secrets_decryption_does_not_depend_on_the_init_key() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let group_secrets = random_bytes(2 * cs.kdf_extract_size());
+        let encrypted_group_info = random_bytes(256);
+
+        let inputs = [
+            cs.kem_derive(&alloc::vec![0u8; cs.kdf_extract_size()])
+                .unwrap(),
+            cs.kem_generate().unwrap(),
+        ]
+        .map(|(secret_key, public_key)| {
+            let ciphertext = encrypt_with_label(
+                &cs,
+                &public_key,
+                b"Welcome",
+                &encrypted_group_info,
+                &group_secrets,
+            )
+            .unwrap();
+
+            (secret_key, public_key, ciphertext)
+        });
+
+        let t = t_statistic(
+            &inputs,
+            &random_classes(5_000),
+            |(secret_key, public_key, ciphertext)| {
+                let secrets = decrypt_with_label(
+                    &cs,
+                    secret_key,
+                    public_key,
+                    b"Welcome",
+                    &encrypted_group_info,
+                    ciphertext,
+                )
+                .unwrap();
+
+                core::hint::black_box(secrets);
+            },
+        );
+
+        assert!(t.abs() < LEAKAGE_THRESHOLD, "t = {t}");
+    }
+
+    #[test]
+    #[ignore]
+    fn message_key_derivation_does_not_depend_on_the_secret() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let inputs = [
+            alloc::vec![0u8; cs.kdf_extract_size()],
+            random_bytes(cs.kdf_extract_size()),
+        ];
+
+        let t = t_statistic(&inputs, &random_classes(5_000), |secret| {
+            let mut tree = SecretTree::<u32>::new(8, Zeroizing::new(secret.clone()));
+
+            let key = tree
+                .message_key_generation(&cs, 10, KeyType::Application, 3, 16)
+                .unwrap();
+
+            core::hint::black_box(key);
+        });
+
+        assert!(t.abs() < LEAKAGE_THRESHOLD, "t = {t}");
+    }
+}
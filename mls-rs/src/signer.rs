@@ -11,7 +11,7 @@ use crate::client::MlsError;
 use crate::crypto::{CipherSuiteProvider, SignaturePublicKey, SignatureSecretKey};
 
 #[derive(Clone, MlsSize, MlsEncode)]
-struct SignContent {
+pub(crate) struct SignContent {
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
     label: Vec<u8>,
     #[mls_codec(with = "mls_rs_codec::byte_vec")]
@@ -56,16 +56,22 @@ pub(crate) trait Signable<'a> {
 
     fn write_signature(&mut self, signature: Vec<u8>);
 
+    /// Bytes passed to the signature algorithm, i.e. the encoded `SignContent`
+    /// of `SignWithLabel`.
+    fn signature_input(&self, context: &Self::SigningContext) -> Result<Vec<u8>, MlsError> {
+        let sign_content = SignContent::new(Self::SIGN_LABEL, self.signable_content(context)?);
+
+        Ok(sign_content.mls_encode_to_vec()?)
+    }
+
     async fn sign<P: CipherSuiteProvider>(
         &mut self,
         signature_provider: &P,
         signer: &SignatureSecretKey,
         context: &Self::SigningContext,
     ) -> Result<(), MlsError> {
-        let sign_content = SignContent::new(Self::SIGN_LABEL, self.signable_content(context)?);
-
         let signature = signature_provider
-            .sign(signer, &sign_content.mls_encode_to_vec()?)
+            .sign(signer, &self.signature_input(context)?)
             .await
             .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
 
@@ -80,13 +86,11 @@ pub(crate) trait Signable<'a> {
         public_key: &SignaturePublicKey,
         context: &Self::SigningContext,
     ) -> Result<(), MlsError> {
-        let sign_content = SignContent::new(Self::SIGN_LABEL, self.signable_content(context)?);
-
         signature_provider
             .verify(
                 public_key,
                 self.signature(),
-                &sign_content.mls_encode_to_vec()?,
+                &self.signature_input(context)?,
             )
             .await
             .map_err(|_| MlsError::InvalidSignature)
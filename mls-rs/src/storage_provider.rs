@@ -2,10 +2,12 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
+mod client_state;
 /// Storage providers that operate completely in memory.
 pub mod in_memory;
 pub(crate) mod key_package;
 
+pub use client_state::*;
 pub use key_package::*;
 
 #[cfg(feature = "sqlite")]
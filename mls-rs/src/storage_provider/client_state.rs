@@ -0,0 +1,633 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
+use mls_rs_core::{
+    crypto::{CipherSuiteProvider, CryptoProvider},
+    error::IntoAnyError,
+    group::{EpochRecord, GroupState, GroupStateStorage},
+    key_package::{KeyPackageData, KeyPackageStorage},
+    psk::{ExternalPskId, PreSharedKey, PreSharedKeyStorage},
+    time::MlsTime,
+};
+use zeroize::Zeroizing;
+
+use crate::{cipher_suite::CipherSuite, client::MlsError, client_config::ClientConfig, Client};
+
+const CLIENT_STATE_VERSION: u16 = 1;
+const EXPORT_KEY_LABEL: &[u8] = b"mls-rs client state export key";
+
+/// Everything stored for a client, as returned by
+/// [`Client::storage_inventory`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StorageInventory {
+    /// Groups in the [`GroupStateStorage`].
+    pub groups: Vec<StoredGroup>,
+    /// Key packages in the [`KeyPackageStorage`].
+    pub key_packages: Vec<StoredKeyPackage>,
+    /// Pre-shared keys in the [`PreSharedKeyStorage`].
+    pub psks: Vec<StoredPsk>,
+}
+
+impl StorageInventory {
+    /// Total size in bytes of all stored values.
+    pub fn total_bytes(&self) -> usize {
+        let groups = self
+            .groups
+            .iter()
+            .map(|group| {
+                group.state_bytes + group.epochs.iter().map(|epoch| epoch.bytes).sum::<usize>()
+            })
+            .sum::<usize>();
+
+        let key_packages = self.key_packages.iter().map(|kp| kp.bytes).sum::<usize>();
+        let psks = self.psks.iter().map(|psk| psk.bytes).sum::<usize>();
+
+        groups + key_packages + psks
+    }
+}
+
+/// A group stored in the [`GroupStateStorage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StoredGroup {
+    pub group_id: Vec<u8>,
+    /// Size of the current group state.
+    pub state_bytes: usize,
+    /// Stored prior epochs, from the oldest to the newest.
+    pub epochs: Vec<StoredEpoch>,
+    /// Time of the last write of the group state, if recorded by the storage
+    /// provider, see [`GroupStateStorage::state_updated_at`].
+    pub updated_at: Option<MlsTime>,
+}
+
+/// A prior epoch of a [`StoredGroup`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StoredEpoch {
+    pub epoch_id: u64,
+    pub bytes: usize,
+    /// Time of the last write of the epoch, if recorded by the storage
+    /// provider, see [`GroupStateStorage::epoch_updated_at`].
+    pub updated_at: Option<MlsTime>,
+}
+
+/// A key package stored in the [`KeyPackageStorage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StoredKeyPackage {
+    pub id: Vec<u8>,
+    /// Size of the encoded [`KeyPackageData`].
+    pub bytes: usize,
+    /// Expiration of the key package in seconds since the Unix epoch.
+    pub expiration: u64,
+}
+
+/// A pre-shared key stored in the [`PreSharedKeyStorage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StoredPsk {
+    pub id: ExternalPskId,
+    pub bytes: usize,
+}
+
+/// Full state of a client created by [`Client::export_state`], encrypted
+/// under a key provided by the application.
+///
+/// The state is restored on another device with [`Client::import_state`].
+#[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
+pub struct ExportedClientState {
+    version: u16,
+    cipher_suite: CipherSuite,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    salt: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    nonce: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    ciphertext: Vec<u8>,
+}
+
+impl ExportedClientState {
+    /// Version of the export format.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    /// Deserialize an exported client state.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlsError> {
+        Self::mls_decode(&mut &*bytes).map_err(Into::into)
+    }
+
+    /// Serialize an exported client state.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MlsError> {
+        self.mls_encode_to_vec().map_err(Into::into)
+    }
+
+    fn header(&self) -> Result<Vec<u8>, MlsError> {
+        let header = ExportedClientState {
+            ciphertext: Vec::new(),
+            ..self.clone()
+        };
+
+        header.mls_encode_to_vec().map_err(Into::into)
+    }
+}
+
+/// Values restored by [`Client::import_state`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ClientStateImport {
+    /// Ids of the groups written to the [`GroupStateStorage`].
+    pub groups: Vec<Vec<u8>>,
+    /// Ids of the key packages written to the [`KeyPackageStorage`].
+    pub key_packages: Vec<Vec<u8>>,
+    /// Exported pre-shared keys. The [`PreSharedKeyStorage`] is read-only
+    /// for the client, so they must be inserted by the application.
+    pub psks: Vec<(ExternalPskId, PreSharedKey)>,
+}
+
+#[derive(MlsSize, MlsEncode, MlsDecode)]
+struct ClientStateContents {
+    groups: Vec<ExportedGroup>,
+    key_packages: Vec<ExportedKeyPackage>,
+    psks: Vec<ExportedPsk>,
+}
+
+#[derive(MlsSize, MlsEncode, MlsDecode)]
+struct ExportedGroup {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    id: Vec<u8>,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    state: Vec<u8>,
+    epochs: Vec<ExportedEpoch>,
+}
+
+#[derive(MlsSize, MlsEncode, MlsDecode)]
+struct ExportedEpoch {
+    id: u64,
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    data: Vec<u8>,
+}
+
+#[derive(MlsSize, MlsEncode, MlsDecode)]
+struct ExportedKeyPackage {
+    #[mls_codec(with = "mls_rs_codec::byte_vec")]
+    id: Vec<u8>,
+    data: KeyPackageData,
+}
+
+#[derive(MlsSize, MlsEncode, MlsDecode)]
+struct ExportedPsk {
+    id: ExternalPskId,
+    psk: PreSharedKey,
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn derive_export_key<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    export_key: &[u8],
+    salt: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    let prk = cipher_suite_provider
+        .kdf_extract(salt, export_key)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+    cipher_suite_provider
+        .kdf_expand(
+            &prk,
+            EXPORT_KEY_LABEL,
+            cipher_suite_provider.aead_key_size(),
+        )
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+impl<C> Client<C>
+where
+    C: ClientConfig + Clone,
+{
+    /// Enumerate the groups, prior epochs, key packages and pre-shared keys
+    /// stored for this client, together with their sizes.
+    ///
+    /// Values are found with [`GroupStateStorage::group_ids`],
+    /// [`KeyPackageStorage::key_package_ids`] and
+    /// [`PreSharedKeyStorage::psk_ids`]. If a storage provider can not
+    /// enumerate its values, the error [`MlsError::GroupStorageNotEnumerable`],
+    /// [`MlsError::KeyPackageStorageNotEnumerable`] or
+    /// [`MlsError::PskStorageNotEnumerable`] is returned.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn storage_inventory(&self) -> Result<StorageInventory, MlsError> {
+        let contents = self.stored_contents().await?;
+        let group_storage = self.config.group_state_storage();
+        let mut groups = Vec::with_capacity(contents.groups.len());
+
+        for group in contents.groups.iter() {
+            let mut epochs = Vec::with_capacity(group.epochs.len());
+
+            for epoch in group.epochs.iter() {
+                let updated_at = group_storage
+                    .epoch_updated_at(&group.id, epoch.id)
+                    .await
+                    .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+                epochs.push(StoredEpoch {
+                    epoch_id: epoch.id,
+                    bytes: epoch.data.len(),
+                    updated_at,
+                });
+            }
+
+            let updated_at = group_storage
+                .state_updated_at(&group.id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            groups.push(StoredGroup {
+                group_id: group.id.clone(),
+                state_bytes: group.state.len(),
+                epochs,
+                updated_at,
+            });
+        }
+
+        let key_packages = contents
+            .key_packages
+            .iter()
+            .map(|kp| StoredKeyPackage {
+                id: kp.id.clone(),
+                bytes: kp.data.mls_encoded_len(),
+                expiration: kp.data.expiration,
+            })
+            .collect();
+
+        let psks = contents
+            .psks
+            .iter()
+            .map(|psk| StoredPsk {
+                id: psk.id.clone(),
+                bytes: psk.psk.len(),
+            })
+            .collect();
+
+        Ok(StorageInventory {
+            groups,
+            key_packages,
+            psks,
+        })
+    }
+
+    /// Export everything stored for this client, encrypted under
+    /// `export_key`, in order to move the client to another device.
+    ///
+    /// The export is encrypted with the cipher suite of the signing identity
+    /// of this client. The `export_key` should be a uniformly random secret,
+    /// e.g. transferred between the devices out of band. The stored values are
+    /// enumerated as described in [`Client::storage_inventory`], and the
+    /// export fails rather than omitting the values of a storage provider that
+    /// can not enumerate them.
+    ///
+    /// # Warning
+    ///
+    /// The export contains the private keys of all groups and key packages.
+    /// The client must not be used on both devices after the import, see
+    /// [`Group::export_membership`](crate::Group::export_membership).
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn export_state(&self, export_key: &[u8]) -> Result<ExportedClientState, MlsError> {
+        let (_, cipher_suite) = self.signing_identity()?;
+        let cs = self.client_state_cipher_suite_provider(cipher_suite)?;
+
+        let salt = cs
+            .random_bytes_vec(cs.kdf_extract_size())
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let nonce = cs
+            .random_bytes_vec(cs.aead_nonce_size())
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let mut exported = ExportedClientState {
+            version: CLIENT_STATE_VERSION,
+            cipher_suite,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+        };
+
+        let header = exported.header()?;
+        let key = derive_export_key(&cs, export_key, &exported.salt).await?;
+        let contents = Zeroizing::new(self.stored_contents().await?.mls_encode_to_vec()?);
+
+        exported.ciphertext = cs
+            .aead_seal(&key, &contents, Some(&header), &exported.nonce)
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        Ok(exported)
+    }
+
+    /// Write a client state exported with [`Client::export_state`] to the
+    /// storage of this client.
+    ///
+    /// Groups and key packages are written to the [`GroupStateStorage`] and
+    /// [`KeyPackageStorage`] of this client, replacing stored values with the
+    /// same id. Pre-shared keys are returned for the application to insert
+    /// into its [`PreSharedKeyStorage`].
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn import_state(
+        &self,
+        exported: &ExportedClientState,
+        export_key: &[u8],
+    ) -> Result<ClientStateImport, MlsError> {
+        if exported.version != CLIENT_STATE_VERSION {
+            return Err(MlsError::UnsupportedClientStateVersion(exported.version));
+        }
+
+        let cs = self.client_state_cipher_suite_provider(exported.cipher_suite)?;
+        let key = derive_export_key(&cs, export_key, &exported.salt).await?;
+
+        let contents = cs
+            .aead_open(
+                &key,
+                &exported.ciphertext,
+                Some(&exported.header()?),
+                &exported.nonce,
+            )
+            .await
+            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+
+        let contents = ClientStateContents::mls_decode(&mut &**contents)?;
+
+        let mut group_storage = self.config.group_state_storage();
+        let mut groups = Vec::with_capacity(contents.groups.len());
+
+        for group in contents.groups {
+            let state = GroupState {
+                id: group.id.clone(),
+                data: group.state,
+            };
+
+            let epochs = group
+                .epochs
+                .into_iter()
+                .map(|epoch| EpochRecord::new(epoch.id, epoch.data))
+                .collect();
+
+            group_storage
+                .write(state, epochs, Vec::new())
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            groups.push(group.id);
+        }
+
+        let mut key_package_repo = self.config.key_package_repo();
+        let mut key_packages = Vec::with_capacity(contents.key_packages.len());
+
+        for kp in contents.key_packages {
+            key_package_repo
+                .insert(kp.id.clone(), kp.data)
+                .await
+                .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+            key_packages.push(kp.id);
+        }
+
+        let psks = contents
+            .psks
+            .into_iter()
+            .map(|psk| (psk.id, psk.psk))
+            .collect();
+
+        Ok(ClientStateImport {
+            groups,
+            key_packages,
+            psks,
+        })
+    }
+
+    fn client_state_cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Result<<C::CryptoProvider as CryptoProvider>::CipherSuiteProvider, MlsError> {
+        self.config
+            .crypto_provider()
+            .cipher_suite_provider(cipher_suite)
+            .ok_or(MlsError::UnsupportedCipherSuite(cipher_suite))
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn stored_contents(&self) -> Result<ClientStateContents, MlsError> {
+        let group_storage = self.config.group_state_storage();
+
+        let group_ids = group_storage
+            .group_ids()
+            .await
+            .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?
+            .ok_or(MlsError::GroupStorageNotEnumerable)?;
+
+        let mut groups = Vec::with_capacity(group_ids.len());
+
+        for id in group_ids {
+            let state = group_storage
+                .state(&id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            let state = match state {
+                Some(state) => state,
+                None => continue,
+            };
+
+            let mut epochs = Vec::new();
+
+            let mut epoch_id = group_storage
+                .max_epoch_id(&id)
+                .await
+                .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+            // Prior epochs are stored contiguously up to the max epoch id
+            while let Some(current) = epoch_id {
+                let data = group_storage
+                    .epoch(&id, current)
+                    .await
+                    .map_err(|e| MlsError::GroupStorageError(e.into_any_error()))?;
+
+                let data = match data {
+                    Some(data) => data,
+                    None => break,
+                };
+
+                epochs.push(ExportedEpoch { id: current, data });
+                epoch_id = current.checked_sub(1);
+            }
+
+            epochs.reverse();
+
+            groups.push(ExportedGroup { id, state, epochs });
+        }
+
+        let key_package_repo = self.config.key_package_repo();
+
+        let key_package_ids = key_package_repo
+            .key_package_ids()
+            .await
+            .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?
+            .ok_or(MlsError::KeyPackageStorageNotEnumerable)?;
+
+        let mut key_packages = Vec::with_capacity(key_package_ids.len());
+
+        for id in key_package_ids {
+            let data = key_package_repo
+                .get(&id)
+                .await
+                .map_err(|e| MlsError::KeyPackageRepoError(e.into_any_error()))?;
+
+            if let Some(data) = data {
+                key_packages.push(ExportedKeyPackage { id, data });
+            }
+        }
+
+        let psk_store = self.config.secret_store();
+
+        let psk_ids = psk_store
+            .psk_ids()
+            .await
+            .map_err(|e| MlsError::PskStoreError(e.into_any_error()))?
+            .ok_or(MlsError::PskStorageNotEnumerable)?;
+
+        let mut psks = Vec::with_capacity(psk_ids.len());
+
+        for id in psk_ids {
+            let psk = psk_store
+                .get(&id)
+                .await
+                .map_err(|e| MlsError::PskStoreError(e.into_any_error()))?;
+
+            if let Some(psk) = psk {
+                psks.push(ExportedPsk { id, psk });
+            }
+        }
+
+        Ok(ClientStateContents {
+            groups,
+            key_packages,
+            psks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use assert_matches::assert_matches;
+    use mls_rs_core::psk::{ExternalPskId, PreSharedKey};
+
+    use crate::{
+        client::{
+            test_utils::{
+                test_client_with_key_pkg, TestClientBuilder, TEST_CIPHER_SUITE,
+                TEST_PROTOCOL_VERSION,
+            },
+            MlsError,
+        },
+        psk::AlwaysFoundPskStorage,
+        ExtensionList,
+    };
+
+    use super::ExportedClientState;
+
+    const EXPORT_KEY: &[u8] = &[42; 32];
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn exported_state_is_imported_on_another_device() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let mut group = alice.create_group(ExtensionList::new()).await.unwrap();
+        group.commit(vec![]).await.unwrap();
+        group.apply_pending_commit().await.unwrap();
+        group.write_to_storage().await.unwrap();
+
+        let psk_id = ExternalPskId::new(vec![1, 2, 3]);
+        let psk = PreSharedKey::new(vec![4; 32]);
+        alice.secret_store().insert(psk_id.clone(), psk.clone());
+
+        let inventory = alice.storage_inventory().await.unwrap();
+
+        assert_eq!(inventory.groups.len(), 1);
+        assert_eq!(inventory.groups[0].group_id, group.group_id());
+        // Prior epochs are only stored with `prior_epoch`.
+        let expected_epochs = if cfg!(feature = "prior_epoch") { 1 } else { 0 };
+        assert_eq!(inventory.groups[0].epochs.len(), expected_epochs);
+        assert_eq!(inventory.key_packages.len(), 1);
+        assert_eq!(inventory.psks.len(), 1);
+        assert!(inventory.total_bytes() > 0);
+
+        let exported = alice.export_state(EXPORT_KEY).await.unwrap();
+        let exported = ExportedClientState::from_bytes(&exported.to_bytes().unwrap()).unwrap();
+
+        let new_device = TestClientBuilder::new_for_test()
+            .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
+            .await
+            .build_unchecked();
+
+        let imported = new_device
+            .import_state(&exported, EXPORT_KEY)
+            .await
+            .unwrap();
+
+        assert_eq!(imported.groups, vec![group.group_id().to_vec()]);
+        assert_eq!(imported.key_packages.len(), 1);
+        assert_eq!(imported.psks, vec![(psk_id.clone(), psk.clone())]);
+
+        new_device.secret_store().insert(psk_id, psk);
+
+        let mut new_inventory = new_device.storage_inventory().await.unwrap();
+
+        for (new_group, group) in new_inventory.groups.iter_mut().zip(&inventory.groups) {
+            new_group.updated_at = group.updated_at;
+
+            for (new_epoch, epoch) in new_group.epochs.iter_mut().zip(&group.epochs) {
+                new_epoch.updated_at = epoch.updated_at;
+            }
+        }
+
+        assert_eq!(new_inventory, inventory);
+
+        let loaded = new_device.load_group(group.group_id()).await.unwrap();
+        assert_eq!(loaded.current_epoch(), group.current_epoch());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn exported_state_requires_export_key() {
+        let (alice, _) =
+            test_client_with_key_pkg(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, "alice").await;
+
+        let exported = alice.export_state(EXPORT_KEY).await.unwrap();
+        let res = alice.import_state(&exported, &[0; 32]).await;
+
+        assert_matches!(res, Err(MlsError::CryptoProviderError(_)));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn export_fails_if_storage_can_not_be_enumerated() {
+        let alice = TestClientBuilder::new_for_test()
+            .with_random_signing_identity("alice", TEST_CIPHER_SUITE)
+            .await
+            .psk_store(AlwaysFoundPskStorage)
+            .build_unchecked();
+
+        let res = alice.export_state(EXPORT_KEY).await;
+        assert_matches!(res, Err(MlsError::PskStorageNotEnumerable));
+
+        let res = alice.storage_inventory().await;
+        assert_matches!(res, Err(MlsError::PskStorageNotEnumerable));
+    }
+}
@@ -2,7 +2,7 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
 
 #[cfg(target_has_atomic = "ptr")]
 use alloc::sync::Arc;
@@ -14,7 +14,10 @@ use core::{
     convert::Infallible,
     fmt::{self, Debug},
 };
-use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+use mls_rs_core::{
+    group::{EpochRecord, GroupState, GroupStateStorage},
+    time::MlsTime,
+};
 #[cfg(not(target_has_atomic = "ptr"))]
 use portable_atomic_util::Arc;
 
@@ -24,7 +27,7 @@ use crate::client::MlsError;
 use std::collections::{hash_map::Entry, HashMap};
 
 #[cfg(not(feature = "std"))]
-use alloc::collections::{btree_map::Entry, BTreeMap};
+use alloc::collections::btree_map::Entry;
 
 #[cfg(feature = "std")]
 use std::sync::Mutex;
@@ -38,6 +41,8 @@ pub(crate) const DEFAULT_EPOCH_RETENTION_LIMIT: usize = 3;
 pub(crate) struct InMemoryGroupData {
     pub(crate) state_data: Vec<u8>,
     pub(crate) epoch_data: VecDeque<EpochRecord>,
+    pub(crate) updated_at: Option<MlsTime>,
+    pub(crate) epoch_updated_at: BTreeMap<u64, MlsTime>,
 }
 
 impl Debug for InMemoryGroupData {
@@ -48,6 +53,8 @@ impl Debug for InMemoryGroupData {
                 &mls_rs_core::debug::pretty_bytes(&self.state_data),
             )
             .field("epoch_data", &self.epoch_data)
+            .field("updated_at", &self.updated_at)
+            .field("epoch_updated_at", &self.epoch_updated_at)
             .finish()
     }
 }
@@ -57,6 +64,8 @@ impl InMemoryGroupData {
         InMemoryGroupData {
             state_data,
             epoch_data: Default::default(),
+            updated_at: now(),
+            epoch_updated_at: Default::default(),
         }
     }
 
@@ -77,6 +86,10 @@ impl InMemoryGroupData {
     }
 
     pub fn insert_epoch(&mut self, epoch: EpochRecord) {
+        if let Some(time) = now() {
+            self.epoch_updated_at.insert(epoch.id, time);
+        }
+
         self.epoch_data.push_back(epoch)
     }
 
@@ -84,8 +97,14 @@ impl InMemoryGroupData {
     // is not in the store, then it can no longer be accessed by future
     // get_epoch calls and is no longer relevant.
     pub fn update_epoch(&mut self, epoch: EpochRecord) {
-        if let Some(existing_epoch) = self.get_mut_epoch(epoch.id) {
-            *existing_epoch = epoch
+        let epoch_id = epoch.id;
+
+        if let Some(existing_epoch) = self.get_mut_epoch(epoch_id) {
+            *existing_epoch = epoch;
+
+            if let Some(time) = now() {
+                self.epoch_updated_at.insert(epoch_id, time);
+            }
         }
     }
 
@@ -93,7 +112,36 @@ impl InMemoryGroupData {
         while self.epoch_data.len() > max_epoch_retention {
             self.epoch_data.pop_front();
         }
+
+        self.forget_deleted_epoch_times();
     }
+
+    pub fn delete_epochs_under(&mut self, epoch_id: u64) -> usize {
+        let mut deleted = 0;
+
+        while let Some(epoch) = self.epoch_data.front().filter(|e| e.id < epoch_id) {
+            deleted += epoch.data.len();
+            self.epoch_data.pop_front();
+        }
+
+        self.epoch_data.shrink_to_fit();
+        self.forget_deleted_epoch_times();
+
+        deleted
+    }
+
+    fn forget_deleted_epoch_times(&mut self) {
+        let first = self.epoch_data.front().map_or(u64::MAX, |e| e.id);
+        self.epoch_updated_at.retain(|id, _| *id >= first);
+    }
+}
+
+fn now() -> Option<MlsTime> {
+    #[cfg(feature = "std")]
+    return Some(MlsTime::now());
+
+    #[cfg(not(feature = "std"))]
+    return None;
 }
 
 #[derive(Clone)]
@@ -201,6 +249,25 @@ impl GroupStateStorage for InMemoryGroupStateStorage {
             .and_then(|data| data.get_epoch(epoch_id).map(|ep| ep.data.clone())))
     }
 
+    async fn group_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        Ok(Some(self.stored_groups()))
+    }
+
+    async fn state_updated_at(&self, group_id: &[u8]) -> Result<Option<MlsTime>, Self::Error> {
+        Ok(self.lock().get(group_id).and_then(|data| data.updated_at))
+    }
+
+    async fn epoch_updated_at(
+        &self,
+        group_id: &[u8],
+        epoch_id: u64,
+    ) -> Result<Option<MlsTime>, Self::Error> {
+        Ok(self
+            .lock()
+            .get(group_id)
+            .and_then(|data| data.epoch_updated_at.get(&epoch_id).copied()))
+    }
+
     async fn write(
         &mut self,
         state: GroupState,
@@ -213,6 +280,7 @@ impl GroupStateStorage for InMemoryGroupStateStorage {
             Entry::Occupied(entry) => {
                 let data = entry.into_mut();
                 data.state_data = state.data;
+                data.updated_at = now();
                 data
             }
             Entry::Vacant(entry) => entry.insert(InMemoryGroupData::new(state.data)),
@@ -230,6 +298,21 @@ impl GroupStateStorage for InMemoryGroupStateStorage {
 
         Ok(())
     }
+
+    async fn compact(
+        &mut self,
+        group_id: &[u8],
+        delete_epoch_under: Option<u64>,
+    ) -> Result<u64, Self::Error> {
+        let mut group_map = self.lock();
+
+        let reclaimed = group_map
+            .get_mut(group_id)
+            .zip(delete_epoch_under)
+            .map_or(0, |(data, epoch_id)| data.delete_epochs_under(epoch_id));
+
+        Ok(reclaimed as u64)
+    }
 }
 
 #[cfg(all(test, feature = "prior_epoch"))]
@@ -351,4 +434,30 @@ mod tests {
         let expected = epoch_inserts.pop().unwrap();
         assert_eq!(stored.epoch_data[0], expected);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn compact_deletes_epochs_under_limit() {
+        let mut storage = test_storage(4).unwrap();
+
+        let epoch_inserts = vec![test_epoch(0), test_epoch(1), test_epoch(2)];
+
+        storage
+            .write(test_snapshot(3), epoch_inserts, Vec::new())
+            .await
+            .unwrap();
+
+        let reclaimed = storage.compact(TEST_GROUP, None).await.unwrap();
+        assert_eq!(reclaimed, 0);
+        assert_eq!(storage.test_data().epoch_data.len(), 3);
+
+        let reclaimed = storage.compact(TEST_GROUP, Some(2)).await.unwrap();
+        assert_eq!(
+            reclaimed,
+            (test_epoch(0).data.len() + test_epoch(1).data.len()) as u64
+        );
+
+        let stored = storage.test_data();
+        assert_eq!(stored.epoch_data.len(), 1);
+        assert_eq!(stored.epoch_data[0], test_epoch(2));
+    }
 }
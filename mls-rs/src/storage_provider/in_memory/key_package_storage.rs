@@ -88,6 +88,16 @@ impl InMemoryKeyPackageStorage {
             .collect()
     }
 
+    /// Get the ids of all key packages expiring before `time`, in seconds
+    /// since the Unix epoch.
+    pub fn expired_before(&self, time: u64) -> Vec<Vec<u8>> {
+        self.lock()
+            .iter()
+            .filter(|(_, pkg)| pkg.expiration < time)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     #[cfg(feature = "std")]
     fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Vec<u8>, KeyPackageData>> {
         self.inner.lock().unwrap()
@@ -114,7 +124,23 @@ impl KeyPackageStorage for InMemoryKeyPackageStorage {
         Ok(())
     }
 
+    async fn insert_batch(
+        &mut self,
+        key_packages: Vec<(Vec<u8>, KeyPackageData)>,
+    ) -> Result<(), Self::Error> {
+        self.lock().extend(key_packages);
+        Ok(())
+    }
+
     async fn get(&self, id: &[u8]) -> Result<Option<KeyPackageData>, Self::Error> {
         Ok(self.get(id))
     }
+
+    async fn expired_before(&self, time: u64) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self.expired_before(time))
+    }
+
+    async fn key_package_ids(&self) -> Result<Option<Vec<Vec<u8>>>, Self::Error> {
+        Ok(Some(self.lock().keys().cloned().collect()))
+    }
 }
@@ -8,6 +8,7 @@ use alloc::sync::Arc;
 #[cfg(not(target_has_atomic = "ptr"))]
 use portable_atomic_util::Arc;
 
+use alloc::vec::Vec;
 use core::convert::Infallible;
 
 #[cfg(feature = "std")]
@@ -70,6 +71,17 @@ impl InMemoryPreSharedKeyStorage {
 
         lock.remove(id);
     }
+
+    /// Get the ids of all stored pre-shared keys.
+    pub fn psk_ids(&self) -> Vec<ExternalPskId> {
+        #[cfg(feature = "std")]
+        let lock = self.inner.lock().unwrap();
+
+        #[cfg(not(feature = "std"))]
+        let lock = self.inner.lock();
+
+        lock.keys().cloned().collect()
+    }
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -80,4 +92,8 @@ impl PreSharedKeyStorage for InMemoryPreSharedKeyStorage {
     async fn get(&self, id: &ExternalPskId) -> Result<Option<PreSharedKey>, Self::Error> {
         Ok(self.get(id))
     }
+
+    async fn psk_ids(&self) -> Result<Option<Vec<ExternalPskId>>, Self::Error> {
+        Ok(Some(self.psk_ids()))
+    }
 }
@@ -0,0 +1,623 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Deterministic groups for golden-file tests.
+//!
+//! [`SeededCryptoProvider`] wraps a crypto provider and draws the randomness
+//! requested by mls-rs from a seed instead: group ids, init and path secrets,
+//! reuse guards, HPKE key pairs, which are derived with
+//! [`kem_derive`](CipherSuiteProvider::kem_derive), the ephemeral keys of
+//! [`hpke_seal`](CipherSuiteProvider::hpke_seal), which is routed through
+//! [`hpke_seal_derand`](CipherSuiteProvider::hpke_seal_derand), and signature
+//! key pairs. All other operations are delegated to the wrapped provider.
+//! Signatures are therefore only reproducible for deterministic signature
+//! schemes, such as the EdDSA schemes of the `CURVE25519` and `CURVE448`
+//! cipher suites.
+//!
+//! Commits and welcome messages, including the encrypted path secrets and
+//! group secrets, only depend on the seed and the operations applied. This
+//! requires the wrapped provider to implement `hpke_seal_derand`, which is
+//! only available with the `test_util` feature of `mls-rs-core`. The providers
+//! of this repository implement it with their own `test_util` feature. HPKE
+//! encryption fails with [`SeededProviderError::DerandomizationUnsupported`]
+//! if the wrapped provider does not implement it. Contexts created with
+//! [`hpke_setup_s`](CipherSuiteProvider::hpke_setup_s), used by external
+//! commits, still use the randomness of the wrapped provider.
+//!
+//! [`deterministic_group`] creates a group with a fixed group id whose members
+//! use seeded providers and fixed key package lifetimes. Each member has its
+//! own generator, so the values drawn by a member only depend on the
+//! operations of that member.
+//!
+//! The generator is [`StdRng`] seeded from a hash of the seed. Values drawn
+//! from a seed only stay the same for a given version of `rand`. Never use
+//! these providers outside tests.
+
+#[cfg(mls_build_async)]
+use alloc::boxed::Box;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use mls_rs_core::{
+    crypto::{
+        CipherSuite, CipherSuiteProvider, CryptoProvider, HpkeCiphertext, HpkePublicKey,
+        HpkeSecretKey, SignaturePublicKey, SignatureSecretKey,
+    },
+    error::IntoAnyError,
+    identity::SigningIdentity,
+    protocol_version::ProtocolVersion,
+};
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use spin::Mutex;
+use zeroize::Zeroizing;
+
+use crate::{
+    client_builder::{BaseConfig, WithCryptoProvider, WithIdentityProvider},
+    identity::basic::BasicIdentityProvider,
+    test_utils::get_test_basic_credential,
+    Client, Group,
+};
+
+/// Configuration of the clients of a [`deterministic_group`].
+pub type DeterministicConfig<P> = WithIdentityProvider<
+    BasicIdentityProvider,
+    WithCryptoProvider<SeededCryptoProvider<P>, BaseConfig>,
+>;
+
+pub type DeterministicMember<P> = (
+    Client<DeterministicConfig<P>>,
+    Group<DeterministicConfig<P>>,
+);
+
+/// Crypto provider drawing random values from a seed. Clones share the state
+/// of the generator.
+#[derive(Clone, Debug)]
+pub struct SeededCryptoProvider<P> {
+    inner: P,
+    rng: Arc<Mutex<StdRng>>,
+}
+
+impl<P> SeededCryptoProvider<P> {
+    pub fn new(inner: P, seed: &[u8]) -> Self {
+        Self {
+            inner,
+            rng: Arc::new(Mutex::new(seeded_rng(seed))),
+        }
+    }
+}
+
+impl<P: CryptoProvider> CryptoProvider for SeededCryptoProvider<P> {
+    type CipherSuiteProvider = SeededCipherSuiteProvider<P::CipherSuiteProvider>;
+
+    fn supported_cipher_suites(&self) -> Vec<CipherSuite> {
+        self.inner.supported_cipher_suites()
+    }
+
+    fn cipher_suite_provider(
+        &self,
+        cipher_suite: CipherSuite,
+    ) -> Option<Self::CipherSuiteProvider> {
+        Some(SeededCipherSuiteProvider {
+            inner: self.inner.cipher_suite_provider(cipher_suite)?,
+            rng: self.rng.clone(),
+        })
+    }
+}
+
+/// Cipher suite provider of a [`SeededCryptoProvider`].
+#[derive(Clone, Debug)]
+pub struct SeededCipherSuiteProvider<C> {
+    inner: C,
+    rng: Arc<Mutex<StdRng>>,
+}
+
+impl<C> SeededCipherSuiteProvider<C> {
+    fn fill(&self, out: &mut [u8]) {
+        self.rng.lock().fill_bytes(out)
+    }
+
+    fn draw(&self, len: usize) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(alloc::vec![0; len]);
+        self.fill(&mut bytes);
+        bytes
+    }
+}
+
+/// Error of a [`SeededCipherSuiteProvider`].
+#[derive(Debug)]
+pub enum SeededProviderError<E> {
+    /// Error of the wrapped provider.
+    Provider(E),
+    /// The wrapped provider does not implement
+    /// [`hpke_seal_derand`](CipherSuiteProvider::hpke_seal_derand), so HPKE
+    /// encryption cannot be derived from the seed.
+    DerandomizationUnsupported,
+}
+
+impl<E: IntoAnyError> IntoAnyError for SeededProviderError<E> {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        match self {
+            Self::Provider(e) => e.into_dyn_error().map_err(Self::Provider),
+            Self::DerandomizationUnsupported => Err(self),
+        }
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+#[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), mls_build_async),
+    maybe_async::must_be_async
+)]
+impl<C: CipherSuiteProvider> CipherSuiteProvider for SeededCipherSuiteProvider<C> {
+    type Error = SeededProviderError<C::Error>;
+    type HpkeContextS = C::HpkeContextS;
+    type HpkeContextR = C::HpkeContextR;
+
+    fn cipher_suite(&self) -> CipherSuite {
+        self.inner.cipher_suite()
+    }
+
+    async fn hash(&self, data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hash(data)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn hash_truncated(&self, data: &[u8], len: usize) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hash_truncated(data, len)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn mac(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .mac(key, data)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    fn constant_time_eq(&self, a: &[u8], b: &[u8]) -> bool {
+        self.inner.constant_time_eq(a, b)
+    }
+
+    async fn aead_seal(
+        &self,
+        key: &[u8],
+        data: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .aead_seal(key, data, aad, nonce)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn aead_open(
+        &self,
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: Option<&[u8]>,
+        nonce: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .aead_open(key, ciphertext, aad, nonce)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    fn aead_key_size(&self) -> usize {
+        self.inner.aead_key_size()
+    }
+
+    fn aead_nonce_size(&self) -> usize {
+        self.inner.aead_nonce_size()
+    }
+
+    async fn kdf_extract(
+        &self,
+        salt: &[u8],
+        ikm: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .kdf_extract(salt, ikm)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn kdf_expand(
+        &self,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, Self::Error> {
+        self.inner
+            .kdf_expand(prk, info, len)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn kdf_expand_batch(
+        &self,
+        prk: &[u8],
+        infos: &[(&[u8], usize)],
+    ) -> Result<Vec<Zeroizing<Vec<u8>>>, Self::Error> {
+        self.inner
+            .kdf_expand_batch(prk, infos)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    fn kdf_extract_size(&self) -> usize {
+        self.inner.kdf_extract_size()
+    }
+
+    async fn hpke_seal(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+    ) -> Result<HpkeCiphertext, Self::Error> {
+        let ikm_e = self.draw(2 * self.inner.kdf_extract_size());
+
+        self.inner
+            .hpke_seal_derand(remote_key, info, aad, pt, &ikm_e)
+            .await
+            .map_err(SeededProviderError::Provider)?
+            .ok_or(SeededProviderError::DerandomizationUnsupported)
+    }
+
+    async fn hpke_seal_derand(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+        pt: &[u8],
+        ikm_e: &[u8],
+    ) -> Result<Option<HpkeCiphertext>, Self::Error> {
+        self.inner
+            .hpke_seal_derand(remote_key, info, aad, pt, ikm_e)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn hpke_seal_multi(
+        &self,
+        recipients: &[(&HpkePublicKey, &[u8])],
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<HpkeCiphertext>, Self::Error> {
+        // One seeded ephemeral key per recipient, drawn in order
+        let mut ciphertexts = Vec::with_capacity(recipients.len());
+
+        for (remote_key, pt) in recipients {
+            ciphertexts.push(self.hpke_seal(remote_key, info, aad, pt).await?);
+        }
+
+        Ok(ciphertexts)
+    }
+
+    async fn hpke_open(
+        &self,
+        ciphertext: &HpkeCiphertext,
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+        aad: Option<&[u8]>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .hpke_open(ciphertext, local_secret, local_public, info, aad)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn hpke_setup_s(
+        &self,
+        remote_key: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, Self::HpkeContextS), Self::Error> {
+        self.inner
+            .hpke_setup_s(remote_key, info)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn hpke_setup_r(
+        &self,
+        kem_output: &[u8],
+        local_secret: &HpkeSecretKey,
+        local_public: &HpkePublicKey,
+        info: &[u8],
+    ) -> Result<Self::HpkeContextR, Self::Error> {
+        self.inner
+            .hpke_setup_r(kem_output, local_secret, local_public, info)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn kem_derive(&self, ikm: &[u8]) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        self.inner
+            .kem_derive(ikm)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn kem_generate(&self) -> Result<(HpkeSecretKey, HpkePublicKey), Self::Error> {
+        // Longer than the secret key of all KEMs, as required by DeriveKeyPair
+        let ikm = self.draw(2 * self.inner.kdf_extract_size());
+        self.inner
+            .kem_derive(&ikm)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    fn kem_public_key_validate(&self, key: &HpkePublicKey) -> Result<(), Self::Error> {
+        self.inner
+            .kem_public_key_validate(key)
+            .map_err(SeededProviderError::Provider)
+    }
+
+    fn random_bytes(&self, out: &mut [u8]) -> Result<(), Self::Error> {
+        self.fill(out);
+        Ok(())
+    }
+
+    async fn signature_key_generate(
+        &self,
+    ) -> Result<(SignatureSecretKey, SignaturePublicKey), Self::Error> {
+        // Secret keys are the raw scalar of the curve. A key generated by the
+        // wrapped provider gives its length, and candidates out of range for
+        // the curve are drawn again.
+        let (template, _) = self
+            .inner
+            .signature_key_generate()
+            .await
+            .map_err(SeededProviderError::Provider)?;
+
+        loop {
+            let secret_key = SignatureSecretKey::new_slice(&self.draw(template.len()));
+
+            if let Ok(public_key) = self.inner.signature_key_derive_public(&secret_key).await {
+                return Ok((secret_key, public_key));
+            }
+        }
+    }
+
+    async fn signature_key_derive_public(
+        &self,
+        secret_key: &SignatureSecretKey,
+    ) -> Result<SignaturePublicKey, Self::Error> {
+        self.inner
+            .signature_key_derive_public(secret_key)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn sign(
+        &self,
+        secret_key: &SignatureSecretKey,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.inner
+            .sign(secret_key, data)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+
+    async fn verify(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature: &[u8],
+        data: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .verify(public_key, signature, data)
+            .await
+            .map_err(SeededProviderError::Provider)
+    }
+}
+
+/// Create a client named `name` whose randomness is derived from `seed` and
+/// `name`. Key packages of the client are valid from the Unix epoch on and
+/// never expire.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn deterministic_client<P: CryptoProvider + Clone>(
+    crypto: P,
+    cipher_suite: CipherSuite,
+    seed: &[u8],
+    name: &str,
+) -> Client<DeterministicConfig<P>> {
+    let crypto = SeededCryptoProvider::new(crypto, &[seed, b"/", name.as_bytes()].concat());
+    let cs = crypto.cipher_suite_provider(cipher_suite).unwrap();
+
+    let (secret_key, public_key) = cs.signature_key_generate().await.unwrap();
+    let credential = get_test_basic_credential(name.as_bytes().to_vec());
+
+    Client::builder()
+        .crypto_provider(crypto)
+        .identity_provider(BasicIdentityProvider::new())
+        .used_protocol_version(ProtocolVersion::MLS_10)
+        .signing_identity(
+            SigningIdentity::new(credential, public_key),
+            secret_key,
+            cipher_suite,
+        )
+        .key_package_lifetime(u64::MAX)
+        .key_package_not_before(0)
+        .build_unchecked()
+}
+
+/// Create the group `group_id` with one member per name of `members`, using
+/// [`deterministic_client`]. The first member creates the group and adds the
+/// others with a single commit, which the others join by the welcome message.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn deterministic_group<P: CryptoProvider + Clone>(
+    crypto: P,
+    cipher_suite: CipherSuite,
+    seed: &[u8],
+    group_id: &[u8],
+    members: &[&str],
+) -> Vec<DeterministicMember<P>> {
+    let mut output = Vec::with_capacity(members.len());
+    let mut joiners = Vec::with_capacity(members.len());
+    let mut key_packages = Vec::with_capacity(members.len());
+
+    for (i, name) in members.iter().enumerate() {
+        let client = deterministic_client(crypto.clone(), cipher_suite, seed, name).await;
+
+        if i == 0 {
+            let group = client
+                .create_group_with_id(group_id.to_vec(), Default::default())
+                .await
+                .unwrap();
+
+            output.push((client, group));
+        } else {
+            key_packages.push(client.generate_key_package_message().await.unwrap());
+            joiners.push(client);
+        }
+    }
+
+    if joiners.is_empty() {
+        return output;
+    }
+
+    let mut builder = output[0].1.commit_builder();
+
+    for key_package in key_packages {
+        builder = builder.add_member(key_package).unwrap();
+    }
+
+    let commit = builder.build().await.unwrap();
+    output[0].1.apply_pending_commit().await.unwrap();
+
+    let tree = output[0].1.export_tree().into_owned();
+
+    for client in joiners {
+        let (group, _) = client
+            .join_group(Some(tree.clone()), &commit.welcome_messages[0])
+            .await
+            .unwrap();
+
+        output.push((client, group));
+    }
+
+    output
+}
+
+/// Hash the seed of a [`SeededCryptoProvider`] to seed its generator.
+fn seeded_rng(seed: &[u8]) -> StdRng {
+    // FNV-1a hash of the seed
+    let state = seed.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    StdRng::seed_from_u64(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use mls_rs_codec::MlsEncode;
+    use mls_rs_core::crypto::{CipherSuite, CipherSuiteProvider, CryptoProvider};
+
+    use crate::{crypto::test_utils::TestCryptoProvider, MlsMessage};
+
+    use super::{deterministic_client, deterministic_group, SeededCryptoProvider};
+
+    const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+    struct Run {
+        key_package: MlsMessage,
+        commit: MlsMessage,
+        welcome: MlsMessage,
+        path_commit: MlsMessage,
+        epoch_authenticator: Vec<u8>,
+    }
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn run(seed: &[u8]) -> Run {
+        let crypto = TestCryptoProvider::new();
+        let mut members = deterministic_group(
+            crypto.clone(),
+            CIPHER_SUITE,
+            seed,
+            b"group",
+            &["alice", "bob"],
+        )
+        .await;
+
+        let carol = deterministic_client(crypto, CIPHER_SUITE, seed, "carol").await;
+        let key_package = carol.generate_key_package_message().await.unwrap();
+
+        let output = members[1]
+            .1
+            .commit_builder()
+            .add_member(key_package.clone())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        members[1].1.apply_pending_commit().await.unwrap();
+
+        // Empty commits have a path encrypted to the other members
+        let path_commit = members[1].1.commit(Vec::new()).await.unwrap();
+        members[1].1.apply_pending_commit().await.unwrap();
+
+        Run {
+            key_package,
+            commit: output.commit_message,
+            welcome: output.welcome_messages[0].clone(),
+            path_commit: path_commit.commit_message,
+            epoch_authenticator: members[1].1.epoch_authenticator().unwrap().to_vec(),
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn groups_are_reproducible_from_the_seed() {
+        let first = run(b"seed").await;
+        let second = run(b"seed").await;
+
+        let encoded = |message: &MlsMessage| message.mls_encode_to_vec().unwrap();
+
+        assert_eq!(encoded(&first.key_package), encoded(&second.key_package));
+        assert_eq!(encoded(&first.commit), encoded(&second.commit));
+        assert_eq!(encoded(&first.welcome), encoded(&second.welcome));
+        assert_eq!(encoded(&first.path_commit), encoded(&second.path_commit));
+        assert_eq!(first.epoch_authenticator, second.epoch_authenticator);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn groups_differ_between_seeds() {
+        let first = run(b"seed").await;
+        let second = run(b"other seed").await;
+
+        assert_ne!(
+            first.key_package.mls_encode_to_vec().unwrap(),
+            second.key_package.mls_encode_to_vec().unwrap()
+        );
+
+        assert_ne!(first.epoch_authenticator, second.epoch_authenticator);
+    }
+
+    #[test]
+    fn random_bytes_are_stable() {
+        let crypto = SeededCryptoProvider::new(TestCryptoProvider::new(), b"seed");
+        let cs = crypto.cipher_suite_provider(CIPHER_SUITE).unwrap();
+
+        // Pins the generator, which golden files depend on
+        assert_eq!(
+            cs.random_bytes_vec(12).unwrap(),
+            hex::decode("f0ff1df08b1d1e18c94f2eaf").unwrap()
+        );
+    }
+}
@@ -93,7 +93,7 @@ fn make_client(cipher_suite: CipherSuite, name: &str) -> Client<TestClientConfig
         .identity_provider(BasicIdentityProvider)
         .crypto_provider(MlsCryptoProvider::default())
         .signing_identity(signing_identity, secret, cipher_suite)
-        .build()
+        .build_unchecked()
 }
 
 fn make_identity(cipher_suite: CipherSuite, name: &str) -> (SignatureSecretKey, SigningIdentity) {
@@ -8,6 +8,8 @@ pub mod benchmarks;
 #[cfg(all(feature = "fuzz_util", not(mls_build_async)))]
 pub mod fuzz_tests;
 
+pub mod deterministic;
+
 use mls_rs_core::{
     crypto::{CipherSuite, CipherSuiteProvider, CryptoProvider},
     identity::{BasicCredential, Credential, SigningIdentity},
@@ -96,7 +98,7 @@ pub async fn generate_basic_client<C: CryptoProvider + Clone>(
             .key_package_not_before(lifetime.not_before);
     }
 
-    builder.build()
+    builder.build_unchecked()
 }
 
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
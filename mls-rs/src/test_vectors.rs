@@ -0,0 +1,271 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Conformance checks of a [`CryptoProvider`] against the MLS test vectors
+//! published at <https://github.com/mlswg/mls-implementations>.
+//!
+//! Vendors shipping a custom provider can run these from their own test
+//! suite. The test vectors are not shipped with this crate and are passed as
+//! the contents of the JSON files. Each function verifies every test case for
+//! a cipher suite supported by the provider, returns an error on the first
+//! mismatch and otherwise returns the number of verified test cases. Test
+//! cases for cipher suites that are not supported are skipped.
+//!
+//! [`verify_cipher_suite_provider`] additionally checks the behavior of a
+//! single [`CipherSuiteProvider`](crate::CipherSuiteProvider) on inputs
+//! that are not covered by the test vectors, such as invalid ciphertexts
+//! and signatures.
+
+use alloc::{string::String, vec::Vec};
+use mls_rs_core::error::{AnyError, IntoAnyError};
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+
+mod cipher_suite_provider;
+
+pub use cipher_suite_provider::{verify_cipher_suite_provider, verify_cipher_suite_providers};
+
+use crate::{group::key_schedule::test_vectors::KeyScheduleTestCase, CryptoProvider};
+
+#[cfg(feature = "private_message")]
+use crate::group::{
+    ciphertext_processor::test_vectors::MessageProtectionTestCase,
+    secret_tree::test_vectors::SecretTreeTestCase,
+};
+
+/// Error returned by the conformance checks.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[non_exhaustive]
+pub enum TestVectorError {
+    #[cfg_attr(feature = "std", error("invalid test vectors: {0}"))]
+    InvalidTestVectors(serde_json::Error),
+    #[cfg_attr(feature = "std", error("check failed: {0}"))]
+    CheckFailed(String),
+    #[cfg_attr(feature = "std", error("{operation}: {source}"))]
+    OperationFailed { operation: String, source: AnyError },
+}
+
+impl IntoAnyError for TestVectorError {
+    #[cfg(feature = "std")]
+    fn into_dyn_error(self) -> Result<Box<dyn std::error::Error + Send + Sync>, Self> {
+        Ok(self.into())
+    }
+}
+
+pub(crate) trait CheckResult<T> {
+    fn check(self, operation: &str) -> Result<T, TestVectorError>;
+}
+
+impl<T, E: IntoAnyError> CheckResult<T> for Result<T, E> {
+    fn check(self, operation: &str) -> Result<T, TestVectorError> {
+        self.map_err(|e| TestVectorError::OperationFailed {
+            operation: operation.into(),
+            source: e.into_any_error(),
+        })
+    }
+}
+
+macro_rules! fail {
+    ($($msg:tt)+) => {
+        return Err($crate::test_vectors::TestVectorError::CheckFailed(alloc::format!($($msg)+)))
+    };
+}
+
+macro_rules! ensure {
+    ($cond:expr, $($msg:tt)+) => {
+        if !$cond {
+            $crate::test_vectors::fail!($($msg)+);
+        }
+    };
+}
+
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr, $($msg:tt)+) => {
+        $crate::test_vectors::ensure!($left == $right, $($msg)+)
+    };
+}
+
+macro_rules! ensure_ne {
+    ($left:expr, $right:expr, $($msg:tt)+) => {
+        $crate::test_vectors::ensure!($left != $right, $($msg)+)
+    };
+}
+
+pub(crate) use {ensure, ensure_eq, ensure_ne, fail};
+
+/// Verify the key schedule test vectors, covering the derivation of all
+/// epoch secrets, external keys and exported secrets.
+///
+/// `test_vectors` is the content of `key-schedule.json`.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_key_schedule<C: CryptoProvider>(
+    crypto: &C,
+    test_vectors: &[u8],
+) -> Result<usize, TestVectorError> {
+    let test_cases: Vec<KeyScheduleTestCase> = parse(test_vectors)?;
+    let mut verified = 0;
+
+    for test_case in test_cases {
+        let Some(cs) = crypto.cipher_suite_provider(test_case.cipher_suite.into()) else {
+            continue;
+        };
+
+        test_case.verify(&cs).await?;
+        verified += 1;
+    }
+
+    Ok(verified)
+}
+
+/// Verify the secret tree test vectors, covering the sender data key and
+/// the handshake and application ratchets of each leaf.
+///
+/// `test_vectors` is the content of `secret-tree.json`.
+#[cfg(feature = "private_message")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_secret_tree<C: CryptoProvider>(
+    crypto: &C,
+    test_vectors: &[u8],
+) -> Result<usize, TestVectorError> {
+    let test_cases: Vec<SecretTreeTestCase> = parse(test_vectors)?;
+    let mut verified = 0;
+
+    for test_case in test_cases {
+        let Some(cs) = crypto.cipher_suite_provider(test_case.cipher_suite.into()) else {
+            continue;
+        };
+
+        test_case.verify(&cs).await?;
+        verified += 1;
+    }
+
+    Ok(verified)
+}
+
+/// Verify the message protection test vectors, covering the decryption and
+/// encryption of private messages as well as the membership tags and
+/// signatures of public messages.
+///
+/// `test_vectors` is the content of `message-protection.json`.
+#[cfg(feature = "private_message")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_message_protection<C: CryptoProvider>(
+    crypto: &C,
+    test_vectors: &[u8],
+) -> Result<usize, TestVectorError> {
+    let test_cases: Vec<MessageProtectionTestCase> = parse(test_vectors)?;
+    let mut verified = 0;
+
+    for test_case in test_cases {
+        let Some(cs) = crypto.cipher_suite_provider(test_case.cipher_suite.into()) else {
+            continue;
+        };
+
+        test_case.verify(&cs).await?;
+        verified += 1;
+    }
+
+    Ok(verified)
+}
+
+/// Run all checks of this module and return the total number of verified
+/// test cases and cipher suites.
+#[cfg(feature = "private_message")]
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_all<C: CryptoProvider>(
+    crypto: &C,
+    key_schedule: &[u8],
+    secret_tree: &[u8],
+    message_protection: &[u8],
+) -> Result<usize, TestVectorError> {
+    Ok(verify_key_schedule(crypto, key_schedule).await?
+        + verify_secret_tree(crypto, secret_tree).await?
+        + verify_message_protection(crypto, message_protection).await?
+        + verify_cipher_suite_providers(crypto).await?)
+}
+
+fn parse<T: serde::de::DeserializeOwned>(test_vectors: &[u8]) -> Result<T, TestVectorError> {
+    serde_json::from_slice(test_vectors).map_err(TestVectorError::InvalidTestVectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::test_utils::TestCryptoProvider;
+    use mls_rs_core::crypto::CryptoProvider;
+
+    use super::verify_cipher_suite_providers;
+
+    #[cfg(feature = "private_message")]
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "private_message")]
+    use assert_matches::assert_matches;
+
+    #[cfg(feature = "private_message")]
+    use super::{
+        verify_key_schedule, verify_message_protection, verify_secret_tree, TestVectorError,
+    };
+
+    #[cfg(feature = "private_message")]
+    const KEY_SCHEDULE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_data/key_schedule_test_vector.json"
+    ));
+
+    #[cfg(feature = "private_message")]
+    const SECRET_TREE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_data/secret_tree_interop.json"
+    ));
+
+    #[cfg(feature = "private_message")]
+    const MESSAGE_PROTECTION: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_data/framing.json"
+    ));
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_crypto_provider_passes_test_vectors() {
+        let crypto = TestCryptoProvider::new();
+
+        let key_schedule = verify_key_schedule(&crypto, KEY_SCHEDULE).await.unwrap();
+        assert!(key_schedule > 0);
+
+        let secret_tree = verify_secret_tree(&crypto, SECRET_TREE).await.unwrap();
+        assert!(secret_tree > 0);
+
+        let message_protection = verify_message_protection(&crypto, MESSAGE_PROTECTION)
+            .await
+            .unwrap();
+
+        assert!(message_protection > 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_crypto_provider_passes_cipher_suite_checks() {
+        let crypto = TestCryptoProvider::new();
+        let verified = verify_cipher_suite_providers(&crypto).await.unwrap();
+
+        assert_eq!(verified, crypto.supported_cipher_suites().len());
+    }
+
+    #[cfg(feature = "private_message")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_mismatch_is_reported() {
+        let crypto = TestCryptoProvider::new();
+
+        let mut test_cases: Vec<serde_json::Value> = serde_json::from_slice(SECRET_TREE).unwrap();
+        test_cases[0]["leaves"][0][0]["application_key"] = "00".into();
+        let test_vectors = serde_json::to_vec(&test_cases).unwrap();
+
+        let res = verify_secret_tree(&crypto, &test_vectors).await;
+        assert_matches!(res, Err(TestVectorError::CheckFailed(_)));
+
+        let res = verify_secret_tree(&crypto, b"[").await;
+        assert_matches!(res, Err(TestVectorError::InvalidTestVectors(_)));
+    }
+}
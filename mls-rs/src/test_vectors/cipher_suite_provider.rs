@@ -0,0 +1,377 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec;
+use alloc::vec::Vec;
+use mls_rs_codec::MlsEncode;
+use mls_rs_core::crypto::{CipherSuiteProvider, HpkeContextR, HpkeContextS, HpkePublicKey};
+
+use crate::{
+    crypto::validate_aead_sizes,
+    signer::SignContent,
+    tree_kem::hpke_encryption::{decrypt_with_label, encrypt_with_label},
+    CryptoProvider,
+};
+
+use super::{ensure, ensure_eq, ensure_ne, fail, CheckResult, TestVectorError};
+
+/// Verify that `cs` meets the expectations of this crate on a
+/// [`CipherSuiteProvider`], beyond the computations covered by the test
+/// vectors.
+///
+/// The checks cover the sizes reported by the provider, KDF outputs of
+/// edge lengths, AEAD and HPKE round-trips including the handling of
+/// additional authenticated data, HPKE and signatures with the labels used
+/// by MLS, and the errors returned for invalid inputs. Returns an error on
+/// the first failed check.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_cipher_suite_provider<P: CipherSuiteProvider>(
+    cs: &P,
+) -> Result<(), TestVectorError> {
+    verify_sizes(cs).await?;
+    verify_kdf(cs).await?;
+    verify_aead(cs).await?;
+    verify_hpke(cs).await?;
+    verify_signatures(cs).await?;
+    verify_random(cs)
+}
+
+/// Run [`verify_cipher_suite_provider`] for every cipher suite supported by
+/// `crypto` and return the number of verified cipher suites.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub async fn verify_cipher_suite_providers<C: CryptoProvider>(
+    crypto: &C,
+) -> Result<usize, TestVectorError> {
+    let mut verified = 0;
+
+    for cipher_suite in crypto.supported_cipher_suites() {
+        let Some(cs) = crypto.cipher_suite_provider(cipher_suite) else {
+            fail!("{cipher_suite:?} is supported but has no provider");
+        };
+
+        ensure_eq!(cs.cipher_suite(), cipher_suite, "wrong cipher suite");
+
+        verify_cipher_suite_provider(&cs).await?;
+        verified += 1;
+    }
+
+    Ok(verified)
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_sizes<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    validate_aead_sizes(cs).check("AEAD key and nonce sizes are unsupported")?;
+
+    let hash_size = cs.kdf_extract_size();
+
+    let hash = cs.hash(b"").await.check("hash failed")?;
+    ensure_eq!(hash.len(), hash_size, "hash size differs from extract size");
+
+    let other_hash = cs.hash(b"mls").await.check("hash failed")?;
+    ensure_ne!(hash, other_hash, "hash ignores its input");
+
+    let truncated = cs.hash_truncated(b"mls", 16).await.check("hash failed")?;
+    ensure_eq!(
+        truncated,
+        other_hash[..16],
+        "truncated hash is not a prefix"
+    );
+
+    let mac = cs.mac(&hash, b"mls").await.check("mac failed")?;
+    ensure_eq!(mac.len(), hash_size, "MAC size differs from extract size");
+
+    let other_mac = cs.mac(&other_hash, b"mls").await.check("mac failed")?;
+    ensure_ne!(mac, other_mac, "MAC ignores its key");
+
+    Ok(())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_kdf<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    let hash_size = cs.kdf_extract_size();
+
+    // MLS extracts with empty salts and zero filled input key material
+    let zeros = vec![0u8; hash_size];
+
+    let prk = cs.kdf_extract(&[], &zeros).await.check("extract failed")?;
+    ensure_eq!(prk.len(), hash_size, "wrong extract size");
+
+    let prk = cs
+        .kdf_extract(&zeros, &zeros)
+        .await
+        .check("extract failed")?;
+    ensure_eq!(prk.len(), hash_size, "wrong extract size");
+
+    let max_len = 255 * hash_size;
+
+    let longest = cs
+        .kdf_expand(&prk, b"info", max_len)
+        .await
+        .check("expand to the maximum length failed")?;
+
+    ensure_eq!(longest.len(), max_len, "wrong expand size");
+
+    for len in [1, hash_size - 1, hash_size, hash_size + 1] {
+        let output = cs
+            .kdf_expand(&prk, b"info", len)
+            .await
+            .check("expand failed")?;
+
+        ensure_eq!(
+            *output,
+            longest[..len],
+            "expand output of length {len} is not a prefix of longer outputs"
+        );
+    }
+
+    let res = cs.kdf_expand(&prk, b"info", max_len + 1).await;
+    ensure!(res.is_err(), "expand beyond the maximum length succeeded");
+
+    let other_info = cs
+        .kdf_expand(&prk, b"other", hash_size)
+        .await
+        .check("expand failed")?;
+    ensure_ne!(*other_info, longest[..hash_size], "expand ignores info");
+
+    let infos: [(&[u8], usize); 3] = [(b"info", hash_size), (b"other", hash_size), (b"", 1)];
+    let batch = cs
+        .kdf_expand_batch(&prk, &infos)
+        .await
+        .check("batch failed")?;
+    ensure_eq!(batch.len(), infos.len(), "wrong number of batch outputs");
+
+    for ((info, len), output) in infos.iter().zip(batch) {
+        let expected = cs
+            .kdf_expand(&prk, info, *len)
+            .await
+            .check("expand failed")?;
+        ensure_eq!(output, expected, "batch expand differs from expand");
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_aead<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    let key = vec![1u8; cs.aead_key_size()];
+    let nonce = vec![2u8; cs.aead_nonce_size()];
+
+    // Plaintexts of private messages are never empty
+    for (pt, aad) in [
+        (&b"m"[..], None),
+        (b"m", Some(&b""[..])),
+        (b"mls", None),
+        (b"mls", Some(b"aad")),
+    ] {
+        let ct = cs
+            .aead_seal(&key, pt, aad, &nonce)
+            .await
+            .check("seal failed")?;
+        ensure!(ct.len() > pt.len(), "ciphertext is not authenticated");
+
+        let opened = cs.aead_open(&key, &ct, aad, &nonce).await;
+        ensure_eq!(
+            opened.check("open failed")?.as_slice(),
+            pt,
+            "wrong plaintext"
+        );
+
+        let other_aad = match aad {
+            Some(aad) if !aad.is_empty() => None,
+            _ => Some(&b"other"[..]),
+        };
+
+        let res = cs.aead_open(&key, &ct, other_aad, &nonce).await;
+        ensure!(res.is_err(), "open succeeded with different AAD");
+
+        let mut tampered = ct.clone();
+        tampered[0] ^= 1;
+
+        let res = cs.aead_open(&key, &tampered, aad, &nonce).await;
+        ensure!(res.is_err(), "open succeeded with a modified ciphertext");
+
+        let res = cs.aead_open(&key, &ct[..ct.len() - 1], aad, &nonce).await;
+        ensure!(res.is_err(), "open succeeded with a truncated ciphertext");
+
+        let other_nonce = vec![3u8; nonce.len()];
+        let res = cs.aead_open(&key, &ct, aad, &other_nonce).await;
+        ensure!(res.is_err(), "open succeeded with a different nonce");
+    }
+
+    let res = cs.aead_seal(&key[1..], b"mls", None, &nonce).await;
+    ensure!(res.is_err(), "seal succeeded with a short key");
+
+    let res = cs.aead_open(&key, &[], None, &nonce).await;
+    ensure!(res.is_err(), "open succeeded with an empty ciphertext");
+
+    Ok(())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_hpke<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    let ikm = vec![4u8; cs.kdf_extract_size()];
+    let (sk, pk) = cs.kem_derive(&ikm).await.check("KEM derivation failed")?;
+
+    let (_, same_pk) = cs.kem_derive(&ikm).await.check("KEM derivation failed")?;
+    ensure_eq!(pk, same_pk, "KEM derivation is not deterministic");
+
+    cs.kem_public_key_validate(&pk)
+        .check("derived public key is invalid")?;
+
+    let res = cs.kem_public_key_validate(&HpkePublicKey::from(vec![]));
+    ensure!(res.is_err(), "empty public key is valid");
+
+    let (other_sk, other_pk) = cs.kem_generate().await.check("KEM generation failed")?;
+    ensure_ne!(pk, other_pk, "generated keys are not fresh");
+
+    // EncryptWithLabel and DecryptWithLabel as used for path secrets
+    let ct = encrypt_with_label(cs, &pk, b"UpdatePathNode", b"context", b"secret")
+        .await
+        .check("EncryptWithLabel failed")?;
+
+    let pt = decrypt_with_label(cs, &sk, &pk, b"UpdatePathNode", b"context", &ct).await;
+    ensure_eq!(
+        pt.check("DecryptWithLabel failed")?.as_slice(),
+        b"secret",
+        "wrong plaintext"
+    );
+
+    let res = decrypt_with_label(cs, &sk, &pk, b"Welcome", b"context", &ct).await;
+    ensure!(
+        res.is_err(),
+        "DecryptWithLabel succeeded with another label"
+    );
+
+    let res = decrypt_with_label(cs, &sk, &pk, b"UpdatePathNode", b"other", &ct).await;
+    ensure!(
+        res.is_err(),
+        "DecryptWithLabel succeeded with another context"
+    );
+
+    let res =
+        decrypt_with_label(cs, &other_sk, &other_pk, b"UpdatePathNode", b"context", &ct).await;
+
+    ensure!(res.is_err(), "DecryptWithLabel succeeded with another key");
+
+    let mut tampered = ct.clone();
+    tampered.ciphertext[0] ^= 1;
+
+    let res = cs.hpke_open(&tampered, &sk, &pk, b"info", None).await;
+    ensure!(
+        res.is_err(),
+        "HPKE open succeeded with a modified ciphertext"
+    );
+
+    // One-shot API with AAD and the multi recipient variant
+    let ct = cs
+        .hpke_seal(&pk, b"info", Some(b"aad"), b"mls")
+        .await
+        .check("HPKE seal failed")?;
+
+    let pt = cs.hpke_open(&ct, &sk, &pk, b"info", Some(b"aad")).await;
+    ensure_eq!(pt.check("HPKE open failed")?, b"mls", "wrong plaintext");
+
+    let res = cs.hpke_open(&ct, &sk, &pk, b"info", None).await;
+    ensure!(res.is_err(), "HPKE open succeeded with different AAD");
+
+    let recipients = [(&pk, &b"first"[..]), (&other_pk, &b"second"[..])];
+
+    let cts = cs
+        .hpke_seal_multi(&recipients, b"info", None)
+        .await
+        .check("HPKE multi seal failed")?;
+
+    ensure_eq!(cts.len(), recipients.len(), "wrong number of ciphertexts");
+
+    for ((sk, (pk, expected)), ct) in [&sk, &other_sk].into_iter().zip(recipients).zip(cts) {
+        let pt = cs.hpke_open(&ct, sk, pk, b"info", None).await;
+        ensure_eq!(pt.check("HPKE open failed")?, expected, "wrong plaintext");
+    }
+
+    // Context API as used for external commits
+    let (kem_output, mut ctx_s) = cs
+        .hpke_setup_s(&pk, b"info")
+        .await
+        .check("HPKE setup failed")?;
+
+    let mut ctx_r = cs
+        .hpke_setup_r(&kem_output, &sk, &pk, b"info")
+        .await
+        .check("HPKE setup failed")?;
+
+    let exported_s = ctx_s.export(b"exporter", 32).await.check("export failed")?;
+    let exported_r = ctx_r.export(b"exporter", 32).await.check("export failed")?;
+    ensure_eq!(exported_s, exported_r, "exported secrets differ");
+    ensure_eq!(exported_s.len(), 32, "wrong exported secret size");
+
+    for aad in [None, Some(&b"aad"[..])] {
+        let ct = ctx_s.seal(aad, b"mls").await.check("context seal failed")?;
+        let pt = ctx_r.open(aad, &ct).await.check("context open failed")?;
+        ensure_eq!(pt, b"mls", "wrong plaintext");
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn verify_signatures<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    let (sk, pk) = cs
+        .signature_key_generate()
+        .await
+        .check("signature key generation failed")?;
+
+    let derived = cs.signature_key_derive_public(&sk).await;
+    ensure_eq!(derived.check("derivation failed")?, pk, "wrong public key");
+
+    let (_, other_pk) = cs
+        .signature_key_generate()
+        .await
+        .check("signature key generation failed")?;
+
+    // SignWithLabel and VerifyWithLabel
+    let sign_content = |label| {
+        SignContent::new(label, b"content".to_vec())
+            .mls_encode_to_vec()
+            .check("encoding sign content")
+    };
+
+    let data = sign_content("LeafNodeTBS")?;
+    let signature = cs.sign(&sk, &data).await.check("signing failed")?;
+
+    cs.verify(&pk, &signature, &data)
+        .await
+        .check("valid signature rejected")?;
+
+    let res = cs
+        .verify(&pk, &signature, &sign_content("GroupInfoTBS")?)
+        .await;
+    ensure!(res.is_err(), "signature verified with another label");
+
+    let res = cs.verify(&other_pk, &signature, &data).await;
+    ensure!(res.is_err(), "signature verified with another key");
+
+    let mut tampered = signature.clone();
+    tampered[0] ^= 1;
+
+    let res = cs.verify(&pk, &tampered, &data).await;
+    ensure!(res.is_err(), "modified signature verified");
+
+    let res = cs.verify(&pk, &[], &data).await;
+    ensure!(res.is_err(), "empty signature verified");
+
+    Ok(())
+}
+
+fn verify_random<P: CipherSuiteProvider>(cs: &P) -> Result<(), TestVectorError> {
+    let first = cs.random_bytes_vec(32).check("random generation failed")?;
+    let second = cs.random_bytes_vec(32).check("random generation failed")?;
+
+    ensure_eq!(first.len(), 32, "wrong number of random bytes");
+    ensure_ne!(first, second, "random bytes repeat");
+
+    let empty: Vec<u8> = cs.random_bytes_vec(0).check("random generation failed")?;
+    ensure!(empty.is_empty(), "random bytes of length 0 are not empty");
+
+    Ok(())
+}
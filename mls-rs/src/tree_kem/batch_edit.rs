@@ -0,0 +1,265 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use super::{tree_index::prefetch_identities, *};
+
+use crate::group::proposal::{AddProposal, Proposal, RemoveProposal, UpdateProposal};
+
+use alloc::boxed::Box;
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+#[cfg(not(target_has_atomic = "ptr"))]
+use portable_atomic_util::Arc;
+
+impl TreeKemPublic {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn batch_edit<I, CP>(
+        &mut self,
+        proposal_bundle: &mut ProposalBundle,
+        extensions: &ExtensionList,
+        id_provider: &I,
+        cipher_suite_provider: &CP,
+        tree_rules: &dyn TreeLayoutRules,
+        filter: bool,
+    ) -> Result<BatchEditOutput, MlsError>
+    where
+        I: IdentityProvider,
+        CP: CipherSuiteProvider,
+    {
+        // Rejected proposals are listed by type, removes then updates then adds, and in the
+        // order of the bundle within a type. Loops going backwards collect them in reverse.
+        let mut rejected = vec![];
+        let mut rejected_rev = vec![];
+        let mut removed_rev = vec![];
+
+        // Apply removes (they commute with updates because they don't touch the same leaves)
+        for i in (0..proposal_bundle.remove_proposals().len()).rev() {
+            let index = proposal_bundle.remove_proposals()[i].proposal.to_remove;
+            let res = self.nodes.blank_leaf_node(index);
+
+            if res.is_ok() {
+                // This shouldn't fail if `blank_leaf_node` succedded.
+                self.nodes.blank_direct_path(index)?;
+            }
+
+            if let Ok(old_leaf) = &res {
+                // If this fails, it's not because the proposal is bad.
+                self.unindex_leaf(old_leaf, id_provider, extensions).await?;
+                removed_rev.push((index, old_leaf.clone()));
+            }
+
+            if proposal_bundle.remove_proposals()[i].is_by_value() || !filter {
+                res?;
+            } else if let Err(e) = res {
+                let proposal = proposal_bundle.remove_proposals()[i].clone();
+                rejected_rev.push(RejectedProposal::new(
+                    proposal.map(Proposal::Remove),
+                    Arc::new(e),
+                ));
+                proposal_bundle.remove::<RemoveProposal>(i);
+            }
+        }
+
+        rejected.extend(rejected_rev.drain(..).rev());
+        let removed = removed_rev.into_iter().rev().collect_vec();
+
+        // Resolve the identities of all new leaves at once instead of one proposal at a time
+        let new_leaves = proposal_bundle
+            .updates
+            .iter()
+            .map(|p| &p.proposal.leaf_node)
+            .chain(
+                proposal_bundle
+                    .additions
+                    .iter()
+                    .map(|p| &p.proposal.key_package.leaf_node),
+            )
+            .collect_vec();
+
+        let mut update_ids = prefetch_identities(&new_leaves, id_provider, extensions).await;
+        let mut add_ids = update_ids
+            .split_off(proposal_bundle.updates.len())
+            .into_iter();
+
+        // Remove from the tree old leaves from updates
+        let mut partial_updates = vec![];
+        let mut bad_indices = vec![];
+        let senders = proposal_bundle.update_senders.iter().copied();
+
+        for (i, (p, index)) in proposal_bundle.updates.iter().zip(senders).enumerate() {
+            let new_leaf = p.proposal.leaf_node.clone();
+
+            match self.nodes.blank_leaf_node(index) {
+                Ok(old_leaf) => {
+                    self.unindex_leaf(&old_leaf, id_provider, extensions)
+                        .await?;
+
+                    partial_updates.push((index, old_leaf, new_leaf, update_ids[i].take(), i));
+                }
+                _ => {
+                    if !filter || !p.is_by_reference() {
+                        return Err(MlsError::UpdatingNonExistingMember);
+                    }
+
+                    bad_indices.push((i, Arc::new(MlsError::UpdatingNonExistingMember)));
+                }
+            }
+        }
+
+        #[cfg(feature = "tree_index")]
+        let index_clone = self.index.clone();
+
+        let mut removed_leaves = vec![];
+        let mut updated_indices = vec![];
+        let mut revert_reason = None;
+
+        // Apply updates one by one. If there's an update which we can't apply or revert, we revert
+        // all updates.
+        for (index, old_leaf, new_leaf, new_id, i) in partial_updates.into_iter() {
+            let res = self
+                .index_leaf_with_identity(&new_leaf, index, new_id, id_provider, extensions)
+                .await;
+
+            let reason = match res {
+                Ok(()) => {
+                    self.nodes.insert_leaf(index, new_leaf);
+                    removed_leaves.push(old_leaf);
+                    updated_indices.push(index);
+                    continue;
+                }
+                Err(e) if !filter => return Err(e),
+                Err(e) => Arc::new(e),
+            };
+
+            let res = self
+                .index_leaf(&old_leaf, index, id_provider, extensions)
+                .await;
+
+            if res.is_ok() {
+                self.nodes.insert_leaf(index, old_leaf);
+                bad_indices.push((i, reason));
+            } else {
+                // Revert all updates and stop. We're already in the "filter" case, so we don't throw an error.
+                #[cfg(feature = "tree_index")]
+                {
+                    self.index = index_clone;
+                }
+
+                removed_leaves
+                    .into_iter()
+                    .zip(updated_indices.iter())
+                    .for_each(|(leaf, index)| self.nodes.insert_leaf(*index, leaf));
+
+                updated_indices = vec![];
+                revert_reason = Some(reason);
+                break;
+            }
+        }
+
+        // If we managed to update something, blank direct paths
+        updated_indices
+            .iter()
+            .try_for_each(|index| self.nodes.blank_direct_path(*index).map(|_| ()))?;
+
+        // Remove rejected updates from applied proposals
+        bad_indices.sort_by_key(|(i, _)| *i);
+
+        if updated_indices.is_empty() {
+            // This takes care of the "revert all" scenario. Updates that were reverted are
+            // rejected for the reason the revert was needed.
+            for (i, update) in core::mem::take(&mut proposal_bundle.updates)
+                .into_iter()
+                .enumerate()
+            {
+                let reason = bad_indices
+                    .iter()
+                    .find(|(j, _)| *j == i)
+                    .map(|(_, reason)| reason.clone())
+                    .or_else(|| revert_reason.clone());
+
+                if let Some(reason) = reason {
+                    rejected.push(RejectedProposal::new(update.map(Proposal::Update), reason));
+                }
+            }
+        } else {
+            for (i, reason) in bad_indices.into_iter().rev() {
+                let update = proposal_bundle.updates[i].clone();
+                rejected_rev.push(RejectedProposal::new(update.map(Proposal::Update), reason));
+                proposal_bundle.remove::<UpdateProposal>(i);
+                proposal_bundle.update_senders.remove(i);
+            }
+
+            rejected.extend(rejected_rev.drain(..).rev());
+        }
+
+        // Apply adds
+        let mut start = LeafIndex(0);
+        let mut added = vec![];
+        let mut bad_indexes = vec![];
+
+        for i in 0..proposal_bundle.additions.len() {
+            let leaf = proposal_bundle.additions[i]
+                .proposal
+                .key_package
+                .leaf_node
+                .clone();
+
+            let (index, leftmost) = self.place_new_leaf(&leaf, tree_rules, start)?;
+
+            let res = self
+                .add_leaf_with_identity(
+                    leaf,
+                    add_ids.next().flatten(),
+                    id_provider,
+                    extensions,
+                    index,
+                )
+                .await;
+
+            match res {
+                Ok(index) => {
+                    if leftmost {
+                        start = index;
+                    }
+
+                    added.push(index);
+                }
+                Err(e) if proposal_bundle.additions[i].is_by_value() || !filter => return Err(e),
+                Err(e) => bad_indexes.push((i, e)),
+            }
+        }
+
+        for (i, reason) in bad_indexes.into_iter().rev() {
+            let add = proposal_bundle.additions[i].clone();
+            rejected_rev.push(RejectedProposal::new(
+                add.map(|p| Proposal::Add(Box::new(p))),
+                Arc::new(reason),
+            ));
+            proposal_bundle.remove::<AddProposal>(i);
+        }
+
+        rejected.extend(rejected_rev.drain(..).rev());
+
+        self.nodes.truncate(tree_rules.truncation(extensions)?);
+
+        let updated_leaves = proposal_bundle
+            .remove_proposals()
+            .iter()
+            .map(|p| p.proposal.to_remove)
+            .chain(updated_indices)
+            .chain(added.iter().copied())
+            .collect_vec();
+
+        self.update_hashes(&updated_leaves, cipher_suite_provider)
+            .await?;
+
+        Ok(BatchEditOutput {
+            added,
+            removed,
+            rejected,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use super::{tree_index::prefetch_identities, *};
+
+impl TreeKemPublic {
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn batch_edit_lite<I, CP>(
+        &mut self,
+        proposal_bundle: &ProposalBundle,
+        extensions: &ExtensionList,
+        id_provider: &I,
+        cipher_suite_provider: &CP,
+        tree_rules: &dyn TreeLayoutRules,
+    ) -> Result<BatchEditOutput, MlsError>
+    where
+        I: IdentityProvider,
+        CP: CipherSuiteProvider,
+    {
+        let mut removed = vec![];
+
+        // Apply removes
+        for p in &proposal_bundle.removals {
+            let index = p.proposal.to_remove;
+
+            // If this fails, it's not because the proposal is bad.
+            let old_leaf = self.nodes.blank_leaf_node(index)?;
+            self.unindex_leaf(&old_leaf, id_provider, extensions)
+                .await?;
+
+            self.nodes.blank_direct_path(index)?;
+            removed.push((index, old_leaf));
+        }
+
+        // Resolve the identities of all new leaves at once instead of one proposal at a time
+        let new_leaves = proposal_bundle
+            .additions
+            .iter()
+            .map(|p| &p.proposal.key_package.leaf_node)
+            .collect_vec();
+
+        let add_ids = prefetch_identities(&new_leaves, id_provider, extensions).await;
+
+        // Apply adds
+        let mut start = LeafIndex(0);
+        let mut added = vec![];
+
+        for (p, id) in proposal_bundle.additions.iter().zip(add_ids) {
+            let leaf = p.proposal.key_package.leaf_node.clone();
+            let (index, leftmost) = self.place_new_leaf(&leaf, tree_rules, start)?;
+
+            self.add_leaf_with_identity(leaf, id, id_provider, extensions, index)
+                .await?;
+
+            if leftmost {
+                start = index;
+            }
+
+            added.push(index);
+        }
+
+        self.nodes.truncate(tree_rules.truncation(extensions)?);
+
+        let updated_leaves = proposal_bundle
+            .remove_proposals()
+            .iter()
+            .map(|p| p.proposal.to_remove)
+            .chain(added.iter().copied())
+            .collect_vec();
+
+        self.update_hashes(&updated_leaves, cipher_suite_provider)
+            .await?;
+
+        Ok(BatchEditOutput { added, removed })
+    }
+}
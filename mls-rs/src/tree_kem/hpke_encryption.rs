@@ -31,14 +31,77 @@ impl Debug for EncryptContext<'_> {
 }
 
 impl<'a> EncryptContext<'a> {
-    pub fn new(label: &str, context: &'a [u8]) -> Self {
+    pub fn new(label: &[u8], context: &'a [u8]) -> Self {
         Self {
-            label: [b"MLS 1.0 ", label.as_bytes()].concat(),
+            label: [b"MLS 1.0 ", label].concat(),
             context,
         }
     }
 }
 
+/// `EncryptWithLabel` as defined in RFC 9420 section 5.1.3.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn encrypt_with_label<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    public_key: &HpkePublicKey,
+    label: &[u8],
+    context: &[u8],
+    plaintext: &[u8],
+) -> Result<HpkeCiphertext, MlsError> {
+    let context = EncryptContext::new(label, context)
+        .mls_encode_to_vec()
+        .map(Zeroizing::new)?;
+
+    cipher_suite_provider
+        .hpke_seal(public_key, &context, None, plaintext)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+/// `EncryptWithLabel` for several recipients sharing the same `label` and
+/// `context`, using [`CipherSuiteProvider::hpke_seal_multi`].
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn encrypt_with_label_multi<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    recipients: &[(&HpkePublicKey, &[u8])],
+    label: &[u8],
+    context: &[u8],
+) -> Result<Vec<HpkeCiphertext>, MlsError> {
+    let context = EncryptContext::new(label, context)
+        .mls_encode_to_vec()
+        .map(Zeroizing::new)?;
+
+    cipher_suite_provider
+        .hpke_seal_multi(recipients, &context, None)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+/// `DecryptWithLabel` as defined in RFC 9420 section 5.1.3.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(crate) async fn decrypt_with_label<P: CipherSuiteProvider>(
+    cipher_suite_provider: &P,
+    secret_key: &HpkeSecretKey,
+    public_key: &HpkePublicKey,
+    label: &[u8],
+    context: &[u8],
+    ciphertext: &HpkeCiphertext,
+) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    let context = EncryptContext::new(label, context).mls_encode_to_vec()?;
+
+    cipher_suite_provider
+        .hpke_open(ciphertext, secret_key, public_key, &context, None)
+        .await
+        .map(Zeroizing::new)
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
+/// Encode the HPKE `info` used by `EncryptWithLabel` for callers that set up
+/// an HPKE context directly.
+pub(crate) fn encrypt_context_info(label: &[u8], context: &[u8]) -> Result<Vec<u8>, MlsError> {
+    Ok(EncryptContext::new(label, context).mls_encode_to_vec()?)
+}
+
 #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
 #[cfg_attr(all(target_arch = "wasm32", mls_build_async), maybe_async::must_be_async(?Send))]
 #[cfg_attr(
@@ -55,16 +118,16 @@ pub(crate) trait HpkeEncryptable: Sized {
         public_key: &HpkePublicKey,
         context: &[u8],
     ) -> Result<HpkeCiphertext, MlsError> {
-        let context = EncryptContext::new(Self::ENCRYPT_LABEL, context)
-            .mls_encode_to_vec()
-            .map(Zeroizing::new)?;
-
         let content = self.get_bytes().map(Zeroizing::new)?;
 
-        cipher_suite_provider
-            .hpke_seal(public_key, &context, None, &content)
-            .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+        encrypt_with_label(
+            cipher_suite_provider,
+            public_key,
+            Self::ENCRYPT_LABEL.as_bytes(),
+            context,
+            &content,
+        )
+        .await
     }
 
     async fn decrypt<P: CipherSuiteProvider>(
@@ -74,12 +137,15 @@ pub(crate) trait HpkeEncryptable: Sized {
         context: &[u8],
         ciphertext: &HpkeCiphertext,
     ) -> Result<Self, MlsError> {
-        let context = EncryptContext::new(Self::ENCRYPT_LABEL, context).mls_encode_to_vec()?;
-
-        let plaintext = cipher_suite_provider
-            .hpke_open(ciphertext, secret_key, public_key, &context, None)
-            .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))?;
+        let plaintext = decrypt_with_label(
+            cipher_suite_provider,
+            secret_key,
+            public_key,
+            Self::ENCRYPT_LABEL.as_bytes(),
+            context,
+            ciphertext,
+        )
+        .await?;
 
         Self::from_bytes(plaintext.to_vec())
     }
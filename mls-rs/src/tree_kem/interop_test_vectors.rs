@@ -14,7 +14,10 @@ use crate::{
 };
 
 use super::{
-    node::NodeVec, test_utils::TreeWithSigners, tree_validator::TreeValidator, TreeKemPublic,
+    node::{NodeIndex, NodeVec},
+    test_utils::TreeWithSigners,
+    tree_validator::TreeValidator,
+    TreeKemPublic,
 };
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
@@ -39,10 +42,19 @@ impl From<crate::tree_kem::tree_hash::TreeHash> for TreeHash {
     }
 }
 
+fn resolution(nodes: &NodeVec, index: u32) -> Vec<u32> {
+    nodes
+        .get_resolution_index(NodeIndex::new(index))
+        .unwrap()
+        .into_iter()
+        .map(|n| n.value())
+        .collect()
+}
+
 impl ValidationTestCase {
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn new<P: CipherSuiteProvider>(tree: TreeKemPublic, group_id: &[u8], cs: &P) -> Self {
-        let tree_size = tree.total_leaf_count() * 2 - 1;
+        let tree_size = tree.nodes.tree_size().node_width();
 
         assert!(
             tree.tree_hashes.current.len() == tree_size as usize,
@@ -52,7 +64,7 @@ impl ValidationTestCase {
         let resolutions = (0..tree_size)
             .map(
                 #[cfg_attr(coverage_nightly, coverage(off))]
-                |i| tree.nodes.get_resolution_index(i).unwrap(),
+                |i| resolution(&tree.nodes, i),
             )
             .collect();
 
@@ -112,9 +124,7 @@ async fn validation() {
             .resolutions
             .iter()
             .enumerate()
-            .for_each(|(i, res)| {
-                assert_eq!(&tree.nodes.get_resolution_index(i as u32).unwrap(), res)
-            });
+            .for_each(|(i, res)| assert_eq!(&resolution(&tree.nodes, i as u32), res));
 
         let mut context = get_test_group_context(1, test_case.cipher_suite.into()).await;
         context.tree_hash = tree_hash;
@@ -12,7 +12,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 use itertools::Itertools;
 use mls_rs_codec::MlsEncode;
-use tree_math::{CopathNode, TreeIndex};
+use tree_math::CopathNode;
 
 #[cfg(all(not(mls_build_async), feature = "rayon"))]
 use {crate::iter::ParallelIteratorExt, rayon::prelude::*};
@@ -83,10 +83,8 @@ impl<'a> TreeKem<'a> {
 
         for (i, (node, f)) in path.iter().zip(&filtered).enumerate() {
             if !f {
-                let secret = secret_generator.next_secret().await?;
-
-                let (secret_key, public_key) =
-                    secret.to_hpke_key_pair(cipher_suite_provider).await?;
+                let (secret, (secret_key, public_key)) =
+                    secret_generator.next_secret_with_key_pair().await?;
 
                 self.private_key.secret_keys[i + 1] = Some(secret_key);
                 self.tree_kem_public.update_node(public_key, node.path)?;
@@ -181,7 +179,7 @@ impl<'a> TreeKem<'a> {
         cipher_suite: &P,
         excluding: &[LeafIndex],
     ) -> Result<Vec<UpdatePathNode>, MlsError> {
-        let excluding = excluding.iter().copied().map(NodeIndex::from);
+        let excluding = excluding.iter().map(LeafIndex::node_index);
 
         #[cfg(feature = "std")]
         let excluding = excluding.collect::<HashSet<NodeIndex>>();
@@ -217,7 +215,7 @@ impl<'a> TreeKem<'a> {
         cipher_suite: &P,
         excluding: &[LeafIndex],
     ) -> Result<Vec<UpdatePathNode>, MlsError> {
-        let excluding = excluding.iter().copied().map(NodeIndex::from);
+        let excluding = excluding.iter().map(LeafIndex::node_index);
 
         #[cfg(feature = "std")]
         let excluding = excluding.collect::<HashSet<NodeIndex>>();
@@ -254,11 +252,10 @@ impl<'a> TreeKem<'a> {
     {
         let self_index = self.private_key.self_index;
 
-        let lca_index =
-            tree_math::leaf_lca_level(self_index.into(), sender_index.into()) as usize - 2;
+        let lca_index = tree_math::leaf_lca_level(self_index, sender_index) as usize - 1;
 
         let mut path = self.tree_kem_public.nodes.direct_copath(self_index);
-        let leaf = CopathNode::new(self_index.into(), 0);
+        let leaf = CopathNode::new(self_index.node_index(), NodeIndex::default());
         path.insert(0, leaf);
         let resolved_pos = self.find_resolved_pos(&path, lca_index)?;
 
@@ -298,12 +295,10 @@ impl<'a> TreeKem<'a> {
 
         for (i, update) in update_path.nodes.iter().enumerate().skip(lca_index) {
             if let Some(update) = update {
-                let secret = node_secret_gen.next_secret().await?;
-
                 // Verify the private key we calculated properly matches the public key we inserted into the tree. This guarantees
                 // that we will be able to decrypt later.
-                let (hpke_private, hpke_public) =
-                    secret.to_hpke_key_pair(cipher_suite_provider).await?;
+                let (_, (hpke_private, hpke_public)) =
+                    node_secret_gen.next_secret_with_key_pair().await?;
 
                 if hpke_public != update.public_key {
                     return Err(MlsError::PubKeyMismatch);
@@ -355,8 +350,10 @@ impl<'a> TreeKem<'a> {
 
         let ctxts = ctxts.try_collect().await?;
 
-        let path_index = copath_index
-            .parent_sibling(&self.tree_kem_public.total_leaf_count())
+        let path_index = self
+            .tree_kem_public
+            .nodes
+            .parent_sibling(copath_index)
             .ok_or(MlsError::ExpectedNode)?
             .parent;
 
@@ -401,7 +398,10 @@ impl<'a> TreeKem<'a> {
 
         let (ct_pos, _) = reso
             .iter()
-            .filter(|idx| **idx % 2 == 1 || !excluding.contains(&LeafIndex(**idx / 2)))
+            .filter(|idx| {
+                idx.try_into_leaf()
+                    .map_or(true, |leaf| !excluding.contains(&leaf))
+            })
             .find_position(|idx| idx == &&resolved)
             .ok_or(MlsError::UpdateErrorNoSecretKey)?;
 
@@ -411,7 +411,7 @@ impl<'a> TreeKem<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{tree_math, TreeKem};
+    use super::TreeKem;
     use crate::{
         cipher_suite::CipherSuite,
         client::test_utils::TEST_CIPHER_SUITE,
@@ -432,7 +432,6 @@ mod tests {
     use alloc::{format, vec, vec::Vec};
     use mls_rs_codec::MlsEncode;
     use mls_rs_core::crypto::CipherSuiteProvider;
-    use tree_math::TreeIndex;
 
     // Verify that the tree is in the correct state after generating an update path
     fn verify_tree_update_path(
@@ -475,7 +474,7 @@ mod tests {
         }
 
         // Verify that we have a public keys up to the root
-        let root = tree.total_leaf_count().root();
+        let root = tree.nodes.root();
         assert!(tree.nodes.borrow_node(root).unwrap().is_some());
     }
 
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use super::{parent_hash::ParentHash, Capabilities, Lifetime};
-use crate::client::MlsError;
+use crate::client::{LeafNodeSourceKind, MlsError};
 use crate::crypto::{CipherSuiteProvider, HpkePublicKey, HpkeSecretKey, SignatureSecretKey};
 use crate::{identity::SigningIdentity, signer::Signable, ExtensionList};
 use alloc::vec::Vec;
@@ -21,6 +21,16 @@ pub enum LeafNodeSource {
     Commit(ParentHash) = 3u8,
 }
 
+impl LeafNodeSource {
+    pub(crate) fn kind(&self) -> LeafNodeSourceKind {
+        match self {
+            LeafNodeSource::KeyPackage(_) => LeafNodeSourceKind::KeyPackage,
+            LeafNodeSource::Update => LeafNodeSourceKind::Update,
+            LeafNodeSource::Commit(_) => LeafNodeSourceKind::Commit,
+        }
+    }
+}
+
 #[derive(Clone, MlsSize, MlsEncode, MlsDecode, PartialEq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
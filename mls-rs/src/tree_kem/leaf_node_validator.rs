@@ -3,7 +3,8 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use super::leaf_node::{LeafNode, LeafNodeSigningContext, LeafNodeSource};
-use crate::client::MlsError;
+use crate::client::{LeafNodeSourceKind, MlsError};
+use crate::group::LifetimePolicy;
 use crate::CipherSuiteProvider;
 use crate::{signer::Signable, time::MlsTime};
 use mls_rs_core::{error::IntoAnyError, extension::ExtensionList, identity::IdentityProvider};
@@ -35,6 +36,22 @@ impl<'a> ValidationContext<'a> {
             ValidationContext::Commit((_, _, t)) => t,
         }
     }
+
+    fn leaf_index(&self) -> Option<u32> {
+        match *self {
+            ValidationContext::Add(_) => None,
+            ValidationContext::Update((_, leaf_index, _)) => Some(leaf_index),
+            ValidationContext::Commit((_, leaf_index, _)) => Some(leaf_index),
+        }
+    }
+
+    fn expected_source(&self) -> LeafNodeSourceKind {
+        match self {
+            ValidationContext::Add(_) => LeafNodeSourceKind::KeyPackage,
+            ValidationContext::Update(_) => LeafNodeSourceKind::Update,
+            ValidationContext::Commit(_) => LeafNodeSourceKind::Commit,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -46,6 +63,7 @@ where
     cipher_suite_provider: &'a CP,
     identity_provider: &'a C,
     group_context_extensions: Option<&'a ExtensionList>,
+    lifetime_policy: LifetimePolicy,
 }
 
 impl<'a, C: IdentityProvider, CP: CipherSuiteProvider> LeafNodeValidator<'a, C, CP> {
@@ -58,6 +76,15 @@ impl<'a, C: IdentityProvider, CP: CipherSuiteProvider> LeafNodeValidator<'a, C,
             cipher_suite_provider,
             identity_provider,
             group_context_extensions,
+            lifetime_policy: Default::default(),
+        }
+    }
+
+    /// Set the policy used to check lifetimes of added leaf nodes.
+    pub fn with_lifetime_policy(self, lifetime_policy: LifetimePolicy) -> Self {
+        Self {
+            lifetime_policy,
+            ..self
         }
     }
 
@@ -66,32 +93,24 @@ impl<'a, C: IdentityProvider, CP: CipherSuiteProvider> LeafNodeValidator<'a, C,
         leaf_node: &LeafNode,
         context: &ValidationContext,
     ) -> Result<(), MlsError> {
-        // Context specific checks
-        match context {
-            ValidationContext::Add(time) => {
-                // If the context is add, and we specified a time to check for lifetime, verify it
-                if let LeafNodeSource::KeyPackage(lifetime) = &leaf_node.leaf_node_source {
-                    if let Some(current_time) = time {
-                        if !lifetime.within_lifetime(*current_time) {
-                            return Err(MlsError::InvalidLifetime);
-                        }
-                    }
-                } else {
-                    // If the leaf_node_source is anything other than Add it is invalid
-                    return Err(MlsError::InvalidLeafNodeSource);
-                }
-            }
-            ValidationContext::Update(_) => {
-                // If the leaf_node_source is anything other than Update it is invalid
-                if !matches!(leaf_node.leaf_node_source, LeafNodeSource::Update) {
-                    return Err(MlsError::InvalidLeafNodeSource);
-                }
-            }
-            ValidationContext::Commit(_) => {
-                // If the leaf_node_source is anything other than Commit it is invalid
-                if !matches!(leaf_node.leaf_node_source, LeafNodeSource::Commit(_)) {
-                    return Err(MlsError::InvalidLeafNodeSource);
-                }
+        // The leaf_node_source must match the context
+        let expected = context.expected_source();
+        let actual = leaf_node.leaf_node_source.kind();
+
+        if expected != actual {
+            return Err(MlsError::UnexpectedLeafNodeSource(
+                context.leaf_index(),
+                expected,
+                actual,
+            ));
+        }
+
+        // If the context is add, and we specified a time to check for lifetime, verify it
+        if let (ValidationContext::Add(Some(current_time)), LeafNodeSource::KeyPackage(lifetime)) =
+            (context, &leaf_node.leaf_node_source)
+        {
+            if !self.lifetime_policy.accepts(lifetime, *current_time) {
+                return Err(MlsError::InvalidLifetime);
             }
         }
 
@@ -187,14 +206,21 @@ impl<'a, C: IdentityProvider, CP: CipherSuiteProvider> LeafNodeValidator<'a, C,
             .await
             .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
 
-        // Verify that the credential signed the leaf node
+        // Verify that the credential signed the leaf node. Outside of key packages, the signature
+        // also binds the leaf node to the group and leaf index.
         leaf_node
             .verify(
                 self.cipher_suite_provider,
                 &leaf_node.signing_identity.signature_key,
                 &context.signing_context(),
             )
-            .await?;
+            .await
+            .map_err(|e| match (e, context.leaf_index()) {
+                (MlsError::InvalidSignature, Some(leaf_index)) => {
+                    MlsError::LeafNodeBindingMismatch(leaf_index, context.expected_source())
+                }
+                (e, _) => e,
+            })?;
 
         // If required capabilities are specified, verify the leaf node meets the requirements
         self.validate_required_capabilities(leaf_node)?;
@@ -224,6 +250,16 @@ impl<'a, C: IdentityProvider, CP: CipherSuiteProvider> LeafNodeValidator<'a, C,
         #[cfg(feature = "by_ref_proposal")]
         self.validate_external_senders_ext_credentials(leaf_node)?;
 
+        // Let the application check the contents of the leaf node extensions
+        self.identity_provider
+            .validate_leaf_node_extensions(
+                &leaf_node.signing_identity,
+                &leaf_node.extensions,
+                self.group_context_extensions,
+            )
+            .await
+            .map_err(|e| MlsError::LeafNodeExtensionsRejected(e.into_any_error()))?;
+
         Ok(())
     }
 }
@@ -236,6 +272,7 @@ mod tests {
     use assert_matches::assert_matches;
     #[cfg(feature = "std")]
     use core::time::Duration;
+    use mls_rs_codec::MlsEncode;
     use mls_rs_core::crypto::CipherSuite;
     use mls_rs_core::group::ProposalType;
 
@@ -250,8 +287,11 @@ mod tests {
     use crate::identity::basic::BasicCredential;
     use crate::identity::basic::BasicIdentityProvider;
     use crate::identity::test_utils::get_test_signing_identity;
+    use crate::identity::SigningIdentity;
     use crate::tree_kem::leaf_node::test_utils::*;
-    use crate::tree_kem::leaf_node_validator::test_utils::FailureIdentityProvider;
+    use crate::tree_kem::leaf_node_validator::test_utils::{
+        FailureIdentityProvider, TestFailureError,
+    };
     use crate::tree_kem::Capabilities;
     use crate::ExtensionList;
 
@@ -296,6 +336,107 @@ mod tests {
         assert_matches!(res, Err(MlsError::IdentityProviderError(_)));
     }
 
+    struct AttestationIdentityProvider;
+
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl IdentityProvider for AttestationIdentityProvider {
+        type Error = TestFailureError;
+
+        async fn validate_member(
+            &self,
+            _signing_identity: &SigningIdentity,
+            _timestamp: Option<MlsTime>,
+            _extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn validate_external_sender(
+            &self,
+            _signing_identity: &SigningIdentity,
+            _timestamp: Option<MlsTime>,
+            _extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn identity(
+            &self,
+            signing_id: &SigningIdentity,
+            _extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            Ok(signing_id.credential.mls_encode_to_vec().unwrap())
+        }
+
+        async fn valid_successor(
+            &self,
+            _predecessor: &SigningIdentity,
+            _successor: &SigningIdentity,
+            _extensions: &ExtensionList,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        async fn validate_leaf_node_extensions(
+            &self,
+            _signing_identity: &SigningIdentity,
+            leaf_node_extensions: &ExtensionList,
+            _group_extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            // The test extension plays the role of an attestation that is valid if it is set to 1
+            match leaf_node_extensions.get_as::<TestExtension>() {
+                Ok(Some(TestExtension { foo: 1 })) => Ok(()),
+                _ => Err(TestFailureError),
+            }
+        }
+
+        fn supported_types(&self) -> Vec<crate::identity::CredentialType> {
+            vec![BasicCredential::credential_type()]
+        }
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn leaf_node_extensions_are_validated_by_identity_provider() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (signing_identity, secret) = get_test_signing_identity(TEST_CIPHER_SUITE, b"foo").await;
+
+        let capabilities = Capabilities {
+            credentials: vec![BasicCredential::credential_type()],
+            extensions: vec![TestExtension::extension_type()],
+            ..Default::default()
+        };
+
+        let validator =
+            LeafNodeValidator::new(&cipher_suite_provider, &AttestationIdentityProvider, None);
+
+        for (attestation, valid) in [(1, true), (0, false)] {
+            let mut extensions = ExtensionList::new();
+            extensions
+                .set_from(TestExtension::from(attestation))
+                .unwrap();
+
+            let (leaf_node, _) = get_test_node(
+                TEST_CIPHER_SUITE,
+                signing_identity.clone(),
+                &secret,
+                Some(capabilities.clone()),
+                Some(extensions),
+            )
+            .await;
+
+            let res = validator
+                .check_if_valid(&leaf_node, ValidationContext::Add(None))
+                .await;
+
+            if valid {
+                assert_matches!(res, Ok(_));
+            } else {
+                assert_matches!(res, Err(MlsError::LeafNodeExtensionsRejected(_)));
+            }
+        }
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_basic_update_validation() {
         let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
@@ -326,6 +467,38 @@ mod tests {
         assert_matches!(res, Ok(_));
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn update_bound_to_other_group_or_leaf_fails() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let (mut leaf_node, secret) = get_test_add_node().await;
+
+        leaf_node
+            .update(
+                &cipher_suite_provider,
+                b"group_id",
+                1,
+                default_properties(),
+                None,
+                &secret,
+            )
+            .await
+            .unwrap();
+
+        let test_validator =
+            LeafNodeValidator::new(&cipher_suite_provider, &BasicIdentityProvider, None);
+
+        for (group_id, leaf_index) in [(b"other_id", 1), (b"group_id", 2)] {
+            let context = ValidationContext::Update((group_id, leaf_index, None));
+            let res = test_validator.check_if_valid(&leaf_node, context).await;
+
+            assert_matches!(
+                res,
+                Err(MlsError::LeafNodeBindingMismatch(index, LeafNodeSourceKind::Update))
+                    if index == leaf_index
+            );
+        }
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_basic_commit_validation() {
         let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
@@ -370,13 +543,27 @@ mod tests {
             .check_if_valid(&leaf_node, ValidationContext::Update((b"foo", 0, None)))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Update,
+                LeafNodeSourceKind::KeyPackage
+            ))
+        );
 
         let res = test_validator
             .check_if_valid(&leaf_node, ValidationContext::Commit((b"foo", 0, None)))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Commit,
+                LeafNodeSourceKind::KeyPackage
+            ))
+        );
 
         leaf_node
             .update(
@@ -394,13 +581,27 @@ mod tests {
             .check_if_valid(&leaf_node, ValidationContext::Add(None))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                None,
+                LeafNodeSourceKind::KeyPackage,
+                LeafNodeSourceKind::Update
+            ))
+        );
 
         let res = test_validator
             .check_if_valid(&leaf_node, ValidationContext::Commit((b"foo", 0, None)))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Commit,
+                LeafNodeSourceKind::Update
+            ))
+        );
 
         leaf_node.leaf_node_source = LeafNodeSource::Commit(hex!("f00d").into());
 
@@ -420,13 +621,27 @@ mod tests {
             .check_if_valid(&leaf_node, ValidationContext::Add(None))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                None,
+                LeafNodeSourceKind::KeyPackage,
+                LeafNodeSourceKind::Commit
+            ))
+        );
 
         let res = test_validator
             .check_if_valid(&leaf_node, ValidationContext::Update((b"foo", 0, None)))
             .await;
 
-        assert_matches!(res, Err(MlsError::InvalidLeafNodeSource));
+        assert_matches!(
+            res,
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Update,
+                LeafNodeSourceKind::Commit
+            ))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
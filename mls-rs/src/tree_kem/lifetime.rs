@@ -46,9 +46,13 @@ impl Lifetime {
         Self::days(365 * y as u32)
     }
 
-    pub(crate) fn within_lifetime(&self, time: MlsTime) -> bool {
+    /// Check that `time` is within the lifetime extended by `grace_period`
+    /// seconds on both ends.
+    pub(crate) fn within_lifetime(&self, time: MlsTime, grace_period: u64) -> bool {
         let since_epoch = time.seconds_since_epoch();
-        since_epoch >= self.not_before && since_epoch <= self.not_after
+
+        since_epoch.saturating_add(grace_period) >= self.not_before
+            && since_epoch <= self.not_after.saturating_add(grace_period)
     }
 }
 
@@ -101,19 +105,29 @@ mod tests {
             not_after: 10,
         };
 
-        assert!(!test_lifetime
-            .within_lifetime(MlsTime::from_duration_since_epoch(Duration::from_secs(4))));
-
-        assert!(!test_lifetime
-            .within_lifetime(MlsTime::from_duration_since_epoch(Duration::from_secs(11))));
-
-        assert!(test_lifetime
-            .within_lifetime(MlsTime::from_duration_since_epoch(Duration::from_secs(5))));
-
-        assert!(test_lifetime
-            .within_lifetime(MlsTime::from_duration_since_epoch(Duration::from_secs(10))));
-
-        assert!(test_lifetime
-            .within_lifetime(MlsTime::from_duration_since_epoch(Duration::from_secs(6))));
+        assert!(!test_lifetime.within_lifetime(
+            MlsTime::from_duration_since_epoch(Duration::from_secs(4)),
+            0
+        ));
+
+        assert!(!test_lifetime.within_lifetime(
+            MlsTime::from_duration_since_epoch(Duration::from_secs(11)),
+            0
+        ));
+
+        assert!(test_lifetime.within_lifetime(
+            MlsTime::from_duration_since_epoch(Duration::from_secs(5)),
+            0
+        ));
+
+        assert!(test_lifetime.within_lifetime(
+            MlsTime::from_duration_since_epoch(Duration::from_secs(10)),
+            0
+        ));
+
+        assert!(test_lifetime.within_lifetime(
+            MlsTime::from_duration_since_epoch(Duration::from_secs(6)),
+            0
+        ));
     }
 }
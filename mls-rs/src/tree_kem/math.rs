@@ -4,25 +4,25 @@
 
 use alloc::vec::Vec;
 use core::{fmt::Debug, hash::Hash};
-use mls_rs_codec::{MlsDecode, MlsEncode};
+use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 
-use super::node::LeafIndex;
+use super::node::{LeafIndex, NodeIndex};
 
 pub trait TreeIndex:
     Send + Sync + Eq + Clone + Debug + Default + MlsEncode + MlsDecode + Hash + Ord
 {
-    fn root(&self) -> Self;
+    /// Number of leaves of a tree indexed by `Self`.
+    type LeafCount: Send + Sync + Copy + Eq + Debug + Default + MlsSize + MlsEncode + MlsDecode;
+
+    fn root(leaf_count: Self::LeafCount) -> Self;
 
     fn left_unchecked(&self) -> Self;
     fn right_unchecked(&self) -> Self;
 
-    fn parent_sibling(&self, leaf_count: &Self) -> Option<ParentSibling<Self>>;
+    fn parent_sibling(&self, leaf_count: Self::LeafCount) -> Option<ParentSibling<Self>>;
     fn is_leaf(&self) -> bool;
     fn is_in_tree(&self, root: &Self) -> bool;
 
-    #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-    fn zero() -> Self;
-
     #[cfg(any(feature = "secret_tree_access", feature = "private_message", test))]
     fn left(&self) -> Option<Self> {
         (!self.is_leaf()).then(|| self.left_unchecked())
@@ -33,8 +33,8 @@ pub trait TreeIndex:
         (!self.is_leaf()).then(|| self.right_unchecked())
     }
 
-    fn direct_copath(&self, leaf_count: &Self) -> Vec<CopathNode<Self>> {
-        let root = leaf_count.root();
+    fn direct_copath(&self, leaf_count: Self::LeafCount) -> Vec<CopathNode<Self>> {
+        let root = Self::root(leaf_count);
 
         if !self.is_in_tree(&root) {
             return Vec::new();
@@ -79,8 +79,10 @@ impl<T: Clone + PartialEq + Eq + core::fmt::Debug> ParentSibling<T> {
 macro_rules! impl_tree_stdint {
     ($t:ty) => {
         impl TreeIndex for $t {
-            fn root(&self) -> $t {
-                *self - 1
+            type LeafCount = $t;
+
+            fn root(leaf_count: $t) -> $t {
+                leaf_count - 1
             }
 
             /// Panicks if `x` is even in debug, overflows in release.
@@ -93,8 +95,8 @@ macro_rules! impl_tree_stdint {
                 *self ^ (0x03 << (self.trailing_ones() - 1))
             }
 
-            fn parent_sibling(&self, leaf_count: &Self) -> Option<ParentSibling<Self>> {
-                if self == &leaf_count.root() {
+            fn parent_sibling(&self, leaf_count: $t) -> Option<ParentSibling<Self>> {
+                if *self == Self::root(leaf_count) {
                     return None;
                 }
 
@@ -117,11 +119,6 @@ macro_rules! impl_tree_stdint {
             fn is_in_tree(&self, root: &Self) -> bool {
                 *self <= 2 * root
             }
-
-            #[cfg(any(feature = "secret_tree_access", feature = "private_message"))]
-            fn zero() -> Self {
-                0
-            }
         }
     };
 }
@@ -131,9 +128,9 @@ impl_tree_stdint!(u32);
 #[cfg(test)]
 impl_tree_stdint!(u64);
 
-pub fn leaf_lca_level(x: u32, y: u32) -> u32 {
-    let mut xn = x;
-    let mut yn = y;
+pub fn leaf_lca_level(x: LeafIndex, y: LeafIndex) -> u32 {
+    let mut xn = *x;
+    let mut yn = *y;
     let mut k = 0;
 
     while xn != yn {
@@ -145,7 +142,8 @@ pub fn leaf_lca_level(x: u32, y: u32) -> u32 {
     k
 }
 
-pub fn subtree(x: u32) -> (LeafIndex, LeafIndex) {
+pub fn subtree(x: NodeIndex) -> (LeafIndex, LeafIndex) {
+    let x = x.value();
     let breadth = 1 << x.trailing_ones();
     (
         LeafIndex((x + 1 - breadth) >> 1),
@@ -153,6 +151,106 @@ pub fn subtree(x: u32) -> (LeafIndex, LeafIndex) {
     )
 }
 
+/// Shape of a full binary tree, determined by its number of leaves.
+///
+/// Nodes of a tree with `n` leaves are numbered from `0` to `2n - 2` in
+/// in-order traversal, so that leaves have even indexes and parents have odd
+/// indexes. See [RFC 9420 Appendix C](https://www.rfc-editor.org/rfc/rfc9420.html#appendix-C).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TreeSize(u32);
+
+impl TreeSize {
+    /// Largest number of leaves that can be addressed with `u32` node indexes.
+    pub const MAX_LEAF_COUNT: u32 = 1 << 31;
+
+    /// Smallest full tree holding at least `leaf_count` leaves.
+    ///
+    /// Returns `None` if `leaf_count` is greater than [`Self::MAX_LEAF_COUNT`].
+    pub fn new(leaf_count: u32) -> Option<Self> {
+        (leaf_count <= Self::MAX_LEAF_COUNT).then(|| Self(leaf_count.max(1).next_power_of_two()))
+    }
+
+    /// Tree with `leaf_count` leaves, which must be a power of two.
+    pub(crate) fn from_leaf_count(leaf_count: u32) -> Self {
+        Self(leaf_count)
+    }
+
+    /// Number of leaves in the tree. This is always a power of two.
+    pub fn leaf_count(&self) -> u32 {
+        self.0
+    }
+
+    /// Number of nodes in the tree.
+    pub fn node_width(&self) -> u32 {
+        2 * (self.0 - 1) + 1
+    }
+
+    /// Root of the tree.
+    pub fn root(&self) -> NodeIndex {
+        NodeIndex::root(self.0)
+    }
+
+    /// Determine if `node` is part of the tree.
+    pub fn contains(&self, node: NodeIndex) -> bool {
+        node.is_in_tree(&self.root())
+    }
+
+    /// Determine if `leaf` is part of the tree.
+    pub fn contains_leaf(&self, leaf: LeafIndex) -> bool {
+        *leaf < self.0
+    }
+
+    /// Parent of `node`, or `None` if `node` is the root or is not part of
+    /// the tree.
+    pub fn parent(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.parent_sibling(node).map(|ps| ps.parent)
+    }
+
+    /// Sibling of `node`, or `None` if `node` is the root or is not part of
+    /// the tree.
+    pub fn sibling(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.parent_sibling(node).map(|ps| ps.sibling)
+    }
+
+    /// Direct path of `node`, ordered from the parent of `node` to the root.
+    ///
+    /// The result is empty if `node` is the root or is not part of the tree.
+    pub fn direct_path(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        node.direct_copath(self.0)
+            .into_iter()
+            .map(|cp| cp.path)
+            .collect()
+    }
+
+    /// Copath of `node`, i.e. the siblings of `node` and of each node of its
+    /// direct path except the root.
+    pub fn copath(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        node.direct_copath(self.0)
+            .into_iter()
+            .map(|cp| cp.copath)
+            .collect()
+    }
+
+    /// Lowest common ancestor of two leaves, or `None` if either leaf is not
+    /// part of the tree.
+    pub fn common_ancestor(&self, a: LeafIndex, b: LeafIndex) -> Option<NodeIndex> {
+        if !self.contains_leaf(a) || !self.contains_leaf(b) {
+            return None;
+        }
+
+        let level = leaf_lca_level(a, b);
+        let index = (u64::from(*a >> level) << (level + 1)) | ((1u64 << level) - 1);
+
+        Some(NodeIndex::new(index as u32))
+    }
+
+    fn parent_sibling(&self, node: NodeIndex) -> Option<ParentSibling<NodeIndex>> {
+        self.contains(node)
+            .then(|| node.parent_sibling(self.0))
+            .flatten()
+    }
+}
+
 pub struct BfsIterTopDown {
     level: usize,
     mask: usize,
@@ -238,7 +336,7 @@ mod tests {
 
             let (parent, sibling) = (0..n_nodes)
                 .map(|x| {
-                    x.parent_sibling(&n_leaves)
+                    x.parent_sibling(n_leaves)
                         .map(|ps| (ps.parent, ps.sibling))
                         .unzip()
                 })
@@ -247,7 +345,7 @@ mod tests {
             test_cases.push(TestCase {
                 n_leaves,
                 n_nodes,
-                root: n_leaves.root(),
+                root: u32::root(n_leaves),
                 left,
                 right,
                 parent,
@@ -268,14 +366,14 @@ mod tests {
 
         for case in test_cases {
             assert_eq!(node_width(case.n_leaves), case.n_nodes);
-            assert_eq!(case.n_leaves.root(), case.root);
+            assert_eq!(u32::root(case.n_leaves), case.root);
 
             for x in 0..case.n_nodes {
                 assert_eq!(x.left(), case.left[x as usize]);
                 assert_eq!(x.right(), case.right[x as usize]);
 
                 let (p, s) = x
-                    .parent_sibling(&case.n_leaves)
+                    .parent_sibling(case.n_leaves)
                     .map(|ps| (ps.parent, ps.sibling))
                     .unzip();
 
@@ -324,7 +422,7 @@ mod tests {
 
         for (i, item) in expected.iter().enumerate() {
             let path = (i as u32)
-                .direct_copath(&16)
+                .direct_copath(16)
                 .into_iter()
                 .map(|cp| cp.path)
                 .collect_vec();
@@ -372,7 +470,7 @@ mod tests {
 
         for (i, item) in expected.iter().enumerate() {
             let copath = (i as u32)
-                .direct_copath(&16)
+                .direct_copath(16)
                 .into_iter()
                 .map(|cp| cp.copath)
                 .collect_vec();
@@ -380,4 +478,130 @@ mod tests {
             assert_eq!(item, &copath)
         }
     }
+
+    fn sizes() -> impl Iterator<Item = TreeSize> {
+        (0..=10).map(|log| TreeSize::new(1 << log).unwrap())
+    }
+
+    fn nodes(size: TreeSize) -> impl Iterator<Item = NodeIndex> {
+        (0..size.node_width()).map(NodeIndex::new)
+    }
+
+    #[test]
+    fn tree_size_rounds_up_to_power_of_two() {
+        assert_eq!(TreeSize::new(0).unwrap().leaf_count(), 1);
+        assert_eq!(TreeSize::new(5).unwrap().leaf_count(), 8);
+        assert_eq!(TreeSize::new(8).unwrap().leaf_count(), 8);
+        assert_eq!(TreeSize::new(TreeSize::MAX_LEAF_COUNT + 1), None);
+    }
+
+    #[test]
+    fn leaf_and_node_index_round_trip() {
+        for i in 0..1024 {
+            let leaf = LeafIndex::new(i);
+            let node = leaf.node_index();
+
+            assert!(node.is_leaf());
+            assert_eq!(node.level(), 0);
+            assert_eq!(node.try_into_leaf(), Some(leaf));
+            assert_eq!(NodeIndex::new(2 * i + 1).try_into_leaf(), None);
+        }
+    }
+
+    #[test]
+    fn children_and_parent_are_consistent() {
+        for size in sizes() {
+            for node in nodes(size) {
+                if let (Some(left), Some(right)) = (node.left(), node.right()) {
+                    assert_eq!(left.level() + 1, node.level());
+                    assert_eq!(right.level() + 1, node.level());
+                    assert_eq!(size.parent(left), Some(node));
+                    assert_eq!(size.parent(right), Some(node));
+                    assert_eq!(size.sibling(left), Some(right));
+                    assert_eq!(size.sibling(right), Some(left));
+                } else {
+                    assert!(node.is_leaf());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn direct_path_ends_at_root() {
+        for size in sizes() {
+            for node in nodes(size) {
+                let path = size.direct_path(node);
+
+                if node == size.root() {
+                    assert!(path.is_empty());
+                } else {
+                    assert_eq!(path.last(), Some(&size.root()));
+                }
+
+                assert_eq!(path.len() as u32, size.root().level() - node.level());
+
+                path.iter().zip(size.copath(node)).for_each(|(p, s)| {
+                    assert_eq!(size.sibling(s).and_then(|s| size.parent(s)), Some(*p))
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn subtree_leaves_match_descendants() {
+        for size in sizes() {
+            for node in nodes(size) {
+                let leaves = (0..size.leaf_count())
+                    .map(LeafIndex::new)
+                    .filter(|l| {
+                        let n = l.node_index();
+                        n == node || size.direct_path(n).contains(&node)
+                    })
+                    .collect::<Vec<_>>();
+
+                let range = node.subtree_leaves();
+
+                assert_eq!(leaves.first(), Some(&range.start));
+                assert_eq!(leaves.last().map(|l| **l + 1), Some(*range.end));
+                assert!(leaves.iter().all(|l| node.covers(*l)));
+            }
+        }
+    }
+
+    #[test]
+    fn common_ancestor_is_lowest_shared_node() {
+        let size = TreeSize::new(32).unwrap();
+
+        for a in (0..32).map(LeafIndex::new) {
+            for b in (0..32).map(LeafIndex::new) {
+                let ancestor = size.common_ancestor(a, b).unwrap();
+
+                assert!(ancestor.covers(a) && ancestor.covers(b));
+
+                let covered_by_child = [ancestor.left(), ancestor.right()]
+                    .into_iter()
+                    .flatten()
+                    .any(|c| c.covers(a) && c.covers(b));
+
+                assert!(!covered_by_child);
+            }
+        }
+
+        assert_eq!(
+            size.common_ancestor(LeafIndex::new(0), LeafIndex::new(32)),
+            None
+        );
+    }
+
+    #[test]
+    fn nodes_outside_tree_have_no_relatives() {
+        let size = TreeSize::new(4).unwrap();
+        let outside = NodeIndex::new(size.node_width());
+
+        assert!(!size.contains(outside));
+        assert_eq!(size.parent(outside), None);
+        assert_eq!(size.sibling(outside), None);
+        assert!(size.direct_path(outside).is_empty());
+        assert!(size.copath(outside).is_empty());
+    }
 }
@@ -12,9 +12,6 @@ use mls_rs_core::extension::ExtensionList;
 
 use mls_rs_core::{error::IntoAnyError, identity::IdentityProvider};
 
-#[cfg(feature = "tree_index")]
-use mls_rs_core::identity::SigningIdentity;
-
 use math as tree_math;
 use node::{LeafIndex, NodeIndex, NodeVec};
 
@@ -24,14 +21,22 @@ use crate::client::MlsError;
 use crate::crypto::{self, CipherSuiteProvider, HpkeSecretKey};
 
 #[cfg(feature = "by_ref_proposal")]
-use crate::group::proposal::{AddProposal, UpdateProposal};
+use crate::group::proposal_filter::RejectedProposal;
 
-#[cfg(any(test, feature = "by_ref_proposal"))]
-use crate::group::proposal::RemoveProposal;
+#[cfg(test)]
+use crate::group::proposal::{Proposal, RemoveProposal};
 
+#[cfg(all(test, feature = "by_ref_proposal"))]
+use crate::group::proposal::UpdateProposal;
+
+use crate::group::mls_rules::{LeafPlacement, TreeLayoutRules};
 use crate::group::proposal_filter::ProposalBundle;
 use crate::tree_kem::tree_hash::TreeHashes;
 
+#[cfg(feature = "by_ref_proposal")]
+mod batch_edit;
+#[cfg(not(feature = "by_ref_proposal"))]
+mod batch_edit_lite;
 mod capabilities;
 pub(crate) mod hpke_encryption;
 mod lifetime;
@@ -40,6 +45,7 @@ pub mod node;
 pub mod parent_hash;
 pub mod path_secret;
 mod private;
+pub mod tree_diff;
 mod tree_hash;
 pub mod tree_validator;
 pub mod update_path;
@@ -49,7 +55,8 @@ pub use lifetime::*;
 pub(crate) use private::*;
 pub use update_path::*;
 
-use tree_index::*;
+#[cfg(feature = "tree_index")]
+use tree_index::TreeIndex;
 
 pub mod kem;
 pub mod leaf_node;
@@ -62,9 +69,6 @@ pub(crate) mod tree_utils;
 #[cfg(test)]
 mod interop_test_vectors;
 
-#[cfg(feature = "custom_proposal")]
-use crate::group::proposal::ProposalType;
-
 #[derive(Clone, Debug, MlsEncode, MlsDecode, MlsSize, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TreeKemPublic {
@@ -81,6 +85,24 @@ impl PartialEq for TreeKemPublic {
     }
 }
 
+/// Changes made to a [`TreeKemPublic`] by applying a [`ProposalBundle`].
+///
+/// All lists follow the order of the proposals in the bundle, which is
+/// canonical, so that replicas applying the same bundle get the same output.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BatchEditOutput {
+    /// Leaves of the added members, in the order of the add proposals.
+    pub added: Vec<LeafIndex>,
+    /// Removed leaves and the leaf nodes they held, in the order of the
+    /// remove proposals.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub removed: Vec<(LeafIndex, LeafNode)>,
+    /// Rejected proposals, removes then updates then adds, each in the order
+    /// of the proposals of that type.
+    #[cfg(feature = "by_ref_proposal")]
+    pub rejected: Vec<RejectedProposal>,
+}
+
 impl TreeKemPublic {
     pub fn new() -> TreeKemPublic {
         Default::default()
@@ -108,58 +130,6 @@ impl TreeKemPublic {
         Ok(tree)
     }
 
-    #[cfg(feature = "tree_index")]
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub(crate) async fn initialize_index_if_necessary<IP: IdentityProvider>(
-        &mut self,
-        identity_provider: &IP,
-        extensions: &ExtensionList,
-    ) -> Result<(), MlsError> {
-        if !self.index.is_initialized() {
-            self.index = TreeIndex::new();
-
-            for (leaf_index, leaf) in self.nodes.non_empty_leaves() {
-                index_insert(
-                    &mut self.index,
-                    leaf,
-                    leaf_index,
-                    identity_provider,
-                    extensions,
-                )
-                .await?;
-            }
-        }
-
-        Ok(())
-    }
-
-    #[cfg(feature = "tree_index")]
-    pub(crate) fn get_leaf_node_with_identity(&self, identity: &[u8]) -> Option<LeafIndex> {
-        self.index.get_leaf_index_with_identity(identity)
-    }
-
-    #[cfg(not(feature = "tree_index"))]
-    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub(crate) async fn get_leaf_node_with_identity<I: IdentityProvider>(
-        &self,
-        identity: &[u8],
-        id_provider: &I,
-        extensions: &ExtensionList,
-    ) -> Result<Option<LeafIndex>, MlsError> {
-        for (i, leaf) in self.nodes.non_empty_leaves() {
-            let leaf_id = id_provider
-                .identity(&leaf.signing_identity, extensions)
-                .await
-                .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
-
-            if leaf_id == identity {
-                return Ok(Some(i));
-            }
-        }
-
-        Ok(None)
-    }
-
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn derive<I: IdentityProvider>(
         leaf_node: LeafNode,
@@ -170,7 +140,7 @@ impl TreeKemPublic {
         let mut public_tree = TreeKemPublic::new();
 
         public_tree
-            .add_leaf(leaf_node, identity_provider, extensions, None)
+            .add_leaf(leaf_node, identity_provider, extensions, LeafIndex(0))
             .await?;
 
         let private_tree = TreeKemPrivate::new_self_leaf(LeafIndex(0), secret_key);
@@ -203,17 +173,6 @@ impl TreeKemPublic {
         )
     }
 
-    #[cfg(feature = "custom_proposal")]
-    pub fn can_support_proposal(&self, proposal_type: ProposalType) -> bool {
-        #[cfg(feature = "tree_index")]
-        return self.index.count_supporting_proposal(proposal_type) == self.occupied_leaf_count();
-
-        #[cfg(not(feature = "tree_index"))]
-        self.nodes
-            .non_empty_leaves()
-            .all(|(_, l)| l.capabilities.proposals.contains(&proposal_type))
-    }
-
     #[cfg(test)]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn add_leaves<I: IdentityProvider, CP: CipherSuiteProvider>(
@@ -226,8 +185,10 @@ impl TreeKemPublic {
         let mut added = vec![];
 
         for leaf in leaf_nodes.into_iter() {
+            let index = self.nodes.next_empty_leaf(start);
+
             start = self
-                .add_leaf(leaf, id_provider, &Default::default(), Some(start))
+                .add_leaf(leaf, id_provider, &Default::default(), index)
                 .await?;
             added.push(start);
         }
@@ -273,18 +234,12 @@ impl TreeKemPublic {
         CP: CipherSuiteProvider,
     {
         // Install the new leaf node
-        let existing_leaf = self.nodes.borrow_as_leaf_mut(sender)?;
+        let original_leaf_node = self.nodes.borrow_as_leaf(sender)?.clone();
 
-        #[cfg(feature = "tree_index")]
-        let original_leaf_node = existing_leaf.clone();
-
-        #[cfg(feature = "tree_index")]
-        let original_identity = identity_provider
-            .identity(&original_leaf_node.signing_identity, extensions)
-            .await
-            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+        self.unindex_leaf(&original_leaf_node, &identity_provider, extensions)
+            .await?;
 
-        *existing_leaf = update_path.leaf_node.clone();
+        *self.nodes.borrow_as_leaf_mut(sender)? = update_path.leaf_node.clone();
 
         // Update the rest of the nodes on the direct path
         let path = self.nodes.direct_copath(sender);
@@ -295,14 +250,7 @@ impl TreeKemPublic {
                 .transpose()?;
         }
 
-        #[cfg(feature = "tree_index")]
-        self.index.remove(&original_leaf_node, &original_identity);
-
-        index_insert(
-            #[cfg(feature = "tree_index")]
-            &mut self.index,
-            #[cfg(not(feature = "tree_index"))]
-            &self.nodes,
+        self.index_leaf(
             &update_path.leaf_node,
             sender,
             &identity_provider,
@@ -329,286 +277,104 @@ impl TreeKemPublic {
         Ok(())
     }
 
-    #[cfg(feature = "by_ref_proposal")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn batch_edit<I, CP>(
+    pub(crate) async fn add_leaf<I: IdentityProvider>(
         &mut self,
-        proposal_bundle: &mut ProposalBundle,
-        extensions: &ExtensionList,
+        leaf: LeafNode,
         id_provider: &I,
-        cipher_suite_provider: &CP,
-        filter: bool,
-    ) -> Result<Vec<LeafIndex>, MlsError>
-    where
-        I: IdentityProvider,
-        CP: CipherSuiteProvider,
-    {
-        // Apply removes (they commute with updates because they don't touch the same leaves)
-        for i in (0..proposal_bundle.remove_proposals().len()).rev() {
-            let index = proposal_bundle.remove_proposals()[i].proposal.to_remove;
-            let res = self.nodes.blank_leaf_node(index);
-
-            if res.is_ok() {
-                // This shouldn't fail if `blank_leaf_node` succedded.
-                self.nodes.blank_direct_path(index)?;
-            }
-
-            #[cfg(feature = "tree_index")]
-            if let Ok(old_leaf) = &res {
-                // If this fails, it's not because the proposal is bad.
-                let identity =
-                    identity(&old_leaf.signing_identity, id_provider, extensions).await?;
-
-                self.index.remove(old_leaf, &identity);
-            }
-
-            if proposal_bundle.remove_proposals()[i].is_by_value() || !filter {
-                res?;
-            } else if res.is_err() {
-                proposal_bundle.remove::<RemoveProposal>(i);
-            }
-        }
-
-        // Remove from the tree old leaves from updates
-        let mut partial_updates = vec![];
-        let senders = proposal_bundle.update_senders.iter().copied();
-
-        for (i, (p, index)) in proposal_bundle.updates.iter().zip(senders).enumerate() {
-            let new_leaf = p.proposal.leaf_node.clone();
-
-            match self.nodes.blank_leaf_node(index) {
-                Ok(old_leaf) => {
-                    #[cfg(feature = "tree_index")]
-                    let old_id =
-                        identity(&old_leaf.signing_identity, id_provider, extensions).await?;
-
-                    #[cfg(feature = "tree_index")]
-                    self.index.remove(&old_leaf, &old_id);
-
-                    partial_updates.push((index, old_leaf, new_leaf, i));
-                }
-                _ => {
-                    if !filter || !p.is_by_reference() {
-                        return Err(MlsError::UpdatingNonExistingMember);
-                    }
-                }
-            }
-        }
-
-        #[cfg(feature = "tree_index")]
-        let index_clone = self.index.clone();
-
-        let mut removed_leaves = vec![];
-        let mut updated_indices = vec![];
-        let mut bad_indices = vec![];
-
-        // Apply updates one by one. If there's an update which we can't apply or revert, we revert
-        // all updates.
-        for (index, old_leaf, new_leaf, i) in partial_updates.into_iter() {
-            #[cfg(feature = "tree_index")]
-            let res =
-                index_insert(&mut self.index, &new_leaf, index, id_provider, extensions).await;
-
-            #[cfg(not(feature = "tree_index"))]
-            let res = index_insert(&self.nodes, &new_leaf, index, id_provider, extensions).await;
-
-            let err = res.is_err();
-
-            if !filter {
-                res?;
-            }
-
-            if !err {
-                self.nodes.insert_leaf(index, new_leaf);
-                removed_leaves.push(old_leaf);
-                updated_indices.push(index);
-            } else {
-                #[cfg(feature = "tree_index")]
-                let res =
-                    index_insert(&mut self.index, &old_leaf, index, id_provider, extensions).await;
-
-                #[cfg(not(feature = "tree_index"))]
-                let res =
-                    index_insert(&self.nodes, &old_leaf, index, id_provider, extensions).await;
-
-                if res.is_ok() {
-                    self.nodes.insert_leaf(index, old_leaf);
-                    bad_indices.push(i);
-                } else {
-                    // Revert all updates and stop. We're already in the "filter" case, so we don't throw an error.
-                    #[cfg(feature = "tree_index")]
-                    {
-                        self.index = index_clone;
-                    }
-
-                    removed_leaves
-                        .into_iter()
-                        .zip(updated_indices.iter())
-                        .for_each(|(leaf, index)| self.nodes.insert_leaf(*index, leaf));
-
-                    updated_indices = vec![];
-                    break;
-                }
-            }
-        }
-
-        // If we managed to update something, blank direct paths
-        updated_indices
-            .iter()
-            .try_for_each(|index| self.nodes.blank_direct_path(*index).map(|_| ()))?;
-
-        // Remove rejected updates from applied proposals
-        if updated_indices.is_empty() {
-            // This takes care of the "revert all" scenario
-            proposal_bundle.updates = vec![];
-        } else {
-            for i in bad_indices.into_iter().rev() {
-                proposal_bundle.remove::<UpdateProposal>(i);
-                proposal_bundle.update_senders.remove(i);
-            }
-        }
-
-        // Apply adds
-        let mut start = LeafIndex(0);
-        let mut added = vec![];
-        let mut bad_indexes = vec![];
-
-        for i in 0..proposal_bundle.additions.len() {
-            let leaf = proposal_bundle.additions[i]
-                .proposal
-                .key_package
-                .leaf_node
-                .clone();
-
-            let res = self
-                .add_leaf(leaf, id_provider, extensions, Some(start))
-                .await;
-
-            if let Ok(index) = res {
-                start = index;
-                added.push(start);
-            } else if proposal_bundle.additions[i].is_by_value() || !filter {
-                res?;
-            } else {
-                bad_indexes.push(i);
-            }
-        }
-
-        for i in bad_indexes.into_iter().rev() {
-            proposal_bundle.remove::<AddProposal>(i);
-        }
-
-        self.nodes.trim();
-
-        let updated_leaves = proposal_bundle
-            .remove_proposals()
-            .iter()
-            .map(|p| p.proposal.to_remove)
-            .chain(updated_indices)
-            .chain(added.iter().copied())
-            .collect_vec();
-
-        self.update_hashes(&updated_leaves, cipher_suite_provider)
-            .await?;
-
-        Ok(added)
+        extensions: &ExtensionList,
+        index: LeafIndex,
+    ) -> Result<LeafIndex, MlsError> {
+        self.add_leaf_with_identity(leaf, None, id_provider, extensions, index)
+            .await
     }
 
-    #[cfg(not(feature = "by_ref_proposal"))]
+    /// Same as [`Self::add_leaf`] using the identity of `leaf` if it was already resolved.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub async fn batch_edit_lite<I, CP>(
+    async fn add_leaf_with_identity<I: IdentityProvider>(
         &mut self,
-        proposal_bundle: &ProposalBundle,
-        extensions: &ExtensionList,
+        leaf: LeafNode,
+        identity: Option<Vec<u8>>,
         id_provider: &I,
-        cipher_suite_provider: &CP,
-    ) -> Result<Vec<LeafIndex>, MlsError>
-    where
-        I: IdentityProvider,
-        CP: CipherSuiteProvider,
-    {
-        // Apply removes
-        for p in &proposal_bundle.removals {
-            let index = p.proposal.to_remove;
-
-            #[cfg(feature = "tree_index")]
-            {
-                // If this fails, it's not because the proposal is bad.
-                let old_leaf = self.nodes.blank_leaf_node(index)?;
-
-                let identity =
-                    identity(&old_leaf.signing_identity, id_provider, extensions).await?;
-
-                self.index.remove(&old_leaf, &identity);
-            }
-
-            #[cfg(not(feature = "tree_index"))]
-            self.nodes.blank_leaf_node(index)?;
-
-            self.nodes.blank_direct_path(index)?;
-        }
-
-        // Apply adds
-        let mut start = LeafIndex(0);
-        let mut added = vec![];
-
-        for p in &proposal_bundle.additions {
-            let leaf = p.proposal.key_package.leaf_node.clone();
-            start = self
-                .add_leaf(leaf, id_provider, extensions, Some(start))
-                .await?;
-            added.push(start);
-        }
-
-        self.nodes.trim();
-
-        let updated_leaves = proposal_bundle
-            .remove_proposals()
-            .iter()
-            .map(|p| p.proposal.to_remove)
-            .chain(added.iter().copied())
-            .collect_vec();
-
-        self.update_hashes(&updated_leaves, cipher_suite_provider)
+        extensions: &ExtensionList,
+        index: LeafIndex,
+    ) -> Result<LeafIndex, MlsError> {
+        self.index_leaf_with_identity(&leaf, index, identity, id_provider, extensions)
             .await?;
 
-        Ok(added)
+        self.nodes.insert_leaf(index, leaf);
+        self.update_unmerged(index)?;
+
+        Ok(index)
     }
 
+    /// Blank the leaf at `index` without updating the tree hashes, returning the removed leaf.
+    #[cfg(feature = "external_client")]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    pub(crate) async fn add_leaf<I: IdentityProvider>(
+    pub(crate) async fn remove_leaf<I: IdentityProvider>(
         &mut self,
-        leaf: LeafNode,
+        index: LeafIndex,
         id_provider: &I,
         extensions: &ExtensionList,
-        start: Option<LeafIndex>,
-    ) -> Result<LeafIndex, MlsError> {
-        let index = self.nodes.next_empty_leaf(start.unwrap_or(LeafIndex(0)));
+    ) -> Result<LeafNode, MlsError> {
+        let leaf = self.nodes.blank_leaf_node(index)?;
+        self.unindex_leaf(&leaf, id_provider, extensions).await?;
 
-        #[cfg(feature = "tree_index")]
-        index_insert(&mut self.index, &leaf, index, id_provider, extensions).await?;
+        Ok(leaf)
+    }
 
-        #[cfg(not(feature = "tree_index"))]
-        index_insert(&self.nodes, &leaf, index, id_provider, extensions).await?;
+    /// Blank all parent nodes and forget the tree hashes, keeping only the leaves. The tree can
+    /// then only be edited with functions that do not update the tree hashes.
+    #[cfg(feature = "external_client")]
+    pub(crate) fn discard_parent_nodes(&mut self) {
+        self.nodes
+            .iter_mut()
+            .skip(1)
+            .step_by(2)
+            .for_each(|node| *node = None);
 
-        self.nodes.insert_leaf(index, leaf);
-        self.update_unmerged(index)?;
+        self.tree_hashes = TreeHashes::default();
+    }
 
-        Ok(index)
+    /// Leaf at which a new member is inserted according to `placement`. No leaf before `start` may
+    /// be blank.
+    pub(crate) fn placement_index(
+        &self,
+        placement: LeafPlacement,
+        start: LeafIndex,
+    ) -> Result<LeafIndex, MlsError> {
+        match placement {
+            LeafPlacement::LeftmostBlank => Ok(self.nodes.next_empty_leaf(start)),
+            LeafPlacement::RightmostBlank => Ok(self.nodes.last_empty_leaf()),
+            LeafPlacement::Index(index) => {
+                let end = self.nodes.leaf_count();
+
+                let blank = index < end
+                    && matches!(
+                        self.nodes.get(LeafIndex(index).node_index().as_usize()),
+                        Some(None)
+                    );
+
+                (blank || index == end)
+                    .then_some(LeafIndex(index))
+                    .ok_or(MlsError::InvalidLeafPlacement(index))
+            }
+        }
     }
-}
 
-#[cfg(feature = "tree_index")]
-#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-async fn identity<I: IdentityProvider>(
-    signing_id: &SigningIdentity,
-    provider: &I,
-    extensions: &ExtensionList,
-) -> Result<Vec<u8>, MlsError> {
-    provider
-        .identity(signing_id, extensions)
-        .await
-        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
+    /// Leaf of the new member with `leaf` chosen by `tree_rules`, and whether that leaf can be
+    /// used as the start of the search for the next leftmost blank leaf.
+    pub(crate) fn place_new_leaf(
+        &self,
+        leaf: &LeafNode,
+        tree_rules: &dyn TreeLayoutRules,
+        start: LeafIndex,
+    ) -> Result<(LeafIndex, bool), MlsError> {
+        let placement = tree_rules.place(&self.roster(), &leaf.signing_identity)?;
+        let index = self.placement_index(placement, start)?;
+
+        Ok((index, placement == LeafPlacement::LeftmostBlank))
+    }
 }
 
 #[cfg(feature = "std")]
@@ -619,7 +385,7 @@ impl Display for TreeKemPublic {
 }
 
 #[cfg(test)]
-use crate::group::{proposal::Proposal, proposal_filter::ProposalSource, Sender};
+use crate::group::{mls_rules::DefaultMlsRules, proposal_filter::ProposalSource, Sender};
 
 #[cfg(test)]
 impl TreeKemPublic {
@@ -647,6 +413,7 @@ impl TreeKemPublic {
             &Default::default(),
             identity_provider,
             cipher_suite_provider,
+            &DefaultMlsRules::new(),
             true,
         )
         .await?;
@@ -665,8 +432,6 @@ impl TreeKemPublic {
         I: IdentityProvider,
         CP: CipherSuiteProvider,
     {
-        let old_tree = self.clone();
-
         let proposals = indexes
             .iter()
             .copied()
@@ -679,33 +444,29 @@ impl TreeKemPublic {
         }
 
         #[cfg(feature = "by_ref_proposal")]
-        self.batch_edit(
-            &mut bundle,
-            &Default::default(),
-            identity_provider,
-            cipher_suite_provider,
-            true,
-        )
-        .await?;
+        let output = self
+            .batch_edit(
+                &mut bundle,
+                &Default::default(),
+                identity_provider,
+                cipher_suite_provider,
+                &DefaultMlsRules::new(),
+                true,
+            )
+            .await?;
 
         #[cfg(not(feature = "by_ref_proposal"))]
-        self.batch_edit_lite(
-            &bundle,
-            &Default::default(),
-            identity_provider,
-            cipher_suite_provider,
-        )
-        .await?;
+        let output = self
+            .batch_edit_lite(
+                &bundle,
+                &Default::default(),
+                identity_provider,
+                cipher_suite_provider,
+                &DefaultMlsRules::new(),
+            )
+            .await?;
 
-        bundle
-            .removals
-            .iter()
-            .map(|p| {
-                let index = p.proposal.to_remove;
-                let leaf = old_tree.get_leaf_node(index)?.clone();
-                Ok((index, leaf))
-            })
-            .collect()
+        Ok(output.removed)
     }
 
     pub fn get_leaf_nodes(&self) -> Vec<&LeafNode> {
@@ -940,9 +701,11 @@ mod tests {
     #[cfg(feature = "custom_proposal")]
     use crate::group::proposal::ProposalType;
 
+    use crate::client::LeafDataKind;
+    use crate::group::mls_rules::LeafPlacement;
     use crate::identity::basic::BasicIdentityProvider;
     use crate::tree_kem::leaf_node::LeafNode;
-    use crate::tree_kem::node::{LeafIndex, Node, NodeIndex, NodeTypeResolver, Parent};
+    use crate::tree_kem::node::{LeafIndex, Node, NodeTypeResolver, Parent};
     use crate::tree_kem::parent_hash::ParentHash;
     use crate::tree_kem::test_utils::{get_test_leaf_nodes, get_test_tree};
     use crate::tree_kem::{MlsError, TreeKemPublic};
@@ -954,10 +717,23 @@ mod tests {
     #[cfg(feature = "by_ref_proposal")]
     use alloc::boxed::Box;
 
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    use crate::{
+        identity::{CredentialType, SigningIdentity},
+        time::MlsTime,
+    };
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    use core::sync::atomic::Ordering;
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    use mls_rs_core::{extension::ExtensionList, identity::IdentityProvider};
+
     #[cfg(feature = "by_ref_proposal")]
     use crate::{
         client::test_utils::TEST_PROTOCOL_VERSION,
         group::{
+            mls_rules::DefaultMlsRules,
             proposal::{Proposal, RemoveProposal, UpdateProposal},
             proposal_filter::{ProposalBundle, ProposalSource},
             proposal_ref::ProposalRef,
@@ -1090,7 +866,10 @@ mod tests {
             .add_leaves(key_packages, &BasicIdentityProvider, &cipher_suite_provider)
             .await;
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(_)));
+        assert_matches!(
+            res,
+            Err(MlsError::DuplicateLeafData(_, LeafDataKind::SignatureKey))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -1207,7 +986,7 @@ mod tests {
 
         // Verify that the direct path has been cleared
         tree.nodes.direct_copath(LeafIndex(0)).iter().for_each(|n| {
-            assert!(tree.nodes[n.path as usize].is_none());
+            assert!(tree.nodes[n.path.as_usize()].is_none());
         });
     }
 
@@ -1317,7 +1096,7 @@ mod tests {
 
         // There should be a blank in the tree
         assert_eq!(
-            tree.nodes.get(NodeIndex::from(to_remove) as usize).unwrap(),
+            tree.nodes.get(to_remove.node_index().as_usize()).unwrap(),
             &None
         );
     }
@@ -1353,7 +1132,7 @@ mod tests {
         // The location of key_packages[1] should now be blank
         let removed_location = tree
             .nodes
-            .get(NodeIndex::from(LeafIndex(2)) as usize)
+            .get(LeafIndex(2).node_index().as_usize())
             .unwrap();
 
         assert_eq!(removed_location, &None);
@@ -1444,6 +1223,7 @@ mod tests {
             &Default::default(),
             &BasicIdentityProvider,
             &cipher_suite_provider,
+            &DefaultMlsRules::new(),
             true,
         )
         .await
@@ -1454,6 +1234,244 @@ mod tests {
         assert_eq!(bundle.update_proposals().len(), 1);
     }
 
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn batch_edit_lists_rejected_proposals_in_bundle_order() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = get_test_tree(TEST_CIPHER_SUITE).await.public;
+        let mut bundle = ProposalBundle::default();
+
+        let refs = [1, 2, 3].map(|i| ProposalRef::new_fake(vec![i]));
+
+        // None of the removed leaves exist
+        for (i, pref) in refs.iter().enumerate() {
+            let remove = Proposal::Remove(RemoveProposal {
+                to_remove: LeafIndex(10 + i as u32),
+            });
+
+            bundle.add(
+                remove,
+                Sender::Member(0),
+                ProposalSource::ByReference(pref.clone()),
+            );
+        }
+
+        let rejected = tree
+            .batch_edit(
+                &mut bundle,
+                &Default::default(),
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+                &DefaultMlsRules::new(),
+                true,
+            )
+            .await
+            .unwrap()
+            .rejected;
+
+        let rejected_refs = rejected.iter().map(|r| r.proposal_ref().unwrap());
+
+        assert!(rejected_refs.eq(refs.iter()));
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn removed_leaves_are_listed_in_bundle_order() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = get_test_tree(TEST_CIPHER_SUITE).await.public;
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let to_remove = vec![LeafIndex(3), LeafIndex(1), LeafIndex(2)];
+
+        let removed = tree
+            .remove_leaves(
+                to_remove.clone(),
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+            )
+            .await
+            .unwrap();
+
+        assert!(removed.iter().map(|(index, _)| index).eq(to_remove.iter()));
+    }
+
+    #[cfg(feature = "by_ref_proposal")]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn batch_edit_places_adds_according_to_rules() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let mut tree = get_test_tree(TEST_CIPHER_SUITE).await.public;
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        let mut bundle = ProposalBundle::default();
+
+        for to_remove in [LeafIndex(1), LeafIndex(2)] {
+            let remove = Proposal::Remove(RemoveProposal { to_remove });
+            bundle.add(remove, Sender::Member(0), ProposalSource::ByValue);
+        }
+
+        for name in ["D", "E", "F"] {
+            let kp = test_key_package(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, name).await;
+            let add = Proposal::Add(Box::new(kp.into()));
+            bundle.add(add, Sender::Member(0), ProposalSource::ByValue);
+        }
+
+        let rules = DefaultMlsRules::new().with_leaf_placement(LeafPlacement::RightmostBlank);
+
+        let output = tree
+            .batch_edit(
+                &mut bundle,
+                &Default::default(),
+                &BasicIdentityProvider,
+                &cipher_suite_provider,
+                &rules,
+                true,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.added, [LeafIndex(2), LeafIndex(1), LeafIndex(4)]);
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    #[derive(Default)]
+    struct CountingIdentityProvider {
+        identity_calls: core::sync::atomic::AtomicUsize,
+        identities_calls: core::sync::atomic::AtomicUsize,
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    #[cfg_attr(mls_build_async, maybe_async::must_be_async)]
+    impl IdentityProvider for CountingIdentityProvider {
+        type Error = <BasicIdentityProvider as IdentityProvider>::Error;
+
+        async fn validate_member(
+            &self,
+            _signing_identity: &SigningIdentity,
+            _timestamp: Option<MlsTime>,
+            _extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn validate_external_sender(
+            &self,
+            _signing_identity: &SigningIdentity,
+            _timestamp: Option<MlsTime>,
+            _extensions: Option<&ExtensionList>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn identity(
+            &self,
+            signing_identity: &SigningIdentity,
+            extensions: &ExtensionList,
+        ) -> Result<Vec<u8>, Self::Error> {
+            self.identity_calls.fetch_add(1, Ordering::SeqCst);
+
+            BasicIdentityProvider
+                .identity(signing_identity, extensions)
+                .await
+        }
+
+        async fn identities(
+            &self,
+            signing_identities: &[&SigningIdentity],
+            extensions: &ExtensionList,
+        ) -> Result<Vec<Vec<u8>>, Self::Error> {
+            self.identities_calls.fetch_add(1, Ordering::SeqCst);
+
+            BasicIdentityProvider
+                .identities(signing_identities, extensions)
+                .await
+        }
+
+        async fn valid_successor(
+            &self,
+            _predecessor: &SigningIdentity,
+            _successor: &SigningIdentity,
+            _extensions: &ExtensionList,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        fn supported_types(&self) -> Vec<CredentialType> {
+            BasicIdentityProvider.supported_types()
+        }
+    }
+
+    #[cfg(all(feature = "by_ref_proposal", feature = "tree_index"))]
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn batch_edit_resolves_identities_of_new_leaves_at_once() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = get_test_tree(TEST_CIPHER_SUITE).await.public;
+        let mut bundle = ProposalBundle::default();
+
+        for i in 0..20 {
+            let name = alloc::format!("member {i}");
+            let kp = test_key_package(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE, &name).await;
+            let add = Proposal::Add(Box::new(kp.into()));
+            bundle.add(add, Sender::Member(0), ProposalSource::ByValue);
+        }
+
+        let id_provider = CountingIdentityProvider::default();
+
+        let output = tree
+            .batch_edit(
+                &mut bundle,
+                &Default::default(),
+                &id_provider,
+                &cipher_suite_provider,
+                &DefaultMlsRules::new(),
+                true,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.added.len(), 20);
+        assert_eq!(id_provider.identities_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(id_provider.identity_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn leaf_placement_index_must_be_blank_or_after_last_leaf() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        let mut tree = get_test_tree(TEST_CIPHER_SUITE).await.public;
+        let leaf_nodes = get_test_leaf_nodes(TEST_CIPHER_SUITE).await;
+
+        tree.add_leaves(leaf_nodes, &BasicIdentityProvider, &cipher_suite_provider)
+            .await
+            .unwrap();
+
+        tree.nodes.blank_leaf_node(LeafIndex(2)).unwrap();
+
+        let place = |placement| tree.placement_index(placement, LeafIndex(0));
+
+        assert_eq!(place(LeafPlacement::Index(2)).unwrap(), LeafIndex(2));
+        assert_eq!(place(LeafPlacement::Index(4)).unwrap(), LeafIndex(4));
+        assert_eq!(place(LeafPlacement::RightmostBlank).unwrap(), LeafIndex(2));
+
+        assert_matches!(
+            place(LeafPlacement::Index(1)),
+            Err(MlsError::InvalidLeafPlacement(1))
+        );
+
+        assert_matches!(
+            place(LeafPlacement::Index(5)),
+            Err(MlsError::InvalidLeafPlacement(5))
+        );
+    }
+
     #[cfg(feature = "custom_proposal")]
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn custom_proposal_support() {
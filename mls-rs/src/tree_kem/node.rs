@@ -5,14 +5,16 @@
 use super::leaf_node::LeafNode;
 use crate::client::MlsError;
 use crate::crypto::HpkePublicKey;
+use crate::group::mls_rules::TreeTruncation;
 use crate::tree_kem::math as tree_math;
+use crate::tree_kem::math::TreeSize;
 use crate::tree_kem::parent_hash::ParentHash;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::hash::Hash;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range};
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
-use tree_math::{CopathNode, TreeIndex};
+use tree_math::{CopathNode, ParentSibling, TreeIndex};
 
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -33,6 +35,15 @@ impl LeafIndex {
     pub fn new(i: u32) -> Self {
         Self(i)
     }
+
+    /// Index of the node holding this leaf in the array representation of the tree.
+    ///
+    /// Leaf indexes of a tree are below [`TreeSize::MAX_LEAF_COUNT`](crate::tree_math::TreeSize::MAX_LEAF_COUNT),
+    /// larger indexes overflow.
+    #[inline]
+    pub fn node_index(&self) -> NodeIndex {
+        NodeIndex(self.0 * 2)
+    }
 }
 
 impl Deref for LeafIndex {
@@ -43,19 +54,108 @@ impl Deref for LeafIndex {
     }
 }
 
-impl From<&LeafIndex> for NodeIndex {
-    fn from(leaf_index: &LeafIndex) -> Self {
-        leaf_index.0 * 2
+/// Index of a node, either leaf or parent, in the array representation of the tree.
+///
+/// Leaf and node indexes are distinct types so that converting between the two
+/// always goes through [`LeafIndex::node_index`] and [`NodeIndex::try_into_leaf`].
+#[derive(
+    Clone, Copy, Debug, Default, Ord, PartialEq, PartialOrd, Hash, Eq, MlsSize, MlsEncode, MlsDecode,
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeIndex(u32);
+
+impl NodeIndex {
+    #[inline]
+    pub fn new(i: u32) -> Self {
+        Self(i)
+    }
+
+    #[inline]
+    pub fn value(&self) -> u32 {
+        self.0
     }
-}
 
-impl From<LeafIndex> for NodeIndex {
-    fn from(leaf_index: LeafIndex) -> Self {
-        leaf_index.0 * 2
+    /// Position of this node in a `NodeVec`.
+    #[inline]
+    pub(crate) fn as_usize(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// Leaf index of this node, or `None` if this node is a parent.
+    #[inline]
+    pub fn try_into_leaf(&self) -> Option<LeafIndex> {
+        self.is_leaf().then_some(LeafIndex(self.0 >> 1))
+    }
+
+    /// Determine if this node is a leaf.
+    #[inline]
+    pub fn is_leaf(&self) -> bool {
+        self.0.is_leaf()
+    }
+
+    /// Level of this node in the tree. Leaves are at level 0.
+    #[inline]
+    pub fn level(&self) -> u32 {
+        self.0.trailing_ones()
+    }
+
+    /// Left child of this node, or `None` if this node is a leaf.
+    pub fn left(&self) -> Option<NodeIndex> {
+        (!self.is_leaf()).then(|| self.left_unchecked())
+    }
+
+    /// Right child of this node, or `None` if this node is a leaf.
+    pub fn right(&self) -> Option<NodeIndex> {
+        (!self.is_leaf()).then(|| self.right_unchecked())
+    }
+
+    /// Range of leaves covered by the subtree rooted at this node.
+    pub fn subtree_leaves(&self) -> Range<LeafIndex> {
+        let (start, end) = tree_math::subtree(*self);
+        start..end
+    }
+
+    /// Determine if the subtree rooted at this node contains `leaf`.
+    pub fn covers(&self, leaf: LeafIndex) -> bool {
+        self.subtree_leaves().contains(&leaf)
     }
 }
 
-pub(crate) type NodeIndex = u32;
+impl TreeIndex for NodeIndex {
+    type LeafCount = u32;
+
+    #[inline]
+    fn root(leaf_count: u32) -> Self {
+        Self(u32::root(leaf_count))
+    }
+
+    #[inline]
+    fn left_unchecked(&self) -> Self {
+        Self(self.0.left_unchecked())
+    }
+
+    #[inline]
+    fn right_unchecked(&self) -> Self {
+        Self(self.0.right_unchecked())
+    }
+
+    fn parent_sibling(&self, leaf_count: u32) -> Option<ParentSibling<Self>> {
+        self.0
+            .parent_sibling(leaf_count)
+            .map(|ps| ParentSibling::new(Self(ps.parent), Self(ps.sibling)))
+    }
+
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.0.is_leaf()
+    }
+
+    #[inline]
+    fn is_in_tree(&self, root: &Self) -> bool {
+        self.0.is_in_tree(&root.0)
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, MlsSize, MlsEncode, MlsDecode)]
 #[allow(clippy::large_enum_variant)]
@@ -184,16 +284,38 @@ impl NodeVec {
         (self.len() as u32 / 2 + 1).next_power_of_two()
     }
 
+    /// Shape of the full tree holding the nodes of the array.
+    #[inline]
+    pub fn tree_size(&self) -> TreeSize {
+        TreeSize::from_leaf_count(self.total_leaf_count())
+    }
+
+    /// Number of leaves in the array, not counting the blank leaves implied by
+    /// [`Self::total_leaf_count`].
+    pub fn leaf_count(&self) -> u32 {
+        (self.len() as u32 + 1) >> 1
+    }
+
+    #[inline]
+    pub fn root(&self) -> NodeIndex {
+        NodeIndex::root(self.total_leaf_count())
+    }
+
+    #[inline]
+    pub fn parent_sibling(&self, index: NodeIndex) -> Option<ParentSibling<NodeIndex>> {
+        index.parent_sibling(self.total_leaf_count())
+    }
+
     #[inline]
     pub fn borrow_node(&self, index: NodeIndex) -> Result<&Option<Node>, MlsError> {
         Ok(self.get(self.validate_index(index)?).unwrap_or(&None))
     }
 
     fn validate_index(&self, index: NodeIndex) -> Result<usize, MlsError> {
-        if (index as usize) >= self.len().next_power_of_two() {
-            Err(MlsError::InvalidNodeIndex(index))
+        if index.as_usize() >= self.len().next_power_of_two() {
+            Err(MlsError::InvalidNodeIndex(index.value()))
         } else {
-            Ok(index as usize)
+            Ok(index.as_usize())
         }
     }
 
@@ -217,7 +339,7 @@ impl NodeVec {
             .enumerate()
             .skip(1)
             .step_by(2)
-            .map(|(i, n)| (i as NodeIndex, n))
+            .map(|(i, n)| (NodeIndex(i as u32), n))
             .filter_map(|(i, n)| n.as_parent().ok().map(|p| (i, p)))
     }
 
@@ -226,15 +348,15 @@ impl NodeVec {
     }
 
     pub fn direct_copath(&self, index: LeafIndex) -> Vec<CopathNode<NodeIndex>> {
-        NodeIndex::from(index).direct_copath(&self.total_leaf_count())
+        index.node_index().direct_copath(self.total_leaf_count())
     }
 
     // Section 8.4
     // The filtered direct path of a node is obtained from the node's direct path by removing
     // all nodes whose child on the nodes's copath has an empty resolution
     pub fn filtered(&self, index: LeafIndex) -> Result<Vec<bool>, MlsError> {
-        Ok(NodeIndex::from(index)
-            .direct_copath(&self.total_leaf_count())
+        Ok(self
+            .direct_copath(index)
             .into_iter()
             .map(|cp| self.is_resolution_empty(cp.copath))
             .collect())
@@ -247,12 +369,12 @@ impl NodeVec {
 
     #[inline]
     pub fn is_leaf(&self, index: NodeIndex) -> bool {
-        index % 2 == 0
+        index.is_leaf()
     }
 
     // Blank a previously filled leaf node, and return the existing leaf
     pub fn blank_leaf_node(&mut self, leaf_index: LeafIndex) -> Result<LeafNode, MlsError> {
-        let node_index = self.validate_index(leaf_index.into())?;
+        let node_index = self.validate_index(leaf_index.node_index())?;
 
         match self.get_mut(node_index).and_then(Option::take) {
             Some(Node::Leaf(l)) => Ok(l),
@@ -262,7 +384,7 @@ impl NodeVec {
 
     pub fn blank_direct_path(&mut self, leaf: LeafIndex) -> Result<(), MlsError> {
         for i in self.direct_copath(leaf) {
-            if let Some(n) = self.get_mut(i.path as usize) {
+            if let Some(n) = self.get_mut(i.path.as_usize()) {
                 *n = None
             }
         }
@@ -277,6 +399,21 @@ impl NodeVec {
         }
     }
 
+    // Remove trailing blank elements allowed by `truncation`
+    pub(crate) fn truncate(&mut self, truncation: TreeTruncation) {
+        match truncation {
+            TreeTruncation::Trailing => self.trim(),
+            TreeTruncation::KeepLeaves(min_leaves) => {
+                let min_len = (min_leaves as usize * 2).saturating_sub(1);
+
+                while self.len() > min_len && self.last() == Some(&None) {
+                    self.pop();
+                }
+            }
+            TreeTruncation::Never => {}
+        }
+    }
+
     pub fn borrow_as_parent(&self, node_index: NodeIndex) -> Result<&Parent, MlsError> {
         self.borrow_node(node_index).and_then(|n| n.as_parent())
     }
@@ -285,22 +422,22 @@ impl NodeVec {
         let index = self.validate_index(node_index)?;
 
         self.get_mut(index)
-            .ok_or(MlsError::InvalidNodeIndex(node_index))?
+            .ok_or(MlsError::InvalidNodeIndex(node_index.value()))?
             .as_parent_mut()
     }
 
     pub fn borrow_as_leaf_mut(&mut self, index: LeafIndex) -> Result<&mut LeafNode, MlsError> {
-        let node_index = NodeIndex::from(index);
+        let node_index = index.node_index();
         let index = self.validate_index(node_index)?;
 
         self.get_mut(index)
-            .ok_or(MlsError::InvalidNodeIndex(node_index))?
+            .ok_or(MlsError::InvalidNodeIndex(node_index.value()))?
             .as_leaf_mut()
     }
 
     pub fn borrow_as_leaf(&self, index: LeafIndex) -> Result<&LeafNode, MlsError> {
-        let node_index = NodeIndex::from(index);
-        self.borrow_node(node_index).and_then(|n| n.as_leaf())
+        self.borrow_node(index.node_index())
+            .and_then(|n| n.as_leaf())
     }
 
     pub fn borrow_or_fill_node_as_parent(
@@ -315,7 +452,7 @@ impl NodeVec {
         }
 
         self.get_mut(index)
-            .ok_or(MlsError::InvalidNodeIndex(node_index))
+            .ok_or(MlsError::InvalidNodeIndex(node_index.value()))
             .and_then(|n| {
                 if n.is_none() {
                     *n = Parent {
@@ -334,11 +471,11 @@ impl NodeVec {
         let mut resolution = vec![];
 
         while let Some(index) = indexes.pop() {
-            if let Some(Some(node)) = self.get(index as usize) {
+            if let Some(Some(node)) = self.get(index.as_usize()) {
                 resolution.push(index);
 
                 if let Node::Parent(p) = node {
-                    resolution.extend(p.unmerged_leaves.iter().map(NodeIndex::from));
+                    resolution.extend(p.unmerged_leaves.iter().map(LeafIndex::node_index));
                 }
             } else if !index.is_leaf() {
                 indexes.push(index.right_unchecked());
@@ -358,7 +495,7 @@ impl NodeVec {
         let mut resolution_len = 0;
 
         while let Some(index) = indexes.pop() {
-            if let Some(Some(node)) = self.get(index as usize) {
+            if let Some(Some(node)) = self.get(index.as_usize()) {
                 if Some(index) == to_find || to_find.is_none() {
                     return Some(resolution_len);
                 }
@@ -366,7 +503,7 @@ impl NodeVec {
                 resolution_len += 1;
 
                 if let Node::Parent(p) = node {
-                    indexes.extend(p.unmerged_leaves.iter().map(NodeIndex::from));
+                    indexes.extend(p.unmerged_leaves.iter().map(LeafIndex::node_index));
                 }
             } else if !index.is_leaf() {
                 indexes.push(index.right_unchecked());
@@ -382,23 +519,32 @@ impl NodeVec {
     }
 
     pub(crate) fn next_empty_leaf(&self, start: LeafIndex) -> LeafIndex {
-        let mut n = NodeIndex::from(start) as usize;
+        let mut leaf = start;
 
-        while n < self.len() {
-            if self.0[n].is_none() {
-                return LeafIndex((n as u32) >> 1);
+        while let Some(node) = self.get(leaf.node_index().as_usize()) {
+            if node.is_none() {
+                return leaf;
             }
 
-            n += 2;
+            leaf = LeafIndex(leaf.0 + 1);
         }
 
-        LeafIndex((self.len() as u32 + 1) >> 1)
+        // First leaf past the end of the array
+        LeafIndex(self.leaf_count())
+    }
+
+    pub(crate) fn last_empty_leaf(&self) -> LeafIndex {
+        self.iter()
+            .step_by(2)
+            .rposition(|node| node.is_none())
+            .map(|i| LeafIndex(i as u32))
+            .unwrap_or(LeafIndex(self.leaf_count()))
     }
 
     /// If `index` fits in the current tree, inserts `leaf` at `index`. Else, inserts `leaf` as the
     /// last leaf
     pub fn insert_leaf(&mut self, index: LeafIndex, leaf: LeafNode) {
-        let node_index = (*index as usize) << 1;
+        let node_index = index.node_index().as_usize();
 
         if node_index > self.len() {
             self.push(None);
@@ -475,11 +621,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn leaf_and_node_index_conversions() {
+        (0..16).for_each(|i| {
+            let node = LeafIndex(i).node_index();
+            assert_eq!(node.value(), 2 * i);
+            assert_eq!(node.try_into_leaf(), Some(LeafIndex(i)));
+            assert_eq!(NodeIndex::new(2 * i + 1).try_into_leaf(), None);
+        });
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_direct_path() {
         let test_vec = get_test_node_vec().await;
         // Tree math is already tested in that module, just ensure equality
-        let expected = 0.direct_copath(&4);
+        let expected = NodeIndex::new(0).direct_copath(4);
         let actual = test_vec.direct_copath(LeafIndex(0));
         assert_eq!(actual, expected);
     }
@@ -497,11 +653,11 @@ mod tests {
         let mut test_vec = get_test_node_vec().await;
 
         // If the node is a leaf it should fail
-        assert!(test_vec.borrow_as_parent_mut(0).is_err());
+        assert!(test_vec.borrow_as_parent_mut(NodeIndex::new(0)).is_err());
 
         // If the node index is out of range it should fail
         assert!(test_vec
-            .borrow_as_parent_mut(test_vec.len() as u32)
+            .borrow_as_parent_mut(NodeIndex::new(test_vec.len() as u32))
             .is_err());
 
         // Otherwise it should succeed
@@ -511,20 +667,28 @@ mod tests {
             unmerged_leaves: vec![LeafIndex(2)],
         };
 
-        assert_eq!(test_vec.borrow_as_parent_mut(5).unwrap(), &mut expected);
+        assert_eq!(
+            test_vec.borrow_as_parent_mut(NodeIndex::new(5)).unwrap(),
+            &mut expected
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_get_resolution() {
         let test_vec = get_test_node_vec().await;
 
-        let resolution_node_5 = test_vec.get_resolution_index(5).unwrap();
-        let resolution_node_2 = test_vec.get_resolution_index(2).unwrap();
-        let resolution_node_3 = test_vec.get_resolution_index(3).unwrap();
+        let resolution = |n| {
+            test_vec
+                .get_resolution_index(NodeIndex::new(n))
+                .unwrap()
+                .into_iter()
+                .map(|n| n.value())
+                .collect::<Vec<_>>()
+        };
 
-        assert_eq!(&resolution_node_5, &[5, 4]);
-        assert!(resolution_node_2.is_empty());
-        assert_eq!(&resolution_node_3, &[0, 5, 4]);
+        assert_eq!(resolution(5), [5, 4]);
+        assert!(resolution(2).is_empty());
+        assert_eq!(resolution(3), [0, 5, 4]);
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -534,7 +698,7 @@ mod tests {
 
         let expected = test_vec[5].as_parent_mut().unwrap();
         let actual = test_vec2
-            .borrow_or_fill_node_as_parent(5, &Vec::new().into())
+            .borrow_or_fill_node_as_parent(NodeIndex::new(5), &Vec::new().into())
             .unwrap();
 
         assert_eq!(actual, expected);
@@ -551,7 +715,7 @@ mod tests {
         };
 
         let actual = test_vec
-            .borrow_or_fill_node_as_parent(1, &vec![0u8; 4].into())
+            .borrow_or_fill_node_as_parent(NodeIndex::new(1), &vec![0u8; 4].into())
             .unwrap();
 
         assert_eq!(actual, &mut expected);
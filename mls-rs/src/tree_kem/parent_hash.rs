@@ -2,10 +2,11 @@
 // Copyright by contributors to this project.
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
-use crate::client::MlsError;
+use crate::client::{LeafNodeSourceKind, MlsError};
 use crate::crypto::{CipherSuiteProvider, HpkePublicKey};
-use crate::tree_kem::math as tree_math;
+use crate::iter::try_map_bounded;
 use crate::tree_kem::node::{LeafIndex, Node, NodeIndex};
+use crate::tree_kem::tree_hash::TreeHash;
 use crate::tree_kem::TreeKemPublic;
 use alloc::vec::Vec;
 use core::{
@@ -14,7 +15,6 @@ use core::{
 };
 use mls_rs_codec::{MlsDecode, MlsEncode, MlsSize};
 use mls_rs_core::error::IntoAnyError;
-use tree_math::TreeIndex;
 
 use super::leaf_node::LeafNodeSource;
 
@@ -131,7 +131,7 @@ impl TreeKemPublic {
                 cipher_suite_provider,
                 &parent.public_key,
                 &hash,
-                &self.tree_hashes.current[node.copath as usize],
+                &self.tree_hashes.current[node.copath.as_usize()],
             )
             .await?;
 
@@ -143,6 +143,12 @@ impl TreeKemPublic {
 
     // Updates all of the required parent hash values, and returns the calculated parent hash value for the leaf node
     // If an update path is provided, additionally verify that the calculated parent hash matches
+    //
+    // Parent hashes along the direct path only depend on the tree hashes of the copath nodes.
+    // Installing the path does not touch the subtrees under the copath, so their hashes cached
+    // in `tree_hashes` are reused and only the O(log n) nodes on the direct path are hashed
+    // (once, after the parent hashes are set), instead of rehashing the direct path before
+    // computing the parent hashes as well.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub(crate) async fn update_parent_hashes<P: CipherSuiteProvider>(
         &mut self,
@@ -150,8 +156,11 @@ impl TreeKemPublic {
         verify_leaf_hash: bool,
         cipher_suite_provider: &P,
     ) -> Result<(), MlsError> {
-        // First update the relevant original hashes used for parent hash computation.
-        self.update_hashes(&[index], cipher_suite_provider).await?;
+        // The cached hashes can only be missing if the tree changed size since they were last
+        // computed, in which case the copath hashes have to be computed first.
+        if self.tree_hashes.current.len() != self.nodes.tree_size().node_width() as usize {
+            self.update_hashes(&[index], cipher_suite_provider).await?;
+        }
 
         let leaf_hash = self
             .parent_hash_for_leaf(cipher_suite_provider, index)
@@ -164,10 +173,18 @@ impl TreeKemPublic {
             // in the local tree
             if let LeafNodeSource::Commit(parent_hash) = &leaf.leaf_node_source {
                 if !leaf_hash.matches(parent_hash) {
-                    return Err(MlsError::ParentHashMismatch);
+                    return Err(MlsError::CommitLeafParentHashMismatch(
+                        *index,
+                        leaf_hash.to_vec(),
+                        parent_hash.to_vec(),
+                    ));
                 }
             } else {
-                return Err(MlsError::InvalidLeafNodeSource);
+                return Err(MlsError::UnexpectedLeafNodeSource(
+                    Some(*index),
+                    LeafNodeSourceKind::Commit,
+                    leaf.leaf_node_source.kind(),
+                ));
             }
         } else {
             leaf.leaf_node_source = LeafNodeSource::Commit(leaf_hash);
@@ -177,12 +194,33 @@ impl TreeKemPublic {
         self.update_hashes(&[index], cipher_suite_provider).await
     }
 
+    /// Validate the parent hashes of the tree. The chains of parent nodes
+    /// starting at each leaf are computed with at most `max_parallelism`
+    /// leaves processed at the same time.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub(super) async fn validate_parent_hashes<P: CipherSuiteProvider>(
         &self,
         cipher_suite_provider: &P,
+        max_parallelism: Option<usize>,
     ) -> Result<(), MlsError> {
         let original_hashes = self.compute_original_hashes(cipher_suite_provider).await?;
+        let original_hashes = &original_hashes;
+
+        let leaves = self
+            .nodes
+            .non_empty_leaves()
+            .map(|(leaf_index, _)| leaf_index)
+            .collect::<Vec<_>>();
+
+        // Errors are only reported once the chain is reached below, as chains of
+        // later leaves are not checked after a chain reached the root.
+        let chains = try_map_bounded(&leaves, max_parallelism, |leaf_index| async move {
+            Ok::<_, MlsError>(
+                self.parent_hash_chain(*leaf_index, original_hashes, cipher_suite_provider)
+                    .await,
+            )
+        })
+        .await?;
 
         let nodes_to_validate = self
             .nodes
@@ -194,80 +232,19 @@ impl TreeKemPublic {
         #[cfg(not(feature = "std"))]
         let mut nodes_to_validate = nodes_to_validate.collect::<BTreeSet<_>>();
 
-        let num_leaves = self.total_leaf_count();
-
-        // For each leaf l, validate all non-blank nodes on the chain from l up the tree.
-        for (leaf_index, _) in self.nodes.non_empty_leaves() {
-            let mut n = NodeIndex::from(leaf_index);
-
-            while let Some(mut ps) = n.parent_sibling(&num_leaves) {
-                // Find the first non-blank ancestor p of n and p's co-path child s.
-                while self.nodes.is_blank(ps.parent)? {
-                    // If we reached the root, we're done with this chain.
-                    let Some(ps_parent) = ps.parent.parent_sibling(&num_leaves) else {
-                        return Ok(());
-                    };
+        for chain in chains {
+            let chain = chain?;
 
-                    ps = ps_parent;
+            // If p is validated for the second time, the check fails ("all non-blank parent
+            // nodes are covered by exactly one such chain").
+            for node in chain.nodes {
+                if !nodes_to_validate.remove(&node) {
+                    return Err(MlsError::ParentHashMismatch);
                 }
+            }
 
-                // Check is n's parent_hash field matches the parent hash of p with co-path child s.
-                let p_parent = self.nodes.borrow_as_parent(ps.parent)?;
-
-                let n_node = self
-                    .nodes
-                    .borrow_node(n)?
-                    .as_ref()
-                    .ok_or(MlsError::ExpectedNode)?;
-
-                let calculated = ParentHash::new(
-                    cipher_suite_provider,
-                    &p_parent.public_key,
-                    &p_parent.parent_hash,
-                    &original_hashes[ps.sibling as usize],
-                )
-                .await?;
-
-                if n_node.get_parent_hash() == Some(calculated) {
-                    // Check that "n is in the resolution of c, and the intersection of p's unmerged_leaves with the subtree
-                    // under c is equal to the resolution of c with n removed".
-                    let Some(cp) = ps.sibling.parent_sibling(&num_leaves) else {
-                        return Err(MlsError::ParentHashMismatch);
-                    };
-
-                    let c = cp.sibling;
-                    let c_resolution = self.nodes.get_resolution_index(c)?.into_iter();
-
-                    #[cfg(feature = "std")]
-                    let mut c_resolution = c_resolution.collect::<HashSet<_>>();
-                    #[cfg(not(feature = "std"))]
-                    let mut c_resolution = c_resolution.collect::<BTreeSet<_>>();
-
-                    let p_unmerged_in_c_subtree = self
-                        .unmerged_in_subtree(ps.parent, c)?
-                        .iter()
-                        .copied()
-                        .map(|x| *x * 2);
-
-                    #[cfg(feature = "std")]
-                    let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<HashSet<_>>();
-                    #[cfg(not(feature = "std"))]
-                    let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<BTreeSet<_>>();
-
-                    if c_resolution.remove(&n)
-                        && c_resolution == p_unmerged_in_c_subtree
-                        && nodes_to_validate.remove(&ps.parent)
-                    {
-                        // If n's parent_hash field matches and p has not been validated yet, mark p as validated and continue.
-                        n = ps.parent;
-                    } else {
-                        // If p is validated for the second time, the check fails ("all non-blank parent nodes are covered by exactly one such chain").
-                        return Err(MlsError::ParentHashMismatch);
-                    }
-                } else {
-                    // If n's parent_hash field doesn't match, we're done with this chain.
-                    break;
-                }
+            if chain.reaches_root {
+                return Ok(());
             }
         }
 
@@ -278,6 +255,94 @@ impl TreeKemPublic {
             Err(MlsError::ParentHashMismatch)
         }
     }
+
+    // Non-blank nodes on the chain from `leaf_index` up the tree whose parent
+    // hash is validated by the node below them.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    async fn parent_hash_chain<P: CipherSuiteProvider>(
+        &self,
+        leaf_index: LeafIndex,
+        original_hashes: &[TreeHash],
+        cipher_suite_provider: &P,
+    ) -> Result<ParentHashChain, MlsError> {
+        let mut chain = ParentHashChain::default();
+        let mut n = leaf_index.node_index();
+
+        while let Some(mut ps) = self.nodes.parent_sibling(n) {
+            // Find the first non-blank ancestor p of n and p's co-path child s.
+            while self.nodes.is_blank(ps.parent)? {
+                // If we reached the root, we're done with this chain.
+                let Some(ps_parent) = self.nodes.parent_sibling(ps.parent) else {
+                    chain.reaches_root = true;
+                    return Ok(chain);
+                };
+
+                ps = ps_parent;
+            }
+
+            // Check is n's parent_hash field matches the parent hash of p with co-path child s.
+            let p_parent = self.nodes.borrow_as_parent(ps.parent)?;
+
+            let n_node = self
+                .nodes
+                .borrow_node(n)?
+                .as_ref()
+                .ok_or(MlsError::ExpectedNode)?;
+
+            let calculated = ParentHash::new(
+                cipher_suite_provider,
+                &p_parent.public_key,
+                &p_parent.parent_hash,
+                &original_hashes[ps.sibling.as_usize()],
+            )
+            .await?;
+
+            if n_node.get_parent_hash() != Some(calculated) {
+                // If n's parent_hash field doesn't match, we're done with this chain.
+                break;
+            }
+
+            // Check that "n is in the resolution of c, and the intersection of p's unmerged_leaves with the subtree
+            // under c is equal to the resolution of c with n removed".
+            let Some(cp) = self.nodes.parent_sibling(ps.sibling) else {
+                return Err(MlsError::ParentHashMismatch);
+            };
+
+            let c = cp.sibling;
+            let c_resolution = self.nodes.get_resolution_index(c)?.into_iter();
+
+            #[cfg(feature = "std")]
+            let mut c_resolution = c_resolution.collect::<HashSet<_>>();
+            #[cfg(not(feature = "std"))]
+            let mut c_resolution = c_resolution.collect::<BTreeSet<_>>();
+
+            let p_unmerged_in_c_subtree = self
+                .unmerged_in_subtree(ps.parent, c)?
+                .iter()
+                .map(LeafIndex::node_index);
+
+            #[cfg(feature = "std")]
+            let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<HashSet<_>>();
+            #[cfg(not(feature = "std"))]
+            let p_unmerged_in_c_subtree = p_unmerged_in_c_subtree.collect::<BTreeSet<_>>();
+
+            if !(c_resolution.remove(&n) && c_resolution == p_unmerged_in_c_subtree) {
+                return Err(MlsError::ParentHashMismatch);
+            }
+
+            // n's parent_hash field matches, mark p as validated and continue.
+            chain.nodes.push(ps.parent);
+            n = ps.parent;
+        }
+
+        Ok(chain)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ParentHashChain {
+    nodes: Vec<NodeIndex>,
+    reaches_root: bool,
 }
 
 #[cfg(test)]
@@ -387,7 +452,11 @@ mod tests {
 
         assert_matches!(
             missing_parent_hash_res,
-            Err(MlsError::InvalidLeafNodeSource)
+            Err(MlsError::UnexpectedLeafNodeSource(
+                Some(0),
+                LeafNodeSourceKind::Commit,
+                LeafNodeSourceKind::KeyPackage
+            ))
         );
     }
 
@@ -412,7 +481,11 @@ mod tests {
             )
             .await;
 
-        assert_matches!(invalid_parent_hash_res, Err(MlsError::ParentHashMismatch));
+        assert_matches!(
+            invalid_parent_hash_res,
+            Err(MlsError::CommitLeafParentHashMismatch(0, expected, actual))
+                if actual == hex!("f00d") && !expected.is_empty()
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
@@ -423,9 +496,32 @@ mod tests {
         test_tree.nodes[2] = None;
 
         let res = test_tree
-            .validate_parent_hashes(&test_cipher_suite_provider(TEST_CIPHER_SUITE))
+            .validate_parent_hashes(&test_cipher_suite_provider(TEST_CIPHER_SUITE), None)
             .await;
 
         assert_matches!(res, Err(MlsError::ParentHashMismatch));
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn cached_copath_hashes_match_full_recomputation() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = TreeWithSigners::make_full_tree(8, &cs).await.tree;
+
+        let mut uncached = tree.clone();
+        uncached.tree_hashes = Default::default();
+
+        tree.update_parent_hashes(LeafIndex(2), false, &cs)
+            .await
+            .unwrap();
+
+        uncached
+            .update_parent_hashes(LeafIndex(2), false, &cs)
+            .await
+            .unwrap();
+
+        assert_eq!(tree, uncached);
+        assert_eq!(tree.tree_hashes, uncached.tree_hashes);
+
+        tree.validate_parent_hashes(&cs, None).await.unwrap();
+    }
 }
@@ -4,7 +4,7 @@
 
 use crate::client::MlsError;
 use crate::crypto::{CipherSuiteProvider, HpkePublicKey, HpkeSecretKey};
-use crate::group::key_schedule::kdf_derive_secret;
+use crate::group::key_schedule::{kdf_derive_secret, kdf_expand_with_labels};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::{
@@ -82,19 +82,27 @@ impl HpkeEncryptable for PathSecret {
 }
 
 impl PathSecret {
+    #[cfg_attr(not(all(test, feature = "rfc_compliant")), allow(dead_code))]
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn to_hpke_key_pair<P: CipherSuiteProvider>(
         &self,
         cs: &P,
     ) -> Result<(HpkeSecretKey, HpkePublicKey), MlsError> {
         let node_secret = Zeroizing::new(kdf_derive_secret(cs, self, b"node").await?);
-
-        cs.kem_derive(&node_secret)
-            .await
-            .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+        node_key_pair(cs, &node_secret).await
     }
 }
 
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn node_key_pair<P: CipherSuiteProvider>(
+    cs: &P,
+    node_secret: &[u8],
+) -> Result<(HpkeSecretKey, HpkePublicKey), MlsError> {
+    cs.kem_derive(node_secret)
+        .await
+        .map_err(|e| MlsError::CryptoProviderError(e.into_any_error()))
+}
+
 #[derive(Clone, Debug)]
 pub struct PathSecretGenerator<'a, P> {
     cipher_suite_provider: &'a P,
@@ -134,6 +142,30 @@ impl<'a, P: CipherSuiteProvider> PathSecretGenerator<'a, P> {
 
         Ok(secret)
     }
+
+    /// Generate the next path secret together with the HPKE key pair of its
+    /// node. The node secret and the following path secret are derived in a
+    /// single batch.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn next_secret_with_key_pair(
+        &mut self,
+    ) -> Result<(PathSecret, (HpkeSecretKey, HpkePublicKey)), MlsError> {
+        let secret = self.next_secret().await?;
+
+        let [node_secret, next_secret] = kdf_expand_with_labels(
+            self.cipher_suite_provider,
+            &secret,
+            [(b"node", &[], None), (b"path", &[], None)],
+        )
+        .await?;
+
+        self.last = None;
+        self.starting_with = Some(next_secret.into());
+
+        let key_pair = node_key_pair(self.cipher_suite_provider, &node_secret).await?;
+
+        Ok((secret, key_pair))
+    }
 }
 
 #[cfg(test)]
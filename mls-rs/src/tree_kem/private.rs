@@ -49,7 +49,7 @@ impl TreeKemPrivate {
         // Identify the lowest common
         // ancestor of the leaves at index and at GroupInfo.signer_index. Set the private key
         // for this node to the private key derived from the path_secret.
-        let lca_index = leaf_lca_level(self.self_index.into(), signer_index.into()) as usize - 2;
+        let lca_index = leaf_lca_level(self.self_index, signer_index) as usize - 1;
 
         // For each parent of the common ancestor, up to the root of the tree, derive a new
         // path secret and set the private key for the node to the private key derived from the
@@ -68,8 +68,6 @@ impl TreeKemPrivate {
                 continue;
             }
 
-            let secret = node_secret_gen.next_secret().await?;
-
             let expected_pub_key = public_tree
                 .nodes
                 .borrow_node(n.path)?
@@ -77,7 +75,7 @@ impl TreeKemPrivate {
                 .map(|n| n.public_key())
                 .ok_or(MlsError::PubKeyMismatch)?;
 
-            let (secret_key, public_key) = secret.to_hpke_key_pair(cipher_suite_provider).await?;
+            let (_, (secret_key, public_key)) = node_secret_gen.next_secret_with_key_pair().await?;
 
             if expected_pub_key != &public_key {
                 return Err(MlsError::PubKeyMismatch);
@@ -122,11 +120,13 @@ mod tests {
             leaf_node::test_utils::{
                 default_properties, get_basic_test_node, get_basic_test_node_sig_key,
             },
-            math::TreeIndex,
             node::LeafIndex,
         },
     };
 
+    #[cfg(feature = "by_ref_proposal")]
+    use crate::tree_kem::math::TreeIndex;
+
     use super::*;
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -259,7 +259,7 @@ mod tests {
         // Sabotage the public tree
         public_tree
             .nodes
-            .borrow_as_parent_mut(public_tree.total_leaf_count().root())
+            .borrow_as_parent_mut(public_tree.nodes.root())
             .unwrap()
             .public_key = random_bytes(32).into();
 
@@ -283,7 +283,7 @@ mod tests {
 
         let mut private_key = TreeKemPrivate::new_self_leaf(self_index, secret.clone());
 
-        private_key.secret_keys = (0..0.direct_copath(&leaf_count).len() + 1)
+        private_key.secret_keys = (0..0u32.direct_copath(leaf_count).len() + 1)
             .map(|_| Some(secret.clone()))
             .collect();
 
@@ -0,0 +1,255 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+use alloc::vec::Vec;
+
+use super::node::{LeafIndex, Node, NodeVec};
+
+/// Differences between two ratchet trees of a group, for example the trees
+/// of two replicas of the same group or the trees of two epochs.
+///
+/// Created by [`ExportedTree::diff`](crate::group::ExportedTree::diff).
+/// Nodes beyond the end of the smaller tree are considered blank.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TreeDiff {
+    /// Leaves that differ, ordered by leaf index.
+    pub leaves: Vec<LeafDiff>,
+    /// Parent nodes that differ, ordered by node index.
+    pub parents: Vec<ParentDiff>,
+    /// Leaves that were blanked or changed and whose direct path was blanked,
+    /// as done when applying removals and updates. A direct path is blanked
+    /// if all of its parent nodes that are non-blank in the old tree are
+    /// blank in the new one.
+    pub blanked_paths: Vec<u32>,
+}
+
+impl TreeDiff {
+    /// Whether the trees are identical.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty() && self.parents.is_empty()
+    }
+}
+
+/// Leaf of a [`TreeDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LeafDiff {
+    pub leaf_index: u32,
+    pub kind: LeafDiffKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeafDiffKind {
+    /// The leaf is blank in the old tree only.
+    Added,
+    /// The leaf is blank in the new tree only.
+    Blanked,
+    /// The leaf is non-blank in both trees with different content.
+    Changed {
+        signature_key_changed: bool,
+        encryption_key_changed: bool,
+    },
+}
+
+/// Parent node of a [`TreeDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParentDiff {
+    pub node_index: u32,
+    pub kind: ParentDiffKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParentDiffKind {
+    /// The node is blank in the old tree only.
+    Added,
+    /// The node is blank in the new tree only.
+    Blanked,
+    /// The node is non-blank in both trees with different content.
+    Changed {
+        public_key_changed: bool,
+        parent_hash_changed: bool,
+        unmerged_leaves_changed: bool,
+    },
+}
+
+impl NodeVec {
+    pub(crate) fn diff(&self, other: &NodeVec) -> TreeDiff {
+        let mut diff = TreeDiff::default();
+
+        for i in 0..self.len().max(other.len()) {
+            let (old, new) = (self.node_at(i), other.node_at(i));
+
+            if old == new {
+                continue;
+            }
+
+            match (old, new) {
+                (Some(Node::Leaf(old)), Some(Node::Leaf(new))) => diff.leaves.push(LeafDiff {
+                    leaf_index: i as u32 / 2,
+                    kind: LeafDiffKind::Changed {
+                        signature_key_changed: old.signing_identity.signature_key
+                            != new.signing_identity.signature_key,
+                        encryption_key_changed: old.public_key != new.public_key,
+                    },
+                }),
+                (Some(Node::Parent(old)), Some(Node::Parent(new))) => {
+                    diff.parents.push(ParentDiff {
+                        node_index: i as u32,
+                        kind: ParentDiffKind::Changed {
+                            public_key_changed: old.public_key != new.public_key,
+                            parent_hash_changed: old.parent_hash != new.parent_hash,
+                            unmerged_leaves_changed: old.unmerged_leaves != new.unmerged_leaves,
+                        },
+                    })
+                }
+                (_, new) if i % 2 == 0 => diff.leaves.push(LeafDiff {
+                    leaf_index: i as u32 / 2,
+                    kind: match new {
+                        Some(_) => LeafDiffKind::Added,
+                        None => LeafDiffKind::Blanked,
+                    },
+                }),
+                (_, new) => diff.parents.push(ParentDiff {
+                    node_index: i as u32,
+                    kind: match new {
+                        Some(_) => ParentDiffKind::Added,
+                        None => ParentDiffKind::Blanked,
+                    },
+                }),
+            }
+        }
+
+        diff.blanked_paths = diff
+            .leaves
+            .iter()
+            .filter(|leaf| leaf.kind != LeafDiffKind::Added)
+            .map(|leaf| leaf.leaf_index)
+            .filter(|leaf_index| self.path_blanked_in(other, LeafIndex(*leaf_index)))
+            .collect();
+
+        diff
+    }
+
+    fn path_blanked_in(&self, other: &NodeVec, leaf_index: LeafIndex) -> bool {
+        let mut path = self
+            .direct_copath(leaf_index)
+            .into_iter()
+            .map(|cp| cp.path.as_usize())
+            .filter(|i| self.node_at(*i).is_some())
+            .peekable();
+
+        path.peek().is_some() && path.all(|i| other.node_at(i).is_none())
+    }
+
+    fn node_at(&self, index: usize) -> Option<&Node> {
+        self.get(index).and_then(Option::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::{
+        client::test_utils::{TEST_CIPHER_SUITE, TEST_PROTOCOL_VERSION},
+        group::test_utils::test_group,
+        tree_kem::{
+            node::{LeafIndex, NodeIndex},
+            parent_hash::test_utils::get_test_tree_fig_12,
+        },
+    };
+
+    use super::{LeafDiff, LeafDiffKind, ParentDiff, ParentDiffKind};
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn synchronized_members_have_identical_trees() {
+        let mut alice = test_group(TEST_PROTOCOL_VERSION, TEST_CIPHER_SUITE).await;
+        let (bob, _) = alice.join("bob").await;
+
+        let diff = alice.group.export_tree().diff(&bob.group.export_tree());
+
+        assert!(diff.is_empty());
+        assert!(diff.blanked_paths.is_empty());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn diff_reports_blanked_leaves_and_paths() {
+        let tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+        let mut new_tree = tree.clone();
+
+        new_tree.nodes.blank_leaf_node(LeafIndex(4)).unwrap();
+        new_tree.nodes.blank_direct_path(LeafIndex(4)).unwrap();
+
+        let diff = tree.nodes.diff(&new_tree.nodes);
+
+        let expected_leaves = vec![LeafDiff {
+            leaf_index: 4,
+            kind: LeafDiffKind::Blanked,
+        }];
+
+        let expected_parents = [7, 9, 11]
+            .into_iter()
+            .map(|node_index| ParentDiff {
+                node_index,
+                kind: ParentDiffKind::Blanked,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(diff.leaves, expected_leaves);
+        assert_eq!(diff.parents, expected_parents);
+        assert_eq!(diff.blanked_paths, vec![4]);
+
+        let reverse = new_tree.nodes.diff(&tree.nodes);
+
+        assert_eq!(reverse.leaves[0].kind, LeafDiffKind::Added);
+        assert_eq!(reverse.parents[0].kind, ParentDiffKind::Added);
+        assert!(reverse.blanked_paths.is_empty());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn diff_reports_changed_fields() {
+        let tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+        let mut new_tree = tree.clone();
+
+        new_tree
+            .nodes
+            .borrow_as_parent_mut(NodeIndex::new(3))
+            .unwrap()
+            .unmerged_leaves
+            .clear();
+
+        new_tree
+            .nodes
+            .borrow_as_leaf_mut(LeafIndex(1))
+            .unwrap()
+            .public_key = vec![0; 32].into();
+
+        let diff = tree.nodes.diff(&new_tree.nodes);
+
+        let expected_leaf = LeafDiff {
+            leaf_index: 1,
+            kind: LeafDiffKind::Changed {
+                signature_key_changed: false,
+                encryption_key_changed: true,
+            },
+        };
+
+        let expected_parent = ParentDiff {
+            node_index: 3,
+            kind: ParentDiffKind::Changed {
+                public_key_changed: false,
+                parent_hash_changed: false,
+                unmerged_leaves_changed: true,
+            },
+        };
+
+        assert_eq!(diff.leaves, vec![expected_leaf]);
+        assert_eq!(diff.parents, vec![expected_parent]);
+        assert!(diff.blanked_paths.is_empty());
+    }
+}
@@ -3,11 +3,12 @@
 // SPDX-License-Identifier: (Apache-2.0 OR MIT)
 
 use super::leaf_node::LeafNode;
-use super::node::{LeafIndex, NodeVec};
+use super::node::{LeafIndex, NodeIndex, NodeVec};
 use super::tree_math::BfsIterTopDown;
 use crate::client::MlsError;
 use crate::crypto::CipherSuiteProvider;
 use crate::tree_kem::math as tree_math;
+use crate::tree_kem::math::TreeSize;
 use crate::tree_kem::node::Parent;
 use crate::tree_kem::TreeKemPublic;
 use alloc::collections::VecDeque;
@@ -74,15 +75,78 @@ enum TreeHashInput<'a> {
 }
 
 impl TreeKemPublic {
+    /// Tree hash of the tree, initializing the cached hashes first if needed.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     #[inline(never)]
     pub async fn tree_hash<P: CipherSuiteProvider>(
         &mut self,
         cipher_suite_provider: &P,
     ) -> Result<Vec<u8>, MlsError> {
-        self.initialize_hashes(cipher_suite_provider).await?;
-        let root = self.total_leaf_count().root();
-        Ok(self.tree_hashes.current[root as usize].to_vec())
+        self.ensure_hashes(cipher_suite_provider).await?;
+        self.compute_tree_hash(cipher_suite_provider).await
+    }
+
+    /// Tree hash of the tree, computed without modifying it.
+    ///
+    /// The hashes cached by [`ensure_hashes`](Self::ensure_hashes) are used if
+    /// they cover the tree. Otherwise all nodes are hashed and the result is not
+    /// cached, so callers that hash the same tree repeatedly should initialize
+    /// the cache once instead.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub async fn compute_tree_hash<P: CipherSuiteProvider>(
+        &self,
+        cipher_suite_provider: &P,
+    ) -> Result<Vec<u8>, MlsError> {
+        let num_leaves = self.total_leaf_count();
+        let root = NodeIndex::root(num_leaves).as_usize();
+
+        if let Some(hash) = self.cached_hash(root) {
+            return Ok(hash.to_vec());
+        }
+
+        let mut hashes = Vec::new();
+
+        tree_hash(
+            &mut hashes,
+            &self.nodes,
+            None,
+            &[],
+            num_leaves,
+            cipher_suite_provider,
+        )
+        .await?;
+
+        Ok(hashes[root].to_vec())
+    }
+
+    /// Tree hash of the leaf at `leaf_index`, as used as input of the hashes of
+    /// its parent nodes.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn leaf_hash<P: CipherSuiteProvider>(
+        &self,
+        leaf_index: LeafIndex,
+        cipher_suite_provider: &P,
+    ) -> Result<Vec<u8>, MlsError> {
+        if let Some(hash) = self.cached_hash(leaf_index.node_index().as_usize()) {
+            return Ok(hash.to_vec());
+        }
+
+        let leaf_node = match self.nodes.borrow_node(leaf_index.node_index())? {
+            Some(_) => Some(self.get_leaf_node(leaf_index)?),
+            None => None,
+        };
+
+        hash_for_leaf(leaf_index, leaf_node, cipher_suite_provider).await
+    }
+
+    /// Cached hash of `node`, if the cache initialized by
+    /// [`ensure_hashes`](Self::ensure_hashes) covers the tree.
+    fn cached_hash(&self, node: usize) -> Option<&TreeHash> {
+        let num_nodes = self.nodes.tree_size().node_width() as usize;
+
+        (self.tree_hashes.current.len() == num_nodes)
+            .then(|| self.tree_hashes.current.get(node))
+            .flatten()
     }
 
     // Update hashes after `committer` makes changes to the tree. `path_blank` is the
@@ -100,7 +164,7 @@ impl TreeKemPublic {
             .map_while(|l| {
                 self.tree_hashes
                     .current
-                    .get(2 * l as usize)
+                    .get(LeafIndex(l).node_index().as_usize())
                     .is_none()
                     .then_some(LeafIndex(l))
             })
@@ -120,9 +184,11 @@ impl TreeKemPublic {
         Ok(())
     }
 
-    // Initialize all hashes after creating / importing a tree.
+    /// Initialize the cached hashes after creating or importing a tree, so that
+    /// [`compute_tree_hash`](Self::compute_tree_hash) can be answered from the
+    /// cache afterwards.
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
-    async fn initialize_hashes<P>(&mut self, cipher_suite_provider: &P) -> Result<(), MlsError>
+    pub async fn ensure_hashes<P>(&mut self, cipher_suite_provider: &P) -> Result<(), MlsError>
     where
         P: CipherSuiteProvider,
     {
@@ -145,8 +211,8 @@ impl TreeKemPublic {
 
     pub(crate) fn unmerged_in_subtree(
         &self,
-        node_unmerged: u32,
-        subtree_root: u32,
+        node_unmerged: NodeIndex,
+        subtree_root: NodeIndex,
     ) -> Result<&[LeafIndex], MlsError> {
         let unmerged = &self.nodes.borrow_as_parent(node_unmerged)?.unmerged_leaves;
         let (left, right) = tree_math::subtree(subtree_root);
@@ -161,7 +227,11 @@ impl TreeKemPublic {
         Ok(&unmerged[start..end])
     }
 
-    fn different_unmerged(&self, ancestor: u32, descendant: u32) -> Result<bool, MlsError> {
+    fn different_unmerged(
+        &self,
+        ancestor: NodeIndex,
+        descendant: NodeIndex,
+    ) -> Result<bool, MlsError> {
         Ok(!self.nodes.is_blank(ancestor)?
             && !self.nodes.is_blank(descendant)?
             && self.unmerged_in_subtree(ancestor, descendant)?
@@ -174,33 +244,34 @@ impl TreeKemPublic {
         cipher_suite: &P,
     ) -> Result<Vec<TreeHash>, MlsError> {
         let num_leaves = self.nodes.total_leaf_count() as usize;
-        let root = (num_leaves as u32).root();
+        let num_nodes = self.nodes.tree_size().node_width() as usize;
+        let root = self.nodes.root();
 
         // The value `filtered_sets[n]` is a list of all ancestors `a` of `n` s.t. we have to compute
         // the tree hash of `n` with the unmerged leaves of `a` filtered out.
-        let mut filtered_sets = vec![vec![]; num_leaves * 2 - 1];
-        filtered_sets[root as usize].push(root);
-        let mut tree_hashes = vec![vec![]; num_leaves * 2 - 1];
+        let mut filtered_sets = vec![vec![]; num_nodes];
+        filtered_sets[root.as_usize()].push(root);
+        let mut tree_hashes = vec![vec![]; num_nodes];
 
         let bfs_iter = BfsIterTopDown::new(num_leaves).skip(1);
 
-        for n in bfs_iter {
-            let Some(ps) = (n as u32).parent_sibling(&(num_leaves as u32)) else {
+        for n in bfs_iter.map(|n| NodeIndex::new(n as u32)) {
+            let Some(ps) = self.nodes.parent_sibling(n) else {
                 break;
             };
 
             let p = ps.parent;
-            filtered_sets[n] = filtered_sets[p as usize].clone();
+            filtered_sets[n.as_usize()] = filtered_sets[p.as_usize()].clone();
 
-            if self.different_unmerged(*filtered_sets[p as usize].last().unwrap(), p)? {
-                filtered_sets[n].push(p);
+            if self.different_unmerged(*filtered_sets[p.as_usize()].last().unwrap(), p)? {
+                filtered_sets[n.as_usize()].push(p);
 
                 // Compute tree hash of `n` without unmerged leaves of `p`. This also computes the tree hash
                 // for any descendants of `n` added to `filtered_sets` later via `clone`.
-                let (start_leaf, end_leaf) = tree_math::subtree(n as u32);
+                let (start_leaf, end_leaf) = tree_math::subtree(n);
 
                 tree_hash(
-                    &mut tree_hashes[p as usize],
+                    &mut tree_hashes[p.as_usize()],
                     &self.nodes,
                     Some((*start_leaf..*end_leaf).map(LeafIndex).collect_vec()),
                     &self.nodes.borrow_as_parent(p)?.unmerged_leaves,
@@ -212,7 +283,7 @@ impl TreeKemPublic {
         }
 
         // Set the `original_hashes` based on the computed `hashes`.
-        let mut original_hashes = vec![TreeHash::default(); num_leaves * 2 - 1];
+        let mut original_hashes = vec![TreeHash::default(); num_nodes];
 
         // If root has unmerged leaves, we recompute it's original hash. Else, we can use the current hash.
         let root_original = if !self.nodes.is_blank(root)? && !self.nodes.is_leaf(root) {
@@ -248,7 +319,7 @@ impl TreeKemPublic {
                     self.tree_hashes.current[i].clone()
                 }
             } else {
-                tree_hashes[*a as usize][i].clone()
+                tree_hashes[a.as_usize()][i].clone()
             }
         }
 
@@ -269,7 +340,8 @@ async fn tree_hash<P: CipherSuiteProvider>(
         leaves_to_update.unwrap_or_else(|| (0..num_leaves).map(LeafIndex).collect::<Vec<_>>());
 
     // Resize the array in case the tree was extended or truncated
-    hashes.resize(num_leaves as usize * 2 - 1, TreeHash::default());
+    let num_nodes = TreeSize::from_leaf_count(num_leaves).node_width();
+    hashes.resize(num_nodes as usize, TreeHash::default());
 
     let mut node_queue = VecDeque::with_capacity(leaves_to_update.len());
 
@@ -278,9 +350,10 @@ async fn tree_hash<P: CipherSuiteProvider>(
             .then_some(nodes.borrow_as_leaf(*l).ok())
             .flatten();
 
-        hashes[2 * **l as usize] = TreeHash(hash_for_leaf(*l, leaf, cipher_suite_provider).await?);
+        hashes[l.node_index().as_usize()] =
+            TreeHash(hash_for_leaf(*l, leaf, cipher_suite_provider).await?);
 
-        if let Some(ps) = (2 * **l).parent_sibling(&num_leaves) {
+        if let Some(ps) = l.node_index().parent_sibling(num_leaves) {
             node_queue.push_back(ps.parent);
         }
     }
@@ -291,15 +364,15 @@ async fn tree_hash<P: CipherSuiteProvider>(
                 nodes.borrow_as_parent(n).ok(),
                 cipher_suite_provider,
                 filtered_leaves,
-                &hashes[n.left_unchecked() as usize],
-                &hashes[n.right_unchecked() as usize],
+                &hashes[n.left_unchecked().as_usize()],
+                &hashes[n.right_unchecked().as_usize()],
             )
             .await?,
         );
 
-        hashes[n as usize] = hash;
+        hashes[n.as_usize()] = hash;
 
-        if let Some(ps) = n.parent_sibling(&num_leaves) {
+        if let Some(ps) = n.parent_sibling(num_leaves) {
             node_queue.push_back(ps.parent);
         }
     }
@@ -358,6 +431,7 @@ mod tests {
 
     use crate::{
         cipher_suite::CipherSuite,
+        client::test_utils::TEST_CIPHER_SUITE,
         crypto::test_utils::{test_cipher_suite_provider, try_test_cipher_suite_provider},
         identity::basic::BasicIdentityProvider,
         tree_kem::{node::NodeVec, parent_hash::test_utils::get_test_tree_fig_12},
@@ -429,4 +503,27 @@ mod tests {
             assert_eq!(calculated_hash, one_case.tree_hash);
         }
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn tree_hash_is_computed_without_cache() {
+        let cs = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+        tree.tree_hashes = Default::default();
+
+        let computed = tree.compute_tree_hash(&cs).await.unwrap();
+        let leaf_hash = tree.leaf_hash(LeafIndex(1), &cs).await.unwrap();
+        assert!(tree.tree_hashes.current.is_empty());
+
+        tree.ensure_hashes(&cs).await.unwrap();
+        assert!(!tree.tree_hashes.current.is_empty());
+
+        let cached = tree.compute_tree_hash(&cs).await.unwrap();
+        assert_eq!(cached, computed);
+
+        let cached = tree.leaf_hash(LeafIndex(1), &cs).await.unwrap();
+        assert_eq!(cached, leaf_hash);
+
+        let hash = tree.tree_hash(&cs).await.unwrap();
+        assert_eq!(hash, computed);
+    }
 }
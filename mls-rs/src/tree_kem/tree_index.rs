@@ -6,7 +6,7 @@ use super::*;
 #[cfg(feature = "tree_index")]
 use core::fmt::{self, Debug};
 
-#[cfg(all(feature = "tree_index", feature = "custom_proposal"))]
+#[cfg(feature = "custom_proposal")]
 use crate::group::proposal::ProposalType;
 
 #[cfg(feature = "tree_index")]
@@ -15,6 +15,8 @@ use crate::identity::CredentialType;
 #[cfg(feature = "tree_index")]
 use mls_rs_core::crypto::SignaturePublicKey;
 
+use mls_rs_core::identity::SigningIdentity;
+
 #[cfg(all(feature = "tree_index", feature = "std"))]
 use itertools::Itertools;
 
@@ -30,6 +32,18 @@ use alloc::collections::BTreeSet;
 #[cfg(feature = "tree_index")]
 use mls_rs_core::crypto::HpkePublicKey;
 
+#[cfg(feature = "tree_index")]
+use mls_rs_codec::Error as CodecError;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as ApplicationIdMap;
+
+#[cfg(feature = "std")]
+use std::collections::HashMap as ApplicationIdMap;
+
+use crate::client::LeafDataKind;
+use crate::extension::ApplicationIdExt;
+
 #[cfg(feature = "tree_index")]
 #[derive(Clone, Default, PartialEq, Eq, MlsSize, MlsEncode, MlsDecode, Hash, PartialOrd, Ord)]
 pub struct Identifier(#[mls_codec(with = "mls_rs_codec::byte_vec")] Vec<u8>);
@@ -52,6 +66,7 @@ pub struct TreeIndex {
     credential_type_counters: HashMap<CredentialType, TypeCounter>,
     #[cfg(feature = "custom_proposal")]
     proposal_type_counter: HashMap<ProposalType, u32>,
+    application_ids: ApplicationIdIndex,
 }
 
 #[cfg(all(feature = "tree_index", not(feature = "std")))]
@@ -63,6 +78,53 @@ pub struct TreeIndex {
     credential_type_counters: BTreeMap<CredentialType, TypeCounter>,
     #[cfg(feature = "custom_proposal")]
     proposal_type_counter: BTreeMap<ProposalType, u32>,
+    application_ids: ApplicationIdIndex,
+}
+
+/// Leaves indexed by the identifier of their [`ApplicationIdExt`].
+///
+/// The mapping is derived from the leaves and therefore not encoded with the
+/// rest of the index. A decoded index has no mapping until it is rebuilt by
+/// [`TreeKemPublic::initialize_index_if_necessary`].
+#[cfg(feature = "tree_index")]
+#[derive(Clone, Debug, PartialEq)]
+struct ApplicationIdIndex(Option<ApplicationIdMap<Identifier, Vec<LeafIndex>>>);
+
+#[cfg(feature = "tree_index")]
+impl Default for ApplicationIdIndex {
+    fn default() -> Self {
+        Self(Some(Default::default()))
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsSize for ApplicationIdIndex {
+    fn mls_encoded_len(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsEncode for ApplicationIdIndex {
+    fn mls_encode(&self, _writer: &mut Vec<u8>) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tree_index")]
+impl MlsDecode for ApplicationIdIndex {
+    fn mls_decode(_reader: &mut &[u8]) -> Result<Self, CodecError> {
+        Ok(Self(None))
+    }
+}
+
+fn leaf_application_id(leaf_node: &LeafNode) -> Option<Vec<u8>> {
+    leaf_node
+        .extensions
+        .get_as::<ApplicationIdExt>()
+        .ok()
+        .flatten()
+        .map(|ext| ext.identifier)
 }
 
 #[cfg(feature = "tree_index")]
@@ -71,14 +133,10 @@ pub(super) async fn index_insert<I: IdentityProvider>(
     tree_index: &mut TreeIndex,
     new_leaf: &LeafNode,
     new_leaf_idx: LeafIndex,
-    id_provider: &I,
-    extensions: &ExtensionList,
+    new_id: Vec<u8>,
+    _id_provider: &I,
+    _extensions: &ExtensionList,
 ) -> Result<(), MlsError> {
-    let new_id = id_provider
-        .identity(&new_leaf.signing_identity, extensions)
-        .await
-        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
-
     tree_index.insert(new_leaf_idx, new_leaf, new_id)
 }
 
@@ -88,31 +146,34 @@ pub(super) async fn index_insert<I: IdentityProvider>(
     nodes: &NodeVec,
     new_leaf: &LeafNode,
     new_leaf_idx: LeafIndex,
+    new_id: Vec<u8>,
     id_provider: &I,
     extensions: &ExtensionList,
 ) -> Result<(), MlsError> {
-    let new_id = id_provider
-        .identity(&new_leaf.signing_identity, extensions)
-        .await
-        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+    let leaves = nodes
+        .non_empty_leaves()
+        .filter(|(i, _)| i != &new_leaf_idx)
+        .collect_vec();
 
-    for (i, leaf) in nodes.non_empty_leaves().filter(|(i, _)| i != &new_leaf_idx) {
-        (new_leaf.public_key != leaf.public_key)
-            .then_some(())
-            .ok_or(MlsError::DuplicateLeafData(*i))?;
+    let signing_identities = leaves
+        .iter()
+        .map(|(_, leaf)| &leaf.signing_identity)
+        .collect_vec();
+
+    let ids = identities(&signing_identities, id_provider, extensions).await?;
 
+    for ((i, leaf), id) in leaves.into_iter().zip(ids) {
         (new_leaf.signing_identity.signature_key != leaf.signing_identity.signature_key)
             .then_some(())
-            .ok_or(MlsError::DuplicateLeafData(*i))?;
+            .ok_or(MlsError::DuplicateLeafData(*i, LeafDataKind::SignatureKey))?;
 
-        let id = id_provider
-            .identity(&leaf.signing_identity, extensions)
-            .await
-            .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+        (new_leaf.public_key != leaf.public_key)
+            .then_some(())
+            .ok_or(MlsError::DuplicateLeafData(*i, LeafDataKind::EncryptionKey))?;
 
         (new_id != id)
             .then_some(())
-            .ok_or(MlsError::DuplicateLeafData(*i))?;
+            .ok_or(MlsError::DuplicateLeafData(*i, LeafDataKind::Identity))?;
 
         let cred_type = leaf.signing_identity.credential.credential_type();
 
@@ -135,6 +196,240 @@ pub(super) async fn index_insert<I: IdentityProvider>(
     Ok(())
 }
 
+impl TreeKemPublic {
+    /// Check that `leaf` can be placed at `index` given the other leaves of
+    /// the tree, and record it in the index if `tree_index` is enabled.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(super) async fn index_leaf<I: IdentityProvider>(
+        &mut self,
+        leaf: &LeafNode,
+        index: LeafIndex,
+        id_provider: &I,
+        extensions: &ExtensionList,
+    ) -> Result<(), MlsError> {
+        self.index_leaf_with_identity(leaf, index, None, id_provider, extensions)
+            .await
+    }
+
+    /// Same as [`Self::index_leaf`] using the identity of `leaf` if it was
+    /// already resolved.
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(super) async fn index_leaf_with_identity<I: IdentityProvider>(
+        &mut self,
+        leaf: &LeafNode,
+        index: LeafIndex,
+        identity: Option<Vec<u8>>,
+        id_provider: &I,
+        extensions: &ExtensionList,
+    ) -> Result<(), MlsError> {
+        let identity = match identity {
+            Some(identity) => identity,
+            None => self::identity(&leaf.signing_identity, id_provider, extensions).await?,
+        };
+
+        #[cfg(feature = "tree_index")]
+        let res = index_insert(
+            &mut self.index,
+            leaf,
+            index,
+            identity,
+            id_provider,
+            extensions,
+        )
+        .await;
+
+        #[cfg(not(feature = "tree_index"))]
+        let res = index_insert(&self.nodes, leaf, index, identity, id_provider, extensions).await;
+
+        res
+    }
+
+    /// Remove `leaf` from the index if `tree_index` is enabled.
+    #[cfg_attr(not(feature = "tree_index"), allow(unused_variables))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(super) async fn unindex_leaf<I: IdentityProvider>(
+        &mut self,
+        leaf: &LeafNode,
+        id_provider: &I,
+        extensions: &ExtensionList,
+    ) -> Result<(), MlsError> {
+        #[cfg(feature = "tree_index")]
+        {
+            let identity = identity(&leaf.signing_identity, id_provider, extensions).await?;
+            self.index.remove(leaf, &identity);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tree_index")]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn initialize_index_if_necessary<IP: IdentityProvider>(
+        &mut self,
+        identity_provider: &IP,
+        extensions: &ExtensionList,
+    ) -> Result<(), MlsError> {
+        if !self.index.is_initialized() {
+            self.index = TreeIndex::new();
+
+            let leaves = self.nodes.non_empty_leaves().collect_vec();
+
+            let signing_identities = leaves
+                .iter()
+                .map(|(_, leaf)| &leaf.signing_identity)
+                .collect_vec();
+
+            let ids = identities(&signing_identities, identity_provider, extensions).await?;
+
+            for ((leaf_index, leaf), id) in leaves.into_iter().zip(ids) {
+                index_insert(
+                    &mut self.index,
+                    leaf,
+                    leaf_index,
+                    id,
+                    identity_provider,
+                    extensions,
+                )
+                .await?;
+            }
+        } else if !self.index.has_application_ids() {
+            self.index
+                .index_application_ids(self.nodes.non_empty_leaves());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tree_index")]
+    pub(crate) fn get_leaf_node_with_identity(&self, identity: &[u8]) -> Option<LeafIndex> {
+        self.index.get_leaf_index_with_identity(identity)
+    }
+
+    #[cfg(not(feature = "tree_index"))]
+    #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+    pub(crate) async fn get_leaf_node_with_identity<I: IdentityProvider>(
+        &self,
+        identity: &[u8],
+        id_provider: &I,
+        extensions: &ExtensionList,
+    ) -> Result<Option<LeafIndex>, MlsError> {
+        for (i, leaf) in self.nodes.non_empty_leaves() {
+            let leaf_id = id_provider
+                .identity(&leaf.signing_identity, extensions)
+                .await
+                .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+
+            if leaf_id == identity {
+                return Ok(Some(i));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Leaves carrying an [`ApplicationIdExt`] with `application_id`, in
+    /// increasing order.
+    pub(crate) fn leaves_with_application_id(&self, application_id: &[u8]) -> Vec<LeafIndex> {
+        #[cfg(feature = "tree_index")]
+        return self
+            .index
+            .get_leaf_indexes_with_application_id(application_id);
+
+        #[cfg(not(feature = "tree_index"))]
+        self.nodes
+            .non_empty_leaves()
+            .filter(|(_, leaf)| leaf_application_id(leaf).as_deref() == Some(application_id))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Application ids carried by more than one leaf together with these
+    /// leaves, ordered by application id.
+    pub(crate) fn duplicate_application_ids(&self) -> Vec<(Vec<u8>, Vec<LeafIndex>)> {
+        #[cfg(feature = "tree_index")]
+        return self.index.duplicate_application_ids();
+
+        #[cfg(not(feature = "tree_index"))]
+        {
+            let mut leaves = ApplicationIdMap::<Vec<u8>, Vec<LeafIndex>>::new();
+
+            for (index, leaf) in self.nodes.non_empty_leaves() {
+                if let Some(id) = leaf_application_id(leaf) {
+                    leaves.entry(id).or_default().push(index);
+                }
+            }
+
+            sorted_duplicates(leaves)
+        }
+    }
+
+    #[cfg(feature = "custom_proposal")]
+    pub fn can_support_proposal(&self, proposal_type: ProposalType) -> bool {
+        #[cfg(feature = "tree_index")]
+        return self.index.count_supporting_proposal(proposal_type) == self.occupied_leaf_count();
+
+        #[cfg(not(feature = "tree_index"))]
+        self.nodes
+            .non_empty_leaves()
+            .all(|(_, l)| l.capabilities.proposals.contains(&proposal_type))
+    }
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn identity<I: IdentityProvider>(
+    signing_id: &SigningIdentity,
+    provider: &I,
+    extensions: &ExtensionList,
+) -> Result<Vec<u8>, MlsError> {
+    provider
+        .identity(signing_id, extensions)
+        .await
+        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))
+}
+
+/// Identities of new `leaves` resolved with a single call to `provider`. If
+/// this fails, no identity is returned and identities are instead resolved
+/// one by one when the leaves are indexed, attributing the failure to the
+/// leaf causing it.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(super) async fn prefetch_identities<I: IdentityProvider>(
+    leaves: &[&LeafNode],
+    provider: &I,
+    extensions: &ExtensionList,
+) -> Vec<Option<Vec<u8>>> {
+    let signing_ids = leaves
+        .iter()
+        .map(|leaf| &leaf.signing_identity)
+        .collect_vec();
+
+    match identities(&signing_ids, provider, extensions).await {
+        Ok(ids) => ids.into_iter().map(Some).collect(),
+        Err(_) => vec![None; leaves.len()],
+    }
+}
+
+/// Identities of all of `signing_ids`, resolved with a single call to `provider`.
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+pub(super) async fn identities<I: IdentityProvider>(
+    signing_ids: &[&SigningIdentity],
+    provider: &I,
+    extensions: &ExtensionList,
+) -> Result<Vec<Vec<u8>>, MlsError> {
+    let identities = provider
+        .identities(signing_ids, extensions)
+        .await
+        .map_err(|e| MlsError::IdentityProviderError(e.into_any_error()))?;
+
+    if identities.len() != signing_ids.len() {
+        return Err(MlsError::IdentityCountMismatch(
+            signing_ids.len(),
+            identities.len(),
+        ));
+    }
+
+    Ok(identities)
+}
+
 #[cfg(feature = "tree_index")]
 impl TreeIndex {
     pub fn new() -> Self {
@@ -157,18 +452,27 @@ impl TreeIndex {
         let credential_entry = self.credential_signature_key.entry(pub_key);
 
         if let Entry::Occupied(entry) = credential_entry {
-            return Err(MlsError::DuplicateLeafData(**entry.get()));
+            return Err(MlsError::DuplicateLeafData(
+                **entry.get(),
+                LeafDataKind::SignatureKey,
+            ));
         }
 
         let hpke_entry = self.hpke_key.entry(leaf_node.public_key.clone());
 
         if let Entry::Occupied(entry) = hpke_entry {
-            return Err(MlsError::DuplicateLeafData(**entry.get()));
+            return Err(MlsError::DuplicateLeafData(
+                **entry.get(),
+                LeafDataKind::EncryptionKey,
+            ));
         }
 
         let identity_entry = self.identities.entry(Identifier(identity));
         if let Entry::Occupied(entry) = identity_entry {
-            return Err(MlsError::DuplicateLeafData(**entry.get()));
+            return Err(MlsError::DuplicateLeafData(
+                **entry.get(),
+                LeafDataKind::Identity,
+            ));
         }
 
         let in_use_cred_type_unsupported_by_new_leaf = self
@@ -230,9 +534,56 @@ impl TreeIndex {
         credential_entry.or_insert(index);
         hpke_entry.or_insert(index);
 
+        if let (Some(ids), Some(id)) = (&mut self.application_ids.0, leaf_application_id(leaf_node))
+        {
+            ids.entry(Identifier(id)).or_default().push(index);
+        }
+
         Ok(())
     }
 
+    fn has_application_ids(&self) -> bool {
+        self.application_ids.0.is_some()
+    }
+
+    fn index_application_ids<'a>(
+        &mut self,
+        leaves: impl Iterator<Item = (LeafIndex, &'a LeafNode)>,
+    ) {
+        let mut ids = ApplicationIdMap::<Identifier, Vec<LeafIndex>>::new();
+
+        for (index, leaf) in leaves {
+            if let Some(id) = leaf_application_id(leaf) {
+                ids.entry(Identifier(id)).or_default().push(index);
+            }
+        }
+
+        self.application_ids = ApplicationIdIndex(Some(ids));
+    }
+
+    pub(crate) fn get_leaf_indexes_with_application_id(
+        &self,
+        application_id: &[u8],
+    ) -> Vec<LeafIndex> {
+        let mut leaves = self
+            .application_ids
+            .0
+            .iter()
+            .filter_map(|ids| ids.get(&Identifier(application_id.to_vec())))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+
+        leaves.sort_unstable();
+        leaves
+    }
+
+    pub(crate) fn duplicate_application_ids(&self) -> Vec<(Vec<u8>, Vec<LeafIndex>)> {
+        let ids = self.application_ids.0.iter().flatten();
+
+        sorted_duplicates(ids.map(|(id, leaves)| (id.0.clone(), leaves.clone())))
+    }
+
     pub(crate) fn get_leaf_index_with_identity(&self, identity: &[u8]) -> Option<LeafIndex> {
         self.identities.get(&Identifier(identity.to_vec())).copied()
     }
@@ -243,11 +594,26 @@ impl TreeIndex {
             .remove(&Identifier(identity.to_vec()))
             .is_some();
 
-        self.credential_signature_key
+        let index = self
+            .credential_signature_key
             .remove(&leaf_node.signing_identity.signature_key);
 
         self.hpke_key.remove(&leaf_node.public_key);
 
+        if let (Some(ids), Some(index), Some(id)) = (
+            &mut self.application_ids.0,
+            index,
+            leaf_application_id(leaf_node),
+        ) {
+            if let Entry::Occupied(mut entry) = ids.entry(Identifier(id)) {
+                entry.get_mut().retain(|i| i != &index);
+
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+
         if !existed {
             return;
         }
@@ -306,6 +672,22 @@ impl TreeIndex {
     }
 }
 
+fn sorted_duplicates(
+    leaves: impl IntoIterator<Item = (Vec<u8>, Vec<LeafIndex>)>,
+) -> Vec<(Vec<u8>, Vec<LeafIndex>)> {
+    let mut duplicates = leaves
+        .into_iter()
+        .filter(|(_, leaves)| leaves.len() > 1)
+        .map(|(id, mut leaves)| {
+            leaves.sort_unstable();
+            (id, leaves)
+        })
+        .collect::<Vec<_>>();
+
+    duplicates.sort_unstable();
+    duplicates
+}
+
 #[cfg(feature = "tree_index")]
 #[derive(Clone, Debug, Default, PartialEq, MlsEncode, MlsDecode, MlsSize)]
 struct TypeCounter {
@@ -398,7 +780,7 @@ mod tests {
             get_test_client_identity(&new_key_package),
         );
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(index))
+        assert_matches!(res, Err(MlsError::DuplicateLeafData(index, LeafDataKind::SignatureKey))
                         if index == *test_data[1].index);
 
         assert_eq!(before_error, test_index);
@@ -419,7 +801,26 @@ mod tests {
             get_test_client_identity(&new_leaf_node),
         );
 
-        assert_matches!(res, Err(MlsError::DuplicateLeafData(index))
+        assert_matches!(res, Err(MlsError::DuplicateLeafData(index, LeafDataKind::EncryptionKey))
+                        if index == *test_data[1].index);
+
+        assert_eq!(before_error, test_index);
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn test_insert_duplicate_identity() {
+        let (test_data, mut test_index) = test_setup().await;
+        let before_error = test_index.clone();
+
+        let new_leaf_node = get_basic_test_node(TEST_CIPHER_SUITE, "foo").await;
+
+        let res = test_index.insert(
+            test_data[1].index,
+            &new_leaf_node,
+            get_test_client_identity(&test_data[1].leaf_node),
+        );
+
+        assert_matches!(res, Err(MlsError::DuplicateLeafData(index, LeafDataKind::Identity))
                         if index == *test_data[1].index);
 
         assert_eq!(before_error, test_index);
@@ -502,4 +903,70 @@ mod tests {
         assert_eq!(test_index.count_supporting_proposal(test_proposal_id), 1);
         assert_eq!(test_index.count_supporting_proposal(other_proposal_id), 0);
     }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_ids_are_indexed() {
+        let mut test_index = TreeIndex::new();
+        let mut test_data = Vec::new();
+
+        for (i, id) in [b"a", b"b", b"a"].into_iter().enumerate() {
+            let mut data = get_test_data(LeafIndex(i as u32)).await;
+
+            data.leaf_node
+                .extensions
+                .set_from(ApplicationIdExt::new(id.to_vec()))
+                .unwrap();
+
+            test_index
+                .insert(data.index, &data.leaf_node, vec![i as u8])
+                .unwrap();
+
+            test_data.push(data);
+        }
+
+        assert_eq!(
+            test_index.get_leaf_indexes_with_application_id(b"b"),
+            vec![LeafIndex(1)]
+        );
+
+        assert_eq!(
+            test_index.duplicate_application_ids(),
+            vec![(b"a".to_vec(), vec![LeafIndex(0), LeafIndex(2)])]
+        );
+
+        test_index.remove(&test_data[0].leaf_node, &[0]);
+
+        assert_eq!(
+            test_index.get_leaf_indexes_with_application_id(b"a"),
+            vec![LeafIndex(2)]
+        );
+
+        assert!(test_index.duplicate_application_ids().is_empty());
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn application_ids_are_not_encoded() {
+        let mut test_data = get_test_data(LeafIndex(0)).await;
+
+        test_data
+            .leaf_node
+            .extensions
+            .set_from(ApplicationIdExt::new(b"a".to_vec()))
+            .unwrap();
+
+        let mut test_index = TreeIndex::new();
+
+        test_index
+            .insert(test_data.index, &test_data.leaf_node, vec![0])
+            .unwrap();
+
+        let mut decoded =
+            TreeIndex::mls_decode(&mut &*test_index.mls_encode_to_vec().unwrap()).unwrap();
+
+        assert!(!decoded.has_application_ids());
+
+        decoded.index_application_ids([(test_data.index, &test_data.leaf_node)].into_iter());
+
+        assert_eq!(decoded, test_index);
+    }
 }
@@ -20,16 +20,16 @@ pub(crate) fn build_tree(
 
     // Leaf Node
     if nodes.is_leaf(idx) {
-        let leaf_tag = format!("{blank_tag}Leaf ({idx})");
+        let leaf_tag = format!("{blank_tag}Leaf ({})", idx.value());
         tree.add_leaf(&leaf_tag);
         return Ok(());
     }
 
     // Parent Leaf
-    let mut parent_tag = format!("{blank_tag}Parent ({idx})");
+    let mut parent_tag = format!("{blank_tag}Parent ({})", idx.value());
 
-    if nodes.total_leaf_count().root() == idx {
-        parent_tag = format!("{blank_tag}Root ({idx})");
+    if nodes.root() == idx {
+        parent_tag = format!("{blank_tag}Root ({})", idx.value());
     }
 
     // Add unmerged leaves indexes
@@ -63,9 +63,8 @@ pub(crate) fn build_tree(
 }
 
 pub(crate) fn build_ascii_tree(nodes: &NodeVec) -> String {
-    let leaves_count: u32 = nodes.total_leaf_count();
     let mut tree = TreeBuilder::new();
-    build_tree(tree.borrow_mut(), nodes, leaves_count.root()).unwrap();
+    build_tree(tree.borrow_mut(), nodes, nodes.root()).unwrap();
     tree.string()
 }
 
@@ -7,22 +7,38 @@ use std::collections::HashSet;
 
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
-use tree_math::TreeIndex;
 
-use super::node::{Node, NodeIndex};
+use super::node::Node;
 use crate::client::MlsError;
 use crate::crypto::CipherSuiteProvider;
-use crate::group::GroupContext;
-use crate::iter::wrap_impl_iter;
-use crate::tree_kem::math as tree_math;
+use crate::group::{mls_rules::TreeTruncation, GroupContext};
+use crate::iter::try_map_bounded;
 use crate::tree_kem::{leaf_node_validator::LeafNodeValidator, TreeKemPublic};
 use mls_rs_core::identity::IdentityProvider;
 
-#[cfg(all(not(mls_build_async), feature = "rayon"))]
-use rayon::prelude::*;
-
-#[cfg(mls_build_async)]
-use futures::{StreamExt, TryStreamExt};
+/// How thoroughly the ratchet tree is validated when joining a group with a
+/// Welcome message.
+///
+/// Validating every leaf node, including its signature, dominates the cost
+/// of joining large groups. Lower levels skip parts of the validation
+/// required by RFC 9420 and must only be used if the Welcome message and the
+/// ratchet tree are received from infrastructure trusted to have validated
+/// the tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TreeValidationLevel {
+    /// Validate the tree hash, the parent hashes, the structure of the tree
+    /// and every leaf node.
+    #[default]
+    Full,
+    /// Validate the tree hash, the parent hashes and the structure of the
+    /// tree but not the leaf nodes.
+    ParentHashes,
+    /// Validate the tree hash and the structure of the tree. The tree hash is
+    /// needed to maintain the tree in any case, and checking the structure
+    /// requires no cryptographic operation.
+    TreeHashOnly,
+}
 
 pub(crate) struct TreeValidator<'a, C, CSP>
 where
@@ -33,6 +49,9 @@ where
     leaf_node_validator: LeafNodeValidator<'a, C, CSP>,
     group_id: &'a [u8],
     cipher_suite_provider: &'a CSP,
+    truncation: TreeTruncation,
+    level: TreeValidationLevel,
+    max_parallelism: Option<usize>,
 }
 
 impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP> {
@@ -50,28 +69,58 @@ impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP
             ),
             group_id: &context.group_id,
             cipher_suite_provider,
+            truncation: TreeTruncation::Trailing,
+            level: TreeValidationLevel::Full,
+            max_parallelism: None,
+        }
+    }
+
+    /// Validate only what `level` requires.
+    pub fn with_level(self, level: TreeValidationLevel) -> Self {
+        Self { level, ..self }
+    }
+
+    /// Accept trailing blank leaves allowed by `truncation`.
+    pub fn with_truncation(self, truncation: TreeTruncation) -> Self {
+        Self { truncation, ..self }
+    }
+
+    /// Validate at most `max_parallelism` leaf nodes or parent hash chains at
+    /// the same time.
+    pub fn with_max_parallelism(self, max_parallelism: Option<usize>) -> Self {
+        Self {
+            max_parallelism,
+            ..self
         }
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     pub async fn validate(&self, tree: &mut TreeKemPublic) -> Result<(), MlsError> {
         self.validate_tree_hash(tree).await?;
+        self.validate_no_trailing_blanks(tree)?;
+        validate_unmerged(tree)?;
 
-        tree.validate_parent_hashes(self.cipher_suite_provider)
-            .await?;
+        if self.level != TreeValidationLevel::TreeHashOnly {
+            tree.validate_parent_hashes(self.cipher_suite_provider, self.max_parallelism)
+                .await?;
+        }
 
-        self.validate_no_trailing_blanks(tree)?;
-        self.validate_leaves(tree).await?;
-        validate_unmerged(tree)
+        if self.level == TreeValidationLevel::Full {
+            self.validate_leaves(tree).await?;
+        }
+
+        Ok(())
     }
 
     fn validate_no_trailing_blanks(&self, tree: &TreeKemPublic) -> Result<(), MlsError> {
-        tree.nodes
-            .last()
-            .ok_or(MlsError::UnexpectedEmptyTree)?
-            .is_some()
-            .then_some(())
-            .ok_or(MlsError::UnexpectedTrailingBlanks)
+        let last = tree.nodes.last().ok_or(MlsError::UnexpectedEmptyTree)?;
+
+        (last.is_some()
+            || self
+                .truncation
+                .allows_trailing_blanks(tree.nodes.leaf_count()))
+        .then_some(())
+        .ok_or(MlsError::UnexpectedTrailingBlanks)
     }
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
@@ -88,18 +137,20 @@ impl<'a, C: IdentityProvider, CSP: CipherSuiteProvider> TreeValidator<'a, C, CSP
 
     #[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
     async fn validate_leaves(&self, tree: &TreeKemPublic) -> Result<(), MlsError> {
-        let leaves = wrap_impl_iter(tree.nodes.non_empty_leaves());
-
-        #[cfg(mls_build_async)]
-        let leaves = leaves.map(Ok);
+        let leaves = tree.nodes.non_empty_leaves().collect::<Vec<_>>();
 
-        { leaves }
-            .try_for_each(|(index, leaf_node)| async move {
+        try_map_bounded(
+            &leaves,
+            self.max_parallelism,
+            |(index, leaf_node)| async move {
                 self.leaf_node_validator
-                    .revalidate(leaf_node, self.group_id, *index)
+                    .revalidate(leaf_node, self.group_id, **index)
                     .await
-            })
-            .await
+            },
+        )
+        .await?;
+
+        Ok(())
     }
 }
 
@@ -125,12 +176,10 @@ fn validate_unmerged(tree: &TreeKemPublic) -> Result<(), MlsError> {
     // For each leaf L, we search for the longest prefix P[1], P[2], ..., P[k] of the direct path of L
     // such that for each i=1..k, either L is in the unmerged leaves of P[i], or P[i] is blank. We will
     // then check that L is unmerged at each P[1], ..., P[k] and no other node.
-    let leaf_count = tree.total_leaf_count();
-
     for (index, _) in tree.nodes.non_empty_leaves() {
-        let mut n = NodeIndex::from(index);
+        let mut n = index.node_index();
 
-        while let Some(ps) = n.parent_sibling(&leaf_count) {
+        while let Some(ps) = tree.nodes.parent_sibling(n) {
             if tree.nodes.is_blank(ps.parent)? {
                 n = ps.parent;
                 continue;
@@ -139,7 +188,7 @@ fn validate_unmerged(tree: &TreeKemPublic) -> Result<(), MlsError> {
             let parent_node = tree.nodes.borrow_as_parent(ps.parent)?;
 
             if parent_node.unmerged_leaves.contains(&index) {
-                unmerged_sets[ps.parent as usize].retain(|i| i != &index);
+                unmerged_sets[ps.parent.as_usize()].retain(|i| i != &index);
 
                 n = ps.parent;
             } else {
@@ -163,7 +212,7 @@ mod tests {
     use super::*;
     use crate::{
         cipher_suite::CipherSuite,
-        client::test_utils::TEST_CIPHER_SUITE,
+        client::{test_utils::TEST_CIPHER_SUITE, LeafNodeSourceKind},
         crypto::test_utils::test_cipher_suite_provider,
         crypto::test_utils::TestCryptoProvider,
         group::test_utils::{get_test_group_context, random_bytes},
@@ -171,7 +220,7 @@ mod tests {
         tree_kem::{
             kem::TreeKem,
             leaf_node::test_utils::{default_properties, get_basic_test_node},
-            node::{LeafIndex, Node, Parent},
+            node::{LeafIndex, Node, NodeIndex, Parent},
             parent_hash::{test_utils::get_test_tree_fig_12, ParentHash},
             test_utils::get_test_tree,
         },
@@ -247,6 +296,39 @@ mod tests {
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn bounded_parallelism_validates_the_same_tree() {
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+
+        for max_parallelism in [Some(1), Some(2), Some(64)] {
+            let mut test_tree = get_valid_tree(TEST_CIPHER_SUITE).await;
+
+            let mut context = get_test_group_context(1, TEST_CIPHER_SUITE).await;
+            context.tree_hash = test_tree.tree_hash(&cipher_suite_provider).await.unwrap();
+
+            let validator =
+                TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider)
+                    .with_max_parallelism(max_parallelism);
+
+            validator.validate(&mut test_tree).await.unwrap();
+
+            test_tree
+                .nodes
+                .borrow_as_leaf_mut(LeafIndex(1))
+                .unwrap()
+                .signature = random_bytes(32);
+
+            context.tree_hash = test_tree.tree_hash(&cipher_suite_provider).await.unwrap();
+
+            let validator =
+                TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider)
+                    .with_max_parallelism(max_parallelism);
+
+            let res = validator.validate(&mut test_tree).await;
+            assert_matches!(res, Err(MlsError::InvalidSignature));
+        }
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn test_tree_hash_mismatch() {
         for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
@@ -269,7 +351,10 @@ mod tests {
         for cipher_suite in TestCryptoProvider::all_supported_cipher_suites() {
             let mut test_tree = get_valid_tree(cipher_suite).await;
 
-            let parent_node = test_tree.nodes.borrow_as_parent_mut(1).unwrap();
+            let parent_node = test_tree
+                .nodes
+                .borrow_as_parent_mut(NodeIndex::new(1))
+                .unwrap();
             parent_node.parent_hash = ParentHash::from(random_bytes(32));
 
             let cipher_suite_provider = test_cipher_suite_provider(cipher_suite);
@@ -305,10 +390,81 @@ mod tests {
 
             let res = validator.validate(&mut test_tree).await;
 
-            assert_matches!(res, Err(MlsError::InvalidSignature));
+            assert_matches!(
+                res,
+                Err(MlsError::LeafNodeBindingMismatch(
+                    0,
+                    LeafNodeSourceKind::Commit
+                ))
+            );
         }
     }
 
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn lower_validation_levels_skip_checks() {
+        let mut test_tree = get_valid_tree(TEST_CIPHER_SUITE).await;
+
+        test_tree
+            .nodes
+            .borrow_as_leaf_mut(LeafIndex(0))
+            .unwrap()
+            .signature = random_bytes(32);
+
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut context = get_test_group_context(1, TEST_CIPHER_SUITE).await;
+        context.tree_hash = test_tree.tree_hash(&cipher_suite_provider).await.unwrap();
+
+        TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider)
+            .with_level(TreeValidationLevel::ParentHashes)
+            .validate(&mut test_tree)
+            .await
+            .unwrap();
+
+        test_tree
+            .nodes
+            .borrow_as_parent_mut(NodeIndex::new(1))
+            .unwrap()
+            .parent_hash = ParentHash::from(random_bytes(32));
+
+        test_tree.tree_hashes = Default::default();
+        context.tree_hash = test_tree.tree_hash(&cipher_suite_provider).await.unwrap();
+
+        let validator =
+            TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider);
+
+        let res = validator
+            .with_level(TreeValidationLevel::ParentHashes)
+            .validate(&mut test_tree)
+            .await;
+
+        assert_matches!(res, Err(MlsError::ParentHashMismatch));
+
+        TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider)
+            .with_level(TreeValidationLevel::TreeHashOnly)
+            .validate(&mut test_tree)
+            .await
+            .unwrap();
+    }
+
+    #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
+    async fn lowest_validation_level_checks_structure() {
+        let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
+
+        // Blank leaf D unmerged at nodes 3, 7
+        tree.nodes[6] = None;
+
+        let cipher_suite_provider = test_cipher_suite_provider(TEST_CIPHER_SUITE);
+        let mut context = get_test_group_context(1, TEST_CIPHER_SUITE).await;
+        context.tree_hash = tree.tree_hash(&cipher_suite_provider).await.unwrap();
+
+        let res = TreeValidator::new(&cipher_suite_provider, &context, &BasicIdentityProvider)
+            .with_level(TreeValidationLevel::TreeHashOnly)
+            .validate(&mut tree)
+            .await;
+
+        assert_matches!(res, Err(MlsError::UnmergedLeavesMismatch));
+    }
+
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
     async fn verify_unmerged_with_correct_tree() {
         let tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
@@ -333,7 +489,10 @@ mod tests {
         let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
 
         // Make D with direct path [3, 7] unmerged at 7 but not 3
-        tree.nodes.borrow_as_parent_mut(3).unwrap().unmerged_leaves = vec![];
+        tree.nodes
+            .borrow_as_parent_mut(NodeIndex::new(3))
+            .unwrap()
+            .unmerged_leaves = vec![];
 
         assert_matches!(
             validate_unmerged(&tree),
@@ -346,7 +505,10 @@ mod tests {
         let mut tree = get_test_tree_fig_12(TEST_CIPHER_SUITE).await;
 
         // Add leaf E from the right subtree of the root to unmerged leaves of node 1 on the left
-        tree.nodes.borrow_as_parent_mut(1).unwrap().unmerged_leaves = vec![LeafIndex(4)];
+        tree.nodes
+            .borrow_as_parent_mut(NodeIndex::new(1))
+            .unwrap()
+            .unmerged_leaves = vec![LeafIndex(4)];
 
         assert_matches!(
             validate_unmerged(&tree),
@@ -126,7 +126,7 @@ mod tests {
     use crate::tree_kem::validate_update_path;
 
     use super::{UpdatePath, UpdatePathNode};
-    use crate::{cipher_suite::CipherSuite, tree_kem::MlsError};
+    use crate::{cipher_suite::CipherSuite, client::LeafNodeSourceKind, tree_kem::MlsError};
 
     use alloc::vec::Vec;
 
@@ -183,6 +183,10 @@ mod tests {
             external_init_index: None,
             #[cfg(feature = "state_update")]
             unused_proposals: vec![],
+            #[cfg(feature = "by_ref_proposal")]
+            stale_proposals: vec![],
+            #[cfg(feature = "by_ref_proposal")]
+            rejected_proposals: vec![],
         }
     }
 
@@ -224,7 +228,13 @@ mod tests {
         )
         .await;
 
-        assert_matches!(validated, Err(MlsError::InvalidSignature));
+        assert_matches!(
+            validated,
+            Err(MlsError::LeafNodeBindingMismatch(
+                0,
+                LeafNodeSourceKind::Commit
+            ))
+        );
     }
 
     #[maybe_async::test(not(mls_build_async), async(mls_build_async, crate::futures_test))]
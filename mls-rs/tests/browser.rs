@@ -0,0 +1,118 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+// Runs with `wasm-pack test --headless --chrome --test browser` and an
+// async build, as required by the WebCrypto provider.
+#![cfg(all(target_arch = "wasm32", mls_build_async))]
+
+use assert_matches::assert_matches;
+use mls_rs::{
+    client_builder::MlsConfig,
+    group::ReceivedMessage,
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    time::MlsTime,
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider, ExtensionList,
+};
+use mls_rs_crypto_webcrypto::WebCryptoProvider;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+const CIPHER_SUITE: CipherSuite = CipherSuite::P256_AES128;
+
+async fn make_client(name: &str) -> Client<impl MlsConfig> {
+    let crypto_provider = WebCryptoProvider::new();
+
+    let (secret, public) = crypto_provider
+        .cipher_suite_provider(CIPHER_SUITE)
+        .unwrap()
+        .signature_key_generate()
+        .await
+        .unwrap();
+
+    let credential = BasicCredential::new(name.as_bytes().to_vec()).into_credential();
+
+    Client::builder()
+        .identity_provider(BasicIdentityProvider)
+        .crypto_provider(crypto_provider)
+        .signing_identity(
+            SigningIdentity::new(credential, public),
+            secret,
+            CIPHER_SUITE,
+        )
+        .try_build()
+        .await
+        .unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn browser_clock_is_used_for_lifetimes() {
+    let now = MlsTime::now().seconds_since_epoch();
+
+    // Any date after this test was written
+    assert!(now > 1_700_000_000);
+
+    let key_package = make_client("alice")
+        .await
+        .generate_key_package_message()
+        .await
+        .unwrap()
+        .into_key_package()
+        .unwrap();
+
+    assert!(key_package.expiration().unwrap() > now);
+}
+
+#[wasm_bindgen_test]
+async fn create_join_and_message_in_browser() {
+    let alice = make_client("alice").await;
+    let bob = make_client("bob").await;
+
+    let mut alice_group = alice.create_group(ExtensionList::new()).await.unwrap();
+    let other_group = alice.create_group(ExtensionList::new()).await.unwrap();
+
+    // Group ids are random
+    assert_ne!(alice_group.group_id(), other_group.group_id());
+
+    let key_package = bob.generate_key_package_message().await.unwrap();
+
+    let commit = alice_group
+        .commit_builder()
+        .add_member(key_package)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    alice_group.apply_pending_commit().await.unwrap();
+
+    let (mut bob_group, _) = bob
+        .join_group(None, &commit.welcome_messages[0])
+        .await
+        .unwrap();
+
+    let message = alice_group
+        .encrypt_application_message(b"hello", vec![])
+        .await
+        .unwrap();
+
+    let received = bob_group
+        .process_incoming_message_with_time(message, MlsTime::now())
+        .await
+        .unwrap();
+
+    assert_matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello");
+
+    let commit = bob_group.commit(vec![]).await.unwrap().commit_message;
+    bob_group.apply_pending_commit().await.unwrap();
+    alice_group.process_incoming_message(commit).await.unwrap();
+
+    assert_eq!(
+        alice_group.epoch_authenticator().unwrap(),
+        bob_group.epoch_authenticator().unwrap()
+    );
+}
@@ -0,0 +1,235 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Exercise the public API gated behind each cargo feature.
+//!
+//! This test is meant to be run for every feature combination, for example
+//! with `cargo hack test -p mls-rs --test feature_api --feature-powerset`,
+//! so that each API is checked to compile and work with any set of features
+//! it can be combined with, including the `minimal` profile.
+
+use cfg_if::cfg_if;
+use mls_rs::{
+    client_builder::MlsConfig,
+    group::ReceivedMessage,
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider, ExtensionList, Group,
+};
+
+cfg_if! {
+    if #[cfg(target_arch = "wasm32")] {
+        use mls_rs_crypto_webcrypto::WebCryptoProvider as TestCryptoProvider;
+    } else {
+        use mls_rs_crypto_openssl::OpensslCryptoProvider as TestCryptoProvider;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as futures_test;
+
+#[cfg(all(mls_build_async, not(target_arch = "wasm32")))]
+use futures_test::test as futures_test;
+
+const CIPHER_SUITE: CipherSuite = CipherSuite::P256_AES128;
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn make_client(name: &str) -> Client<impl MlsConfig> {
+    let crypto_provider = TestCryptoProvider::default();
+    let cipher_suite = crypto_provider.cipher_suite_provider(CIPHER_SUITE).unwrap();
+    let (secret, public) = cipher_suite.signature_key_generate().await.unwrap();
+
+    let credential = BasicCredential::new(name.as_bytes().to_vec()).into_credential();
+    let signing_identity = SigningIdentity::new(credential, public);
+
+    let builder = Client::builder()
+        .identity_provider(BasicIdentityProvider)
+        .crypto_provider(crypto_provider)
+        .signing_identity(signing_identity, secret, CIPHER_SUITE);
+
+    #[cfg(feature = "custom_proposal")]
+    let builder = builder.custom_proposal_type(CUSTOM_PROPOSAL);
+
+    builder.try_build().await.unwrap()
+}
+
+#[cfg_attr(not(mls_build_async), maybe_async::must_be_sync)]
+async fn two_member_groups() -> (Group<impl MlsConfig>, Group<impl MlsConfig>) {
+    let alice = make_client("alice").await;
+    let bob = make_client("bob").await;
+
+    let mut alice_group = alice.create_group(ExtensionList::new()).await.unwrap();
+    let key_package = bob.generate_key_package_message().await.unwrap();
+
+    let commit = alice_group
+        .commit_builder()
+        .add_member(key_package)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    alice_group.apply_pending_commit().await.unwrap();
+
+    let (bob_group, _) = bob
+        .join_group(None, &commit.welcome_messages[0])
+        .await
+        .unwrap();
+
+    (alice_group, bob_group)
+}
+
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn core_api() {
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let commit = alice.commit(Vec::new()).await.unwrap();
+    alice.apply_pending_commit().await.unwrap();
+
+    let received = bob
+        .process_incoming_message(commit.commit_message)
+        .await
+        .unwrap();
+
+    assert!(matches!(received, ReceivedMessage::Commit(_)));
+    assert_eq!(alice.current_epoch(), bob.current_epoch());
+
+    let alice_secret = alice.export_secret(b"label", b"", 16).await.unwrap();
+    let bob_secret = bob.export_secret(b"label", b"", 16).await.unwrap();
+    assert_eq!(alice_secret, bob_secret);
+}
+
+#[cfg(feature = "private_message")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn private_message_api() {
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let message = alice
+        .encrypt_application_message(b"hello", Vec::new())
+        .await
+        .unwrap();
+
+    let received = bob.process_incoming_message(message).await.unwrap();
+
+    assert!(matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello"));
+}
+
+#[cfg(feature = "by_ref_proposal")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn by_ref_proposal_api() {
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let proposal = bob.propose_update(Vec::new()).await.unwrap();
+    let received = alice.process_incoming_message(proposal).await.unwrap();
+    assert!(matches!(received, ReceivedMessage::Proposal(_)));
+
+    let commit = alice.commit(Vec::new()).await.unwrap();
+    alice.apply_pending_commit().await.unwrap();
+    bob.process_incoming_message(commit.commit_message)
+        .await
+        .unwrap();
+
+    assert_eq!(alice.current_epoch(), bob.current_epoch());
+}
+
+#[cfg(feature = "state_update")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn state_update_api() {
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let commit = alice
+        .commit_builder()
+        .remove_member(1)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let received = bob
+        .process_incoming_message(commit.commit_message)
+        .await
+        .unwrap();
+
+    let ReceivedMessage::Commit(description) = received else {
+        panic!("expected a commit");
+    };
+
+    assert!(!description.state_update.is_active());
+    assert_eq!(description.state_update.roster_update().removed().len(), 1);
+}
+
+#[cfg(all(feature = "prior_epoch", feature = "private_message"))]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn prior_epoch_api() {
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let message = bob
+        .encrypt_application_message(b"late", Vec::new())
+        .await
+        .unwrap();
+
+    let commit = alice.commit(Vec::new()).await.unwrap();
+    alice.apply_pending_commit().await.unwrap();
+
+    bob.process_incoming_message(commit.commit_message)
+        .await
+        .unwrap();
+
+    let received = alice.process_incoming_message(message).await.unwrap();
+    assert!(matches!(received, ReceivedMessage::ApplicationMessage(_)));
+}
+
+#[cfg(feature = "custom_proposal")]
+const CUSTOM_PROPOSAL: mls_rs::group::proposal::ProposalType =
+    mls_rs::group::proposal::ProposalType::new(0xf000);
+
+#[cfg(feature = "custom_proposal")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn custom_proposal_api() {
+    use mls_rs::group::proposal::CustomProposal;
+
+    let (mut alice, mut bob) = two_member_groups().await;
+
+    let commit = alice
+        .commit_builder()
+        .custom_proposal(CustomProposal::new(CUSTOM_PROPOSAL, vec![1, 2, 3]))
+        .build()
+        .await
+        .unwrap();
+
+    alice.apply_pending_commit().await.unwrap();
+
+    bob.process_incoming_message(commit.commit_message)
+        .await
+        .unwrap();
+
+    assert_eq!(alice.current_epoch(), bob.current_epoch());
+}
+
+#[cfg(feature = "external_client")]
+#[maybe_async::test(not(mls_build_async), async(mls_build_async, futures_test))]
+async fn external_client_api() {
+    use mls_rs::external_client::ExternalClient;
+
+    let (alice, _) = two_member_groups().await;
+
+    let server = ExternalClient::builder()
+        .identity_provider(BasicIdentityProvider)
+        .crypto_provider(TestCryptoProvider::default())
+        .build();
+
+    let group_info = alice
+        .group_info_message_allowing_ext_commit(true)
+        .await
+        .unwrap();
+
+    let observed = server.observe_group(group_info, None).await.unwrap();
+
+    assert_eq!(observed.group_context().epoch(), alice.current_epoch());
+}
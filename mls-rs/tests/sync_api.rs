@@ -0,0 +1,121 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// Copyright by contributors to this project.
+// SPDX-License-Identifier: (Apache-2.0 OR MIT)
+
+//! Use the synchronous API, which is built unless `--cfg mls_build_async` is
+//! set, from plain functions without an async runtime.
+//!
+//! The storage used by the clients implements the traits of `mls_rs_core`
+//! with ordinary functions, as applications do in synchronous builds.
+
+#![cfg(all(
+    not(mls_build_async),
+    not(target_arch = "wasm32"),
+    feature = "private_message"
+))]
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+use mls_rs::{
+    client_builder::MlsConfig,
+    group::ReceivedMessage,
+    identity::{
+        basic::{BasicCredential, BasicIdentityProvider},
+        SigningIdentity,
+    },
+    CipherSuite, CipherSuiteProvider, Client, CryptoProvider, ExtensionList,
+};
+use mls_rs_core::group::{EpochRecord, GroupState, GroupStateStorage};
+use mls_rs_crypto_openssl::OpensslCryptoProvider;
+
+const CIPHER_SUITE: CipherSuite = CipherSuite::CURVE25519_AES128;
+
+/// Storage keeping the latest state of each group, without prior epochs.
+#[derive(Clone, Default)]
+struct LatestStateStorage {
+    states: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl GroupStateStorage for LatestStateStorage {
+    type Error = Infallible;
+
+    fn state(&self, group_id: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.states.lock().unwrap().get(group_id).cloned())
+    }
+
+    fn epoch(&self, _group_id: &[u8], _epoch_id: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(None)
+    }
+
+    fn write(
+        &mut self,
+        state: GroupState,
+        _epoch_inserts: Vec<EpochRecord>,
+        _epoch_updates: Vec<EpochRecord>,
+    ) -> Result<(), Self::Error> {
+        self.states.lock().unwrap().insert(state.id, state.data);
+        Ok(())
+    }
+
+    fn max_epoch_id(&self, _group_id: &[u8]) -> Result<Option<u64>, Self::Error> {
+        Ok(None)
+    }
+}
+
+fn make_client(name: &str, storage: LatestStateStorage) -> Client<impl MlsConfig> {
+    let crypto_provider = OpensslCryptoProvider::default();
+    let cipher_suite = crypto_provider.cipher_suite_provider(CIPHER_SUITE).unwrap();
+    let (secret, public) = cipher_suite.signature_key_generate().unwrap();
+
+    let credential = BasicCredential::new(name.as_bytes().to_vec()).into_credential();
+
+    Client::builder()
+        .identity_provider(BasicIdentityProvider)
+        .crypto_provider(crypto_provider)
+        .group_state_storage(storage)
+        .signing_identity(
+            SigningIdentity::new(credential, public),
+            secret,
+            CIPHER_SUITE,
+        )
+        .try_build()
+        .unwrap()
+}
+
+#[test]
+fn groups_are_used_without_async_runtime() {
+    let storage = LatestStateStorage::default();
+    let alice = make_client("alice", storage.clone());
+    let bob = make_client("bob", LatestStateStorage::default());
+
+    let mut alice_group = alice.create_group(ExtensionList::default()).unwrap();
+
+    let commit = alice_group
+        .commit_builder()
+        .add_member(bob.generate_key_package_message().unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+
+    alice_group.apply_pending_commit().unwrap();
+    alice_group.write_to_storage().unwrap();
+
+    let (mut bob_group, _) = bob.join_group(None, &commit.welcome_messages[0]).unwrap();
+
+    let message = bob_group
+        .encrypt_application_message(b"hello", vec![])
+        .unwrap();
+
+    // The group written to the synchronous storage is loaded back
+    let group_id = alice_group.group_id().to_vec();
+    let mut alice_group = alice.load_group(&group_id).unwrap();
+
+    let received = alice_group.process_incoming_message(message).unwrap();
+
+    assert!(matches!(received, ReceivedMessage::ApplicationMessage(m) if m.data() == b"hello"));
+    assert!(storage.states.lock().unwrap().contains_key(&group_id));
+}